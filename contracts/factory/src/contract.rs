@@ -5,25 +5,28 @@ use cosmwasm_std::{
 
 use crate::error::ContractError;
 use crate::migration;
-use crate::querier::query_pair_info;
+use crate::querier::{query_multi_asset_pair_info, query_pair_info};
 
 use crate::state::{
-    pair_key, read_pairs, Config, TmpPairInfo, CONFIG, OWNERSHIP_PROPOSAL, PAIRS, PAIR_CONFIGS,
+    pair_key, read_multi_asset_pairs, read_pairs, Config, PairFees, TmpPairInfo, CONFIG,
+    MULTI_ASSET_PAIRS, OWNERSHIP_PROPOSAL, PAIRS, PAIR_CONFIGS, PAIR_FEES, PAIR_TYPES,
     TMP_PAIR_INFO,
 };
 
 use crate::response::MsgInstantiateContractResponse;
 
-use astroport::asset::{addr_validate_to_lower, AssetInfo, PairInfo};
+use astroport::asset::{addr_validate_to_lower, AssetInfo, MultiAssetPairInfo, PairInfo};
 use astroport::factory::{
-    ConfigResponse, ExecuteMsg, FeeInfoResponse, InstantiateMsg, MigrateMsg, PairConfig, PairType,
-    PairsResponse, QueryMsg,
+    ConfigResponse, ExecuteMsg, FeeInfoResponse, InstantiateMsg, MigrateMsg,
+    MultiAssetPairsResponse, PairConfig, PairType, PairsResponse, QueryMsg,
 };
 
-use crate::migration::migrate_pair_configs_to_v120;
+use crate::migration::{migrate_pair_configs_to_v120, migrate_pair_configs_to_v130};
 use astroport::common::{claim_ownership, drop_ownership_proposal, propose_new_owner};
 use astroport::generator::ExecuteMsg::DeactivatePool;
-use astroport::pair::InstantiateMsg as PairInstantiateMsg;
+use astroport::pair::{
+    InstantiateMsg as PairInstantiateMsg, MultiAssetInstantiateMsg, MAX_MULTI_ASSETS,
+};
 use cw2::{get_contract_version, set_contract_version};
 use protobuf::Message;
 use std::collections::HashSet;
@@ -136,9 +139,24 @@ pub struct UpdateConfig {
 ///             init_params,
 ///         }** Creates a new pair with the specified input parameters.
 ///
+/// * **ExecuteMsg::CreateMultiAssetPair {
+///             pair_type,
+///             asset_infos,
+///             init_params,
+///         }** Creates a new pair that holds more than two assets.
+///
+/// * **ExecuteMsg::UpdatePairFees {
+///             asset_infos,
+///             total_fee_bps,
+///             maker_fee_bps,
+///         }** Sets fees for a specific pair that override the fees of its pair type.
+///
 /// * **ExecuteMsg::Deregister { asset_infos }** Removes an existing pair from the factory.
 /// * The asset information is for the assets that are traded in the pair.
 ///
+/// * **ExecuteMsg::DeregisterMultiAssetPair { asset_infos }** Removes an existing pair that holds
+/// more than two assets from the factory.
+///
 /// * **ExecuteMsg::ProposeNewOwner { owner, expires_in }** Creates a request to change contract ownership.
 ///
 /// * **ExecuteMsg::DropOwnershipProposal {}** Removes a request to change contract ownership.
@@ -174,7 +192,28 @@ pub fn execute(
             asset_infos,
             init_params,
         } => execute_create_pair(deps, env, pair_type, asset_infos, init_params),
+        ExecuteMsg::CreateMultiAssetPair {
+            pair_type,
+            asset_infos,
+            init_params,
+        } => execute_create_multi_asset_pair(deps, env, pair_type, asset_infos, init_params),
+        ExecuteMsg::UpdatePairFees {
+            asset_infos,
+            total_fee_bps,
+            maker_fee_bps,
+        } => execute_update_pair_fees(
+            deps,
+            info,
+            asset_infos,
+            PairFees {
+                total_fee_bps,
+                maker_fee_bps,
+            },
+        ),
         ExecuteMsg::Deregister { asset_infos } => deregister(deps, info, asset_infos),
+        ExecuteMsg::DeregisterMultiAssetPair { asset_infos } => {
+            deregister_multi_asset_pair(deps, info, asset_infos)
+        }
         ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
             let config: Config = CONFIG.load(deps.storage)?;
 
@@ -300,6 +339,63 @@ pub fn execute_update_pair_config(
     Ok(Response::new().add_attribute("action", "update_pair_config"))
 }
 
+/// ## Description
+/// Sets fees for a specific pair that override the fees of its pair type. Fees that are set to [`None`]
+/// fall back to the pair type fees. Returns [`ContractError`] on failure.
+///
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **info** is an object of type [`MessageInfo`]
+///
+/// * **asset_infos** is a vector with items of type [`AssetInfo`]. These are the assets of the pair for which we update fees.
+/// Pairs that hold more than two assets are looked up among the multi-asset pairs.
+///
+/// * **pair_fees** is an object of type [`PairFees`] that contains the fees to set for the pair.
+///
+/// ## Executor
+/// Only the owner can execute this.
+pub fn execute_update_pair_fees(
+    deps: DepsMut,
+    info: MessageInfo,
+    asset_infos: Vec<AssetInfo>,
+    pair_fees: PairFees,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Permission check
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let pairs = if asset_infos.len() > 2 {
+        MULTI_ASSET_PAIRS
+    } else {
+        PAIRS
+    };
+    let pair_addr = pairs.load(deps.storage, &pair_key(&asset_infos))?;
+
+    // Validate the fees the pair ends up with. The maker fee is a share of the total fee,
+    // so keeping it within 10,000 bps keeps the Maker's cut within the total fee
+    let pair_type = PAIR_TYPES.load(deps.storage, &pair_addr)?;
+    let mut pair_config = PAIR_CONFIGS.load(deps.storage, pair_type.to_string())?;
+    pair_fees.apply(&mut pair_config);
+    if !pair_config.valid_fee_bps() {
+        return Err(ContractError::PairInvalidFeeBps {});
+    }
+
+    if pair_fees.total_fee_bps.is_none() && pair_fees.maker_fee_bps.is_none() {
+        PAIR_FEES.remove(deps.storage, &pair_addr);
+    } else {
+        PAIR_FEES.save(deps.storage, &pair_addr, &pair_fees)?;
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_pair_fees"),
+        attr("pair_contract_addr", pair_addr),
+    ]))
+}
+
 /// ## Description
 /// Creates a new pair of `pair_type` with the assets specified in `asset_infos`. Returns a [`ContractError`] on failure or
 /// returns the address of the pair contract if the transaction was successful.
@@ -348,7 +444,14 @@ pub fn execute_create_pair(
     }
 
     let pair_key = pair_key(&asset_infos);
-    TMP_PAIR_INFO.save(deps.storage, &TmpPairInfo { pair_key })?;
+    TMP_PAIR_INFO.save(
+        deps.storage,
+        &TmpPairInfo {
+            pair_key,
+            multi_asset: false,
+            pair_type,
+        },
+    )?;
 
     let sub_msg: Vec<SubMsg> = vec![SubMsg {
         id: INSTANTIATE_PAIR_REPLY_ID,
@@ -377,6 +480,99 @@ pub fn execute_create_pair(
         ]))
 }
 
+/// ## Description
+/// Creates a new pair of `pair_type` that holds all the assets specified in `asset_infos`.
+/// Returns a [`ContractError`] on failure or returns the address of the pair contract if the transaction was successful.
+///
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **pair_type** is an object of type [`PairType`]. This is the pair type of the newly created pair.
+///
+/// * **asset_infos** is a vector with more than two items of type [`AssetInfo`]. These are the assets for which we create a pair.
+///
+/// * **init_params** is an [`Option`] type. These are packed params used for custom pair types that need extra data to be instantiated.
+pub fn execute_create_multi_asset_pair(
+    deps: DepsMut,
+    env: Env,
+    pair_type: PairType,
+    asset_infos: Vec<AssetInfo>,
+    init_params: Option<Binary>,
+) -> Result<Response, ContractError> {
+    if asset_infos.len() <= 2 || asset_infos.len() > MAX_MULTI_ASSETS {
+        return Err(ContractError::InvalidNumberOfAssets {});
+    }
+
+    for asset_info in &asset_infos {
+        asset_info.check(deps.api)?;
+    }
+
+    let unique_assets: HashSet<String> = asset_infos.iter().map(|a| a.to_string()).collect();
+    if unique_assets.len() != asset_infos.len() {
+        return Err(ContractError::DoublingAssets {});
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+
+    let pair_key = pair_key(&asset_infos);
+    if MULTI_ASSET_PAIRS
+        .may_load(deps.storage, &pair_key)?
+        .is_some()
+    {
+        return Err(ContractError::PairWasCreated {});
+    }
+
+    // Get pair type from config
+    let pair_config = PAIR_CONFIGS
+        .load(deps.storage, pair_type.to_string())
+        .map_err(|_| ContractError::PairConfigNotFound {})?;
+
+    // Check if pair config is disabled
+    if pair_config.is_disabled {
+        return Err(ContractError::PairConfigDisabled {});
+    }
+
+    TMP_PAIR_INFO.save(
+        deps.storage,
+        &TmpPairInfo {
+            pair_key,
+            multi_asset: true,
+            pair_type,
+        },
+    )?;
+
+    let pair_name = asset_infos
+        .iter()
+        .map(|a| a.to_string())
+        .collect::<Vec<String>>()
+        .join("-");
+
+    let sub_msg: Vec<SubMsg> = vec![SubMsg {
+        id: INSTANTIATE_PAIR_REPLY_ID,
+        msg: WasmMsg::Instantiate {
+            admin: Some(config.owner.to_string()),
+            code_id: pair_config.code_id,
+            msg: to_binary(&MultiAssetInstantiateMsg {
+                asset_infos,
+                token_code_id: config.token_code_id,
+                factory_addr: env.contract.address.to_string(),
+                init_params,
+            })?,
+            funds: vec![],
+            label: "Astroport pair".to_string(),
+        }
+        .into(),
+        gas_limit: None,
+        reply_on: ReplyOn::Success,
+    }];
+
+    Ok(Response::new()
+        .add_submessages(sub_msg)
+        .add_attributes(vec![attr("action", "create_pair"), attr("pair", pair_name)]))
+}
+
 /// ## Description
 /// The entry point to the contract for processing replies from submessages.
 /// # Params
@@ -388,7 +584,12 @@ pub fn execute_create_pair(
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
     let tmp = TMP_PAIR_INFO.load(deps.storage)?;
-    if PAIRS.may_load(deps.storage, &tmp.pair_key)?.is_some() {
+    let pairs = if tmp.multi_asset {
+        MULTI_ASSET_PAIRS
+    } else {
+        PAIRS
+    };
+    if pairs.may_load(deps.storage, &tmp.pair_key)?.is_some() {
         return Err(ContractError::PairWasRegistered {});
     }
 
@@ -400,7 +601,8 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 
     let pair_contract = addr_validate_to_lower(deps.api, res.get_contract_address())?;
 
-    PAIRS.save(deps.storage, &tmp.pair_key, &pair_contract)?;
+    pairs.save(deps.storage, &tmp.pair_key, &pair_contract)?;
+    PAIR_TYPES.save(deps.storage, &pair_contract, &tmp.pair_type)?;
 
     Ok(Response::new().add_attributes(vec![
         attr("action", "register"),
@@ -437,6 +639,8 @@ pub fn deregister(
 
     let pair_addr: Addr = PAIRS.load(deps.storage, &pair_key(&asset_infos))?;
     PAIRS.remove(deps.storage, &pair_key(&asset_infos));
+    PAIR_TYPES.remove(deps.storage, &pair_addr);
+    PAIR_FEES.remove(deps.storage, &pair_addr);
 
     let mut messages: Vec<CosmosMsg> = vec![];
     if let Some(generator) = config.generator_address {
@@ -458,6 +662,59 @@ pub fn deregister(
     ]))
 }
 
+/// ## Description
+/// Removes an existing pair that holds more than two assets from the factory. Returns an [`ContractError`] on failure
+/// or returns a [`Response`] with the specified attributes if the operation was successful.
+///
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **asset_infos** is a vector with items of type [`AssetInfo`]. These are the asets for which we deregister the pair.
+///
+/// ## Executor
+/// Only the owner can execute this.
+pub fn deregister_multi_asset_pair(
+    deps: DepsMut,
+    info: MessageInfo,
+    asset_infos: Vec<AssetInfo>,
+) -> Result<Response, ContractError> {
+    for asset_info in &asset_infos {
+        asset_info.check(deps.api)?;
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let pair_addr: Addr = MULTI_ASSET_PAIRS.load(deps.storage, &pair_key(&asset_infos))?;
+    MULTI_ASSET_PAIRS.remove(deps.storage, &pair_key(&asset_infos));
+    PAIR_TYPES.remove(deps.storage, &pair_addr);
+    PAIR_FEES.remove(deps.storage, &pair_addr);
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if let Some(generator) = config.generator_address {
+        let pair_info = query_multi_asset_pair_info(deps.as_ref(), &pair_addr)?;
+
+        // sets the allocation point to zero for the lp_token
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: generator.to_string(),
+            msg: to_binary(&DeactivatePool {
+                lp_token: pair_info.liquidity_token.to_string(),
+            })?,
+            funds: vec![],
+        }));
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "deregister"),
+        attr("pair_contract_addr", pair_addr),
+    ]))
+}
+
 /// ## Description
 /// Exposes all the queries available in the contract.
 /// ## Params
@@ -475,7 +732,14 @@ pub fn deregister(
 /// * **QueryMsg::Pairs { start_after, limit }** Returns an array that contains items of type [`PairInfo`].
 /// This returns information about multiple Astroport pairs
 ///
+/// * **QueryMsg::MultiAssetPair { asset_infos }** Returns a [`MultiAssetPairInfo`] object with information about
+/// a specific Astroport pair that holds more than two assets.
+///
+/// * **QueryMsg::MultiAssetPairs { start_after, limit }** Returns an array that contains items of type [`MultiAssetPairInfo`].
+///
 /// * **QueryMsg::FeeInfo { pair_type }** Returns the fee structure (total and maker fees) for a specific pair type.
+///
+/// * **QueryMsg::PairFeeInfo { pair_addr }** Returns the fee structure used by a specific pair, including its fee overrides.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -484,7 +748,14 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Pairs { start_after, limit } => {
             to_binary(&query_pairs(deps, start_after, limit)?)
         }
+        QueryMsg::MultiAssetPair { asset_infos } => {
+            to_binary(&query_multi_asset_pair(deps, asset_infos)?)
+        }
+        QueryMsg::MultiAssetPairs { start_after, limit } => {
+            to_binary(&query_multi_asset_pairs(deps, start_after, limit)?)
+        }
         QueryMsg::FeeInfo { pair_type } => to_binary(&query_fee_info(deps, pair_type)?),
+        QueryMsg::PairFeeInfo { pair_addr } => to_binary(&query_pair_fee_info(deps, pair_addr)?),
     }
 }
 
@@ -546,6 +817,43 @@ pub fn query_pairs(
     Ok(PairsResponse { pairs })
 }
 
+/// ## Description
+/// Returns the data of a pair that holds more than two assets using the assets in `asset_infos` as input.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **asset_infos** is a vector with items of type [`AssetInfo`]. These are the assets traded in the pair.
+pub fn query_multi_asset_pair(
+    deps: Deps,
+    asset_infos: Vec<AssetInfo>,
+) -> StdResult<MultiAssetPairInfo> {
+    let pair_addr = MULTI_ASSET_PAIRS.load(deps.storage, &pair_key(&asset_infos))?;
+    query_multi_asset_pair_info(deps, &pair_addr)
+}
+
+/// ## Description
+/// Returns an array with the data of pairs that hold more than two assets. Querying starts at `start_after`
+/// and returns `limit` pairs.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **start_after** is an [`Option`] field which accepts a vector with items of type [`AssetInfo`].
+/// This is the pair from which we start to query.
+///
+/// * **limit** is a [`Option`] type. Sets the number of pairs to be retrieved.
+pub fn query_multi_asset_pairs(
+    deps: Deps,
+    start_after: Option<Vec<AssetInfo>>,
+    limit: Option<u32>,
+) -> StdResult<MultiAssetPairsResponse> {
+    let pairs = read_multi_asset_pairs(deps, start_after, limit)
+        .iter()
+        .map(|pair_addr| query_multi_asset_pair_info(deps, pair_addr))
+        .collect::<StdResult<Vec<MultiAssetPairInfo>>>()?;
+
+    Ok(MultiAssetPairsResponse { pairs })
+}
+
 /// ## Description
 /// Returns the fee setup for a specific pair type using a [`FeeInfoResponse`] struct.
 /// ## Params
@@ -560,6 +868,35 @@ pub fn query_fee_info(deps: Deps, pair_type: PairType) -> StdResult<FeeInfoRespo
         fee_address: config.fee_address,
         total_fee_bps: pair_config.total_fee_bps,
         maker_fee_bps: pair_config.maker_fee_bps,
+        flash_loan_fee_bps: pair_config.flash_loan_fee_bps,
+    })
+}
+
+/// ## Description
+/// Returns the fee setup used by a specific pair using a [`FeeInfoResponse`] struct.
+/// Fees that are not overridden for the pair are taken from its pair type. Returns an error if the pair
+/// is not registered in the factory.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **pair_addr** is an object of type [`String`]. This is the pair for which we return fee information.
+pub fn query_pair_fee_info(deps: Deps, pair_addr: String) -> StdResult<FeeInfoResponse> {
+    let pair_addr = addr_validate_to_lower(deps.api, &pair_addr)?;
+    let pair_type = PAIR_TYPES
+        .may_load(deps.storage, &pair_addr)?
+        .ok_or_else(|| StdError::generic_err("The pair is not registered in the factory"))?;
+    let config = CONFIG.load(deps.storage)?;
+    let mut pair_config = PAIR_CONFIGS.load(deps.storage, pair_type.to_string())?;
+
+    if let Some(pair_fees) = PAIR_FEES.may_load(deps.storage, &pair_addr)? {
+        pair_fees.apply(&mut pair_config);
+    }
+
+    Ok(FeeInfoResponse {
+        fee_address: config.fee_address,
+        total_fee_bps: pair_config.total_fee_bps,
+        maker_fee_bps: pair_config.maker_fee_bps,
+        flash_loan_fee_bps: pair_config.flash_loan_fee_bps,
     })
 }
 
@@ -572,7 +909,7 @@ pub fn query_fee_info(deps: Deps, pair_type: PairType) -> StdResult<FeeInfoRespo
 ///
 /// * **_msg** is an object of type [`MigrateMsg`].
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+pub fn migrate(mut deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
     let contract_version = get_contract_version(deps.storage)?;
 
     match contract_version.contract.as_ref() {
@@ -595,11 +932,16 @@ pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, Co
                 migrate_pair_configs_to_v120(deps.storage)?
             }
             "1.1.0" => migrate_pair_configs_to_v120(deps.storage)?,
+            "1.2.0" => migrate_pair_configs_to_v130(deps.storage)?,
+            "1.3.0" => {}
             _ => return Err(ContractError::MigrationError {}),
         },
         _ => return Err(ContractError::MigrationError {}),
     }
 
+    // Pairs created before the pair types were stored by the factory
+    migration::migrate_pair_types(deps.branch())?;
+
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     Ok(Response::new()
@@ -1,3 +1,4 @@
+use astroport::pair::MAX_MULTI_ASSETS;
 use cosmwasm_std::StdError;
 use thiserror::Error;
 
@@ -23,6 +24,9 @@ pub enum ContractError {
     #[error("Fee bps in pair config must be smaller than or equal to 10,000")]
     PairConfigInvalidFeeBps {},
 
+    #[error("Pair fee bps must be smaller than or equal to 10,000")]
+    PairInvalidFeeBps {},
+
     #[error("Pair config not found")]
     PairConfigNotFound {},
 
@@ -32,6 +36,12 @@ pub enum ContractError {
     #[error("Doubling assets in asset infos")]
     DoublingAssets {},
 
+    #[error(
+        "A multi-asset pair must hold more than two and at most {} assets",
+        MAX_MULTI_ASSETS
+    )]
+    InvalidNumberOfAssets {},
+
     #[error("Contract can't be migrated!")]
     MigrationError {},
 }
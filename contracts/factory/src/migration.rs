@@ -1,6 +1,7 @@
-use crate::state::PAIR_CONFIGS;
+use crate::querier::query_multi_asset_pair_info;
+use crate::state::{PAIRS, PAIR_CONFIGS, PAIR_TYPES};
 use astroport::factory::{PairConfig, PairType};
-use cosmwasm_std::{Addr, StdError, Storage};
+use cosmwasm_std::{Addr, DepsMut, Order, StdError, StdResult, Storage};
 use cw_storage_plus::{Item, Map};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -58,6 +59,7 @@ pub fn migrate_pair_configs_to_v120(storage: &mut dyn Storage) -> Result<(), Std
             pair_type: pair_configs_v110.pair_type,
             total_fee_bps: pair_configs_v110.total_fee_bps,
             maker_fee_bps: pair_configs_v110.maker_fee_bps,
+            flash_loan_fee_bps: 0,
             is_disabled: pair_configs_v110.is_disabled.unwrap_or(false),
             is_generator_disabled: false,
         };
@@ -66,3 +68,61 @@ pub fn migrate_pair_configs_to_v120(storage: &mut dyn Storage) -> Result<(), Std
 
     Ok(())
 }
+
+/// This structure describes a pair's configuration.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PairConfigV120 {
+    /// Pair contract code ID that's used to create new pairs of this type
+    pub code_id: u64,
+    /// The pair type (e.g XYK, stable)
+    pub pair_type: PairType,
+    /// The total amount of fees charged for the swap
+    pub total_fee_bps: u16,
+    /// The amount of fees that go to the Maker contract
+    pub maker_fee_bps: u16,
+    /// Whether a pair type is disabled or not
+    pub is_disabled: bool,
+    /// Whether pairs of this type can get an ASTRO generator or not
+    pub is_generator_disabled: bool,
+}
+
+pub const PAIR_CONFIGSV120: Map<String, PairConfigV120> = Map::new("pair_configs");
+
+pub fn migrate_pair_configs_to_v130(storage: &mut dyn Storage) -> Result<(), StdError> {
+    let keys = PAIR_CONFIGSV120
+        .keys(storage, None, None, cosmwasm_std::Order::Ascending {})
+        .map(|v| String::from_utf8(v).map_err(StdError::from))
+        .collect::<Result<Vec<String>, StdError>>()?;
+
+    for key in keys {
+        let pair_configs_v120 = PAIR_CONFIGSV120.load(storage, key.clone())?;
+        let pair_config = PairConfig {
+            code_id: pair_configs_v120.code_id,
+            pair_type: pair_configs_v120.pair_type,
+            total_fee_bps: pair_configs_v120.total_fee_bps,
+            maker_fee_bps: pair_configs_v120.maker_fee_bps,
+            flash_loan_fee_bps: 0,
+            is_disabled: pair_configs_v120.is_disabled,
+            is_generator_disabled: pair_configs_v120.is_generator_disabled,
+        };
+        PAIR_CONFIGS.save(storage, key, &pair_config)?;
+    }
+
+    Ok(())
+}
+
+/// Stores the pair type of every registered pair that holds two assets in [`PAIR_TYPES`].
+/// The pair types are queried from the pairs.
+pub fn migrate_pair_types(deps: DepsMut) -> StdResult<()> {
+    let pairs = PAIRS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, pair_addr)| pair_addr))
+        .collect::<StdResult<Vec<Addr>>>()?;
+
+    for pair_addr in pairs {
+        let pair_info = query_multi_asset_pair_info(deps.as_ref(), &pair_addr)?;
+        PAIR_TYPES.save(deps.storage, &pair_addr, &pair_info.pair_type)?;
+    }
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use astroport::asset::PairInfo;
+use astroport::asset::{MultiAssetPairInfo, PairInfo};
 use astroport::pair::QueryMsg;
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
@@ -25,6 +25,7 @@ pub fn mock_dependencies(
 pub struct WasmMockQuerier {
     base: MockQuerier<Empty>,
     astroport_pair_querier: AstroportPairQuerier,
+    multi_asset_pairs: HashMap<String, MultiAssetPairInfo>,
 }
 
 #[derive(Clone, Default)]
@@ -70,6 +71,10 @@ impl WasmMockQuerier {
             QueryRequest::Wasm(WasmQuery::Smart {contract_addr, msg})// => {
                 => match from_binary(&msg).unwrap() {
                     QueryMsg::Pair {} => {
+                        if let Some(pair_info) = self.multi_asset_pairs.get(contract_addr) {
+                            return SystemResult::Ok(to_binary(pair_info).into());
+                        }
+
                        let pair_info: PairInfo =
                         match self.astroport_pair_querier.pairs.get(contract_addr) {
                             Some(v) => v.clone(),
@@ -94,6 +99,7 @@ impl WasmMockQuerier {
         WasmMockQuerier {
             base,
             astroport_pair_querier: AstroportPairQuerier::default(),
+            multi_asset_pairs: HashMap::new(),
         }
     }
 
@@ -101,4 +107,12 @@ impl WasmMockQuerier {
     pub fn with_astroport_pairs(&mut self, pairs: &[(&String, &PairInfo)]) {
         self.astroport_pair_querier = AstroportPairQuerier::new(pairs);
     }
+
+    // Configure the Astroport pairs that hold more than two assets
+    pub fn with_astroport_multi_asset_pairs(&mut self, pairs: &[(&String, &MultiAssetPairInfo)]) {
+        self.multi_asset_pairs = pairs
+            .iter()
+            .map(|(key, pair)| (key.to_string(), (*pair).clone()))
+            .collect();
+    }
 }
@@ -1,4 +1,4 @@
-use astroport::asset::PairInfo;
+use astroport::asset::{MultiAssetPairInfo, PairInfo};
 use astroport::pair::QueryMsg;
 use cosmwasm_std::{to_binary, Addr, Deps, QueryRequest, StdResult, WasmQuery};
 
@@ -12,3 +12,17 @@ pub fn query_pair_info(deps: Deps, pair_contract: &Addr) -> StdResult<PairInfo>
         msg: to_binary(&QueryMsg::Pair {})?,
     }))
 }
+
+/// ## Description
+/// Returns information about a pair that holds any amount of assets (using the [`MultiAssetPairInfo`] struct).
+/// ## Params
+/// `pair_contract` is a param of type [`Addr`]. This is the pair for which to retrieve information.
+pub fn query_multi_asset_pair_info(
+    deps: Deps,
+    pair_contract: &Addr,
+) -> StdResult<MultiAssetPairInfo> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: pair_contract.to_string(),
+        msg: to_binary(&QueryMsg::Pair {})?,
+    }))
+}
@@ -7,7 +7,7 @@ use cosmwasm_std::{Addr, Deps, Order};
 use astroport::asset::AssetInfo;
 
 use astroport::common::OwnershipProposal;
-use astroport::factory::PairConfig;
+use astroport::factory::{PairConfig, PairType};
 
 /// ## Description
 /// This structure holds the main contract parameters.
@@ -30,6 +30,11 @@ pub struct Config {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct TmpPairInfo {
     pub pair_key: Vec<u8>,
+    /// Whether the pair holds more than two assets
+    #[serde(default)]
+    pub multi_asset: bool,
+    /// The pair type of the pair
+    pub pair_type: PairType,
 }
 
 /// Saves a pair's key
@@ -41,20 +46,59 @@ pub const CONFIG: Item<Config> = Item::new("config");
 /// Saves created pairs (from olders to latest)
 pub const PAIRS: Map<&[u8], Addr> = Map::new("pair_info");
 
+/// Saves created pairs that hold more than two assets (from olders to latest)
+pub const MULTI_ASSET_PAIRS: Map<&[u8], Addr> = Map::new("multi_asset_pair_info");
+
+/// Saves the pair type of every registered pair by pair address
+pub const PAIR_TYPES: Map<&Addr, PairType> = Map::new("pair_types");
+
 /// ## Description
 /// Calculates a pair key from the specified parameters in the `asset_infos` variable.
 /// ## Params
-/// `asset_infos` is an array with two items of type [`AssetInfo`].
-pub fn pair_key(asset_infos: &[AssetInfo; 2]) -> Vec<u8> {
+/// `asset_infos` is a slice with items of type [`AssetInfo`].
+pub fn pair_key(asset_infos: &[AssetInfo]) -> Vec<u8> {
     let mut asset_infos = asset_infos.to_vec();
     asset_infos.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
 
-    [asset_infos[0].as_bytes(), asset_infos[1].as_bytes()].concat()
+    asset_infos
+        .iter()
+        .map(|asset_info| asset_info.as_bytes())
+        .collect::<Vec<&[u8]>>()
+        .concat()
 }
 
 /// Saves pair type configurations
 pub const PAIR_CONFIGS: Map<String, PairConfig> = Map::new("pair_configs");
 
+/// ## Description
+/// This structure holds the fees that override the fees of the pair type for a specific pair.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PairFees {
+    /// The total fees (in bps) charged by the pair
+    pub total_fee_bps: Option<u16>,
+    /// The amount of fees (in bps) collected by the Maker contract from the pair
+    pub maker_fee_bps: Option<u16>,
+}
+
+impl PairFees {
+    /// ## Description
+    /// Overrides the fees of the pair type in `pair_config` with the fees set for the pair.
+    /// Fees that are set to [`None`] keep the pair type fees.
+    /// ## Params
+    /// * **pair_config** is an object of type [`PairConfig`]. This is the config of the pair type.
+    pub fn apply(&self, pair_config: &mut PairConfig) {
+        if let Some(total_fee_bps) = self.total_fee_bps {
+            pair_config.total_fee_bps = total_fee_bps;
+        }
+        if let Some(maker_fee_bps) = self.maker_fee_bps {
+            pair_config.maker_fee_bps = maker_fee_bps;
+        }
+    }
+}
+
+/// Saves fee overrides by pair address
+pub const PAIR_FEES: Map<&Addr, PairFees> = Map::new("pair_fees");
+
 /// ## Pagination settings
 /// The maximum limit for reading pairs from [`PAIRS`]
 const MAX_LIMIT: u32 = 30;
@@ -72,11 +116,37 @@ pub fn read_pairs(
     deps: Deps,
     start_after: Option<[AssetInfo; 2]>,
     limit: Option<u32>,
+) -> Vec<Addr> {
+    read_pairs_from(deps, PAIRS, start_after.map(|a| a.to_vec()), limit)
+}
+
+/// ## Description
+/// Reads pairs that hold more than two assets from the [`MULTI_ASSET_PAIRS`] vector according to
+/// the `start_after` and `limit` variables.
+/// ## Params
+/// `start_after` is the pair from which the function starts to fetch results. It is an [`Option`].
+///
+/// `limit` is the number of items to retreive. It is an [`Option`].
+pub fn read_multi_asset_pairs(
+    deps: Deps,
+    start_after: Option<Vec<AssetInfo>>,
+    limit: Option<u32>,
+) -> Vec<Addr> {
+    read_pairs_from(deps, MULTI_ASSET_PAIRS, start_after, limit)
+}
+
+/// ## Description
+/// Reads pairs from the specified map according to the `start_after` and `limit` variables.
+fn read_pairs_from(
+    deps: Deps,
+    pairs: Map<&[u8], Addr>,
+    start_after: Option<Vec<AssetInfo>>,
+    limit: Option<u32>,
 ) -> Vec<Addr> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start = calc_range_start(start_after).map(Bound::exclusive);
 
-    PAIRS
+    pairs
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|item| {
@@ -89,17 +159,11 @@ pub fn read_pairs(
 /// ## Description
 /// Calculates the key of a pair from which to start reading data.
 /// ## Params
-/// `start_after` is an [`Option`] type that accepts two [`AssetInfo`] elements.
+/// `start_after` is an [`Option`] type that accepts a vector of [`AssetInfo`] elements.
 /// It is the token pair which we use to determine the start index for a range when returning data for multiple pairs
-fn calc_range_start(start_after: Option<[AssetInfo; 2]>) -> Option<Vec<u8>> {
+fn calc_range_start(start_after: Option<Vec<AssetInfo>>) -> Option<Vec<u8>> {
     start_after.map(|asset_infos| {
-        let mut asset_infos = asset_infos.to_vec();
-        asset_infos.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
-
-        let mut v = [asset_infos[0].as_bytes(), asset_infos[1].as_bytes()]
-            .concat()
-            .as_slice()
-            .to_vec();
+        let mut v = pair_key(&asset_infos);
         v.push(1);
         v
     })
@@ -1,24 +1,27 @@
 use cosmwasm_std::{
-    attr, from_binary, to_binary, Addr, ContractResult, Reply, ReplyOn, SubMsg,
-    SubMsgExecutionResponse, WasmMsg,
+    attr, from_binary, from_slice, to_binary, Addr, ContractResult, Deps, Empty, Reply, ReplyOn,
+    StdError, SubMsg, SubMsgExecutionResponse, WasmMsg,
 };
 
+use crate::migration::{PairConfigV120, PAIR_CONFIGSV120};
 use crate::mock_querier::mock_dependencies;
-use crate::state::CONFIG;
+use crate::state::{pair_key, CONFIG, PAIRS, PAIR_CONFIGS, PAIR_FEES, PAIR_TYPES};
 use crate::{
-    contract::{execute, instantiate, query},
+    contract::{execute, instantiate, migrate, query},
     error::ContractError,
 };
 
-use astroport::asset::{AssetInfo, PairInfo};
+use astroport::asset::{AssetInfo, MultiAssetPairInfo, PairInfo};
 use astroport::factory::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, PairConfig, PairType, PairsResponse, QueryMsg,
+    ConfigResponse, ExecuteMsg, FeeInfoResponse, InstantiateMsg, MigrateMsg,
+    MultiAssetPairsResponse, PairConfig, PairType, PairsResponse, QueryMsg,
 };
 
 use crate::contract::reply;
 use crate::response::MsgInstantiateContractResponse;
-use astroport::pair::InstantiateMsg as PairInstantiateMsg;
+use astroport::pair::{InstantiateMsg as PairInstantiateMsg, MultiAssetInstantiateMsg};
 use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cw2::{get_contract_version, set_contract_version};
 use protobuf::Message;
 
 #[test]
@@ -40,6 +43,7 @@ fn proper_initialization() {
                 pair_type: PairType::Xyk {},
                 total_fee_bps: 100,
                 maker_fee_bps: 10,
+                flash_loan_fee_bps: 0,
                 is_disabled: false,
                 is_generator_disabled: false,
             },
@@ -48,6 +52,7 @@ fn proper_initialization() {
                 pair_type: PairType::Xyk {},
                 total_fee_bps: 100,
                 maker_fee_bps: 10,
+                flash_loan_fee_bps: 0,
                 is_disabled: false,
                 is_generator_disabled: false,
             },
@@ -71,6 +76,7 @@ fn proper_initialization() {
             pair_type: PairType::Xyk {},
             total_fee_bps: 10_001,
             maker_fee_bps: 10,
+            flash_loan_fee_bps: 0,
             is_disabled: false,
             is_generator_disabled: false,
         }],
@@ -96,6 +102,7 @@ fn proper_initialization() {
                 pair_type: PairType::Stable {},
                 total_fee_bps: 100,
                 maker_fee_bps: 10,
+                flash_loan_fee_bps: 0,
                 is_disabled: false,
                 is_generator_disabled: false,
             },
@@ -104,6 +111,7 @@ fn proper_initialization() {
                 pair_type: PairType::Xyk {},
                 total_fee_bps: 100,
                 maker_fee_bps: 10,
+                flash_loan_fee_bps: 0,
                 is_disabled: false,
                 is_generator_disabled: false,
             },
@@ -137,6 +145,7 @@ fn update_config() {
         pair_type: PairType::Xyk {},
         total_fee_bps: 3,
         maker_fee_bps: 166,
+        flash_loan_fee_bps: 0,
         is_disabled: false,
         is_generator_disabled: false,
     }];
@@ -284,6 +293,7 @@ fn update_pair_config() {
         pair_type: PairType::Xyk {},
         total_fee_bps: 100,
         maker_fee_bps: 10,
+        flash_loan_fee_bps: 0,
         is_disabled: false,
         is_generator_disabled: false,
     }];
@@ -314,6 +324,7 @@ fn update_pair_config() {
         pair_type: PairType::Xyk {},
         total_fee_bps: 1,
         maker_fee_bps: 2,
+        flash_loan_fee_bps: 0,
         is_disabled: false,
         is_generator_disabled: false,
     };
@@ -337,6 +348,7 @@ fn update_pair_config() {
             pair_type: PairType::Xyk {},
             total_fee_bps: 3,
             maker_fee_bps: 10_001,
+            flash_loan_fee_bps: 0,
             is_disabled: false,
             is_generator_disabled: false,
         },
@@ -364,6 +376,7 @@ fn update_pair_config() {
         pair_type: PairType::Custom("test".to_string()),
         total_fee_bps: 10,
         maker_fee_bps: 20,
+        flash_loan_fee_bps: 0,
         is_disabled: false,
         is_generator_disabled: false,
     };
@@ -393,6 +406,7 @@ fn create_pair() {
         pair_type: PairType::Xyk {},
         total_fee_bps: 100,
         maker_fee_bps: 10,
+        flash_loan_fee_bps: 0,
         is_disabled: false,
         is_generator_disabled: false,
     };
@@ -493,6 +507,7 @@ fn register() {
             pair_type: PairType::Xyk {},
             total_fee_bps: 100,
             maker_fee_bps: 10,
+            flash_loan_fee_bps: 0,
             is_disabled: false,
             is_generator_disabled: false,
         }],
@@ -740,3 +755,662 @@ fn register() {
         },]
     );
 }
+
+#[test]
+fn create_multi_asset_pair() {
+    let mut deps = mock_dependencies(&[]);
+    let owner = "owner0000";
+
+    let pair_type = PairType::Custom("stable_n".to_string());
+    let pair_config = PairConfig {
+        code_id: 321u64,
+        pair_type: pair_type.clone(),
+        total_fee_bps: 5,
+        maker_fee_bps: 5000,
+        flash_loan_fee_bps: 0,
+        is_disabled: false,
+        is_generator_disabled: false,
+    };
+
+    let msg = InstantiateMsg {
+        pair_configs: vec![pair_config.clone()],
+        token_code_id: 123u64,
+        fee_address: None,
+        owner: owner.to_string(),
+        generator_address: Some(String::from("generator")),
+        whitelist_code_id: 234u64,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    let asset_infos = vec![
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0001"),
+        },
+    ];
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+
+    // Two assets must be handled by a regular pair
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::CreateMultiAssetPair {
+            pair_type: pair_type.clone(),
+            asset_infos: asset_infos[..2].to_vec(),
+            init_params: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidNumberOfAssets {});
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::CreateMultiAssetPair {
+            pair_type: pair_type.clone(),
+            asset_infos: vec![
+                asset_infos[0].clone(),
+                asset_infos[1].clone(),
+                asset_infos[1].clone(),
+            ],
+            init_params: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::DoublingAssets {});
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        ExecuteMsg::CreateMultiAssetPair {
+            pair_type: pair_type.clone(),
+            asset_infos: asset_infos.clone(),
+            init_params: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "create_pair"),
+            attr("pair", "uusd-asset0000-asset0001")
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg {
+            msg: WasmMsg::Instantiate {
+                msg: to_binary(&MultiAssetInstantiateMsg {
+                    factory_addr: String::from(MOCK_CONTRACT_ADDR),
+                    asset_infos: asset_infos.clone(),
+                    token_code_id: 123u64,
+                    init_params: None
+                })
+                .unwrap(),
+                code_id: pair_config.code_id,
+                funds: vec![],
+                admin: Some(owner.to_string()),
+                label: String::from("Astroport pair"),
+            }
+            .into(),
+            id: 1,
+            gas_limit: None,
+            reply_on: ReplyOn::Success
+        }]
+    );
+
+    let pair_addr = "pair0000".to_string();
+    let pair_info = MultiAssetPairInfo {
+        asset_infos: asset_infos.clone(),
+        contract_addr: Addr::unchecked("pair0000"),
+        liquidity_token: Addr::unchecked("liquidity0000"),
+        pair_type: pair_type.clone(),
+    };
+    deps.querier
+        .with_astroport_multi_asset_pairs(&[(&pair_addr, &pair_info)]);
+
+    let data = MsgInstantiateContractResponse {
+        contract_address: String::from("pair0000"),
+        data: vec![],
+        unknown_fields: Default::default(),
+        cached_size: Default::default(),
+    }
+    .write_to_bytes()
+    .unwrap();
+
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(data.into()),
+        }),
+    };
+    reply(deps.as_mut(), mock_env(), reply_msg.clone()).unwrap();
+
+    // Check pair was registered
+    let err = reply(deps.as_mut(), mock_env(), reply_msg).unwrap_err();
+    assert_eq!(err, ContractError::PairWasRegistered {});
+
+    // The pair can be found regardless of the order of its assets
+    let mut reversed_asset_infos = asset_infos.clone();
+    reversed_asset_infos.reverse();
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::MultiAssetPair {
+            asset_infos: reversed_asset_infos.clone(),
+        },
+    )
+    .unwrap();
+    assert_eq!(from_binary::<MultiAssetPairInfo>(&res).unwrap(), pair_info);
+
+    // Two-asset pair queries don't list it
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::Pairs {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(from_binary::<PairsResponse>(&res).unwrap().pairs, vec![]);
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::MultiAssetPairs {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        from_binary::<MultiAssetPairsResponse>(&res).unwrap().pairs,
+        vec![pair_info.clone()]
+    );
+
+    // The pair gets the fees of its pair type
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::PairFeeInfo {
+            pair_addr: pair_addr.clone(),
+        },
+    )
+    .unwrap();
+    let fee_info: FeeInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(fee_info.total_fee_bps, 5);
+    assert_eq!(fee_info.maker_fee_bps, 5000);
+
+    // Fees of pairs that hold more than two assets can be overridden as well
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(owner, &[]),
+        ExecuteMsg::UpdatePairFees {
+            asset_infos: reversed_asset_infos.clone(),
+            total_fee_bps: Some(20),
+            maker_fee_bps: None,
+        },
+    )
+    .unwrap();
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::PairFeeInfo {
+            pair_addr: pair_addr.clone(),
+        },
+    )
+    .unwrap();
+    let fee_info: FeeInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(fee_info.total_fee_bps, 20);
+    assert_eq!(fee_info.maker_fee_bps, 5000);
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(owner, &[]),
+        ExecuteMsg::CreateMultiAssetPair {
+            pair_type,
+            asset_infos: asset_infos.clone(),
+            init_params: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::PairWasCreated {});
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("wrong_addr0000", &[]),
+        ExecuteMsg::DeregisterMultiAssetPair {
+            asset_infos: asset_infos.clone(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(owner, &[]),
+        ExecuteMsg::DeregisterMultiAssetPair { asset_infos },
+    )
+    .unwrap();
+    assert_eq!(res.attributes[0], attr("action", "deregister"));
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: "generator".to_string(),
+            msg: to_binary(&astroport::generator::ExecuteMsg::DeactivatePool {
+                lp_token: "liquidity0000".to_string(),
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+
+    // The fee overrides and the pair type of the pair are removed with it
+    let pair_addr = Addr::unchecked(pair_addr);
+    assert_eq!(
+        PAIR_FEES
+            .may_load(deps.as_ref().storage, &pair_addr)
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        PAIR_TYPES
+            .may_load(deps.as_ref().storage, &pair_addr)
+            .unwrap(),
+        None
+    );
+    let err = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::PairFeeInfo {
+            pair_addr: pair_addr.to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("The pair is not registered in the factory")
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::MultiAssetPairs {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        from_binary::<MultiAssetPairsResponse>(&res).unwrap().pairs,
+        vec![]
+    );
+}
+
+#[test]
+fn update_pair_fees() {
+    let mut deps = mock_dependencies(&[]);
+    let owner = "owner0000";
+
+    let msg = InstantiateMsg {
+        pair_configs: vec![PairConfig {
+            code_id: 123u64,
+            pair_type: PairType::Xyk {},
+            total_fee_bps: 30,
+            maker_fee_bps: 1660,
+            flash_loan_fee_bps: 9,
+            is_disabled: false,
+            is_generator_disabled: false,
+        }],
+        token_code_id: 123u64,
+        fee_address: Some(String::from("fee")),
+        generator_address: None,
+        owner: owner.to_string(),
+        whitelist_code_id: 234u64,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let asset_infos = [
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0001"),
+        },
+    ];
+
+    let msg = ExecuteMsg::CreatePair {
+        pair_type: PairType::Xyk {},
+        asset_infos: asset_infos.clone(),
+        init_params: None,
+    };
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let pair0_addr = "pair0000".to_string();
+    let pair0_info = PairInfo {
+        asset_infos: asset_infos.clone(),
+        contract_addr: Addr::unchecked("pair0000"),
+        liquidity_token: Addr::unchecked("liquidity0000"),
+        pair_type: PairType::Xyk {},
+    };
+    deps.querier
+        .with_astroport_pairs(&[(&pair0_addr, &pair0_info)]);
+
+    let data = MsgInstantiateContractResponse {
+        contract_address: String::from("pair0000"),
+        data: vec![],
+        unknown_fields: Default::default(),
+        cached_size: Default::default(),
+    }
+    .write_to_bytes()
+    .unwrap();
+
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(data.into()),
+        }),
+    };
+    reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    let query_pair_fees = |deps: Deps| -> FeeInfoResponse {
+        from_binary(
+            &query(
+                deps,
+                mock_env(),
+                QueryMsg::PairFeeInfo {
+                    pair_addr: pair0_addr.clone(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+
+    // Without overrides the pair uses the fees of its pair type
+    let default_fees = FeeInfoResponse {
+        fee_address: Some(Addr::unchecked("fee")),
+        total_fee_bps: 30,
+        maker_fee_bps: 1660,
+        flash_loan_fee_bps: 9,
+    };
+    assert_eq!(query_pair_fees(deps.as_ref()), default_fees);
+
+    // Pairs that are not registered in the factory have no fees
+    let err = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::PairFeeInfo {
+            pair_addr: "unknown_pair".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("The pair is not registered in the factory")
+    );
+
+    // Only the owner can override fees
+    let msg = ExecuteMsg::UpdatePairFees {
+        asset_infos: asset_infos.to_vec(),
+        total_fee_bps: Some(100),
+        maker_fee_bps: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    // Fee bps can't exceed 10,000
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(owner, &[]),
+        ExecuteMsg::UpdatePairFees {
+            asset_infos: asset_infos.to_vec(),
+            total_fee_bps: None,
+            maker_fee_bps: Some(10_001),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::PairInvalidFeeBps {});
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(owner, &[]),
+        ExecuteMsg::UpdatePairFees {
+            asset_infos: asset_infos.to_vec(),
+            total_fee_bps: Some(10_001),
+            maker_fee_bps: Some(0),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::PairInvalidFeeBps {});
+    assert_eq!(query_pair_fees(deps.as_ref()), default_fees);
+
+    // The pair must exist
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(owner, &[]),
+        ExecuteMsg::UpdatePairFees {
+            asset_infos: vec![
+                AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0002"),
+                },
+            ],
+            total_fee_bps: Some(100),
+            maker_fee_bps: None,
+        },
+    )
+    .unwrap_err();
+
+    // Override the total fee only
+    let res = execute(deps.as_mut(), env.clone(), mock_info(owner, &[]), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_pair_fees"),
+            attr("pair_contract_addr", "pair0000")
+        ]
+    );
+    assert_eq!(
+        query_pair_fees(deps.as_ref()),
+        FeeInfoResponse {
+            total_fee_bps: 100,
+            ..default_fees.clone()
+        }
+    );
+
+    // The pair type fees stay the same
+    let res: FeeInfoResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::FeeInfo {
+                pair_type: PairType::Xyk {},
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res, default_fees);
+
+    // Override both fees
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(owner, &[]),
+        ExecuteMsg::UpdatePairFees {
+            asset_infos: asset_infos.to_vec(),
+            total_fee_bps: Some(5),
+            maker_fee_bps: Some(0),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        query_pair_fees(deps.as_ref()),
+        FeeInfoResponse {
+            total_fee_bps: 5,
+            maker_fee_bps: 0,
+            ..default_fees.clone()
+        }
+    );
+
+    // Removing the overrides falls back to the pair type fees
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(owner, &[]),
+        ExecuteMsg::UpdatePairFees {
+            asset_infos: asset_infos.to_vec(),
+            total_fee_bps: None,
+            maker_fee_bps: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(query_pair_fees(deps.as_ref()), default_fees);
+
+    // Deregistering a pair removes its overrides
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(owner, &[]),
+        ExecuteMsg::UpdatePairFees {
+            asset_infos: asset_infos.to_vec(),
+            total_fee_bps: Some(100),
+            maker_fee_bps: None,
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info(owner, &[]),
+        ExecuteMsg::Deregister { asset_infos },
+    )
+    .unwrap();
+    assert!(PAIR_FEES
+        .may_load(deps.as_ref().storage, &Addr::unchecked("pair0000"))
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn migrate_pair_configs_without_flash_loan_fee() {
+    let mut deps = mock_dependencies(&[]);
+
+    // Pair configs and fee info stored or returned before flash loans were added still deserialize
+    let pair_config: PairConfig = from_slice(
+        br#"{"code_id":123,"pair_type":{"xyk":{}},"total_fee_bps":30,"maker_fee_bps":1660,"is_disabled":false,"is_generator_disabled":false}"#,
+    )
+    .unwrap();
+    assert_eq!(pair_config.flash_loan_fee_bps, 0);
+    assert!(pair_config.valid_fee_bps());
+
+    let fee_info: FeeInfoResponse =
+        from_slice(br#"{"fee_address":"fee","total_fee_bps":30,"maker_fee_bps":1660}"#).unwrap();
+    assert_eq!(fee_info.flash_loan_fee_bps, 0);
+
+    set_contract_version(deps.as_mut().storage, "astroport-factory", "1.2.0").unwrap();
+    PAIR_CONFIGSV120
+        .save(
+            deps.as_mut().storage,
+            PairType::Xyk {}.to_string(),
+            &PairConfigV120 {
+                code_id: 123u64,
+                pair_type: PairType::Xyk {},
+                total_fee_bps: 30,
+                maker_fee_bps: 1660,
+                is_disabled: false,
+                is_generator_disabled: true,
+            },
+        )
+        .unwrap();
+
+    // Pairs registered before the pair types were stored by the factory
+    let asset_infos = [
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        AssetInfo::NativeToken {
+            denom: "uluna".to_string(),
+        },
+    ];
+    let pair_addr = Addr::unchecked("pair0000");
+    PAIRS
+        .save(deps.as_mut().storage, &pair_key(&asset_infos), &pair_addr)
+        .unwrap();
+    deps.querier.with_astroport_pairs(&[(
+        &pair_addr.to_string(),
+        &PairInfo {
+            asset_infos,
+            contract_addr: pair_addr.clone(),
+            liquidity_token: Addr::unchecked("liquidity0000"),
+            pair_type: PairType::Stable {},
+        },
+    )]);
+
+    migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            params: to_binary(&Empty {}).unwrap(),
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        PAIR_CONFIGS
+            .load(deps.as_ref().storage, PairType::Xyk {}.to_string())
+            .unwrap(),
+        PairConfig {
+            code_id: 123u64,
+            pair_type: PairType::Xyk {},
+            total_fee_bps: 30,
+            maker_fee_bps: 1660,
+            flash_loan_fee_bps: 0,
+            is_disabled: false,
+            is_generator_disabled: true,
+        }
+    );
+    assert_eq!(
+        PAIR_TYPES.load(deps.as_ref().storage, &pair_addr).unwrap(),
+        PairType::Stable {}
+    );
+    assert_eq!(
+        get_contract_version(deps.as_ref().storage).unwrap().version,
+        env!("CARGO_PKG_VERSION")
+    );
+}
@@ -75,6 +75,7 @@ fn proper_initialization() {
         pair_type: PairType::Xyk {},
         total_fee_bps: 100,
         maker_fee_bps: 10,
+        flash_loan_fee_bps: 0,
         is_disabled: false,
         is_generator_disabled: false,
     }];
@@ -183,6 +184,7 @@ fn instantiate_contract(app: &mut TerraApp, owner: &Addr, token_code_id: u64) ->
         pair_type: PairType::Xyk {},
         total_fee_bps: 100,
         maker_fee_bps: 10,
+        flash_loan_fee_bps: 0,
         is_disabled: false,
         is_generator_disabled: false,
     }];
@@ -1,25 +1,45 @@
 use crate::error::ContractError;
-use crate::state::{Config, CONFIG};
+use crate::state::{
+    Config, FlashLoanState, PendingProvide, ProvideParams, CONFIG, FLASH_LOAN, PENDING_PROVIDE,
+    RESERVES,
+};
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    attr, entry_point, from_binary, to_binary, Addr, Binary, Coin, CosmosMsg, Decimal, Deps,
-    DepsMut, Env, MessageInfo, Reply, ReplyOn, Response, StdError, StdResult, SubMsg, Uint128,
-    WasmMsg,
+    attr, entry_point, from_binary, to_binary, Addr, Api, Binary, Coin, CosmosMsg, Decimal, Deps,
+    DepsMut, Env, Fraction, MessageInfo, Reply, ReplyOn, Response, StdError, StdResult, Storage,
+    SubMsg, Uint128, WasmMsg,
 };
 
 use crate::response::MsgInstantiateContractResponse;
-use astroport::asset::{addr_validate_to_lower, format_lp_token_name, Asset, AssetInfo, PairInfo};
+use astroport::asset::{
+    addr_validate_to_lower, format_lp_token_name, Asset, AssetInfo, Callback, PairInfo,
+};
+use astroport::circuit_breaker::{
+    self, guardian, is_paused, query_status, set_paused, track_price_change,
+};
 use astroport::factory::PairType;
 use astroport::generator::Cw20HookMsg as GeneratorHookMsg;
-use astroport::pair::{ConfigResponse, DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE};
+use astroport::observation::{
+    increase_observations_size, observe, oldest_observation, store_observation, ObserveResponse,
+};
 use astroport::pair::{
-    CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, PoolResponse,
-    QueryMsg, ReverseSimulationResponse, SimulationResponse, TWAP_PRECISION,
+    ConfigResponse, DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE, MINIMUM_LIQUIDITY_AMOUNT,
 };
-use astroport::querier::{query_factory_config, query_fee_info, query_supply};
+use astroport::pair::{
+    CumulativePricesResponse, Cw20HookMsg, DynamicFeeParams, ExecuteMsg, FlashLoanCallbackMsg,
+    InstantiateMsg, MigrateMsg, PoolResponse, PriceImpactResponse, QueryMsg,
+    ReverseSimulationResponse, SimulateProvideResponse, SimulateWithdrawResponse,
+    SimulationResponse, SpotPriceResponse, WithdrawOneAssetSimulationResponse, XykPoolConfig,
+    XykPoolParams, XykPoolUpdateParams, TWAP_PRECISION,
+};
+use astroport::querier::{query_factory_config, query_pair_fee_info, query_supply, FeeInfo};
+use astroport::reward_bearing::{
+    self, init_reward_bearing, load_reward_bearing_config, REWARD_INDEXES,
+};
+use astroport::stats::{query_daily_stats, query_stats, record_fee, record_swap};
 use astroport::{token::InstantiateMsg as TokenInstantiateMsg, U256};
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
 use protobuf::Message;
 use std::str::FromStr;
@@ -31,6 +51,12 @@ const CONTRACT_NAME: &str = "astroport-pair";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 /// A `reply` call code ID used for sub-messages.
 const INSTANTIATE_TOKEN_REPLY_ID: u64 = 1;
+/// A `reply` call code ID of the flash loan callback sub-message.
+const FLASH_LOAN_REPLY_ID: u64 = 2;
+/// A `reply` call code ID of the reward holder instantiation sub-message.
+const INSTANTIATE_REWARD_HOLDER_REPLY_ID: u64 = 3;
+/// A `reply` call code ID of the last token transfer of a liquidity provision in the fee-on-transfer mode.
+const PROVIDE_LIQUIDITY_REPLY_ID: u64 = 4;
 
 /// ## Description
 /// Creates a new contract with the specified parameters in the [`InstantiateMsg`].
@@ -45,7 +71,7 @@ const INSTANTIATE_TOKEN_REPLY_ID: u64 = 1;
 /// * **msg** is a message of type [`InstantiateMsg`] which contains the basic settings for creating a contract.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
@@ -59,6 +85,13 @@ pub fn instantiate(
 
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    let params: XykPoolParams = match &msg.init_params {
+        Some(init_params) => from_binary(init_params)?,
+        None => XykPoolParams {
+            fee_on_transfer: false,
+        },
+    };
+
     let config = Config {
         pair_info: PairInfo {
             contract_addr: env.contract.address.clone(),
@@ -70,14 +103,19 @@ pub fn instantiate(
         block_time_last: 0,
         price0_cumulative_last: Uint128::zero(),
         price1_cumulative_last: Uint128::zero(),
+        dynamic_fee: None,
+        mint_maker_fee: false,
+        sqrt_k_last: Uint128::zero(),
+        fee_on_transfer: params.fee_on_transfer,
     };
 
     CONFIG.save(deps.storage, &config)?;
+    RESERVES.save(deps.storage, &[Uint128::zero(), Uint128::zero()])?;
 
-    let token_name = format_lp_token_name(msg.asset_infos, &deps.querier)?;
+    let token_name = format_lp_token_name(&msg.asset_infos, &deps.querier)?;
 
     // Create the LP token contract
-    let sub_msg: Vec<SubMsg> = vec![SubMsg {
+    let mut sub_msg: Vec<SubMsg> = vec![SubMsg {
         msg: WasmMsg::Instantiate {
             code_id: msg.token_code_id,
             msg: to_binary(&TokenInstantiateMsg {
@@ -100,6 +138,15 @@ pub fn instantiate(
         reply_on: ReplyOn::Success,
     }];
 
+    // Create the reward holder if the pair holds a reward-bearing asset
+    sub_msg.extend(init_reward_bearing(
+        deps.branch(),
+        &env,
+        &msg.init_params,
+        &config.factory_addr,
+        INSTANTIATE_REWARD_HOLDER_REPLY_ID,
+    )?);
+
     Ok(Response::new().add_submessages(sub_msg))
 }
 
@@ -108,23 +155,38 @@ pub fn instantiate(
 /// # Params
 /// * **deps** is an object of type [`DepsMut`].
 ///
-/// * **_env** is an object of type [`Env`].
+/// * **env** is an object of type [`Env`].
 ///
 /// * **msg** is an object of type [`Reply`].
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
-    let mut config: Config = CONFIG.load(deps.storage)?;
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    if msg.id == FLASH_LOAN_REPLY_ID {
+        return repay_flash_loan(deps, env);
+    }
 
-    if config.pair_info.liquidity_token != Addr::unchecked("") {
-        return Err(ContractError::Unauthorized {});
+    if msg.id == PROVIDE_LIQUIDITY_REPLY_ID {
+        return settle_provide_liquidity(deps, env);
     }
 
+    let reply_id = msg.id;
     let data = msg.result.unwrap().data.unwrap();
     let res: MsgInstantiateContractResponse =
         Message::parse_from_bytes(data.as_slice()).map_err(|_| {
             StdError::parse_err("MsgInstantiateContractResponse", "failed to parse data")
         })?;
 
+    if reply_id == INSTANTIATE_REWARD_HOLDER_REPLY_ID {
+        let reward_holder = addr_validate_to_lower(deps.api, res.get_contract_address())?;
+        REWARD_INDEXES.holder.save(deps.storage, &reward_holder)?;
+        return Ok(Response::new().add_attribute("reward_holder", reward_holder));
+    }
+
+    let mut config: Config = CONFIG.load(deps.storage)?;
+
+    if config.pair_info.liquidity_token != Addr::unchecked("") {
+        return Err(ContractError::Unauthorized {});
+    }
+
     config.pair_info.liquidity_token =
         addr_validate_to_lower(deps.api, res.get_contract_address())?;
 
@@ -145,7 +207,10 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 /// * **msg** is an object of type [`ExecuteMsg`].
 ///
 /// ## Queries
-/// * **ExecuteMsg::UpdateConfig { params: Binary }** Not supported.
+/// * **ExecuteMsg::UpdateConfig { params: Binary }** Enables or disables the dynamic fee mode or the Maker fee minting mode of the pair.
+///
+/// * **ExecuteMsg::IncreaseObservationsSize { size }** Increases the amount of price observations
+/// stored by the pair.
 ///
 /// * **ExecuteMsg::Receive(msg)** Receives a message of type [`Cw20ReceiveMsg`] and processes
 /// it depending on the received template.
@@ -155,6 +220,9 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 ///             slippage_tolerance,
 ///             auto_stake,
 ///             receiver,
+///             min_lp_to_receive,
+///             deadline,
+///             callback,
 ///         }** Provides liquidity in the pair with the specified input parameters.
 ///
 /// * **ExecuteMsg::Swap {
@@ -162,7 +230,51 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 ///             belief_price,
 ///             max_spread,
 ///             to,
+///             deadline,
+///             callback,
 ///         }** Performs a swap operation with the specified parameters.
+///
+/// * **ExecuteMsg::SwapExactOut {
+///             ask_asset,
+///             max_offer_amount,
+///             to,
+///         }** Performs a swap that returns an exact amount of ask assets and refunds the unused offer assets.
+///
+/// * **ExecuteMsg::FlashLoan {
+///             asset,
+///             callback_contract,
+///             msg,
+///         }** Lends a pool asset to a callback contract that has to repay it within the same transaction.
+///
+/// * **ExecuteMsg::Skim { to }** Sends the pair balances that exceed the pool reserves to the recipient.
+///
+/// * **ExecuteMsg::Sync {}** Sets the pool reserves to the current pair balances.
+///
+/// * **ExecuteMsg::Pause {}** Stops swaps and liquidity provision in the pair.
+///
+/// * **ExecuteMsg::Unpause {}** Resumes swaps and liquidity provision in the pair.
+///
+/// * **ExecuteMsg::UpdateCircuitBreaker {
+///             guardian,
+///             max_price_change_bps,
+///         }** Updates the guardian and the maximum price change of the pair.
+///
+/// * **ExecuteMsg::ClaimReward { receiver }** Claims the rewards of the reward-bearing pool asset
+/// and sends them to the receiver.
+///
+/// * **ExecuteMsg::ClaimRewardByGenerator {
+///             user,
+///             user_share,
+///             total_share,
+///         }** Claims the rewards for a LP position that a user staked in the Generator contract.
+///
+/// * **ExecuteMsg::HandleReward {
+///             previous_reward_balance,
+///             user,
+///             user_share,
+///             total_share,
+///             receiver,
+///         }** Distributes the claimed rewards.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -170,29 +282,51 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // The pool balances must not change while a flash loan is in progress
+    if FLASH_LOAN.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::FlashLoanInProgress {});
+    }
+
     match msg {
-        ExecuteMsg::UpdateConfig { .. } => Err(ContractError::NonSupported {}),
+        ExecuteMsg::UpdateConfig { params } => update_config(deps, info, params),
+        ExecuteMsg::IncreaseObservationsSize { size } => {
+            increase_observations_size(deps.storage, size)?;
+            Ok(Response::new()
+                .add_attribute("action", "increase_observations_size")
+                .add_attribute("size", size.to_string()))
+        }
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
         ExecuteMsg::ProvideLiquidity {
             assets,
             slippage_tolerance,
             auto_stake,
             receiver,
-        } => provide_liquidity(
-            deps,
-            env,
-            info,
-            assets,
-            slippage_tolerance,
-            auto_stake,
-            receiver,
-        ),
+            min_lp_to_receive,
+            deadline,
+            callback,
+        } => {
+            assert_deadline(&env, deadline)?;
+            provide_liquidity(
+                deps,
+                env,
+                info,
+                assets,
+                slippage_tolerance,
+                auto_stake,
+                receiver,
+                min_lp_to_receive,
+                callback,
+            )
+        }
         ExecuteMsg::Swap {
             offer_asset,
             belief_price,
             max_spread,
             to,
+            deadline,
+            callback,
         } => {
+            assert_deadline(&env, deadline)?;
             offer_asset.info.check(deps.api)?;
             if !offer_asset.is_native_token() {
                 return Err(ContractError::Unauthorized {});
@@ -213,8 +347,76 @@ pub fn execute(
                 belief_price,
                 max_spread,
                 to_addr,
+                callback,
+            )
+        }
+        ExecuteMsg::SwapExactOut {
+            ask_asset,
+            max_offer_amount,
+            to,
+        } => {
+            ask_asset.info.check(deps.api)?;
+
+            let config: Config = CONFIG.load(deps.storage)?;
+            let offer_asset = Asset {
+                info: get_offer_asset_info(&config, &ask_asset.info)?,
+                amount: max_offer_amount,
+            };
+            if !offer_asset.is_native_token() {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let to_addr = if let Some(to_addr) = to {
+                Some(addr_validate_to_lower(deps.api, &to_addr)?)
+            } else {
+                None
+            };
+
+            swap_exact_out(
+                deps,
+                env,
+                info.clone(),
+                info.sender,
+                offer_asset,
+                ask_asset,
+                to_addr,
             )
         }
+        ExecuteMsg::FlashLoan {
+            asset,
+            callback_contract,
+            msg,
+        } => flash_loan(deps, env, info, asset, callback_contract, msg),
+        ExecuteMsg::Skim { to } => skim(deps, env, to),
+        ExecuteMsg::Sync {} => sync(deps, env),
+        ExecuteMsg::Pause {} => pause(deps, info),
+        ExecuteMsg::Unpause {} => unpause(deps, info),
+        ExecuteMsg::UpdateCircuitBreaker {
+            guardian,
+            max_price_change_bps,
+        } => update_circuit_breaker(deps, info, guardian, max_price_change_bps),
+        ExecuteMsg::ClaimReward { receiver } => claim_reward(deps, env, info, receiver),
+        ExecuteMsg::ClaimRewardByGenerator {
+            user,
+            user_share,
+            total_share,
+        } => claim_reward_by_generator(deps, env, info, user, user_share, total_share),
+        ExecuteMsg::HandleReward {
+            previous_reward_balance,
+            user,
+            user_share,
+            total_share,
+            receiver,
+        } => handle_reward(
+            deps,
+            env,
+            info,
+            previous_reward_balance,
+            user,
+            user_share,
+            total_share,
+            receiver,
+        ),
     }
 }
 
@@ -242,13 +444,16 @@ pub fn receive_cw20(
             belief_price,
             max_spread,
             to,
+            deadline,
+            callback,
         }) => {
+            assert_deadline(&env, deadline)?;
             // Only asset contract can execute this message
             let mut authorized: bool = false;
             let config: Config = CONFIG.load(deps.storage)?;
 
-            for pool in config.pair_info.asset_infos {
-                if let AssetInfo::Token { contract_addr, .. } = &pool {
+            for pool in config.pair_info.asset_infos.iter() {
+                if let AssetInfo::Token { contract_addr, .. } = pool {
                     if contract_addr == &info.sender {
                         authorized = true;
                     }
@@ -265,31 +470,132 @@ pub fn receive_cw20(
                 None
             };
 
+            let offer_asset = query_received_asset(
+                deps.as_ref(),
+                &env,
+                &config,
+                Asset {
+                    info: AssetInfo::Token { contract_addr },
+                    amount: cw20_msg.amount,
+                },
+            )?;
+
             swap(
                 deps,
                 env,
                 info,
                 Addr::unchecked(cw20_msg.sender),
+                offer_asset,
+                belief_price,
+                max_spread,
+                to_addr,
+                callback,
+            )
+        }
+        Ok(Cw20HookMsg::SwapExactOut { ask_asset, to }) => {
+            // Only asset contract can execute this message
+            let config: Config = CONFIG.load(deps.storage)?;
+            let offer_asset_info = AssetInfo::Token { contract_addr };
+            if !config
+                .pair_info
+                .asset_infos
+                .iter()
+                .any(|a| a.equal(&offer_asset_info))
+            {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let to_addr = if let Some(to_addr) = to {
+                Some(addr_validate_to_lower(deps.api, to_addr.as_str())?)
+            } else {
+                None
+            };
+
+            let offer_asset = query_received_asset(
+                deps.as_ref(),
+                &env,
+                &config,
                 Asset {
-                    info: AssetInfo::Token { contract_addr },
+                    info: offer_asset_info,
                     amount: cw20_msg.amount,
                 },
-                belief_price,
-                max_spread,
+            )?;
+
+            swap_exact_out(
+                deps,
+                env,
+                info,
+                Addr::unchecked(cw20_msg.sender),
+                offer_asset,
+                ask_asset,
                 to_addr,
             )
         }
-        Ok(Cw20HookMsg::WithdrawLiquidity {}) => withdraw_liquidity(
+        Ok(Cw20HookMsg::WithdrawLiquidity {
+            min_assets_to_receive,
+            deadline,
+            callback,
+        }) => {
+            assert_deadline(&env, deadline)?;
+            withdraw_liquidity(
+                deps,
+                env,
+                info,
+                Addr::unchecked(cw20_msg.sender),
+                cw20_msg.amount,
+                min_assets_to_receive,
+                callback,
+            )
+        }
+        Ok(Cw20HookMsg::WithdrawLiquidityOneAsset {
+            ask_asset_info,
+            min_amount,
+        }) => withdraw_liquidity_one_asset(
             deps,
             env,
             info,
             Addr::unchecked(cw20_msg.sender),
             cw20_msg.amount,
+            ask_asset_info,
+            min_amount,
         ),
         Err(err) => Err(ContractError::Std(err)),
     }
 }
 
+/// ## Description
+/// Returns the offer asset with the amount that the pair received for a swap. A pair in the fee-on-transfer mode
+/// measures the amount as its balance above the pool reserve, but never more than the sent amount.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **asset** is an object of type [`Asset`]. This is the offer asset with the sent amount.
+fn query_received_asset(deps: Deps, env: &Env, config: &Config, asset: Asset) -> StdResult<Asset> {
+    if !config.fee_on_transfer {
+        return Ok(asset);
+    }
+
+    let pools: [Asset; 2] = load_pools(deps.storage, config)?;
+    let reserve = pools
+        .iter()
+        .find(|pool| pool.info.equal(&asset.info))
+        .map(|pool| pool.amount)
+        .unwrap_or_default();
+    let balance = asset
+        .info
+        .query_pool(&deps.querier, env.contract.address.clone())?;
+    let received = balance.checked_sub(reserve).unwrap_or_default();
+
+    Ok(Asset {
+        amount: std::cmp::min(asset.amount, received),
+        info: asset.info,
+    })
+}
+
 /// ## Description
 /// Provides liquidity in the pair with the specified input parameters.
 /// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified
@@ -311,7 +617,14 @@ pub fn receive_cw20(
 ///
 /// * **receiver** is an [`Option`] field of type [`String`]. This is the receiver of the LP tokens.
 /// If no custom receiver is specified, the pair will mint LP tokens for the function caller.
+///
+/// * **min_lp_to_receive** is an [`Option`] field of type [`Uint128`]. This is the minimum amount of LP tokens
+/// to receive. The provision fails if fewer LP tokens are minted.
+///
+/// * **callback** is an [`Option`] field of type [`Callback`]. This is the contract that receives
+/// the LP tokens along with a message.
 // NOTE - the address that wants to provide liquidity should approve the pair contract to pull its relevant tokens.
+#[allow(clippy::too_many_arguments)]
 pub fn provide_liquidity(
     deps: DepsMut,
     env: Env,
@@ -320,38 +633,28 @@ pub fn provide_liquidity(
     slippage_tolerance: Option<Decimal>,
     auto_stake: Option<bool>,
     receiver: Option<String>,
+    min_lp_to_receive: Option<Uint128>,
+    callback: Option<Callback>,
 ) -> Result<Response, ContractError> {
+    if is_paused(deps.storage)? {
+        return Err(ContractError::Paused {});
+    }
+
     assets[0].info.check(deps.api)?;
     assets[1].info.check(deps.api)?;
 
     let auto_stake = auto_stake.unwrap_or(false);
+    assert_callback(deps.api, &callback, auto_stake || receiver.is_some())?;
     for asset in assets.iter() {
         asset.assert_sent_native_token_balance(&info)?;
     }
 
-    let mut config: Config = CONFIG.load(deps.storage)?;
-    let mut pools: [Asset; 2] = config
-        .pair_info
-        .query_pools(&deps.querier, env.contract.address.clone())?;
-    let deposits: [Uint128; 2] = [
-        assets
-            .iter()
-            .find(|a| a.info.equal(&pools[0].info))
-            .map(|a| a.amount)
-            .expect("Wrong asset info is given"),
-        assets
-            .iter()
-            .find(|a| a.info.equal(&pools[1].info))
-            .map(|a| a.amount)
-            .expect("Wrong asset info is given"),
-    ];
-
-    if deposits[0].is_zero() || deposits[1].is_zero() {
-        return Err(ContractError::InvalidZeroAmount {});
-    }
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
+    let deposits = get_deposits(&assets, &pools)?;
 
     let mut messages: Vec<CosmosMsg> = vec![];
-    for (i, pool) in pools.iter_mut().enumerate() {
+    for (i, pool) in pools.iter().enumerate() {
         // If the asset is a token contract, then we need to execute a TransferFrom msg to receive assets
         if let AssetInfo::Token { contract_addr, .. } = &pool.info {
             messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -363,63 +666,210 @@ pub fn provide_liquidity(
                 })?,
                 funds: vec![],
             }));
-        } else {
-            // If the asset is native token, the pool balance is already increased
-            // To calculate the total amount of deposits properly, we should subtract the user deposit from the pool
-            pool.amount = pool.amount.checked_sub(deposits[i])?;
         }
     }
 
-    let total_share = query_supply(&deps.querier, config.pair_info.liquidity_token.clone())?;
-    let share = if total_share.is_zero() {
-        // Initial share = collateral amount
-        Uint128::new(
-            (U256::from(deposits[0].u128()) * U256::from(deposits[1].u128()))
-                .integer_sqrt()
-                .as_u128(),
-        )
+    let params = ProvideParams {
+        sender: info.sender,
+        assets,
+        slippage_tolerance,
+        auto_stake,
+        receiver,
+        min_lp_to_receive,
+        callback,
+    };
+
+    // The received token amounts are measured after the last transfer, so the provision is settled in a reply
+    if config.fee_on_transfer {
+        if let Some(last_transfer) = messages.pop() {
+            let balances: [Asset; 2] = config
+                .pair_info
+                .query_pools(&deps.querier, env.contract.address)?;
+            PENDING_PROVIDE.save(
+                deps.storage,
+                &PendingProvide {
+                    params,
+                    balances: [balances[0].amount, balances[1].amount],
+                },
+            )?;
+
+            return Ok(Response::new().add_messages(messages).add_submessage(
+                SubMsg::reply_on_success(last_transfer, PROVIDE_LIQUIDITY_REPLY_ID),
+            ));
+        }
+    }
+
+    finalize_provide_liquidity(deps, env, params, messages)
+}
+
+/// ## Description
+/// Measures the token amounts that a pair in the fee-on-transfer mode received for a pending liquidity
+/// provision and settles the provision with them.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified
+/// attributes if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+pub fn settle_provide_liquidity(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let PendingProvide {
+        mut params,
+        balances,
+    } = PENDING_PROVIDE.load(deps.storage)?;
+    PENDING_PROVIDE.remove(deps.storage);
+
+    let balances_after: [Asset; 2] = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address.clone())?;
+
+    // Native coins are sent along with the message, so only the token amounts can differ
+    for asset in params.assets.iter_mut().filter(|a| !a.is_native_token()) {
+        for (balance, balance_after) in balances.iter().zip(balances_after.iter()) {
+            if balance_after.info.equal(&asset.info) {
+                let received = balance_after.amount.checked_sub(*balance)?;
+                asset.amount = std::cmp::min(asset.amount, received);
+            }
+        }
+    }
+
+    finalize_provide_liquidity(deps, env, params, vec![])
+}
+
+/// ## Description
+/// Mints LP tokens for the deposited assets and updates the pool reserves.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified
+/// attributes if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **params** is an object of type [`ProvideParams`]. These are the parameters of the liquidity provision
+/// with the amounts that the pair received.
+///
+/// * **messages** is a [`Vec`] of type [`CosmosMsg`]. These are the messages that transfer the deposited tokens
+/// to the pair.
+fn finalize_provide_liquidity(
+    deps: DepsMut,
+    env: Env,
+    params: ProvideParams,
+    mut messages: Vec<CosmosMsg>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
+    let deposits = get_deposits(&params.assets, &pools)?;
+
+    let mut total_share = query_supply(&deps.querier, config.pair_info.liquidity_token.clone())?;
+
+    // Mint the Maker fee accrued since the last liquidity event before computing the share
+    let (maker_fee_msg, maker_fee_share) =
+        mint_maker_fee_message(deps.as_ref(), &config, &pools, total_share)?;
+    messages.extend(maker_fee_msg);
+    total_share = total_share.checked_add(maker_fee_share)?;
+
+    let share = compute_share(&deposits, &pools, total_share)?;
+    if total_share.is_zero() {
+        // Lock the minimum liquidity amount in the pair forever, so the first provider
+        // can't inflate the LP token price and round the share of the next providers down to zero
+        messages.extend(mint_liquidity_token_message(
+            deps.as_ref(),
+            &config,
+            env.clone(),
+            env.contract.address.clone(),
+            MINIMUM_LIQUIDITY_AMOUNT,
+            false,
+        )?);
     } else {
         // Assert slippage tolerance
-        assert_slippage_tolerance(slippage_tolerance, &deposits, &pools)?;
+        assert_slippage_tolerance(params.slippage_tolerance, &deposits, &pools)?;
+    }
 
-        // min(1, 2)
-        // 1. sqrt(deposit_0 * exchange_rate_0_to_1 * deposit_0) * (total_share / sqrt(pool_0 * pool_1))
-        // == deposit_0 * total_share / pool_0
-        // 2. sqrt(deposit_1 * exchange_rate_1_to_0 * deposit_1) * (total_share / sqrt(pool_1 * pool_1))
-        // == deposit_1 * total_share / pool_1
-        std::cmp::min(
-            deposits[0].multiply_ratio(total_share, pools[0].amount),
-            deposits[1].multiply_ratio(total_share, pools[1].amount),
-        )
-    };
+    // The minimum is checked before minting, so it also applies to the LP tokens staked in the Generator
+    if let Some(min_lp_to_receive) = params.min_lp_to_receive {
+        if share < min_lp_to_receive {
+            return Err(ContractError::MinLpToReceiveAssertion {});
+        }
+    }
 
-    // Mint LP tokens for the sender or for the receiver (if set)
-    let receiver = receiver.unwrap_or_else(|| info.sender.to_string());
-    messages.extend(mint_liquidity_token_message(
-        deps.as_ref(),
-        &config,
-        env.clone(),
-        addr_validate_to_lower(deps.api, receiver.as_str())?,
-        share,
-        auto_stake,
-    )?);
+    let receiver = match &params.callback {
+        Some(callback) => callback.contract.clone(),
+        None => params.receiver.unwrap_or_else(|| params.sender.to_string()),
+    };
+    let callback_messages = match params.callback {
+        // Mint LP tokens for the pair and send them to the callback contract after all the other messages
+        Some(callback) => {
+            messages.extend(mint_liquidity_token_message(
+                deps.as_ref(),
+                &config,
+                env.clone(),
+                env.contract.address.clone(),
+                share,
+                false,
+            )?);
+            callback.into_msgs(
+                &deps.querier,
+                &[Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: config.pair_info.liquidity_token.clone(),
+                    },
+                    amount: share,
+                }],
+            )?
+        }
+        // Mint LP tokens for the sender or for the receiver (if set)
+        None => {
+            messages.extend(mint_liquidity_token_message(
+                deps.as_ref(),
+                &config,
+                env.clone(),
+                addr_validate_to_lower(deps.api, receiver.as_str())?,
+                share,
+                params.auto_stake,
+            )?);
+            vec![]
+        }
+    };
 
     // Accumulate prices for the assets in the pool
     if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
-        accumulate_prices(env, &config, pools[0].amount, pools[1].amount)?
+        accumulate_prices(env.clone(), &config, pools[0].amount, pools[1].amount)?
     {
+        store_observation(
+            deps.storage,
+            config.block_time_last,
+            block_time,
+            price0_cumulative_new.wrapping_sub(config.price0_cumulative_last),
+            price1_cumulative_new.wrapping_sub(config.price1_cumulative_last),
+        )?;
         config.price0_cumulative_last = price0_cumulative_new;
         config.price1_cumulative_last = price1_cumulative_new;
         config.block_time_last = block_time;
-        CONFIG.save(deps.storage, &config)?;
     }
 
+    let reserves = [
+        pools[0].amount.checked_add(deposits[0])?,
+        pools[1].amount.checked_add(deposits[1])?,
+    ];
+    if config.mint_maker_fee {
+        config.sqrt_k_last = compute_sqrt_k(&reserves);
+    }
+    CONFIG.save(deps.storage, &config)?;
+    RESERVES.save(deps.storage, &reserves)?;
+    record_price_change(deps.storage, &env, [pools[0].amount, pools[1].amount])?;
+
+    messages.extend(callback_messages);
+
     Ok(Response::new().add_messages(messages).add_attributes(vec![
         attr("action", "provide_liquidity"),
-        attr("sender", info.sender.as_str()),
+        attr("sender", params.sender.as_str()),
         attr("receiver", receiver.as_str()),
-        attr("assets", format!("{}, {}", assets[0], assets[1])),
+        attr(
+            "assets",
+            format!("{}, {}", params.assets[0], params.assets[1]),
+        ),
         attr("share", share.to_string()),
+        attr("maker_fee_share", maker_fee_share.to_string()),
     ]))
 }
 
@@ -502,46 +952,86 @@ fn mint_liquidity_token_message(
 /// * **sender** is an object of type [`Addr`]. This is the address that will receive assets back from the pair contract.
 ///
 /// * **amount** is an object of type [`Uint128`]. This is the amount of LP tokens to burn.
+///
+/// * **min_assets_to_receive** is an [`Option`] field of type [`Vec<Asset>`]. These are the minimum
+/// amounts of pool assets to receive.
+///
+/// * **callback** is an [`Option`] field of type [`Callback`]. This is the contract that receives
+/// the withdrawn assets along with a message.
 pub fn withdraw_liquidity(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     sender: Addr,
     amount: Uint128,
+    min_assets_to_receive: Option<Vec<Asset>>,
+    callback: Option<Callback>,
 ) -> Result<Response, ContractError> {
     let mut config: Config = CONFIG.load(deps.storage).unwrap();
 
     if info.sender != config.pair_info.liquidity_token {
         return Err(ContractError::Unauthorized {});
     }
+    assert_callback(deps.api, &callback, false)?;
+
+    let (pools, mut total_share) = pool_info(deps.as_ref(), config.clone())?;
+
+    // Mint the Maker fee accrued since the last liquidity event before computing the refund
+    let (maker_fee_msg, maker_fee_share) =
+        mint_maker_fee_message(deps.as_ref(), &config, &pools, total_share)?;
+    total_share = total_share.checked_add(maker_fee_share)?;
 
-    let (pools, total_share) = pool_info(deps.as_ref(), config.clone())?;
     let refund_assets = get_share_in_assets(&pools, amount, total_share);
+    assert_min_assets_to_receive(&refund_assets, min_assets_to_receive)?;
 
     // Accumulate prices for the pair assets
     if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
         accumulate_prices(env, &config, pools[0].amount, pools[1].amount)?
     {
+        store_observation(
+            deps.storage,
+            config.block_time_last,
+            block_time,
+            price0_cumulative_new.wrapping_sub(config.price0_cumulative_last),
+            price1_cumulative_new.wrapping_sub(config.price1_cumulative_last),
+        )?;
         config.price0_cumulative_last = price0_cumulative_new;
         config.price1_cumulative_last = price1_cumulative_new;
         config.block_time_last = block_time;
-        CONFIG.save(deps.storage, &config)?;
     }
 
-    // Update the pool info
-    let messages: Vec<CosmosMsg> = vec![
-        refund_assets[0]
-            .clone()
-            .into_msg(&deps.querier, sender.clone())?,
-        refund_assets[1]
-            .clone()
-            .into_msg(&deps.querier, sender.clone())?,
-        CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: config.pair_info.liquidity_token.to_string(),
-            msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
-            funds: vec![],
-        }),
+    let reserves = [
+        pools[0].amount.checked_sub(refund_assets[0].amount)?,
+        pools[1].amount.checked_sub(refund_assets[1].amount)?,
     ];
+    if config.mint_maker_fee {
+        config.sqrt_k_last = compute_sqrt_k(&reserves);
+    }
+    CONFIG.save(deps.storage, &config)?;
+    RESERVES.save(deps.storage, &reserves)?;
+
+    // Update the pool info
+    let mut messages: Vec<CosmosMsg> = match &callback {
+        Some(_) => vec![],
+        None => vec![
+            refund_assets[0]
+                .clone()
+                .into_msg(&deps.querier, sender.clone())?,
+            refund_assets[1]
+                .clone()
+                .into_msg(&deps.querier, sender.clone())?,
+        ],
+    };
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.pair_info.liquidity_token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
+        funds: vec![],
+    }));
+    messages.extend(maker_fee_msg);
+    if let Some(callback) = callback {
+        // The callback contract gets the assets after the LP tokens are burned
+        messages.extend(callback.into_msgs(&deps.querier, &refund_assets)?);
+    }
 
     let attributes = vec![
         attr("action", "withdraw_liquidity"),
@@ -551,6 +1041,7 @@ pub fn withdraw_liquidity(
             "refund_assets",
             format!("{}, {}", refund_assets[0], refund_assets[1]),
         ),
+        attr("maker_fee_share", maker_fee_share.to_string()),
     ];
 
     Ok(Response::new()
@@ -559,13 +1050,245 @@ pub fn withdraw_liquidity(
 }
 
 /// ## Description
-/// Returns the amount of pool assets that correspond to an amount of LP tokens.
+/// Withdraws liquidity from the pool in a single asset. The share of the other asset is swapped
+/// into the ask asset inside the pool. Returns a [`ContractError`] on failure,
+/// otherwise returns a [`Response`] with the specified attributes if the operation was successful.
 /// ## Params
-/// * **pools** are an array of [`Asset`] type items. These are the assets in the pool.
+/// * **deps** is an object of type [`DepsMut`].
 ///
-/// * **amount** is an object of type [`Uint128`]. This is the amount of LP tokens to compute a corresponding amount of assets for.
+/// * **env** is an object of type [`Env`].
 ///
-/// * **total_share** is an object of type [`Uint128`]. This is the total amount of LP tokens currently minted.
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **sender** is an object of type [`Addr`]. This is the address that will receive the ask assets.
+///
+/// * **amount** is an object of type [`Uint128`]. This is the amount of LP tokens to burn.
+///
+/// * **ask_asset_info** is an object of type [`AssetInfo`]. This is the asset to receive.
+///
+/// * **min_amount** is an [`Option`] field of type [`Uint128`]. This is the minimum amount of ask assets to receive.
+pub fn withdraw_liquidity_one_asset(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    amount: Uint128,
+    ask_asset_info: AssetInfo,
+    min_amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    // The withdrawal swaps one of the pool assets, so it is stopped together with swaps
+    if is_paused(deps.storage)? {
+        return Err(ContractError::Paused {});
+    }
+
+    let mut config: Config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.pair_info.liquidity_token {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let (pools, mut total_share) = pool_info(deps.as_ref(), config.clone())?;
+    let ask_index = pools
+        .iter()
+        .position(|pool| pool.info.equal(&ask_asset_info))
+        .ok_or(ContractError::AssetMismatch {})?;
+
+    // Mint the Maker fee accrued since the last liquidity event before computing the withdrawal
+    let (maker_fee_msg, maker_fee_share) =
+        mint_maker_fee_message(deps.as_ref(), &config, &pools, total_share)?;
+    total_share = total_share.checked_add(maker_fee_share)?;
+
+    let fee_info = query_fee_info(deps.as_ref(), &env, &config, &pools)?;
+    let (return_amount, commission_amount) =
+        compute_withdraw_one_asset(&pools, amount, total_share, ask_index, &fee_info)?;
+
+    if return_amount < min_amount.unwrap_or_default() {
+        return Err(ContractError::MinAmountAssertion {});
+    }
+
+    let return_asset = Asset {
+        info: ask_asset_info.clone(),
+        amount: return_amount,
+    };
+    let mut messages: Vec<CosmosMsg> = vec![return_asset
+        .clone()
+        .into_msg(&deps.querier, sender.clone())?];
+    messages.extend(maker_fee_msg);
+
+    // Maker fee
+    let mut maker_fee_amount = Uint128::new(0);
+    if let Some(fee_address) = swap_fee_address(&config, &fee_info) {
+        if let Some(f) =
+            calculate_maker_fee(ask_asset_info, commission_amount, fee_info.maker_fee_rate)
+        {
+            messages.push(f.clone().into_msg(&deps.querier, fee_address)?);
+            maker_fee_amount = f.amount;
+        }
+    }
+
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.pair_info.liquidity_token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
+        funds: vec![],
+    }));
+
+    // Accumulate prices for the pair assets
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
+        accumulate_prices(env.clone(), &config, pools[0].amount, pools[1].amount)?
+    {
+        store_observation(
+            deps.storage,
+            config.block_time_last,
+            block_time,
+            price0_cumulative_new.wrapping_sub(config.price0_cumulative_last),
+            price1_cumulative_new.wrapping_sub(config.price1_cumulative_last),
+        )?;
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+    }
+
+    // The share of the other asset stays in the pool
+    let mut reserves = [pools[0].amount, pools[1].amount];
+    reserves[ask_index] = reserves[ask_index].checked_sub(return_amount + maker_fee_amount)?;
+    if config.mint_maker_fee {
+        config.sqrt_k_last = compute_sqrt_k(&reserves);
+    }
+    CONFIG.save(deps.storage, &config)?;
+    RESERVES.save(deps.storage, &reserves)?;
+    record_price_change(deps.storage, &env, [pools[0].amount, pools[1].amount])?;
+    record_fee(
+        deps.storage,
+        env.block.time.seconds(),
+        &return_asset.info,
+        commission_amount,
+        maker_fee_amount,
+    )?;
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "withdraw_liquidity_one_asset"),
+        attr("sender", sender.as_str()),
+        attr("withdrawn_share", amount.to_string()),
+        attr("ask_asset", return_asset.info.to_string()),
+        attr("return_amount", return_amount.to_string()),
+        attr("commission_amount", commission_amount.to_string()),
+        attr("maker_fee_amount", maker_fee_amount.to_string()),
+        attr("maker_fee_share", maker_fee_share.to_string()),
+    ]))
+}
+
+/// ## Description
+/// Returns the amount of ask assets and the commission for burning LP tokens and withdrawing
+/// a single asset. The LP tokens are burned for both pool assets first and then the share of the
+/// other asset is swapped into the ask asset against the remaining pool.
+/// ## Params
+/// * **pools** is an array with two objects of type [`Asset`]. These are the current pool reserves.
+///
+/// * **amount** is an object of type [`Uint128`]. This is the amount of LP tokens to burn.
+///
+/// * **total_share** is an object of type [`Uint128`]. This is the total amount of LP tokens currently issued by the pool.
+///
+/// * **ask_index** is an object of type [`usize`]. This is the position of the ask asset in the pool.
+///
+/// * **fee_info** is an object of type [`FeeInfo`]. These are the fees charged by the pair.
+fn compute_withdraw_one_asset(
+    pools: &[Asset; 2],
+    amount: Uint128,
+    total_share: Uint128,
+    ask_index: usize,
+    fee_info: &FeeInfo,
+) -> StdResult<(Uint128, Uint128)> {
+    let refund_assets = get_share_in_assets(pools, amount, total_share);
+    let offer_index = 1 - ask_index;
+
+    let (swap_amount, _, commission_amount) = compute_swap(
+        pools[offer_index]
+            .amount
+            .checked_sub(refund_assets[offer_index].amount)?,
+        pools[ask_index]
+            .amount
+            .checked_sub(refund_assets[ask_index].amount)?,
+        refund_assets[offer_index].amount,
+        fee_info.total_fee_rate,
+    )?;
+
+    Ok((
+        refund_assets[ask_index].amount.checked_add(swap_amount)?,
+        commission_amount,
+    ))
+}
+
+/// ## Description
+/// Returns the deposit amounts of the given assets in the order of the pool assets.
+/// Returns a [`ContractError`] if an asset does not belong to the pool or if a deposit amount is zero.
+/// ## Params
+/// * **assets** is an array with two objects of type [`Asset`]. These are the assets to deposit.
+///
+/// * **pools** is an array with two objects of type [`Asset`]. These are the assets in the pool.
+fn get_deposits(assets: &[Asset; 2], pools: &[Asset; 2]) -> Result<[Uint128; 2], ContractError> {
+    let mut deposits = [Uint128::zero(); 2];
+    for (deposit, pool) in deposits.iter_mut().zip(pools.iter()) {
+        *deposit = assets
+            .iter()
+            .find(|a| a.info.equal(&pool.info))
+            .map(|a| a.amount)
+            .ok_or(ContractError::AssetMismatch {})?;
+    }
+
+    if deposits[0].is_zero() || deposits[1].is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    Ok(deposits)
+}
+
+/// ## Description
+/// Returns the amount of LP tokens that a deposit mints. The first deposit mints the square root
+/// of the product of the deposit amounts minus the [`MINIMUM_LIQUIDITY_AMOUNT`] that is locked in the pair.
+/// ## Params
+/// * **deposits** is an array with two objects of type [`Uint128`]. These are the deposit amounts in the order of the pool assets.
+///
+/// * **pools** is an array with two objects of type [`Asset`]. These are the assets in the pool.
+///
+/// * **total_share** is an object of type [`Uint128`]. This is the total amount of LP tokens including the Maker fee share.
+fn compute_share(
+    deposits: &[Uint128; 2],
+    pools: &[Asset; 2],
+    total_share: Uint128,
+) -> Result<Uint128, ContractError> {
+    if total_share.is_zero() {
+        // Initial share = collateral amount
+        let share = Uint128::new(
+            (U256::from(deposits[0].u128()) * U256::from(deposits[1].u128()))
+                .integer_sqrt()
+                .as_u128(),
+        );
+        if share <= MINIMUM_LIQUIDITY_AMOUNT {
+            return Err(ContractError::MinimumLiquidityAmountError {});
+        }
+
+        Ok(share - MINIMUM_LIQUIDITY_AMOUNT)
+    } else {
+        // min(1, 2)
+        // 1. sqrt(deposit_0 * exchange_rate_0_to_1 * deposit_0) * (total_share / sqrt(pool_0 * pool_1))
+        // == deposit_0 * total_share / pool_0
+        // 2. sqrt(deposit_1 * exchange_rate_1_to_0 * deposit_1) * (total_share / sqrt(pool_1 * pool_1))
+        // == deposit_1 * total_share / pool_1
+        Ok(std::cmp::min(
+            deposits[0].multiply_ratio(total_share, pools[0].amount),
+            deposits[1].multiply_ratio(total_share, pools[1].amount),
+        ))
+    }
+}
+
+/// ## Description
+/// Returns the amount of pool assets that correspond to an amount of LP tokens.
+/// ## Params
+/// * **pools** are an array of [`Asset`] type items. These are the assets in the pool.
+///
+/// * **amount** is an object of type [`Uint128`]. This is the amount of LP tokens to compute a corresponding amount of assets for.
+///
+/// * **total_share** is an object of type [`Uint128`]. This is the total amount of LP tokens currently minted.
 pub fn get_share_in_assets(
     pools: &[Asset; 2],
     amount: Uint128,
@@ -606,6 +1329,9 @@ pub fn get_share_in_assets(
 ///
 /// * **to** is an object of type [`Option<Addr>`]. Sets the recipient of the swap operation.
 /// NOTE - the address that wants to swap should approve the pair contract to pull the offer token.
+///
+/// * **callback** is an [`Option`] field of type [`Callback`]. This is the contract that receives
+/// the ask assets along with a message.
 #[allow(clippy::too_many_arguments)]
 pub fn swap(
     deps: DepsMut,
@@ -616,25 +1342,17 @@ pub fn swap(
     belief_price: Option<Decimal>,
     max_spread: Option<Decimal>,
     to: Option<Addr>,
+    callback: Option<Callback>,
 ) -> Result<Response, ContractError> {
+    if is_paused(deps.storage)? {
+        return Err(ContractError::Paused {});
+    }
+
     offer_asset.assert_sent_native_token_balance(&info)?;
 
     let mut config: Config = CONFIG.load(deps.storage)?;
 
-    // If the asset balance is already increased, we should subtract the user deposit from the pool amount
-    let pools: Vec<Asset> = config
-        .pair_info
-        .query_pools(&deps.querier, env.clone().contract.address)?
-        .iter()
-        .map(|p| {
-            let mut p = p.clone();
-            if p.info.equal(&offer_asset.info) {
-                p.amount = p.amount.checked_sub(offer_asset.amount).unwrap();
-            }
-
-            p
-        })
-        .collect();
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
 
     let offer_pool: Asset;
     let ask_pool: Asset;
@@ -651,11 +1369,7 @@ pub fn swap(
     }
 
     // Get fee info from the factory
-    let fee_info = query_fee_info(
-        &deps.querier,
-        config.factory_addr.clone(),
-        config.pair_info.pair_type.clone(),
-    )?;
+    let fee_info = query_fee_info(deps.as_ref(), &env, &config, &pools)?;
 
     let offer_amount = offer_asset.amount;
     
@@ -685,15 +1399,25 @@ pub fn swap(
     let tax_amount = return_asset.compute_tax(&deps.querier)?;
     
     // receiver is address receiving the new tokens (could be sender or to)
-    let receiver = to.unwrap_or_else(|| sender.clone());
+    assert_callback(deps.api, &callback, to.is_some())?;
+    let receiver = match &callback {
+        Some(callback) => Addr::unchecked(&callback.contract),
+        None => to.unwrap_or_else(|| sender.clone()),
+    };
     
     // transfer the tokens to receiver
-    let mut messages: Vec<CosmosMsg> =
-        vec![return_asset.into_msg(&deps.querier, receiver.clone())?];
+    let (mut messages, callback_messages): (Vec<CosmosMsg>, Vec<CosmosMsg>) = match callback {
+        // The callback contract gets the return assets after all the other messages
+        Some(callback) => (vec![], callback.into_msgs(&deps.querier, &[return_asset])?),
+        None => (
+            vec![return_asset.into_msg(&deps.querier, receiver.clone())?],
+            vec![],
+        ),
+    };
 
     // Compute the Maker fee
     let mut maker_fee_amount = Uint128::new(0);
-    if let Some(fee_address) = fee_info.fee_address {
+    if let Some(fee_address) = swap_fee_address(&config, &fee_info) {
         if let Some(f) = calculate_maker_fee(
             ask_pool.info.clone(),
             commission_amount,
@@ -706,14 +1430,44 @@ pub fn swap(
 
     // Accumulate prices for the assets in the pool
     if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
-        accumulate_prices(env, &config, pools[0].amount, pools[1].amount)?
+        accumulate_prices(env.clone(), &config, pools[0].amount, pools[1].amount)?
     {
+        store_observation(
+            deps.storage,
+            config.block_time_last,
+            block_time,
+            price0_cumulative_new.wrapping_sub(config.price0_cumulative_last),
+            price1_cumulative_new.wrapping_sub(config.price1_cumulative_last),
+        )?;
         config.price0_cumulative_last = price0_cumulative_new;
         config.price1_cumulative_last = price1_cumulative_new;
         config.block_time_last = block_time;
         CONFIG.save(deps.storage, &config)?;
     }
 
+    // The Maker fee leaves the pool along with the return amount
+    update_reserves_after_swap(
+        deps.storage,
+        &pools,
+        &offer_pool.info,
+        offer_amount,
+        return_amount + maker_fee_amount,
+    )?;
+    record_price_change(deps.storage, &env, [pools[0].amount, pools[1].amount])?;
+    record_swap(
+        deps.storage,
+        env.block.time.seconds(),
+        &offer_asset,
+        &Asset {
+            info: ask_pool.info.clone(),
+            amount: return_amount,
+        },
+        commission_amount,
+        maker_fee_amount,
+    )?;
+
+    messages.extend(callback_messages);
+
     Ok(Response::new()
         .add_messages(
             // 1. send collateral tokens from the contract to a user
@@ -734,74 +1488,1130 @@ pub fn swap(
 }
 
 /// ## Description
-/// Accumulate token prices for the assets in the pool.
-/// Note that this function shifts **block_time** when any of the token prices is zero in order to not
-/// fill an accumulator with a null price for that period.
+/// Performs a swap that returns an exact amount of ask assets. The required offer amount is computed
+/// the same way as in a reverse simulation and the unused part of the offered assets is refunded to the sender.
+/// Returns a [`ContractError`] on failure, otherwise returns the [`Response`] with the specified attributes if the operation was successful.
 /// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
 /// * **env** is an object of type [`Env`].
 ///
-/// * **config** is an object of type [`Config`].
+/// * **info** is an object of type [`MessageInfo`].
 ///
-/// * **x** is an object of type [`Uint128`]. This is the balance of asset\[\0] in the pool.
+/// * **sender** is an object of type [`Addr`]. This is the sender of the swap operation.
 ///
-/// * **y** is an object of type [`Uint128`]. This is the balance of asset\[\1] in the pool.
-pub fn accumulate_prices(
+/// * **offer_asset** is an object of type [`Asset`]. This is the asset to swap as well as the maximum amount of it to spend.
+///
+/// * **ask_asset** is an object of type [`Asset`]. This is the asset to receive as well as the exact amount to receive.
+///
+/// * **to** is an object of type [`Option<Addr>`]. Sets the recipient of the swap operation.
+pub fn swap_exact_out(
+    deps: DepsMut,
     env: Env,
-    config: &Config,
-    x: Uint128,
-    y: Uint128,
-) -> StdResult<Option<(Uint128, Uint128, u64)>> {
-    let block_time = env.block.time.seconds();
-    if block_time <= config.block_time_last {
-        return Ok(None);
+    info: MessageInfo,
+    sender: Addr,
+    offer_asset: Asset,
+    ask_asset: Asset,
+    to: Option<Addr>,
+) -> Result<Response, ContractError> {
+    if is_paused(deps.storage)? {
+        return Err(ContractError::Paused {});
     }
 
-    // We have to shift block_time when any price is zero in order to not fill an accumulator with a null price for that period
-    let time_elapsed = Uint128::from(block_time - config.block_time_last);
+    offer_asset.assert_sent_native_token_balance(&info)?;
 
-    let mut pcl0 = config.price0_cumulative_last;
-    let mut pcl1 = config.price1_cumulative_last;
+    if ask_asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
 
-    if !x.is_zero() && !y.is_zero() {
-        let price_precision = Uint128::from(10u128.pow(TWAP_PRECISION.into()));
-        pcl0 = config.price0_cumulative_last.wrapping_add(
-            time_elapsed
-                .checked_mul(price_precision)?
-                .multiply_ratio(y, x),
-        );
-        pcl1 = config.price1_cumulative_last.wrapping_add(
-            time_elapsed
-                .checked_mul(price_precision)?
-                .multiply_ratio(x, y),
-        );
+    let mut config: Config = CONFIG.load(deps.storage)?;
+
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
+
+    let offer_pool: Asset;
+    let ask_pool: Asset;
+
+    if offer_asset.info.equal(&pools[0].info) {
+        offer_pool = pools[0].clone();
+        ask_pool = pools[1].clone();
+    } else if offer_asset.info.equal(&pools[1].info) {
+        offer_pool = pools[1].clone();
+        ask_pool = pools[0].clone();
+    } else {
+        return Err(ContractError::AssetMismatch {});
+    }
+
+    if !ask_asset.info.equal(&ask_pool.info) {
+        return Err(ContractError::AssetMismatch {});
+    }
+
+    // The pool can't give away its whole ask balance
+    if ask_asset.amount >= ask_pool.amount {
+        return Err(ContractError::AskAmountExceedsPool {});
+    }
+
+    // Get fee info from the factory
+    let fee_info = query_fee_info(deps.as_ref(), &env, &config, &pools)?;
+
+    let (offer_amount, spread_amount, commission_amount) = compute_offer_amount(
+        offer_pool.amount,
+        ask_pool.amount,
+        ask_asset.amount,
+        fee_info.total_fee_rate,
+    )?;
+
+    if offer_amount > offer_asset.amount {
+        return Err(ContractError::MaxOfferAmountAssertion {});
+    }
+
+    let return_asset = Asset {
+        info: ask_pool.info.clone(),
+        amount: ask_asset.amount,
     };
 
-    Ok(Some((pcl0, pcl1, block_time)))
-}
+    let tax_amount = return_asset.compute_tax(&deps.querier)?;
 
-/// ## Description
-/// Calculates the amount of fees the Maker contract gets according to specified pair parameters.
-/// Returns a [`None`] if the Maker fee is zero, otherwise returns a [`Asset`] struct with the specified attributes.
-/// ## Params
-/// * **pool_info** is an object of type [`AssetInfo`]. Contains information about the pool asset for which the commission will be calculated.
-///
-/// * **commission_amount** is an object of type [`Env`]. This is the total amount of fees charged for a swap.
-///
-/// * **maker_commission_rate** is an object of type [`MessageInfo`]. This is the percentage of fees that go to the Maker contract.
-pub fn calculate_maker_fee(
-    pool_info: AssetInfo,
-    commission_amount: Uint128,
-    maker_commission_rate: Decimal,
-) -> Option<Asset> {
-    let maker_fee: Uint128 = commission_amount * maker_commission_rate;
-    if maker_fee.is_zero() {
-        return None;
+    let receiver = to.unwrap_or_else(|| sender.clone());
+
+    let mut messages: Vec<CosmosMsg> =
+        vec![return_asset.into_msg(&deps.querier, receiver.clone())?];
+
+    // Refund the unused offer assets
+    let refund_amount = offer_asset.amount.checked_sub(offer_amount)?;
+    if !refund_amount.is_zero() {
+        messages.push(
+            Asset {
+                info: offer_pool.info.clone(),
+                amount: refund_amount,
+            }
+            .into_msg(&deps.querier, sender.clone())?,
+        );
+    }
+
+    // Compute the Maker fee
+    let mut maker_fee_amount = Uint128::new(0);
+    if let Some(fee_address) = swap_fee_address(&config, &fee_info) {
+        if let Some(f) = calculate_maker_fee(
+            ask_pool.info.clone(),
+            commission_amount,
+            fee_info.maker_fee_rate,
+        ) {
+            messages.push(f.clone().into_msg(&deps.querier, fee_address)?);
+            maker_fee_amount = f.amount;
+        }
+    }
+
+    // Accumulate prices for the assets in the pool
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
+        accumulate_prices(env.clone(), &config, pools[0].amount, pools[1].amount)?
+    {
+        store_observation(
+            deps.storage,
+            config.block_time_last,
+            block_time,
+            price0_cumulative_new.wrapping_sub(config.price0_cumulative_last),
+            price1_cumulative_new.wrapping_sub(config.price1_cumulative_last),
+        )?;
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    update_reserves_after_swap(
+        deps.storage,
+        &pools,
+        &offer_pool.info,
+        offer_amount,
+        ask_asset.amount + maker_fee_amount,
+    )?;
+    record_price_change(deps.storage, &env, [pools[0].amount, pools[1].amount])?;
+    record_swap(
+        deps.storage,
+        env.block.time.seconds(),
+        &Asset {
+            info: offer_pool.info.clone(),
+            amount: offer_amount,
+        },
+        &ask_asset,
+        commission_amount,
+        maker_fee_amount,
+    )?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "swap_exact_out")
+        .add_attribute("sender", sender.as_str())
+        .add_attribute("receiver", receiver.as_str())
+        .add_attribute("offer_asset", offer_asset.info.to_string())
+        .add_attribute("ask_asset", ask_pool.info.to_string())
+        .add_attribute("offer_amount", offer_amount.to_string())
+        .add_attribute("refund_amount", refund_amount.to_string())
+        .add_attribute("return_amount", ask_asset.amount.to_string())
+        .add_attribute("tax_amount", tax_amount.to_string())
+        .add_attribute("spread_amount", spread_amount.to_string())
+        .add_attribute("commission_amount", commission_amount.to_string())
+        .add_attribute("maker_fee_amount", maker_fee_amount.to_string()))
+}
+
+/// ## Description
+/// Lends a pool asset to a callback contract and executes the callback contract with a [`FlashLoanCallbackMsg`].
+/// The loan is checked in [`repay_flash_loan`] once the callback contract returns.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified attributes
+/// if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **asset** is an object of type [`Asset`]. This is the asset and the amount to borrow.
+///
+/// * **callback_contract** is an object of type [`String`]. This is the contract that receives the borrowed assets.
+///
+/// * **msg** is an object of type [`Binary`]. This is the message forwarded to the callback contract.
+pub fn flash_loan(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    asset: Asset,
+    callback_contract: String,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    if is_paused(deps.storage)? {
+        return Err(ContractError::Paused {});
+    }
+
+    asset.info.check(deps.api)?;
+    if asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let callback_contract = addr_validate_to_lower(deps.api, &callback_contract)?;
+
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
+    let balances: [Asset; 2] = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address.clone())?;
+
+    if !pools.iter().any(|pool| pool.info.equal(&asset.info)) {
+        return Err(ContractError::AssetMismatch {});
+    }
+
+    // Get fee info from the factory
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
+
+    let fee_amount = compute_flash_loan_fee(asset.amount, fee_info.flash_loan_fee_rate);
+
+    // Accumulate prices for the assets in the pool
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
+        accumulate_prices(env, &config, pools[0].amount, pools[1].amount)?
+    {
+        store_observation(
+            deps.storage,
+            config.block_time_last,
+            block_time,
+            price0_cumulative_new.wrapping_sub(config.price0_cumulative_last),
+            price1_cumulative_new.wrapping_sub(config.price1_cumulative_last),
+        )?;
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    FLASH_LOAN.save(
+        deps.storage,
+        &FlashLoanState {
+            pools,
+            balances: [balances[0].amount, balances[1].amount],
+            asset: asset.clone(),
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_message(
+            asset
+                .clone()
+                .into_msg(&deps.querier, callback_contract.clone())?,
+        )
+        .add_submessage(SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: callback_contract.to_string(),
+                msg: to_binary(&FlashLoanCallbackMsg::FlashLoanCallback {
+                    sender: info.sender.to_string(),
+                    asset: asset.clone(),
+                    fee_amount,
+                    msg,
+                })?,
+                funds: vec![],
+            },
+            FLASH_LOAN_REPLY_ID,
+        ))
+        .add_attribute("action", "flash_loan")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("callback_contract", callback_contract.as_str())
+        .add_attribute("asset", asset.to_string())
+        .add_attribute("fee_amount", fee_amount.to_string()))
+}
+
+/// ## Description
+/// Sends the pair balances that exceed the pool reserves (e.g. tokens that were transferred to the pair directly)
+/// to the specified recipient. Returns a [`ContractError`] on failure, otherwise returns a [`Response`]
+/// with the specified attributes if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **to** is an object of type [`String`]. This is the recipient of the excess balances.
+pub fn skim(deps: DepsMut, env: Env, to: String) -> Result<Response, ContractError> {
+    let to = addr_validate_to_lower(deps.api, &to)?;
+
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
+    let balances: [Asset; 2] = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut skimmed: Vec<Asset> = vec![];
+    for (pool, balance) in pools.iter().zip(balances.iter()) {
+        let amount = balance.amount.checked_sub(pool.amount).unwrap_or_default();
+        if !amount.is_zero() {
+            let asset = Asset {
+                info: pool.info.clone(),
+                amount,
+            };
+            messages.push(asset.clone().into_msg(&deps.querier, to.clone())?);
+            skimmed.push(asset);
+        }
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "skim")
+        .add_attribute("to", to.as_str())
+        .add_attribute("assets", format_assets(&skimmed)))
+}
+
+/// ## Description
+/// Sets the pool reserves to the current pair balances. The cumulative prices are accumulated
+/// with the previous reserves first. Returns a [`ContractError`] on failure, otherwise returns a [`Response`]
+/// with the specified attributes if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+pub fn sync(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    if is_paused(deps.storage)? {
+        return Err(ContractError::Paused {});
+    }
+
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
+    let balances: [Asset; 2] = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address.clone())?;
+
+    // Accumulate prices for the assets in the pool
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
+        accumulate_prices(env.clone(), &config, pools[0].amount, pools[1].amount)?
+    {
+        store_observation(
+            deps.storage,
+            config.block_time_last,
+            block_time,
+            price0_cumulative_new.wrapping_sub(config.price0_cumulative_last),
+            price1_cumulative_new.wrapping_sub(config.price1_cumulative_last),
+        )?;
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    RESERVES.save(deps.storage, &[balances[0].amount, balances[1].amount])?;
+    record_price_change(deps.storage, &env, [pools[0].amount, pools[1].amount])?;
+
+    Ok(Response::new()
+        .add_attribute("action", "sync")
+        .add_attribute("reserves", format_assets(&balances)))
+}
+
+/// ## Description
+/// Checks that a flash loan was repaid after the callback contract was executed and sends the Maker fee.
+/// The fee is charged on every asset that came back to the pool, so the loan may also be repaid
+/// in the other pool asset (a flash swap) as long as the pool invariant without fees doesn't decrease.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified attributes
+/// if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+pub fn repay_flash_loan(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let flash_loan = FLASH_LOAN.load(deps.storage)?;
+    FLASH_LOAN.remove(deps.storage);
+
+    let pools: [Asset; 2] = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address.clone())?;
+
+    // Get fee info from the factory
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
+
+    let mut fees: Vec<Asset> = vec![];
+    let mut reserves = [Uint128::zero(); 2];
+    let mut balances = [Uint128::zero(); 2];
+    for (i, pool) in pools.iter().enumerate() {
+        let mut reserve = flash_loan.pools[i].amount;
+        let mut balance_after_loan = flash_loan.balances[i];
+        if pool.info.equal(&flash_loan.asset.info) {
+            reserve = reserve.checked_sub(flash_loan.asset.amount)?;
+            balance_after_loan = balance_after_loan.checked_sub(flash_loan.asset.amount)?;
+        }
+
+        // The flash loan fee is charged on everything that was sent back to the pool
+        let amount_in = pool
+            .amount
+            .checked_sub(balance_after_loan)
+            .unwrap_or_default();
+        let fee_amount = mul_ceil(amount_in, fee_info.flash_loan_fee_rate);
+
+        reserves[i] = reserve.checked_add(amount_in)?;
+        balances[i] = reserves[i].checked_sub(fee_amount)?;
+        if !fee_amount.is_zero() {
+            fees.push(Asset {
+                info: pool.info.clone(),
+                amount: fee_amount,
+            });
+        }
+    }
+
+    if U256::from(balances[0].u128()) * U256::from(balances[1].u128())
+        < U256::from(flash_loan.pools[0].amount.u128())
+            * U256::from(flash_loan.pools[1].amount.u128())
+    {
+        return Err(ContractError::FlashLoanNotRepaid {});
+    }
+
+    // Maker fee
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut maker_fees: Vec<Asset> = vec![];
+    if let Some(fee_address) = swap_fee_address(&config, &fee_info) {
+        for fee in fees.iter() {
+            if let Some(f) =
+                calculate_maker_fee(fee.info.clone(), fee.amount, fee_info.maker_fee_rate)
+            {
+                messages.push(f.clone().into_msg(&deps.querier, fee_address.clone())?);
+                maker_fees.push(f);
+            }
+        }
+    }
+
+    // The Maker fee leaves the pool while the rest of the flash loan fee stays with the LPs
+    for (reserve, pool) in reserves.iter_mut().zip(pools.iter()) {
+        for maker_fee in maker_fees.iter() {
+            if maker_fee.info.equal(&pool.info) {
+                *reserve = reserve.checked_sub(maker_fee.amount)?;
+            }
+        }
+    }
+    RESERVES.save(deps.storage, &reserves)?;
+    record_price_change(
+        deps.storage,
+        &env,
+        [flash_loan.pools[0].amount, flash_loan.pools[1].amount],
+    )?;
+    for fee in fees.iter() {
+        let maker_fee_amount = maker_fees
+            .iter()
+            .find(|maker_fee| maker_fee.info.equal(&fee.info))
+            .map(|maker_fee| maker_fee.amount)
+            .unwrap_or_default();
+        record_fee(
+            deps.storage,
+            env.block.time.seconds(),
+            &fee.info,
+            fee.amount,
+            maker_fee_amount,
+        )?;
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "repay_flash_loan")
+        .add_attribute("asset", flash_loan.asset.to_string())
+        .add_attribute("fees", format_assets(&fees))
+        .add_attribute("maker_fees", format_assets(&maker_fees)))
+}
+
+/// ## Description
+/// Returns the fee that has to be paid on top of a flash loan of `amount` tokens if it is repaid in the borrowed asset.
+/// The fee is charged on the whole repaid amount, so the result is `amount / (1 - flash_loan_fee_rate) - amount` rounded up.
+/// ## Params
+/// * **amount** is an object of type [`Uint128`]. This is the amount of tokens to borrow.
+///
+/// * **flash_loan_fee_rate** is an object of type [`Decimal`]. This is the flash loan fee rate.
+fn compute_flash_loan_fee(amount: Uint128, flash_loan_fee_rate: Decimal) -> Uint128 {
+    let denominator = U256::from(flash_loan_fee_rate.denominator());
+    let remainder = denominator - U256::from(flash_loan_fee_rate.numerator());
+    let repay_amount =
+        (U256::from(amount.u128()) * denominator + remainder - U256::one()) / remainder;
+
+    Uint128::new(repay_amount.as_u128()) - amount
+}
+
+/// ## Description
+/// Returns `amount * rate` rounded up.
+/// ## Params
+/// * **amount** is an object of type [`Uint128`].
+///
+/// * **rate** is an object of type [`Decimal`].
+fn mul_ceil(amount: Uint128, rate: Decimal) -> Uint128 {
+    let denominator = U256::from(rate.denominator());
+    let result = (U256::from(amount.u128()) * U256::from(rate.numerator()) + denominator
+        - U256::one())
+        / denominator;
+
+    Uint128::new(result.as_u128())
+}
+
+/// ## Description
+/// Returns a comma separated list of assets to be used in attributes.
+/// ## Params
+/// * **assets** is a slice of [`Asset`] objects.
+fn format_assets(assets: &[Asset]) -> String {
+    assets
+        .iter()
+        .map(|asset| asset.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// ## Description
+/// Returns the information about the pool asset that has to be offered in order to receive `ask_asset_info`.
+/// Returns a [`ContractError`] if `ask_asset_info` does not belong to the pair.
+/// ## Params
+/// * **config** is an object of type [`Config`].
+///
+/// * **ask_asset_info** is an object of type [`AssetInfo`]. This is the asset to swap to.
+fn get_offer_asset_info(
+    config: &Config,
+    ask_asset_info: &AssetInfo,
+) -> Result<AssetInfo, ContractError> {
+    let asset_infos = &config.pair_info.asset_infos;
+    if ask_asset_info.equal(&asset_infos[0]) {
+        Ok(asset_infos[1].clone())
+    } else if ask_asset_info.equal(&asset_infos[1]) {
+        Ok(asset_infos[0].clone())
+    } else {
+        Err(ContractError::AssetMismatch {})
+    }
+}
+
+/// ## Description
+/// Accumulate token prices for the assets in the pool.
+/// Note that this function shifts **block_time** when any of the token prices is zero in order to not
+/// fill an accumulator with a null price for that period.
+/// ## Params
+/// * **env** is an object of type [`Env`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **x** is an object of type [`Uint128`]. This is the balance of asset\[\0] in the pool.
+///
+/// * **y** is an object of type [`Uint128`]. This is the balance of asset\[\1] in the pool.
+pub fn accumulate_prices(
+    env: Env,
+    config: &Config,
+    x: Uint128,
+    y: Uint128,
+) -> StdResult<Option<(Uint128, Uint128, u64)>> {
+    let block_time = env.block.time.seconds();
+    if block_time <= config.block_time_last {
+        return Ok(None);
+    }
+
+    // We have to shift block_time when any price is zero in order to not fill an accumulator with a null price for that period
+    let time_elapsed = block_time - config.block_time_last;
+
+    let mut pcl0 = config.price0_cumulative_last;
+    let mut pcl1 = config.price1_cumulative_last;
+
+    // The accumulators wrap around on overflow like in Uniswap v2
+    if !x.is_zero() && !y.is_zero() {
+        pcl0 = config
+            .price0_cumulative_last
+            .wrapping_add(price_increment(time_elapsed, y, x));
+        pcl1 = config
+            .price1_cumulative_last
+            .wrapping_add(price_increment(time_elapsed, x, y));
+    };
+
+    Ok(Some((pcl0, pcl1, block_time)))
+}
+
+/// ## Description
+/// Returns the amount a cumulative price grows by over `time_elapsed` seconds at the price
+/// `numerator / denominator` scaled by [`TWAP_PRECISION`]. The amount is computed with 256 bits and
+/// truncated to its lower 128 bits, so an overflowing accumulator wraps around instead of failing the operation.
+/// Consumers take the difference between two accumulator values with a wrapping subtraction, which is correct
+/// as long as the accumulator wraps at most once between them.
+/// ## Params
+/// * **time_elapsed** is an object of type [`u64`]. This is the amount of seconds since the last accumulation.
+///
+/// * **numerator** is an object of type [`Uint128`]. This is the pool balance of the asset the price is quoted in.
+///
+/// * **denominator** is an object of type [`Uint128`]. This is the pool balance of the priced asset.
+pub fn price_increment(time_elapsed: u64, numerator: Uint128, denominator: Uint128) -> Uint128 {
+    let price_precision = U256::from(10u128.pow(TWAP_PRECISION.into()));
+    let increment = U256::from(time_elapsed) * price_precision * U256::from(numerator.u128())
+        / U256::from(denominator.u128());
+
+    Uint128::new(increment.low_u128())
+}
+
+/// ## Description
+/// Returns the fee information for the pair. If the dynamic fee mode is enabled, the total fee rate
+/// is computed with [`compute_dynamic_fee_rate`] instead of being taken from the factory.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **pools** is an array with two objects of type [`Asset`]. These are the current pool reserves.
+pub fn query_fee_info(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    pools: &[Asset; 2],
+) -> StdResult<FeeInfo> {
+    let mut fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
+
+    if let Some(params) = &config.dynamic_fee {
+        fee_info.total_fee_rate = compute_dynamic_fee_rate(deps, env, config, pools, params)?;
+    }
+
+    Ok(fee_info)
+}
+
+/// ## Description
+/// Computes the total fee rate of the dynamic fee mode. The fee scales linearly from the floor to the ceiling
+/// as the current pool price moves away from the time-weighted average price over the dynamic fee window.
+/// If the stored price history is shorter than the window, the average is taken over the whole history.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **pools** is an array with two objects of type [`Asset`]. These are the current pool reserves.
+///
+/// * **params** is an object of type [`DynamicFeeParams`].
+pub fn compute_dynamic_fee_rate(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    pools: &[Asset; 2],
+    params: &DynamicFeeParams,
+) -> StdResult<Decimal> {
+    let min_fee_rate = Decimal::from_ratio(params.min_fee_bps, 10000u16);
+    let max_fee_rate = Decimal::from_ratio(params.max_fee_bps, 10000u16);
+
+    if pools[0].amount.is_zero() || pools[1].amount.is_zero() {
+        return Ok(min_fee_rate);
+    }
+
+    let block_time = env.block.time.seconds();
+    let history_start = oldest_observation(deps.storage)?
+        .map(|observation| observation.timestamp)
+        .unwrap_or(config.block_time_last);
+    let window_start = block_time.saturating_sub(params.window).max(history_start);
+
+    // There is no price history to compare the current price with
+    if config.block_time_last == 0 || window_start >= block_time {
+        return Ok(min_fee_rate);
+    }
+
+    let mut price0_increment = Uint128::zero();
+    if let Some((price0_cumulative_new, _, _)) =
+        accumulate_prices(env.clone(), config, pools[0].amount, pools[1].amount)?
+    {
+        price0_increment = price0_cumulative_new.wrapping_sub(config.price0_cumulative_last);
+    }
+
+    let observations = observe(
+        deps.storage,
+        config.block_time_last,
+        block_time,
+        price0_increment,
+        Uint128::zero(),
+        &[block_time - window_start, 0],
+    )?
+    .observations;
+
+    let price0_cumulative_diff =
+        Uint128::try_from(observations[1].price0_cumulative - observations[0].price0_cumulative)
+            .map_err(|source| StdError::ConversionOverflow { source })?;
+
+    // Compare the prices with the precision of the cumulative prices
+    let price_precision = Uint128::from(10u128.pow(TWAP_PRECISION.into()));
+    let average_price =
+        price0_cumulative_diff.checked_div(Uint128::from(block_time - window_start))?;
+    if average_price.is_zero() {
+        return Ok(min_fee_rate);
+    }
+
+    let current_price = pools[1]
+        .amount
+        .multiply_ratio(price_precision, pools[0].amount);
+    let price_movement = if current_price > average_price {
+        current_price - average_price
+    } else {
+        average_price - current_price
+    };
+
+    let max_price_movement = average_price.multiply_ratio(params.max_volatility_bps, 10000u16);
+    if price_movement >= max_price_movement {
+        return Ok(max_fee_rate);
+    }
+
+    let fee_rate_increase = Uint128::from((max_fee_rate - min_fee_rate).numerator())
+        .multiply_ratio(price_movement, max_price_movement);
+
+    Ok(min_fee_rate + Decimal::from_ratio(fee_rate_increase, Decimal::one().denominator()))
+}
+
+/// ## Description
+/// Updates the pool configuration with the specified parameters in the `params` variable.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified attributes.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **params** is an object of type [`Binary`]. These are the the new parameter values.
+///
+/// ## Executor
+/// Only the factory owner can execute this.
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    params: Binary,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, config.factory_addr.clone())?;
+
+    if info.sender != factory_config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    match from_binary::<XykPoolUpdateParams>(&params)? {
+        XykPoolUpdateParams::EnableDynamicFee { params } => {
+            if params.min_fee_bps > params.max_fee_bps
+                || params.max_fee_bps > 10_000
+                || params.window == 0
+                || params.max_volatility_bps == 0
+            {
+                return Err(ContractError::IncorrectDynamicFeeParams {});
+            }
+            config.dynamic_fee = Some(params);
+        }
+        XykPoolUpdateParams::DisableDynamicFee {} => config.dynamic_fee = None,
+        XykPoolUpdateParams::EnableMakerFeeMinting {} => {
+            if !config.mint_maker_fee {
+                config.mint_maker_fee = true;
+                config.sqrt_k_last = compute_sqrt_k(&RESERVES.load(deps.storage)?);
+            }
+        }
+        XykPoolUpdateParams::DisableMakerFeeMinting {} => {
+            // Mint the Maker fee accrued so far, the next swaps send it to the fee address again
+            let (pools, total_share) = pool_info(deps.as_ref(), config.clone())?;
+            messages.extend(mint_maker_fee_message(deps.as_ref(), &config, &pools, total_share)?.0);
+            config.mint_maker_fee = false;
+            config.sqrt_k_last = Uint128::zero();
+        }
+    }
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "update_config"))
+}
+
+/// ## Description
+/// Stops swaps and liquidity provision in the pair. Withdrawals stay open.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified attributes.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// ## Executor
+/// Only the factory owner or the guardian can execute this.
+pub fn pause(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, config.factory_addr)?;
+
+    if info.sender != factory_config.owner && Some(info.sender) != guardian(deps.storage)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    set_paused(deps.storage, true)?;
+
+    Ok(Response::new().add_attribute("action", "pause"))
+}
+
+/// ## Description
+/// Resumes swaps and liquidity provision in the pair after a pause.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified attributes.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// ## Executor
+/// Only the factory owner can execute this.
+pub fn unpause(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, config.factory_addr)?;
+
+    if info.sender != factory_config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    set_paused(deps.storage, false)?;
+
+    Ok(Response::new().add_attribute("action", "unpause"))
+}
+
+/// ## Description
+/// Updates the guardian and the maximum price change of the pair.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified attributes.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **guardian** is an [`Option`] field of type [`String`]. This is the address that can pause the pair.
+///
+/// * **max_price_change_bps** is an [`Option`] field of type [`u16`]. This is the maximum spot price change
+/// within a single block. Operations that exceed it fail.
+///
+/// ## Executor
+/// Only the factory owner can execute this.
+pub fn update_circuit_breaker(
+    deps: DepsMut,
+    info: MessageInfo,
+    guardian: Option<String>,
+    max_price_change_bps: Option<u16>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, config.factory_addr)?;
+
+    if info.sender != factory_config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let guardian = guardian
+        .map(|guardian| addr_validate_to_lower(deps.api, &guardian))
+        .transpose()?;
+    circuit_breaker::update_circuit_breaker(deps.storage, guardian, max_price_change_bps)?;
+
+    Ok(Response::new().add_attribute("action", "update_circuit_breaker"))
+}
+
+/// ## Description
+/// Claims the rewards of the reward-bearing pool asset on behalf of the caller and sends them to the receiver.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the claim messages.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **receiver** is an [`Option`] field of type [`String`]. This is the address that receives the rewards.
+pub fn claim_reward(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    receiver: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let reward_config = load_reward_bearing_config(deps.storage)?;
+
+    Ok(reward_bearing::claim_reward(
+        deps.as_ref(),
+        &env,
+        info,
+        &REWARD_INDEXES,
+        &reward_config,
+        &config.pair_info.liquidity_token,
+        receiver,
+    )?)
+}
+
+/// ## Description
+/// Claims the rewards of the reward-bearing pool asset on behalf of a user that staked LP tokens in the Generator.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the claim messages.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **user** is an object of type [`String`]. This is the user for which to claim rewards.
+///
+/// * **user_share** is an object of type [`Uint128`]. This is the amount of LP tokens the user has staked.
+///
+/// * **total_share** is an object of type [`Uint128`]. This is the total amount of staked LP tokens.
+///
+/// ## Executor
+/// Only the Generator can execute this.
+pub fn claim_reward_by_generator(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    user: String,
+    user_share: Uint128,
+    total_share: Uint128,
+) -> Result<Response, ContractError> {
+    let reward_config = load_reward_bearing_config(deps.storage)?;
+
+    Ok(reward_bearing::claim_reward_by_generator(
+        deps.as_ref(),
+        &env,
+        info,
+        &REWARD_INDEXES,
+        &reward_config,
+        user,
+        user_share,
+        total_share,
+    )?)
+}
+
+/// ## Description
+/// Distributes the rewards claimed for a user. Returns a [`ContractError`] on failure,
+/// otherwise returns a [`Response`] with the specified attributes.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **previous_reward_balance** is an object of type [`Uint128`]. This is the reward holder balance
+/// before the rewards were claimed.
+///
+/// * **user** is an object of type [`Addr`]. This is the address for which rewards are distributed.
+///
+/// * **user_share** is an object of type [`Uint128`]. This is the amount of LP tokens the user has staked.
+///
+/// * **total_share** is an object of type [`Uint128`]. This is the total amount of staked LP tokens.
+///
+/// * **receiver** is an [`Option`] field of type [`Addr`]. This is the address that receives the rewards.
+///
+/// ## Executor
+/// Only the pair itself can execute this.
+#[allow(clippy::too_many_arguments)]
+pub fn handle_reward(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    previous_reward_balance: Uint128,
+    user: Addr,
+    user_share: Uint128,
+    total_share: Uint128,
+    receiver: Option<Addr>,
+) -> Result<Response, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let reward_config = load_reward_bearing_config(deps.storage)?;
+
+    Ok(reward_bearing::handle_reward(
+        deps,
+        &REWARD_INDEXES,
+        &reward_config,
+        previous_reward_balance,
+        user,
+        user_share,
+        total_share,
+        receiver,
+    )?)
+}
+
+/// ## Description
+/// Passes the spot price change caused by an operation to the circuit breaker of the pair.
+/// Returns a [`ContractError`] if the price moved by more than the pair allows within a block.
+/// The pool reserves must already be updated.
+/// ## Params
+/// * **storage** is an object of type [`Storage`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **reserves_before** is an array with two objects of type [`Uint128`].
+/// These are the pool reserves before the operation.
+fn record_price_change(
+    storage: &mut dyn Storage,
+    env: &Env,
+    reserves_before: [Uint128; 2],
+) -> Result<(), ContractError> {
+    let reserves_after = RESERVES.load(storage)?;
+    if !track_price_change(
+        storage,
+        env.block.height,
+        spot_price(reserves_before),
+        spot_price(reserves_after),
+    )? {
+        return Err(ContractError::MaxPriceChangeAssertion {});
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Returns the price of asset 0 in asset 1 with [`TWAP_PRECISION`] decimals or zero for an empty pool.
+/// ## Params
+/// * **reserves** is an array with two objects of type [`Uint128`]. These are the pool reserves.
+fn spot_price(reserves: [Uint128; 2]) -> Uint128 {
+    if reserves[0].is_zero() {
+        return Uint128::zero();
+    }
+
+    reserves[1].multiply_ratio(10u128.pow(TWAP_PRECISION.into()), reserves[0])
+}
+
+/// ## Description
+/// Calculates the amount of fees the Maker contract gets according to specified pair parameters.
+/// Returns a [`None`] if the Maker fee is zero, otherwise returns a [`Asset`] struct with the specified attributes.
+/// ## Params
+/// * **pool_info** is an object of type [`AssetInfo`]. Contains information about the pool asset for which the commission will be calculated.
+///
+/// * **commission_amount** is an object of type [`Env`]. This is the total amount of fees charged for a swap.
+///
+/// * **maker_commission_rate** is an object of type [`MessageInfo`]. This is the percentage of fees that go to the Maker contract.
+pub fn calculate_maker_fee(
+    pool_info: AssetInfo,
+    commission_amount: Uint128,
+    maker_commission_rate: Decimal,
+) -> Option<Asset> {
+    let maker_fee: Uint128 = commission_amount * maker_commission_rate;
+    if maker_fee.is_zero() {
+        return None;
+    }
+
+    Some(Asset {
+        info: pool_info,
+        amount: maker_fee,
+    })
+}
+
+/// ## Description
+/// Returns the address that receives the Maker fee on every swap. Returns [`None`] if the factory
+/// has no fee address or if the Maker fee stays in the pool to be minted as LP tokens on liquidity events.
+/// ## Params
+/// * **config** is an object of type [`Config`].
+///
+/// * **fee_info** is an object of type [`FeeInfo`]. These are the fees charged by the pair.
+fn swap_fee_address(config: &Config, fee_info: &FeeInfo) -> Option<Addr> {
+    if config.mint_maker_fee {
+        return None;
+    }
+
+    fee_info.fee_address.clone()
+}
+
+/// ## Description
+/// Returns the square root of the product of the pool reserves.
+/// ## Params
+/// * **reserves** is an array with two objects of type [`Uint128`]. These are the pool reserves.
+fn compute_sqrt_k(reserves: &[Uint128; 2]) -> Uint128 {
+    Uint128::new(
+        (U256::from(reserves[0].u128()) * U256::from(reserves[1].u128()))
+            .integer_sqrt()
+            .as_u128(),
+    )
+}
+
+/// ## Description
+/// Returns the message that mints the Maker share of the growth in `sqrt(k)` since the last liquidity
+/// event as LP tokens to the fee address, along with the amount of LP tokens to mint.
+/// The minted share `s` satisfies `s / (total_share + s) = maker_fee_rate * (sqrt(k) - sqrt(k_last)) / sqrt(k)`,
+/// so the Maker gets the same part of the swap fees as when the fees are sent on every swap.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **pools** is an array with two objects of type [`Asset`]. These are the current pool reserves.
+///
+/// * **total_share** is an object of type [`Uint128`]. This is the total amount of LP tokens currently issued by the pool.
+fn mint_maker_fee_message(
+    deps: Deps,
+    config: &Config,
+    pools: &[Asset; 2],
+    total_share: Uint128,
+) -> StdResult<(Option<CosmosMsg>, Uint128)> {
+    if !config.mint_maker_fee || config.sqrt_k_last.is_zero() || total_share.is_zero() {
+        return Ok((None, Uint128::zero()));
     }
 
-    Some(Asset {
-        info: pool_info,
-        amount: maker_fee,
-    })
+    let sqrt_k = compute_sqrt_k(&[pools[0].amount, pools[1].amount]);
+    if sqrt_k <= config.sqrt_k_last {
+        return Ok((None, Uint128::zero()));
+    }
+
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
+    let fee_address = match fee_info.fee_address {
+        Some(fee_address) => fee_address,
+        None => return Ok((None, Uint128::zero())),
+    };
+
+    let maker_growth = (sqrt_k - config.sqrt_k_last) * fee_info.maker_fee_rate;
+    let share = total_share.multiply_ratio(maker_growth, sqrt_k - maker_growth);
+    if share.is_zero() {
+        return Ok((None, Uint128::zero()));
+    }
+
+    let msg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.pair_info.liquidity_token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Mint {
+            recipient: fee_address.to_string(),
+            amount: share,
+        })?,
+        funds: vec![],
+    });
+
+    Ok((Some(msg), share))
 }
 
 /// ## Description
@@ -830,19 +2640,87 @@ pub fn calculate_maker_fee(
 /// * **QueryMsg::CumulativePrices {}** Returns information about cumulative prices for the assets in the
 /// pool using a [`CumulativePricesResponse`] object.
 ///
+/// * **QueryMsg::Observe { seconds_ago }** Returns the cumulative prices of the pool at the specified
+/// points in time using an [`ObserveResponse`] object.
+///
 /// * **QueryMsg::Config {}** Returns the configuration for the pair contract using a [`ConfigResponse`] object.
+///
+/// * **QueryMsg::Status {}** Returns the circuit breaker status of the pair using a [`StatusResponse`] object.
+///
+/// * **QueryMsg::SimulateWithdrawOneAsset { lp_amount, ask_asset_info }** Returns the result of a single asset
+/// withdrawal simulation using a [`WithdrawOneAssetSimulationResponse`] object.
+///
+/// * **QueryMsg::SimulateProvide { assets }** Returns the amount of LP tokens minted for a deposit
+/// using a [`SimulateProvideResponse`] object.
+///
+/// * **QueryMsg::SimulateWithdraw { lp_amount, ask_asset_info }** Returns the assets received for burning
+/// LP tokens using a [`SimulateWithdrawResponse`] object.
+///
+/// * **QueryMsg::SpotPrice { offer_asset_info }** Returns the marginal price of the offer asset using
+/// a [`SpotPriceResponse`] object.
+///
+/// * **QueryMsg::PriceImpact { offer_asset }** Returns the execution price, the spot price and the price
+/// impact of a swap using a [`PriceImpactResponse`] object.
+///
+/// * **QueryMsg::VirtualPrice {}** and **QueryMsg::PoolInvariant {}** are supported only by stableswap pairs.
+///
+/// * **QueryMsg::PendingReward { user }** Returns the rewards of the reward-bearing pool asset that
+/// the user can claim using an [`Asset`] object.
+///
+/// * **QueryMsg::Stats {}** Returns the cumulative swap volume and commissions of the pair using
+/// a [`astroport::stats::StatsResponse`] object.
+///
+/// * **QueryMsg::DailyStats { start_after, limit }** Returns the swap volume and commissions of the pair
+/// by day using a [`astroport::stats::DailyStatsResponse`] object.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Pair {} => to_binary(&query_pair_info(deps)?),
         QueryMsg::Pool {} => to_binary(&query_pool(deps)?),
         QueryMsg::Share { amount } => to_binary(&query_share(deps, amount)?),
-        QueryMsg::Simulation { offer_asset } => to_binary(&query_simulation(deps, offer_asset)?),
+        QueryMsg::Simulation { offer_asset } => {
+            to_binary(&query_simulation(deps, env.clone(), offer_asset)?)
+        }
         QueryMsg::ReverseSimulation { ask_asset } => {
-            to_binary(&query_reverse_simulation(deps, ask_asset)?)
+            to_binary(&query_reverse_simulation(deps, env.clone(), ask_asset)?)
         }
-        QueryMsg::CumulativePrices {} => to_binary(&query_cumulative_prices(deps, env)?),
+        QueryMsg::CumulativePrices {} => to_binary(&query_cumulative_prices(deps, env.clone())?),
+        QueryMsg::Observe { seconds_ago } => to_binary(&query_observe(deps, env, seconds_ago)?),
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::Status {} => to_binary(&query_status(deps.storage)?),
+        QueryMsg::SimulateWithdrawOneAsset {
+            lp_amount,
+            ask_asset_info,
+        } => to_binary(&query_simulate_withdraw_one_asset(
+            deps,
+            env,
+            lp_amount,
+            ask_asset_info,
+        )?),
+        QueryMsg::SimulateProvide { assets } => to_binary(&query_simulate_provide(deps, assets)?),
+        QueryMsg::SimulateWithdraw {
+            lp_amount,
+            ask_asset_info,
+        } => to_binary(&query_simulate_withdraw(
+            deps,
+            env,
+            lp_amount,
+            ask_asset_info,
+        )?),
+        QueryMsg::SpotPrice { offer_asset_info } => {
+            to_binary(&query_spot_price(deps, offer_asset_info)?)
+        }
+        QueryMsg::PriceImpact { offer_asset } => {
+            to_binary(&query_price_impact(deps, env, offer_asset)?)
+        }
+        QueryMsg::VirtualPrice {} | QueryMsg::PoolInvariant {} => Err(StdError::generic_err(
+            "The query is supported only by stableswap pairs",
+        )),
+        QueryMsg::PendingReward { user } => to_binary(&query_pending_reward(deps, user)?),
+        QueryMsg::Stats {} => to_binary(&query_stats(deps.storage)?),
+        QueryMsg::DailyStats { start_after, limit } => {
+            to_binary(&query_daily_stats(deps.storage, start_after, limit)?)
+        }
     }
 }
 
@@ -892,12 +2770,12 @@ pub fn query_share(deps: Deps, amount: Uint128) -> StdResult<Vec<Asset>> {
 /// ## Params
 /// * **deps** is an object of type [`Deps`].
 ///
+/// * **env** is an object of type [`Env`].
+///
 /// * **offer_asset** is an object of type [`Asset`]. This is the asset to swap as well as an amount of the said asset.
-pub fn query_simulation(deps: Deps, offer_asset: Asset) -> StdResult<SimulationResponse> {
+pub fn query_simulation(deps: Deps, env: Env, offer_asset: Asset) -> StdResult<SimulationResponse> {
     let config: Config = CONFIG.load(deps.storage)?;
-    let contract_addr = config.pair_info.contract_addr.clone();
-
-    let pools: [Asset; 2] = config.pair_info.query_pools(&deps.querier, contract_addr)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
 
     let offer_pool: Asset;
     let ask_pool: Asset;
@@ -914,11 +2792,7 @@ pub fn query_simulation(deps: Deps, offer_asset: Asset) -> StdResult<SimulationR
     }
 
     // Get fee info from the factory contract
-    let fee_info = query_fee_info(
-        &deps.querier,
-        config.factory_addr,
-        config.pair_info.pair_type,
-    )?;
+    let fee_info = query_fee_info(deps, &env, &config, &pools)?;
 
     let (return_amount, spread_amount, commission_amount) = compute_swap(
         offer_pool.amount,
@@ -931,6 +2805,84 @@ pub fn query_simulation(deps: Deps, offer_asset: Asset) -> StdResult<SimulationR
         return_amount,
         spread_amount,
         commission_amount,
+        total_fee_rate: fee_info.total_fee_rate,
+    })
+}
+
+/// ## Description
+/// Returns the marginal price of the offer asset in the other pool asset in a [`SpotPriceResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **offer_asset_info** is an object of type [`AssetInfo`]. This is the asset to price.
+pub fn query_spot_price(deps: Deps, offer_asset_info: AssetInfo) -> StdResult<SpotPriceResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
+
+    let (offer_pool, ask_pool) = if offer_asset_info.equal(&pools[0].info) {
+        (&pools[0], &pools[1])
+    } else if offer_asset_info.equal(&pools[1].info) {
+        (&pools[1], &pools[0])
+    } else {
+        return Err(StdError::generic_err(
+            "Given offer asset does not belong in the pair",
+        ));
+    };
+
+    Ok(SpotPriceResponse {
+        price: compute_spot_price(offer_pool.amount, ask_pool.amount)?,
+    })
+}
+
+/// ## Description
+/// Returns the execution price, the spot price and the price impact of a swap in a [`PriceImpactResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **offer_asset** is an object of type [`Asset`]. This is the asset to swap as well as an amount of the said asset.
+pub fn query_price_impact(
+    deps: Deps,
+    env: Env,
+    offer_asset: Asset,
+) -> StdResult<PriceImpactResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
+
+    let (offer_pool, ask_pool) = if offer_asset.info.equal(&pools[0].info) {
+        (&pools[0], &pools[1])
+    } else if offer_asset.info.equal(&pools[1].info) {
+        (&pools[1], &pools[0])
+    } else {
+        return Err(StdError::generic_err(
+            "Given offer asset does not belong in the pair",
+        ));
+    };
+    if offer_asset.amount.is_zero() {
+        return Err(StdError::generic_err(
+            ContractError::InvalidZeroAmount {}.to_string(),
+        ));
+    }
+
+    let fee_info = query_fee_info(deps, &env, &config, &pools)?;
+    let spot_price = compute_spot_price(offer_pool.amount, ask_pool.amount)?;
+
+    let (return_amount, _, commission_amount) = compute_swap(
+        offer_pool.amount,
+        ask_pool.amount,
+        offer_asset.amount,
+        fee_info.total_fee_rate,
+    )?;
+
+    Ok(PriceImpactResponse {
+        execution_price: Decimal::from_ratio(return_amount, offer_asset.amount),
+        spot_price,
+        price_impact: compute_price_impact(
+            offer_asset.amount,
+            return_amount + commission_amount,
+            spot_price,
+        ),
     })
 }
 
@@ -939,16 +2891,17 @@ pub fn query_simulation(deps: Deps, offer_asset: Asset) -> StdResult<SimulationR
 /// ## Params
 /// * **deps** is an object of type [`Deps`].
 ///
+/// * **env** is an object of type [`Env`].
+///
 /// * **ask_asset** is an object of type [`Asset`]. This is the asset to swap to as well as the desired
 /// amount of ask assets to receive from the swap.
 pub fn query_reverse_simulation(
     deps: Deps,
+    env: Env,
     ask_asset: Asset,
 ) -> StdResult<ReverseSimulationResponse> {
     let config: Config = CONFIG.load(deps.storage)?;
-    let contract_addr = config.pair_info.contract_addr.clone();
-
-    let pools: [Asset; 2] = config.pair_info.query_pools(&deps.querier, contract_addr)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
 
     let offer_pool: Asset;
     let ask_pool: Asset;
@@ -965,11 +2918,7 @@ pub fn query_reverse_simulation(
     }
 
     // Get fee info from factory
-    let fee_info = query_fee_info(
-        &deps.querier,
-        config.factory_addr,
-        config.pair_info.pair_type,
-    )?;
+    let fee_info = query_fee_info(deps, &env, &config, &pools)?;
 
     let (offer_amount, spread_amount, commission_amount) = compute_offer_amount(
         offer_pool.amount,
@@ -985,6 +2934,122 @@ pub fn query_reverse_simulation(
     })
 }
 
+/// ## Description
+/// Returns the result of a single asset withdrawal simulation in a [`WithdrawOneAssetSimulationResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **lp_amount** is an object of type [`Uint128`]. This is the amount of LP tokens to burn.
+///
+/// * **ask_asset_info** is an object of type [`AssetInfo`]. This is the asset to receive.
+pub fn query_simulate_withdraw_one_asset(
+    deps: Deps,
+    env: Env,
+    lp_amount: Uint128,
+    ask_asset_info: AssetInfo,
+) -> StdResult<WithdrawOneAssetSimulationResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps, config.clone())?;
+    let ask_index = pools
+        .iter()
+        .position(|pool| pool.info.equal(&ask_asset_info))
+        .ok_or_else(|| StdError::generic_err("Given ask asset doesn't belong to pairs"))?;
+
+    let fee_info = query_fee_info(deps, &env, &config, &pools)?;
+    let (return_amount, commission_amount) =
+        compute_withdraw_one_asset(&pools, lp_amount, total_share, ask_index, &fee_info)?;
+
+    Ok(WithdrawOneAssetSimulationResponse {
+        return_amount,
+        commission_amount,
+    })
+}
+
+/// ## Description
+/// Returns the amount of LP tokens that [`provide_liquidity`] mints for a deposit in a [`SimulateProvideResponse`] object.
+/// The pair charges no imbalance fees, so the fees are always zero. The slippage tolerance is not checked.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **assets** is an array with two objects of type [`Asset`]. These are the assets to deposit.
+pub fn query_simulate_provide(
+    deps: Deps,
+    assets: [Asset; 2],
+) -> StdResult<SimulateProvideResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, mut total_share) = pool_info(deps, config.clone())?;
+    let deposits =
+        get_deposits(&assets, &pools).map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    let (_, maker_fee_share) = mint_maker_fee_message(deps, &config, &pools, total_share)?;
+    total_share = total_share.checked_add(maker_fee_share)?;
+
+    let share = compute_share(&deposits, &pools, total_share)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    Ok(SimulateProvideResponse {
+        share,
+        imbalance_fees: pools
+            .iter()
+            .map(|pool| Asset {
+                info: pool.info.clone(),
+                amount: Uint128::zero(),
+            })
+            .collect(),
+    })
+}
+
+/// ## Description
+/// Returns the assets that [`withdraw_liquidity`] or [`withdraw_liquidity_one_asset`] return for burning
+/// LP tokens in a [`SimulateWithdrawResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **lp_amount** is an object of type [`Uint128`]. This is the amount of LP tokens to burn.
+///
+/// * **ask_asset_info** is an [`Option`] field of type [`AssetInfo`]. The withdrawal is simulated in this asset if it is set.
+pub fn query_simulate_withdraw(
+    deps: Deps,
+    env: Env,
+    lp_amount: Uint128,
+    ask_asset_info: Option<AssetInfo>,
+) -> StdResult<SimulateWithdrawResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, mut total_share) = pool_info(deps, config.clone())?;
+
+    let (_, maker_fee_share) = mint_maker_fee_message(deps, &config, &pools, total_share)?;
+    total_share = total_share.checked_add(maker_fee_share)?;
+
+    match ask_asset_info {
+        None => Ok(SimulateWithdrawResponse {
+            assets: get_share_in_assets(&pools, lp_amount, total_share),
+            commission_amount: Uint128::zero(),
+        }),
+        Some(ask_asset_info) => {
+            let ask_index = pools
+                .iter()
+                .position(|pool| pool.info.equal(&ask_asset_info))
+                .ok_or_else(|| StdError::generic_err("Given ask asset doesn't belong to pairs"))?;
+
+            let fee_info = query_fee_info(deps, &env, &config, &pools)?;
+            let (return_amount, commission_amount) =
+                compute_withdraw_one_asset(&pools, lp_amount, total_share, ask_index, &fee_info)?;
+
+            Ok(SimulateWithdrawResponse {
+                assets: vec![Asset {
+                    info: ask_asset_info,
+                    amount: return_amount,
+                }],
+                commission_amount,
+            })
+        }
+    }
+}
+
 /// ## Description
 /// Returns information about cumulative prices for the assets in the pool using a [`CumulativePricesResponse`] object.
 /// ## Params
@@ -1015,6 +3080,39 @@ pub fn query_cumulative_prices(deps: Deps, env: Env) -> StdResult<CumulativePric
     Ok(resp)
 }
 
+/// ## Description
+/// Returns the cumulative prices of the pair `seconds_ago` seconds before the current block time
+/// using an [`ObserveResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **seconds_ago** is a vector of [`u64`] time offsets from the current block time.
+pub fn query_observe(deps: Deps, env: Env, seconds_ago: Vec<u64>) -> StdResult<ObserveResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (assets, _) = pool_info(deps, config.clone())?;
+
+    let mut price0_increment = Uint128::zero();
+    let mut price1_increment = Uint128::zero();
+
+    if let Some((price0_cumulative_new, price1_cumulative_new, _)) =
+        accumulate_prices(env.clone(), &config, assets[0].amount, assets[1].amount)?
+    {
+        price0_increment = price0_cumulative_new.wrapping_sub(config.price0_cumulative_last);
+        price1_increment = price1_cumulative_new.wrapping_sub(config.price1_cumulative_last);
+    }
+
+    observe(
+        deps.storage,
+        config.block_time_last,
+        env.block.time.seconds(),
+        price0_increment,
+        price1_increment,
+        &seconds_ago,
+    )
+}
+
 /// ## Description
 /// Returns the pair contract configuration in a [`ConfigResponse`] object.
 /// ## Params
@@ -1023,10 +3121,33 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let config: Config = CONFIG.load(deps.storage)?;
     Ok(ConfigResponse {
         block_time_last: config.block_time_last,
-        params: None,
+        params: Some(to_binary(&XykPoolConfig {
+            dynamic_fee: config.dynamic_fee,
+            mint_maker_fee: config.mint_maker_fee,
+            fee_on_transfer: config.fee_on_transfer,
+        })?),
     })
 }
 
+/// ## Description
+/// Returns the rewards of the reward-bearing pool asset that a user can claim in an [`Asset`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **user** is an object of type [`String`]. This is the address for which to return pending rewards.
+pub fn query_pending_reward(deps: Deps, user: String) -> StdResult<Asset> {
+    let config = CONFIG.load(deps.storage)?;
+    let reward_config = load_reward_bearing_config(deps.storage)?;
+
+    reward_bearing::query_pending_reward(
+        deps,
+        &REWARD_INDEXES,
+        &reward_config,
+        &config.pair_info.liquidity_token,
+        user,
+    )
+}
+
 /// ## Description
 /// Returns an amount of coins. For each coin in the specified vector, if the coin is null, we return `Uint128::zero()`,
 /// otherwise we return the specified coin amount.
@@ -1083,6 +3204,44 @@ pub fn compute_swap(
     ))
 }
 
+/// ## Description
+/// Returns the marginal amount of ask assets that one offer asset buys (ignoring fees).
+/// Returns an error if the pool is empty.
+/// ## Params
+/// * **offer_pool** is an object of type [`Uint128`]. This is the total amount of offer assets in the pool.
+///
+/// * **ask_pool** is an object of type [`Uint128`]. This is the total amount of ask assets in the pool.
+fn compute_spot_price(offer_pool: Uint128, ask_pool: Uint128) -> StdResult<Decimal> {
+    if offer_pool.is_zero() || ask_pool.is_zero() {
+        return Err(StdError::generic_err("The pool is empty"));
+    }
+
+    Ok(Decimal::from_ratio(ask_pool, offer_pool))
+}
+
+/// ## Description
+/// Returns the relative difference between the amount of ask assets that the offer amount buys at the
+/// spot price and the amount that the swap returns before the commission is deducted.
+/// ## Params
+/// * **offer_amount** is an object of type [`Uint128`]. This is the amount of offer assets to swap.
+///
+/// * **ask_amount** is an object of type [`Uint128`]. This is the amount of ask assets returned by the
+/// swap before the commission is deducted.
+///
+/// * **spot_price** is an object of type [`Decimal`]. This is the spot price of the offer asset.
+fn compute_price_impact(
+    offer_amount: Uint128,
+    ask_amount: Uint128,
+    spot_price: Decimal,
+) -> Decimal {
+    let spot_amount = offer_amount * spot_price;
+    if spot_amount.is_zero() {
+        return Decimal::zero();
+    }
+
+    Decimal::from_ratio(spot_amount.saturating_sub(ask_amount), spot_amount)
+}
+
 /// ## Description
 /// Returns an amount of offer assets for a specified amount of ask assets.
 /// ## Params
@@ -1105,11 +3264,16 @@ fn compute_offer_amount(
     let one_minus_commission = Decimal256::one() - Decimal256::from(commission_rate);
     let inv_one_minus_commission: Decimal = (Decimal256::one() / one_minus_commission).into();
 
-    let offer_amount: Uint128 = Uint128::from(cp.multiply_ratio(
-        Uint256::one(),
-        Uint256::from(ask_pool.checked_sub(ask_amount * inv_one_minus_commission)?),
-    ))
-    .checked_sub(offer_pool)?;
+    let new_ask_pool = ask_pool.checked_sub(ask_amount * inv_one_minus_commission)?;
+    if new_ask_pool.is_zero() {
+        return Err(StdError::generic_err(
+            "Ask amount must be less than the ask pool balance",
+        ));
+    }
+
+    let offer_amount: Uint128 =
+        Uint128::from(cp.multiply_ratio(Uint256::one(), Uint256::from(new_ask_pool)))
+            .checked_sub(offer_pool)?;
 
     let before_commission_deduction = ask_amount * inv_one_minus_commission;
     let spread_amount = (offer_amount * Decimal::from_ratio(ask_pool, offer_pool))
@@ -1208,16 +3372,165 @@ fn assert_slippage_tolerance(
 }
 
 /// ## Description
-/// Used for the contract migration. Returns a default object of type [`Response`].
+/// This is an internal function that checks the withdrawn assets against the minimum amounts
+/// that the LP token holder wants to receive. Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
 /// ## Params
-/// * **_deps** is an object of type [`DepsMut`].
+/// * **refund_assets** are an array of [`Asset`] type items. These are the assets that will be withdrawn.
 ///
-/// * **_env** is an object of type [`Env`].
+/// * **min_assets_to_receive** is an [`Option`] field of type [`Vec<Asset>`]. These are the minimum
+/// amounts of assets to receive.
+fn assert_min_assets_to_receive(
+    refund_assets: &[Asset],
+    min_assets_to_receive: Option<Vec<Asset>>,
+) -> Result<(), ContractError> {
+    for min_asset in min_assets_to_receive.unwrap_or_default() {
+        let refund_asset = refund_assets
+            .iter()
+            .find(|asset| asset.info.equal(&min_asset.info))
+            .ok_or(ContractError::AssetMismatch {})?;
+        if refund_asset.amount < min_asset.amount {
+            return Err(ContractError::MinAssetsToReceiveAssertion {});
+        }
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Checks that the deadline of a message has not passed yet.
+/// Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+/// ## Params
+/// * **env** is an object of type [`Env`].
+///
+/// * **deadline** is an [`Option`] field of type [`u64`]. This is the timestamp (in seconds) after which the message fails.
+fn assert_deadline(env: &Env, deadline: Option<u64>) -> Result<(), ContractError> {
+    if let Some(deadline) = deadline {
+        if env.block.time.seconds() > deadline {
+            return Err(ContractError::DeadlineExpired {});
+        }
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Checks that the callback contract address is valid and that the output of an action is not
+/// also sent to a receiver. Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+/// ## Params
+/// * **api** is an object of type [`Api`].
+///
+/// * **callback** is an [`Option`] field of type [`Callback`]. This is the contract that receives the output.
+///
+/// * **has_receiver** is the field of type [`bool`]. Determines whether a receiver was specified as well.
+fn assert_callback(
+    api: &dyn Api,
+    callback: &Option<Callback>,
+    has_receiver: bool,
+) -> Result<(), ContractError> {
+    if let Some(callback) = callback {
+        if has_receiver {
+            return Err(ContractError::CallbackWithReceiver {});
+        }
+        addr_validate_to_lower(api, &callback.contract)?;
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Used for the contract migration. Pairs migrating from v1.0.0 start tracking their reserves
+/// from their current asset balances. The other state that was added since then is created
+/// with its defaults when it is first used.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
 ///
 /// * **_msg** is an object of type [`MigrateMsg`].
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
-    Ok(Response::default())
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let contract_version = get_contract_version(deps.storage)?;
+
+    match contract_version.contract.as_ref() {
+        "astroport-pair" => match contract_version.version.as_ref() {
+            "1.0.0" | "1.1.0" => {
+                // Pairs deployed before the reserves were tracked start from their current balances
+                if RESERVES.may_load(deps.storage)?.is_none() {
+                    let config: Config = CONFIG.load(deps.storage)?;
+                    let pools: [Asset; 2] = config
+                        .pair_info
+                        .query_pools(&deps.querier, env.contract.address)?;
+                    RESERVES.save(deps.storage, &[pools[0].amount, pools[1].amount])?;
+                }
+            }
+            _ => return Err(ContractError::MigrationError {}),
+        },
+        _ => return Err(ContractError::MigrationError {}),
+    };
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("previous_contract_name", &contract_version.contract)
+        .add_attribute("previous_contract_version", &contract_version.version)
+        .add_attribute("new_contract_name", CONTRACT_NAME)
+        .add_attribute("new_contract_version", CONTRACT_VERSION))
+}
+
+/// ## Description
+/// Returns the pool assets with the amounts that are tracked as the pool reserves.
+/// Tokens that were sent to the pair directly are not part of the reserves until [`sync`] is called.
+/// ## Params
+/// * **storage** is an object of type [`Storage`].
+///
+/// * **config** is an object of type [`Config`].
+pub fn load_pools(storage: &dyn Storage, config: &Config) -> StdResult<[Asset; 2]> {
+    let reserves = RESERVES.load(storage)?;
+
+    Ok([
+        Asset {
+            info: config.pair_info.asset_infos[0].clone(),
+            amount: reserves[0],
+        },
+        Asset {
+            info: config.pair_info.asset_infos[1].clone(),
+            amount: reserves[1],
+        },
+    ])
+}
+
+/// ## Description
+/// Stores the pool reserves after a swap.
+/// ## Params
+/// * **storage** is an object of type [`Storage`].
+///
+/// * **pools** is an array with two objects of type [`Asset`]. These are the pool reserves before the swap.
+///
+/// * **offer_asset_info** is an object of type [`AssetInfo`]. This is the asset that was swapped.
+///
+/// * **offer_amount** is an object of type [`Uint128`]. This is the amount of offer assets that were added to the pool.
+///
+/// * **ask_amount** is an object of type [`Uint128`]. This is the amount of ask assets that left the pool.
+fn update_reserves_after_swap(
+    storage: &mut dyn Storage,
+    pools: &[Asset; 2],
+    offer_asset_info: &AssetInfo,
+    offer_amount: Uint128,
+    ask_amount: Uint128,
+) -> StdResult<()> {
+    let reserves = if offer_asset_info.equal(&pools[0].info) {
+        [
+            pools[0].amount.checked_add(offer_amount)?,
+            pools[1].amount.checked_sub(ask_amount)?,
+        ]
+    } else {
+        [
+            pools[0].amount.checked_sub(ask_amount)?,
+            pools[1].amount.checked_add(offer_amount)?,
+        ]
+    };
+
+    RESERVES.save(storage, &reserves)
 }
 
 /// ## Description
@@ -1227,8 +3540,7 @@ pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Respons
 ///
 /// * **config** is an object of type [`Config`].
 pub fn pool_info(deps: Deps, config: Config) -> StdResult<([Asset; 2], Uint128)> {
-    let contract_addr = config.pair_info.contract_addr.clone();
-    let pools: [Asset; 2] = config.pair_info.query_pools(&deps.querier, contract_addr)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
     let total_share: Uint128 = query_supply(&deps.querier, config.pair_info.liquidity_token)?;
 
     Ok((pools, total_share))
@@ -1,3 +1,4 @@
+use astroport::pair::MINIMUM_LIQUIDITY_AMOUNT;
 use cosmwasm_std::{OverflowError, StdError};
 use thiserror::Error;
 
@@ -14,18 +15,48 @@ pub enum ContractError {
     #[error("Operation non supported")]
     NonSupported {},
 
+    #[error("The pair is paused")]
+    Paused {},
+
+    #[error("Incorrect dynamic fee parameters")]
+    IncorrectDynamicFeeParams {},
+
     #[error("Event of zero transfer")]
     InvalidZeroAmount {},
 
     #[error("Operation exceeds max spread limit")]
     MaxSpreadAssertion {},
 
+    #[error("Operation moves the price by more than the circuit breaker allows within a block")]
+    MaxPriceChangeAssertion {},
+
     #[error("Provided spread amount exceeds allowed limit")]
     AllowedSpreadAssertion {},
 
     #[error("Operation exceeds max splippage tolerance")]
     MaxSlippageAssertion {},
 
+    #[error("Operation exceeds max offer amount")]
+    MaxOfferAmountAssertion {},
+
+    #[error("Ask amount must be less than the ask pool balance")]
+    AskAmountExceedsPool {},
+
+    #[error("Return amount is less than the minimum amount")]
+    MinAmountAssertion {},
+
+    #[error("The amount of LP tokens to receive is less than the minimum amount")]
+    MinLpToReceiveAssertion {},
+
+    #[error("The amount of withdrawn assets is less than the minimum amount")]
+    MinAssetsToReceiveAssertion {},
+
+    #[error("Flash loan was not repaid")]
+    FlashLoanNotRepaid {},
+
+    #[error("A flash loan is in progress")]
+    FlashLoanInProgress {},
+
     #[error("Doubling assets in asset infos")]
     DoublingAssets {},
 
@@ -37,6 +68,18 @@ pub enum ContractError {
 
     #[error("Generator address is not set in factory. Cannot auto-stake")]
     AutoStakeError {},
+
+    #[error("Initial liquidity must be more than {}", MINIMUM_LIQUIDITY_AMOUNT)]
+    MinimumLiquidityAmountError {},
+
+    #[error("Contract can't be migrated!")]
+    MigrationError {},
+
+    #[error("The deadline of the message has passed")]
+    DeadlineExpired {},
+
+    #[error("A callback contract can't be combined with a receiver or auto staking")]
+    CallbackWithReceiver {},
 }
 
 impl From<OverflowError> for ContractError {
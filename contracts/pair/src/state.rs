@@ -1,5 +1,6 @@
-use astroport::asset::PairInfo;
-use cosmwasm_std::{Addr, Uint128};
+use astroport::asset::{Asset, Callback, PairInfo};
+use astroport::pair::DynamicFeeParams;
+use cosmwasm_std::{Addr, Decimal, Uint128};
 use cw_storage_plus::Item;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -14,12 +15,82 @@ pub struct Config {
     pub factory_addr: Addr,
     /// The last timestamp when the pair contract update the asset cumulative prices
     pub block_time_last: u64,
-    /// The last cumulative price for asset 0
+    /// The last cumulative price for asset 0. It wraps around on overflow, so it must only be used
+    /// to compute differences with wrapping subtraction
     pub price0_cumulative_last: Uint128,
-    /// The last cumulative price for asset 1
+    /// The last cumulative price for asset 1. It wraps around on overflow, so it must only be used
+    /// to compute differences with wrapping subtraction
     pub price1_cumulative_last: Uint128,
+    /// The dynamic fee parameters. If [`None`], the pair charges the fee set in the factory
+    pub dynamic_fee: Option<DynamicFeeParams>,
+    /// Whether the Maker fee is minted as LP tokens on liquidity events instead of being sent on every swap
+    #[serde(default)]
+    pub mint_maker_fee: bool,
+    /// The square root of the product of the pool reserves after the last liquidity event.
+    /// It is only tracked while the Maker fee is minted as LP tokens
+    #[serde(default)]
+    pub sqrt_k_last: Uint128,
+    /// Whether the pair measures the amounts of CW20 tokens that it actually receives,
+    /// so that tokens which charge a fee on transfers can be pooled
+    #[serde(default)]
+    pub fee_on_transfer: bool,
 }
 
 /// ## Description
 /// Stores the config struct at the given key
 pub const CONFIG: Item<Config> = Item::new("config");
+
+/// ## Description
+/// This structure stores the state of a flash loan while the callback contract is being executed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FlashLoanState {
+    /// The pool reserves before the loan
+    pub pools: [Asset; 2],
+    /// The pair balances before the loan
+    pub balances: [Uint128; 2],
+    /// The borrowed asset and amount
+    pub asset: Asset,
+}
+
+/// ## Description
+/// Stores the state of the flash loan that is currently in progress
+pub const FLASH_LOAN: Item<FlashLoanState> = Item::new("flash_loan");
+
+/// ## Description
+/// Stores the pool reserves. The pair balances may exceed them when tokens are sent to the pair directly
+pub const RESERVES: Item<[Uint128; 2]> = Item::new("reserves");
+
+/// ## Description
+/// This structure describes the parameters of a liquidity provision.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProvideParams {
+    /// The liquidity provider
+    pub sender: Addr,
+    /// The assets deposited in the pool
+    pub assets: [Asset; 2],
+    /// How much the pool price can move until the provision fails
+    pub slippage_tolerance: Option<Decimal>,
+    /// Whether the LP tokens are staked in the Generator on behalf of the receiver
+    pub auto_stake: bool,
+    /// The receiver of the LP tokens
+    pub receiver: Option<String>,
+    /// The minimum amount of LP tokens to receive
+    pub min_lp_to_receive: Option<Uint128>,
+    /// The contract that receives the LP tokens along with a message
+    pub callback: Option<Callback>,
+}
+
+/// ## Description
+/// This structure stores a liquidity provision while the deposited tokens are being transferred
+/// to a pair that measures the amounts it actually receives.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingProvide {
+    /// The parameters of the liquidity provision
+    pub params: ProvideParams,
+    /// The pair balances before the transfers
+    pub balances: [Uint128; 2],
+}
+
+/// ## Description
+/// Stores the liquidity provision that waits for its token transfers to settle
+pub const PENDING_PROVIDE: Item<PendingProvide> = Item::new("pending_provide");
@@ -1,29 +1,40 @@
-use crate::contract::reply;
 use crate::contract::{
-    accumulate_prices, assert_max_spread, compute_swap, execute, instantiate, query_pair_info,
-    query_pool, query_reverse_simulation, query_share, query_simulation,
+    accumulate_prices, assert_max_spread, compute_swap, execute, instantiate, price_increment,
+    query, query_pair_info, query_pool, query_reverse_simulation, query_share, query_simulation,
 };
+use crate::contract::{migrate, reply};
 use crate::error::ContractError;
 use crate::mock_querier::mock_dependencies;
 use crate::response::MsgInstantiateContractResponse;
-use crate::state::Config;
-use astroport::asset::{Asset, AssetInfo, PairInfo};
+use crate::state::{Config, RESERVES};
+use astroport::asset::{Asset, AssetInfo, Callback, PairInfo};
+use astroport::circuit_breaker::StatusResponse;
 use astroport::factory::PairType;
+use astroport::observation::ObserveResponse;
 
 use astroport::pair::{
-    Cw20HookMsg, ExecuteMsg, InstantiateMsg, PoolResponse, ReverseSimulationResponse,
-    SimulationResponse, TWAP_PRECISION,
+    ConfigResponse, CumulativePricesResponse, Cw20HookMsg, DynamicFeeParams, ExecuteMsg,
+    FlashLoanCallbackMsg, InstantiateMsg, MigrateMsg, PoolResponse, PriceImpactResponse, QueryMsg,
+    ReverseSimulationResponse, SimulateProvideResponse, SimulateWithdrawResponse,
+    SimulationResponse, SpotPriceResponse, WithdrawOneAssetSimulationResponse, XykPoolConfig,
+    XykPoolParams, XykPoolUpdateParams, MINIMUM_LIQUIDITY_AMOUNT, TWAP_PRECISION,
 };
+use astroport::reward_bearing::{RewardBearingInitParams, RewardBearingParams, RewarderExecuteMsg};
+use astroport::stats::{AssetStats, DailyStats, DailyStatsResponse, StatsResponse};
 use astroport::token::InstantiateMsg as TokenInstantiateMsg;
+use astroport::whitelist::{
+    ExecuteMsg as WhitelistExecuteMsg, InstantiateMsg as WhitelistInstantiateMsg,
+};
 use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    attr, to_binary, Addr, BankMsg, BlockInfo, Coin, ContractResult, CosmosMsg, Decimal, DepsMut,
-    Env, Reply, ReplyOn, Response, StdError, SubMsg, SubMsgExecutionResponse, Timestamp, Uint128,
-    WasmMsg,
+    attr, from_binary, to_binary, Addr, BankMsg, Binary, BlockInfo, Coin, ContractResult,
+    CosmosMsg, Decimal, Deps, DepsMut, Empty, Env, Reply, ReplyOn, Response, StdError, SubMsg,
+    SubMsgExecutionResponse, Timestamp, Uint128, Uint256, WasmMsg,
 };
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
 use proptest::prelude::*;
 use protobuf::Message;
+use std::str::FromStr;
 
 fn store_liquidity_token(deps: DepsMut, msg_id: u64, contract_addr: String) {
     let data = MsgInstantiateContractResponse {
@@ -46,6 +57,10 @@ fn store_liquidity_token(deps: DepsMut, msg_id: u64, contract_addr: String) {
     let _res = reply(deps, mock_env(), reply_msg.clone()).unwrap();
 }
 
+fn store_reserves(deps: DepsMut, reserves: [Uint128; 2]) {
+    RESERVES.save(deps.storage, &reserves).unwrap();
+}
+
 #[test]
 fn proper_initialization() {
     let mut deps = mock_dependencies(&[]);
@@ -179,6 +194,9 @@ fn provide_liquidity() {
         slippage_tolerance: None,
         auto_stake: None,
         receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
     };
 
     let env = mock_env();
@@ -191,7 +209,8 @@ fn provide_liquidity() {
     );
     let res = execute(deps.as_mut(), env.clone().clone(), info, msg).unwrap();
     let transfer_from_msg = res.messages.get(0).expect("no message");
-    let mint_msg = res.messages.get(1).expect("no message");
+    let mint_min_liquidity_msg = res.messages.get(1).expect("no message");
+    let mint_msg = res.messages.get(2).expect("no message");
     assert_eq!(
         transfer_from_msg,
         &SubMsg {
@@ -211,6 +230,24 @@ fn provide_liquidity() {
             reply_on: ReplyOn::Never
         }
     );
+    assert_eq!(
+        mint_min_liquidity_msg,
+        &SubMsg {
+            msg: WasmMsg::Execute {
+                contract_addr: String::from("liquidity0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: String::from(MOCK_CONTRACT_ADDR),
+                    amount: MINIMUM_LIQUIDITY_AMOUNT,
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+            .into(),
+            id: 0,
+            gas_limit: None,
+            reply_on: ReplyOn::Never,
+        }
+    );
     assert_eq!(
         mint_msg,
         &SubMsg {
@@ -218,7 +255,7 @@ fn provide_liquidity() {
                 contract_addr: String::from("liquidity0000"),
                 msg: to_binary(&Cw20ExecuteMsg::Mint {
                     recipient: String::from("addr0000"),
-                    amount: Uint128::from(100_000000000000000000u128),
+                    amount: Uint128::from(100_000000000000000000u128) - MINIMUM_LIQUIDITY_AMOUNT,
                 })
                 .unwrap(),
                 funds: vec![],
@@ -239,6 +276,13 @@ fn provide_liquidity() {
             amount: Uint128::new(200_000000000000000000 + 200_000000000000000000 /* user deposit must be pre-applied */),
         }],
     )]);
+    store_reserves(
+        deps.as_mut(),
+        [
+            Uint128::new(200_000000000000000000),
+            Uint128::new(200_000000000000000000),
+        ],
+    );
 
     deps.querier.with_token_balances(&[
         (
@@ -275,6 +319,9 @@ fn provide_liquidity() {
         slippage_tolerance: Some(Decimal::percent(50)),
         auto_stake: None,
         receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
     };
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
@@ -347,6 +394,9 @@ fn provide_liquidity() {
         slippage_tolerance: None,
         auto_stake: None,
         receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
     };
 
     let env = mock_env();
@@ -374,6 +424,13 @@ fn provide_liquidity() {
             amount: Uint128::new(100_000000000000000000 + 100_000000000000000000 /* user deposit must be pre-applied */),
         }],
     )]);
+    store_reserves(
+        deps.as_mut(),
+        [
+            Uint128::new(100_000000000000000000),
+            Uint128::new(100_000000000000000000),
+        ],
+    );
 
     deps.querier.with_token_balances(&[
         (
@@ -411,6 +468,9 @@ fn provide_liquidity() {
         slippage_tolerance: Some(Decimal::percent(1)),
         auto_stake: None,
         receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
     };
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
@@ -432,6 +492,13 @@ fn provide_liquidity() {
             amount: Uint128::new(100_000000000000000000 + 98_000000000000000000 /* user deposit must be pre-applied */),
         }],
     )]);
+    store_reserves(
+        deps.as_mut(),
+        [
+            Uint128::new(100_000000000000000000),
+            Uint128::new(100_000000000000000000),
+        ],
+    );
 
     // Failed because the price is under slippage_tolerance
     let msg = ExecuteMsg::ProvideLiquidity {
@@ -452,6 +519,9 @@ fn provide_liquidity() {
         slippage_tolerance: Some(Decimal::percent(1)),
         auto_stake: None,
         receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
     };
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
@@ -473,6 +543,13 @@ fn provide_liquidity() {
             amount: Uint128::new(100_000000000000000000 + 100_000000000000000000 /* user deposit must be pre-applied */),
         }],
     )]);
+    store_reserves(
+        deps.as_mut(),
+        [
+            Uint128::new(100_000000000000000000),
+            Uint128::new(100_000000000000000000),
+        ],
+    );
 
     // Successfully provides liquidity
     let msg = ExecuteMsg::ProvideLiquidity {
@@ -493,6 +570,9 @@ fn provide_liquidity() {
         slippage_tolerance: Some(Decimal::percent(1)),
         auto_stake: None,
         receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
     };
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
@@ -513,6 +593,13 @@ fn provide_liquidity() {
             amount: Uint128::new(100_000000000000000000 + 99_000000000000000000 /* user deposit must be pre-applied */),
         }],
     )]);
+    store_reserves(
+        deps.as_mut(),
+        [
+            Uint128::new(100_000000000000000000),
+            Uint128::new(100_000000000000000000),
+        ],
+    );
 
     // Successfully provides liquidity
     let msg = ExecuteMsg::ProvideLiquidity {
@@ -533,6 +620,9 @@ fn provide_liquidity() {
         slippage_tolerance: Some(Decimal::percent(1)),
         auto_stake: None,
         receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
     };
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
@@ -546,6 +636,154 @@ fn provide_liquidity() {
     let _res = execute(deps.as_mut(), env, info, msg).unwrap();
 }
 
+#[test]
+fn first_depositor_inflation_attack() {
+    let donation_amount = Uint128::new(1_000_000_000_000u128);
+
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(0))],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(0))],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    let provide_msg = |amount: u128| ExecuteMsg::ProvideLiquidity {
+        assets: [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::new(amount),
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                amount: Uint128::new(amount),
+            },
+        ],
+        slippage_tolerance: None,
+        auto_stake: None,
+        receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
+    };
+    let provide_info = |sender: &str, amount: u128| {
+        mock_info(
+            sender,
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(amount),
+            }],
+        )
+    };
+
+    // The initial deposit must be larger than the locked amount
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        provide_info("attacker", 1000),
+        provide_msg(1000),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::MinimumLiquidityAmountError {});
+
+    // The attacker provides the smallest possible initial deposit and gets a single LP token
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        provide_info("attacker", 1001),
+        provide_msg(1001),
+    )
+    .unwrap();
+    assert_eq!(res.attributes[4], attr("share", "1"));
+
+    // The attacker donates a large amount of tokens and adds them to the pool
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(1001) + donation_amount,
+        }],
+    )]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(Uint128::new(1001) + donation_amount),
+            )],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[
+                (&String::from(MOCK_CONTRACT_ADDR), &MINIMUM_LIQUIDITY_AMOUNT),
+                (&String::from("attacker"), &Uint128::new(1)),
+            ],
+        ),
+    ]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("attacker", &[]),
+        ExecuteMsg::Sync {},
+    )
+    .unwrap();
+
+    // The share of the next provider is not rounded down to zero
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        provide_info("addr0000", donation_amount.u128()),
+        provide_msg(donation_amount.u128()),
+    )
+    .unwrap();
+    assert_eq!(res.attributes[4], attr("share", "1000"));
+
+    deps.querier.with_token_balances(&[(
+        &String::from("liquidity0000"),
+        &[
+            (&String::from(MOCK_CONTRACT_ADDR), &MINIMUM_LIQUIDITY_AMOUNT),
+            (&String::from("attacker"), &Uint128::new(1)),
+            (&String::from("addr0000"), &Uint128::new(1000)),
+        ],
+    )]);
+
+    // The provider keeps almost the whole deposit while the attacker loses most of the donation
+    let res = query_share(deps.as_ref(), Uint128::new(1000)).unwrap();
+    assert_eq!(res[0].amount, Uint128::new(999_500_250_375u128));
+    let res = query_share(deps.as_ref(), Uint128::new(1)).unwrap();
+    assert_eq!(res[0].amount, Uint128::new(999_500_250u128));
+}
+
 #[test]
 fn withdraw_liquidity() {
     let mut deps = mock_dependencies(&[Coin {
@@ -590,11 +828,20 @@ fn withdraw_liquidity() {
 
     // Store liquidity token
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(
+        deps.as_mut(),
+        [Uint128::new(100u128), Uint128::new(100u128)],
+    );
 
     // Withdraw liquidity
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: String::from("addr0000"),
-        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {}).unwrap(),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            min_assets_to_receive: None,
+            deadline: None,
+            callback: None,
+        })
+        .unwrap(),
         amount: Uint128::new(100u128),
     });
 
@@ -668,30 +915,24 @@ fn withdraw_liquidity() {
 }
 
 #[test]
-fn try_native_to_token() {
-    let total_share = Uint128::new(30000000000u128);
-    let asset_pool_amount = Uint128::new(20000000000u128);
-    let collateral_pool_amount = Uint128::new(30000000000u128);
-    let offer_amount = Uint128::new(1500000000u128);
-
+fn callbacks() {
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
-        amount: collateral_pool_amount + offer_amount, /* user deposit must be pre-applied */
+        amount: Uint128::new(100u128),
     }]);
 
     deps.querier.with_tax(
         Decimal::zero(),
         &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
     );
-
     deps.querier.with_token_balances(&[
         (
             &String::from("liquidity0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+            &[(&String::from("addr0000"), &Uint128::new(100u128))],
         ),
         (
             &String::from("asset0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(100u128))],
         ),
     ]);
 
@@ -708,171 +949,199 @@ fn try_native_to_token() {
         factory_addr: String::from("factory"),
         init_params: None,
     };
-
-    let env = mock_env();
-    let info = mock_info("addr0000", &[]);
-    // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
-
-    // Store liquidity token
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(
+        deps.as_mut(),
+        [Uint128::new(100u128), Uint128::new(100u128)],
+    );
 
-    // Normal swap
-    let msg = ExecuteMsg::Swap {
-        offer_asset: Asset {
-            info: AssetInfo::NativeToken {
-                denom: "uusd".to_string(),
-            },
-            amount: offer_amount,
+    let callback = Callback {
+        contract: String::from("vault0000"),
+        msg: to_binary(&Empty {}).unwrap(),
+    };
+    let uusd_asset = |amount: u128| Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
         },
+        amount: Uint128::new(amount),
+    };
+    let send_msg = |token: &str, amount: Uint128| -> CosmosMsg {
+        WasmMsg::Execute {
+            contract_addr: String::from(token),
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: String::from("vault0000"),
+                amount,
+                msg: to_binary(&Empty {}).unwrap(),
+            })
+            .unwrap(),
+            funds: vec![],
+        }
+        .into()
+    };
+
+    // The output can't be sent to both a receiver and the callback contract
+    let msg = ExecuteMsg::Swap {
+        offer_asset: uusd_asset(10),
         belief_price: None,
         max_spread: Some(Decimal::percent(50)),
-        to: None,
+        to: Some(String::from("addr0001")),
+        deadline: None,
+        callback: Some(callback.clone()),
     };
-    let env = mock_env_with_block_time(1000);
-    let info = mock_info(
-        "addr0000",
-        &[Coin {
-            denom: "uusd".to_string(),
-            amount: offer_amount,
-        }],
-    );
-
-    let res = execute(deps.as_mut(), env, info, msg).unwrap();
-    let msg_transfer = res.messages.get(0).expect("no message");
-
-    // Current price is 1.5, so expected return without spread is 1000
-    // 952380952 = 20000000000 - (30000000000 * 20000000000) / (30000000000 + 1500000000)
-    let expected_ret_amount = Uint128::new(952_380_952u128);
-
-    // 47619047 = 1500000000 * (20000000000 / 30000000000) - 952380952
-    let expected_spread_amount = Uint128::new(47619047u128);
-
-    let expected_commission_amount = expected_ret_amount.multiply_ratio(3u128, 1000u128); // 0.3%
-    let expected_maker_fee_amount = expected_commission_amount.multiply_ratio(166u128, 1000u128); // 0.166
-
-    let expected_return_amount = expected_ret_amount
-        .checked_sub(expected_commission_amount)
-        .unwrap();
-    let expected_tax_amount = Uint128::zero(); // no tax for token
-
-    // Check simulation result
-    deps.querier.with_balance(&[(
-        &String::from(MOCK_CONTRACT_ADDR),
-        &[Coin {
-            denom: "uusd".to_string(),
-            amount: collateral_pool_amount, /* user deposit must be pre-applied */
-        }],
-    )]);
+    let info = mock_info("addr0000", &[Coin::new(10, "uusd")]);
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(err, ContractError::CallbackWithReceiver {});
 
-    let simulation_res: SimulationResponse = query_simulation(
-        deps.as_ref(),
-        Asset {
-            info: AssetInfo::NativeToken {
-                denom: "uusd".to_string(),
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: [
+            uusd_asset(100),
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                amount: Uint128::new(100u128),
             },
-            amount: offer_amount,
-        },
-    )
-    .unwrap();
-    assert_eq!(expected_return_amount, simulation_res.return_amount);
-    assert_eq!(expected_commission_amount, simulation_res.commission_amount);
-    assert_eq!(expected_spread_amount, simulation_res.spread_amount);
+        ],
+        slippage_tolerance: None,
+        auto_stake: Some(true),
+        receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: Some(callback.clone()),
+    };
+    let info = mock_info("addr0000", &[Coin::new(100, "uusd")]);
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(err, ContractError::CallbackWithReceiver {});
 
-    // Check reverse simulation result
-    let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
-        deps.as_ref(),
-        Asset {
-            info: AssetInfo::Token {
-                contract_addr: Addr::unchecked("asset0000"),
-            },
-            amount: expected_return_amount,
-        },
+    // The withdrawn assets are sent to the callback contract after the LP tokens are burned
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            min_assets_to_receive: None,
+            deadline: None,
+            callback: Some(callback.clone()),
+        })
+        .unwrap(),
+        amount: Uint128::new(100u128),
+    });
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("liquidity0000", &[]),
+        msg,
     )
     .unwrap();
+    let msgs: Vec<CosmosMsg> = res.messages.into_iter().map(|msg| msg.msg).collect();
     assert_eq!(
-        (offer_amount.u128() as i128 - reverse_simulation_res.offer_amount.u128() as i128).abs()
-            < 5i128,
-        true
-    );
-    assert_eq!(
-        (expected_commission_amount.u128() as i128
-            - reverse_simulation_res.commission_amount.u128() as i128)
-            .abs()
-            < 5i128,
-        true
-    );
-    assert_eq!(
-        (expected_spread_amount.u128() as i128
-            - reverse_simulation_res.spread_amount.u128() as i128)
-            .abs()
-            < 5i128,
-        true
+        msgs,
+        vec![
+            WasmMsg::Execute {
+                contract_addr: String::from("liquidity0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::new(100u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+            .into(),
+            send_msg("asset0000", Uint128::new(100u128)),
+            WasmMsg::Execute {
+                contract_addr: String::from("vault0000"),
+                msg: to_binary(&Empty {}).unwrap(),
+                funds: vec![Coin::new(100, "uusd")],
+            }
+            .into(),
+        ]
     );
 
-    assert_eq!(
-        res.attributes,
-        vec![
-            attr("action", "swap"),
-            attr("sender", "addr0000"),
-            attr("receiver", "addr0000"),
-            attr("offer_asset", "uusd"),
-            attr("ask_asset", "asset0000"),
-            attr("offer_amount", offer_amount.to_string()),
-            attr("return_amount", expected_return_amount.to_string()),
-            attr("tax_amount", expected_tax_amount.to_string()),
-            attr("spread_amount", expected_spread_amount.to_string()),
-            attr("commission_amount", expected_commission_amount.to_string()),
-            attr("maker_fee_amount", expected_maker_fee_amount.to_string()),
-        ]
+    // The LP tokens are minted for the pair and sent to the callback contract
+    store_reserves(
+        deps.as_mut(),
+        [Uint128::new(100u128), Uint128::new(100u128)],
     );
-
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: [
+            uusd_asset(100),
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                amount: Uint128::new(100u128),
+            },
+        ],
+        slippage_tolerance: None,
+        auto_stake: None,
+        receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: Some(callback.clone()),
+    };
+    let info = mock_info("addr0000", &[Coin::new(100, "uusd")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(res.attributes[2], attr("receiver", "vault0000"));
+    let msgs: Vec<CosmosMsg> = res.messages.into_iter().map(|msg| msg.msg).collect();
     assert_eq!(
-        &SubMsg {
-            msg: WasmMsg::Execute {
-                contract_addr: String::from("asset0000"),
-                msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                    recipient: String::from("addr0000"),
-                    amount: Uint128::from(expected_return_amount),
+        msgs[1..],
+        [
+            WasmMsg::Execute {
+                contract_addr: String::from("liquidity0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: String::from(MOCK_CONTRACT_ADDR),
+                    amount: Uint128::new(100u128),
                 })
                 .unwrap(),
                 funds: vec![],
             }
             .into(),
-            id: 0,
-            gas_limit: None,
-            reply_on: ReplyOn::Never,
-        },
-        msg_transfer,
+            send_msg("liquidity0000", Uint128::new(100u128)),
+        ]
+    );
+
+    // The return assets are sent to the callback contract after the Maker fee
+    let msg = ExecuteMsg::Swap {
+        offer_asset: uusd_asset(10),
+        belief_price: None,
+        max_spread: Some(Decimal::percent(50)),
+        to: None,
+        deadline: None,
+        callback: Some(callback),
+    };
+    let info = mock_info("addr0000", &[Coin::new(10, "uusd")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let return_amount = res
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "return_amount")
+        .unwrap()
+        .value
+        .clone();
+    assert_eq!(res.attributes[2], attr("receiver", "vault0000"));
+    assert_eq!(
+        res.messages.last().unwrap().msg,
+        send_msg("asset0000", Uint128::from_str(&return_amount).unwrap())
     );
 }
 
 #[test]
-fn try_token_to_native() {
-    let total_share = Uint128::new(20000000000u128);
-    let asset_pool_amount = Uint128::new(30000000000u128);
-    let collateral_pool_amount = Uint128::new(20000000000u128);
-    let offer_amount = Uint128::new(1500000000u128);
-
+fn fee_on_transfer_tokens() {
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
-        amount: collateral_pool_amount,
+        amount: Uint128::new(1100u128),
     }]);
+
     deps.querier.with_tax(
-        Decimal::percent(1),
+        Decimal::zero(),
         &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
     );
     deps.querier.with_token_balances(&[
         (
             &String::from("liquidity0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+            &[(&String::from("addr0000"), &Uint128::new(1000u128))],
         ),
         (
             &String::from("asset0000"),
-            &[(
-                &String::from(MOCK_CONTRACT_ADDR),
-                &(asset_pool_amount + offer_amount),
-            )],
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(1000u128))],
         ),
     ]);
 
@@ -887,333 +1156,330 @@ fn try_token_to_native() {
         ],
         token_code_id: 10u64,
         factory_addr: String::from("factory"),
-        init_params: None,
+        init_params: Some(
+            to_binary(&XykPoolParams {
+                fee_on_transfer: true,
+            })
+            .unwrap(),
+        ),
     };
-
-    let env = mock_env();
-    let info = mock_info("addr0000", &[]);
-    // We can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
-
-    // Store liquidity token
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(
+        deps.as_mut(),
+        [Uint128::new(1000u128), Uint128::new(1000u128)],
+    );
 
-    // Unauthorized access; can not execute swap directy for token swap
-    let msg = ExecuteMsg::Swap {
-        offer_asset: Asset {
-            info: AssetInfo::Token {
-                contract_addr: Addr::unchecked("asset0000"),
+    let res: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert!(
+        from_binary::<XykPoolConfig>(&res.params.unwrap())
+            .unwrap()
+            .fee_on_transfer
+    );
+
+    // The provision waits for the token transfer to settle
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::new(100u128),
             },
-            amount: offer_amount,
-        },
-        belief_price: None,
-        max_spread: None,
-        to: None,
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                amount: Uint128::new(100u128),
+            },
+        ],
+        slippage_tolerance: Some(Decimal::percent(2)),
+        auto_stake: None,
+        receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
     };
-    let env = mock_env_with_block_time(1000);
-    let info = mock_info("addr0000", &[]);
-    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
-    assert_eq!(res, ContractError::Unauthorized {});
+    let info = mock_info("addr0000", &[Coin::new(100, "uusd")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: String::from("addr0000"),
+                    recipient: String::from(MOCK_CONTRACT_ADDR),
+                    amount: Uint128::new(100u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            },
+            4,
+        )]
+    );
 
-    // Normal sell
+    // The token charges a 1% transfer fee, so the pair receives only 99 tokens
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &Uint128::new(1000u128))],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(1099u128))],
+        ),
+    ]);
+    let settle_reply = Reply {
+        id: 4,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+    let res = reply(deps.as_mut(), mock_env(), settle_reply.clone()).unwrap();
+    assert_eq!(res.attributes[3], attr("assets", "100uusd, 99asset0000"));
+    assert_eq!(res.attributes[4], attr("share", "99"));
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: String::from("liquidity0000"),
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: String::from("addr0000"),
+                amount: Uint128::new(99u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [Uint128::new(1100u128), Uint128::new(1099u128)]
+    );
+
+    // The pending provision is settled only once
+    reply(deps.as_mut(), mock_env(), settle_reply).unwrap_err();
+
+    // A swap uses the amount that the pair received instead of the sent amount
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &Uint128::new(1099u128))],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(1194u128))],
+        ),
+    ]);
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: String::from("addr0000"),
-        amount: offer_amount,
+        amount: Uint128::new(100u128),
         msg: to_binary(&Cw20HookMsg::Swap {
             belief_price: None,
             max_spread: Some(Decimal::percent(50)),
             to: None,
+            deadline: None,
+            callback: None,
         })
         .unwrap(),
     });
-    let env = mock_env_with_block_time(1000);
-    let info = mock_info("asset0000", &[]);
-
-    let res = execute(deps.as_mut(), env, info, msg).unwrap();
-    let msg_transfer = res.messages.get(0).expect("no message");
-
-    // Current price is 1.5, so expected return without spread is 1000
-    // 952380952,3809524 = 20000000000 - (30000000000 * 20000000000) / (30000000000 + 1500000000)
-    let expected_ret_amount = Uint128::new(952_380_952u128);
+    let res = execute(deps.as_mut(), mock_env(), mock_info("asset0000", &[]), msg).unwrap();
+    assert_eq!(res.attributes[5], attr("offer_amount", "95"));
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap()[1],
+        Uint128::new(1194u128)
+    );
+}
 
-    // 47619047 = 1500000000 * (20000000000 / 30000000000) - 952380952,3809524
-    let expected_spread_amount = Uint128::new(47619047u128);
+#[test]
+fn min_amounts_to_receive() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(100u128),
+    }]);
 
-    let expected_commission_amount = expected_ret_amount.multiply_ratio(3u128, 1000u128); // 0.3%
-    let expected_maker_fee_amount = expected_commission_amount.multiply_ratio(166u128, 1000u128);
-    let expected_return_amount = expected_ret_amount
-        .checked_sub(expected_commission_amount)
-        .unwrap();
-    let expected_tax_amount = std::cmp::min(
-        Uint128::new(1000000u128),
-        expected_return_amount
-            .checked_sub(
-                expected_return_amount.multiply_ratio(Uint128::new(100u128), Uint128::new(101u128)),
-            )
-            .unwrap(),
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
     );
-    // Check simulation res
-    // Return asset token balance as normal
     deps.querier.with_token_balances(&[
         (
             &String::from("liquidity0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+            &[(&String::from("addr0000"), &Uint128::new(100u128))],
         ),
         (
             &String::from("asset0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &(asset_pool_amount))],
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(100u128))],
         ),
     ]);
 
-    let simulation_res: SimulationResponse = query_simulation(
-        deps.as_ref(),
-        Asset {
-            amount: offer_amount,
-            info: AssetInfo::Token {
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
                 contract_addr: Addr::unchecked("asset0000"),
             },
-        },
-    )
-    .unwrap();
-    assert_eq!(expected_return_amount, simulation_res.return_amount);
-    assert_eq!(expected_commission_amount, simulation_res.commission_amount);
-    assert_eq!(expected_spread_amount, simulation_res.spread_amount);
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
 
-    // Check reverse simulation result
-    let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
-        deps.as_ref(),
-        Asset {
-            amount: expected_return_amount,
-            info: AssetInfo::NativeToken {
-                denom: "uusd".to_string(),
-            },
-        },
-    )
-    .unwrap();
-    assert_eq!(
-        (offer_amount.u128() as i128 - reverse_simulation_res.offer_amount.u128() as i128).abs()
-            < 5i128,
-        true
-    );
-    assert_eq!(
-        (expected_commission_amount.u128() as i128
-            - reverse_simulation_res.commission_amount.u128() as i128)
-            .abs()
-            < 5i128,
-        true
-    );
-    assert_eq!(
-        (expected_spread_amount.u128() as i128
-            - reverse_simulation_res.spread_amount.u128() as i128)
-            .abs()
-            < 5i128,
-        true
-    );
-
-    assert_eq!(
-        res.attributes,
-        vec![
-            attr("action", "swap"),
-            attr("sender", "addr0000"),
-            attr("receiver", "addr0000"),
-            attr("offer_asset", "asset0000"),
-            attr("ask_asset", "uusd"),
-            attr("offer_amount", offer_amount.to_string()),
-            attr("return_amount", expected_return_amount.to_string()),
-            attr("tax_amount", expected_tax_amount.to_string()),
-            attr("spread_amount", expected_spread_amount.to_string()),
-            attr("commission_amount", expected_commission_amount.to_string()),
-            attr("maker_fee_amount", expected_maker_fee_amount.to_string()),
-        ]
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(
+        deps.as_mut(),
+        [Uint128::new(100u128), Uint128::new(100u128)],
     );
 
-    assert_eq!(
-        &SubMsg {
-            msg: CosmosMsg::Bank(BankMsg::Send {
-                to_address: String::from("addr0000"),
-                amount: vec![Coin {
+    // Providing 10 of each asset mints 10 LP tokens
+    let provide_msg = |min_lp_to_receive: u128, auto_stake: bool| ExecuteMsg::ProvideLiquidity {
+        assets: [
+            Asset {
+                info: AssetInfo::NativeToken {
                     denom: "uusd".to_string(),
-                    amount: expected_return_amount
-                        .checked_sub(expected_tax_amount)
-                        .unwrap(),
-                }],
-            })
-            .into(),
-            id: 0,
-            gas_limit: None,
-            reply_on: ReplyOn::Never,
-        },
-        msg_transfer,
+                },
+                amount: Uint128::new(10u128),
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                amount: Uint128::new(10u128),
+            },
+        ],
+        slippage_tolerance: None,
+        auto_stake: Some(auto_stake),
+        receiver: None,
+        min_lp_to_receive: Some(Uint128::new(min_lp_to_receive)),
+        deadline: None,
+        callback: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(10u128),
+        }],
     );
 
-    // Failed due to trying to swap a non token (specifying an address of a non token contract)
-    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
-        sender: String::from("addr0000"),
-        amount: offer_amount,
-        msg: to_binary(&Cw20HookMsg::Swap {
-            belief_price: None,
-            max_spread: None,
-            to: None,
-        })
-        .unwrap(),
-    });
-    let env = mock_env_with_block_time(1000);
-    let info = mock_info("liquidtity0000", &[]);
-    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
-    assert_eq!(res, ContractError::Unauthorized {});
-}
-
-#[test]
-fn test_max_spread() {
-    assert_max_spread(
-        Some(Decimal::from_ratio(1200u128, 1u128)),
-        Some(Decimal::percent(1)),
-        Uint128::from(1200000000u128),
-        Uint128::from(989999u128),
-        Uint128::zero(),
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        provide_msg(11u128, false),
     )
     .unwrap_err();
+    assert_eq!(err, ContractError::MinLpToReceiveAssertion {});
 
-    assert_max_spread(
-        Some(Decimal::from_ratio(1200u128, 1u128)),
-        Some(Decimal::percent(1)),
-        Uint128::from(1200000000u128),
-        Uint128::from(990000u128),
-        Uint128::zero(),
-    )
-    .unwrap();
-
-    assert_max_spread(
-        None,
-        Some(Decimal::percent(1)),
-        Uint128::zero(),
-        Uint128::from(989999u128),
-        Uint128::from(10001u128),
+    // The minimum also applies to the LP tokens that are staked in the Generator
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        provide_msg(11u128, true),
     )
     .unwrap_err();
+    assert_eq!(err, ContractError::MinLpToReceiveAssertion {});
 
-    assert_max_spread(
-        None,
-        Some(Decimal::percent(1)),
-        Uint128::zero(),
-        Uint128::from(990000u128),
-        Uint128::from(10000u128),
-    )
-    .unwrap();
-}
-
-#[test]
-fn test_deduct() {
-    let mut deps = mock_dependencies(&[]);
-
-    let tax_rate = Decimal::percent(2);
-    let tax_cap = Uint128::from(1_000_000u128);
-    deps.querier.with_tax(
-        Decimal::percent(2),
-        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
-    );
-
-    let amount = Uint128::new(1000_000_000u128);
-    let expected_after_amount = std::cmp::max(
-        amount.checked_sub(amount * tax_rate).unwrap(),
-        amount.checked_sub(tax_cap).unwrap(),
-    );
-
-    let after_amount = (Asset {
-        info: AssetInfo::NativeToken {
-            denom: "uusd".to_string(),
-        },
-        amount,
-    })
-    .deduct_tax(&deps.as_ref().querier)
-    .unwrap();
-
-    assert_eq!(expected_after_amount, after_amount.amount);
-}
-
-#[test]
-fn test_query_pool() {
-    let total_share_amount = Uint128::from(111u128);
-    let asset_0_amount = Uint128::from(222u128);
-    let asset_1_amount = Uint128::from(333u128);
-    let mut deps = mock_dependencies(&[Coin {
-        denom: "uusd".to_string(),
-        amount: asset_0_amount,
-    }]);
+    let res = execute(deps.as_mut(), mock_env(), info, provide_msg(10u128, false)).unwrap();
+    assert_eq!(res.attributes[4], attr("share", "10"));
 
-    deps.querier.with_token_balances(&[
-        (
-            &String::from("asset0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_1_amount)],
-        ),
-        (
-            &String::from("liquidity0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share_amount)],
-        ),
-    ]);
+    // The reserves are 110 of each asset now, so withdrawing 50 of 100 LP tokens returns 55 of each asset
+    let withdraw_msg = |min_assets_to_receive: Vec<Asset>| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("addr0000"),
+            msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+                min_assets_to_receive: Some(min_assets_to_receive),
+                deadline: None,
+                callback: None,
+            })
+            .unwrap(),
+            amount: Uint128::new(50u128),
+        })
+    };
+    let info = mock_info("liquidity0000", &[]);
 
-    let msg = InstantiateMsg {
-        asset_infos: [
-            AssetInfo::NativeToken {
-                denom: "uusd".to_string(),
-            },
-            AssetInfo::Token {
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        withdraw_msg(vec![Asset {
+            info: AssetInfo::Token {
                 contract_addr: Addr::unchecked("asset0000"),
             },
-        ],
-        token_code_id: 10u64,
-        factory_addr: String::from("factory"),
-        init_params: None,
-    };
-
-    let env = mock_env();
-    let info = mock_info("addr0000", &[]);
-    // We can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
-
-    // Store liquidity token
-    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+            amount: Uint128::new(56u128),
+        }]),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::MinAssetsToReceiveAssertion {});
 
-    let res: PoolResponse = query_pool(deps.as_ref()).unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        withdraw_msg(vec![Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uluna".to_string(),
+            },
+            amount: Uint128::new(1u128),
+        }]),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::AssetMismatch {});
 
-    assert_eq!(
-        res.assets,
-        [
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        withdraw_msg(vec![
             Asset {
                 info: AssetInfo::NativeToken {
                     denom: "uusd".to_string(),
                 },
-                amount: asset_0_amount
+                amount: Uint128::new(55u128),
             },
             Asset {
                 info: AssetInfo::Token {
                     contract_addr: Addr::unchecked("asset0000"),
                 },
-                amount: asset_1_amount
-            }
-        ]
+                amount: Uint128::new(55u128),
+            },
+        ]),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes[3],
+        attr("refund_assets", "55uusd, 55asset0000")
     );
-    assert_eq!(res.total_share, total_share_amount);
 }
 
 #[test]
-fn test_query_share() {
-    let total_share_amount = Uint128::from(500u128);
-    let asset_0_amount = Uint128::from(250u128);
-    let asset_1_amount = Uint128::from(1000u128);
+fn simulate_provide_and_withdraw() {
+    let pool_amount = Uint128::new(1000000u128);
+
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
-        amount: asset_0_amount,
+        amount: pool_amount,
     }]);
-
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
     deps.querier.with_token_balances(&[
         (
-            &String::from("asset0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_1_amount)],
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &pool_amount)],
         ),
         (
-            &String::from("liquidity0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share_amount)],
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &pool_amount)],
         ),
     ]);
 
@@ -1233,123 +1499,2342 @@ fn test_query_share() {
 
     let env = mock_env();
     let info = mock_info("addr0000", &[]);
-    // We can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+    instantiate(deps.as_mut(), env, info, msg).unwrap();
 
-    // Store liquidity token
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [pool_amount, pool_amount]);
 
-    let res = query_share(deps.as_ref(), Uint128::new(250)).unwrap();
+    let assets = [
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::new(100000u128),
+        },
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            amount: Uint128::new(120000u128),
+        },
+    ];
 
-    assert_eq!(res[0].amount, Uint128::new(125));
-    assert_eq!(res[1].amount, Uint128::new(500));
-}
+    // The assets must belong to the pair
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::SimulateProvide {
+            assets: [
+                assets[0].clone(),
+                Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "uluna".to_string(),
+                    },
+                    amount: Uint128::new(120000u128),
+                },
+            ],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(ContractError::AssetMismatch {}.to_string())
+    );
 
-#[test]
-fn test_accumulate_prices() {
-    struct Case {
-        block_time: u64,
-        block_time_last: u64,
-        last0: u128,
-        last1: u128,
-        x_amount: u128,
-        y_amount: u128,
-    }
+    let res: SimulateProvideResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateProvide {
+                assets: assets.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.share, Uint128::new(100000u128));
+    assert!(res.imbalance_fees.iter().all(|fee| fee.amount.is_zero()));
 
-    struct Result {
-        block_time_last: u64,
-        price_x: u128,
-        price_y: u128,
-        is_some: bool,
-    }
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100000u128),
+            }],
+        ),
+        ExecuteMsg::ProvideLiquidity {
+            assets,
+            slippage_tolerance: Some(Decimal::percent(50)),
+            auto_stake: None,
+            receiver: None,
+            min_lp_to_receive: None,
+            deadline: None,
+            callback: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(res.attributes[4], attr("share", "100000"));
 
-    let price_precision = 10u128.pow(TWAP_PRECISION.into());
+    // The reserves are 1100000 uusd and 1120000 asset0000 now
+    let res: SimulateWithdrawResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateWithdraw {
+                lp_amount: Uint128::new(100000u128),
+                ask_asset_info: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.commission_amount, Uint128::zero());
+    assert_eq!(
+        format!("{}, {}", res.assets[0], res.assets[1]),
+        "110000uusd, 112000asset0000"
+    );
 
-    let test_cases: Vec<(Case, Result)> = vec![
-        (
-            Case {
-                block_time: 1000,
-                block_time_last: 0,
-                last0: 0,
-                last1: 0,
-                x_amount: 250,
-                y_amount: 500,
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("liquidity0000", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("addr0000"),
+            msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+                min_assets_to_receive: None,
+                deadline: None,
+                callback: None,
+            })
+            .unwrap(),
+            amount: Uint128::new(100000u128),
+        }),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes[3],
+        attr("refund_assets", "110000uusd, 112000asset0000")
+    );
+
+    let ask_asset_info = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let res: SimulateWithdrawResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateWithdraw {
+                lp_amount: Uint128::new(100000u128),
+                ask_asset_info: Some(ask_asset_info.clone()),
             },
-            Result {
-                block_time_last: 1000,
-                price_x: 2000, // 500/250*1000
-                price_y: 500,  // 250/500*1000
-                is_some: true,
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("liquidity0000", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("addr0000"),
+            msg: to_binary(&Cw20HookMsg::WithdrawLiquidityOneAsset {
+                ask_asset_info: ask_asset_info.clone(),
+                min_amount: None,
+            })
+            .unwrap(),
+            amount: Uint128::new(100000u128),
+        }),
+    )
+    .unwrap();
+    assert_eq!(res.assets.len(), 1);
+    assert_eq!(res.assets[0].info, ask_asset_info);
+    assert_eq!(
+        execute_res.attributes[4],
+        attr("return_amount", res.assets[0].amount.to_string())
+    );
+    assert_eq!(
+        execute_res.attributes[5],
+        attr("commission_amount", res.commission_amount.to_string())
+    );
+    assert!(!res.commission_amount.is_zero());
+}
+
+#[test]
+fn spot_price_and_price_impact() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(1000000u128),
+    }]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &Uint128::new(2000000u128),
+        )],
+    )]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
             },
-        ),
-        // Same block height, no changes
-        (
-            Case {
-                block_time: 1000,
-                block_time_last: 1000,
-                last0: 1 * price_precision,
-                last1: 2 * price_precision,
-                x_amount: 250,
-                y_amount: 500,
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
             },
-            Result {
-                block_time_last: 1000,
-                price_x: 1,
-                price_y: 2,
-                is_some: false,
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    // The pool is empty before the reserves are stored
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::SpotPrice {
+            offer_asset_info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, StdError::generic_err("The pool is empty"));
+
+    store_reserves(
+        deps.as_mut(),
+        [Uint128::new(1000000u128), Uint128::new(2000000u128)],
+    );
+
+    let res: SpotPriceResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SpotPrice {
+                offer_asset_info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.price, Decimal::from_ratio(2u128, 1u128));
+
+    let res: SpotPriceResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SpotPrice {
+                offer_asset_info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.price, Decimal::from_ratio(1u128, 2u128));
+
+    let offer_asset = Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: Uint128::new(100000u128),
+    };
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::PriceImpact {
+            offer_asset: Asset {
+                amount: Uint128::zero(),
+                ..offer_asset.clone()
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(ContractError::InvalidZeroAmount {}.to_string())
+    );
+
+    // The swap returns 181818 before the commission of 545 instead of the 200000 at the spot price
+    let res: PriceImpactResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PriceImpact {
+                offer_asset: offer_asset.clone(),
             },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let simulation: SimulationResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Simulation { offer_asset },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(simulation.return_amount, Uint128::new(181273u128));
+    assert_eq!(res.spot_price, Decimal::from_ratio(2u128, 1u128));
+    assert_eq!(
+        res.execution_price,
+        Decimal::from_ratio(181273u128, 100000u128)
+    );
+    assert_eq!(res.price_impact, Decimal::from_ratio(18182u128, 200000u128));
+}
+
+#[test]
+fn deadline() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(100u128),
+    }]);
+
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &Uint128::new(100u128))],
         ),
         (
-            Case {
-                block_time: 1500,
-                block_time_last: 1000,
-                last0: 500 * price_precision,
-                last1: 2000 * price_precision,
-                x_amount: 250,
-                y_amount: 500,
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(100u128))],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
             },
-            Result {
-                block_time_last: 1500,
-                price_x: 1500, // 500 + (500/250*500)
-                price_y: 2250, // 2000 + (250/500*500)
-                is_some: true,
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
             },
-        ),
-    ];
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
 
-    for test_case in test_cases {
-        let (case, result) = test_case;
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env, info, msg).unwrap();
 
-        let env = mock_env_with_block_time(case.block_time);
-        let config = accumulate_prices(
-            env,
-            &Config {
-                pair_info: PairInfo {
-                    asset_infos: [
-                        AssetInfo::NativeToken {
-                            denom: "uusd".to_string(),
-                        },
-                        AssetInfo::Token {
-                            contract_addr: Addr::unchecked("asset0000"),
-                        },
-                    ],
-                    contract_addr: Addr::unchecked("pair"),
-                    liquidity_token: Addr::unchecked("lp_token"),
-                    pair_type: PairType::Xyk {}, // Implemented in mock querier
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(
+        deps.as_mut(),
+        [Uint128::new(100u128), Uint128::new(100u128)],
+    );
+
+    let now = mock_env().block.time.seconds();
+    let uusd = |amount: u128| Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: Uint128::new(amount),
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(10u128),
+        }],
+    );
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: uusd(10u128),
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: Some(now - 1),
+        callback: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::DeadlineExpired {});
+
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: [
+            uusd(10u128),
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
                 },
-                factory_addr: Addr::unchecked("factory"),
-                block_time_last: case.block_time_last,
-                price0_cumulative_last: Uint128::new(case.last0),
-                price1_cumulative_last: Uint128::new(case.last1),
+                amount: Uint128::new(10u128),
             },
-            Uint128::new(case.x_amount),
-            Uint128::new(case.y_amount),
-        )
-        .unwrap();
+        ],
+        slippage_tolerance: None,
+        auto_stake: None,
+        receiver: None,
+        min_lp_to_receive: None,
+        deadline: Some(now - 1),
+        callback: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(err, ContractError::DeadlineExpired {});
 
-        assert_eq!(result.is_some, config.is_some());
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::Swap {
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            deadline: Some(now - 1),
+            callback: None,
+        })
+        .unwrap(),
+        amount: Uint128::new(10u128),
+    });
+    let err = execute(deps.as_mut(), mock_env(), mock_info("asset0000", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::DeadlineExpired {});
 
-        if let Some(config) = config {
+    let withdraw_msg = |deadline: u64| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("addr0000"),
+            msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+                min_assets_to_receive: None,
+                deadline: Some(deadline),
+                callback: None,
+            })
+            .unwrap(),
+            amount: Uint128::new(10u128),
+        })
+    };
+    let info = mock_info("liquidity0000", &[]);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        withdraw_msg(now - 1),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::DeadlineExpired {});
+
+    // The deadline itself is still in time
+    execute(deps.as_mut(), mock_env(), info, withdraw_msg(now)).unwrap();
+}
+
+#[test]
+fn withdraw_liquidity_one_asset() {
+    let pool_amount = Uint128::new(1000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: pool_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &pool_amount)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [pool_amount, pool_amount]);
+
+    let ask_asset_info = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let withdraw_msg = |min_amount: Option<Uint128>| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("addr0000"),
+            msg: to_binary(&Cw20HookMsg::WithdrawLiquidityOneAsset {
+                ask_asset_info: ask_asset_info.clone(),
+                min_amount,
+            })
+            .unwrap(),
+            amount: Uint128::new(100000u128),
+        })
+    };
+
+    // 100000 uusd are withdrawn directly and 100000 asset0000 are swapped into 90000 uusd,
+    // out of which 270 uusd are charged as the commission
+    let res: WithdrawOneAssetSimulationResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateWithdrawOneAsset {
+                lp_amount: Uint128::new(100000u128),
+                ask_asset_info: ask_asset_info.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        WithdrawOneAssetSimulationResponse {
+            return_amount: Uint128::new(189730u128),
+            commission_amount: Uint128::new(270u128),
+        }
+    );
+
+    // The pair must belong to the ask asset
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidityOneAsset {
+            ask_asset_info: AssetInfo::NativeToken {
+                denom: "uluna".to_string(),
+            },
+            min_amount: None,
+        })
+        .unwrap(),
+        amount: Uint128::new(100000u128),
+    });
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("liquidity0000", &[]),
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::AssetMismatch {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("liquidity0000", &[]),
+        withdraw_msg(Some(Uint128::new(189731u128))),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::MinAmountAssertion {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("liquidity0000", &[]),
+        withdraw_msg(Some(Uint128::new(189730u128))),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("addr0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::new(189730u128),
+                }],
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("fee_address"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::new(44u128),
+                }],
+            })),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("liquidity0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::new(100000u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+
+    // The other asset stays in the pool
+    let res: PoolResponse = query_pool(deps.as_ref()).unwrap();
+    assert_eq!(res.assets[0].amount, Uint128::new(810226u128));
+    assert_eq!(res.assets[1].amount, pool_amount);
+
+    // The commission is added to the statistics without counting as a swap
+    let res: StatsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Stats {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        StatsResponse {
+            swap_count: 0,
+            assets: vec![AssetStats {
+                info: ask_asset_info,
+                volume_in: Uint128::zero(),
+                volume_out: Uint128::zero(),
+                lp_commission: Uint128::new(226u128),
+                maker_commission: Uint128::new(44u128),
+            }],
+        }
+    );
+}
+
+#[test]
+fn try_native_to_token() {
+    let total_share = Uint128::new(30000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+    let offer_amount = Uint128::new(1500000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount, /* user deposit must be pre-applied */
+    }]);
+
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+
+    // Normal swap
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        belief_price: None,
+        max_spread: Some(Decimal::percent(50)),
+        to: None,
+        deadline: None,
+        callback: None,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    let msg_transfer = res.messages.get(0).expect("no message");
+
+    // Current price is 1.5, so expected return without spread is 1000
+    // 952380952 = 20000000000 - (30000000000 * 20000000000) / (30000000000 + 1500000000)
+    let expected_ret_amount = Uint128::new(952_380_952u128);
+
+    // 47619047 = 1500000000 * (20000000000 / 30000000000) - 952380952
+    let expected_spread_amount = Uint128::new(47619047u128);
+
+    let expected_commission_amount = expected_ret_amount.multiply_ratio(3u128, 1000u128); // 0.3%
+    let expected_maker_fee_amount = expected_commission_amount.multiply_ratio(166u128, 1000u128); // 0.166
+
+    let expected_return_amount = expected_ret_amount
+        .checked_sub(expected_commission_amount)
+        .unwrap();
+    let expected_tax_amount = Uint128::zero(); // no tax for token
+
+    // Check simulation result
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount, /* user deposit must be pre-applied */
+        }],
+    )]);
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+
+    let simulation_res: SimulationResponse = query_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+    )
+    .unwrap();
+    assert_eq!(expected_return_amount, simulation_res.return_amount);
+    assert_eq!(expected_commission_amount, simulation_res.commission_amount);
+    assert_eq!(expected_spread_amount, simulation_res.spread_amount);
+
+    // Check reverse simulation result
+    let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            amount: expected_return_amount,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        (offer_amount.u128() as i128 - reverse_simulation_res.offer_amount.u128() as i128).abs()
+            < 5i128,
+        true
+    );
+    assert_eq!(
+        (expected_commission_amount.u128() as i128
+            - reverse_simulation_res.commission_amount.u128() as i128)
+            .abs()
+            < 5i128,
+        true
+    );
+    assert_eq!(
+        (expected_spread_amount.u128() as i128
+            - reverse_simulation_res.spread_amount.u128() as i128)
+            .abs()
+            < 5i128,
+        true
+    );
+
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "swap"),
+            attr("sender", "addr0000"),
+            attr("receiver", "addr0000"),
+            attr("offer_asset", "uusd"),
+            attr("ask_asset", "asset0000"),
+            attr("offer_amount", offer_amount.to_string()),
+            attr("return_amount", expected_return_amount.to_string()),
+            attr("tax_amount", expected_tax_amount.to_string()),
+            attr("spread_amount", expected_spread_amount.to_string()),
+            attr("commission_amount", expected_commission_amount.to_string()),
+            attr("maker_fee_amount", expected_maker_fee_amount.to_string()),
+        ]
+    );
+
+    assert_eq!(
+        &SubMsg {
+            msg: WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0000"),
+                    amount: Uint128::from(expected_return_amount),
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+            .into(),
+            id: 0,
+            gas_limit: None,
+            reply_on: ReplyOn::Never,
+        },
+        msg_transfer,
+    );
+}
+
+#[test]
+fn try_token_to_native() {
+    let total_share = Uint128::new(20000000000u128);
+    let asset_pool_amount = Uint128::new(30000000000u128);
+    let collateral_pool_amount = Uint128::new(20000000000u128);
+    let offer_amount = Uint128::new(1500000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(asset_pool_amount + offer_amount),
+            )],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+
+    // Unauthorized access; can not execute swap directy for token swap
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            amount: offer_amount,
+        },
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+        callback: None,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    // Normal sell
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: offer_amount,
+        msg: to_binary(&Cw20HookMsg::Swap {
+            belief_price: None,
+            max_spread: Some(Decimal::percent(50)),
+            to: None,
+            deadline: None,
+            callback: None,
+        })
+        .unwrap(),
+    });
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("asset0000", &[]);
+
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    let msg_transfer = res.messages.get(0).expect("no message");
+
+    // Current price is 1.5, so expected return without spread is 1000
+    // 952380952,3809524 = 20000000000 - (30000000000 * 20000000000) / (30000000000 + 1500000000)
+    let expected_ret_amount = Uint128::new(952_380_952u128);
+
+    // 47619047 = 1500000000 * (20000000000 / 30000000000) - 952380952,3809524
+    let expected_spread_amount = Uint128::new(47619047u128);
+
+    let expected_commission_amount = expected_ret_amount.multiply_ratio(3u128, 1000u128); // 0.3%
+    let expected_maker_fee_amount = expected_commission_amount.multiply_ratio(166u128, 1000u128);
+    let expected_return_amount = expected_ret_amount
+        .checked_sub(expected_commission_amount)
+        .unwrap();
+    let expected_tax_amount = std::cmp::min(
+        Uint128::new(1000000u128),
+        expected_return_amount
+            .checked_sub(
+                expected_return_amount.multiply_ratio(Uint128::new(100u128), Uint128::new(101u128)),
+            )
+            .unwrap(),
+    );
+    // Check simulation res
+    // Return asset token balance as normal
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &(asset_pool_amount))],
+        ),
+    ]);
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+
+    let simulation_res: SimulationResponse = query_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            amount: offer_amount,
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        },
+    )
+    .unwrap();
+    assert_eq!(expected_return_amount, simulation_res.return_amount);
+    assert_eq!(expected_commission_amount, simulation_res.commission_amount);
+    assert_eq!(expected_spread_amount, simulation_res.spread_amount);
+
+    // Check reverse simulation result
+    let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            amount: expected_return_amount,
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        (offer_amount.u128() as i128 - reverse_simulation_res.offer_amount.u128() as i128).abs()
+            < 5i128,
+        true
+    );
+    assert_eq!(
+        (expected_commission_amount.u128() as i128
+            - reverse_simulation_res.commission_amount.u128() as i128)
+            .abs()
+            < 5i128,
+        true
+    );
+    assert_eq!(
+        (expected_spread_amount.u128() as i128
+            - reverse_simulation_res.spread_amount.u128() as i128)
+            .abs()
+            < 5i128,
+        true
+    );
+
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "swap"),
+            attr("sender", "addr0000"),
+            attr("receiver", "addr0000"),
+            attr("offer_asset", "asset0000"),
+            attr("ask_asset", "uusd"),
+            attr("offer_amount", offer_amount.to_string()),
+            attr("return_amount", expected_return_amount.to_string()),
+            attr("tax_amount", expected_tax_amount.to_string()),
+            attr("spread_amount", expected_spread_amount.to_string()),
+            attr("commission_amount", expected_commission_amount.to_string()),
+            attr("maker_fee_amount", expected_maker_fee_amount.to_string()),
+        ]
+    );
+
+    assert_eq!(
+        &SubMsg {
+            msg: CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("addr0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: expected_return_amount
+                        .checked_sub(expected_tax_amount)
+                        .unwrap(),
+                }],
+            })
+            .into(),
+            id: 0,
+            gas_limit: None,
+            reply_on: ReplyOn::Never,
+        },
+        msg_transfer,
+    );
+
+    // Failed due to trying to swap a non token (specifying an address of a non token contract)
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: offer_amount,
+        msg: to_binary(&Cw20HookMsg::Swap {
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            deadline: None,
+            callback: None,
+        })
+        .unwrap(),
+    });
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("liquidtity0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+}
+
+#[test]
+fn swap_exact_out_native_to_token() {
+    let total_share = Uint128::new(30000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+    let ask_amount = Uint128::new(1000000000u128);
+    let max_offer_amount = Uint128::new(2000000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+
+    let ask_asset = Asset {
+        info: AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+        amount: ask_amount,
+    };
+
+    // The exact output swap spends the same amount as the reverse simulation returns
+    let reverse_simulation_res: ReverseSimulationResponse =
+        query_reverse_simulation(deps.as_ref(), mock_env(), ask_asset.clone()).unwrap();
+    let expected_offer_amount = reverse_simulation_res.offer_amount;
+    let expected_refund_amount = max_offer_amount - expected_offer_amount;
+
+    // The max offer amount is too small
+    let too_small_offer_amount = expected_offer_amount - Uint128::new(1u128);
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount + too_small_offer_amount, /* user deposit must be pre-applied */
+        }],
+    )]);
+    let msg = ExecuteMsg::SwapExactOut {
+        ask_asset: ask_asset.clone(),
+        max_offer_amount: too_small_offer_amount,
+        to: None,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: too_small_offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::MaxOfferAmountAssertion {});
+
+    // The ask amount can't drain the ask pool
+    let msg = ExecuteMsg::SwapExactOut {
+        ask_asset: Asset {
+            amount: asset_pool_amount,
+            ..ask_asset.clone()
+        },
+        max_offer_amount: too_small_offer_amount,
+        to: None,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: too_small_offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::AskAmountExceedsPool {});
+
+    // Normal exact output swap
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount + max_offer_amount, /* user deposit must be pre-applied */
+        }],
+    )]);
+    let msg = ExecuteMsg::SwapExactOut {
+        ask_asset: ask_asset.clone(),
+        max_offer_amount,
+        to: None,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: max_offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+    assert_eq!(res.attributes[0], attr("action", "swap_exact_out"));
+    assert_eq!(
+        res.attributes[5],
+        attr("offer_amount", expected_offer_amount.to_string())
+    );
+    assert_eq!(
+        res.attributes[6],
+        attr("refund_amount", expected_refund_amount.to_string())
+    );
+    assert_eq!(
+        res.attributes[7],
+        attr("return_amount", ask_amount.to_string())
+    );
+
+    assert_eq!(
+        res.messages[0],
+        SubMsg {
+            msg: WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0000"),
+                    amount: ask_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+            .into(),
+            id: 0,
+            gas_limit: None,
+            reply_on: ReplyOn::Never,
+        },
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg {
+            msg: CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("addr0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: expected_refund_amount,
+                }],
+            }),
+            id: 0,
+            gas_limit: None,
+            reply_on: ReplyOn::Never,
+        },
+    );
+
+    // Asking for an asset that does not belong to the pair
+    let msg = ExecuteMsg::SwapExactOut {
+        ask_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "ukrw".to_string(),
+            },
+            amount: ask_amount,
+        },
+        max_offer_amount,
+        to: None,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: max_offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::AssetMismatch {});
+}
+
+#[test]
+fn swap_exact_out_token_to_native() {
+    let total_share = Uint128::new(20000000000u128);
+    let asset_pool_amount = Uint128::new(30000000000u128);
+    let collateral_pool_amount = Uint128::new(20000000000u128);
+    let ask_amount = Uint128::new(1000000000u128);
+    let max_offer_amount = Uint128::new(2000000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+
+    let ask_asset = Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: ask_amount,
+    };
+
+    let reverse_simulation_res: ReverseSimulationResponse =
+        query_reverse_simulation(deps.as_ref(), mock_env(), ask_asset.clone()).unwrap();
+    let expected_offer_amount = reverse_simulation_res.offer_amount;
+    let expected_refund_amount = max_offer_amount - expected_offer_amount;
+
+    // Unauthorized access; can not offer tokens directly
+    let msg = ExecuteMsg::SwapExactOut {
+        ask_asset: ask_asset.clone(),
+        max_offer_amount,
+        to: None,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    // Normal exact output swap
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(asset_pool_amount + max_offer_amount),
+            )],
+        ),
+    ]);
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: max_offer_amount,
+        msg: to_binary(&Cw20HookMsg::SwapExactOut {
+            ask_asset: ask_asset.clone(),
+            to: Some(String::from("addr0001")),
+        })
+        .unwrap(),
+    });
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("asset0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+    assert_eq!(
+        res.attributes[5],
+        attr("offer_amount", expected_offer_amount.to_string())
+    );
+    assert_eq!(
+        res.attributes[6],
+        attr("refund_amount", expected_refund_amount.to_string())
+    );
+    assert_eq!(
+        res.messages[0],
+        SubMsg {
+            msg: CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("addr0001"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: ask_amount,
+                }],
+            }),
+            id: 0,
+            gas_limit: None,
+            reply_on: ReplyOn::Never,
+        },
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg {
+            msg: WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0000"),
+                    amount: expected_refund_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+            .into(),
+            id: 0,
+            gas_limit: None,
+            reply_on: ReplyOn::Never,
+        },
+    );
+
+    // Only pair tokens can be offered
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: max_offer_amount,
+        msg: to_binary(&Cw20HookMsg::SwapExactOut {
+            ask_asset,
+            to: None,
+        })
+        .unwrap(),
+    });
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("liquidity0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+}
+
+#[test]
+fn swap_stats() {
+    let offer_amount = Uint128::new(1500000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(30000000000u128),
+    }]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &Uint128::new(20000000000u128),
+        )],
+    )]);
+
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let asset0000 = AssetInfo::Token {
+        contract_addr: Addr::unchecked("asset0000"),
+    };
+    let msg = InstantiateMsg {
+        asset_infos: [uusd.clone(), asset0000.clone()],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(
+        deps.as_mut(),
+        [Uint128::new(30000000000u128), Uint128::new(20000000000u128)],
+    );
+
+    let res: StatsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Stats {}).unwrap()).unwrap();
+    assert_eq!(res.swap_count, 0);
+    assert_eq!(res.assets, vec![]);
+
+    let attribute = |res: &Response, key: &str| -> Uint128 {
+        let attr = res.attributes.iter().find(|attr| attr.key == key).unwrap();
+        Uint128::from_str(&attr.value).unwrap()
+    };
+
+    // Sell uusd on the first day
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: uusd.clone(),
+            amount: offer_amount,
+        },
+        belief_price: None,
+        max_spread: Some(Decimal::percent(50)),
+        to: None,
+        deadline: None,
+        callback: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let first = execute(deps.as_mut(), mock_env_with_block_time(1000), info, msg).unwrap();
+
+    // Sell asset0000 two days later
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: offer_amount,
+        msg: to_binary(&Cw20HookMsg::Swap {
+            belief_price: None,
+            max_spread: Some(Decimal::percent(50)),
+            to: None,
+            deadline: None,
+            callback: None,
+        })
+        .unwrap(),
+    });
+    let second = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(2 * 86400 + 1000),
+        mock_info("asset0000", &[]),
+        msg,
+    )
+    .unwrap();
+
+    // The commission is charged in the ask asset and the Maker fee is a part of it
+    let expected_stats = |res: &Response, offer_info: &AssetInfo, ask_info: &AssetInfo| {
+        let commission_amount = attribute(res, "commission_amount");
+        let maker_fee_amount = attribute(res, "maker_fee_amount");
+        assert!(!maker_fee_amount.is_zero());
+        vec![
+            AssetStats {
+                info: offer_info.clone(),
+                volume_in: attribute(res, "offer_amount"),
+                volume_out: Uint128::zero(),
+                lp_commission: Uint128::zero(),
+                maker_commission: Uint128::zero(),
+            },
+            AssetStats {
+                info: ask_info.clone(),
+                volume_in: Uint128::zero(),
+                volume_out: attribute(res, "return_amount"),
+                lp_commission: commission_amount - maker_fee_amount,
+                maker_commission: maker_fee_amount,
+            },
+        ]
+    };
+    let first_day = expected_stats(&first, &uusd, &asset0000);
+    let third_day = expected_stats(&second, &asset0000, &uusd);
+
+    let res: StatsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Stats {}).unwrap()).unwrap();
+    assert_eq!(res.swap_count, 2);
+    assert_eq!(
+        res.assets,
+        vec![
+            AssetStats {
+                info: uusd.clone(),
+                volume_in: first_day[0].volume_in,
+                volume_out: third_day[1].volume_out,
+                lp_commission: third_day[1].lp_commission,
+                maker_commission: third_day[1].maker_commission,
+            },
+            AssetStats {
+                info: asset0000.clone(),
+                volume_in: third_day[0].volume_in,
+                volume_out: first_day[1].volume_out,
+                lp_commission: first_day[1].lp_commission,
+                maker_commission: first_day[1].maker_commission,
+            },
+        ]
+    );
+
+    let res: DailyStatsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::DailyStats {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.days,
+        vec![
+            DailyStats {
+                day: 0,
+                swap_count: 1,
+                assets: first_day,
+            },
+            DailyStats {
+                day: 2,
+                swap_count: 1,
+                assets: third_day.clone(),
+            },
+        ]
+    );
+
+    let res: DailyStatsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::DailyStats {
+                start_after: Some(0),
+                limit: Some(1),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.days,
+        vec![DailyStats {
+            day: 2,
+            swap_count: 1,
+            assets: third_day,
+        }]
+    );
+}
+
+#[test]
+fn flash_loan() {
+    let total_share = Uint128::new(20000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+    let loan_amount = Uint128::new(1000000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+
+    let loan_asset = Asset {
+        info: AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+        amount: loan_amount,
+    };
+    let flash_loan_msg = ExecuteMsg::FlashLoan {
+        asset: loan_asset.clone(),
+        callback_contract: String::from("borrower"),
+        msg: Binary::from(b"arbitrage".to_vec()),
+    };
+    let repay_reply = Reply {
+        id: 2,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+
+    // The fee is 9 bps of the repaid amount
+    let expected_fee_amount = Uint128::new(900811u128);
+
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env, info, flash_loan_msg.clone()).unwrap();
+    assert_eq!(res.attributes[0], attr("action", "flash_loan"));
+    assert_eq!(
+        res.attributes[4],
+        attr("fee_amount", expected_fee_amount.to_string())
+    );
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("borrower"),
+                    amount: loan_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::reply_on_success(
+                WasmMsg::Execute {
+                    contract_addr: String::from("borrower"),
+                    msg: to_binary(&FlashLoanCallbackMsg::FlashLoanCallback {
+                        sender: String::from("addr0000"),
+                        asset: loan_asset.clone(),
+                        fee_amount: expected_fee_amount,
+                        msg: Binary::from(b"arbitrage".to_vec()),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                },
+                2,
+            ),
+        ]
+    );
+
+    // The pair can't be used while the loan is in progress
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::new(1000u128),
+        },
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+        callback: None,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info(
+        "borrower",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(1000u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::FlashLoanInProgress {});
+
+    // The borrower didn't pay the whole fee
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(asset_pool_amount + expected_fee_amount - Uint128::new(1u128)),
+            )],
+        ),
+    ]);
+    let res = reply(deps.as_mut(), mock_env(), repay_reply.clone()).unwrap_err();
+    assert_eq!(res, ContractError::FlashLoanNotRepaid {});
+
+    // The borrower repaid the loan together with the fee
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), env, info, flash_loan_msg.clone()).unwrap();
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(asset_pool_amount + expected_fee_amount),
+            )],
+        ),
+    ]);
+    let res = reply(deps.as_mut(), mock_env(), repay_reply.clone()).unwrap();
+    assert_eq!(res.attributes[0], attr("action", "repay_flash_loan"));
+    assert_eq!(res.attributes[2], attr("fees", "900811asset0000"));
+    assert_eq!(res.attributes[3], attr("maker_fees", "149534asset0000"));
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: String::from("asset0000"),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("fee_address"),
+                amount: Uint128::new(149534u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+    // The fee stays in the pool reserves without the Maker fee
+    assert_eq!(
+        RESERVES.load(&deps.storage).unwrap(),
+        [
+            collateral_pool_amount,
+            asset_pool_amount + Uint128::new(900811u128 - 149534u128)
+        ]
+    );
+
+    // The loan is repaid in the other pool asset (a flash swap)
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), env, info, flash_loan_msg).unwrap();
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(asset_pool_amount - loan_amount),
+            )],
+        ),
+    ]);
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount + Uint128::new(1600000000u128),
+        }],
+    )]);
+    let res = reply(deps.as_mut(), mock_env(), repay_reply).unwrap();
+    assert_eq!(res.attributes[2], attr("fees", "1440000uusd"));
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("fee_address"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(239040u128),
+            }],
+        }))]
+    );
+
+    // The flash loan fees are added to the statistics without counting as swaps
+    let res: StatsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Stats {}).unwrap()).unwrap();
+    let fee_stats = |info: AssetInfo, lp_commission: u128, maker_commission: u128| AssetStats {
+        info,
+        volume_in: Uint128::zero(),
+        volume_out: Uint128::zero(),
+        lp_commission: Uint128::new(lp_commission),
+        maker_commission: Uint128::new(maker_commission),
+    };
+    assert_eq!(
+        res,
+        StatsResponse {
+            swap_count: 0,
+            assets: vec![
+                fee_stats(
+                    AssetInfo::Token {
+                        contract_addr: Addr::unchecked("asset0000"),
+                    },
+                    751277u128,
+                    149534u128,
+                ),
+                fee_stats(
+                    AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    1200960u128,
+                    239040u128,
+                ),
+            ],
+        }
+    );
+}
+
+#[test]
+fn skim_and_sync() {
+    let total_share = Uint128::new(20000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+    let asset_donation = Uint128::new(700u128);
+    let collateral_donation = Uint128::new(500u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + collateral_donation,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(asset_pool_amount + asset_donation),
+            )],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+
+    // Donations are not part of the pool
+    let res: PoolResponse = query_pool(deps.as_ref()).unwrap();
+    assert_eq!(res.assets[0].amount, collateral_pool_amount);
+    assert_eq!(res.assets[1].amount, asset_pool_amount);
+
+    // Skim sends the donations to the recipient
+    let msg = ExecuteMsg::Skim {
+        to: String::from("addr0001"),
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "skim"),
+            attr("to", "addr0001"),
+            attr("assets", "500uusd, 700asset0000"),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("addr0001"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: collateral_donation,
+                }],
+            })),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0001"),
+                    amount: asset_donation,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+    assert_eq!(
+        RESERVES.load(&deps.storage).unwrap(),
+        [collateral_pool_amount, asset_pool_amount]
+    );
+
+    // Sync adds the donations to the pool
+    let msg = ExecuteMsg::Sync {};
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "sync"),
+            attr("reserves", "30000000500uusd, 20000000700asset0000"),
+        ]
+    );
+    assert!(res.messages.is_empty());
+
+    let res: PoolResponse = query_pool(deps.as_ref()).unwrap();
+    assert_eq!(
+        res.assets[0].amount,
+        collateral_pool_amount + collateral_donation
+    );
+    assert_eq!(res.assets[1].amount, asset_pool_amount + asset_donation);
+
+    // Nothing is left to skim
+    let msg = ExecuteMsg::Skim {
+        to: String::from("addr0001"),
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert!(res.messages.is_empty());
+}
+
+#[test]
+fn migrate_from_previous_versions() {
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+    )]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // A 1.0.0 pair did not track its reserves, so they are seeded from the pair balances
+    RESERVES.remove(deps.as_mut().storage);
+    cw2::set_contract_version(deps.as_mut().storage, "astroport-pair", "1.0.0").unwrap();
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        res.attributes[1],
+        attr("previous_contract_version", "1.0.0")
+    );
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [collateral_pool_amount, asset_pool_amount]
+    );
+    assert_eq!(
+        cw2::get_contract_version(deps.as_ref().storage)
+            .unwrap()
+            .version,
+        env!("CARGO_PKG_VERSION")
+    );
+
+    // A 1.1.0 pair keeps its reserves, so donations can still be skimmed
+    store_reserves(
+        deps.as_mut(),
+        [
+            collateral_pool_amount - Uint128::new(500u128),
+            asset_pool_amount - Uint128::new(700u128),
+        ],
+    );
+    cw2::set_contract_version(deps.as_mut().storage, "astroport-pair", "1.1.0").unwrap();
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [
+            collateral_pool_amount - Uint128::new(500u128),
+            asset_pool_amount - Uint128::new(700u128),
+        ]
+    );
+
+    // Unknown versions can not be migrated
+    cw2::set_contract_version(deps.as_mut().storage, "astroport-pair", "0.9.0").unwrap();
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(res, ContractError::MigrationError {});
+}
+
+#[test]
+fn test_max_spread() {
+    assert_max_spread(
+        Some(Decimal::from_ratio(1200u128, 1u128)),
+        Some(Decimal::percent(1)),
+        Uint128::from(1200000000u128),
+        Uint128::from(989999u128),
+        Uint128::zero(),
+    )
+    .unwrap_err();
+
+    assert_max_spread(
+        Some(Decimal::from_ratio(1200u128, 1u128)),
+        Some(Decimal::percent(1)),
+        Uint128::from(1200000000u128),
+        Uint128::from(990000u128),
+        Uint128::zero(),
+    )
+    .unwrap();
+
+    assert_max_spread(
+        None,
+        Some(Decimal::percent(1)),
+        Uint128::zero(),
+        Uint128::from(989999u128),
+        Uint128::from(10001u128),
+    )
+    .unwrap_err();
+
+    assert_max_spread(
+        None,
+        Some(Decimal::percent(1)),
+        Uint128::zero(),
+        Uint128::from(990000u128),
+        Uint128::from(10000u128),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_deduct() {
+    let mut deps = mock_dependencies(&[]);
+
+    let tax_rate = Decimal::percent(2);
+    let tax_cap = Uint128::from(1_000_000u128);
+    deps.querier.with_tax(
+        Decimal::percent(2),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let amount = Uint128::new(1000_000_000u128);
+    let expected_after_amount = std::cmp::max(
+        amount.checked_sub(amount * tax_rate).unwrap(),
+        amount.checked_sub(tax_cap).unwrap(),
+    );
+
+    let after_amount = (Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount,
+    })
+    .deduct_tax(&deps.as_ref().querier)
+    .unwrap();
+
+    assert_eq!(expected_after_amount, after_amount.amount);
+}
+
+#[test]
+fn test_query_pool() {
+    let total_share_amount = Uint128::from(111u128);
+    let asset_0_amount = Uint128::from(222u128);
+    let asset_1_amount = Uint128::from(333u128);
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: asset_0_amount,
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_1_amount)],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [asset_0_amount, asset_1_amount]);
+
+    let res: PoolResponse = query_pool(deps.as_ref()).unwrap();
+
+    assert_eq!(
+        res.assets,
+        [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: asset_0_amount
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                amount: asset_1_amount
+            }
+        ]
+    );
+    assert_eq!(res.total_share, total_share_amount);
+}
+
+#[test]
+fn test_query_share() {
+    let total_share_amount = Uint128::from(500u128);
+    let asset_0_amount = Uint128::from(250u128);
+    let asset_1_amount = Uint128::from(1000u128);
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: asset_0_amount,
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_1_amount)],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [asset_0_amount, asset_1_amount]);
+
+    let res = query_share(deps.as_ref(), Uint128::new(250)).unwrap();
+
+    assert_eq!(res[0].amount, Uint128::new(125));
+    assert_eq!(res[1].amount, Uint128::new(500));
+}
+
+#[test]
+fn test_accumulate_prices() {
+    struct Case {
+        block_time: u64,
+        block_time_last: u64,
+        last0: u128,
+        last1: u128,
+        x_amount: u128,
+        y_amount: u128,
+    }
+
+    struct Result {
+        block_time_last: u64,
+        price_x: u128,
+        price_y: u128,
+        is_some: bool,
+    }
+
+    let price_precision = 10u128.pow(TWAP_PRECISION.into());
+
+    let test_cases: Vec<(Case, Result)> = vec![
+        (
+            Case {
+                block_time: 1000,
+                block_time_last: 0,
+                last0: 0,
+                last1: 0,
+                x_amount: 250,
+                y_amount: 500,
+            },
+            Result {
+                block_time_last: 1000,
+                price_x: 2000, // 500/250*1000
+                price_y: 500,  // 250/500*1000
+                is_some: true,
+            },
+        ),
+        // Same block height, no changes
+        (
+            Case {
+                block_time: 1000,
+                block_time_last: 1000,
+                last0: 1 * price_precision,
+                last1: 2 * price_precision,
+                x_amount: 250,
+                y_amount: 500,
+            },
+            Result {
+                block_time_last: 1000,
+                price_x: 1,
+                price_y: 2,
+                is_some: false,
+            },
+        ),
+        (
+            Case {
+                block_time: 1500,
+                block_time_last: 1000,
+                last0: 500 * price_precision,
+                last1: 2000 * price_precision,
+                x_amount: 250,
+                y_amount: 500,
+            },
+            Result {
+                block_time_last: 1500,
+                price_x: 1500, // 500 + (500/250*500)
+                price_y: 2250, // 2000 + (250/500*500)
+                is_some: true,
+            },
+        ),
+    ];
+
+    for test_case in test_cases {
+        let (case, result) = test_case;
+
+        let env = mock_env_with_block_time(case.block_time);
+        let config = accumulate_prices(
+            env,
+            &Config {
+                pair_info: PairInfo {
+                    asset_infos: [
+                        AssetInfo::NativeToken {
+                            denom: "uusd".to_string(),
+                        },
+                        AssetInfo::Token {
+                            contract_addr: Addr::unchecked("asset0000"),
+                        },
+                    ],
+                    contract_addr: Addr::unchecked("pair"),
+                    liquidity_token: Addr::unchecked("lp_token"),
+                    pair_type: PairType::Xyk {}, // Implemented in mock querier
+                },
+                factory_addr: Addr::unchecked("factory"),
+                block_time_last: case.block_time_last,
+                price0_cumulative_last: Uint128::new(case.last0),
+                price1_cumulative_last: Uint128::new(case.last1),
+                dynamic_fee: None,
+                mint_maker_fee: false,
+                sqrt_k_last: Uint128::zero(),
+                fee_on_transfer: false,
+            },
+            Uint128::new(case.x_amount),
+            Uint128::new(case.y_amount),
+        )
+        .unwrap();
+
+        assert_eq!(result.is_some, config.is_some());
+
+        if let Some(config) = config {
             assert_eq!(config.2, result.block_time_last);
             assert_eq!(
                 config.0 / Uint128::from(price_precision),
@@ -1360,7 +3845,1037 @@ fn test_accumulate_prices() {
                 Uint128::new(result.price_y)
             );
         }
-    }
+    }
+}
+
+#[test]
+fn test_accumulate_prices_wraparound() {
+    let price_precision = 10u128.pow(TWAP_PRECISION.into());
+    let config = Config {
+        pair_info: PairInfo {
+            asset_infos: [
+                AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+            ],
+            contract_addr: Addr::unchecked("pair"),
+            liquidity_token: Addr::unchecked("lp_token"),
+            pair_type: PairType::Xyk {},
+        },
+        factory_addr: Addr::unchecked("factory"),
+        block_time_last: 1000,
+        price0_cumulative_last: Uint128::MAX - Uint128::new(100 * price_precision - 1),
+        price1_cumulative_last: Uint128::new(5),
+        dynamic_fee: None,
+        mint_maker_fee: false,
+        sqrt_k_last: Uint128::zero(),
+        fee_on_transfer: false,
+    };
+
+    // The price 0 accumulator overflows and wraps around
+    let (price0_cumulative, price1_cumulative, block_time) = accumulate_prices(
+        mock_env_with_block_time(1500),
+        &config,
+        Uint128::new(250),
+        Uint128::new(500),
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(block_time, 1500);
+    assert_eq!(price0_cumulative, Uint128::new(900 * price_precision));
+    assert_eq!(price1_cumulative, Uint128::new(5 + 250 * price_precision));
+
+    // Wrapping subtraction still returns the increment since the last accumulation
+    assert_eq!(
+        price0_cumulative.wrapping_sub(config.price0_cumulative_last),
+        Uint128::new(1000 * price_precision)
+    );
+
+    // An increment that doesn't fit in 128 bits is truncated instead of failing the operation
+    let increment = price_increment(u64::MAX, Uint128::MAX, Uint128::new(1));
+    assert_eq!(
+        increment,
+        Uint128::new(
+            (u64::MAX as u128)
+                .wrapping_mul(price_precision)
+                .wrapping_mul(u128::MAX)
+        )
+    );
+    accumulate_prices(
+        mock_env_with_block_time(1500),
+        &config,
+        Uint128::new(1),
+        Uint128::MAX,
+    )
+    .unwrap()
+    .unwrap();
+}
+
+#[test]
+fn test_observe() {
+    let total_share = Uint128::new(30000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+    let offer_amount = Uint128::new(1000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount, /* user deposit must be pre-applied */
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+
+    let msg = ExecuteMsg::IncreaseObservationsSize { size: 200 };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "increase_observations_size"),
+            attr("size", "200")
+        ]
+    );
+
+    // The swap stores an observation
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+        callback: None,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    execute(deps.as_mut(), env, info, msg).unwrap();
+
+    let cumulative_prices_then: CumulativePricesResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env_with_block_time(1000),
+            QueryMsg::CumulativePrices {},
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let cumulative_prices_now: CumulativePricesResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env_with_block_time(2000),
+            QueryMsg::CumulativePrices {},
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    let res: ObserveResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env_with_block_time(2000),
+            QueryMsg::Observe {
+                seconds_ago: vec![0, 1000],
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.observations[0].timestamp, 2000);
+    assert_eq!(res.observations[1].timestamp, 1000);
+    assert_eq!(
+        res.observations[0].price0_cumulative - res.observations[1].price0_cumulative,
+        Uint256::from(
+            cumulative_prices_now.price0_cumulative_last
+                - cumulative_prices_then.price0_cumulative_last
+        )
+    );
+    assert_eq!(
+        res.observations[0].price1_cumulative - res.observations[1].price1_cumulative,
+        Uint256::from(
+            cumulative_prices_now.price1_cumulative_last
+                - cumulative_prices_then.price1_cumulative_last
+        )
+    );
+
+    // There are no observations before the swap
+    let res = query(
+        deps.as_ref(),
+        mock_env_with_block_time(2000),
+        QueryMsg::Observe {
+            seconds_ago: vec![1001],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        res,
+        StdError::generic_err(
+            "The requested observation is older than the oldest stored observation"
+        )
+    );
+}
+
+#[test]
+fn dynamic_fee() {
+    let total_share = Uint128::new(30000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+    let offer_amount = Uint128::new(3000000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+
+    let simulate = |deps: Deps, time: u64| -> SimulationResponse {
+        query_simulation(
+            deps,
+            mock_env_with_block_time(time),
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::new(1000000u128),
+            },
+        )
+        .unwrap()
+    };
+
+    // The pair charges the factory fee by default
+    assert_eq!(
+        simulate(deps.as_ref(), 1000).total_fee_rate,
+        Decimal::from_ratio(30u128, 10000u128)
+    );
+
+    let params = DynamicFeeParams {
+        min_fee_bps: 10,
+        max_fee_bps: 100,
+        window: 1000,
+        max_volatility_bps: 1000,
+    };
+
+    // Only the factory owner can update the config
+    let msg = ExecuteMsg::UpdateConfig {
+        params: to_binary(&XykPoolUpdateParams::EnableDynamicFee {
+            params: params.clone(),
+        })
+        .unwrap(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    // The floor can't be greater than the ceiling
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            params: to_binary(&XykPoolUpdateParams::EnableDynamicFee {
+                params: DynamicFeeParams {
+                    min_fee_bps: 101,
+                    ..params.clone()
+                },
+            })
+            .unwrap(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::IncorrectDynamicFeeParams {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        mock_info("owner", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(res.attributes, vec![attr("action", "update_config")]);
+
+    let res: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(
+        from_binary::<XykPoolConfig>(&res.params.unwrap()).unwrap(),
+        XykPoolConfig {
+            dynamic_fee: Some(params.clone()),
+            mint_maker_fee: false,
+            fee_on_transfer: false,
+        }
+    );
+
+    // Without price history the pair charges the floor fee
+    assert_eq!(
+        simulate(deps.as_ref(), 1000).total_fee_rate,
+        Decimal::permille(1)
+    );
+
+    // Start the price history
+    execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Sync {},
+    )
+    .unwrap();
+
+    // The price didn't move
+    assert_eq!(
+        simulate(deps.as_ref(), 2000).total_fee_rate,
+        Decimal::permille(1)
+    );
+
+    // Move the price by more than the max volatility
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        belief_price: None,
+        max_spread: Some(Decimal::percent(50)),
+        to: None,
+        deadline: None,
+        callback: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    execute(deps.as_mut(), mock_env_with_block_time(2000), info, msg).unwrap();
+
+    // The next swaps pay the ceiling fee
+    let res = simulate(deps.as_ref(), 2000);
+    assert_eq!(res.total_fee_rate, Decimal::percent(1));
+    assert_eq!(
+        res.commission_amount,
+        (res.return_amount + res.commission_amount) * Decimal::percent(1)
+    );
+
+    // The fee goes down as the average price catches up with the current price
+    let fee_rate = simulate(deps.as_ref(), 2500).total_fee_rate;
+    assert!(fee_rate > Decimal::permille(1) && fee_rate < Decimal::percent(1));
+    assert_eq!(
+        simulate(deps.as_ref(), 3500).total_fee_rate,
+        Decimal::permille(1)
+    );
+
+    // Disabling the dynamic fee brings back the factory fee
+    execute(
+        deps.as_mut(),
+        mock_env_with_block_time(3500),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            params: to_binary(&XykPoolUpdateParams::DisableDynamicFee {}).unwrap(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        simulate(deps.as_ref(), 3500).total_fee_rate,
+        Decimal::from_ratio(30u128, 10000u128)
+    );
+}
+
+#[test]
+fn maker_fee_minting() {
+    let total_share = Uint128::new(1_000_000_000_000u128);
+    let pool_amount = Uint128::new(1_000_000_000_000u128);
+    let offer_amount = Uint128::new(1_000_000_000u128);
+
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [pool_amount, pool_amount]);
+
+    let update_config = |deps: DepsMut, sender: &str, params: XykPoolUpdateParams| {
+        execute(
+            deps,
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::UpdateConfig {
+                params: to_binary(&params).unwrap(),
+            },
+        )
+    };
+
+    let res = update_config(
+        deps.as_mut(),
+        "addr0000",
+        XykPoolUpdateParams::EnableMakerFeeMinting {},
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    update_config(
+        deps.as_mut(),
+        "owner",
+        XykPoolUpdateParams::EnableMakerFeeMinting {},
+    )
+    .unwrap();
+    let res: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert!(
+        from_binary::<XykPoolConfig>(&res.params.unwrap())
+            .unwrap()
+            .mint_maker_fee
+    );
+
+    // The swap does not send the Maker fee, the whole commission stays in the pool
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: offer_amount,
+            }],
+        ),
+        ExecuteMsg::Swap {
+            offer_asset: Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: offer_amount,
+            },
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            deadline: None,
+            callback: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 1);
+    assert!(res.attributes.contains(&attr("maker_fee_amount", "0")));
+    let return_amount = res
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "return_amount")
+        .map(|attr| Uint128::from_str(&attr.value).unwrap())
+        .unwrap();
+    let commission_amount = res
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "commission_amount")
+        .map(|attr| Uint128::from_str(&attr.value).unwrap())
+        .unwrap();
+
+    let reserves = RESERVES.load(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        reserves,
+        [pool_amount + offer_amount, pool_amount - return_amount]
+    );
+
+    // The first liquidity event mints the Maker share of the sqrt(k) growth to the fee address
+    let withdraw = |deps: DepsMut| {
+        execute(
+            deps,
+            mock_env(),
+            mock_info("liquidity0000", &[]),
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from("addr0000"),
+                msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+                    min_assets_to_receive: None,
+                    deadline: None,
+                    callback: None,
+                })
+                .unwrap(),
+                amount: Uint128::new(1_000_000u128),
+            }),
+        )
+        .unwrap()
+    };
+    let res = withdraw(deps.as_mut());
+
+    let sqrt_k = (reserves[0].u128() as f64 * reserves[1].u128() as f64).sqrt();
+    let maker_growth = (sqrt_k - pool_amount.u128() as f64) * 0.166;
+    let expected_share = total_share.u128() as f64 * maker_growth / (sqrt_k - maker_growth);
+    let maker_fee_share = res
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "maker_fee_share")
+        .map(|attr| Uint128::from_str(&attr.value).unwrap())
+        .unwrap();
+    assert!((maker_fee_share.u128() as f64 - expected_share).abs() <= 2.0);
+    assert_eq!(
+        res.messages.last().unwrap(),
+        &SubMsg::new(WasmMsg::Execute {
+            contract_addr: String::from("liquidity0000"),
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: String::from("fee_address"),
+                amount: maker_fee_share,
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+
+    // The minted share is worth about the Maker fee that would have been sent on the swap
+    let maker_fee_value = maker_fee_share.u128() as f64 * 2.0 * sqrt_k
+        / (total_share + maker_fee_share).u128() as f64;
+    let maker_fee = (commission_amount * Decimal::from_ratio(1660u128, 10000u128)).u128() as f64;
+    assert!((maker_fee_value - maker_fee).abs() / maker_fee < 0.01);
+
+    // The refund is computed with the minted share included in the total share
+    assert!(res.attributes.contains(&attr(
+        "refund_assets",
+        format!(
+            "{}uusd, {}asset0000",
+            reserves[0].multiply_ratio(1_000_000u128, total_share + maker_fee_share),
+            reserves[1].multiply_ratio(1_000_000u128, total_share + maker_fee_share)
+        )
+    )));
+
+    // Nothing accrues without swaps
+    let res = withdraw(deps.as_mut());
+    assert!(res.attributes.contains(&attr("maker_fee_share", "0")));
+
+    update_config(
+        deps.as_mut(),
+        "owner",
+        XykPoolUpdateParams::DisableMakerFeeMinting {},
+    )
+    .unwrap();
+    let res: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert!(
+        !from_binary::<XykPoolConfig>(&res.params.unwrap())
+            .unwrap()
+            .mint_maker_fee
+    );
+}
+
+#[test]
+fn circuit_breaker() {
+    let total_share = Uint128::new(30000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+
+    let swap = |deps: DepsMut, amount: u128| {
+        let msg = ExecuteMsg::Swap {
+            offer_asset: Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::new(amount),
+            },
+            belief_price: None,
+            max_spread: Some(Decimal::percent(50)),
+            to: None,
+            deadline: None,
+            callback: None,
+        };
+        let info = mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(amount),
+            }],
+        );
+        execute(deps, mock_env_with_block_time(1000), info, msg)
+    };
+    let status = |deps: Deps| -> StatusResponse {
+        from_binary(&query(deps, mock_env(), QueryMsg::Status {}).unwrap()).unwrap()
+    };
+
+    // Only the factory owner can set the guardian
+    let msg = ExecuteMsg::UpdateCircuitBreaker {
+        guardian: Some("guardian".to_string()),
+        max_price_change_bps: Some(500),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+    // Anyone else can't pause the pair
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Pause {},
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian", &[]),
+        ExecuteMsg::Pause {},
+    )
+    .unwrap();
+    assert_eq!(res.attributes, vec![attr("action", "pause")]);
+    assert_eq!(
+        status(deps.as_ref()),
+        StatusResponse {
+            paused: true,
+            guardian: Some(Addr::unchecked("guardian")),
+            max_price_change_bps: Some(500),
+        }
+    );
+
+    // Swaps and liquidity provision are stopped
+    assert_eq!(
+        swap(deps.as_mut(), 1000000u128).unwrap_err(),
+        ContractError::Paused {}
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ProvideLiquidity {
+            assets: [
+                Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: Addr::unchecked("asset0000"),
+                    },
+                    amount: Uint128::new(100u128),
+                },
+                Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    amount: Uint128::new(100u128),
+                },
+            ],
+            slippage_tolerance: None,
+            auto_stake: None,
+            receiver: None,
+            min_lp_to_receive: None,
+            deadline: None,
+            callback: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Paused {});
+
+    // Withdrawals stay open
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            min_assets_to_receive: None,
+            deadline: None,
+            callback: None,
+        })
+        .unwrap(),
+        amount: Uint128::new(3000u128),
+    });
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("liquidity0000", &[]),
+        msg,
+    )
+    .unwrap();
+
+    // Only the factory owner can unpause the pair
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian", &[]),
+        ExecuteMsg::Unpause {},
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Unpause {},
+    )
+    .unwrap();
+    assert!(!status(deps.as_ref()).paused);
+
+    // A small swap doesn't trip the breaker
+    swap(deps.as_mut(), 1000000u128).unwrap();
+    let reserves = RESERVES.load(&deps.storage).unwrap();
+
+    // The swap that moves the price too far fails and doesn't pause the pair
+    assert_eq!(
+        swap(deps.as_mut(), 3000000000u128).unwrap_err(),
+        ContractError::MaxPriceChangeAssertion {}
+    );
+    assert!(!status(deps.as_ref()).paused);
+
+    // The failed swap is reverted on chain, but the mock storage keeps its writes
+    store_reserves(deps.as_mut(), reserves);
+
+    // Swaps within the limit still go through in the same block
+    swap(deps.as_mut(), 1000000u128).unwrap();
+}
+
+#[test]
+fn reward_bearing_asset() {
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+    )]);
+
+    let msg = InstantiateMsg {
+        factory_addr: String::from("factory"),
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        init_params: Some(
+            to_binary(&RewardBearingInitParams {
+                reward_bearing: Some(RewardBearingParams {
+                    rewarder: "rewarder".to_string(),
+                    reward_asset: AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    generator: "generator".to_string(),
+                }),
+            })
+            .unwrap(),
+        ),
+    };
+    let res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // The pair creates a reward holder next to the LP token
+    assert_eq!(
+        res.messages[1],
+        SubMsg {
+            msg: WasmMsg::Instantiate {
+                code_id: 666u64,
+                msg: to_binary(&WhitelistInstantiateMsg {
+                    admins: vec![MOCK_CONTRACT_ADDR.to_string()],
+                    mutable: false,
+                })
+                .unwrap(),
+                funds: vec![],
+                admin: None,
+                label: String::from("Reward holder"),
+            }
+            .into(),
+            id: 3,
+            gas_limit: None,
+            reply_on: ReplyOn::Success
+        }
+    );
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_liquidity_token(deps.as_mut(), 3, "reward_holder".to_string());
+
+    // Only the generator can claim on behalf of a user
+    let msg = ExecuteMsg::ClaimRewardByGenerator {
+        user: "addr0000".to_string(),
+        user_share: Uint128::new(10u128),
+        total_share: Uint128::new(100u128),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err(
+            "Only the generator can use this method!"
+        ))
+    );
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info("generator", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: "rewarder".to_string(),
+                msg: to_binary(&RewarderExecuteMsg::ClaimRewards {
+                    recipient: Some("reward_holder".to_string()),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&ExecuteMsg::HandleReward {
+                    previous_reward_balance: Uint128::zero(),
+                    user: Addr::unchecked("addr0000"),
+                    user_share: Uint128::new(10u128),
+                    total_share: Uint128::new(100u128),
+                    receiver: None,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+
+    // The rewarder paid 1000 uusd to the reward holder
+    deps.querier.with_balance(&[(
+        &String::from("reward_holder"),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(1000u128),
+        }],
+    )]);
+    let msg = ExecuteMsg::HandleReward {
+        previous_reward_balance: Uint128::zero(),
+        user: Addr::unchecked("addr0000"),
+        user_share: Uint128::new(10u128),
+        total_share: Uint128::new(100u128),
+        receiver: None,
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: "reward_holder".to_string(),
+            msg: to_binary(&WhitelistExecuteMsg::<Empty>::Execute {
+                msgs: vec![CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "addr0000".to_string(),
+                    amount: vec![Coin {
+                        denom: "uusd".to_string(),
+                        amount: Uint128::new(100u128),
+                    }],
+                })],
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("claimed_reward_to_pool", "1000"),
+            attr("user", "addr0000"),
+            attr("receiver", "addr0000"),
+            attr("sent_reward", "100"),
+        ]
+    );
+}
+
+#[test]
+fn claim_reward_without_reward_bearing_asset() {
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+    )]);
+
+    let msg = InstantiateMsg {
+        factory_addr: String::from("factory"),
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        init_params: None,
+    };
+    let res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    assert_eq!(res.messages.len(), 1);
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimReward { receiver: None },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err(
+            "The pair has no reward-bearing asset"
+        ))
+    );
 }
 
 fn mock_env_with_block_time(time: u64) -> Env {
@@ -119,11 +119,11 @@ fn test_provide_and_withdraw_liquidity() {
             vec![
                 Coin {
                     denom: "uusd".to_string(),
-                    amount: Uint128::new(233u128),
+                    amount: Uint128::new(20_000u128),
                 },
                 Coin {
                     denom: "uluna".to_string(),
-                    amount: Uint128::new(200u128),
+                    amount: Uint128::new(20_000u128),
                 },
             ],
         )
@@ -150,25 +150,9 @@ fn test_provide_and_withdraw_liquidity() {
         ],
     );
 
-    // When dealing with native tokens the transfer should happen before the contract call, which cw-multitest doesn't support
-    router
-        .init_bank_balance(
-            &pair_instance,
-            vec![
-                Coin {
-                    denom: "uusd".to_string(),
-                    amount: Uint128::new(100u128),
-                },
-                Coin {
-                    denom: "uluna".to_string(),
-                    amount: Uint128::new(100u128),
-                },
-            ],
-        )
-        .unwrap();
-
     // Provide liquidity
-    let (msg, coins) = provide_liquidity_msg(Uint128::new(100), Uint128::new(100), None, None);
+    let (msg, coins) =
+        provide_liquidity_msg(Uint128::new(10_000), Uint128::new(10_000), None, None);
     let res = router
         .execute_contract(alice_address.clone(), pair_instance.clone(), &msg, &coins)
         .unwrap();
@@ -180,20 +164,33 @@ fn test_provide_and_withdraw_liquidity() {
     assert_eq!(res.events[1].attributes[3], attr("receiver", "alice"),);
     assert_eq!(
         res.events[1].attributes[4],
-        attr("assets", "100uusd, 100uluna")
+        attr("assets", "10000uusd, 10000uluna")
     );
     assert_eq!(
         res.events[1].attributes[5],
-        attr("share", 100u128.to_string())
+        attr("share", 9_000u128.to_string())
     );
+    // The minimum liquidity amount is locked in the pair
     assert_eq!(res.events[3].attributes[1], attr("action", "mint"));
-    assert_eq!(res.events[3].attributes[2], attr("to", "alice"));
-    assert_eq!(res.events[3].attributes[3], attr("amount", 100.to_string()));
+    assert_eq!(
+        res.events[3].attributes[2],
+        attr("to", pair_instance.as_str())
+    );
+    assert_eq!(
+        res.events[3].attributes[3],
+        attr("amount", 1_000u128.to_string())
+    );
+    assert_eq!(res.events[5].attributes[1], attr("action", "mint"));
+    assert_eq!(res.events[5].attributes[2], attr("to", "alice"));
+    assert_eq!(
+        res.events[5].attributes[3],
+        attr("amount", 9_000u128.to_string())
+    );
 
     // Provide liquidity for receiver
     let (msg, coins) = provide_liquidity_msg(
-        Uint128::new(100),
-        Uint128::new(100),
+        Uint128::new(10_000),
+        Uint128::new(10_000),
         Some("bob".to_string()),
         None,
     );
@@ -208,15 +205,18 @@ fn test_provide_and_withdraw_liquidity() {
     assert_eq!(res.events[1].attributes[3], attr("receiver", "bob"),);
     assert_eq!(
         res.events[1].attributes[4],
-        attr("assets", "100uusd, 100uluna")
+        attr("assets", "10000uusd, 10000uluna")
     );
     assert_eq!(
         res.events[1].attributes[5],
-        attr("share", 50u128.to_string())
+        attr("share", 10_000u128.to_string())
     );
     assert_eq!(res.events[3].attributes[1], attr("action", "mint"));
     assert_eq!(res.events[3].attributes[2], attr("to", "bob"));
-    assert_eq!(res.events[3].attributes[3], attr("amount", 50.to_string()));
+    assert_eq!(
+        res.events[3].attributes[3],
+        attr("amount", 10_000.to_string())
+    );
 }
 
 fn provide_liquidity_msg(
@@ -243,6 +243,9 @@ fn provide_liquidity_msg(
         slippage_tolerance: Option::from(slippage_tolerance),
         auto_stake: None,
         receiver,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
     };
 
     let coins = [
@@ -334,6 +337,7 @@ fn test_compatibility_of_tokens_with_different_precision() {
         pair_configs: vec![PairConfig {
             code_id: pair_code_id,
             maker_fee_bps: 0,
+            flash_loan_fee_bps: 0,
             pair_type: PairType::Xyk {},
             total_fee_bps: 0,
             is_disabled: false,
@@ -426,6 +430,9 @@ fn test_compatibility_of_tokens_with_different_precision() {
         slippage_tolerance: None,
         auto_stake: None,
         receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
     };
 
     app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &[])
@@ -439,6 +446,8 @@ fn test_compatibility_of_tokens_with_different_precision() {
             belief_price: None,
             max_spread: None,
             to: Some(user.to_string()),
+            deadline: None,
+            callback: None,
         })
         .unwrap(),
         amount: x_offer,
@@ -0,0 +1,2180 @@
+use crate::error::ContractError;
+use crate::math::{
+    amounts_for_liquidity, compute_swap_step, liquidity_for_amounts, mul_div, sqrt_price_at_tick,
+    tick_at_sqrt_price, ONE,
+};
+use crate::state::{
+    tick_from_key, tick_key, Config, PoolState, Position, TickInfo, CONFIG, FEE_GROWTH_PRECISION,
+    NEXT_POSITION_ID, OWNER_POSITIONS, POOL, POSITIONS, TICKS,
+};
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{
+    attr, entry_point, from_binary, to_binary, Addr, Api, Binary, CosmosMsg, Decimal, Deps,
+    DepsMut, Env, Fraction, MessageInfo, Order, Response, StdError, StdResult, Storage, Uint128,
+    WasmMsg,
+};
+use cw_storage_plus::{Bound, U64Key};
+
+use astroport::asset::{addr_validate_to_lower, Asset, AssetInfo, Callback, PairInfo};
+use astroport::factory::PairType;
+use astroport::pair::InstantiateMsg;
+use astroport::pair::{
+    ConfigResponse, CumulativePricesResponse, PoolResponse, ReverseSimulationResponse,
+    SimulationResponse, DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE, TWAP_PRECISION,
+};
+use astroport::pair_concentrated::{
+    ConcentratedPoolConfig, ConcentratedPoolParams, Cw20HookMsg, ExecuteMsg, PositionResponse,
+    PositionsResponse, QueryMsg, SimulatePositionResponse, StateResponse, DEFAULT_POSITIONS_LIMIT,
+    MAX_POSITIONS_LIMIT, MAX_TICK, MIN_TICK,
+};
+use astroport::querier::{query_pair_fee_info, FeeInfo};
+use astroport::stats::{query_daily_stats, query_stats, record_swap};
+use astroport::U256;
+use cw2::set_contract_version;
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use std::str::FromStr;
+
+/// Contract name that is used for migration.
+const CONTRACT_NAME: &str = "astroport-pair-concentrated";
+/// Contract version that is used for migration.
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// The custom pair type of the pair.
+pub const PAIR_TYPE: &str = "concentrated";
+
+/// ## Description
+/// Creates a new contract with the specified parameters in the [`InstantiateMsg`].
+/// Returns the [`Response`] with the specified attributes if the operation was successful, or a [`ContractError`] if
+/// the contract was not created.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **_info** is an object of type [`MessageInfo`].
+///
+/// * **msg** is a message of type [`InstantiateMsg`] which contains the basic settings for creating a contract.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    msg.asset_infos[0].check(deps.api)?;
+    msg.asset_infos[1].check(deps.api)?;
+
+    if msg.asset_infos[0] == msg.asset_infos[1] {
+        return Err(ContractError::DoublingAssets {});
+    }
+
+    if msg.init_params.is_none() {
+        return Err(ContractError::InitParamsNotFound {});
+    }
+
+    let params: ConcentratedPoolParams = from_binary(msg.init_params.as_ref().unwrap())?;
+
+    if params.tick_spacing == 0 || params.tick_spacing > MAX_TICK as u32 {
+        return Err(ContractError::IncorrectTickSpacing {});
+    }
+
+    // sqrt_price = sqrt(price) with 18 decimals
+    let sqrt_price =
+        (U256::from(params.initial_price.numerator()) * U256::from(ONE)).integer_sqrt();
+    if sqrt_price < sqrt_price_at_tick(MIN_TICK) || sqrt_price >= sqrt_price_at_tick(MAX_TICK) {
+        return Err(ContractError::IncorrectInitialPrice {});
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let config = Config {
+        pair_info: PairInfo {
+            contract_addr: env.contract.address.clone(),
+            // Positions are tracked by the pair itself instead of a LP token
+            liquidity_token: env.contract.address,
+            asset_infos: msg.asset_infos,
+            pair_type: PairType::Custom(PAIR_TYPE.to_string()),
+        },
+        factory_addr: addr_validate_to_lower(deps.api, msg.factory_addr.as_str())?,
+        block_time_last: 0,
+        price0_cumulative_last: Uint128::zero(),
+        price1_cumulative_last: Uint128::zero(),
+        tick_spacing: params.tick_spacing,
+    };
+
+    CONFIG.save(deps.storage, &config)?;
+    POOL.save(
+        deps.storage,
+        &PoolState {
+            sqrt_price: to_decimal(sqrt_price)?,
+            tick: tick_at_sqrt_price(sqrt_price),
+            liquidity: Uint128::zero(),
+            fee_growth_global: [Uint128::zero(); 2],
+        },
+    )?;
+    NEXT_POSITION_ID.save(deps.storage, &1)?;
+
+    Ok(Response::new())
+}
+
+/// ## Description
+/// Exposes all the execute functions available in the contract.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **msg** is an object of type [`ExecuteMsg`].
+///
+/// ## Queries
+/// * **ExecuteMsg::Receive(msg)** Receives a message of type [`Cw20ReceiveMsg`] and processes
+/// it depending on the received template.
+///
+/// * **ExecuteMsg::Swap {
+///             offer_asset,
+///             belief_price,
+///             max_spread,
+///             to,
+///             deadline,
+///             callback,
+///         }** Performs a swap operation with the specified parameters.
+///
+/// * **ExecuteMsg::CreatePosition {
+///             lower_tick,
+///             upper_tick,
+///             assets,
+///             receiver,
+///             min_liquidity_to_receive,
+///             deadline,
+///         }** Opens a new position that provides liquidity between two ticks.
+///
+/// * **ExecuteMsg::IncreaseLiquidity {
+///             position_id,
+///             assets,
+///             min_liquidity_to_receive,
+///             deadline,
+///         }** Adds liquidity to an existing position.
+///
+/// * **ExecuteMsg::DecreaseLiquidity {
+///             position_id,
+///             liquidity,
+///             receiver,
+///             min_assets_to_receive,
+///             deadline,
+///             callback,
+///         }** Withdraws liquidity from a position together with the fees it earned.
+///
+/// * **ExecuteMsg::CollectFees { position_id, receiver }** Sends the fees earned by a position to the receiver.
+///
+/// * **ExecuteMsg::TransferPosition { position_id, recipient }** Changes the owner of a position.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
+        ExecuteMsg::Swap {
+            offer_asset,
+            belief_price,
+            max_spread,
+            to,
+            deadline,
+            callback,
+        } => {
+            assert_deadline(&env, deadline)?;
+            offer_asset.info.check(deps.api)?;
+            if !offer_asset.is_native_token() {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let to_addr = if let Some(to_addr) = to {
+                Some(addr_validate_to_lower(deps.api, &to_addr)?)
+            } else {
+                None
+            };
+
+            swap(
+                deps,
+                env,
+                info.clone(),
+                info.sender,
+                offer_asset,
+                belief_price,
+                max_spread,
+                to_addr,
+                callback,
+            )
+        }
+        ExecuteMsg::CreatePosition {
+            lower_tick,
+            upper_tick,
+            assets,
+            receiver,
+            min_liquidity_to_receive,
+            deadline,
+        } => {
+            assert_deadline(&env, deadline)?;
+            create_position(
+                deps,
+                env,
+                info,
+                lower_tick,
+                upper_tick,
+                assets,
+                receiver,
+                min_liquidity_to_receive,
+            )
+        }
+        ExecuteMsg::IncreaseLiquidity {
+            position_id,
+            assets,
+            min_liquidity_to_receive,
+            deadline,
+        } => {
+            assert_deadline(&env, deadline)?;
+            increase_liquidity(
+                deps,
+                env,
+                info,
+                position_id,
+                assets,
+                min_liquidity_to_receive,
+            )
+        }
+        ExecuteMsg::DecreaseLiquidity {
+            position_id,
+            liquidity,
+            receiver,
+            min_assets_to_receive,
+            deadline,
+            callback,
+        } => {
+            assert_deadline(&env, deadline)?;
+            decrease_liquidity(
+                deps,
+                env,
+                info,
+                position_id,
+                liquidity,
+                receiver,
+                min_assets_to_receive,
+                callback,
+            )
+        }
+        ExecuteMsg::CollectFees {
+            position_id,
+            receiver,
+        } => collect_fees(deps, info, position_id, receiver),
+        ExecuteMsg::TransferPosition {
+            position_id,
+            recipient,
+        } => transfer_position(deps, info, position_id, recipient),
+    }
+}
+
+/// ## Description
+/// Receives a message of type [`Cw20ReceiveMsg`] and processes it depending on the received template.
+/// If the template is not found in the received message, then an [`ContractError`] is returned,
+/// otherwise it returns the [`Response`] with the specified attributes if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **cw20_msg** is an object of type [`Cw20ReceiveMsg`]. This is the CW20 message that has to be processed.
+pub fn receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let contract_addr = info.sender.clone();
+    match from_binary(&cw20_msg.msg)? {
+        Cw20HookMsg::Swap {
+            belief_price,
+            max_spread,
+            to,
+            deadline,
+            callback,
+        } => {
+            assert_deadline(&env, deadline)?;
+            // Only asset contract can execute this message
+            let config: Config = CONFIG.load(deps.storage)?;
+            let offer_asset_info = AssetInfo::Token { contract_addr };
+            if !config
+                .pair_info
+                .asset_infos
+                .iter()
+                .any(|a| a.equal(&offer_asset_info))
+            {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let to_addr = if let Some(to_addr) = to {
+                Some(addr_validate_to_lower(deps.api, to_addr.as_str())?)
+            } else {
+                None
+            };
+
+            swap(
+                deps,
+                env,
+                info,
+                Addr::unchecked(cw20_msg.sender),
+                Asset {
+                    info: offer_asset_info,
+                    amount: cw20_msg.amount,
+                },
+                belief_price,
+                max_spread,
+                to_addr,
+                callback,
+            )
+        }
+    }
+}
+
+/// ## Description
+/// Opens a new position that provides liquidity between `lower_tick` and `upper_tick`.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified
+/// attributes if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **lower_tick** is an object of type [`i32`]. This is the lower tick of the position.
+///
+/// * **upper_tick** is an object of type [`i32`]. This is the upper tick of the position.
+///
+/// * **assets** is an array with two objects of type [`Asset`]. These are the maximum amounts of assets to deposit.
+///
+/// * **receiver** is an [`Option`] field of type [`String`]. This is the owner of the position.
+/// If no custom receiver is specified, the position is owned by the function caller.
+///
+/// * **min_liquidity_to_receive** is an [`Option`] field of type [`Uint128`]. This is the minimum amount
+/// of liquidity that the position must get.
+// NOTE - the address that wants to provide liquidity should approve the pair contract to pull its relevant tokens.
+#[allow(clippy::too_many_arguments)]
+pub fn create_position(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    lower_tick: i32,
+    upper_tick: i32,
+    assets: [Asset; 2],
+    receiver: Option<String>,
+    min_liquidity_to_receive: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    assert_ticks(&config, lower_tick, upper_tick)?;
+
+    let owner = if let Some(receiver) = receiver {
+        addr_validate_to_lower(deps.api, &receiver)?
+    } else {
+        info.sender.clone()
+    };
+
+    let mut position = Position {
+        owner,
+        lower_tick,
+        upper_tick,
+        liquidity: Uint128::zero(),
+        fee_growth_inside_last: [Uint128::zero(); 2],
+        fees_owed: [Uint128::zero(); 2],
+    };
+
+    let (messages, liquidity, amounts) =
+        deposit(deps.branch(), &env, &info, &config, &mut position, assets)?;
+    assert_min_liquidity_to_receive(liquidity, min_liquidity_to_receive)?;
+
+    let position_id = NEXT_POSITION_ID.load(deps.storage)?;
+    NEXT_POSITION_ID.save(deps.storage, &(position_id + 1))?;
+
+    POSITIONS.save(deps.storage, U64Key::new(position_id), &position)?;
+    OWNER_POSITIONS.save(
+        deps.storage,
+        (&position.owner, U64Key::new(position_id)),
+        &true,
+    )?;
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "create_position"),
+        attr("sender", info.sender.as_str()),
+        attr("owner", position.owner.as_str()),
+        attr("position_id", position_id.to_string()),
+        attr("lower_tick", lower_tick.to_string()),
+        attr("upper_tick", upper_tick.to_string()),
+        attr("liquidity", liquidity.to_string()),
+        attr("assets", format_amounts(&config, amounts)),
+    ]))
+}
+
+/// ## Description
+/// Adds liquidity to an existing position. Anyone can add liquidity to any position.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified
+/// attributes if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **position_id** is an object of type [`u64`]. This is the position to add liquidity to.
+///
+/// * **assets** is an array with two objects of type [`Asset`]. These are the maximum amounts of assets to deposit.
+///
+/// * **min_liquidity_to_receive** is an [`Option`] field of type [`Uint128`]. This is the minimum amount
+/// of liquidity that the position must get.
+pub fn increase_liquidity(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    position_id: u64,
+    assets: [Asset; 2],
+    min_liquidity_to_receive: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let mut position = load_position(deps.storage, position_id)?;
+
+    let (messages, liquidity, amounts) =
+        deposit(deps.branch(), &env, &info, &config, &mut position, assets)?;
+    assert_min_liquidity_to_receive(liquidity, min_liquidity_to_receive)?;
+
+    POSITIONS.save(deps.storage, U64Key::new(position_id), &position)?;
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "increase_liquidity"),
+        attr("sender", info.sender.as_str()),
+        attr("position_id", position_id.to_string()),
+        attr("liquidity", liquidity.to_string()),
+        attr("assets", format_amounts(&config, amounts)),
+    ]))
+}
+
+/// ## Description
+/// Withdraws liquidity from a position and sends the withdrawn assets together with the fees
+/// that the position earned to the receiver. The position is removed once it has no liquidity left.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified
+/// attributes if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **position_id** is an object of type [`u64`]. This is the position to withdraw liquidity from.
+///
+/// * **liquidity** is an [`Option`] field of type [`Uint128`]. This is the amount of liquidity to withdraw.
+/// All of the position liquidity is withdrawn if it is not specified.
+///
+/// * **receiver** is an [`Option`] field of type [`String`]. This is the receiver of the withdrawn assets.
+/// If no custom receiver is specified, the assets are sent to the position owner.
+///
+/// * **min_assets_to_receive** is an [`Option`] field of type [`Vec<Asset>`]. These are the minimum
+/// amounts of pool assets to withdraw, not counting the fees.
+///
+/// ## Executor
+/// Only the position owner can execute this.
+///
+/// * **callback** is an [`Option`] field of type [`Callback`]. This is the contract that receives
+/// the withdrawn assets and fees along with a message.
+#[allow(clippy::too_many_arguments)]
+pub fn decrease_liquidity(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    position_id: u64,
+    liquidity: Option<Uint128>,
+    receiver: Option<String>,
+    min_assets_to_receive: Option<Vec<Asset>>,
+    callback: Option<Callback>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    let mut position = load_position(deps.storage, position_id)?;
+
+    if position.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let liquidity = liquidity.unwrap_or(position.liquidity);
+    if liquidity.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    if liquidity > position.liquidity {
+        return Err(ContractError::InsufficientPositionLiquidity {});
+    }
+
+    assert_callback(deps.api, &callback, receiver.is_some())?;
+    let receiver = if let Some(callback) = &callback {
+        Addr::unchecked(&callback.contract)
+    } else if let Some(receiver) = receiver {
+        addr_validate_to_lower(deps.api, &receiver)?
+    } else {
+        info.sender.clone()
+    };
+
+    let mut pool = POOL.load(deps.storage)?;
+    update_cumulative_prices(deps.storage, &env, &mut config, &pool)?;
+
+    let amounts = modify_position(deps.storage, &mut pool, &mut position, liquidity, false)?;
+    assert_min_assets_to_receive(&config, amounts, min_assets_to_receive)?;
+    POOL.save(deps.storage, &pool)?;
+
+    let fees = position.fees_owed;
+    position.fees_owed = [Uint128::zero(); 2];
+
+    if position.liquidity.is_zero() {
+        POSITIONS.remove(deps.storage, U64Key::new(position_id));
+        OWNER_POSITIONS.remove(deps.storage, (&position.owner, U64Key::new(position_id)));
+    } else {
+        POSITIONS.save(deps.storage, U64Key::new(position_id), &position)?;
+    }
+
+    let withdrawn = [amounts[0] + fees[0], amounts[1] + fees[1]];
+    let messages = match callback {
+        Some(callback) => callback.into_msgs(&deps.querier, &to_assets(&config, withdrawn))?,
+        None => transfer_messages(deps.as_ref(), &config, withdrawn, &receiver)?,
+    };
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "decrease_liquidity"),
+        attr("sender", info.sender.as_str()),
+        attr("receiver", receiver.as_str()),
+        attr("position_id", position_id.to_string()),
+        attr("liquidity", liquidity.to_string()),
+        attr("assets", format_amounts(&config, amounts)),
+        attr("fees", format_amounts(&config, fees)),
+    ]))
+}
+
+/// ## Description
+/// Sends the fees earned by a position to the receiver.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified
+/// attributes if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **position_id** is an object of type [`u64`]. This is the position to collect fees from.
+///
+/// * **receiver** is an [`Option`] field of type [`String`]. This is the receiver of the fees.
+/// If no custom receiver is specified, the fees are sent to the position owner.
+///
+/// ## Executor
+/// Only the position owner can execute this.
+pub fn collect_fees(
+    deps: DepsMut,
+    info: MessageInfo,
+    position_id: u64,
+    receiver: Option<String>,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let mut position = load_position(deps.storage, position_id)?;
+
+    if position.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let receiver = if let Some(receiver) = receiver {
+        addr_validate_to_lower(deps.api, &receiver)?
+    } else {
+        info.sender.clone()
+    };
+
+    // Modifying the position by zero liquidity only moves the earned fees to the owed fees
+    let mut pool = POOL.load(deps.storage)?;
+    modify_position(
+        deps.storage,
+        &mut pool,
+        &mut position,
+        Uint128::zero(),
+        true,
+    )?;
+
+    let fees = position.fees_owed;
+    position.fees_owed = [Uint128::zero(); 2];
+    POSITIONS.save(deps.storage, U64Key::new(position_id), &position)?;
+
+    let messages = transfer_messages(deps.as_ref(), &config, fees, &receiver)?;
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "collect_fees"),
+        attr("receiver", receiver.as_str()),
+        attr("position_id", position_id.to_string()),
+        attr("fees", format_amounts(&config, fees)),
+    ]))
+}
+
+/// ## Description
+/// Changes the owner of a position.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified
+/// attributes if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **position_id** is an object of type [`u64`]. This is the position to transfer.
+///
+/// * **recipient** is an object of type [`String`]. This is the new owner of the position.
+///
+/// ## Executor
+/// Only the position owner can execute this.
+pub fn transfer_position(
+    deps: DepsMut,
+    info: MessageInfo,
+    position_id: u64,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    let mut position = load_position(deps.storage, position_id)?;
+
+    if position.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let recipient = addr_validate_to_lower(deps.api, &recipient)?;
+
+    OWNER_POSITIONS.remove(deps.storage, (&position.owner, U64Key::new(position_id)));
+    OWNER_POSITIONS.save(deps.storage, (&recipient, U64Key::new(position_id)), &true)?;
+
+    position.owner = recipient;
+    POSITIONS.save(deps.storage, U64Key::new(position_id), &position)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "transfer_position"),
+        attr("sender", info.sender.as_str()),
+        attr("recipient", position.owner.as_str()),
+        attr("position_id", position_id.to_string()),
+    ]))
+}
+
+/// ## Description
+/// Adds the greatest liquidity that the specified assets can buy to a position and returns the
+/// messages that pull the CW20 deposits and refund the unused native deposits, the added liquidity
+/// and the deposited amounts.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **position** is an object of type [`Position`]. This is the position to add liquidity to.
+///
+/// * **assets** is an array with two objects of type [`Asset`]. These are the maximum amounts of assets to deposit.
+fn deposit(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    config: &Config,
+    position: &mut Position,
+    assets: [Asset; 2],
+) -> Result<(Vec<CosmosMsg>, Uint128, [Uint128; 2]), ContractError> {
+    assets[0].info.check(deps.api)?;
+    assets[1].info.check(deps.api)?;
+
+    for asset in assets.iter() {
+        asset.assert_sent_native_token_balance(info)?;
+    }
+
+    let asset_infos = &config.pair_info.asset_infos;
+    let max_amounts = get_max_amounts(config, &assets)?;
+
+    let mut config = config.clone();
+    let mut pool = POOL.load(deps.storage)?;
+    update_cumulative_prices(deps.storage, env, &mut config, &pool)?;
+
+    let liquidity =
+        compute_liquidity(&pool, position.lower_tick, position.upper_tick, max_amounts)?;
+
+    let amounts = modify_position(deps.storage, &mut pool, position, liquidity, true)?;
+    POOL.save(deps.storage, &pool)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for (i, asset_info) in asset_infos.iter().enumerate() {
+        match asset_info {
+            // If the asset is a token contract, then we need to execute a TransferFrom msg to receive assets
+            AssetInfo::Token { contract_addr } => {
+                if !amounts[i].is_zero() {
+                    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                        contract_addr: contract_addr.to_string(),
+                        msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                            owner: info.sender.to_string(),
+                            recipient: env.contract.address.to_string(),
+                            amount: amounts[i],
+                        })?,
+                        funds: vec![],
+                    }));
+                }
+            }
+            // The native assets were sent along with the message, so the unused part is refunded
+            AssetInfo::NativeToken { .. } => {
+                let refund_amount = max_amounts[i].checked_sub(amounts[i])?;
+                if !refund_amount.is_zero() {
+                    messages.push(
+                        Asset {
+                            info: asset_info.clone(),
+                            amount: refund_amount,
+                        }
+                        .into_msg(&deps.querier, info.sender.clone())?,
+                    );
+                }
+            }
+        }
+    }
+
+    Ok((messages, liquidity, amounts))
+}
+
+/// ## Description
+/// Adds liquidity to or removes liquidity from a position. Updates the ticks of the position,
+/// the active liquidity of the pool and the fees owed to the position. Returns the amounts of
+/// assets that enter the pool if `add` is true, otherwise the amounts of assets that leave the pool.
+/// ## Params
+/// * **storage** is an object of type [`Storage`].
+///
+/// * **pool** is an object of type [`PoolState`].
+///
+/// * **position** is an object of type [`Position`].
+///
+/// * **liquidity** is an object of type [`Uint128`]. This is the amount of liquidity to add or remove.
+///
+/// * **add** is an object of type [`bool`]. Determines whether the liquidity is added.
+fn modify_position(
+    storage: &mut dyn Storage,
+    pool: &mut PoolState,
+    position: &mut Position,
+    liquidity: Uint128,
+    add: bool,
+) -> Result<[Uint128; 2], ContractError> {
+    let mut lower_info = load_tick(storage, pool, position.lower_tick)?;
+    let mut upper_info = load_tick(storage, pool, position.upper_tick)?;
+
+    let fee_growth_inside = fee_growth_inside(
+        pool,
+        position.lower_tick,
+        position.upper_tick,
+        &lower_info,
+        &upper_info,
+    );
+    let earned_fees = earned_fees(position, fee_growth_inside)?;
+    position.fees_owed = [
+        position.fees_owed[0].checked_add(earned_fees[0])?,
+        position.fees_owed[1].checked_add(earned_fees[1])?,
+    ];
+    position.fee_growth_inside_last = fee_growth_inside;
+
+    if add {
+        position.liquidity = position.liquidity.checked_add(liquidity)?;
+        lower_info.liquidity_lower = lower_info.liquidity_lower.checked_add(liquidity)?;
+        upper_info.liquidity_upper = upper_info.liquidity_upper.checked_add(liquidity)?;
+    } else {
+        position.liquidity = position.liquidity.checked_sub(liquidity)?;
+        lower_info.liquidity_lower = lower_info.liquidity_lower.checked_sub(liquidity)?;
+        upper_info.liquidity_upper = upper_info.liquidity_upper.checked_sub(liquidity)?;
+    }
+    save_tick(storage, position.lower_tick, &lower_info)?;
+    save_tick(storage, position.upper_tick, &upper_info)?;
+
+    // The liquidity is active only if the current tick is within the position range
+    if position.lower_tick <= pool.tick && pool.tick < position.upper_tick {
+        pool.liquidity = if add {
+            pool.liquidity.checked_add(liquidity)?
+        } else {
+            pool.liquidity.checked_sub(liquidity)?
+        };
+    }
+
+    compute_amounts(
+        pool,
+        position.lower_tick,
+        position.upper_tick,
+        liquidity,
+        add,
+    )
+}
+
+/// ## Description
+/// Returns the maximum deposits of the pool assets in the order of the pair assets.
+/// Returns a [`ContractError`] if the assets do not match the pair assets.
+/// ## Params
+/// * **config** is an object of type [`Config`].
+///
+/// * **assets** is an array with two objects of type [`Asset`]. These are the maximum amounts of assets to deposit.
+fn get_max_amounts(config: &Config, assets: &[Asset; 2]) -> Result<[Uint128; 2], ContractError> {
+    let mut max_amounts = [Uint128::zero(); 2];
+    for (i, asset_info) in config.pair_info.asset_infos.iter().enumerate() {
+        max_amounts[i] = assets
+            .iter()
+            .find(|a| a.info.equal(asset_info))
+            .map(|a| a.amount)
+            .ok_or(ContractError::AssetMismatch {})?;
+    }
+
+    Ok(max_amounts)
+}
+
+/// ## Description
+/// Returns the greatest liquidity between the specified ticks that the maximum deposits can buy
+/// at the current price. Returns a [`ContractError`] if the deposits do not buy any liquidity.
+/// ## Params
+/// * **pool** is an object of type [`PoolState`].
+///
+/// * **lower_tick** is an object of type [`i32`]. This is the lower tick of the range.
+///
+/// * **upper_tick** is an object of type [`i32`]. This is the upper tick of the range.
+///
+/// * **max_amounts** is an array with two objects of type [`Uint128`]. These are the maximum deposits.
+fn compute_liquidity(
+    pool: &PoolState,
+    lower_tick: i32,
+    upper_tick: i32,
+    max_amounts: [Uint128; 2],
+) -> Result<Uint128, ContractError> {
+    let liquidity = liquidity_for_amounts(
+        U256::from(pool.sqrt_price.numerator()),
+        lower_tick,
+        upper_tick,
+        [max_amounts[0].u128(), max_amounts[1].u128()],
+    )
+    .ok_or(ContractError::SwapComputationError {})?;
+    if liquidity.is_zero() {
+        return Err(ContractError::ZeroLiquidity {});
+    }
+
+    Ok(to_uint128(liquidity)?)
+}
+
+/// ## Description
+/// Returns the amounts of assets that enter the pool if `add` is true, otherwise the amounts of
+/// assets that leave the pool when the liquidity between the specified ticks changes at the current price.
+/// ## Params
+/// * **pool** is an object of type [`PoolState`].
+///
+/// * **lower_tick** is an object of type [`i32`]. This is the lower tick of the range.
+///
+/// * **upper_tick** is an object of type [`i32`]. This is the upper tick of the range.
+///
+/// * **liquidity** is an object of type [`Uint128`]. This is the amount of liquidity to add or remove.
+///
+/// * **add** is an object of type [`bool`]. Determines whether the liquidity is added.
+fn compute_amounts(
+    pool: &PoolState,
+    lower_tick: i32,
+    upper_tick: i32,
+    liquidity: Uint128,
+    add: bool,
+) -> Result<[Uint128; 2], ContractError> {
+    let amounts = amounts_for_liquidity(
+        pool.tick,
+        U256::from(pool.sqrt_price.numerator()),
+        lower_tick,
+        upper_tick,
+        U256::from(liquidity.u128()),
+        add,
+    )
+    .ok_or(ContractError::SwapComputationError {})?;
+
+    Ok([to_uint128(amounts[0])?, to_uint128(amounts[1])?])
+}
+
+/// ## Description
+/// Loads a tick from the storage. A tick that is not used by any position yet assumes that all
+/// the fees were earned below the current tick.
+/// ## Params
+/// * **storage** is an object of type [`Storage`].
+///
+/// * **pool** is an object of type [`PoolState`].
+///
+/// * **tick** is an object of type [`i32`]. This is the tick to load.
+fn load_tick(storage: &dyn Storage, pool: &PoolState, tick: i32) -> StdResult<TickInfo> {
+    match TICKS.may_load(storage, tick_key(tick))? {
+        Some(tick_info) => Ok(tick_info),
+        None if tick <= pool.tick => Ok(TickInfo {
+            fee_growth_outside: pool.fee_growth_global,
+            ..TickInfo::default()
+        }),
+        None => Ok(TickInfo::default()),
+    }
+}
+
+/// ## Description
+/// Saves a tick to the storage or removes it if no position uses it anymore.
+/// ## Params
+/// * **storage** is an object of type [`Storage`].
+///
+/// * **tick** is an object of type [`i32`].
+///
+/// * **tick_info** is an object of type [`TickInfo`].
+fn save_tick(storage: &mut dyn Storage, tick: i32, tick_info: &TickInfo) -> StdResult<()> {
+    if tick_info.liquidity_lower.is_zero() && tick_info.liquidity_upper.is_zero() {
+        TICKS.remove(storage, tick_key(tick));
+        Ok(())
+    } else {
+        TICKS.save(storage, tick_key(tick), tick_info)
+    }
+}
+
+/// ## Description
+/// Returns the fees earned per unit of liquidity between two ticks.
+/// ## Params
+/// * **pool** is an object of type [`PoolState`].
+///
+/// * **lower_tick** is an object of type [`i32`].
+///
+/// * **upper_tick** is an object of type [`i32`].
+///
+/// * **lower_info** is an object of type [`TickInfo`]. This is the state of the lower tick.
+///
+/// * **upper_info** is an object of type [`TickInfo`]. This is the state of the upper tick.
+fn fee_growth_inside(
+    pool: &PoolState,
+    lower_tick: i32,
+    upper_tick: i32,
+    lower_info: &TickInfo,
+    upper_info: &TickInfo,
+) -> [Uint128; 2] {
+    let mut fee_growth_inside = [Uint128::zero(); 2];
+    for (i, fee_growth) in fee_growth_inside.iter_mut().enumerate() {
+        let global = pool.fee_growth_global[i];
+        let below = if pool.tick >= lower_tick {
+            lower_info.fee_growth_outside[i]
+        } else {
+            global.wrapping_sub(lower_info.fee_growth_outside[i])
+        };
+        let above = if pool.tick < upper_tick {
+            upper_info.fee_growth_outside[i]
+        } else {
+            global.wrapping_sub(upper_info.fee_growth_outside[i])
+        };
+
+        // The fee growth values wrap around, so only their differences are meaningful
+        *fee_growth = global.wrapping_sub(below).wrapping_sub(above);
+    }
+
+    fee_growth_inside
+}
+
+/// ## Description
+/// Returns the fees that a position earned since it was updated last time.
+/// ## Params
+/// * **position** is an object of type [`Position`].
+///
+/// * **fee_growth_inside** is an array with two objects of type [`Uint128`]. These are the current
+/// fees earned per unit of liquidity within the position range.
+fn earned_fees(position: &Position, fee_growth_inside: [Uint128; 2]) -> StdResult<[Uint128; 2]> {
+    let mut fees = [Uint128::zero(); 2];
+    for (i, fee) in fees.iter_mut().enumerate() {
+        let fee_growth = fee_growth_inside[i].wrapping_sub(position.fee_growth_inside_last[i]);
+        *fee = to_uint128(
+            mul_div(
+                U256::from(fee_growth.u128()),
+                U256::from(position.liquidity.u128()),
+                U256::from(FEE_GROWTH_PRECISION),
+                false,
+            )
+            .unwrap_or_default(),
+        )?;
+    }
+
+    Ok(fees)
+}
+
+/// ## Description
+/// Performs a swap with the specified parameters.
+/// Returns a [`ContractError`] on failure, otherwise returns the [`Response`] with the specified attributes if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **sender** is an object of type [`Addr`]. This is the sender of the swap operation.
+///
+/// * **offer_asset** is an object of type [`Asset`]. Proposed asset for swapping.
+///
+/// * **belief_price** is an object of type [`Option<Decimal>`]. Used to calculate the maximum swap spread.
+///
+/// * **max_spread** is an object of type [`Option<Decimal>`]. Sets the maximum spread of the swap operation.
+///
+/// * **to** is an object of type [`Option<Addr>`]. Sets the recipient of the swap operation.
+///
+/// * **callback** is an [`Option`] field of type [`Callback`]. This is the contract that receives
+/// the ask assets along with a message.
+#[allow(clippy::too_many_arguments)]
+pub fn swap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    offer_asset: Asset,
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    to: Option<Addr>,
+    callback: Option<Callback>,
+) -> Result<Response, ContractError> {
+    offer_asset.assert_sent_native_token_balance(&info)?;
+
+    if offer_asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    let pool = POOL.load(deps.storage)?;
+
+    let (zero_for_one, ask_asset_info) = swap_direction(&config, &offer_asset.info)?;
+
+    // Get fee info from the factory
+    let fee_info = query_fee_info(deps.as_ref(), &config)?;
+
+    let offer_amount = offer_asset.amount;
+    let result = compute_swap(
+        deps.storage,
+        &pool,
+        zero_for_one,
+        offer_amount,
+        true,
+        &fee_info,
+    )?;
+    let return_amount = result.amount_out.checked_sub(result.commission_amount)?;
+    let spread_amount = compute_spread(&pool, zero_for_one, offer_amount, result.amount_out)?;
+
+    // Check the max spread limit (if it was specified)
+    assert_max_spread(
+        belief_price,
+        max_spread,
+        offer_amount,
+        result.amount_out,
+        spread_amount,
+    )?;
+
+    // Compute the tax for the receiving asset (if it is a native one)
+    let return_asset = Asset {
+        info: ask_asset_info.clone(),
+        amount: return_amount,
+    };
+
+    let tax_amount = return_asset.compute_tax(&deps.querier)?;
+
+    // receiver is address receiving the new tokens (could be sender or to)
+    assert_callback(deps.api, &callback, to.is_some())?;
+    let receiver = match &callback {
+        Some(callback) => Addr::unchecked(&callback.contract),
+        None => to.unwrap_or_else(|| sender.clone()),
+    };
+
+    let (mut messages, callback_messages): (Vec<CosmosMsg>, Vec<CosmosMsg>) = match callback {
+        // The callback contract gets the return assets after all the other messages
+        Some(callback) => (vec![], callback.into_msgs(&deps.querier, &[return_asset])?),
+        None if return_amount.is_zero() => (vec![], vec![]),
+        None => (
+            vec![return_asset.into_msg(&deps.querier, receiver.clone())?],
+            vec![],
+        ),
+    };
+
+    // The Maker fee leaves the pool along with the return amount
+    if let Some(fee_address) = fee_info.fee_address {
+        if !result.maker_fee_amount.is_zero() {
+            messages.push(
+                Asset {
+                    info: ask_asset_info.clone(),
+                    amount: result.maker_fee_amount,
+                }
+                .into_msg(&deps.querier, fee_address)?,
+            );
+        }
+    }
+
+    // Accumulate prices for the assets in the pool
+    update_cumulative_prices(deps.storage, &env, &mut config, &pool)?;
+
+    // Update the fees earned outside of every crossed tick
+    for (tick, fee_growth_global) in result.crossed_ticks.iter() {
+        let mut tick_info = TICKS.load(deps.storage, tick_key(*tick))?;
+        for (outside, global) in tick_info
+            .fee_growth_outside
+            .iter_mut()
+            .zip(fee_growth_global.iter())
+        {
+            *outside = global.wrapping_sub(*outside);
+        }
+        TICKS.save(deps.storage, tick_key(*tick), &tick_info)?;
+    }
+    POOL.save(deps.storage, &result.pool)?;
+
+    record_swap(
+        deps.storage,
+        env.block.time.seconds(),
+        &offer_asset,
+        &Asset {
+            info: ask_asset_info.clone(),
+            amount: return_amount,
+        },
+        result.commission_amount,
+        result.maker_fee_amount,
+    )?;
+
+    messages.extend(callback_messages);
+
+    Ok(Response::new()
+        .add_messages(
+            // 1. send collateral tokens from the contract to a user
+            // 2. send inactive commission fees to the Maker contract
+            messages,
+        )
+        .add_attribute("action", "swap")
+        .add_attribute("sender", sender.as_str())
+        .add_attribute("receiver", receiver.as_str())
+        .add_attribute("offer_asset", offer_asset.info.to_string())
+        .add_attribute("ask_asset", ask_asset_info.to_string())
+        .add_attribute("offer_amount", offer_amount.to_string())
+        .add_attribute("return_amount", return_amount.to_string())
+        .add_attribute("tax_amount", tax_amount.to_string())
+        .add_attribute("spread_amount", spread_amount.to_string())
+        .add_attribute("commission_amount", result.commission_amount.to_string())
+        .add_attribute("maker_fee_amount", result.maker_fee_amount.to_string()))
+}
+
+/// ## Description
+/// This structure describes the result of a swap computed by [`compute_swap`].
+struct SwapResult {
+    /// The amount of offer assets that enter the pool
+    amount_in: Uint128,
+    /// The amount of ask assets that leave the range positions, including the commission
+    amount_out: Uint128,
+    /// The total commission charged on the ask assets
+    commission_amount: Uint128,
+    /// The part of the commission that is sent to the Maker
+    maker_fee_amount: Uint128,
+    /// The pool state after the swap
+    pool: PoolState,
+    /// The crossed ticks together with the global fee growth at the time they were crossed
+    crossed_ticks: Vec<(i32, [Uint128; 2])>,
+}
+
+/// ## Description
+/// Computes a swap through the ranges of liquidity without changing the storage.
+/// The commission is charged on the ask assets that leave every range and the part of it that
+/// does not go to the Maker is distributed to the liquidity of that range.
+/// ## Params
+/// * **storage** is an object of type [`Storage`].
+///
+/// * **pool** is an object of type [`PoolState`]. This is the pool state before the swap.
+///
+/// * **zero_for_one** is an object of type [`bool`]. Determines whether the first pool asset is swapped
+/// for the second one.
+///
+/// * **amount** is an object of type [`Uint128`]. This is the offer amount if `exact_in` is true,
+/// otherwise it is the ask amount including the commission.
+///
+/// * **exact_in** is an object of type [`bool`]. Determines whether `amount` is an offer amount.
+///
+/// * **fee_info** is an object of type [`FeeInfo`]. These are the fee rates of the pair.
+fn compute_swap(
+    storage: &dyn Storage,
+    pool: &PoolState,
+    zero_for_one: bool,
+    amount: Uint128,
+    exact_in: bool,
+    fee_info: &FeeInfo,
+) -> Result<SwapResult, ContractError> {
+    let ask_index = if zero_for_one { 1 } else { 0 };
+    let limit_tick = if zero_for_one { MIN_TICK } else { MAX_TICK };
+
+    let mut state = pool.clone();
+    let mut sqrt_price = U256::from(state.sqrt_price.numerator());
+    let mut remaining = U256::from(amount.u128());
+    let mut amount_in = Uint128::zero();
+    let mut amount_out = Uint128::zero();
+    let mut commission_amount = Uint128::zero();
+    let mut maker_fee_amount = Uint128::zero();
+    let mut crossed_ticks = vec![];
+
+    while !remaining.is_zero() {
+        let next_tick = next_initialized_tick(storage, state.tick, zero_for_one)?;
+        let target_sqrt_price = sqrt_price_at_tick(next_tick.unwrap_or(limit_tick));
+        let liquidity = U256::from(state.liquidity.u128());
+
+        let step = compute_swap_step(
+            sqrt_price,
+            target_sqrt_price,
+            liquidity,
+            remaining,
+            zero_for_one,
+            exact_in,
+        )
+        .ok_or(ContractError::SwapComputationError {})?;
+
+        remaining -= if exact_in {
+            step.amount_in
+        } else {
+            step.amount_out
+        };
+        sqrt_price = step.sqrt_price;
+
+        let step_amount_out = to_uint128(step.amount_out)?;
+        amount_in = amount_in.checked_add(to_uint128(step.amount_in)?)?;
+        amount_out = amount_out.checked_add(step_amount_out)?;
+
+        // Charge the commission on the ask assets that leave this range
+        let step_commission = step_amount_out * fee_info.total_fee_rate;
+        let step_maker_fee = if fee_info.fee_address.is_some() {
+            step_commission * fee_info.maker_fee_rate
+        } else {
+            Uint128::zero()
+        };
+        commission_amount = commission_amount.checked_add(step_commission)?;
+        maker_fee_amount = maker_fee_amount.checked_add(step_maker_fee)?;
+
+        if !liquidity.is_zero() {
+            let fee_growth = mul_div(
+                U256::from((step_commission - step_maker_fee).u128()),
+                U256::from(FEE_GROWTH_PRECISION),
+                liquidity,
+                false,
+            )
+            .ok_or(ContractError::SwapComputationError {})?;
+            state.fee_growth_global[ask_index] = state.fee_growth_global[ask_index]
+                .wrapping_add(Uint128::new(fee_growth.low_u128()));
+        }
+
+        if sqrt_price == target_sqrt_price {
+            match next_tick {
+                Some(tick) => {
+                    // Cross the tick and activate or deactivate the liquidity that starts or ends there
+                    let tick_info = TICKS.load(storage, tick_key(tick))?;
+                    crossed_ticks.push((tick, state.fee_growth_global));
+                    if zero_for_one {
+                        state.liquidity = state
+                            .liquidity
+                            .checked_add(tick_info.liquidity_upper)?
+                            .checked_sub(tick_info.liquidity_lower)?;
+                        state.tick = tick - 1;
+                    } else {
+                        state.liquidity = state
+                            .liquidity
+                            .checked_add(tick_info.liquidity_lower)?
+                            .checked_sub(tick_info.liquidity_upper)?;
+                        state.tick = tick;
+                    }
+                }
+                // The price reached the limit of the supported price range
+                None => break,
+            }
+        } else {
+            state.tick = tick_at_sqrt_price(sqrt_price);
+        }
+    }
+
+    if !remaining.is_zero() {
+        return Err(ContractError::InsufficientLiquidity {});
+    }
+
+    state.sqrt_price = to_decimal(sqrt_price)?;
+
+    Ok(SwapResult {
+        amount_in,
+        amount_out,
+        commission_amount,
+        maker_fee_amount,
+        pool: state,
+        crossed_ticks,
+    })
+}
+
+/// ## Description
+/// Returns the closest tick used by a position in the direction of the swap. The current tick
+/// is included when the price moves down because the price may still be above it.
+/// ## Params
+/// * **storage** is an object of type [`Storage`].
+///
+/// * **tick** is an object of type [`i32`]. This is the current tick.
+///
+/// * **zero_for_one** is an object of type [`bool`]. Determines whether the price moves down.
+fn next_initialized_tick(
+    storage: &dyn Storage,
+    tick: i32,
+    zero_for_one: bool,
+) -> StdResult<Option<i32>> {
+    let next = if zero_for_one {
+        if tick < MIN_TICK {
+            return Ok(None);
+        }
+        TICKS
+            .keys(
+                storage,
+                None,
+                Some(Bound::inclusive(tick_key(tick))),
+                Order::Descending,
+            )
+            .next()
+    } else {
+        let start = if tick < MIN_TICK {
+            None
+        } else {
+            Some(Bound::exclusive(tick_key(tick)))
+        };
+        TICKS.keys(storage, start, None, Order::Ascending).next()
+    };
+
+    Ok(next.map(|key| tick_from_key(&key)))
+}
+
+/// ## Description
+/// Returns whether the first pool asset is offered and the information about the ask asset.
+/// Returns a [`ContractError`] if `offer_asset_info` does not belong to the pair.
+/// ## Params
+/// * **config** is an object of type [`Config`].
+///
+/// * **offer_asset_info** is an object of type [`AssetInfo`]. This is the asset to swap.
+fn swap_direction(
+    config: &Config,
+    offer_asset_info: &AssetInfo,
+) -> Result<(bool, AssetInfo), ContractError> {
+    let asset_infos = &config.pair_info.asset_infos;
+    if offer_asset_info.equal(&asset_infos[0]) {
+        Ok((true, asset_infos[1].clone()))
+    } else if offer_asset_info.equal(&asset_infos[1]) {
+        Ok((false, asset_infos[0].clone()))
+    } else {
+        Err(ContractError::AssetMismatch {})
+    }
+}
+
+/// ## Description
+/// Returns the spread of a swap: the difference between the ask amount at the price before the
+/// swap and the ask amount that actually leaves the pool before the commission.
+/// ## Params
+/// * **pool** is an object of type [`PoolState`]. This is the pool state before the swap.
+///
+/// * **zero_for_one** is an object of type [`bool`]. Determines whether the first pool asset is offered.
+///
+/// * **offer_amount** is an object of type [`Uint128`]. This is the amount of offer assets.
+///
+/// * **amount_out** is an object of type [`Uint128`]. This is the amount of ask assets before the commission.
+fn compute_spread(
+    pool: &PoolState,
+    zero_for_one: bool,
+    offer_amount: Uint128,
+    amount_out: Uint128,
+) -> Result<Uint128, ContractError> {
+    let sqrt_price = U256::from(pool.sqrt_price.numerator());
+    let one = U256::from(ONE);
+    let price = sqrt_price * sqrt_price;
+
+    let expected_amount = if zero_for_one {
+        mul_div(U256::from(offer_amount.u128()), price, one * one, false)
+    } else {
+        mul_div(U256::from(offer_amount.u128()), one * one, price, false)
+    }
+    .ok_or(ContractError::SwapComputationError {})?;
+
+    Ok(to_uint128(expected_amount)
+        .unwrap_or(Uint128::MAX)
+        .saturating_sub(amount_out))
+}
+
+/// ## Description
+/// Accumulates the prices of the pool assets and saves the config if the prices changed.
+/// ## Params
+/// * **storage** is an object of type [`Storage`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **pool** is an object of type [`PoolState`]. This is the pool state before the operation.
+fn update_cumulative_prices(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &mut Config,
+    pool: &PoolState,
+) -> StdResult<()> {
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
+        accumulate_prices(env, config, pool.sqrt_price)?
+    {
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(storage, config)?;
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Accumulate token prices for the assets in the pool. The prices are derived from the current
+/// square root price of the pool.
+/// ## Params
+/// * **env** is an object of type [`Env`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **sqrt_price** is an object of type [`Decimal`]. This is the current square root price.
+pub fn accumulate_prices(
+    env: &Env,
+    config: &Config,
+    sqrt_price: Decimal,
+) -> StdResult<Option<(Uint128, Uint128, u64)>> {
+    let block_time = env.block.time.seconds();
+    if block_time <= config.block_time_last {
+        return Ok(None);
+    }
+
+    let time_elapsed = U256::from(block_time - config.block_time_last)
+        * U256::from(10u128.pow(TWAP_PRECISION.into()));
+    let sqrt_price = U256::from(sqrt_price.numerator());
+    let one = U256::from(ONE);
+
+    let price0_increment = mul_div(time_elapsed, sqrt_price * sqrt_price, one * one, false)
+        .ok_or_else(|| StdError::generic_err("Failed to accumulate the prices"))?;
+    let price1_increment = mul_div(time_elapsed, one * one, sqrt_price * sqrt_price, false)
+        .ok_or_else(|| StdError::generic_err("Failed to accumulate the prices"))?;
+
+    Ok(Some((
+        config
+            .price0_cumulative_last
+            .wrapping_add(Uint128::new(price0_increment.low_u128())),
+        config
+            .price1_cumulative_last
+            .wrapping_add(Uint128::new(price1_increment.low_u128())),
+        block_time,
+    )))
+}
+
+/// ## Description
+/// Returns the fee information for the pair from the factory.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **config** is an object of type [`Config`].
+pub fn query_fee_info(deps: Deps, config: &Config) -> StdResult<FeeInfo> {
+    query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )
+}
+
+/// ## Description
+/// Exposes all the queries available in the contract.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **msg** is an object of type [`QueryMsg`].
+///
+/// ## Queries
+/// * **QueryMsg::Pair {}** Returns information about the pair in an object of type [`PairInfo`].
+///
+/// * **QueryMsg::Pool {}** Returns the pair balances as well as the active liquidity using an object
+/// of type [`PoolResponse`].
+///
+/// * **QueryMsg::Config {}** Returns the configuration for the pair contract using a [`ConfigResponse`] object.
+///
+/// * **QueryMsg::Simulation { offer_asset }** Returns the result of a swap simulation using a [`SimulationResponse`] object.
+///
+/// * **QueryMsg::ReverseSimulation { ask_asset }** Returns the result of a reverse swap simulation using
+/// a [`ReverseSimulationResponse`] object.
+///
+/// * **QueryMsg::CumulativePrices {}** Returns information about cumulative prices for the assets in the
+/// pool using a [`CumulativePricesResponse`] object.
+///
+/// * **QueryMsg::State {}** Returns the current price, tick and active liquidity using a [`StateResponse`] object.
+///
+/// * **QueryMsg::Position { position_id }** Returns information about a position using a [`PositionResponse`] object.
+///
+/// * **QueryMsg::Positions { owner, start_after, limit }** Returns the positions of an owner using a
+/// [`PositionsResponse`] object.
+///
+/// * **QueryMsg::SimulateProvide { lower_tick, upper_tick, assets }** Returns the liquidity that a new
+/// position would get and the amounts it would take using a [`SimulatePositionResponse`] object.
+///
+/// * **QueryMsg::SimulateWithdraw { position_id, liquidity }** Returns the amounts of assets that
+/// withdrawing liquidity from a position would return using a [`SimulatePositionResponse`] object.
+///
+/// * **QueryMsg::Stats {}** Returns the cumulative swap volume and commissions of the pair using
+/// a [`astroport::stats::StatsResponse`] object.
+///
+/// * **QueryMsg::DailyStats { start_after, limit }** Returns the swap volume and commissions of the pair
+/// by day using a [`astroport::stats::DailyStatsResponse`] object.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Pair {} => to_binary(&query_pair_info(deps)?),
+        QueryMsg::Pool {} => to_binary(&query_pool(deps)?),
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::Simulation { offer_asset } => to_binary(&query_simulation(deps, offer_asset)?),
+        QueryMsg::ReverseSimulation { ask_asset } => {
+            to_binary(&query_reverse_simulation(deps, ask_asset)?)
+        }
+        QueryMsg::CumulativePrices {} => to_binary(&query_cumulative_prices(deps, env)?),
+        QueryMsg::State {} => to_binary(&query_state(deps)?),
+        QueryMsg::Position { position_id } => to_binary(&query_position(deps, position_id)?),
+        QueryMsg::Positions {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_positions(deps, owner, start_after, limit)?),
+        QueryMsg::SimulateProvide {
+            lower_tick,
+            upper_tick,
+            assets,
+        } => to_binary(&query_simulate_provide(
+            deps, lower_tick, upper_tick, assets,
+        )?),
+        QueryMsg::SimulateWithdraw {
+            position_id,
+            liquidity,
+        } => to_binary(&query_simulate_withdraw(deps, position_id, liquidity)?),
+        QueryMsg::Stats {} => to_binary(&query_stats(deps.storage)?),
+        QueryMsg::DailyStats { start_after, limit } => {
+            to_binary(&query_daily_stats(deps.storage, start_after, limit)?)
+        }
+    }
+}
+
+/// ## Description
+/// Returns information about the pair contract in an object of type [`PairInfo`].
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+pub fn query_pair_info(deps: Deps) -> StdResult<PairInfo> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    Ok(config.pair_info)
+}
+
+/// ## Description
+/// Returns the amounts of assets in the pair contract as well as the active liquidity
+/// in an object of type [`PoolResponse`].
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+pub fn query_pool(deps: Deps) -> StdResult<PoolResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pool = POOL.load(deps.storage)?;
+
+    Ok(PoolResponse {
+        assets: config
+            .pair_info
+            .query_pools(&deps.querier, config.pair_info.contract_addr.clone())?,
+        total_share: pool.liquidity,
+    })
+}
+
+/// ## Description
+/// Returns the pair contract configuration in a [`ConfigResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse {
+        block_time_last: config.block_time_last,
+        params: Some(to_binary(&ConcentratedPoolConfig {
+            tick_spacing: config.tick_spacing,
+        })?),
+    })
+}
+
+/// ## Description
+/// Returns information about a swap simulation in a [`SimulationResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **offer_asset** is an object of type [`Asset`]. This is the asset to swap as well as an amount of the said asset.
+pub fn query_simulation(deps: Deps, offer_asset: Asset) -> StdResult<SimulationResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pool = POOL.load(deps.storage)?;
+
+    let (zero_for_one, _) = swap_direction(&config, &offer_asset.info)
+        .map_err(|_| StdError::generic_err("Given offer asset does not belong in the pair"))?;
+
+    // Get fee info from the factory contract
+    let fee_info = query_fee_info(deps, &config)?;
+
+    let result = compute_swap(
+        deps.storage,
+        &pool,
+        zero_for_one,
+        offer_asset.amount,
+        true,
+        &fee_info,
+    )
+    .map_err(|e| StdError::generic_err(e.to_string()))?;
+    let spread_amount = compute_spread(&pool, zero_for_one, offer_asset.amount, result.amount_out)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    Ok(SimulationResponse {
+        return_amount: result.amount_out - result.commission_amount,
+        spread_amount,
+        commission_amount: result.commission_amount,
+        total_fee_rate: fee_info.total_fee_rate,
+    })
+}
+
+/// ## Description
+/// Returns information about a reverse swap simulation in a [`ReverseSimulationResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **ask_asset** is an object of type [`Asset`]. This is the asset to swap to as well as the desired
+/// amount of ask assets to receive from the swap.
+pub fn query_reverse_simulation(
+    deps: Deps,
+    ask_asset: Asset,
+) -> StdResult<ReverseSimulationResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pool = POOL.load(deps.storage)?;
+
+    // The ask asset is the second pool asset when the first one is offered
+    let (ask_is_first, _) = swap_direction(&config, &ask_asset.info)
+        .map_err(|_| StdError::generic_err("Given ask asset doesn't belong to pairs"))?;
+    let zero_for_one = !ask_is_first;
+
+    // Get fee info from factory
+    let fee_info = query_fee_info(deps, &config)?;
+
+    // ask_amount_before_commission = ask_amount / (1 - commission_rate)
+    let one_minus_commission = ONE - fee_info.total_fee_rate.numerator();
+    let amount_out = mul_div(
+        U256::from(ask_asset.amount.u128()),
+        U256::from(ONE),
+        U256::from(one_minus_commission),
+        true,
+    )
+    .ok_or_else(|| StdError::generic_err("Failed to compute the offer amount"))?;
+
+    let result = compute_swap(
+        deps.storage,
+        &pool,
+        zero_for_one,
+        to_uint128(amount_out)?,
+        false,
+        &fee_info,
+    )
+    .map_err(|e| StdError::generic_err(e.to_string()))?;
+    let spread_amount = compute_spread(&pool, zero_for_one, result.amount_in, result.amount_out)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    Ok(ReverseSimulationResponse {
+        offer_amount: result.amount_in,
+        spread_amount,
+        commission_amount: result.commission_amount,
+    })
+}
+
+/// ## Description
+/// Returns information about cumulative prices for the assets in the pool using a [`CumulativePricesResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+pub fn query_cumulative_prices(deps: Deps, env: Env) -> StdResult<CumulativePricesResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pool = POOL.load(deps.storage)?;
+
+    let mut price0_cumulative_last = config.price0_cumulative_last;
+    let mut price1_cumulative_last = config.price1_cumulative_last;
+
+    if let Some((price0_cumulative_new, price1_cumulative_new, _)) =
+        accumulate_prices(&env, &config, pool.sqrt_price)?
+    {
+        price0_cumulative_last = price0_cumulative_new;
+        price1_cumulative_last = price1_cumulative_new;
+    }
+
+    Ok(CumulativePricesResponse {
+        assets: config
+            .pair_info
+            .query_pools(&deps.querier, config.pair_info.contract_addr.clone())?,
+        total_share: pool.liquidity,
+        price0_cumulative_last,
+        price1_cumulative_last,
+    })
+}
+
+/// ## Description
+/// Returns the current price, tick and active liquidity of the pool in a [`StateResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+pub fn query_state(deps: Deps) -> StdResult<StateResponse> {
+    let pool = POOL.load(deps.storage)?;
+
+    let sqrt_price = U256::from(pool.sqrt_price.numerator());
+    let price = sqrt_price * sqrt_price / U256::from(ONE);
+
+    Ok(StateResponse {
+        price: to_decimal(price)?,
+        sqrt_price: pool.sqrt_price,
+        tick: pool.tick,
+        liquidity: pool.liquidity,
+    })
+}
+
+/// ## Description
+/// Returns information about a position in a [`PositionResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **position_id** is an object of type [`u64`]. This is the position to return.
+pub fn query_position(deps: Deps, position_id: u64) -> StdResult<PositionResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pool = POOL.load(deps.storage)?;
+    let position = POSITIONS
+        .may_load(deps.storage, U64Key::new(position_id))?
+        .ok_or_else(|| StdError::generic_err(ContractError::PositionNotFound {}.to_string()))?;
+
+    position_response(deps.storage, &config, &pool, position_id, position)
+}
+
+/// ## Description
+/// Returns the positions of an owner in a [`PositionsResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **owner** is an object of type [`String`]. This is the owner of the positions.
+///
+/// * **start_after** is an [`Option`] field of type [`u64`]. This is the position identifier to start after.
+///
+/// * **limit** is an [`Option`] field of type [`u32`]. This is the maximum amount of positions to return.
+pub fn query_positions(
+    deps: Deps,
+    owner: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<PositionsResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pool = POOL.load(deps.storage)?;
+    let owner = addr_validate_to_lower(deps.api, &owner)?;
+
+    let limit = limit
+        .unwrap_or(DEFAULT_POSITIONS_LIMIT)
+        .min(MAX_POSITIONS_LIMIT) as usize;
+    let start = start_after.map(|id| Bound::exclusive(U64Key::new(id)));
+
+    let positions = OWNER_POSITIONS
+        .prefix(&owner)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|key| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&key);
+            let position_id = u64::from_be_bytes(bytes);
+            let position = POSITIONS.load(deps.storage, U64Key::new(position_id))?;
+            position_response(deps.storage, &config, &pool, position_id, position)
+        })
+        .collect::<StdResult<Vec<PositionResponse>>>()?;
+
+    Ok(PositionsResponse { positions })
+}
+
+/// ## Description
+/// Returns the liquidity that a new position between the specified ticks would get for the
+/// specified maximum deposits and the amounts it would take in a [`SimulatePositionResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **lower_tick** is an object of type [`i32`]. This is the lower tick of the position.
+///
+/// * **upper_tick** is an object of type [`i32`]. This is the upper tick of the position.
+///
+/// * **assets** is an array with two objects of type [`Asset`]. These are the maximum amounts of assets to deposit.
+pub fn query_simulate_provide(
+    deps: Deps,
+    lower_tick: i32,
+    upper_tick: i32,
+    assets: [Asset; 2],
+) -> StdResult<SimulatePositionResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pool = POOL.load(deps.storage)?;
+
+    assert_ticks(&config, lower_tick, upper_tick)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+    let max_amounts =
+        get_max_amounts(&config, &assets).map_err(|e| StdError::generic_err(e.to_string()))?;
+    let liquidity = compute_liquidity(&pool, lower_tick, upper_tick, max_amounts)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+    let amounts = compute_amounts(&pool, lower_tick, upper_tick, liquidity, true)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    Ok(SimulatePositionResponse {
+        liquidity,
+        assets: to_assets(&config, amounts),
+    })
+}
+
+/// ## Description
+/// Returns the amounts of assets that withdrawing liquidity from a position would return, not
+/// counting the fees, in a [`SimulatePositionResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **position_id** is an object of type [`u64`]. This is the position to withdraw liquidity from.
+///
+/// * **liquidity** is an [`Option`] field of type [`Uint128`]. This is the amount of liquidity to withdraw.
+/// All of the position liquidity is withdrawn if it is not specified.
+pub fn query_simulate_withdraw(
+    deps: Deps,
+    position_id: u64,
+    liquidity: Option<Uint128>,
+) -> StdResult<SimulatePositionResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pool = POOL.load(deps.storage)?;
+
+    let position = POSITIONS
+        .may_load(deps.storage, U64Key::new(position_id))?
+        .ok_or_else(|| StdError::generic_err(ContractError::PositionNotFound {}.to_string()))?;
+
+    let liquidity = liquidity.unwrap_or(position.liquidity);
+    if liquidity.is_zero() {
+        return Err(StdError::generic_err(
+            ContractError::InvalidZeroAmount {}.to_string(),
+        ));
+    }
+    if liquidity > position.liquidity {
+        return Err(StdError::generic_err(
+            ContractError::InsufficientPositionLiquidity {}.to_string(),
+        ));
+    }
+
+    let amounts = compute_amounts(
+        &pool,
+        position.lower_tick,
+        position.upper_tick,
+        liquidity,
+        false,
+    )
+    .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    Ok(SimulatePositionResponse {
+        liquidity,
+        assets: to_assets(&config, amounts),
+    })
+}
+
+/// ## Description
+/// Returns a [`PositionResponse`] with the assets that the position holds at the current price and
+/// the fees that it earned.
+/// ## Params
+/// * **storage** is an object of type [`Storage`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **pool** is an object of type [`PoolState`].
+///
+/// * **position_id** is an object of type [`u64`].
+///
+/// * **position** is an object of type [`Position`].
+fn position_response(
+    storage: &dyn Storage,
+    config: &Config,
+    pool: &PoolState,
+    position_id: u64,
+    position: Position,
+) -> StdResult<PositionResponse> {
+    let lower_info = load_tick(storage, pool, position.lower_tick)?;
+    let upper_info = load_tick(storage, pool, position.upper_tick)?;
+    let fee_growth_inside = fee_growth_inside(
+        pool,
+        position.lower_tick,
+        position.upper_tick,
+        &lower_info,
+        &upper_info,
+    );
+    let earned_fees = earned_fees(&position, fee_growth_inside)?;
+
+    let amounts = amounts_for_liquidity(
+        pool.tick,
+        U256::from(pool.sqrt_price.numerator()),
+        position.lower_tick,
+        position.upper_tick,
+        U256::from(position.liquidity.u128()),
+        false,
+    )
+    .ok_or_else(|| StdError::generic_err("Failed to compute the position assets"))?;
+
+    Ok(PositionResponse {
+        position_id,
+        owner: position.owner.clone(),
+        lower_tick: position.lower_tick,
+        upper_tick: position.upper_tick,
+        liquidity: position.liquidity,
+        assets: to_assets(config, [to_uint128(amounts[0])?, to_uint128(amounts[1])?]),
+        fees: to_assets(
+            config,
+            [
+                position.fees_owed[0].checked_add(earned_fees[0])?,
+                position.fees_owed[1].checked_add(earned_fees[1])?,
+            ],
+        ),
+    })
+}
+
+/// ## Description
+/// Ensures the maximum spread of a swap is not exceeded.
+/// Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+/// ## Params
+/// * **belief_price** is an object of type [`Option<Decimal>`]. This is the belief price used in the swap.
+///
+/// * **max_spread** is an object of type [`Option<Decimal>`]. This is the
+/// max spread allowed so that the swap can be executed successfuly.
+///
+/// * **offer_amount** is an object of type [`Uint128`]. This is the amount of assets to swap.
+///
+/// * **return_amount** is an object of type [`Uint128`]. This is the amount of assets to receive from the swap.
+///
+/// * **spread_amount** is an object of type [`Uint128`]. This is the spread used in the swap.
+pub fn assert_max_spread(
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    offer_amount: Uint128,
+    return_amount: Uint128,
+    spread_amount: Uint128,
+) -> Result<(), ContractError> {
+    let default_spread = Decimal::from_str(DEFAULT_SLIPPAGE)?;
+    let max_allowed_spread = Decimal::from_str(MAX_ALLOWED_SLIPPAGE)?;
+
+    let max_spread = max_spread.unwrap_or(default_spread);
+    if max_spread.gt(&max_allowed_spread) {
+        return Err(ContractError::AllowedSpreadAssertion {});
+    }
+
+    if let Some(belief_price) = belief_price {
+        let expected_return =
+            offer_amount * Decimal::from(Decimal256::one() / Decimal256::from(belief_price));
+        let spread_amount = expected_return
+            .checked_sub(return_amount)
+            .unwrap_or_else(|_| Uint128::zero());
+
+        if return_amount < expected_return
+            && Decimal::from_ratio(spread_amount, expected_return) > max_spread
+        {
+            return Err(ContractError::MaxSpreadAssertion {});
+        }
+    } else if Decimal::from_ratio(spread_amount, return_amount + spread_amount) > max_spread {
+        return Err(ContractError::MaxSpreadAssertion {});
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Loads a position from the storage. Returns a [`ContractError`] if the position does not exist.
+/// ## Params
+/// * **storage** is an object of type [`Storage`].
+///
+/// * **position_id** is an object of type [`u64`].
+fn load_position(storage: &dyn Storage, position_id: u64) -> Result<Position, ContractError> {
+    POSITIONS
+        .may_load(storage, U64Key::new(position_id))?
+        .ok_or(ContractError::PositionNotFound {})
+}
+
+/// ## Description
+/// Returns the messages that send the specified amounts of the pool assets to a recipient.
+/// Zero amounts are skipped.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **amounts** is an array with two objects of type [`Uint128`].
+///
+/// * **recipient** is an object of type [`Addr`].
+fn transfer_messages(
+    deps: Deps,
+    config: &Config,
+    amounts: [Uint128; 2],
+    recipient: &Addr,
+) -> StdResult<Vec<CosmosMsg>> {
+    config
+        .pair_info
+        .asset_infos
+        .iter()
+        .zip(amounts.iter())
+        .filter(|(_, amount)| !amount.is_zero())
+        .map(|(info, amount)| {
+            Asset {
+                info: info.clone(),
+                amount: *amount,
+            }
+            .into_msg(&deps.querier, recipient.clone())
+        })
+        .collect()
+}
+
+/// ## Description
+/// Checks the liquidity that a deposit bought against the minimum liquidity that the depositor wants to get.
+/// ## Params
+/// * **liquidity** is an object of type [`Uint128`]. This is the liquidity added to the position.
+///
+/// * **min_liquidity_to_receive** is an [`Option`] field of type [`Uint128`]. This is the minimum liquidity to get.
+fn assert_min_liquidity_to_receive(
+    liquidity: Uint128,
+    min_liquidity_to_receive: Option<Uint128>,
+) -> Result<(), ContractError> {
+    if let Some(min_liquidity_to_receive) = min_liquidity_to_receive {
+        if liquidity < min_liquidity_to_receive {
+            return Err(ContractError::MinLiquidityToReceiveAssertion {});
+        }
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Checks the withdrawn amounts of the pool assets against the minimum amounts that the position owner wants to receive.
+/// ## Params
+/// * **config** is an object of type [`Config`].
+///
+/// * **amounts** is an array with two objects of type [`Uint128`]. These are the withdrawn amounts of the pool assets.
+///
+/// * **min_assets_to_receive** is an [`Option`] field of type [`Vec<Asset>`]. These are the minimum amounts to receive.
+fn assert_min_assets_to_receive(
+    config: &Config,
+    amounts: [Uint128; 2],
+    min_assets_to_receive: Option<Vec<Asset>>,
+) -> Result<(), ContractError> {
+    for min_asset in min_assets_to_receive.unwrap_or_default() {
+        let index = config
+            .pair_info
+            .asset_infos
+            .iter()
+            .position(|info| info.equal(&min_asset.info))
+            .ok_or(ContractError::AssetMismatch {})?;
+        if amounts[index] < min_asset.amount {
+            return Err(ContractError::MinAssetsToReceiveAssertion {});
+        }
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Checks that the ticks form a range that is within the supported ticks and that both ticks are
+/// multiples of the tick spacing. Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+/// ## Params
+/// * **config** is an object of type [`Config`].
+///
+/// * **lower_tick** is an object of type [`i32`]. This is the lower tick of the range.
+///
+/// * **upper_tick** is an object of type [`i32`]. This is the upper tick of the range.
+fn assert_ticks(config: &Config, lower_tick: i32, upper_tick: i32) -> Result<(), ContractError> {
+    if lower_tick >= upper_tick
+        || lower_tick < MIN_TICK
+        || upper_tick > MAX_TICK
+        || lower_tick % config.tick_spacing as i32 != 0
+        || upper_tick % config.tick_spacing as i32 != 0
+    {
+        return Err(ContractError::IncorrectTicks {});
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Checks that the deadline of a message has not passed yet.
+/// Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+/// ## Params
+/// * **env** is an object of type [`Env`].
+///
+/// * **deadline** is an [`Option`] field of type [`u64`]. This is the timestamp (in seconds) after which the message fails.
+fn assert_deadline(env: &Env, deadline: Option<u64>) -> Result<(), ContractError> {
+    if let Some(deadline) = deadline {
+        if env.block.time.seconds() > deadline {
+            return Err(ContractError::DeadlineExpired {});
+        }
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Checks that the callback contract address is valid and that the output of an action is not
+/// also sent to a receiver. Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+/// ## Params
+/// * **api** is an object of type [`Api`].
+///
+/// * **callback** is an [`Option`] field of type [`Callback`]. This is the contract that receives the output.
+///
+/// * **has_receiver** is the field of type [`bool`]. Determines whether a receiver was specified as well.
+fn assert_callback(
+    api: &dyn Api,
+    callback: &Option<Callback>,
+    has_receiver: bool,
+) -> Result<(), ContractError> {
+    if let Some(callback) = callback {
+        if has_receiver {
+            return Err(ContractError::CallbackWithReceiver {});
+        }
+        addr_validate_to_lower(api, &callback.contract)?;
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Returns the amounts of the pool assets as an array of [`Asset`] objects.
+/// ## Params
+/// * **config** is an object of type [`Config`].
+///
+/// * **amounts** is an array with two objects of type [`Uint128`].
+fn to_assets(config: &Config, amounts: [Uint128; 2]) -> [Asset; 2] {
+    let asset_infos = &config.pair_info.asset_infos;
+    [
+        Asset {
+            info: asset_infos[0].clone(),
+            amount: amounts[0],
+        },
+        Asset {
+            info: asset_infos[1].clone(),
+            amount: amounts[1],
+        },
+    ]
+}
+
+/// ## Description
+/// Formats the amounts of the pool assets for the response attributes.
+/// ## Params
+/// * **config** is an object of type [`Config`].
+///
+/// * **amounts** is an array with two objects of type [`Uint128`].
+fn format_amounts(config: &Config, amounts: [Uint128; 2]) -> String {
+    let asset_infos = &config.pair_info.asset_infos;
+    format!(
+        "{}{}, {}{}",
+        amounts[0], asset_infos[0], amounts[1], asset_infos[1]
+    )
+}
+
+/// ## Description
+/// Converts a fixed point number with 18 decimals to a [`Decimal`].
+fn to_decimal(value: U256) -> StdResult<Decimal> {
+    Ok(Decimal::from_ratio(to_uint128(value)?, ONE))
+}
+
+/// ## Description
+/// Converts a [`U256`] to a [`Uint128`]. Returns an error if the value does not fit.
+fn to_uint128(value: U256) -> StdResult<Uint128> {
+    if value > U256::from(u128::MAX) {
+        return Err(StdError::generic_err("Value does not fit into Uint128"));
+    }
+
+    Ok(Uint128::new(value.as_u128()))
+}
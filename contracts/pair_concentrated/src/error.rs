@@ -0,0 +1,78 @@
+use astroport::pair_concentrated::{MAX_TICK, MIN_TICK};
+use cosmwasm_std::{OverflowError, StdError};
+use thiserror::Error;
+
+/// ## Description
+/// This enum describes concentrated liquidity pair contract errors!
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("You need to provide init params")]
+    InitParamsNotFound {},
+
+    #[error("The tick spacing must be positive")]
+    IncorrectTickSpacing {},
+
+    #[error("The initial price is out of the supported price range")]
+    IncorrectInitialPrice {},
+
+    #[error(
+        "The ticks must be multiples of the tick spacing within {} and {} and the lower tick must be lower than the upper tick",
+        MIN_TICK,
+        MAX_TICK
+    )]
+    IncorrectTicks {},
+
+    #[error("Event of zero transfer")]
+    InvalidZeroAmount {},
+
+    #[error("The deposit is too small to add any liquidity")]
+    ZeroLiquidity {},
+
+    #[error("The position does not have that much liquidity")]
+    InsufficientPositionLiquidity {},
+
+    #[error("The liquidity to receive is less than the minimum amount")]
+    MinLiquidityToReceiveAssertion {},
+
+    #[error("The amount of withdrawn assets is less than the minimum amount")]
+    MinAssetsToReceiveAssertion {},
+
+    #[error("The position does not exist")]
+    PositionNotFound {},
+
+    #[error("The pool does not have enough liquidity for the swap")]
+    InsufficientLiquidity {},
+
+    #[error("Failed to compute the swap")]
+    SwapComputationError {},
+
+    #[error("Operation exceeds max spread limit")]
+    MaxSpreadAssertion {},
+
+    #[error("Provided spread amount exceeds allowed limit")]
+    AllowedSpreadAssertion {},
+
+    #[error("Doubling assets in asset infos")]
+    DoublingAssets {},
+
+    #[error("Asset mismatch between the requested and the stored asset in contract")]
+    AssetMismatch {},
+
+    #[error("The deadline of the message has passed")]
+    DeadlineExpired {},
+
+    #[error("A callback contract can't be combined with a receiver or auto staking")]
+    CallbackWithReceiver {},
+}
+
+impl From<OverflowError> for ContractError {
+    fn from(o: OverflowError) -> Self {
+        StdError::from(o).into()
+    }
+}
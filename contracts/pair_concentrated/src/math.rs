@@ -0,0 +1,379 @@
+use astroport::pair_concentrated::{MAX_TICK, MIN_TICK};
+use astroport::U256;
+
+/// The fixed point precision of the square root prices (18 decimals).
+pub const ONE: u128 = 1_000_000_000_000_000_000;
+
+/// `sqrt(1.0001) ^ (2 ^ i)` with 18 decimals. The square root price at a tick is the product
+/// of the ratios that match the set bits of the tick.
+const SQRT_RATIOS: [u128; 19] = [
+    1_000_049_998_750_062_496,
+    1_000_100_000_000_000_000,
+    1_000_200_010_000_000_000,
+    1_000_400_060_004_000_100,
+    1_000_800_280_056_007_000,
+    1_001_601_200_560_182_043,
+    1_003_204_964_963_598_014,
+    1_006_420_201_727_613_920,
+    1_012_881_622_445_451_097,
+    1_025_929_181_087_729_343,
+    1_052_530_684_607_338_948,
+    1_107_820_842_039_993_613,
+    1_227_267_018_058_200_482,
+    1_506_184_333_613_467_388,
+    2_268_591_246_822_644_826,
+    5_146_506_245_160_322_222,
+    26_486_526_531_474_198_664,
+    701_536_087_702_486_644_953,
+    492_152_882_348_911_033_633_683,
+];
+
+#[allow(clippy::all)]
+mod uints {
+    use uint::construct_uint;
+    construct_uint! {
+        pub struct U512(8);
+    }
+}
+
+use uints::U512;
+
+/// This structure describes the result of a swap within a single range of liquidity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwapStep {
+    /// The square root price after the step
+    pub sqrt_price: U256,
+    /// The amount of offer assets that enter the pool
+    pub amount_in: U256,
+    /// The amount of ask assets that leave the pool before fees
+    pub amount_out: U256,
+}
+
+/// ## Description
+/// Returns the square root price at a tick with 18 decimals: `sqrt(1.0001 ^ tick)`.
+/// ## Params
+/// * **tick** is an object of type [`i32`]. It has to be within [`MIN_TICK`] and [`MAX_TICK`].
+pub fn sqrt_price_at_tick(tick: i32) -> U256 {
+    let abs_tick = tick.unsigned_abs();
+
+    let mut sqrt_price = one();
+    for (i, ratio) in SQRT_RATIOS.iter().enumerate() {
+        if abs_tick & (1 << i) != 0 {
+            sqrt_price = sqrt_price * U256::from(*ratio) / one();
+        }
+    }
+
+    if tick < 0 {
+        one() * one() / sqrt_price
+    } else {
+        sqrt_price
+    }
+}
+
+/// ## Description
+/// Returns the greatest tick whose square root price is lower than or equal to `sqrt_price`.
+/// ## Params
+/// * **sqrt_price** is an object of type [`U256`]. It has to be within the square root prices
+/// of [`MIN_TICK`] and [`MAX_TICK`].
+pub fn tick_at_sqrt_price(sqrt_price: U256) -> i32 {
+    let mut low = MIN_TICK;
+    let mut high = MAX_TICK;
+
+    while low < high {
+        let middle = low + (high - low + 1) / 2;
+        if sqrt_price_at_tick(middle) <= sqrt_price {
+            low = middle;
+        } else {
+            high = middle - 1;
+        }
+    }
+
+    low
+}
+
+/// ## Description
+/// Returns the amount of the first pool asset that `liquidity` holds between two square root prices:
+/// `liquidity * (sqrt_price_b - sqrt_price_a) / (sqrt_price_a * sqrt_price_b)`.
+/// ## Params
+/// * **sqrt_price_a** is an object of type [`U256`]. This is the lower square root price.
+///
+/// * **sqrt_price_b** is an object of type [`U256`]. This is the upper square root price.
+///
+/// * **liquidity** is an object of type [`U256`].
+///
+/// * **round_up** is an object of type [`bool`]. Determines whether the result is rounded up.
+pub fn amount0_delta(
+    sqrt_price_a: U256,
+    sqrt_price_b: U256,
+    liquidity: U256,
+    round_up: bool,
+) -> Option<U256> {
+    mul_div(
+        liquidity.checked_mul(sqrt_price_b.checked_sub(sqrt_price_a)?)?,
+        one(),
+        sqrt_price_a.checked_mul(sqrt_price_b)?,
+        round_up,
+    )
+}
+
+/// ## Description
+/// Returns the amount of the second pool asset that `liquidity` holds between two square root prices:
+/// `liquidity * (sqrt_price_b - sqrt_price_a)`.
+/// ## Params
+/// * **sqrt_price_a** is an object of type [`U256`]. This is the lower square root price.
+///
+/// * **sqrt_price_b** is an object of type [`U256`]. This is the upper square root price.
+///
+/// * **liquidity** is an object of type [`U256`].
+///
+/// * **round_up** is an object of type [`bool`]. Determines whether the result is rounded up.
+pub fn amount1_delta(
+    sqrt_price_a: U256,
+    sqrt_price_b: U256,
+    liquidity: U256,
+    round_up: bool,
+) -> Option<U256> {
+    mul_div(
+        liquidity,
+        sqrt_price_b.checked_sub(sqrt_price_a)?,
+        one(),
+        round_up,
+    )
+}
+
+/// ## Description
+/// Returns the amounts of the pool assets that `liquidity` holds between two ticks at the current price.
+/// Only the first asset is held above the current price and only the second asset is held below it.
+/// ## Params
+/// * **tick** is an object of type [`i32`]. This is the current tick.
+///
+/// * **sqrt_price** is an object of type [`U256`]. This is the current square root price.
+///
+/// * **lower_tick** is an object of type [`i32`].
+///
+/// * **upper_tick** is an object of type [`i32`].
+///
+/// * **liquidity** is an object of type [`U256`].
+///
+/// * **round_up** is an object of type [`bool`]. Determines whether the amounts are rounded up.
+pub fn amounts_for_liquidity(
+    tick: i32,
+    sqrt_price: U256,
+    lower_tick: i32,
+    upper_tick: i32,
+    liquidity: U256,
+    round_up: bool,
+) -> Option<[U256; 2]> {
+    let sqrt_price_a = sqrt_price_at_tick(lower_tick);
+    let sqrt_price_b = sqrt_price_at_tick(upper_tick);
+
+    if tick < lower_tick {
+        Some([
+            amount0_delta(sqrt_price_a, sqrt_price_b, liquidity, round_up)?,
+            U256::zero(),
+        ])
+    } else if tick < upper_tick {
+        Some([
+            amount0_delta(sqrt_price, sqrt_price_b, liquidity, round_up)?,
+            amount1_delta(sqrt_price_a, sqrt_price, liquidity, round_up)?,
+        ])
+    } else {
+        Some([
+            U256::zero(),
+            amount1_delta(sqrt_price_a, sqrt_price_b, liquidity, round_up)?,
+        ])
+    }
+}
+
+/// ## Description
+/// Returns the greatest liquidity that can be bought with the specified amounts of the pool assets
+/// between two ticks at the current price.
+/// ## Params
+/// * **sqrt_price** is an object of type [`U256`]. This is the current square root price.
+///
+/// * **lower_tick** is an object of type [`i32`].
+///
+/// * **upper_tick** is an object of type [`i32`].
+///
+/// * **amounts** is an array with two objects of type [`u128`]. These are the maximum amounts of the pool assets.
+pub fn liquidity_for_amounts(
+    sqrt_price: U256,
+    lower_tick: i32,
+    upper_tick: i32,
+    amounts: [u128; 2],
+) -> Option<U256> {
+    let sqrt_price_a = sqrt_price_at_tick(lower_tick);
+    let sqrt_price_b = sqrt_price_at_tick(upper_tick);
+
+    // liquidity = amount0 * sqrt_price_a * sqrt_price_b / (sqrt_price_b - sqrt_price_a)
+    let liquidity0 = |sqrt_price_a: U256| {
+        mul_div(
+            U256::from(amounts[0]).checked_mul(sqrt_price_a)?,
+            sqrt_price_b,
+            (sqrt_price_b - sqrt_price_a).checked_mul(one())?,
+            false,
+        )
+    };
+    // liquidity = amount1 / (sqrt_price_b - sqrt_price_a)
+    let liquidity1 = |sqrt_price_b: U256| {
+        mul_div(
+            U256::from(amounts[1]),
+            one(),
+            sqrt_price_b - sqrt_price_a,
+            false,
+        )
+    };
+
+    if sqrt_price <= sqrt_price_a {
+        liquidity0(sqrt_price_a)
+    } else if sqrt_price < sqrt_price_b {
+        Some(liquidity0(sqrt_price)?.min(liquidity1(sqrt_price)?))
+    } else {
+        liquidity1(sqrt_price_b)
+    }
+}
+
+/// ## Description
+/// Swaps within a single range of liquidity until either the amount is used up or the target
+/// square root price is reached. The amounts that enter the pool are rounded up and the amounts
+/// that leave the pool are rounded down.
+/// ## Params
+/// * **sqrt_price** is an object of type [`U256`]. This is the current square root price.
+///
+/// * **target_sqrt_price** is an object of type [`U256`]. This is the square root price at the end of the range.
+///
+/// * **liquidity** is an object of type [`U256`]. This is the liquidity within the range.
+///
+/// * **amount** is an object of type [`U256`]. This is the remaining amount of offer assets if
+/// `exact_in` is true, otherwise it is the remaining amount of ask assets.
+///
+/// * **zero_for_one** is an object of type [`bool`]. Determines whether the first pool asset is swapped
+/// for the second one.
+///
+/// * **exact_in** is an object of type [`bool`]. Determines whether `amount` is an offer amount.
+pub fn compute_swap_step(
+    sqrt_price: U256,
+    target_sqrt_price: U256,
+    liquidity: U256,
+    amount: U256,
+    zero_for_one: bool,
+    exact_in: bool,
+) -> Option<SwapStep> {
+    // The amounts that enter and leave the pool if the target price is reached
+    let (max_in, max_out) = if zero_for_one {
+        (
+            amount0_delta(target_sqrt_price, sqrt_price, liquidity, true)?,
+            amount1_delta(target_sqrt_price, sqrt_price, liquidity, false)?,
+        )
+    } else {
+        (
+            amount1_delta(sqrt_price, target_sqrt_price, liquidity, true)?,
+            amount0_delta(sqrt_price, target_sqrt_price, liquidity, false)?,
+        )
+    };
+
+    if (exact_in && amount >= max_in) || (!exact_in && amount >= max_out) {
+        return Some(SwapStep {
+            sqrt_price: target_sqrt_price,
+            amount_in: max_in,
+            amount_out: max_out,
+        });
+    }
+
+    let next_sqrt_price = match (zero_for_one, exact_in) {
+        // sqrt_price' = liquidity * sqrt_price / (liquidity + amount * sqrt_price)
+        (true, true) => mul_div(
+            liquidity.checked_mul(sqrt_price)?,
+            one(),
+            liquidity
+                .checked_mul(one())?
+                .checked_add(amount.checked_mul(sqrt_price)?)?,
+            true,
+        )?,
+        // sqrt_price' = sqrt_price + amount / liquidity
+        (false, true) => sqrt_price.checked_add(mul_div(amount, one(), liquidity, false)?)?,
+        // sqrt_price' = sqrt_price - amount / liquidity
+        (true, false) => sqrt_price.checked_sub(mul_div(amount, one(), liquidity, true)?)?,
+        // sqrt_price' = liquidity * sqrt_price / (liquidity - amount * sqrt_price)
+        (false, false) => mul_div(
+            liquidity.checked_mul(sqrt_price)?,
+            one(),
+            liquidity
+                .checked_mul(one())?
+                .checked_sub(amount.checked_mul(sqrt_price)?)?,
+            true,
+        )?,
+    };
+
+    let (amount_in, amount_out) = if zero_for_one {
+        (
+            amount0_delta(next_sqrt_price, sqrt_price, liquidity, true)?,
+            amount1_delta(next_sqrt_price, sqrt_price, liquidity, false)?,
+        )
+    } else {
+        (
+            amount1_delta(sqrt_price, next_sqrt_price, liquidity, true)?,
+            amount0_delta(sqrt_price, next_sqrt_price, liquidity, false)?,
+        )
+    };
+
+    // The whole remaining amount is used when the target price is not reached
+    Some(if exact_in {
+        SwapStep {
+            sqrt_price: next_sqrt_price,
+            amount_in: amount,
+            amount_out,
+        }
+    } else {
+        SwapStep {
+            sqrt_price: next_sqrt_price,
+            amount_in,
+            amount_out: amount,
+        }
+    })
+}
+
+/// ## Description
+/// Returns `a * b / c` without overflowing on the intermediate product.
+/// ## Params
+/// * **a** is an object of type [`U256`].
+///
+/// * **b** is an object of type [`U256`].
+///
+/// * **c** is an object of type [`U256`].
+///
+/// * **round_up** is an object of type [`bool`]. Determines whether the result is rounded up.
+pub fn mul_div(a: U256, b: U256, c: U256, round_up: bool) -> Option<U256> {
+    if c.is_zero() {
+        return None;
+    }
+
+    let product = to_u512(a) * to_u512(b);
+    let c = to_u512(c);
+    let mut result = product / c;
+    if round_up && !(product % c).is_zero() {
+        result += U512::one();
+    }
+
+    from_u512(result)
+}
+
+fn to_u512(value: U256) -> U512 {
+    let mut bytes = [0u8; 32];
+    value.to_little_endian(&mut bytes);
+    U512::from_little_endian(&bytes)
+}
+
+fn from_u512(value: U512) -> Option<U256> {
+    if value.bits() > 256 {
+        return None;
+    }
+
+    let mut bytes = [0u8; 64];
+    value.to_little_endian(&mut bytes);
+    Some(U256::from_little_endian(&bytes[..32]))
+}
+
+fn one() -> U256 {
+    U256::from(ONE)
+}
@@ -0,0 +1,115 @@
+use astroport::asset::PairInfo;
+use astroport::pair_concentrated::MIN_TICK;
+use cosmwasm_std::{Addr, Decimal, Uint128};
+use cw_storage_plus::{Item, Map, U32Key, U64Key};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// ## Description
+/// This structure stores the main config parameters for a concentrated liquidity pair contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    /// General pair information (e.g pair type)
+    pub pair_info: PairInfo,
+    /// The factory contract address
+    pub factory_addr: Addr,
+    /// The last timestamp when the pair contract update the asset cumulative prices
+    pub block_time_last: u64,
+    /// The last cumulative price for asset 0
+    pub price0_cumulative_last: Uint128,
+    /// The last cumulative price for asset 1
+    pub price1_cumulative_last: Uint128,
+    /// The position ticks must be multiples of the tick spacing
+    pub tick_spacing: u32,
+}
+
+/// ## Description
+/// Stores the config struct at the given key
+pub const CONFIG: Item<Config> = Item::new("config");
+
+/// ## Description
+/// This structure stores the current state of the pool.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PoolState {
+    /// The square root of the current price of asset 0 expressed in asset 1
+    pub sqrt_price: Decimal,
+    /// The greatest tick whose price is lower than or equal to the current price
+    pub tick: i32,
+    /// The liquidity of the positions whose range contains the current tick
+    pub liquidity: Uint128,
+    /// The total fees earned per unit of liquidity for both assets.
+    /// The values are scaled by [`FEE_GROWTH_PRECISION`] and wrap around on overflow
+    pub fee_growth_global: [Uint128; 2],
+}
+
+/// ## Description
+/// Stores the pool state
+pub const POOL: Item<PoolState> = Item::new("pool");
+
+/// ## Description
+/// This structure stores the liquidity that starts and ends at a tick.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct TickInfo {
+    /// The liquidity of the positions whose lower tick is this tick
+    pub liquidity_lower: Uint128,
+    /// The liquidity of the positions whose upper tick is this tick
+    pub liquidity_upper: Uint128,
+    /// The fees earned per unit of liquidity on the other side of this tick from the current tick
+    pub fee_growth_outside: [Uint128; 2],
+}
+
+/// ## Description
+/// Stores the ticks that are used by at least one position
+pub const TICKS: Map<U32Key, TickInfo> = Map::new("ticks");
+
+/// ## Description
+/// This structure stores a liquidity position.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Position {
+    /// The owner of the position
+    pub owner: Addr,
+    /// The lower tick of the position
+    pub lower_tick: i32,
+    /// The upper tick of the position
+    pub upper_tick: i32,
+    /// The liquidity of the position
+    pub liquidity: Uint128,
+    /// The fees earned per unit of liquidity within the position range at the last position update
+    pub fee_growth_inside_last: [Uint128; 2],
+    /// The fees earned by the position that were not collected yet
+    pub fees_owed: [Uint128; 2],
+}
+
+/// ## Description
+/// Stores the positions by their identifiers
+pub const POSITIONS: Map<U64Key, Position> = Map::new("positions");
+
+/// ## Description
+/// Stores the identifiers of the positions of every owner
+pub const OWNER_POSITIONS: Map<(&Addr, U64Key), bool> = Map::new("owner_positions");
+
+/// ## Description
+/// Stores the identifier of the next position
+pub const NEXT_POSITION_ID: Item<u64> = Item::new("next_position_id");
+
+/// The precision of the fee growth values.
+pub const FEE_GROWTH_PRECISION: u128 = 1_000_000_000_000_000_000_000_000;
+
+/// ## Description
+/// Returns the storage key of a tick. The ticks are shifted by [`MIN_TICK`] so that the keys are
+/// ordered the same way as the ticks.
+/// ## Params
+/// * **tick** is an object of type [`i32`].
+pub fn tick_key(tick: i32) -> U32Key {
+    U32Key::new((tick - MIN_TICK) as u32)
+}
+
+/// ## Description
+/// Returns the tick of a storage key created with [`tick_key`].
+/// ## Params
+/// * **key** is a slice of bytes.
+pub fn tick_from_key(key: &[u8]) -> i32 {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(key);
+    u32::from_be_bytes(bytes) as i32 + MIN_TICK
+}
@@ -0,0 +1,970 @@
+use crate::contract::{execute, instantiate, query, PAIR_TYPE};
+use crate::error::ContractError;
+use crate::math::{
+    amounts_for_liquidity, liquidity_for_amounts, sqrt_price_at_tick, tick_at_sqrt_price, ONE,
+};
+use astroport::asset::{Asset, AssetInfo, PairInfo};
+use astroport::factory::PairType;
+use astroport::pair::{
+    ConfigResponse, InstantiateMsg, ReverseSimulationResponse, SimulationResponse,
+};
+use astroport::pair_concentrated::{
+    ConcentratedPoolConfig, ConcentratedPoolParams, Cw20HookMsg, ExecuteMsg, PositionResponse,
+    PositionsResponse, QueryMsg, SimulatePositionResponse, StateResponse, MAX_TICK, MIN_TICK,
+};
+use astroport::U256;
+use astroport_mocks::mock_querier::{mock_dependencies, WasmMockQuerier};
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    from_binary, to_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, OwnedDeps, Response, StdError,
+    Uint128, WasmMsg,
+};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use std::str::FromStr;
+
+fn instantiate_msg(tick_spacing: u32, initial_price: &str) -> InstantiateMsg {
+    InstantiateMsg {
+        factory_addr: String::from("factory"),
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        init_params: Some(
+            to_binary(&ConcentratedPoolParams {
+                tick_spacing,
+                initial_price: Decimal::from_str(initial_price).unwrap(),
+            })
+            .unwrap(),
+        ),
+    }
+}
+
+fn mock_pair(
+    tick_spacing: u32,
+    initial_price: &str,
+) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+    )]);
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(tick_spacing, initial_price),
+    )
+    .unwrap();
+
+    deps
+}
+
+fn pool_assets(amount0: u128, amount1: u128) -> [Asset; 2] {
+    [
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::new(amount0),
+        },
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            amount: Uint128::new(amount1),
+        },
+    ]
+}
+
+fn create_position(
+    deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+    sender: &str,
+    lower_tick: i32,
+    upper_tick: i32,
+    amount0: u128,
+    amount1: u128,
+) -> Result<Response, ContractError> {
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            sender,
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(amount0),
+            }],
+        ),
+        ExecuteMsg::CreatePosition {
+            lower_tick,
+            upper_tick,
+            assets: pool_assets(amount0, amount1),
+            receiver: None,
+            min_liquidity_to_receive: None,
+            deadline: None,
+        },
+    )
+}
+
+fn swap_native(
+    deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+    amount: u128,
+) -> Result<Response, ContractError> {
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(amount),
+            }],
+        ),
+        ExecuteMsg::Swap {
+            offer_asset: pool_assets(amount, 0)[0].clone(),
+            belief_price: None,
+            max_spread: Some(Decimal::percent(50)),
+            to: None,
+            deadline: None,
+            callback: None,
+        },
+    )
+}
+
+fn swap_token(
+    deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+    amount: u128,
+) -> Result<Response, ContractError> {
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("asset0000", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("addr0000"),
+            amount: Uint128::new(amount),
+            msg: to_binary(&Cw20HookMsg::Swap {
+                belief_price: None,
+                max_spread: Some(Decimal::percent(50)),
+                to: None,
+                deadline: None,
+                callback: None,
+            })
+            .unwrap(),
+        }),
+    )
+}
+
+fn query_state(deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier>) -> StateResponse {
+    from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap()
+}
+
+fn query_position(
+    deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+    position_id: u64,
+) -> PositionResponse {
+    from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Position { position_id },
+        )
+        .unwrap(),
+    )
+    .unwrap()
+}
+
+fn attribute(res: &Response, key: &str) -> String {
+    res.attributes
+        .iter()
+        .find(|attr| attr.key == key)
+        .unwrap()
+        .value
+        .clone()
+}
+
+#[test]
+fn proper_initialization() {
+    let deps = mock_pair(10, "1");
+
+    let pair_info: PairInfo =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Pair {}).unwrap()).unwrap();
+    assert_eq!(pair_info.pair_type, PairType::Custom(PAIR_TYPE.to_string()));
+    // Positions are tracked by the pair itself
+    assert_eq!(
+        pair_info.liquidity_token,
+        Addr::unchecked(MOCK_CONTRACT_ADDR)
+    );
+
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    let params: ConcentratedPoolConfig = from_binary(&config.params.unwrap()).unwrap();
+    assert_eq!(params.tick_spacing, 10);
+
+    let state = query_state(&deps);
+    assert_eq!(state.price, Decimal::one());
+    assert_eq!(state.sqrt_price, Decimal::one());
+    assert_eq!(state.tick, 0);
+    assert_eq!(state.liquidity, Uint128::zero());
+
+    // The tick is the greatest tick below the initial price
+    let deps = mock_pair(10, "4");
+    let state = query_state(&deps);
+    assert_eq!(state.sqrt_price, Decimal::from_str("2").unwrap());
+    assert_eq!(state.tick, 13863);
+}
+
+#[test]
+fn incorrect_init_params() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mut msg = instantiate_msg(10, "1");
+    msg.init_params = None;
+    let err = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::InitParamsNotFound {});
+
+    let err = instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(0, "1"),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::IncorrectTickSpacing {});
+
+    for price in ["0", "0.0000000000001", "10000000000000"] {
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr0000", &[]),
+            instantiate_msg(10, price),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::IncorrectInitialPrice {});
+    }
+
+    let mut msg = instantiate_msg(10, "1");
+    msg.asset_infos[1] = msg.asset_infos[0].clone();
+    let err = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::DoublingAssets {});
+}
+
+#[test]
+fn tick_math() {
+    let one = U256::from(ONE);
+    assert_eq!(sqrt_price_at_tick(0), one);
+    assert_eq!(
+        sqrt_price_at_tick(2),
+        U256::from(1_000_100_000_000_000_000u128)
+    );
+    assert_eq!(
+        sqrt_price_at_tick(-2),
+        U256::from(999_900_009_999_000_099u128)
+    );
+
+    // The prices at the limits are about 1e-12 and 1e12
+    let min_price = sqrt_price_at_tick(MIN_TICK) * sqrt_price_at_tick(MIN_TICK) / one;
+    let max_price = sqrt_price_at_tick(MAX_TICK) * sqrt_price_at_tick(MAX_TICK) / one;
+    assert!(min_price > U256::from(999_000u128) && min_price < U256::from(1_001_000u128));
+    assert!(
+        max_price > U256::from(999_000_000_000_000_000_000_000_000_000u128)
+            && max_price < U256::from(1_001_000_000_000_000_000_000_000_000_000u128)
+    );
+
+    for tick in [MIN_TICK, -100_001, -1, 0, 1, 7, 65_536, MAX_TICK] {
+        let sqrt_price = sqrt_price_at_tick(tick);
+        assert_eq!(tick_at_sqrt_price(sqrt_price), tick);
+        if tick < MAX_TICK {
+            assert_eq!(tick_at_sqrt_price(sqrt_price + U256::one()), tick);
+        }
+        if tick > MIN_TICK {
+            assert_eq!(tick_at_sqrt_price(sqrt_price - U256::one()), tick - 1);
+        }
+    }
+
+    // The liquidity bought with some amounts never needs more than these amounts
+    let sqrt_price = sqrt_price_at_tick(1234) + U256::from(1_000u128);
+    let amounts = [1_000_000_000u128, 3_000_000_000u128];
+    let liquidity = liquidity_for_amounts(sqrt_price, -1000, 5000, amounts).unwrap();
+    let needed = amounts_for_liquidity(1234, sqrt_price, -1000, 5000, liquidity, true).unwrap();
+    assert!(needed[0] <= U256::from(amounts[0]));
+    assert!(needed[1] <= U256::from(amounts[1]));
+    // The first asset limits the liquidity, so it is used up
+    assert!(needed[0] + U256::from(2u128) >= U256::from(amounts[0]));
+
+    // Out of range liquidity holds only one of the assets
+    let above = amounts_for_liquidity(1234, sqrt_price, 2000, 5000, liquidity, false).unwrap();
+    assert!(!above[0].is_zero() && above[1].is_zero());
+    let below = amounts_for_liquidity(1234, sqrt_price, -1000, 1000, liquidity, false).unwrap();
+    assert!(below[0].is_zero() && !below[1].is_zero());
+}
+
+#[test]
+fn create_and_increase_position() {
+    let mut deps = mock_pair(10, "1");
+
+    for (lower_tick, upper_tick) in [(100, 100), (200, 100), (-105, 100), (MIN_TICK - 6, 100)] {
+        let err =
+            create_position(&mut deps, "addr0000", lower_tick, upper_tick, 100, 100).unwrap_err();
+        assert_eq!(err, ContractError::IncorrectTicks {});
+    }
+
+    // The native amount has to be sent along with the message
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::CreatePosition {
+            lower_tick: -100,
+            upper_tick: 100,
+            assets: pool_assets(100, 100),
+            receiver: None,
+            min_liquidity_to_receive: None,
+            deadline: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err(
+            "Native token balance mismatch between the argument and the transferred"
+        ))
+    );
+
+    let err = create_position(&mut deps, "addr0000", -100, 100, 0, 0).unwrap_err();
+    assert_eq!(err, ContractError::ZeroLiquidity {});
+
+    // The token amount limits the liquidity, so the unused native amount is refunded
+    let res = create_position(&mut deps, "addr0000", -100, 100, 2_000_000, 1_000_000).unwrap();
+    assert_eq!(attribute(&res, "position_id"), "1");
+    let liquidity = Uint128::from_str(&attribute(&res, "liquidity")).unwrap();
+
+    let position = query_position(&deps, 1);
+    assert_eq!(position.owner, Addr::unchecked("addr0000"));
+    assert_eq!(position.liquidity, liquidity);
+    assert!(position.assets[1].amount >= Uint128::new(999_999));
+
+    // The range is symmetric around the current price, so both assets are needed in equal amounts
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("addr0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(1_000_000),
+            }],
+        })
+    );
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("asset0000"),
+            msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                owner: String::from("addr0000"),
+                recipient: String::from(MOCK_CONTRACT_ADDR),
+                amount: Uint128::new(1_000_000),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+
+    // The position liquidity is active
+    assert_eq!(query_state(&deps).liquidity, liquidity);
+
+    // A position above the current price needs only the first asset
+    let res = create_position(&mut deps, "addr0001", 100, 200, 1_000_000, 0).unwrap();
+    assert_eq!(attribute(&res, "position_id"), "2");
+    assert_eq!(res.messages.len(), 0);
+    let position = query_position(&deps, 2);
+    assert_eq!(position.assets[1].amount, Uint128::zero());
+    assert_eq!(query_state(&deps).liquidity, liquidity);
+
+    // Anyone can add liquidity to a position
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(1_000_000),
+            }],
+        ),
+        ExecuteMsg::IncreaseLiquidity {
+            position_id: 2,
+            assets: pool_assets(1_000_000, 0),
+            min_liquidity_to_receive: None,
+            deadline: None,
+        },
+    )
+    .unwrap();
+    let added = Uint128::from_str(&attribute(&res, "liquidity")).unwrap();
+    assert_eq!(
+        query_position(&deps, 2).liquidity,
+        position.liquidity + added
+    );
+
+    let positions: PositionsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Positions {
+                owner: String::from("addr0001"),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(positions.positions.len(), 1);
+    assert_eq!(positions.positions[0].position_id, 2);
+}
+
+#[test]
+fn swap_within_range() {
+    let mut deps = mock_pair(10, "1");
+    create_position(&mut deps, "addr0000", -1000, 1000, 10_000_000, 10_000_000).unwrap();
+    let state_before = query_state(&deps);
+
+    let offer_amount = 100_000u128;
+    let simulation: SimulationResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Simulation {
+                offer_asset: pool_assets(offer_amount, 0)[0].clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    let res = swap_native(&mut deps, offer_amount).unwrap();
+    let return_amount = Uint128::from_str(&attribute(&res, "return_amount")).unwrap();
+    let commission_amount = Uint128::from_str(&attribute(&res, "commission_amount")).unwrap();
+    let maker_fee_amount = Uint128::from_str(&attribute(&res, "maker_fee_amount")).unwrap();
+    assert_eq!(simulation.return_amount, return_amount);
+    assert_eq!(simulation.commission_amount, commission_amount);
+
+    // The price moves down by about twice the offer share of the virtual reserves
+    let gross_amount = return_amount + commission_amount;
+    assert!(gross_amount > Uint128::new(98_000) && gross_amount < Uint128::new(100_000));
+    assert_eq!(commission_amount, gross_amount * Decimal::permille(3));
+    assert_eq!(
+        maker_fee_amount,
+        commission_amount * Decimal::from_ratio(1660u128, 10000u128)
+    );
+    let state = query_state(&deps);
+    assert!(state.price < state_before.price);
+    assert_eq!(state.liquidity, state_before.liquidity);
+
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("asset0000"),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("addr0000"),
+                amount: return_amount,
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("asset0000"),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("fee_address"),
+                amount: maker_fee_amount,
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+
+    // The rest of the commission is earned by the position
+    let position = query_position(&deps, 1);
+    let lp_fee = commission_amount - maker_fee_amount;
+    assert_eq!(position.fees[0].amount, Uint128::zero());
+    assert!(
+        position.fees[1].amount <= lp_fee && position.fees[1].amount + Uint128::new(1) >= lp_fee
+    );
+
+    // The reverse simulation finds about the same offer amount
+    let reverse_simulation: ReverseSimulationResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ReverseSimulation {
+                ask_asset: pool_assets(0, return_amount.u128())[1].clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(reverse_simulation.offer_amount > Uint128::new(offer_amount));
+    assert!(reverse_simulation.offer_amount < Uint128::new(offer_amount + 500));
+
+    // Swap the token back through the CW20 hook
+    let res = swap_token(&mut deps, return_amount.u128()).unwrap();
+    assert_eq!(attribute(&res, "ask_asset"), "uusd");
+    assert!(query_position(&deps, 1).fees[0].amount > Uint128::zero());
+
+    // Only the pool tokens can be swapped through the hook
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("asset0001", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("addr0000"),
+            amount: Uint128::new(1000),
+            msg: to_binary(&Cw20HookMsg::Swap {
+                belief_price: None,
+                max_spread: None,
+                to: None,
+                deadline: None,
+                callback: None,
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let err = swap_native(&mut deps, 0).unwrap_err();
+    assert_eq!(err, ContractError::InvalidZeroAmount {});
+}
+
+#[test]
+fn swap_across_ticks() {
+    let mut deps = mock_pair(10, "1");
+    let res = create_position(&mut deps, "addr0000", -100, 100, 1_000_000, 1_000_000).unwrap();
+    let liquidity_a = Uint128::from_str(&attribute(&res, "liquidity")).unwrap();
+    let res = create_position(&mut deps, "addr0001", 100, 1000, 1_000_000, 0).unwrap();
+    let liquidity_b = Uint128::from_str(&attribute(&res, "liquidity")).unwrap();
+    assert_eq!(query_state(&deps).liquidity, liquidity_a);
+
+    // Buying the first asset moves the price up into the second range
+    let res = swap_token(&mut deps, 1_500_000).unwrap();
+    let return_amount = Uint128::from_str(&attribute(&res, "return_amount")).unwrap();
+    let state = query_state(&deps);
+    assert!(state.tick >= 100 && state.tick < 1000);
+    assert_eq!(state.liquidity, liquidity_b);
+
+    // Both positions earned fees in the first asset
+    let position_a = query_position(&deps, 1);
+    let position_b = query_position(&deps, 2);
+    assert!(!position_a.fees[0].amount.is_zero());
+    assert!(!position_b.fees[0].amount.is_zero());
+    assert!(position_a.assets[0].amount.is_zero());
+
+    // The return amount exceeds the reserves of the first range
+    assert!(return_amount > Uint128::new(1_000_000));
+
+    // Selling the first asset moves the price back into the first range
+    swap_native(&mut deps, return_amount.u128()).unwrap();
+    let state = query_state(&deps);
+    assert!(state.tick < 100);
+    assert_eq!(state.liquidity, liquidity_a);
+
+    // The fees of the second position do not change outside of its range
+    let fees_b = query_position(&deps, 2).fees;
+    swap_native(&mut deps, 10_000).unwrap();
+    assert_eq!(query_position(&deps, 2).fees, fees_b);
+
+    // A swap that exhausts all the ranges fails
+    let err = swap_token(&mut deps, 1_000_000_000).unwrap_err();
+    assert_eq!(err, ContractError::InsufficientLiquidity {});
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Simulation {
+            offer_asset: pool_assets(0, 1_000_000_000)[1].clone(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(ContractError::InsufficientLiquidity {}.to_string())
+    );
+}
+
+#[test]
+fn decrease_collect_and_transfer() {
+    let mut deps = mock_pair(10, "1");
+    let res = create_position(&mut deps, "addr0000", -100, 100, 1_000_000, 1_000_000).unwrap();
+    let liquidity = Uint128::from_str(&attribute(&res, "liquidity")).unwrap();
+    swap_native(&mut deps, 100_000).unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::CollectFees {
+            position_id: 1,
+            receiver: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // Collect the fees to another address
+    let fees = query_position(&deps, 1).fees;
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::CollectFees {
+            position_id: 1,
+            receiver: Some(String::from("addr0002")),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("asset0000"),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("addr0002"),
+                amount: fees[1].amount,
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    assert_eq!(query_position(&deps, 1).fees[1].amount, Uint128::zero());
+
+    // Transfer the position
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::TransferPosition {
+            position_id: 1,
+            recipient: String::from("addr0001"),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::TransferPosition {
+            position_id: 1,
+            recipient: String::from("addr0001"),
+        },
+    )
+    .unwrap();
+    assert_eq!(query_position(&deps, 1).owner, Addr::unchecked("addr0001"));
+    let positions: PositionsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Positions {
+                owner: String::from("addr0000"),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(positions.positions.is_empty());
+
+    // Withdraw a part of the liquidity
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::DecreaseLiquidity {
+            position_id: 1,
+            liquidity: Some(liquidity + Uint128::new(1)),
+            receiver: None,
+            min_assets_to_receive: None,
+            deadline: None,
+            callback: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InsufficientPositionLiquidity {});
+
+    let half = liquidity.multiply_ratio(1u128, 2u128);
+    let assets = query_position(&deps, 1).assets;
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::DecreaseLiquidity {
+            position_id: 1,
+            liquidity: Some(half),
+            receiver: None,
+            min_assets_to_receive: None,
+            deadline: None,
+            callback: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(query_position(&deps, 1).liquidity, liquidity - half);
+    assert_eq!(query_state(&deps).liquidity, liquidity - half);
+
+    // Withdraw the rest, which removes the position
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::DecreaseLiquidity {
+            position_id: 1,
+            liquidity: None,
+            receiver: None,
+            min_assets_to_receive: None,
+            deadline: None,
+            callback: None,
+        },
+    )
+    .unwrap();
+    // Every withdrawal rounds down, so the position returns at most what it holds
+    match &res.messages[0].msg {
+        CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+            assert_eq!(to_address, "addr0001");
+            assert_eq!(amount[0].denom, "uusd");
+            let expected_amount = assets[0].amount - assets[0].amount.multiply_ratio(1u128, 2u128);
+            assert!(amount[0].amount <= expected_amount);
+            assert!(amount[0].amount + Uint128::new(1) >= expected_amount);
+        }
+        _ => panic!("Unexpected message"),
+    }
+    assert_eq!(query_state(&deps).liquidity, Uint128::zero());
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::CollectFees {
+            position_id: 1,
+            receiver: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::PositionNotFound {});
+}
+
+#[test]
+fn min_amounts_to_receive() {
+    let mut deps = mock_pair(10, "1");
+    let res = create_position(&mut deps, "addr0000", -100, 100, 1_000_000, 1_000_000).unwrap();
+    let liquidity = Uint128::from_str(&attribute(&res, "liquidity")).unwrap();
+
+    // The same deposit at the same price buys the same liquidity
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(1_000_000),
+            }],
+        ),
+        ExecuteMsg::CreatePosition {
+            lower_tick: -100,
+            upper_tick: 100,
+            assets: pool_assets(1_000_000, 1_000_000),
+            receiver: None,
+            min_liquidity_to_receive: Some(liquidity + Uint128::new(1)),
+            deadline: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::MinLiquidityToReceiveAssertion {});
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(1_000_000),
+            }],
+        ),
+        ExecuteMsg::IncreaseLiquidity {
+            position_id: 1,
+            assets: pool_assets(1_000_000, 1_000_000),
+            min_liquidity_to_receive: Some(liquidity + Uint128::new(1)),
+            deadline: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::MinLiquidityToReceiveAssertion {});
+
+    // Withdrawals round down, so the position never returns more than it holds
+    let assets = query_position(&deps, 1).assets;
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::DecreaseLiquidity {
+            position_id: 1,
+            liquidity: None,
+            receiver: None,
+            min_assets_to_receive: Some(vec![Asset {
+                info: assets[1].info.clone(),
+                amount: assets[1].amount + Uint128::new(1),
+            }]),
+            deadline: None,
+            callback: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::MinAssetsToReceiveAssertion {});
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::DecreaseLiquidity {
+            position_id: 1,
+            liquidity: None,
+            receiver: None,
+            min_assets_to_receive: Some(vec![Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uluna".to_string(),
+                },
+                amount: Uint128::new(1),
+            }]),
+            deadline: None,
+            callback: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::AssetMismatch {});
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::DecreaseLiquidity {
+            position_id: 1,
+            liquidity: None,
+            receiver: None,
+            min_assets_to_receive: Some(vec![Asset {
+                info: assets[1].info.clone(),
+                amount: assets[1].amount - Uint128::new(1),
+            }]),
+            deadline: None,
+            callback: None,
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn simulate_provide_and_withdraw() {
+    let mut deps = mock_pair(10, "1");
+
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::SimulateProvide {
+            lower_tick: 100,
+            upper_tick: -100,
+            assets: pool_assets(2_000_000, 1_000_000),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(ContractError::IncorrectTicks {}.to_string())
+    );
+
+    let simulation: SimulatePositionResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateProvide {
+                lower_tick: -100,
+                upper_tick: 100,
+                assets: pool_assets(2_000_000, 1_000_000),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(simulation.assets[0].amount, Uint128::new(1_000_000));
+    assert_eq!(simulation.assets[1].amount, Uint128::new(1_000_000));
+
+    // The simulation matches the position that is created for the same deposit
+    let res = create_position(&mut deps, "addr0000", -100, 100, 2_000_000, 1_000_000).unwrap();
+    assert_eq!(
+        attribute(&res, "liquidity"),
+        simulation.liquidity.to_string()
+    );
+    assert_eq!(
+        attribute(&res, "assets"),
+        format!(
+            "{}uusd, {}asset0000",
+            simulation.assets[0].amount, simulation.assets[1].amount
+        )
+    );
+    swap_native(&mut deps, 100_000).unwrap();
+
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::SimulateWithdraw {
+            position_id: 1,
+            liquidity: Some(simulation.liquidity + Uint128::new(1)),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(ContractError::InsufficientPositionLiquidity {}.to_string())
+    );
+
+    let half = simulation.liquidity.multiply_ratio(1u128, 2u128);
+    let withdrawal: SimulatePositionResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateWithdraw {
+                position_id: 1,
+                liquidity: Some(half),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(withdrawal.liquidity, half);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::DecreaseLiquidity {
+            position_id: 1,
+            liquidity: Some(half),
+            receiver: None,
+            min_assets_to_receive: None,
+            deadline: None,
+            callback: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        attribute(&res, "assets"),
+        format!(
+            "{}uusd, {}asset0000",
+            withdrawal.assets[0].amount, withdrawal.assets[1].amount
+        )
+    );
+}
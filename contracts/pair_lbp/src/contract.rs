@@ -0,0 +1,388 @@
+use crate::error::ContractError;
+use crate::state::{LbpConfig, LBP_CONFIG};
+
+use cosmwasm_std::{
+    entry_point, from_binary, to_binary, Addr, Binary, Decimal, Deps, DepsMut, Env, Fraction,
+    MessageInfo, Reply, Response, StdResult, Uint128,
+};
+
+use astroport::asset::addr_validate_to_lower;
+use astroport::pair::{ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg};
+use astroport::pair_lbp::{LbpPoolConfig, LbpPoolParams, LbpPoolUpdateParams};
+use astroport_pair_weighted::contract::{
+    assert_no_flash_loan, execute_with_weights, instantiate_pair, normalize_weights,
+    query_with_weights, reply_with_weights,
+};
+use astroport_pair_weighted::error::ContractError as WeightedContractError;
+use astroport_pair_weighted::state::CONFIG;
+use cw2::set_contract_version;
+
+/// Contract name that is used for migration.
+const CONTRACT_NAME: &str = "astroport-pair-lbp";
+/// Contract version that is used for migration.
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// The custom pair type of the pair.
+pub const PAIR_TYPE: &str = "lbp";
+
+/// ## Description
+/// Creates a new contract with the specified parameters in the [`InstantiateMsg`].
+/// Returns the [`Response`] with the specified attributes if the operation was successful, or a [`ContractError`] if
+/// the contract was not created.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **_info** is an object of type [`MessageInfo`].
+/// * **msg** is a message of type [`InstantiateMsg`] which contains the basic settings for creating a contract.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    if msg.init_params.is_none() {
+        return Err(WeightedContractError::InitParamsNotFound {}.into());
+    }
+
+    let params: LbpPoolParams = from_binary(msg.init_params.as_ref().unwrap())?;
+    let start_weights = normalize_weights(params.start_weights)?;
+    let end_weights = normalize_weights(params.end_weights)?;
+
+    if params.start_time < env.block.time.seconds() || params.end_time <= params.start_time {
+        return Err(ContractError::IncorrectSchedule {});
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let config = LbpConfig {
+        owner: addr_validate_to_lower(deps.api, &params.owner)?,
+        start_weights,
+        end_weights,
+        start_time: params.start_time,
+        end_time: params.end_time,
+        allow_third_party_liquidity: params.allow_third_party_liquidity,
+    };
+    LBP_CONFIG.save(deps.storage, &config)?;
+
+    Ok(instantiate_pair(
+        deps,
+        env,
+        msg,
+        PAIR_TYPE,
+        params.fee_on_transfer,
+    )?)
+}
+
+/// ## Description
+/// Returns the current normalized weights of the pool assets. The weights change linearly from
+/// the start weights to the end weights between the start and the end time.
+/// ## Params
+/// * **config** is an object of type [`LbpConfig`].
+///
+/// * **env** is an object of type [`Env`].
+pub fn compute_current_weights(config: &LbpConfig, env: &Env) -> [Decimal; 2] {
+    let block_time = env.block.time.seconds();
+
+    let weight0 = if block_time <= config.start_time {
+        config.start_weights[0]
+    } else if block_time < config.end_time {
+        let elapsed_time = block_time - config.start_time;
+        let time_range = config.end_time - config.start_time;
+        let start_weight = Uint128::from(config.start_weights[0].numerator());
+        let end_weight = Uint128::from(config.end_weights[0].numerator());
+
+        let weight = if end_weight > start_weight {
+            start_weight + (end_weight - start_weight).multiply_ratio(elapsed_time, time_range)
+        } else {
+            start_weight - (start_weight - end_weight).multiply_ratio(elapsed_time, time_range)
+        };
+        Decimal::from_ratio(weight, Decimal::one().numerator())
+    } else {
+        config.end_weights[0]
+    };
+
+    // The second weight is derived from the first one so that the weights sum up to exactly 1
+    [weight0, Decimal::one() - weight0]
+}
+
+/// ## Description
+/// The entry point to the contract for processing replies from submessages.
+/// The replies are processed by the weighted pair with the current weights.
+/// # Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **msg** is an object of type [`Reply`].
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let config = LBP_CONFIG.load(deps.storage)?;
+    let weights = compute_current_weights(&config, &env);
+
+    Ok(reply_with_weights(deps, env, msg, &weights)?)
+}
+
+/// ## Description
+/// Exposes all the execute functions available in the contract. The liquidity bootstrapping pair
+/// checks the sale schedule and the liquidity window, then passes every message except
+/// `UpdateConfig` to the weighted pair with the current weights.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **msg** is an object of type [`ExecuteMsg`].
+///
+/// ## Queries
+/// * **ExecuteMsg::UpdateConfig { params: Binary }** Updates the weight schedule or the liquidity
+/// provision settings of the pool.
+///
+/// * **ExecuteMsg::ProvideLiquidity { .. }** Provides liquidity in the pair. Only the owner can
+/// provide liquidity before the sale starts.
+///
+/// * **ExecuteMsg::Swap { .. }** and **ExecuteMsg::SwapExactOut { .. }** Perform a swap after
+/// the sale starts. The same holds for the swaps and the single asset withdrawals sent with
+/// [`ExecuteMsg::Receive`].
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    let config = LBP_CONFIG.load(deps.storage)?;
+
+    match &msg {
+        ExecuteMsg::UpdateConfig { params } => {
+            assert_no_flash_loan(deps.storage)?;
+            return update_config(deps, env, info, params.clone());
+        }
+        ExecuteMsg::ProvideLiquidity { .. } => {
+            assert_liquidity_provider(&config, &env, &info.sender)?
+        }
+        ExecuteMsg::Swap { .. } | ExecuteMsg::SwapExactOut { .. } => {
+            assert_sale_started(&config, &env)?
+        }
+        ExecuteMsg::Receive(cw20_msg) => {
+            // Single asset withdrawals swap a part of the withdrawn liquidity
+            if matches!(
+                from_binary(&cw20_msg.msg),
+                Ok(Cw20HookMsg::Swap { .. }
+                    | Cw20HookMsg::SwapExactOut { .. }
+                    | Cw20HookMsg::WithdrawLiquidityOneAsset { .. })
+            ) {
+                assert_sale_started(&config, &env)?;
+            }
+        }
+        _ => {}
+    }
+
+    let weights = compute_current_weights(&config, &env);
+
+    Ok(execute_with_weights(deps, env, info, msg, &weights)?)
+}
+
+/// ## Description
+/// Exposes all the queries available in the contract. The configuration query returns the weight
+/// schedule of the pool, every other query is answered by the weighted pair with the current weights.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **msg** is an object of type [`QueryMsg`].
+///
+/// ## Queries
+/// * **QueryMsg::Config {}** Returns the configuration of the pair in a [`ConfigResponse`] object
+/// whose params are a [`LbpPoolConfig`] object.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    let config = LBP_CONFIG.load(deps.storage)?;
+
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps, env, config)?),
+        _ => {
+            let weights = compute_current_weights(&config, &env);
+            query_with_weights(deps, env, msg, &weights)
+        }
+    }
+}
+
+/// ## Description
+/// Returns the pair contract configuration in a [`ConfigResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **config** is an object of type [`LbpConfig`].
+pub fn query_config(deps: Deps, env: Env, config: LbpConfig) -> StdResult<ConfigResponse> {
+    let pair_config = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse {
+        block_time_last: pair_config.block_time_last,
+        params: Some(to_binary(&LbpPoolConfig {
+            weights: compute_current_weights(&config, &env),
+            owner: config.owner,
+            start_weights: config.start_weights,
+            end_weights: config.end_weights,
+            start_time: config.start_time,
+            end_time: config.end_time,
+            allow_third_party_liquidity: config.allow_third_party_liquidity,
+            fee_on_transfer: pair_config.fee_on_transfer,
+        })?),
+    })
+}
+
+/// ## Description
+/// Updates the pool configuration with the specified parameters in the `params` variable.
+/// Returns a [`ContractError`] as a failure, otherwise returns a [`Response`] with the specified
+/// attributes if the operation was successful
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **params** is an object of type [`Binary`]. These are the the new parameter values.
+///
+/// ## Executor
+/// Only the pool owner can execute this.
+pub fn update_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    params: Binary,
+) -> Result<Response, ContractError> {
+    let config = LBP_CONFIG.load(deps.storage)?;
+
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    match from_binary::<LbpPoolUpdateParams>(&params)? {
+        LbpPoolUpdateParams::StartChangingWeights {
+            end_weights,
+            end_time,
+        } => start_changing_weights(config, deps, env, end_weights, end_time)?,
+        LbpPoolUpdateParams::StopChangingWeights {} => stop_changing_weights(config, deps, env)?,
+        LbpPoolUpdateParams::SetThirdPartyLiquidity { allowed } => {
+            let mut config = config;
+            config.allow_third_party_liquidity = allowed;
+            LBP_CONFIG.save(deps.storage, &config)?;
+        }
+    }
+
+    Ok(Response::default())
+}
+
+/// ## Description
+/// Start changing the weights from the current weights to `end_weights`.
+/// Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+/// ## Params
+/// * **mut config** is an object of type [`LbpConfig`]. This is a mutable reference to the pool configuration.
+///
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **end_weights** is an array with two objects of type [`u64`]. These are the relative weights
+/// of the pool assets at `end_time`.
+///
+/// * **end_time** is an object of type [`u64`]. This is the time when the weights stop changing.
+fn start_changing_weights(
+    mut config: LbpConfig,
+    deps: DepsMut,
+    env: Env,
+    end_weights: [u64; 2],
+    end_time: u64,
+) -> Result<(), ContractError> {
+    let end_weights = normalize_weights(end_weights)?;
+
+    // The sale start is kept, so the weights start changing at the sale start at the earliest
+    let start_time = env.block.time.seconds().max(config.start_time);
+    if end_time <= start_time {
+        return Err(ContractError::IncorrectSchedule {});
+    }
+
+    config.start_weights = compute_current_weights(&config, &env);
+    config.end_weights = end_weights;
+    config.start_time = start_time;
+    config.end_time = end_time;
+
+    LBP_CONFIG.save(deps.storage, &config)?;
+
+    Ok(())
+}
+
+/// ## Description
+/// Stop changing the weights and keep the current weights. Returns [`Ok`].
+/// ## Params
+/// * **mut config** is an object of type [`LbpConfig`]. This is a mutable reference to the pool configuration.
+///
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+fn stop_changing_weights(mut config: LbpConfig, deps: DepsMut, env: Env) -> StdResult<()> {
+    let current_weights = compute_current_weights(&config, &env);
+
+    // The sale start is kept, so swaps stay closed until then
+    let block_time = env.block.time.seconds().max(config.start_time);
+
+    config.start_weights = current_weights;
+    config.end_weights = current_weights;
+    config.start_time = block_time;
+    config.end_time = block_time;
+
+    LBP_CONFIG.save(deps.storage, &config)?;
+
+    Ok(())
+}
+
+/// ## Description
+/// Ensures the sale has started. Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+/// ## Params
+/// * **config** is an object of type [`LbpConfig`].
+///
+/// * **env** is an object of type [`Env`].
+fn assert_sale_started(config: &LbpConfig, env: &Env) -> Result<(), ContractError> {
+    if env.block.time.seconds() < config.start_time {
+        return Err(ContractError::SaleNotStarted {});
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Ensures an address can provide liquidity. The owner can always provide liquidity, other
+/// addresses only after the sale starts and only if third party liquidity is allowed.
+/// Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+/// ## Params
+/// * **config** is an object of type [`LbpConfig`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **sender** is an object of type [`Addr`]. This is the liquidity provider.
+fn assert_liquidity_provider(
+    config: &LbpConfig,
+    env: &Env,
+    sender: &Addr,
+) -> Result<(), ContractError> {
+    if *sender == config.owner {
+        return Ok(());
+    }
+
+    if env.block.time.seconds() < config.start_time {
+        return Err(ContractError::OwnerLiquidityWindow {});
+    }
+
+    if !config.allow_third_party_liquidity {
+        return Err(ContractError::ThirdPartyLiquidityDisabled {});
+    }
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use astroport_pair_weighted::error::ContractError as WeightedContractError;
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+/// ## Description
+/// This enum describes liquidity bootstrapping pair contract errors!
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Weighted(#[from] WeightedContractError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("The weights must stop changing after they start changing and the sale cannot start in the past")]
+    IncorrectSchedule {},
+
+    #[error("The sale has not started yet")]
+    SaleNotStarted {},
+
+    #[error("Only the pool owner can provide liquidity before the sale starts")]
+    OwnerLiquidityWindow {},
+
+    #[error("Liquidity provision by third parties is disabled")]
+    ThirdPartyLiquidityDisabled {},
+}
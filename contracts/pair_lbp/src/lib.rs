@@ -0,0 +1,7 @@
+pub mod contract;
+pub mod state;
+
+pub mod error;
+
+#[cfg(test)]
+mod testing;
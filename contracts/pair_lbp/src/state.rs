@@ -0,0 +1,28 @@
+use cosmwasm_std::{Addr, Decimal};
+use cw_storage_plus::Item;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// ## Description
+/// This structure stores the weight schedule and the liquidity settings of a liquidity bootstrapping
+/// pair contract. The rest of the pair state is stored by the weighted pair.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LbpConfig {
+    /// The pool owner. Only the owner can provide liquidity before the sale starts
+    pub owner: Addr,
+    /// The normalized weights of the pool assets at `start_time`
+    pub start_weights: [Decimal; 2],
+    /// The normalized weights of the pool assets at `end_time`
+    pub end_weights: [Decimal; 2],
+    /// The timestamp when the weights start changing. It stays at the sale start until the sale starts,
+    /// so swaps are not allowed before it
+    pub start_time: u64,
+    /// The timestamp when the weights stop changing
+    pub end_time: u64,
+    /// Whether addresses other than the owner can provide liquidity after the sale starts
+    pub allow_third_party_liquidity: bool,
+}
+
+/// ## Description
+/// Stores the weight schedule of the pair at the given key
+pub const LBP_CONFIG: Item<LbpConfig> = Item::new("lbp_config");
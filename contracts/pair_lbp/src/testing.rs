@@ -0,0 +1,668 @@
+use crate::contract::{execute, instantiate, query, reply, PAIR_TYPE};
+use crate::error::ContractError;
+use astroport::asset::{Asset, AssetInfo};
+use astroport::factory::PairType;
+use astroport::pair::{
+    ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg, SimulationResponse,
+};
+use astroport::pair_lbp::{LbpPoolConfig, LbpPoolParams, LbpPoolUpdateParams};
+use astroport_mocks::mock_querier::{mock_dependencies, WasmMockQuerier};
+use astroport_pair_weighted::contract::query_pool;
+use astroport_pair_weighted::error::ContractError as WeightedContractError;
+use astroport_pair_weighted::response::MsgInstantiateContractResponse;
+use astroport_pair_weighted::state::RESERVES;
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    attr, from_binary, to_binary, Addr, Coin, ContractResult, Decimal, DepsMut, Env, OwnedDeps,
+    Reply, Response, SubMsg, SubMsgExecutionResponse, Timestamp, Uint128, WasmMsg,
+};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use protobuf::Message;
+use std::str::FromStr;
+
+fn store_liquidity_token(deps: DepsMut, msg_id: u64, contract_addr: String) {
+    let data = MsgInstantiateContractResponse {
+        contract_address: contract_addr,
+        data: vec![],
+        unknown_fields: Default::default(),
+        cached_size: Default::default(),
+    }
+    .write_to_bytes()
+    .unwrap();
+
+    let reply_msg = Reply {
+        id: msg_id,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(data.into()),
+        }),
+    };
+
+    let _res = reply(deps, mock_env(), reply_msg.clone()).unwrap();
+}
+
+fn start_time() -> u64 {
+    mock_env().block.time.seconds() + 1_000
+}
+
+fn end_time() -> u64 {
+    start_time() + 10_000
+}
+
+fn env_at(time: u64) -> Env {
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(time);
+    env
+}
+
+fn lbp_params() -> LbpPoolParams {
+    LbpPoolParams {
+        owner: String::from("owner"),
+        start_weights: [90, 10],
+        end_weights: [30, 70],
+        start_time: start_time(),
+        end_time: end_time(),
+        allow_third_party_liquidity: false,
+        fee_on_transfer: false,
+    }
+}
+
+fn instantiate_msg(params: &LbpPoolParams) -> InstantiateMsg {
+    InstantiateMsg {
+        factory_addr: String::from("factory"),
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        init_params: Some(to_binary(params).unwrap()),
+    }
+}
+
+fn mock_pair(lp_supply: Uint128) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &lp_supply)],
+        ),
+    ]);
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(&lbp_params()),
+    )
+    .unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    deps
+}
+
+fn query_lbp_config(
+    deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+    env: Env,
+) -> LbpPoolConfig {
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), env, QueryMsg::Config {}).unwrap()).unwrap();
+    from_binary(&config.params.unwrap()).unwrap()
+}
+
+fn update_config(
+    deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+    env: Env,
+    sender: &str,
+    params: LbpPoolUpdateParams,
+) -> Result<Response, ContractError> {
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info(sender, &[]),
+        ExecuteMsg::UpdateConfig {
+            params: to_binary(&params).unwrap(),
+        },
+    )
+}
+
+fn provide_liquidity(
+    deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+    env: Env,
+    sender: &str,
+) -> Result<Response, ContractError> {
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info(
+            sender,
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000),
+            }],
+        ),
+        ExecuteMsg::ProvideLiquidity {
+            assets: [
+                Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    amount: Uint128::new(100_000_000),
+                },
+                Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: Addr::unchecked("asset0000"),
+                    },
+                    amount: Uint128::new(25_000_000),
+                },
+            ],
+            slippage_tolerance: None,
+            auto_stake: None,
+            receiver: None,
+            min_lp_to_receive: None,
+            deadline: None,
+            callback: None,
+        },
+    )
+}
+
+fn simulate(
+    deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+    env: Env,
+    offer_asset: Asset,
+) -> SimulationResponse {
+    from_binary(&query(deps.as_ref(), env, QueryMsg::Simulation { offer_asset }).unwrap()).unwrap()
+}
+
+/// Returns `ask_pool * (1 - (offer_pool / (offer_pool + offer_amount)) ^ (offer_weight / ask_weight))`
+/// computed with floats.
+fn float_ask_amount(
+    offer_pool: f64,
+    ask_pool: f64,
+    offer_amount: f64,
+    offer_weight: f64,
+    ask_weight: f64,
+) -> f64 {
+    ask_pool * (1.0 - (offer_pool / (offer_pool + offer_amount)).powf(offer_weight / ask_weight))
+}
+
+#[test]
+fn proper_initialization() {
+    let deps = mock_pair(Uint128::zero());
+
+    let pair_info: astroport::asset::PairInfo =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Pair {}).unwrap()).unwrap();
+    assert_eq!(pair_info.pair_type, PairType::Custom(PAIR_TYPE.to_string()));
+    assert_eq!(pair_info.liquidity_token, Addr::unchecked("liquidity0000"));
+
+    let params = query_lbp_config(&deps, mock_env());
+    assert_eq!(
+        params,
+        LbpPoolConfig {
+            owner: Addr::unchecked("owner"),
+            weights: [
+                Decimal::from_str("0.9").unwrap(),
+                Decimal::from_str("0.1").unwrap()
+            ],
+            start_weights: [
+                Decimal::from_str("0.9").unwrap(),
+                Decimal::from_str("0.1").unwrap()
+            ],
+            end_weights: [
+                Decimal::from_str("0.3").unwrap(),
+                Decimal::from_str("0.7").unwrap()
+            ],
+            start_time: start_time(),
+            end_time: end_time(),
+            allow_third_party_liquidity: false,
+            fee_on_transfer: false,
+        }
+    );
+}
+
+#[test]
+fn incorrect_init_params() {
+    let mut deps = mock_pair(Uint128::zero());
+
+    let mut msg = instantiate_msg(&lbp_params());
+    msg.init_params = None;
+    let err = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Weighted(WeightedContractError::InitParamsNotFound {})
+    );
+
+    let mut params = lbp_params();
+    params.end_weights = [100, 0];
+    let err = instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(&params),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Weighted(WeightedContractError::IncorrectWeights {})
+    );
+
+    // The sale cannot start in the past
+    let mut params = lbp_params();
+    params.start_time = mock_env().block.time.seconds() - 1;
+    let err = instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(&params),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::IncorrectSchedule {});
+
+    let mut params = lbp_params();
+    params.end_time = params.start_time;
+    let err = instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(&params),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::IncorrectSchedule {});
+}
+
+#[test]
+fn weights_change_linearly() {
+    let deps = mock_pair(Uint128::zero());
+
+    for (time, weight0) in [
+        (mock_env().block.time.seconds(), "0.9"),
+        (start_time(), "0.9"),
+        (start_time() + 2_500, "0.75"),
+        (start_time() + 5_000, "0.6"),
+        (end_time(), "0.3"),
+        (end_time() + 1_000_000, "0.3"),
+    ] {
+        let weights = query_lbp_config(&deps, env_at(time)).weights;
+        assert_eq!(weights[0], Decimal::from_str(weight0).unwrap());
+        assert_eq!(weights[0] + weights[1], Decimal::one());
+    }
+}
+
+#[test]
+fn swap() {
+    let mut deps = mock_pair(Uint128::new(400_000_000_000));
+
+    let reserves = [Uint128::new(800_000_000_000), Uint128::new(200_000_000_000)];
+    RESERVES.save(deps.as_mut().storage, &reserves).unwrap();
+
+    let offer_amount = Uint128::new(1_000_000_000);
+    let offer_asset = Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: offer_amount,
+    };
+    let swap_msg = ExecuteMsg::Swap {
+        offer_asset: offer_asset.clone(),
+        belief_price: None,
+        max_spread: Some(Decimal::percent(50)),
+        to: None,
+        deadline: None,
+        callback: None,
+    };
+    let swap_info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+
+    // Swaps are not allowed before the sale starts
+    let err = execute(
+        deps.as_mut(),
+        env_at(start_time() - 1),
+        swap_info.clone(),
+        swap_msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::SaleNotStarted {});
+
+    // In the middle of the sale the weights are 60/40
+    let env = env_at(start_time() + 5_000);
+    let simulation = simulate(&deps, env.clone(), offer_asset.clone());
+    let expected = float_ask_amount(
+        800_000_000_000.0,
+        200_000_000_000.0,
+        1_000_000_000.0,
+        0.6,
+        0.4,
+    );
+    let before_fee = simulation.return_amount + simulation.commission_amount;
+    assert!((before_fee.u128() as f64 - expected).abs() < 10.0);
+
+    let res = execute(deps.as_mut(), env, swap_info, swap_msg).unwrap();
+    assert!(res
+        .attributes
+        .contains(&attr("return_amount", simulation.return_amount.to_string())));
+
+    let maker_fee_amount = simulation.commission_amount * Decimal::from_ratio(1660u128, 10000u128);
+    let pool = query_pool(deps.as_ref()).unwrap();
+    assert_eq!(
+        pool.assets[1].amount,
+        reserves[1]
+            .checked_sub(simulation.return_amount + maker_fee_amount)
+            .unwrap()
+    );
+
+    // The price of the first asset falls as its weight decreases over time
+    let early = simulate(&deps, env_at(start_time()), offer_asset.clone());
+    let late = simulate(&deps, env_at(end_time()), offer_asset);
+    assert!(early.return_amount > late.return_amount);
+}
+
+#[test]
+fn liquidity_window() {
+    let mut deps = mock_pair(Uint128::new(400_000_000));
+    RESERVES
+        .save(
+            deps.as_mut().storage,
+            &[Uint128::new(800_000_000), Uint128::new(200_000_000)],
+        )
+        .unwrap();
+
+    // Only the owner can provide liquidity before the sale starts
+    let err = provide_liquidity(&mut deps, mock_env(), "addr0001").unwrap_err();
+    assert_eq!(err, ContractError::OwnerLiquidityWindow {});
+    let res = provide_liquidity(&mut deps, mock_env(), "owner").unwrap();
+    assert!(res.attributes.contains(&attr("share", "50000000")));
+
+    // Third party liquidity is disabled
+    let err = provide_liquidity(&mut deps, env_at(start_time()), "addr0001").unwrap_err();
+    assert_eq!(err, ContractError::ThirdPartyLiquidityDisabled {});
+
+    let err = update_config(
+        &mut deps,
+        mock_env(),
+        "addr0001",
+        LbpPoolUpdateParams::SetThirdPartyLiquidity { allowed: true },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    update_config(
+        &mut deps,
+        mock_env(),
+        "owner",
+        LbpPoolUpdateParams::SetThirdPartyLiquidity { allowed: true },
+    )
+    .unwrap();
+    assert!(query_lbp_config(&deps, mock_env()).allow_third_party_liquidity);
+
+    // The owner window still applies
+    let err = provide_liquidity(&mut deps, mock_env(), "addr0001").unwrap_err();
+    assert_eq!(err, ContractError::OwnerLiquidityWindow {});
+    provide_liquidity(&mut deps, env_at(start_time()), "addr0001").unwrap();
+}
+
+#[test]
+fn fee_on_transfer_tokens() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(200_000_000),
+            )],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &Uint128::new(400_000_000))],
+        ),
+    ]);
+
+    let mut params = lbp_params();
+    params.fee_on_transfer = true;
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(&params),
+    )
+    .unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    RESERVES
+        .save(
+            deps.as_mut().storage,
+            &[Uint128::new(800_000_000), Uint128::new(200_000_000)],
+        )
+        .unwrap();
+    assert!(query_lbp_config(&deps, mock_env()).fee_on_transfer);
+
+    // The provision waits for the token transfer to settle. The slippage tolerance covers the transfer fee
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "owner",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000),
+            }],
+        ),
+        ExecuteMsg::ProvideLiquidity {
+            assets: [
+                Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    amount: Uint128::new(100_000_000),
+                },
+                Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: Addr::unchecked("asset0000"),
+                    },
+                    amount: Uint128::new(25_000_000),
+                },
+            ],
+            slippage_tolerance: Some(Decimal::percent(2)),
+            auto_stake: None,
+            receiver: None,
+            min_lp_to_receive: None,
+            deadline: None,
+            callback: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: String::from("owner"),
+                    recipient: String::from(MOCK_CONTRACT_ADDR),
+                    amount: Uint128::new(25_000_000),
+                })
+                .unwrap(),
+                funds: vec![],
+            },
+            4,
+        )]
+    );
+
+    // The token charges a 1% transfer fee, so the pair receives only 24.75 tokens
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(224_750_000),
+            )],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &Uint128::new(400_000_000))],
+        ),
+    ]);
+    let settle_reply = Reply {
+        id: 4,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+    let res = reply(deps.as_mut(), mock_env(), settle_reply.clone()).unwrap();
+    assert!(res
+        .attributes
+        .contains(&attr("assets", "100000000uusd, 24750000asset0000")));
+    assert!(res.attributes.contains(&attr("share", "49500000")));
+
+    let pool = query_pool(deps.as_ref()).unwrap();
+    assert_eq!(pool.assets[0].amount, Uint128::new(900_000_000));
+    assert_eq!(pool.assets[1].amount, Uint128::new(224_750_000));
+
+    // The pending provision is settled only once
+    reply(deps.as_mut(), mock_env(), settle_reply).unwrap_err();
+
+    // A swap uses the amount that the pair received instead of the sent amount
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(225_740_000),
+            )],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &Uint128::new(400_000_000))],
+        ),
+    ]);
+    let swap_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0001"),
+        amount: Uint128::new(1_000_000),
+        msg: to_binary(&Cw20HookMsg::Swap {
+            belief_price: None,
+            max_spread: Some(Decimal::percent(50)),
+            to: None,
+            deadline: None,
+            callback: None,
+        })
+        .unwrap(),
+    });
+
+    // Token swaps are not allowed before the sale starts either
+    let err = execute(
+        deps.as_mut(),
+        env_at(start_time() - 1),
+        mock_info("asset0000", &[]),
+        swap_msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::SaleNotStarted {});
+
+    let res = execute(
+        deps.as_mut(),
+        env_at(start_time()),
+        mock_info("asset0000", &[]),
+        swap_msg,
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&attr("offer_amount", "990000")));
+
+    let pool = query_pool(deps.as_ref()).unwrap();
+    assert_eq!(pool.assets[1].amount, Uint128::new(225_740_000));
+}
+
+#[test]
+fn change_weights() {
+    let mut deps = mock_pair(Uint128::zero());
+
+    let err = update_config(
+        &mut deps,
+        mock_env(),
+        "addr0000",
+        LbpPoolUpdateParams::StopChangingWeights {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // Before the sale starts the new schedule starts at the sale start
+    update_config(
+        &mut deps,
+        mock_env(),
+        "owner",
+        LbpPoolUpdateParams::StartChangingWeights {
+            end_weights: [50, 50],
+            end_time: start_time() + 4_000,
+        },
+    )
+    .unwrap();
+    let params = query_lbp_config(&deps, env_at(start_time() + 2_000));
+    assert_eq!(params.start_time, start_time());
+    assert_eq!(params.weights[0], Decimal::from_str("0.7").unwrap());
+
+    let err = update_config(
+        &mut deps,
+        mock_env(),
+        "owner",
+        LbpPoolUpdateParams::StartChangingWeights {
+            end_weights: [50, 50],
+            end_time: start_time(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::IncorrectSchedule {});
+
+    let err = update_config(
+        &mut deps,
+        mock_env(),
+        "owner",
+        LbpPoolUpdateParams::StartChangingWeights {
+            end_weights: [0, 50],
+            end_time: end_time(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Weighted(WeightedContractError::IncorrectWeights {})
+    );
+
+    // During the sale the new schedule starts from the current weights
+    let env = env_at(start_time() + 2_000);
+    update_config(
+        &mut deps,
+        env.clone(),
+        "owner",
+        LbpPoolUpdateParams::StartChangingWeights {
+            end_weights: [10, 90],
+            end_time: start_time() + 8_000,
+        },
+    )
+    .unwrap();
+    let params = query_lbp_config(&deps, env_at(start_time() + 5_000));
+    assert_eq!(params.start_time, start_time() + 2_000);
+    assert_eq!(params.start_weights[0], Decimal::from_str("0.7").unwrap());
+    assert_eq!(params.weights[0], Decimal::from_str("0.4").unwrap());
+
+    // Stopping keeps the current weights
+    update_config(
+        &mut deps,
+        env_at(start_time() + 5_000),
+        "owner",
+        LbpPoolUpdateParams::StopChangingWeights {},
+    )
+    .unwrap();
+    let params = query_lbp_config(&deps, env_at(end_time()));
+    assert_eq!(params.weights[0], Decimal::from_str("0.4").unwrap());
+}
@@ -1,36 +1,53 @@
 use crate::error::ContractError;
-use crate::math::{
-    calc_ask_amount, calc_offer_amount, compute_d, AMP_PRECISION, MAX_AMP, MAX_AMP_CHANGE,
-    MIN_AMP_CHANGING_TIME, N_COINS,
+use crate::state::{
+    Config, FlashLoanState, PendingProvide, ProvideParams, CONFIG, FLASH_LOAN, PENDING_PROVIDE,
+    RESERVES,
+};
+use astroport::stable_math::{
+    calc_ask_amount, calc_offer_amount, calc_spot_price, calc_withdraw_one_amount, compute_d,
+    AMP_PRECISION, MAX_AMP, MAX_AMP_CHANGE, MIN_AMP_CHANGING_TIME, N_COINS, PRICE_PRECISION,
 };
-use crate::state::{Config, CONFIG};
 
 use cosmwasm_bignumber::Decimal256;
 use cosmwasm_std::{
-    attr, entry_point, from_binary, to_binary, Addr, Binary, Coin, CosmosMsg, Decimal, Deps,
-    DepsMut, Env, MessageInfo, Reply, ReplyOn, Response, StdError, StdResult, SubMsg, Uint128,
-    WasmMsg,
+    attr, entry_point, from_binary, to_binary, Addr, Api, Binary, Coin, CosmosMsg, Decimal, Deps,
+    DepsMut, Env, Fraction, MessageInfo, QuerierWrapper, Reply, ReplyOn, Response, StdError,
+    StdResult, Storage, SubMsg, Uint128, WasmMsg,
 };
 
 use crate::response::MsgInstantiateContractResponse;
-use astroport::asset::{addr_validate_to_lower, format_lp_token_name, Asset, AssetInfo, PairInfo};
+use astroport::asset::{
+    addr_validate_to_lower, format_lp_token_name, Asset, AssetInfo, Callback, PairInfo,
+};
+use astroport::circuit_breaker::{
+    self, guardian, is_paused, query_status, set_paused, track_price_change,
+};
 use astroport::factory::PairType;
 
 use astroport::generator::Cw20HookMsg as GeneratorHookMsg;
 use astroport::pair::{
     ConfigResponse, InstantiateMsg, StablePoolParams, StablePoolUpdateParams, DEFAULT_SLIPPAGE,
-    MAX_ALLOWED_SLIPPAGE, TWAP_PRECISION,
+    MAX_ALLOWED_SLIPPAGE, MINIMUM_LIQUIDITY_AMOUNT, TWAP_PRECISION,
 };
 
+use astroport::observation::{
+    increase_observations_size, observe, store_observation, ObserveResponse,
+};
 use astroport::pair::{
-    CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, MigrateMsg, PoolResponse, QueryMsg,
-    ReverseSimulationResponse, SimulationResponse, StablePoolConfig,
+    CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, FlashLoanCallbackMsg, MigrateMsg,
+    PoolInvariantResponse, PoolResponse, PriceImpactResponse, QueryMsg, ReverseSimulationResponse,
+    SimulateProvideResponse, SimulateWithdrawResponse, SimulationResponse, SpotPriceResponse,
+    StablePoolConfig, VirtualPriceResponse, WithdrawOneAssetSimulationResponse,
 };
 use astroport::querier::{
-    query_factory_config, query_fee_info, query_supply, query_token_precision,
+    query_factory_config, query_pair_fee_info, query_supply, query_token_precision,
 };
+use astroport::reward_bearing::{
+    self, init_reward_bearing, load_reward_bearing_config, REWARD_INDEXES,
+};
+use astroport::stats::{query_daily_stats, query_stats, record_fee, record_swap};
 use astroport::{token::InstantiateMsg as TokenInstantiateMsg, U256};
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
 use protobuf::Message;
 use std::cmp::Ordering;
@@ -43,6 +60,12 @@ const CONTRACT_NAME: &str = "astroport-pair-stable";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 /// A `reply` call code ID of sub-message.
 const INSTANTIATE_TOKEN_REPLY_ID: u64 = 1;
+/// A `reply` call code ID of the flash loan callback sub-message.
+const FLASH_LOAN_REPLY_ID: u64 = 2;
+/// A `reply` call code ID of the reward holder instantiation sub-message.
+const INSTANTIATE_REWARD_HOLDER_REPLY_ID: u64 = 3;
+/// A `reply` call code ID of the last token transfer of a liquidity provision in the fee-on-transfer mode.
+const PROVIDE_LIQUIDITY_REPLY_ID: u64 = 4;
 
 /// ## Description
 /// Creates a new contract with the specified parameters in [`InstantiateMsg`].
@@ -58,7 +81,7 @@ const INSTANTIATE_TOKEN_REPLY_ID: u64 = 1;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
@@ -74,7 +97,7 @@ pub fn instantiate(
         return Err(ContractError::InitParamsNotFound {});
     }
 
-    let params: StablePoolParams = from_binary(&msg.init_params.unwrap())?;
+    let params: StablePoolParams = from_binary(msg.init_params.as_ref().unwrap())?;
 
     if params.amp == 0 || params.amp > MAX_AMP {
         return Err(ContractError::IncorrectAmp {});
@@ -97,14 +120,16 @@ pub fn instantiate(
         init_amp_time: env.block.time.seconds(),
         next_amp: params.amp * AMP_PRECISION,
         next_amp_time: env.block.time.seconds(),
+        fee_on_transfer: params.fee_on_transfer,
     };
 
     CONFIG.save(deps.storage, &config)?;
+    RESERVES.save(deps.storage, &[Uint128::zero(), Uint128::zero()])?;
 
-    let token_name = format_lp_token_name(msg.asset_infos, &deps.querier)?;
+    let token_name = format_lp_token_name(&msg.asset_infos, &deps.querier)?;
 
     // Create LP token
-    let sub_msg: Vec<SubMsg> = vec![SubMsg {
+    let mut sub_msg: Vec<SubMsg> = vec![SubMsg {
         msg: WasmMsg::Instantiate {
             code_id: msg.token_code_id,
             msg: to_binary(&TokenInstantiateMsg {
@@ -127,6 +152,15 @@ pub fn instantiate(
         reply_on: ReplyOn::Success,
     }];
 
+    // Create the reward holder if the pair holds a reward-bearing asset
+    sub_msg.extend(init_reward_bearing(
+        deps.branch(),
+        &env,
+        &msg.init_params,
+        &config.factory_addr,
+        INSTANTIATE_REWARD_HOLDER_REPLY_ID,
+    )?);
+
     Ok(Response::new().add_submessages(sub_msg))
 }
 
@@ -135,23 +169,38 @@ pub fn instantiate(
 /// # Params
 /// * **deps** is an object of type [`DepsMut`].
 ///
-/// * **_env** is an object of type [`Env`].
+/// * **env** is an object of type [`Env`].
 ///
 /// * **msg** is an object of type [`Reply`]. This is the reply from the submessage.
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
-    let mut config: Config = CONFIG.load(deps.storage)?;
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    if msg.id == FLASH_LOAN_REPLY_ID {
+        return repay_flash_loan(deps, env);
+    }
 
-    if config.pair_info.liquidity_token != Addr::unchecked("") {
-        return Err(ContractError::Unauthorized {});
+    if msg.id == PROVIDE_LIQUIDITY_REPLY_ID {
+        return settle_provide_liquidity(deps, env);
     }
 
+    let reply_id = msg.id;
     let data = msg.result.unwrap().data.unwrap();
     let res: MsgInstantiateContractResponse =
         Message::parse_from_bytes(data.as_slice()).map_err(|_| {
             StdError::parse_err("MsgInstantiateContractResponse", "failed to parse data")
         })?;
 
+    if reply_id == INSTANTIATE_REWARD_HOLDER_REPLY_ID {
+        let reward_holder = addr_validate_to_lower(deps.api, res.get_contract_address())?;
+        REWARD_INDEXES.holder.save(deps.storage, &reward_holder)?;
+        return Ok(Response::new().add_attribute("reward_holder", reward_holder));
+    }
+
+    let mut config: Config = CONFIG.load(deps.storage)?;
+
+    if config.pair_info.liquidity_token != Addr::unchecked("") {
+        return Err(ContractError::Unauthorized {});
+    }
+
     config.pair_info.liquidity_token =
         addr_validate_to_lower(deps.api, res.get_contract_address())?;
 
@@ -175,6 +224,9 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 /// * **ExecuteMsg::UpdateConfig { params: Binary }** Updates the contract configuration with the specified
 /// input parameters.
 ///
+/// * **ExecuteMsg::IncreaseObservationsSize { size }** Increases the amount of price observations
+/// stored by the pair.
+///
 /// * **ExecuteMsg::Receive(msg)** Receives a message of type [`Cw20ReceiveMsg`] and processes
 /// it depending on the received template.
 ///
@@ -183,6 +235,9 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 ///             slippage_tolerance,
 ///             auto_stake,
 ///             receiver,
+///             min_lp_to_receive,
+///             deadline,
+///             callback,
 ///         }** Provides liquidity in the pair using the specified input parameters.
 ///
 /// * **ExecuteMsg::Swap {
@@ -190,7 +245,51 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 ///             belief_price,
 ///             max_spread,
 ///             to,
+///             deadline,
+///             callback,
 ///         }** Performs an swap using the specified parameters.
+///
+/// * **ExecuteMsg::SwapExactOut {
+///             ask_asset,
+///             max_offer_amount,
+///             to,
+///         }** Performs a swap that returns an exact amount of ask assets and refunds the unused offer assets.
+///
+/// * **ExecuteMsg::FlashLoan {
+///             asset,
+///             callback_contract,
+///             msg,
+///         }** Lends a pool asset to a callback contract that has to repay it within the same transaction.
+///
+/// * **ExecuteMsg::Skim { to }** Sends the pair balances that exceed the pool reserves to the recipient.
+///
+/// * **ExecuteMsg::Sync {}** Sets the pool reserves to the current pair balances.
+///
+/// * **ExecuteMsg::Pause {}** Stops swaps and liquidity provision in the pair.
+///
+/// * **ExecuteMsg::Unpause {}** Resumes swaps and liquidity provision in the pair.
+///
+/// * **ExecuteMsg::UpdateCircuitBreaker {
+///             guardian,
+///             max_price_change_bps,
+///         }** Updates the guardian and the maximum price change of the pair.
+///
+/// * **ExecuteMsg::ClaimReward { receiver }** Claims the rewards of the reward-bearing pool asset
+/// and sends them to the receiver.
+///
+/// * **ExecuteMsg::ClaimRewardByGenerator {
+///             user,
+///             user_share,
+///             total_share,
+///         }** Claims the rewards for a LP position that a user staked in the Generator contract.
+///
+/// * **ExecuteMsg::HandleReward {
+///             previous_reward_balance,
+///             user,
+///             user_share,
+///             total_share,
+///             receiver,
+///         }** Distributes the claimed rewards.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -198,29 +297,51 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // The pool balances must not change while a flash loan is in progress
+    if FLASH_LOAN.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::FlashLoanInProgress {});
+    }
+
     match msg {
         ExecuteMsg::UpdateConfig { params } => update_config(deps, env, info, params),
+        ExecuteMsg::IncreaseObservationsSize { size } => {
+            increase_observations_size(deps.storage, size)?;
+            Ok(Response::new()
+                .add_attribute("action", "increase_observations_size")
+                .add_attribute("size", size.to_string()))
+        }
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
         ExecuteMsg::ProvideLiquidity {
             assets,
             slippage_tolerance,
             auto_stake,
             receiver,
-        } => provide_liquidity(
-            deps,
-            env,
-            info,
-            assets,
-            slippage_tolerance,
-            auto_stake,
-            receiver,
-        ),
+            min_lp_to_receive,
+            deadline,
+            callback,
+        } => {
+            assert_deadline(&env, deadline)?;
+            provide_liquidity(
+                deps,
+                env,
+                info,
+                assets,
+                slippage_tolerance,
+                auto_stake,
+                receiver,
+                min_lp_to_receive,
+                callback,
+            )
+        }
         ExecuteMsg::Swap {
             offer_asset,
             belief_price,
             max_spread,
             to,
+            deadline,
+            callback,
         } => {
+            assert_deadline(&env, deadline)?;
             offer_asset.info.check(deps.api)?;
             if !offer_asset.is_native_token() {
                 return Err(ContractError::Unauthorized {});
@@ -241,8 +362,76 @@ pub fn execute(
                 belief_price,
                 max_spread,
                 to_addr,
+                callback,
+            )
+        }
+        ExecuteMsg::SwapExactOut {
+            ask_asset,
+            max_offer_amount,
+            to,
+        } => {
+            ask_asset.info.check(deps.api)?;
+
+            let config: Config = CONFIG.load(deps.storage)?;
+            let offer_asset = Asset {
+                info: get_offer_asset_info(&config, &ask_asset.info)?,
+                amount: max_offer_amount,
+            };
+            if !offer_asset.is_native_token() {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let to_addr = if let Some(to_addr) = to {
+                Some(addr_validate_to_lower(deps.api, &to_addr)?)
+            } else {
+                None
+            };
+
+            swap_exact_out(
+                deps,
+                env,
+                info.clone(),
+                info.sender,
+                offer_asset,
+                ask_asset,
+                to_addr,
             )
         }
+        ExecuteMsg::FlashLoan {
+            asset,
+            callback_contract,
+            msg,
+        } => flash_loan(deps, env, info, asset, callback_contract, msg),
+        ExecuteMsg::Skim { to } => skim(deps, env, to),
+        ExecuteMsg::Sync {} => sync(deps, env),
+        ExecuteMsg::Pause {} => pause(deps, info),
+        ExecuteMsg::Unpause {} => unpause(deps, info),
+        ExecuteMsg::UpdateCircuitBreaker {
+            guardian,
+            max_price_change_bps,
+        } => update_circuit_breaker(deps, info, guardian, max_price_change_bps),
+        ExecuteMsg::ClaimReward { receiver } => claim_reward(deps, env, info, receiver),
+        ExecuteMsg::ClaimRewardByGenerator {
+            user,
+            user_share,
+            total_share,
+        } => claim_reward_by_generator(deps, env, info, user, user_share, total_share),
+        ExecuteMsg::HandleReward {
+            previous_reward_balance,
+            user,
+            user_share,
+            total_share,
+            receiver,
+        } => handle_reward(
+            deps,
+            env,
+            info,
+            previous_reward_balance,
+            user,
+            user_share,
+            total_share,
+            receiver,
+        ),
     }
 }
 
@@ -270,13 +459,16 @@ pub fn receive_cw20(
             belief_price,
             max_spread,
             to,
+            deadline,
+            callback,
         }) => {
+            assert_deadline(&env, deadline)?;
             // Only an asset (token) contract can execute this message
             let mut authorized: bool = false;
             let config: Config = CONFIG.load(deps.storage)?;
 
-            for pool in config.pair_info.asset_infos {
-                if let AssetInfo::Token { contract_addr, .. } = &pool {
+            for pool in config.pair_info.asset_infos.iter() {
+                if let AssetInfo::Token { contract_addr, .. } = pool {
                     if contract_addr == &info.sender {
                         authorized = true;
                     }
@@ -293,31 +485,132 @@ pub fn receive_cw20(
                 None
             };
 
+            let offer_asset = query_received_asset(
+                deps.as_ref(),
+                &env,
+                &config,
+                Asset {
+                    info: AssetInfo::Token { contract_addr },
+                    amount: cw20_msg.amount,
+                },
+            )?;
+
             swap(
                 deps,
                 env,
                 info,
                 Addr::unchecked(cw20_msg.sender),
+                offer_asset,
+                belief_price,
+                max_spread,
+                to_addr,
+                callback,
+            )
+        }
+        Ok(Cw20HookMsg::SwapExactOut { ask_asset, to }) => {
+            // Only an asset (token) contract can execute this message
+            let config: Config = CONFIG.load(deps.storage)?;
+            let offer_asset_info = AssetInfo::Token { contract_addr };
+            if !config
+                .pair_info
+                .asset_infos
+                .iter()
+                .any(|a| a.equal(&offer_asset_info))
+            {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let to_addr = if let Some(to_addr) = to {
+                Some(addr_validate_to_lower(deps.api, to_addr.as_str())?)
+            } else {
+                None
+            };
+
+            let offer_asset = query_received_asset(
+                deps.as_ref(),
+                &env,
+                &config,
                 Asset {
-                    info: AssetInfo::Token { contract_addr },
+                    info: offer_asset_info,
                     amount: cw20_msg.amount,
                 },
-                belief_price,
-                max_spread,
+            )?;
+
+            swap_exact_out(
+                deps,
+                env,
+                info,
+                Addr::unchecked(cw20_msg.sender),
+                offer_asset,
+                ask_asset,
                 to_addr,
             )
         }
-        Ok(Cw20HookMsg::WithdrawLiquidity {}) => withdraw_liquidity(
+        Ok(Cw20HookMsg::WithdrawLiquidity {
+            min_assets_to_receive,
+            deadline,
+            callback,
+        }) => {
+            assert_deadline(&env, deadline)?;
+            withdraw_liquidity(
+                deps,
+                env,
+                info,
+                Addr::unchecked(cw20_msg.sender),
+                cw20_msg.amount,
+                min_assets_to_receive,
+                callback,
+            )
+        }
+        Ok(Cw20HookMsg::WithdrawLiquidityOneAsset {
+            ask_asset_info,
+            min_amount,
+        }) => withdraw_liquidity_one_asset(
             deps,
             env,
             info,
             Addr::unchecked(cw20_msg.sender),
             cw20_msg.amount,
+            ask_asset_info,
+            min_amount,
         ),
         Err(err) => Err(ContractError::Std(err)),
     }
 }
 
+/// ## Description
+/// Returns the offer asset with the amount that the pair received for a swap. A pair in the fee-on-transfer mode
+/// measures the amount as its balance above the pool reserve, but never more than the sent amount.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **asset** is an object of type [`Asset`]. This is the offer asset with the sent amount.
+fn query_received_asset(deps: Deps, env: &Env, config: &Config, asset: Asset) -> StdResult<Asset> {
+    if !config.fee_on_transfer {
+        return Ok(asset);
+    }
+
+    let pools: [Asset; 2] = load_pools(deps.storage, config)?;
+    let reserve = pools
+        .iter()
+        .find(|pool| pool.info.equal(&asset.info))
+        .map(|pool| pool.amount)
+        .unwrap_or_default();
+    let balance = asset
+        .info
+        .query_pool(&deps.querier, env.contract.address.clone())?;
+    let received = balance.checked_sub(reserve).unwrap_or_default();
+
+    Ok(Asset {
+        amount: std::cmp::min(asset.amount, received),
+        info: asset.info,
+    })
+}
+
 /// ## Description
 /// Provides liquidity with the specified input parameters.
 /// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the
@@ -338,7 +631,14 @@ pub fn receive_cw20(
 ///
 /// * **receiver** is object of type [`Option<String>`]. This is the address that receives LP tokens.
 /// If this address isn't specified, the function will default to the caller.
+///
+/// * **min_lp_to_receive** is an object of type [`Option<Uint128>`]. This is the minimum amount of LP tokens to receive.
+/// The provision fails if fewer LP tokens are minted.
 /// NOTE - the address that wants to provide liquidity should approve the pair contract to pull its relevant tokens.
+///
+/// * **callback** is an [`Option`] field of type [`Callback`]. This is the contract that receives
+/// the LP tokens along with a message.
+#[allow(clippy::too_many_arguments)]
 pub fn provide_liquidity(
     deps: DepsMut,
     env: Env,
@@ -347,38 +647,28 @@ pub fn provide_liquidity(
     slippage_tolerance: Option<Decimal>,
     auto_stake: Option<bool>,
     receiver: Option<String>,
+    min_lp_to_receive: Option<Uint128>,
+    callback: Option<Callback>,
 ) -> Result<Response, ContractError> {
+    if is_paused(deps.storage)? {
+        return Err(ContractError::Paused {});
+    }
+
     assets[0].info.check(deps.api)?;
     assets[1].info.check(deps.api)?;
 
     let auto_stake = auto_stake.unwrap_or(false);
+    assert_callback(deps.api, &callback, auto_stake || receiver.is_some())?;
     for asset in assets.iter() {
         asset.assert_sent_native_token_balance(&info)?;
     }
 
-    let mut config: Config = CONFIG.load(deps.storage)?;
-    let mut pools: [Asset; 2] = config
-        .pair_info
-        .query_pools(&deps.querier, env.contract.address.clone())?;
-    let deposits: [Uint128; 2] = [
-        assets
-            .iter()
-            .find(|a| a.info.equal(&pools[0].info))
-            .map(|a| a.amount)
-            .expect("Wrong asset info is given"),
-        assets
-            .iter()
-            .find(|a| a.info.equal(&pools[1].info))
-            .map(|a| a.amount)
-            .expect("Wrong asset info is given"),
-    ];
-
-    if deposits[0].is_zero() || deposits[1].is_zero() {
-        return Err(ContractError::InvalidZeroAmount {});
-    }
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
+    let deposits = get_deposits(&assets, &pools)?;
 
     let mut messages: Vec<CosmosMsg> = vec![];
-    for (i, pool) in pools.iter_mut().enumerate() {
+    for (i, pool) in pools.iter().enumerate() {
         // If the pool is a token contract, then we need to execute a TransferFrom msg to receive funds
         if let AssetInfo::Token { contract_addr, .. } = &pool.info {
             messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -390,109 +680,275 @@ pub fn provide_liquidity(
                 })?,
                 funds: vec![],
             }))
-        } else {
-            // If the asset is a native token, the pool balance already increased
-            // To calculate the pool balance properly, we should subtract the user deposit from the recorded pool token amount
-            pool.amount = pool.amount.checked_sub(deposits[i])?;
         }
     }
 
-    // Assert that slippage tolerance is respected
-    assert_slippage_tolerance(&slippage_tolerance, &deposits, &pools)?;
+    let params = ProvideParams {
+        sender: info.sender,
+        assets,
+        slippage_tolerance,
+        auto_stake,
+        receiver,
+        min_lp_to_receive,
+        callback,
+    };
 
-    let token_precision_0 = query_token_precision(&deps.querier, pools[0].info.clone())?;
-    let token_precision_1 = query_token_precision(&deps.querier, pools[1].info.clone())?;
+    // The received token amounts are measured after the last transfer, so the provision is settled in a reply
+    if config.fee_on_transfer {
+        if let Some(last_transfer) = messages.pop() {
+            let balances: [Asset; 2] = config
+                .pair_info
+                .query_pools(&deps.querier, env.contract.address)?;
+            PENDING_PROVIDE.save(
+                deps.storage,
+                &PendingProvide {
+                    params,
+                    balances: [balances[0].amount, balances[1].amount],
+                },
+            )?;
 
-    let greater_precision = token_precision_0.max(token_precision_1);
+            return Ok(Response::new().add_messages(messages).add_submessage(
+                SubMsg::reply_on_success(last_transfer, PROVIDE_LIQUIDITY_REPLY_ID),
+            ));
+        }
+    }
 
-    let deposit_amount_0 = adjust_precision(deposits[0], token_precision_0, greater_precision)?;
-    let deposit_amount_1 = adjust_precision(deposits[1], token_precision_1, greater_precision)?;
+    finalize_provide_liquidity(deps, env, params, messages)
+}
 
-    let total_share = query_supply(&deps.querier, config.pair_info.liquidity_token.clone())?;
-    let share = if total_share.is_zero() {
-        let liquidity_token_precision = query_token_precision(
-            &deps.querier,
-            AssetInfo::Token {
-                contract_addr: config.pair_info.liquidity_token.clone(),
-            },
-        )?;
+/// ## Description
+/// Measures the token amounts that a pair in the fee-on-transfer mode received for a pending liquidity
+/// provision and settles the provision with them.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified
+/// attributes if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+pub fn settle_provide_liquidity(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let PendingProvide {
+        mut params,
+        balances,
+    } = PENDING_PROVIDE.load(deps.storage)?;
+    PENDING_PROVIDE.remove(deps.storage);
 
-        // Initial share = collateral amount
-        adjust_precision(
-            Uint128::new(
-                (U256::from(deposit_amount_0.u128()) * U256::from(deposit_amount_1.u128()))
-                    .integer_sqrt()
-                    .as_u128(),
-            ),
-            greater_precision,
-            liquidity_token_precision,
-        )?
-    } else {
-        let leverage = compute_current_amp(&config, &env)?
-            .checked_mul(u64::from(N_COINS))
-            .unwrap();
+    let balances_after: [Asset; 2] = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address.clone())?;
+
+    // Native coins are sent along with the message, so only the token amounts can differ
+    for asset in params.assets.iter_mut().filter(|a| !a.is_native_token()) {
+        for (balance, balance_after) in balances.iter().zip(balances_after.iter()) {
+            if balance_after.info.equal(&asset.info) {
+                let received = balance_after.amount.checked_sub(*balance)?;
+                asset.amount = std::cmp::min(asset.amount, received);
+            }
+        }
+    }
 
-        let mut pool_amount_0 =
-            adjust_precision(pools[0].amount, token_precision_0, greater_precision)?;
-        let mut pool_amount_1 =
-            adjust_precision(pools[1].amount, token_precision_1, greater_precision)?;
+    finalize_provide_liquidity(deps, env, params, vec![])
+}
 
-        let d_before_addition_liquidity =
-            compute_d(leverage, pool_amount_0.u128(), pool_amount_1.u128()).unwrap();
+/// ## Description
+/// Mints LP tokens for the deposited assets and updates the pool reserves.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified
+/// attributes if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **params** is an object of type [`ProvideParams`]. These are the parameters of the liquidity provision
+/// with the amounts that the pair received.
+///
+/// * **messages** is a [`Vec`] of type [`CosmosMsg`]. These are the messages that transfer the deposited tokens
+/// to the pair.
+fn finalize_provide_liquidity(
+    deps: DepsMut,
+    env: Env,
+    params: ProvideParams,
+    mut messages: Vec<CosmosMsg>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
+    let deposits = get_deposits(&params.assets, &pools)?;
+
+    // Assert that slippage tolerance is respected
+    assert_slippage_tolerance(&params.slippage_tolerance, &deposits, &pools)?;
 
-        pool_amount_0 = pool_amount_0.checked_add(deposit_amount_0)?;
-        pool_amount_1 = pool_amount_1.checked_add(deposit_amount_1)?;
+    let token_precision_0 = query_token_precision(&deps.querier, pools[0].info.clone())?;
+    let token_precision_1 = query_token_precision(&deps.querier, pools[1].info.clone())?;
 
-        let d_after_addition_liquidity =
-            compute_d(leverage, pool_amount_0.u128(), pool_amount_1.u128()).unwrap();
+    let total_share = query_supply(&deps.querier, config.pair_info.liquidity_token.clone())?;
 
-        // d after adding liquidity may be less than or equal to d before adding liquidity because of rounding
-        if d_before_addition_liquidity >= d_after_addition_liquidity {
-            return Err(ContractError::LiquidityAmountTooSmall {});
+    // Get fee info from the factory
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
+
+    let (share, imbalance_fees) = compute_share(
+        &deps.querier,
+        &env,
+        &config,
+        &pools,
+        &deposits,
+        [token_precision_0, token_precision_1],
+        total_share,
+        fee_info.total_fee_rate,
+    )?;
+
+    if total_share.is_zero() {
+        // Lock the minimum liquidity amount in the pair forever, so the first provider
+        // can't inflate the LP token price and round the share of the next providers down to zero
+        messages.extend(mint_liquidity_token_message(
+            deps.as_ref(),
+            &config,
+            env.clone(),
+            env.contract.address.clone(),
+            MINIMUM_LIQUIDITY_AMOUNT,
+            false,
+        )?);
+    }
+
+    // Maker fee
+    let mut maker_fees = [Uint128::zero(); 2];
+    let mut maker_fee_messages: Vec<CosmosMsg> = vec![];
+    if let Some(fee_address) = fee_info.fee_address {
+        for (i, pool) in pools.iter().enumerate() {
+            if let Some(f) = calculate_maker_fee(
+                pool.info.clone(),
+                imbalance_fees[i],
+                fee_info.maker_fee_rate,
+            ) {
+                maker_fee_messages.push(f.clone().into_msg(&deps.querier, fee_address.clone())?);
+                maker_fees[i] = f.amount;
+            }
         }
+    }
 
-        total_share.multiply_ratio(
-            d_after_addition_liquidity - d_before_addition_liquidity,
-            d_before_addition_liquidity,
-        )
-    };
+    // The imbalance fee is charged like a swap commission
+    for (i, pool) in pools.iter().enumerate() {
+        record_fee(
+            deps.storage,
+            env.block.time.seconds(),
+            &pool.info,
+            imbalance_fees[i],
+            maker_fees[i],
+        )?;
+    }
 
-    if share.is_zero() {
-        return Err(ContractError::LiquidityAmountTooSmall {});
+    // The minimum is checked before minting, so it also applies to the LP tokens staked in the Generator
+    if let Some(min_lp_to_receive) = params.min_lp_to_receive {
+        if share < min_lp_to_receive {
+            return Err(ContractError::MinLpToReceiveAssertion {});
+        }
     }
 
-    // Mint LP token for the caller (or for the receiver if it was set)
-    let receiver = receiver.unwrap_or_else(|| info.sender.to_string());
-    messages.extend(mint_liquidity_token_message(
-        deps.as_ref(),
-        &config,
-        env.clone(),
-        addr_validate_to_lower(deps.api, receiver.as_str())?,
-        share,
-        auto_stake,
-    )?);
+    let receiver = match &params.callback {
+        Some(callback) => callback.contract.clone(),
+        None => params.receiver.unwrap_or_else(|| params.sender.to_string()),
+    };
+    let callback_messages = match params.callback {
+        // Mint LP tokens for the pair and send them to the callback contract after all the other messages
+        Some(callback) => {
+            messages.extend(mint_liquidity_token_message(
+                deps.as_ref(),
+                &config,
+                env.clone(),
+                env.contract.address.clone(),
+                share,
+                false,
+            )?);
+            callback.into_msgs(
+                &deps.querier,
+                &[Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: config.pair_info.liquidity_token.clone(),
+                    },
+                    amount: share,
+                }],
+            )?
+        }
+        // Mint LP token for the caller (or for the receiver if it was set)
+        None => {
+            messages.extend(mint_liquidity_token_message(
+                deps.as_ref(),
+                &config,
+                env.clone(),
+                addr_validate_to_lower(deps.api, receiver.as_str())?,
+                share,
+                params.auto_stake,
+            )?);
+            vec![]
+        }
+    };
+    messages.extend(maker_fee_messages);
 
     // Accumulate prices assets in the pool
     if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
-        env,
+        env.clone(),
         &config,
         pools[0].amount,
         token_precision_0,
         pools[1].amount,
         token_precision_1,
     )? {
+        store_observation(
+            deps.storage,
+            config.block_time_last,
+            block_time,
+            price0_cumulative_new.wrapping_sub(config.price0_cumulative_last),
+            price1_cumulative_new.wrapping_sub(config.price1_cumulative_last),
+        )?;
         config.price0_cumulative_last = price0_cumulative_new;
         config.price1_cumulative_last = price1_cumulative_new;
         config.block_time_last = block_time;
         CONFIG.save(deps.storage, &config)?;
     }
 
+    RESERVES.save(
+        deps.storage,
+        &[
+            pools[0]
+                .amount
+                .checked_add(deposits[0])?
+                .checked_sub(maker_fees[0])?,
+            pools[1]
+                .amount
+                .checked_add(deposits[1])?
+                .checked_sub(maker_fees[1])?,
+        ],
+    )?;
+    record_price_change(deps.storage, &deps.querier, &env, &config, &pools)?;
+
+    messages.extend(callback_messages);
+
     Ok(Response::new().add_messages(messages).add_attributes(vec![
         attr("action", "provide_liquidity"),
-        attr("sender", info.sender.as_str()),
+        attr("sender", params.sender.as_str()),
         attr("receiver", receiver.as_str()),
-        attr("assets", format!("{}, {}", assets[0], assets[1])),
+        attr(
+            "assets",
+            format!("{}, {}", params.assets[0], params.assets[1]),
+        ),
         attr("share", share.to_string()),
+        attr(
+            "imbalance_fees",
+            format!(
+                "{}{}, {}{}",
+                imbalance_fees[0], pools[0].info, imbalance_fees[1], pools[1].info
+            ),
+        ),
+        attr(
+            "maker_fees",
+            format!(
+                "{}{}, {}{}",
+                maker_fees[0], pools[0].info, maker_fees[1], pools[1].info
+            ),
+        ),
     ]))
 }
 
@@ -574,21 +1030,30 @@ fn mint_liquidity_token_message(
 /// * **sender** is an object of type [`Addr`]. This is the address that will receive the withdrawn liquidity.
 ///
 /// * **amount** is an object of type [`Uint128`]. This is the amount of LP tokens to burn and withdraw liquidity with.
+///
+/// * **min_assets_to_receive** is an object of type [`Option<Vec<Asset>>`]. These are the minimum amounts of pool assets to receive.
+///
+/// * **callback** is an [`Option`] field of type [`Callback`]. This is the contract that receives
+/// the withdrawn assets along with a message.
 pub fn withdraw_liquidity(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     sender: Addr,
     amount: Uint128,
+    min_assets_to_receive: Option<Vec<Asset>>,
+    callback: Option<Callback>,
 ) -> Result<Response, ContractError> {
     let mut config: Config = CONFIG.load(deps.storage).unwrap();
 
     if info.sender != config.pair_info.liquidity_token {
         return Err(ContractError::Unauthorized {});
     }
+    assert_callback(deps.api, &callback, false)?;
 
     let (pools, total_share) = pool_info(deps.as_ref(), config.clone())?;
     let refund_assets = get_share_in_assets(&pools, amount, total_share);
+    assert_min_assets_to_receive(&refund_assets, min_assets_to_receive)?;
 
     // Accumulate prices for the assets in the pool
     if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
@@ -599,25 +1064,47 @@ pub fn withdraw_liquidity(
         pools[1].amount,
         query_token_precision(&deps.querier, pools[1].info.clone())?,
     )? {
+        store_observation(
+            deps.storage,
+            config.block_time_last,
+            block_time,
+            price0_cumulative_new.wrapping_sub(config.price0_cumulative_last),
+            price1_cumulative_new.wrapping_sub(config.price1_cumulative_last),
+        )?;
         config.price0_cumulative_last = price0_cumulative_new;
         config.price1_cumulative_last = price1_cumulative_new;
         config.block_time_last = block_time;
         CONFIG.save(deps.storage, &config)?;
     }
 
-    let messages: Vec<CosmosMsg> = vec![
-        refund_assets[0]
-            .clone()
-            .into_msg(&deps.querier, sender.clone())?,
-        refund_assets[1]
-            .clone()
-            .into_msg(&deps.querier, sender.clone())?,
-        CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: config.pair_info.liquidity_token.to_string(),
-            msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
-            funds: vec![],
-        }),
-    ];
+    RESERVES.save(
+        deps.storage,
+        &[
+            pools[0].amount.checked_sub(refund_assets[0].amount)?,
+            pools[1].amount.checked_sub(refund_assets[1].amount)?,
+        ],
+    )?;
+
+    let mut messages: Vec<CosmosMsg> = match &callback {
+        Some(_) => vec![],
+        None => vec![
+            refund_assets[0]
+                .clone()
+                .into_msg(&deps.querier, sender.clone())?,
+            refund_assets[1]
+                .clone()
+                .into_msg(&deps.querier, sender.clone())?,
+        ],
+    };
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.pair_info.liquidity_token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
+        funds: vec![],
+    }));
+    if let Some(callback) = callback {
+        // The callback contract gets the assets after the LP tokens are burned
+        messages.extend(callback.into_msgs(&deps.querier, &refund_assets)?);
+    }
 
     let attributes = vec![
         attr("action", "withdraw_liquidity"),
@@ -634,6 +1121,359 @@ pub fn withdraw_liquidity(
         .add_attributes(attributes))
 }
 
+/// ## Description
+/// Withdraws liquidity from the pool in a single asset using the StableSwap invariant.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified attributes
+/// if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **sender** is an object of type [`Addr`]. This is the address that will receive the ask assets.
+///
+/// * **amount** is an object of type [`Uint128`]. This is the amount of LP tokens to burn.
+///
+/// * **ask_asset_info** is an object of type [`AssetInfo`]. This is the asset to receive.
+///
+/// * **min_amount** is an [`Option`] field of type [`Uint128`]. This is the minimum amount of ask assets to receive.
+pub fn withdraw_liquidity_one_asset(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    amount: Uint128,
+    ask_asset_info: AssetInfo,
+    min_amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    // The withdrawal swaps one of the pool assets, so it is stopped together with swaps
+    if is_paused(deps.storage)? {
+        return Err(ContractError::Paused {});
+    }
+
+    let mut config: Config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.pair_info.liquidity_token {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let (pools, total_share) = pool_info(deps.as_ref(), config.clone())?;
+    let ask_index = pools
+        .iter()
+        .position(|pool| pool.info.equal(&ask_asset_info))
+        .ok_or(ContractError::AssetMismatch {})?;
+
+    // Get fee info from the factory
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
+
+    let (return_amount, commission_amount) = compute_withdraw_one_asset(
+        &deps.querier,
+        &env,
+        &config,
+        &pools,
+        amount,
+        total_share,
+        ask_index,
+        fee_info.total_fee_rate,
+    )?;
+
+    if return_amount < min_amount.unwrap_or_default() {
+        return Err(ContractError::MinAmountAssertion {});
+    }
+
+    let return_asset = Asset {
+        info: ask_asset_info.clone(),
+        amount: return_amount,
+    };
+    let mut messages: Vec<CosmosMsg> = vec![return_asset
+        .clone()
+        .into_msg(&deps.querier, sender.clone())?];
+
+    // Maker fee
+    let mut maker_fee_amount = Uint128::new(0);
+    if let Some(fee_address) = fee_info.fee_address {
+        if let Some(f) =
+            calculate_maker_fee(ask_asset_info, commission_amount, fee_info.maker_fee_rate)
+        {
+            messages.push(f.clone().into_msg(&deps.querier, fee_address)?);
+            maker_fee_amount = f.amount;
+        }
+    }
+
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.pair_info.liquidity_token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
+        funds: vec![],
+    }));
+
+    // Accumulate prices for the assets in the pool
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
+        env.clone(),
+        &config,
+        pools[0].amount,
+        query_token_precision(&deps.querier, pools[0].info.clone())?,
+        pools[1].amount,
+        query_token_precision(&deps.querier, pools[1].info.clone())?,
+    )? {
+        store_observation(
+            deps.storage,
+            config.block_time_last,
+            block_time,
+            price0_cumulative_new.wrapping_sub(config.price0_cumulative_last),
+            price1_cumulative_new.wrapping_sub(config.price1_cumulative_last),
+        )?;
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    // The other asset stays in the pool
+    let mut reserves = [pools[0].amount, pools[1].amount];
+    reserves[ask_index] = reserves[ask_index].checked_sub(return_amount + maker_fee_amount)?;
+    RESERVES.save(deps.storage, &reserves)?;
+    record_price_change(deps.storage, &deps.querier, &env, &config, &pools)?;
+
+    record_fee(
+        deps.storage,
+        env.block.time.seconds(),
+        &return_asset.info,
+        commission_amount,
+        maker_fee_amount,
+    )?;
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "withdraw_liquidity_one_asset"),
+        attr("sender", sender.as_str()),
+        attr("withdrawn_share", amount.to_string()),
+        attr("ask_asset", return_asset.info.to_string()),
+        attr("return_amount", return_amount.to_string()),
+        attr("commission_amount", commission_amount.to_string()),
+        attr("maker_fee_amount", maker_fee_amount.to_string()),
+    ]))
+}
+
+/// ## Description
+/// Returns the amount of ask assets and the commission for burning LP tokens and withdrawing a single asset.
+/// Only the part of the withdrawal above the proportional share of the ask asset acts as a swap,
+/// so the fee is charged on that part only.
+/// ## Params
+/// * **querier** is an object of type [`QuerierWrapper`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **pools** is an array with two objects of type [`Asset`]. These are the current pool amounts.
+///
+/// * **amount** is an object of type [`Uint128`]. This is the amount of LP tokens to burn.
+///
+/// * **total_share** is an object of type [`Uint128`]. This is the total amount of LP tokens currently issued by the pool.
+///
+/// * **ask_index** is an object of type [`usize`]. This is the position of the ask asset in the pool.
+///
+/// * **commission_rate** is an object of type [`Decimal`]. This is the total fee rate charged by the pair.
+#[allow(clippy::too_many_arguments)]
+fn compute_withdraw_one_asset(
+    querier: &QuerierWrapper,
+    env: &Env,
+    config: &Config,
+    pools: &[Asset; 2],
+    amount: Uint128,
+    total_share: Uint128,
+    ask_index: usize,
+    commission_rate: Decimal,
+) -> StdResult<(Uint128, Uint128)> {
+    let other_index = 1 - ask_index;
+    let ask_precision = query_token_precision(querier, pools[ask_index].info.clone())?;
+    let other_precision = query_token_precision(querier, pools[other_index].info.clone())?;
+    let greater_precision = ask_precision.max(other_precision);
+
+    let ask_pool = adjust_precision(pools[ask_index].amount, ask_precision, greater_precision)?;
+    let other_pool = adjust_precision(
+        pools[other_index].amount,
+        other_precision,
+        greater_precision,
+    )?;
+
+    let withdraw_amount = calc_withdraw_one_amount(
+        ask_pool.u128(),
+        other_pool.u128(),
+        amount.u128(),
+        total_share.u128(),
+        compute_current_amp(config, env)?,
+    )?;
+    let withdraw_amount = adjust_precision(
+        Uint128::new(withdraw_amount),
+        greater_precision,
+        ask_precision,
+    )?;
+
+    let proportional_amount = pools[ask_index].amount.multiply_ratio(amount, total_share);
+    let commission_amount = withdraw_amount.saturating_sub(proportional_amount) * commission_rate;
+
+    Ok((
+        withdraw_amount.checked_sub(commission_amount)?,
+        commission_amount,
+    ))
+}
+
+/// ## Description
+/// Returns the deposit amounts of the given assets in the order of the pool assets.
+/// Returns a [`ContractError`] if an asset does not belong to the pool or if a deposit amount is zero.
+/// ## Params
+/// * **assets** is an array with two objects of type [`Asset`]. These are the assets to deposit.
+///
+/// * **pools** is an array with two objects of type [`Asset`]. These are the assets in the pool.
+fn get_deposits(assets: &[Asset; 2], pools: &[Asset; 2]) -> Result<[Uint128; 2], ContractError> {
+    let mut deposits = [Uint128::zero(); 2];
+    for (deposit, pool) in deposits.iter_mut().zip(pools.iter()) {
+        *deposit = assets
+            .iter()
+            .find(|a| a.info.equal(&pool.info))
+            .map(|a| a.amount)
+            .ok_or(ContractError::AssetMismatch {})?;
+    }
+
+    if deposits[0].is_zero() || deposits[1].is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    Ok(deposits)
+}
+
+/// ## Description
+/// Returns the amount of LP tokens that a deposit mints together with the imbalance fees charged on it
+/// in the precision of the pool assets. The first deposit mints the square root of the product of the
+/// deposit amounts minus the [`MINIMUM_LIQUIDITY_AMOUNT`] that is locked in the pair and charges no fees.
+/// ## Params
+/// * **querier** is an object of type [`QuerierWrapper`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **pools** is an array with two objects of type [`Asset`]. These are the assets in the pool.
+///
+/// * **deposits** is an array with two objects of type [`Uint128`]. These are the deposit amounts in the order of the pool assets.
+///
+/// * **token_precisions** is an array with two items of type [`u8`]. These are the precisions of the pool assets.
+///
+/// * **total_share** is an object of type [`Uint128`]. This is the total amount of LP tokens currently issued by the pool.
+///
+/// * **total_fee_rate** is an object of type [`Decimal`]. This is the total fee rate charged by the pair.
+#[allow(clippy::too_many_arguments)]
+fn compute_share(
+    querier: &QuerierWrapper,
+    env: &Env,
+    config: &Config,
+    pools: &[Asset; 2],
+    deposits: &[Uint128; 2],
+    token_precisions: [u8; 2],
+    total_share: Uint128,
+    total_fee_rate: Decimal,
+) -> Result<(Uint128, [Uint128; 2]), ContractError> {
+    let greater_precision = token_precisions[0].max(token_precisions[1]);
+
+    let deposit_amount_0 = adjust_precision(deposits[0], token_precisions[0], greater_precision)?;
+    let deposit_amount_1 = adjust_precision(deposits[1], token_precisions[1], greater_precision)?;
+
+    let mut imbalance_fees = [Uint128::zero(); 2];
+    let share = if total_share.is_zero() {
+        let liquidity_token_precision = query_token_precision(
+            querier,
+            AssetInfo::Token {
+                contract_addr: config.pair_info.liquidity_token.clone(),
+            },
+        )?;
+
+        // Initial share = collateral amount
+        let share = adjust_precision(
+            Uint128::new(
+                (U256::from(deposit_amount_0.u128()) * U256::from(deposit_amount_1.u128()))
+                    .integer_sqrt()
+                    .as_u128(),
+            ),
+            greater_precision,
+            liquidity_token_precision,
+        )?;
+        if share <= MINIMUM_LIQUIDITY_AMOUNT {
+            return Err(ContractError::MinimumLiquidityAmountError {});
+        }
+
+        share - MINIMUM_LIQUIDITY_AMOUNT
+    } else {
+        let leverage = compute_current_amp(config, env)?
+            .checked_mul(u64::from(N_COINS))
+            .ok_or(ContractError::InvariantComputationError {})?;
+
+        let old_balances = [
+            adjust_precision(pools[0].amount, token_precisions[0], greater_precision)?,
+            adjust_precision(pools[1].amount, token_precisions[1], greater_precision)?,
+        ];
+        let new_balances = [
+            old_balances[0].checked_add(deposit_amount_0)?,
+            old_balances[1].checked_add(deposit_amount_1)?,
+        ];
+
+        let d_before_addition_liquidity =
+            compute_d(leverage, old_balances[0].u128(), old_balances[1].u128())
+                .ok_or(ContractError::InvariantComputationError {})?;
+        let d_after_addition_liquidity =
+            compute_d(leverage, new_balances[0].u128(), new_balances[1].u128())
+                .ok_or(ContractError::InvariantComputationError {})?;
+
+        // d after adding liquidity may be less than or equal to d before adding liquidity because of rounding
+        if d_before_addition_liquidity >= d_after_addition_liquidity {
+            return Err(ContractError::LiquidityAmountTooSmall {});
+        }
+
+        // Charge the fee on the deviation of every deposit from a balanced deposit, which is about
+        // what swapping the excess into the other asset would cost. The fee rate is fee * N / (4 * (N - 1))
+        let mut balances_after_fee = new_balances;
+        for i in 0..N_COINS as usize {
+            let ideal_balance = old_balances[i]
+                .multiply_ratio(d_after_addition_liquidity, d_before_addition_liquidity);
+            let difference = if ideal_balance > new_balances[i] {
+                ideal_balance - new_balances[i]
+            } else {
+                new_balances[i] - ideal_balance
+            };
+            let fee = (difference * total_fee_rate).multiply_ratio(N_COINS, 4 * (N_COINS - 1));
+            balances_after_fee[i] = new_balances[i].checked_sub(fee)?;
+            imbalance_fees[i] = adjust_precision(fee, greater_precision, token_precisions[i])?;
+        }
+
+        let d_after_fee = compute_d(
+            leverage,
+            balances_after_fee[0].u128(),
+            balances_after_fee[1].u128(),
+        )
+        .ok_or(ContractError::InvariantComputationError {})?;
+        if d_before_addition_liquidity >= d_after_fee {
+            return Err(ContractError::LiquidityAmountTooSmall {});
+        }
+
+        // The rest of the fee stays in the pool and goes to the current liquidity providers
+        total_share.multiply_ratio(
+            d_after_fee - d_before_addition_liquidity,
+            d_before_addition_liquidity,
+        )
+    };
+
+    if share.is_zero() {
+        return Err(ContractError::LiquidityAmountTooSmall {});
+    }
+
+    Ok((share, imbalance_fees))
+}
+
 /// ## Description
 /// Return the amount of tokens that a specific amount of LP tokens would withdraw.
 /// ## Params
@@ -685,6 +1525,9 @@ pub fn get_share_in_assets(
 ///
 /// * **to** is an object of type [`Option<Addr>`]. This is the address that receives ask tokens.
 /// NOTE - the address that wants to swap should approve the pair contract to pull the offer token.
+///
+/// * **callback** is an [`Option`] field of type [`Callback`]. This is the contract that receives
+/// the ask assets along with a message.
 #[allow(clippy::too_many_arguments)]
 pub fn swap(
     deps: DepsMut,
@@ -695,26 +1538,17 @@ pub fn swap(
     belief_price: Option<Decimal>,
     max_spread: Option<Decimal>,
     to: Option<Addr>,
+    callback: Option<Callback>,
 ) -> Result<Response, ContractError> {
+    if is_paused(deps.storage)? {
+        return Err(ContractError::Paused {});
+    }
+
     offer_asset.assert_sent_native_token_balance(&info)?;
 
     let mut config: Config = CONFIG.load(deps.storage)?;
 
-    // If the asset balance already increased
-    // We should subtract the user deposit from the pool offer asset amount
-    let pools: Vec<Asset> = config
-        .pair_info
-        .query_pools(&deps.querier, env.clone().contract.address)?
-        .iter()
-        .map(|p| {
-            let mut p = p.clone();
-            if p.info.equal(&offer_asset.info) {
-                p.amount = p.amount.checked_sub(offer_asset.amount).unwrap();
-            }
-
-            p
-        })
-        .collect();
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
 
     let offer_pool: Asset;
     let ask_pool: Asset;
@@ -730,10 +1564,10 @@ pub fn swap(
     }
 
     // Get fee info from the factory
-    let fee_info = query_fee_info(
+    let fee_info = query_pair_fee_info(
         &deps.querier,
         config.factory_addr.clone(),
-        config.pair_info.pair_type.clone(),
+        config.pair_info.contract_addr.clone(),
     )?;
 
     let offer_amount = offer_asset.amount;
@@ -764,10 +1598,20 @@ pub fn swap(
 
     let tax_amount = return_asset.compute_tax(&deps.querier)?;
 
-    let receiver = to.unwrap_or_else(|| sender.clone());
+    assert_callback(deps.api, &callback, to.is_some())?;
+    let receiver = match &callback {
+        Some(callback) => Addr::unchecked(&callback.contract),
+        None => to.unwrap_or_else(|| sender.clone()),
+    };
 
-    let mut messages: Vec<CosmosMsg> =
-        vec![return_asset.into_msg(&deps.querier, receiver.clone())?];
+    let (mut messages, callback_messages): (Vec<CosmosMsg>, Vec<CosmosMsg>) = match callback {
+        // The callback contract gets the return assets after all the other messages
+        Some(callback) => (vec![], callback.into_msgs(&deps.querier, &[return_asset])?),
+        None => (
+            vec![return_asset.into_msg(&deps.querier, receiver.clone())?],
+            vec![],
+        ),
+    };
 
     // Maker fee
     let mut maker_fee_amount = Uint128::new(0);
@@ -784,19 +1628,50 @@ pub fn swap(
 
     // Accumulate prices for the assets in the pool
     if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
-        env,
+        env.clone(),
         &config,
         pools[0].amount,
         query_token_precision(&deps.querier, pools[0].info.clone())?,
         pools[1].amount,
         query_token_precision(&deps.querier, pools[1].info.clone())?,
     )? {
+        store_observation(
+            deps.storage,
+            config.block_time_last,
+            block_time,
+            price0_cumulative_new.wrapping_sub(config.price0_cumulative_last),
+            price1_cumulative_new.wrapping_sub(config.price1_cumulative_last),
+        )?;
         config.price0_cumulative_last = price0_cumulative_new;
         config.price1_cumulative_last = price1_cumulative_new;
         config.block_time_last = block_time;
         CONFIG.save(deps.storage, &config)?;
     }
 
+    // The Maker fee leaves the pool along with the return amount
+    update_reserves_after_swap(
+        deps.storage,
+        &pools,
+        &offer_asset.info,
+        offer_amount,
+        return_amount + maker_fee_amount,
+    )?;
+    record_price_change(deps.storage, &deps.querier, &env, &config, &pools)?;
+
+    record_swap(
+        deps.storage,
+        env.block.time.seconds(),
+        &offer_asset,
+        &Asset {
+            info: ask_pool.info.clone(),
+            amount: return_amount,
+        },
+        commission_amount,
+        maker_fee_amount,
+    )?;
+
+    messages.extend(callback_messages);
+
     Ok(Response::new()
         .add_messages(
             // 1. send collateral token from the contract to a user
@@ -817,28 +1692,623 @@ pub fn swap(
 }
 
 /// ## Description
-/// Accumulate token prices for the assets in the pool.
-/// Note that this function shifts **block_time** when any of the token prices is zero in order to not
-/// fill an accumulator with a null price for that period.
+/// Performs a swap that returns an exact amount of ask assets. The required offer amount is computed
+/// the same way as in a reverse simulation and the unused part of the offered assets is refunded to the sender.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the
+/// specified attributes if the operation was successful.
 /// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
 /// * **env** is an object of type [`Env`].
 ///
-/// * **config** is an object of type [`Config`].
+/// * **info** is an object of type [`MessageInfo`].
 ///
-/// * **x** is an object of type [`Uint128`]. This is the balance of asset\[\0] in the pool.
+/// * **sender** is an object of type [`Addr`]. This is the default recipient of the swap operation.
 ///
-/// * **x_precision** is an object of type [`u8`]. This is the precision for the x token.
+/// * **offer_asset** is an object of type [`Asset`]. This is the asset to swap as well as the maximum amount of it to spend.
 ///
-/// * **y** is an object of type [`Uint128`]. This is the balance of asset\[\1] in the pool.
+/// * **ask_asset** is an object of type [`Asset`]. This is the asset to receive as well as the exact amount to receive.
 ///
-/// * **y_precision** is an object of type [`u8`]. This is the precision for the y token.
-pub fn accumulate_prices(
+/// * **to** is an object of type [`Option<Addr>`]. This is the address that receives ask tokens.
+pub fn swap_exact_out(
+    deps: DepsMut,
     env: Env,
-    config: &Config,
-    x: Uint128,
-    x_precision: u8,
-    y: Uint128,
-    y_precision: u8,
+    info: MessageInfo,
+    sender: Addr,
+    offer_asset: Asset,
+    ask_asset: Asset,
+    to: Option<Addr>,
+) -> Result<Response, ContractError> {
+    if is_paused(deps.storage)? {
+        return Err(ContractError::Paused {});
+    }
+
+    offer_asset.assert_sent_native_token_balance(&info)?;
+
+    if ask_asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let mut config: Config = CONFIG.load(deps.storage)?;
+
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
+
+    let offer_pool: Asset;
+    let ask_pool: Asset;
+
+    if offer_asset.info.equal(&pools[0].info) {
+        offer_pool = pools[0].clone();
+        ask_pool = pools[1].clone();
+    } else if offer_asset.info.equal(&pools[1].info) {
+        offer_pool = pools[1].clone();
+        ask_pool = pools[0].clone();
+    } else {
+        return Err(ContractError::AssetMismatch {});
+    }
+
+    if !ask_asset.info.equal(&ask_pool.info) {
+        return Err(ContractError::AssetMismatch {});
+    }
+
+    // The pool can't give away its whole ask balance
+    if ask_asset.amount >= ask_pool.amount {
+        return Err(ContractError::AskAmountExceedsPool {});
+    }
+
+    // Get fee info from the factory
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
+
+    let (offer_amount, spread_amount, commission_amount) = compute_offer_amount(
+        offer_pool.amount,
+        query_token_precision(&deps.querier, offer_pool.info.clone())?,
+        ask_pool.amount,
+        query_token_precision(&deps.querier, ask_pool.info.clone())?,
+        ask_asset.amount,
+        fee_info.total_fee_rate,
+        compute_current_amp(&config, &env)?,
+    )?;
+
+    if offer_amount > offer_asset.amount {
+        return Err(ContractError::MaxOfferAmountAssertion {});
+    }
+
+    let return_asset = Asset {
+        info: ask_pool.info.clone(),
+        amount: ask_asset.amount,
+    };
+
+    let tax_amount = return_asset.compute_tax(&deps.querier)?;
+
+    let receiver = to.unwrap_or_else(|| sender.clone());
+
+    let mut messages: Vec<CosmosMsg> =
+        vec![return_asset.into_msg(&deps.querier, receiver.clone())?];
+
+    // Refund the unused offer assets
+    let refund_amount = offer_asset.amount.checked_sub(offer_amount)?;
+    if !refund_amount.is_zero() {
+        messages.push(
+            Asset {
+                info: offer_pool.info.clone(),
+                amount: refund_amount,
+            }
+            .into_msg(&deps.querier, sender.clone())?,
+        );
+    }
+
+    // Maker fee
+    let mut maker_fee_amount = Uint128::new(0);
+    if let Some(fee_address) = fee_info.fee_address {
+        if let Some(f) = calculate_maker_fee(
+            ask_pool.info.clone(),
+            commission_amount,
+            fee_info.maker_fee_rate,
+        ) {
+            messages.push(f.clone().into_msg(&deps.querier, fee_address)?);
+            maker_fee_amount = f.amount;
+        }
+    }
+
+    // Accumulate prices for the assets in the pool
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
+        env.clone(),
+        &config,
+        pools[0].amount,
+        query_token_precision(&deps.querier, pools[0].info.clone())?,
+        pools[1].amount,
+        query_token_precision(&deps.querier, pools[1].info.clone())?,
+    )? {
+        store_observation(
+            deps.storage,
+            config.block_time_last,
+            block_time,
+            price0_cumulative_new.wrapping_sub(config.price0_cumulative_last),
+            price1_cumulative_new.wrapping_sub(config.price1_cumulative_last),
+        )?;
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    update_reserves_after_swap(
+        deps.storage,
+        &pools,
+        &offer_asset.info,
+        offer_amount,
+        ask_asset.amount + maker_fee_amount,
+    )?;
+    record_price_change(deps.storage, &deps.querier, &env, &config, &pools)?;
+
+    record_swap(
+        deps.storage,
+        env.block.time.seconds(),
+        &Asset {
+            info: offer_pool.info.clone(),
+            amount: offer_amount,
+        },
+        &ask_asset,
+        commission_amount,
+        maker_fee_amount,
+    )?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "swap_exact_out")
+        .add_attribute("sender", sender.as_str())
+        .add_attribute("receiver", receiver.as_str())
+        .add_attribute("offer_asset", offer_asset.info.to_string())
+        .add_attribute("ask_asset", ask_pool.info.to_string())
+        .add_attribute("offer_amount", offer_amount.to_string())
+        .add_attribute("refund_amount", refund_amount.to_string())
+        .add_attribute("return_amount", ask_asset.amount.to_string())
+        .add_attribute("tax_amount", tax_amount.to_string())
+        .add_attribute("spread_amount", spread_amount.to_string())
+        .add_attribute("commission_amount", commission_amount.to_string())
+        .add_attribute("maker_fee_amount", maker_fee_amount.to_string()))
+}
+
+/// ## Description
+/// Lends a pool asset to a callback contract and executes the callback contract with a [`FlashLoanCallbackMsg`].
+/// The loan is checked in [`repay_flash_loan`] once the callback contract returns.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified attributes
+/// if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **asset** is an object of type [`Asset`]. This is the asset and the amount to borrow.
+///
+/// * **callback_contract** is an object of type [`String`]. This is the contract that receives the borrowed assets.
+///
+/// * **msg** is an object of type [`Binary`]. This is the message forwarded to the callback contract.
+pub fn flash_loan(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    asset: Asset,
+    callback_contract: String,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    if is_paused(deps.storage)? {
+        return Err(ContractError::Paused {});
+    }
+
+    asset.info.check(deps.api)?;
+    if asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let callback_contract = addr_validate_to_lower(deps.api, &callback_contract)?;
+
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
+    let balances: [Asset; 2] = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address.clone())?;
+
+    if !pools.iter().any(|pool| pool.info.equal(&asset.info)) {
+        return Err(ContractError::AssetMismatch {});
+    }
+
+    // Get fee info from the factory
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
+
+    let fee_amount = compute_flash_loan_fee(asset.amount, fee_info.flash_loan_fee_rate);
+
+    // Accumulate prices for the assets in the pool
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
+        env,
+        &config,
+        pools[0].amount,
+        query_token_precision(&deps.querier, pools[0].info.clone())?,
+        pools[1].amount,
+        query_token_precision(&deps.querier, pools[1].info.clone())?,
+    )? {
+        store_observation(
+            deps.storage,
+            config.block_time_last,
+            block_time,
+            price0_cumulative_new.wrapping_sub(config.price0_cumulative_last),
+            price1_cumulative_new.wrapping_sub(config.price1_cumulative_last),
+        )?;
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    FLASH_LOAN.save(
+        deps.storage,
+        &FlashLoanState {
+            pools,
+            balances: [balances[0].amount, balances[1].amount],
+            asset: asset.clone(),
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_message(
+            asset
+                .clone()
+                .into_msg(&deps.querier, callback_contract.clone())?,
+        )
+        .add_submessage(SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: callback_contract.to_string(),
+                msg: to_binary(&FlashLoanCallbackMsg::FlashLoanCallback {
+                    sender: info.sender.to_string(),
+                    asset: asset.clone(),
+                    fee_amount,
+                    msg,
+                })?,
+                funds: vec![],
+            },
+            FLASH_LOAN_REPLY_ID,
+        ))
+        .add_attribute("action", "flash_loan")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("callback_contract", callback_contract.as_str())
+        .add_attribute("asset", asset.to_string())
+        .add_attribute("fee_amount", fee_amount.to_string()))
+}
+
+/// ## Description
+/// Sends the pair balances that exceed the pool reserves (e.g. tokens that were transferred to the pair directly)
+/// to the specified recipient. Returns a [`ContractError`] on failure, otherwise returns a [`Response`]
+/// with the specified attributes if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **to** is an object of type [`String`]. This is the recipient of the excess balances.
+pub fn skim(deps: DepsMut, env: Env, to: String) -> Result<Response, ContractError> {
+    let to = addr_validate_to_lower(deps.api, &to)?;
+
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
+    let balances: [Asset; 2] = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut skimmed: Vec<Asset> = vec![];
+    for (pool, balance) in pools.iter().zip(balances.iter()) {
+        let amount = balance.amount.checked_sub(pool.amount).unwrap_or_default();
+        if !amount.is_zero() {
+            let asset = Asset {
+                info: pool.info.clone(),
+                amount,
+            };
+            messages.push(asset.clone().into_msg(&deps.querier, to.clone())?);
+            skimmed.push(asset);
+        }
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "skim")
+        .add_attribute("to", to.as_str())
+        .add_attribute("assets", format_assets(&skimmed)))
+}
+
+/// ## Description
+/// Sets the pool reserves to the current pair balances. The cumulative prices are accumulated
+/// with the previous reserves first. Returns a [`ContractError`] on failure, otherwise returns a [`Response`]
+/// with the specified attributes if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+pub fn sync(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    if is_paused(deps.storage)? {
+        return Err(ContractError::Paused {});
+    }
+
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
+    let balances: [Asset; 2] = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address.clone())?;
+
+    // Accumulate prices for the assets in the pool
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
+        env.clone(),
+        &config,
+        pools[0].amount,
+        query_token_precision(&deps.querier, pools[0].info.clone())?,
+        pools[1].amount,
+        query_token_precision(&deps.querier, pools[1].info.clone())?,
+    )? {
+        store_observation(
+            deps.storage,
+            config.block_time_last,
+            block_time,
+            price0_cumulative_new.wrapping_sub(config.price0_cumulative_last),
+            price1_cumulative_new.wrapping_sub(config.price1_cumulative_last),
+        )?;
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    RESERVES.save(deps.storage, &[balances[0].amount, balances[1].amount])?;
+    record_price_change(deps.storage, &deps.querier, &env, &config, &pools)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "sync")
+        .add_attribute("reserves", format_assets(&balances)))
+}
+
+/// ## Description
+/// Checks that a flash loan was repaid after the callback contract was executed and sends the Maker fee.
+/// The fee is charged on every asset that came back to the pool, so the loan may also be repaid
+/// in the other pool asset (a flash swap) as long as the stableswap invariant without fees doesn't decrease.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified attributes
+/// if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+pub fn repay_flash_loan(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let flash_loan = FLASH_LOAN.load(deps.storage)?;
+    FLASH_LOAN.remove(deps.storage);
+
+    let pools: [Asset; 2] = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address.clone())?;
+
+    // Get fee info from the factory
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
+
+    let mut fees: Vec<Asset> = vec![];
+    let mut reserves = [Uint128::zero(); 2];
+    let mut balances = [Uint128::zero(); 2];
+    for (i, pool) in pools.iter().enumerate() {
+        let mut reserve = flash_loan.pools[i].amount;
+        let mut balance_after_loan = flash_loan.balances[i];
+        if pool.info.equal(&flash_loan.asset.info) {
+            reserve = reserve.checked_sub(flash_loan.asset.amount)?;
+            balance_after_loan = balance_after_loan.checked_sub(flash_loan.asset.amount)?;
+        }
+
+        // The flash loan fee is charged on everything that was sent back to the pool
+        let amount_in = pool
+            .amount
+            .checked_sub(balance_after_loan)
+            .unwrap_or_default();
+        let fee_amount = mul_ceil(amount_in, fee_info.flash_loan_fee_rate);
+
+        reserves[i] = reserve.checked_add(amount_in)?;
+        balances[i] = reserves[i].checked_sub(fee_amount)?;
+        if !fee_amount.is_zero() {
+            fees.push(Asset {
+                info: pool.info.clone(),
+                amount: fee_amount,
+            });
+        }
+    }
+
+    // If some pool balance is below its value before the loan, the pool invariant must not decrease
+    if balances
+        .iter()
+        .zip(flash_loan.pools.iter())
+        .any(|(balance, pool)| *balance < pool.amount)
+    {
+        let token_precision_0 = query_token_precision(&deps.querier, pools[0].info.clone())?;
+        let token_precision_1 = query_token_precision(&deps.querier, pools[1].info.clone())?;
+        let greater_precision = token_precision_0.max(token_precision_1);
+        let leverage = compute_current_amp(&config, &env)?
+            .checked_mul(u64::from(N_COINS))
+            .unwrap();
+
+        let d_before = compute_d(
+            leverage,
+            adjust_precision(
+                flash_loan.pools[0].amount,
+                token_precision_0,
+                greater_precision,
+            )?
+            .u128(),
+            adjust_precision(
+                flash_loan.pools[1].amount,
+                token_precision_1,
+                greater_precision,
+            )?
+            .u128(),
+        )
+        .unwrap();
+        let d_after = compute_d(
+            leverage,
+            adjust_precision(balances[0], token_precision_0, greater_precision)?.u128(),
+            adjust_precision(balances[1], token_precision_1, greater_precision)?.u128(),
+        )
+        .unwrap();
+
+        if d_after < d_before {
+            return Err(ContractError::FlashLoanNotRepaid {});
+        }
+    }
+
+    // Maker fee
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut maker_fees: Vec<Asset> = vec![];
+    if let Some(fee_address) = fee_info.fee_address {
+        for fee in fees.iter() {
+            if let Some(f) =
+                calculate_maker_fee(fee.info.clone(), fee.amount, fee_info.maker_fee_rate)
+            {
+                messages.push(f.clone().into_msg(&deps.querier, fee_address.clone())?);
+                maker_fees.push(f);
+            }
+        }
+    }
+
+    // The Maker fee leaves the pool while the rest of the flash loan fee stays with the LPs
+    for (reserve, pool) in reserves.iter_mut().zip(pools.iter()) {
+        for maker_fee in maker_fees.iter() {
+            if maker_fee.info.equal(&pool.info) {
+                *reserve = reserve.checked_sub(maker_fee.amount)?;
+            }
+        }
+    }
+    RESERVES.save(deps.storage, &reserves)?;
+    record_price_change(
+        deps.storage,
+        &deps.querier,
+        &env,
+        &config,
+        &flash_loan.pools,
+    )?;
+
+    for fee in fees.iter() {
+        let maker_fee_amount = maker_fees
+            .iter()
+            .find(|maker_fee| maker_fee.info.equal(&fee.info))
+            .map(|maker_fee| maker_fee.amount)
+            .unwrap_or_default();
+        record_fee(
+            deps.storage,
+            env.block.time.seconds(),
+            &fee.info,
+            fee.amount,
+            maker_fee_amount,
+        )?;
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "repay_flash_loan")
+        .add_attribute("asset", flash_loan.asset.to_string())
+        .add_attribute("fees", format_assets(&fees))
+        .add_attribute("maker_fees", format_assets(&maker_fees)))
+}
+
+/// ## Description
+/// Returns the fee that has to be paid on top of a flash loan of `amount` tokens if it is repaid in the borrowed asset.
+/// The fee is charged on the whole repaid amount, so the result is `amount / (1 - flash_loan_fee_rate) - amount` rounded up.
+/// ## Params
+/// * **amount** is an object of type [`Uint128`]. This is the amount of tokens to borrow.
+///
+/// * **flash_loan_fee_rate** is an object of type [`Decimal`]. This is the flash loan fee rate.
+fn compute_flash_loan_fee(amount: Uint128, flash_loan_fee_rate: Decimal) -> Uint128 {
+    let denominator = U256::from(flash_loan_fee_rate.denominator());
+    let remainder = denominator - U256::from(flash_loan_fee_rate.numerator());
+    let repay_amount =
+        (U256::from(amount.u128()) * denominator + remainder - U256::one()) / remainder;
+
+    Uint128::new(repay_amount.as_u128()) - amount
+}
+
+/// ## Description
+/// Returns `amount * rate` rounded up.
+/// ## Params
+/// * **amount** is an object of type [`Uint128`].
+///
+/// * **rate** is an object of type [`Decimal`].
+fn mul_ceil(amount: Uint128, rate: Decimal) -> Uint128 {
+    let denominator = U256::from(rate.denominator());
+    let result = (U256::from(amount.u128()) * U256::from(rate.numerator()) + denominator
+        - U256::one())
+        / denominator;
+
+    Uint128::new(result.as_u128())
+}
+
+/// ## Description
+/// Returns a comma separated list of assets to be used in attributes.
+/// ## Params
+/// * **assets** is a slice of [`Asset`] objects.
+fn format_assets(assets: &[Asset]) -> String {
+    assets
+        .iter()
+        .map(|asset| asset.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// ## Description
+/// Returns the information about the pool asset that has to be offered in order to receive `ask_asset_info`.
+/// Returns a [`ContractError`] if `ask_asset_info` does not belong to the pair.
+/// ## Params
+/// * **config** is an object of type [`Config`].
+///
+/// * **ask_asset_info** is an object of type [`AssetInfo`]. This is the asset to swap to.
+fn get_offer_asset_info(
+    config: &Config,
+    ask_asset_info: &AssetInfo,
+) -> Result<AssetInfo, ContractError> {
+    let asset_infos = &config.pair_info.asset_infos;
+    if ask_asset_info.equal(&asset_infos[0]) {
+        Ok(asset_infos[1].clone())
+    } else if ask_asset_info.equal(&asset_infos[1]) {
+        Ok(asset_infos[0].clone())
+    } else {
+        Err(ContractError::AssetMismatch {})
+    }
+}
+
+/// ## Description
+/// Accumulate token prices for the assets in the pool.
+/// Note that this function shifts **block_time** when any of the token prices is zero in order to not
+/// fill an accumulator with a null price for that period.
+/// ## Params
+/// * **env** is an object of type [`Env`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **x** is an object of type [`Uint128`]. This is the balance of asset\[\0] in the pool.
+///
+/// * **x_precision** is an object of type [`u8`]. This is the precision for the x token.
+///
+/// * **y** is an object of type [`Uint128`]. This is the balance of asset\[\1] in the pool.
+///
+/// * **y_precision** is an object of type [`u8`]. This is the precision for the y token.
+pub fn accumulate_prices(
+    env: Env,
+    config: &Config,
+    x: Uint128,
+    x_precision: u8,
+    y: Uint128,
+    y_precision: u8,
 ) -> StdResult<Option<(Uint128, Uint128, u64)>> {
     let block_time = env.block.time.seconds();
     if block_time <= config.block_time_last {
@@ -939,7 +2409,42 @@ pub fn calculate_maker_fee(
 /// * **QueryMsg::CumulativePrices {}** Returns information about cumulative prices for the assets in the
 /// pool using a [`CumulativePricesResponse`] object.
 ///
+/// * **QueryMsg::Observe { seconds_ago }** Returns the cumulative prices of the pool at the specified
+/// points in time using an [`ObserveResponse`] object.
+///
 /// * **QueryMsg::Config {}** Returns the configuration for the pair contract using a [`ConfigResponse`] object.
+///
+/// * **QueryMsg::Status {}** Returns the circuit breaker status of the pair using a [`StatusResponse`] object.
+///
+/// * **QueryMsg::SimulateWithdrawOneAsset { lp_amount, ask_asset_info }** Returns the result of a single asset
+/// withdrawal simulation using a [`WithdrawOneAssetSimulationResponse`] object.
+///
+/// * **QueryMsg::SimulateProvide { assets }** Returns the amount of LP tokens minted for a deposit and
+/// the imbalance fees charged on it using a [`SimulateProvideResponse`] object.
+///
+/// * **QueryMsg::SimulateWithdraw { lp_amount, ask_asset_info }** Returns the assets received for burning
+/// LP tokens using a [`SimulateWithdrawResponse`] object.
+///
+/// * **QueryMsg::SpotPrice { offer_asset_info }** Returns the marginal price of the offer asset using
+/// a [`SpotPriceResponse`] object.
+///
+/// * **QueryMsg::PriceImpact { offer_asset }** Returns the execution price, the spot price and the price
+/// impact of a swap using a [`PriceImpactResponse`] object.
+///
+/// * **QueryMsg::VirtualPrice {}** Returns the pool invariant divided by the LP token supply using a
+/// [`VirtualPriceResponse`] object.
+///
+/// * **QueryMsg::PoolInvariant {}** Returns the current pool invariant and amplification using a
+/// [`PoolInvariantResponse`] object.
+///
+/// * **QueryMsg::PendingReward { user }** Returns the rewards of the reward-bearing pool asset that
+/// the user can claim using an [`Asset`] object.
+///
+/// * **QueryMsg::Stats {}** Returns the cumulative swap volume and commissions of the pair using
+/// a [`astroport::stats::StatsResponse`] object.
+///
+/// * **QueryMsg::DailyStats { start_after, limit }** Returns the swap volume and commissions of the pair
+/// by day using a [`astroport::stats::DailyStatsResponse`] object.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -952,8 +2457,44 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::ReverseSimulation { ask_asset } => {
             to_binary(&query_reverse_simulation(deps, env, ask_asset)?)
         }
-        QueryMsg::CumulativePrices {} => to_binary(&query_cumulative_prices(deps, env)?),
+        QueryMsg::CumulativePrices {} => to_binary(&query_cumulative_prices(deps, env.clone())?),
+        QueryMsg::Observe { seconds_ago } => to_binary(&query_observe(deps, env, seconds_ago)?),
         QueryMsg::Config {} => to_binary(&query_config(deps, env)?),
+        QueryMsg::Status {} => to_binary(&query_status(deps.storage)?),
+        QueryMsg::SimulateWithdrawOneAsset {
+            lp_amount,
+            ask_asset_info,
+        } => to_binary(&query_simulate_withdraw_one_asset(
+            deps,
+            env,
+            lp_amount,
+            ask_asset_info,
+        )?),
+        QueryMsg::SimulateProvide { assets } => {
+            to_binary(&query_simulate_provide(deps, env, assets)?)
+        }
+        QueryMsg::SimulateWithdraw {
+            lp_amount,
+            ask_asset_info,
+        } => to_binary(&query_simulate_withdraw(
+            deps,
+            env,
+            lp_amount,
+            ask_asset_info,
+        )?),
+        QueryMsg::SpotPrice { offer_asset_info } => {
+            to_binary(&query_spot_price(deps, env, offer_asset_info)?)
+        }
+        QueryMsg::PriceImpact { offer_asset } => {
+            to_binary(&query_price_impact(deps, env, offer_asset)?)
+        }
+        QueryMsg::VirtualPrice {} => to_binary(&query_virtual_price(deps, env)?),
+        QueryMsg::PoolInvariant {} => to_binary(&query_pool_invariant(deps, env)?),
+        QueryMsg::PendingReward { user } => to_binary(&query_pending_reward(deps, user)?),
+        QueryMsg::Stats {} => to_binary(&query_stats(deps.storage)?),
+        QueryMsg::DailyStats { start_after, limit } => {
+            to_binary(&query_daily_stats(deps.storage, start_after, limit)?)
+        }
     }
 }
 
@@ -1008,9 +2549,7 @@ pub fn query_share(deps: Deps, amount: Uint128) -> StdResult<[Asset; 2]> {
 /// * **offer_asset** is an object of type [`Asset`]. This is the asset to swap as well as an amount of the said asset.
 pub fn query_simulation(deps: Deps, env: Env, offer_asset: Asset) -> StdResult<SimulationResponse> {
     let config: Config = CONFIG.load(deps.storage)?;
-    let contract_addr = config.pair_info.contract_addr.clone();
-
-    let pools: [Asset; 2] = config.pair_info.query_pools(&deps.querier, contract_addr)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
 
     let offer_pool: Asset;
     let ask_pool: Asset;
@@ -1027,10 +2566,10 @@ pub fn query_simulation(deps: Deps, env: Env, offer_asset: Asset) -> StdResult<S
     }
 
     // Get fee info from factory
-    let fee_info = query_fee_info(
+    let fee_info = query_pair_fee_info(
         &deps.querier,
         config.factory_addr.clone(),
-        config.pair_info.pair_type.clone(),
+        config.pair_info.contract_addr.clone(),
     )?;
 
     let (return_amount, spread_amount, commission_amount) = compute_swap(
@@ -1047,6 +2586,112 @@ pub fn query_simulation(deps: Deps, env: Env, offer_asset: Asset) -> StdResult<S
         return_amount,
         spread_amount,
         commission_amount,
+        total_fee_rate: fee_info.total_fee_rate,
+    })
+}
+
+/// ## Description
+/// Returns the marginal price of the offer asset in the other pool asset in a [`SpotPriceResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **offer_asset_info** is an object of type [`AssetInfo`]. This is the asset to price.
+pub fn query_spot_price(
+    deps: Deps,
+    env: Env,
+    offer_asset_info: AssetInfo,
+) -> StdResult<SpotPriceResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
+
+    let (offer_pool, ask_pool) = if offer_asset_info.equal(&pools[0].info) {
+        (&pools[0], &pools[1])
+    } else if offer_asset_info.equal(&pools[1].info) {
+        (&pools[1], &pools[0])
+    } else {
+        return Err(StdError::generic_err(
+            "Given offer asset doesn't belong to pairs",
+        ));
+    };
+
+    Ok(SpotPriceResponse {
+        price: compute_spot_price(
+            offer_pool.amount,
+            query_token_precision(&deps.querier, offer_pool.info.clone())?,
+            ask_pool.amount,
+            query_token_precision(&deps.querier, ask_pool.info.clone())?,
+            compute_current_amp(&config, &env)?,
+        )?,
+    })
+}
+
+/// ## Description
+/// Returns the execution price, the spot price and the price impact of a swap in a [`PriceImpactResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **offer_asset** is an object of type [`Asset`]. This is the asset to swap as well as an amount of the said asset.
+pub fn query_price_impact(
+    deps: Deps,
+    env: Env,
+    offer_asset: Asset,
+) -> StdResult<PriceImpactResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
+
+    let (offer_pool, ask_pool) = if offer_asset.info.equal(&pools[0].info) {
+        (&pools[0], &pools[1])
+    } else if offer_asset.info.equal(&pools[1].info) {
+        (&pools[1], &pools[0])
+    } else {
+        return Err(StdError::generic_err(
+            "Given offer asset doesn't belong to pairs",
+        ));
+    };
+    if offer_asset.amount.is_zero() {
+        return Err(StdError::generic_err(
+            ContractError::InvalidZeroAmount {}.to_string(),
+        ));
+    }
+
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
+    let offer_precision = query_token_precision(&deps.querier, offer_pool.info.clone())?;
+    let ask_precision = query_token_precision(&deps.querier, ask_pool.info.clone())?;
+    let amp = compute_current_amp(&config, &env)?;
+
+    let spot_price = compute_spot_price(
+        offer_pool.amount,
+        offer_precision,
+        ask_pool.amount,
+        ask_precision,
+        amp,
+    )?;
+    let (return_amount, _, commission_amount) = compute_swap(
+        offer_pool.amount,
+        offer_precision,
+        ask_pool.amount,
+        ask_precision,
+        offer_asset.amount,
+        fee_info.total_fee_rate,
+        amp,
+    )?;
+
+    Ok(PriceImpactResponse {
+        execution_price: Decimal::from_ratio(return_amount, offer_asset.amount),
+        spot_price,
+        price_impact: compute_price_impact(
+            offer_asset.amount,
+            return_amount + commission_amount,
+            spot_price,
+        ),
     })
 }
 
@@ -1065,9 +2710,7 @@ pub fn query_reverse_simulation(
     ask_asset: Asset,
 ) -> StdResult<ReverseSimulationResponse> {
     let config: Config = CONFIG.load(deps.storage)?;
-    let contract_addr = config.pair_info.contract_addr.clone();
-
-    let pools: [Asset; 2] = config.pair_info.query_pools(&deps.querier, contract_addr)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
 
     let offer_pool: Asset;
     let ask_pool: Asset;
@@ -1084,10 +2727,10 @@ pub fn query_reverse_simulation(
     }
 
     // Get fee info from the factory
-    let fee_info = query_fee_info(
+    let fee_info = query_pair_fee_info(
         &deps.querier,
         config.factory_addr.clone(),
-        config.pair_info.pair_type.clone(),
+        config.pair_info.contract_addr.clone(),
     )?;
 
     let (offer_amount, spread_amount, commission_amount) = compute_offer_amount(
@@ -1107,6 +2750,248 @@ pub fn query_reverse_simulation(
     })
 }
 
+/// ## Description
+/// Returns the result of a single asset withdrawal simulation in a [`WithdrawOneAssetSimulationResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **lp_amount** is an object of type [`Uint128`]. This is the amount of LP tokens to burn.
+///
+/// * **ask_asset_info** is an object of type [`AssetInfo`]. This is the asset to receive.
+pub fn query_simulate_withdraw_one_asset(
+    deps: Deps,
+    env: Env,
+    lp_amount: Uint128,
+    ask_asset_info: AssetInfo,
+) -> StdResult<WithdrawOneAssetSimulationResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps, config.clone())?;
+    let ask_index = pools
+        .iter()
+        .position(|pool| pool.info.equal(&ask_asset_info))
+        .ok_or_else(|| StdError::generic_err("Given ask asset doesn't belong to pairs"))?;
+
+    // Get fee info from the factory
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
+
+    let (return_amount, commission_amount) = compute_withdraw_one_asset(
+        &deps.querier,
+        &env,
+        &config,
+        &pools,
+        lp_amount,
+        total_share,
+        ask_index,
+        fee_info.total_fee_rate,
+    )?;
+
+    Ok(WithdrawOneAssetSimulationResponse {
+        return_amount,
+        commission_amount,
+    })
+}
+
+/// ## Description
+/// Returns the amount of LP tokens that [`provide_liquidity`] mints for a deposit together with the imbalance fees
+/// charged on it in a [`SimulateProvideResponse`] object. The slippage tolerance is not checked.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **assets** is an array with two objects of type [`Asset`]. These are the assets to deposit.
+pub fn query_simulate_provide(
+    deps: Deps,
+    env: Env,
+    assets: [Asset; 2],
+) -> StdResult<SimulateProvideResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps, config.clone())?;
+    let deposits =
+        get_deposits(&assets, &pools).map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    let token_precisions = [
+        query_token_precision(&deps.querier, pools[0].info.clone())?,
+        query_token_precision(&deps.querier, pools[1].info.clone())?,
+    ];
+
+    // Get fee info from the factory
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
+
+    let (share, imbalance_fees) = compute_share(
+        &deps.querier,
+        &env,
+        &config,
+        &pools,
+        &deposits,
+        token_precisions,
+        total_share,
+        fee_info.total_fee_rate,
+    )
+    .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    Ok(SimulateProvideResponse {
+        share,
+        imbalance_fees: pools
+            .iter()
+            .zip(imbalance_fees.iter())
+            .map(|(pool, fee)| Asset {
+                info: pool.info.clone(),
+                amount: *fee,
+            })
+            .collect(),
+    })
+}
+
+/// ## Description
+/// Returns the assets that [`withdraw_liquidity`] or [`withdraw_liquidity_one_asset`] return for burning
+/// LP tokens in a [`SimulateWithdrawResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **lp_amount** is an object of type [`Uint128`]. This is the amount of LP tokens to burn.
+///
+/// * **ask_asset_info** is an [`Option`] field of type [`AssetInfo`]. The withdrawal is simulated in this asset if it is set.
+pub fn query_simulate_withdraw(
+    deps: Deps,
+    env: Env,
+    lp_amount: Uint128,
+    ask_asset_info: Option<AssetInfo>,
+) -> StdResult<SimulateWithdrawResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps, config.clone())?;
+
+    match ask_asset_info {
+        None => Ok(SimulateWithdrawResponse {
+            assets: get_share_in_assets(&pools, lp_amount, total_share).to_vec(),
+            commission_amount: Uint128::zero(),
+        }),
+        Some(ask_asset_info) => {
+            let ask_index = pools
+                .iter()
+                .position(|pool| pool.info.equal(&ask_asset_info))
+                .ok_or_else(|| StdError::generic_err("Given ask asset doesn't belong to pairs"))?;
+
+            // Get fee info from the factory
+            let fee_info = query_pair_fee_info(
+                &deps.querier,
+                config.factory_addr.clone(),
+                config.pair_info.contract_addr.clone(),
+            )?;
+
+            let (return_amount, commission_amount) = compute_withdraw_one_asset(
+                &deps.querier,
+                &env,
+                &config,
+                &pools,
+                lp_amount,
+                total_share,
+                ask_index,
+                fee_info.total_fee_rate,
+            )?;
+
+            Ok(SimulateWithdrawResponse {
+                assets: vec![Asset {
+                    info: ask_asset_info,
+                    amount: return_amount,
+                }],
+                commission_amount,
+            })
+        }
+    }
+}
+
+/// ## Description
+/// Returns the pool invariant divided by the LP token supply in a [`VirtualPriceResponse`] object.
+/// The invariant only grows with fees, so unlike the pool balances it can't be moved by a large swap.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+pub fn query_virtual_price(deps: Deps, env: Env) -> StdResult<VirtualPriceResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps, config.clone())?;
+    if total_share.is_zero() {
+        return Err(StdError::generic_err("The pool has no liquidity"));
+    }
+
+    let (d, _, greater_precision) = compute_invariant(deps, &env, &config, &pools)?;
+    let liquidity_token_precision = query_token_precision(
+        &deps.querier,
+        AssetInfo::Token {
+            contract_addr: config.pair_info.liquidity_token,
+        },
+    )?;
+
+    Ok(VirtualPriceResponse {
+        virtual_price: Decimal::from_ratio(
+            adjust_precision(d, greater_precision, liquidity_token_precision)?,
+            total_share,
+        ),
+    })
+}
+
+/// ## Description
+/// Returns the current pool invariant and amplification in a [`PoolInvariantResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+pub fn query_pool_invariant(deps: Deps, env: Env) -> StdResult<PoolInvariantResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, _) = pool_info(deps, config.clone())?;
+    let (d, amp, _) = compute_invariant(deps, &env, &config, &pools)?;
+
+    Ok(PoolInvariantResponse {
+        d,
+        amp: Decimal::from_ratio(amp, AMP_PRECISION),
+    })
+}
+
+/// ## Description
+/// Returns the pool invariant D expressed with the greater precision of the two pool assets,
+/// the current amplification and that precision.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **pools** is an array with two objects of type [`Asset`]. These are the assets available in the pool.
+fn compute_invariant(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    pools: &[Asset; 2],
+) -> StdResult<(Uint128, u64, u8)> {
+    let token_precision_0 = query_token_precision(&deps.querier, pools[0].info.clone())?;
+    let token_precision_1 = query_token_precision(&deps.querier, pools[1].info.clone())?;
+    let greater_precision = token_precision_0.max(token_precision_1);
+
+    let amp = compute_current_amp(config, env)?;
+    let d = compute_d(
+        amp.checked_mul(u64::from(N_COINS)).unwrap(),
+        adjust_precision(pools[0].amount, token_precision_0, greater_precision)?.u128(),
+        adjust_precision(pools[1].amount, token_precision_1, greater_precision)?.u128(),
+    )
+    .ok_or_else(|| StdError::generic_err("Failed to compute the pool invariant"))?;
+
+    Ok((Uint128::new(d), amp, greater_precision))
+}
+
 /// ## Description
 /// Returns information about cumulative prices for the assets in the pool using a [`CumulativePricesResponse`] object.
 /// ## Params
@@ -1142,6 +3027,44 @@ pub fn query_cumulative_prices(deps: Deps, env: Env) -> StdResult<CumulativePric
     Ok(resp)
 }
 
+/// ## Description
+/// Returns the cumulative prices of the pair `seconds_ago` seconds before the current block time
+/// using an [`ObserveResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **seconds_ago** is a vector of [`u64`] time offsets from the current block time.
+pub fn query_observe(deps: Deps, env: Env, seconds_ago: Vec<u64>) -> StdResult<ObserveResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (assets, _) = pool_info(deps, config.clone())?;
+
+    let mut price0_increment = Uint128::zero();
+    let mut price1_increment = Uint128::zero();
+
+    if let Some((price0_cumulative_new, price1_cumulative_new, _)) = accumulate_prices(
+        env.clone(),
+        &config,
+        assets[0].amount,
+        query_token_precision(&deps.querier, assets[0].info.clone())?,
+        assets[1].amount,
+        query_token_precision(&deps.querier, assets[1].info.clone())?,
+    )? {
+        price0_increment = price0_cumulative_new.wrapping_sub(config.price0_cumulative_last);
+        price1_increment = price1_cumulative_new.wrapping_sub(config.price1_cumulative_last);
+    }
+
+    observe(
+        deps.storage,
+        config.block_time_last,
+        env.block.time.seconds(),
+        price0_increment,
+        price1_increment,
+        &seconds_ago,
+    )
+}
+
 /// ## Description
 /// Returns the pair contract configuration in a [`ConfigResponse`] object.
 /// ## Params
@@ -1152,10 +3075,30 @@ pub fn query_config(deps: Deps, env: Env) -> StdResult<ConfigResponse> {
         block_time_last: config.block_time_last,
         params: Some(to_binary(&StablePoolConfig {
             amp: Decimal::from_ratio(compute_current_amp(&config, &env)?, AMP_PRECISION),
+            fee_on_transfer: config.fee_on_transfer,
         })?),
     })
 }
 
+/// ## Description
+/// Returns the rewards of the reward-bearing pool asset that a user can claim in an [`Asset`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **user** is an object of type [`String`]. This is the address for which to return pending rewards.
+pub fn query_pending_reward(deps: Deps, user: String) -> StdResult<Asset> {
+    let config = CONFIG.load(deps.storage)?;
+    let reward_config = load_reward_bearing_config(deps.storage)?;
+
+    reward_bearing::query_pending_reward(
+        deps,
+        &REWARD_INDEXES,
+        &reward_config,
+        &config.pair_info.liquidity_token,
+        user,
+    )
+}
+
 /// ## Description
 /// Returns an amount of coins. For each coin in the specified vector, if the coin is null, we return `Uint128::zero()`,
 /// otherwise we return the specified coin amount.
@@ -1221,6 +3164,67 @@ fn compute_swap(
     Ok((return_amount, spread_amount, commission_amount))
 }
 
+/// ## Description
+/// Returns the marginal amount of ask assets that one offer asset buys (ignoring fees) using the
+/// derivative of the invariant. Returns an error if the pool is empty.
+/// ## Params
+/// * **offer_pool** is an object of type [`Uint128`]. This is the total amount of offer assets in the pool.
+///
+/// * **offer_precision** is an object of type [`u8`]. This is the token precision used for the offer amount.
+///
+/// * **ask_pool** is an object of type [`Uint128`]. This is the total amount of ask assets in the pool.
+///
+/// * **ask_precision** is an object of type [`u8`]. This is the token precision used for the ask amount.
+///
+/// * **amp** is an object of type [`u64`]. This is the pool's amplification parameter.
+fn compute_spot_price(
+    offer_pool: Uint128,
+    offer_precision: u8,
+    ask_pool: Uint128,
+    ask_precision: u8,
+    amp: u64,
+) -> StdResult<Decimal> {
+    if offer_pool.is_zero() || ask_pool.is_zero() {
+        return Err(StdError::generic_err("The pool is empty"));
+    }
+
+    let greater_precision = offer_precision.max(ask_precision);
+    let offer_pool = adjust_precision(offer_pool, offer_precision, greater_precision)?;
+    let ask_pool = adjust_precision(ask_pool, ask_precision, greater_precision)?;
+
+    let price = calc_spot_price(offer_pool.u128(), ask_pool.u128(), amp)
+        .ok_or_else(|| StdError::generic_err("Failed to calculate the spot price"))?;
+
+    // The price of the amounts with the greater precision is converted back to the token precisions
+    Ok(Decimal::from_ratio(
+        adjust_precision(Uint128::new(price), offer_precision, ask_precision)?,
+        PRICE_PRECISION,
+    ))
+}
+
+/// ## Description
+/// Returns the relative difference between the amount of ask assets that the offer amount buys at the
+/// spot price and the amount that the swap returns before the commission is deducted.
+/// ## Params
+/// * **offer_amount** is an object of type [`Uint128`]. This is the amount of offer assets to swap.
+///
+/// * **ask_amount** is an object of type [`Uint128`]. This is the amount of ask assets returned by the
+/// swap before the commission is deducted.
+///
+/// * **spot_price** is an object of type [`Decimal`]. This is the spot price of the offer asset.
+fn compute_price_impact(
+    offer_amount: Uint128,
+    ask_amount: Uint128,
+    spot_price: Decimal,
+) -> Decimal {
+    let spot_amount = offer_amount * spot_price;
+    if spot_amount.is_zero() {
+        return Decimal::zero();
+    }
+
+    Decimal::from_ratio(spot_amount.saturating_sub(ask_amount), spot_amount)
+}
+
 /// ## Description
 /// Returns an amount of offer assets for a specified amount of ask assets.
 /// ## Params
@@ -1369,16 +3373,165 @@ fn assert_slippage_tolerance(
 }
 
 /// ## Description
-/// Used for contract migration. Returns a default object of type [`Response`].
+/// This is an internal function that checks the withdrawn assets against the minimum amounts
+/// that the LP token holder wants to receive. Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
 /// ## Params
-/// * **_deps** is an object of type [`DepsMut`].
+/// * **refund_assets** are an array of [`Asset`] type items. These are the assets that will be withdrawn.
 ///
-/// * **_env** is an object of type [`Env`].
+/// * **min_assets_to_receive** is an [`Option`] field of type [`Vec<Asset>`]. These are the minimum
+/// amounts of assets to receive.
+fn assert_min_assets_to_receive(
+    refund_assets: &[Asset],
+    min_assets_to_receive: Option<Vec<Asset>>,
+) -> Result<(), ContractError> {
+    for min_asset in min_assets_to_receive.unwrap_or_default() {
+        let refund_asset = refund_assets
+            .iter()
+            .find(|asset| asset.info.equal(&min_asset.info))
+            .ok_or(ContractError::AssetMismatch {})?;
+        if refund_asset.amount < min_asset.amount {
+            return Err(ContractError::MinAssetsToReceiveAssertion {});
+        }
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Checks that the deadline of a message has not passed yet.
+/// Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+/// ## Params
+/// * **env** is an object of type [`Env`].
+///
+/// * **deadline** is an [`Option`] field of type [`u64`]. This is the timestamp (in seconds) after which the message fails.
+fn assert_deadline(env: &Env, deadline: Option<u64>) -> Result<(), ContractError> {
+    if let Some(deadline) = deadline {
+        if env.block.time.seconds() > deadline {
+            return Err(ContractError::DeadlineExpired {});
+        }
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Checks that the callback contract address is valid and that the output of an action is not
+/// also sent to a receiver. Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+/// ## Params
+/// * **api** is an object of type [`Api`].
+///
+/// * **callback** is an [`Option`] field of type [`Callback`]. This is the contract that receives the output.
+///
+/// * **has_receiver** is the field of type [`bool`]. Determines whether a receiver was specified as well.
+fn assert_callback(
+    api: &dyn Api,
+    callback: &Option<Callback>,
+    has_receiver: bool,
+) -> Result<(), ContractError> {
+    if let Some(callback) = callback {
+        if has_receiver {
+            return Err(ContractError::CallbackWithReceiver {});
+        }
+        addr_validate_to_lower(api, &callback.contract)?;
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Used for contract migration. Pairs migrating from v1.0.0 start tracking their reserves
+/// from their current asset balances. The other state that was added since then is created
+/// with its defaults when it is first used.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
 ///
 /// * **_msg** is an object of type [`MigrateMsg`].
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
-    Ok(Response::default())
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let contract_version = get_contract_version(deps.storage)?;
+
+    match contract_version.contract.as_ref() {
+        "astroport-pair-stable" => match contract_version.version.as_ref() {
+            "1.0.0" | "1.1.0" => {
+                // Pairs deployed before the reserves were tracked start from their current balances
+                if RESERVES.may_load(deps.storage)?.is_none() {
+                    let config: Config = CONFIG.load(deps.storage)?;
+                    let pools: [Asset; 2] = config
+                        .pair_info
+                        .query_pools(&deps.querier, env.contract.address)?;
+                    RESERVES.save(deps.storage, &[pools[0].amount, pools[1].amount])?;
+                }
+            }
+            _ => return Err(ContractError::MigrationError {}),
+        },
+        _ => return Err(ContractError::MigrationError {}),
+    };
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("previous_contract_name", &contract_version.contract)
+        .add_attribute("previous_contract_version", &contract_version.version)
+        .add_attribute("new_contract_name", CONTRACT_NAME)
+        .add_attribute("new_contract_version", CONTRACT_VERSION))
+}
+
+/// ## Description
+/// Returns the pool assets with the amounts that are tracked as the pool reserves.
+/// Tokens that were sent to the pair directly are not part of the reserves until [`sync`] is called.
+/// ## Params
+/// * **storage** is an object of type [`Storage`].
+///
+/// * **config** is an object of type [`Config`].
+pub fn load_pools(storage: &dyn Storage, config: &Config) -> StdResult<[Asset; 2]> {
+    let reserves = RESERVES.load(storage)?;
+
+    Ok([
+        Asset {
+            info: config.pair_info.asset_infos[0].clone(),
+            amount: reserves[0],
+        },
+        Asset {
+            info: config.pair_info.asset_infos[1].clone(),
+            amount: reserves[1],
+        },
+    ])
+}
+
+/// ## Description
+/// Stores the pool reserves after a swap.
+/// ## Params
+/// * **storage** is an object of type [`Storage`].
+///
+/// * **pools** is an array with two objects of type [`Asset`]. These are the pool reserves before the swap.
+///
+/// * **offer_asset_info** is an object of type [`AssetInfo`]. This is the asset that was swapped.
+///
+/// * **offer_amount** is an object of type [`Uint128`]. This is the amount of offer assets that were added to the pool.
+///
+/// * **ask_amount** is an object of type [`Uint128`]. This is the amount of ask assets that left the pool.
+fn update_reserves_after_swap(
+    storage: &mut dyn Storage,
+    pools: &[Asset; 2],
+    offer_asset_info: &AssetInfo,
+    offer_amount: Uint128,
+    ask_amount: Uint128,
+) -> StdResult<()> {
+    let reserves = if offer_asset_info.equal(&pools[0].info) {
+        [
+            pools[0].amount.checked_add(offer_amount)?,
+            pools[1].amount.checked_sub(ask_amount)?,
+        ]
+    } else {
+        [
+            pools[0].amount.checked_sub(ask_amount)?,
+            pools[1].amount.checked_add(offer_amount)?,
+        ]
+    };
+
+    RESERVES.save(storage, &reserves)
 }
 
 /// ## Description
@@ -1388,8 +3541,7 @@ pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Respons
 ///
 /// * **config** is an object of type [`Config`].
 pub fn pool_info(deps: Deps, config: Config) -> StdResult<([Asset; 2], Uint128)> {
-    let contract_addr = config.pair_info.contract_addr.clone();
-    let pools: [Asset; 2] = config.pair_info.query_pools(&deps.querier, contract_addr)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
     let total_share: Uint128 = query_supply(&deps.querier, config.pair_info.liquidity_token)?;
 
     Ok((pools, total_share))
@@ -1431,6 +3583,286 @@ pub fn update_config(
     Ok(Response::default())
 }
 
+/// ## Description
+/// Stops swaps and liquidity provision in the pair. Withdrawals stay open.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified attributes.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// ## Executor
+/// Only the factory owner or the guardian can execute this.
+pub fn pause(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, config.factory_addr)?;
+
+    if info.sender != factory_config.owner && Some(info.sender) != guardian(deps.storage)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    set_paused(deps.storage, true)?;
+
+    Ok(Response::new().add_attribute("action", "pause"))
+}
+
+/// ## Description
+/// Resumes swaps and liquidity provision in the pair after a pause.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified attributes.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// ## Executor
+/// Only the factory owner can execute this.
+pub fn unpause(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, config.factory_addr)?;
+
+    if info.sender != factory_config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    set_paused(deps.storage, false)?;
+
+    Ok(Response::new().add_attribute("action", "unpause"))
+}
+
+/// ## Description
+/// Updates the guardian and the maximum price change of the pair.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified attributes.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **guardian** is an [`Option`] field of type [`String`]. This is the address that can pause the pair.
+///
+/// * **max_price_change_bps** is an [`Option`] field of type [`u16`]. This is the maximum spot price change
+/// within a single block. Operations that exceed it fail.
+///
+/// ## Executor
+/// Only the factory owner can execute this.
+pub fn update_circuit_breaker(
+    deps: DepsMut,
+    info: MessageInfo,
+    guardian: Option<String>,
+    max_price_change_bps: Option<u16>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, config.factory_addr)?;
+
+    if info.sender != factory_config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let guardian = guardian
+        .map(|guardian| addr_validate_to_lower(deps.api, &guardian))
+        .transpose()?;
+    circuit_breaker::update_circuit_breaker(deps.storage, guardian, max_price_change_bps)?;
+
+    Ok(Response::new().add_attribute("action", "update_circuit_breaker"))
+}
+
+/// ## Description
+/// Claims the rewards of the reward-bearing pool asset on behalf of the caller and sends them to the receiver.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the claim messages.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **receiver** is an [`Option`] field of type [`String`]. This is the address that receives the rewards.
+pub fn claim_reward(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    receiver: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let reward_config = load_reward_bearing_config(deps.storage)?;
+
+    Ok(reward_bearing::claim_reward(
+        deps.as_ref(),
+        &env,
+        info,
+        &REWARD_INDEXES,
+        &reward_config,
+        &config.pair_info.liquidity_token,
+        receiver,
+    )?)
+}
+
+/// ## Description
+/// Claims the rewards of the reward-bearing pool asset on behalf of a user that staked LP tokens in the Generator.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the claim messages.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **user** is an object of type [`String`]. This is the user for which to claim rewards.
+///
+/// * **user_share** is an object of type [`Uint128`]. This is the amount of LP tokens the user has staked.
+///
+/// * **total_share** is an object of type [`Uint128`]. This is the total amount of staked LP tokens.
+///
+/// ## Executor
+/// Only the Generator can execute this.
+pub fn claim_reward_by_generator(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    user: String,
+    user_share: Uint128,
+    total_share: Uint128,
+) -> Result<Response, ContractError> {
+    let reward_config = load_reward_bearing_config(deps.storage)?;
+
+    Ok(reward_bearing::claim_reward_by_generator(
+        deps.as_ref(),
+        &env,
+        info,
+        &REWARD_INDEXES,
+        &reward_config,
+        user,
+        user_share,
+        total_share,
+    )?)
+}
+
+/// ## Description
+/// Distributes the rewards claimed for a user. Returns a [`ContractError`] on failure,
+/// otherwise returns a [`Response`] with the specified attributes.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **previous_reward_balance** is an object of type [`Uint128`]. This is the reward holder balance
+/// before the rewards were claimed.
+///
+/// * **user** is an object of type [`Addr`]. This is the address for which rewards are distributed.
+///
+/// * **user_share** is an object of type [`Uint128`]. This is the amount of LP tokens the user has staked.
+///
+/// * **total_share** is an object of type [`Uint128`]. This is the total amount of staked LP tokens.
+///
+/// * **receiver** is an [`Option`] field of type [`Addr`]. This is the address that receives the rewards.
+///
+/// ## Executor
+/// Only the pair itself can execute this.
+#[allow(clippy::too_many_arguments)]
+pub fn handle_reward(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    previous_reward_balance: Uint128,
+    user: Addr,
+    user_share: Uint128,
+    total_share: Uint128,
+    receiver: Option<Addr>,
+) -> Result<Response, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let reward_config = load_reward_bearing_config(deps.storage)?;
+
+    Ok(reward_bearing::handle_reward(
+        deps,
+        &REWARD_INDEXES,
+        &reward_config,
+        previous_reward_balance,
+        user,
+        user_share,
+        total_share,
+        receiver,
+    )?)
+}
+
+/// ## Description
+/// Passes the spot price change caused by an operation to the circuit breaker of the pair.
+/// Returns a [`ContractError`] if the price moved by more than the pair allows within a block.
+/// The pool reserves must already be updated.
+/// ## Params
+/// * **storage** is an object of type [`Storage`].
+///
+/// * **querier** is an object of type [`QuerierWrapper`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **pools** is an array with two objects of type [`Asset`]. These are the pool reserves before the operation.
+fn record_price_change(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    env: &Env,
+    config: &Config,
+    pools: &[Asset; 2],
+) -> Result<(), ContractError> {
+    let precisions = [
+        query_token_precision(querier, pools[0].info.clone())?,
+        query_token_precision(querier, pools[1].info.clone())?,
+    ];
+    let reserves_before = [pools[0].amount, pools[1].amount];
+    let reserves_after = RESERVES.load(storage)?;
+
+    if !track_price_change(
+        storage,
+        env.block.height,
+        spot_price(config, env, reserves_before, precisions)?,
+        spot_price(config, env, reserves_after, precisions)?,
+    )? {
+        return Err(ContractError::MaxPriceChangeAssertion {});
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Returns the amount of asset 1 received for one unit of asset 0 (ignoring fees) with
+/// [`TWAP_PRECISION`] decimals or zero for an empty pool.
+/// ## Params
+/// * **config** is an object of type [`Config`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **reserves** is an array with two objects of type [`Uint128`]. These are the pool reserves.
+///
+/// * **precisions** is an array with two objects of type [`u8`]. These are the precisions of the pool assets.
+fn spot_price(
+    config: &Config,
+    env: &Env,
+    reserves: [Uint128; 2],
+    precisions: [u8; 2],
+) -> StdResult<Uint128> {
+    let greater_precision = precisions[0].max(precisions[1]).max(TWAP_PRECISION);
+    let x = adjust_precision(reserves[0], precisions[0], greater_precision)?;
+    let y = adjust_precision(reserves[1], precisions[1], greater_precision)?;
+    if x.is_zero() || y.is_zero() {
+        return Ok(Uint128::zero());
+    }
+
+    let price = calc_ask_amount(
+        x.u128(),
+        y.u128(),
+        adjust_precision(Uint128::new(1), 0, greater_precision)?.u128(),
+        compute_current_amp(config, env)?,
+    )
+    .ok_or_else(|| StdError::generic_err("Failed to calculate the spot price"))?;
+
+    adjust_precision(Uint128::new(price), greater_precision, TWAP_PRECISION)
+}
+
 /// ## Description
 /// Start changing the AMP value. Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
 /// ## Params
@@ -1,25 +1,32 @@
 use crate::contract::{
-    accumulate_prices, assert_max_spread, execute, instantiate, query_pair_info, query_pool,
-    query_share, query_simulation, reply,
+    accumulate_prices, assert_max_spread, execute, instantiate, migrate, query, query_pair_info,
+    query_pool, query_reverse_simulation, query_share, query_simulation, reply,
 };
 use crate::error::ContractError;
-use crate::math::{calc_ask_amount, calc_offer_amount, AMP_PRECISION};
 use crate::mock_querier::mock_dependencies;
+use astroport::stable_math::{
+    calc_ask_amount, calc_offer_amount, calc_withdraw_one_amount, AMP_PRECISION,
+};
 
 use crate::response::MsgInstantiateContractResponse;
-use crate::state::Config;
+use crate::state::{Config, RESERVES};
 use astroport::asset::{Asset, AssetInfo, PairInfo};
+use astroport::circuit_breaker::StatusResponse;
 
 use astroport::pair::{
-    Cw20HookMsg, ExecuteMsg, InstantiateMsg, PoolResponse, SimulationResponse, StablePoolParams,
-    TWAP_PRECISION,
+    ConfigResponse, Cw20HookMsg, ExecuteMsg, FlashLoanCallbackMsg, InstantiateMsg, MigrateMsg,
+    PoolInvariantResponse, PoolResponse, PriceImpactResponse, QueryMsg, ReverseSimulationResponse,
+    SimulateProvideResponse, SimulateWithdrawResponse, SimulationResponse, SpotPriceResponse,
+    StablePoolConfig, StablePoolParams, VirtualPriceResponse, WithdrawOneAssetSimulationResponse,
+    MINIMUM_LIQUIDITY_AMOUNT, TWAP_PRECISION,
 };
+use astroport::stats::StatsResponse;
 use astroport::token::InstantiateMsg as TokenInstantiateMsg;
 use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    attr, to_binary, Addr, BankMsg, BlockInfo, Coin, ContractResult, CosmosMsg, Decimal, DepsMut,
-    Env, Reply, ReplyOn, Response, StdError, SubMsg, SubMsgExecutionResponse, Timestamp, Uint128,
-    WasmMsg,
+    attr, from_binary, to_binary, Addr, BankMsg, Binary, BlockInfo, Coin, ContractResult,
+    CosmosMsg, Decimal, Deps, DepsMut, Env, Reply, ReplyOn, Response, StdError, SubMsg,
+    SubMsgExecutionResponse, Timestamp, Uint128, WasmMsg,
 };
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
 use protobuf::Message;
@@ -45,6 +52,29 @@ fn store_liquidity_token(deps: DepsMut, msg_id: u64, contract_addr: String) {
     let _res = reply(deps, mock_env(), reply_msg.clone()).unwrap();
 }
 
+fn store_reserves(deps: DepsMut, reserves: [Uint128; 2]) {
+    RESERVES.save(deps.storage, &reserves).unwrap();
+}
+
+/// Returns the swap count and the LP and Maker commissions of every asset in the pair statistics
+fn query_commissions(deps: Deps) -> (u64, Vec<(String, u128, u128)>) {
+    let stats: StatsResponse =
+        from_binary(&query(deps, mock_env(), QueryMsg::Stats {}).unwrap()).unwrap();
+    let commissions = stats
+        .assets
+        .iter()
+        .map(|asset| {
+            (
+                asset.info.to_string(),
+                asset.lp_commission.u128(),
+                asset.maker_commission.u128(),
+            )
+        })
+        .collect();
+
+    (stats.swap_count, commissions)
+}
+
 #[test]
 fn proper_initialization() {
     let mut deps = mock_dependencies(&[]);
@@ -65,7 +95,13 @@ fn proper_initialization() {
             },
         ],
         token_code_id: 10u64,
-        init_params: Some(to_binary(&StablePoolParams { amp: 100 }).unwrap()),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_on_transfer: false,
+            })
+            .unwrap(),
+        ),
     };
 
     let sender = "addr0000";
@@ -148,7 +184,13 @@ fn provide_liquidity() {
         ],
         token_code_id: 10u64,
         factory_addr: String::from("factory"),
-        init_params: Some(to_binary(&StablePoolParams { amp: 100 }).unwrap()),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_on_transfer: false,
+            })
+            .unwrap(),
+        ),
     };
 
     let env = mock_env();
@@ -178,6 +220,9 @@ fn provide_liquidity() {
         slippage_tolerance: None,
         auto_stake: None,
         receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
     };
 
     let env = mock_env();
@@ -190,7 +235,8 @@ fn provide_liquidity() {
     );
     let res = execute(deps.as_mut(), env.clone().clone(), info, msg).unwrap();
     let transfer_from_msg = res.messages.get(0).expect("no message");
-    let mint_msg = res.messages.get(1).expect("no message");
+    let mint_min_liquidity_msg = res.messages.get(1).expect("no message");
+    let mint_msg = res.messages.get(2).expect("no message");
     assert_eq!(
         transfer_from_msg,
         &SubMsg {
@@ -210,6 +256,24 @@ fn provide_liquidity() {
             reply_on: ReplyOn::Never
         }
     );
+    assert_eq!(
+        mint_min_liquidity_msg,
+        &SubMsg {
+            msg: WasmMsg::Execute {
+                contract_addr: String::from("liquidity0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: String::from(MOCK_CONTRACT_ADDR),
+                    amount: MINIMUM_LIQUIDITY_AMOUNT,
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+            .into(),
+            id: 0,
+            gas_limit: None,
+            reply_on: ReplyOn::Never,
+        }
+    );
     assert_eq!(
         mint_msg,
         &SubMsg {
@@ -217,7 +281,7 @@ fn provide_liquidity() {
                 contract_addr: String::from("liquidity0000"),
                 msg: to_binary(&Cw20ExecuteMsg::Mint {
                     recipient: String::from("addr0000"),
-                    amount: Uint128::from(100_000000000000000000u128),
+                    amount: Uint128::from(100_000000000000000000u128) - MINIMUM_LIQUIDITY_AMOUNT,
                 })
                 .unwrap(),
                 funds: vec![],
@@ -237,6 +301,13 @@ fn provide_liquidity() {
             amount: Uint128::new(200_000000000000000000 + 200_000000000000000000 /* user deposit must be pre-applied */),
         }],
     )]);
+    store_reserves(
+        deps.as_mut(),
+        [
+            Uint128::new(200_000000000000000000),
+            Uint128::new(200_000000000000000000),
+        ],
+    );
 
     deps.querier.with_token_balances(&[
         (
@@ -273,6 +344,9 @@ fn provide_liquidity() {
         slippage_tolerance: None,
         auto_stake: None,
         receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
     };
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
@@ -313,7 +387,7 @@ fn provide_liquidity() {
                 contract_addr: String::from("liquidity0000"),
                 msg: to_binary(&Cw20ExecuteMsg::Mint {
                     recipient: String::from("addr0000"),
-                    amount: Uint128::from(74_981956874579206461u128),
+                    amount: Uint128::from(74_944452888487171363u128),
                 })
                 .unwrap(),
                 funds: vec![],
@@ -344,6 +418,9 @@ fn provide_liquidity() {
         slippage_tolerance: None,
         auto_stake: None,
         receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
     };
 
     let env = mock_env();
@@ -371,6 +448,13 @@ fn provide_liquidity() {
             amount: Uint128::new(100_000000000000000000 + 100_000000000000000000 /* user deposit must be pre-applied */),
         }],
     )]);
+    store_reserves(
+        deps.as_mut(),
+        [
+            Uint128::new(100_000000000000000000),
+            Uint128::new(100_000000000000000000),
+        ],
+    );
 
     deps.querier.with_token_balances(&[
         (
@@ -397,6 +481,13 @@ fn provide_liquidity() {
             amount: Uint128::new(100_000000000000000000 + 98_000000000000000000 /* user deposit must be pre-applied */),
         }],
     )]);
+    store_reserves(
+        deps.as_mut(),
+        [
+            Uint128::new(100_000000000000000000),
+            Uint128::new(100_000000000000000000),
+        ],
+    );
 
     // Initialize token balances with a ratio of 1:1
     deps.querier.with_balance(&[(
@@ -406,6 +497,13 @@ fn provide_liquidity() {
             amount: Uint128::new(100_000000000000000000 + 100_000000000000000000 /* user deposit must be pre-applied */),
         }],
     )]);
+    store_reserves(
+        deps.as_mut(),
+        [
+            Uint128::new(100_000000000000000000),
+            Uint128::new(100_000000000000000000),
+        ],
+    );
 
     // Successfully provide liquidity
     let msg = ExecuteMsg::ProvideLiquidity {
@@ -426,6 +524,9 @@ fn provide_liquidity() {
         slippage_tolerance: Some(Decimal::percent(1)),
         auto_stake: None,
         receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
     };
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
@@ -446,6 +547,13 @@ fn provide_liquidity() {
             amount: Uint128::new(100_000000000000000000 + 99_000000000000000000 /* user deposit must be pre-applied */),
         }],
     )]);
+    store_reserves(
+        deps.as_mut(),
+        [
+            Uint128::new(100_000000000000000000),
+            Uint128::new(100_000000000000000000),
+        ],
+    );
 
     // Successfully provide liquidity
     let msg = ExecuteMsg::ProvideLiquidity {
@@ -466,6 +574,9 @@ fn provide_liquidity() {
         slippage_tolerance: Some(Decimal::percent(1)),
         auto_stake: None,
         receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
     };
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
@@ -479,6 +590,327 @@ fn provide_liquidity() {
     let _res = execute(deps.as_mut(), env, info, msg).unwrap();
 }
 
+#[test]
+fn provide_liquidity_below_minimum() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(0))],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(0))],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_on_transfer: false,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    let provide_msg = |amount: u128| ExecuteMsg::ProvideLiquidity {
+        assets: [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::new(amount),
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                amount: Uint128::new(amount),
+            },
+        ],
+        slippage_tolerance: None,
+        auto_stake: None,
+        receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
+    };
+    let provide_info = |amount: u128| {
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(amount),
+            }],
+        )
+    };
+
+    // The initial deposit must be larger than the locked amount
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        provide_info(1000),
+        provide_msg(1000),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::MinimumLiquidityAmountError {});
+
+    // The smallest possible initial deposit mints a single LP token for the provider
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        provide_info(1001),
+        provide_msg(1001),
+    )
+    .unwrap();
+    assert_eq!(res.attributes[4], attr("share", "1"));
+}
+
+#[test]
+fn provide_liquidity_imbalance_fee() {
+    let pool_amount = Uint128::new(1000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: pool_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &pool_amount)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_on_transfer: false,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env_with_block_time(100);
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    let provide = |deps: DepsMut, uusd_amount: u128, token_amount: u128| {
+        let msg = ExecuteMsg::ProvideLiquidity {
+            assets: [
+                Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    amount: Uint128::new(uusd_amount),
+                },
+                Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: Addr::unchecked("asset0000"),
+                    },
+                    amount: Uint128::new(token_amount),
+                },
+            ],
+            slippage_tolerance: None,
+            auto_stake: None,
+            receiver: None,
+            min_lp_to_receive: None,
+            deadline: None,
+            callback: None,
+        };
+        let info = mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(uusd_amount),
+            }],
+        );
+        execute(deps, mock_env_with_block_time(1000), info, msg).unwrap()
+    };
+
+    // A balanced deposit pays no fee
+    store_reserves(deps.as_mut(), [pool_amount, pool_amount]);
+    let res = provide(deps.as_mut(), 100000, 100000);
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(res.attributes[4], attr("share", "100000"));
+    assert_eq!(
+        res.attributes[5],
+        attr("imbalance_fees", "0uusd, 0asset0000")
+    );
+
+    // An imbalanced deposit of the same value pays the fee on its deviation from a balanced deposit
+    // and sends the maker share to the fee address
+    store_reserves(deps.as_mut(), [pool_amount, pool_amount]);
+    let simulation: SimulateProvideResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env_with_block_time(1000),
+            QueryMsg::SimulateProvide {
+                assets: [
+                    Asset {
+                        info: AssetInfo::NativeToken {
+                            denom: "uusd".to_string(),
+                        },
+                        amount: Uint128::new(190000u128),
+                    },
+                    Asset {
+                        info: AssetInfo::Token {
+                            contract_addr: Addr::unchecked("asset0000"),
+                        },
+                        amount: Uint128::new(10000u128),
+                    },
+                ],
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(simulation.share, Uint128::new(99828u128));
+    assert_eq!(
+        format!(
+            "{}, {}",
+            simulation.imbalance_fees[0], simulation.imbalance_fees[1]
+        ),
+        "135uusd, 134asset0000"
+    );
+
+    let res = provide(deps.as_mut(), 190000, 10000);
+    assert_eq!(res.messages.len(), 4);
+    assert_eq!(res.attributes[4], attr("share", "99828"));
+    assert_eq!(
+        res.attributes[5],
+        attr("imbalance_fees", "135uusd, 134asset0000")
+    );
+    assert_eq!(res.attributes[6], attr("maker_fees", "22uusd, 22asset0000"));
+
+    // The imbalance fees are added to the statistics without counting as a swap
+    assert_eq!(
+        query_commissions(deps.as_ref()),
+        (
+            0,
+            vec![
+                ("uusd".to_string(), 113, 22),
+                ("asset0000".to_string(), 112, 22)
+            ]
+        )
+    );
+    assert_eq!(
+        res.messages[2].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("fee_address"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(22u128),
+            }],
+        })
+    );
+    assert_eq!(
+        res.messages[3].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("asset0000"),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("fee_address"),
+                amount: Uint128::new(22u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+
+    // The maker fees leave the pool
+    let reserves = RESERVES.load(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        reserves,
+        [Uint128::new(1189978u128), Uint128::new(1009978u128)]
+    );
+
+    // The LP supply of the mock stays at 1000000, so 10% of it returns 10% of the reserves
+    let simulation: SimulateWithdrawResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env_with_block_time(1000),
+            QueryMsg::SimulateWithdraw {
+                lp_amount: Uint128::new(100000u128),
+                ask_asset_info: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        format!("{}, {}", simulation.assets[0], simulation.assets[1]),
+        "118997uusd, 100997asset0000"
+    );
+    assert_eq!(simulation.commission_amount, Uint128::zero());
+    // An invariant that overflows returns an error instead of aborting the contract
+    store_reserves(deps.as_mut(), [Uint128::new(u128::MAX / 4); 2]);
+    let err = query(
+        deps.as_ref(),
+        mock_env_with_block_time(1000),
+        QueryMsg::SimulateProvide {
+            assets: [
+                Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    amount: Uint128::new(190000u128),
+                },
+                Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: Addr::unchecked("asset0000"),
+                    },
+                    amount: Uint128::new(10000u128),
+                },
+            ],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(ContractError::InvariantComputationError {}.to_string())
+    );
+}
+
 #[test]
 fn withdraw_liquidity() {
     let mut deps = mock_dependencies(&[Coin {
@@ -512,7 +944,13 @@ fn withdraw_liquidity() {
         ],
         token_code_id: 10u64,
         factory_addr: String::from("factory"),
-        init_params: Some(to_binary(&StablePoolParams { amp: 100 }).unwrap()),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_on_transfer: false,
+            })
+            .unwrap(),
+        ),
     };
 
     let env = mock_env();
@@ -522,11 +960,45 @@ fn withdraw_liquidity() {
 
     // Store the liquidity token
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(
+        deps.as_mut(),
+        [Uint128::new(100u128), Uint128::new(100u128)],
+    );
+
+    // The withdrawal fails if it returns less than the minimum amount of any listed asset
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            min_assets_to_receive: Some(vec![Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::new(101u128),
+            }]),
+            deadline: None,
+            callback: None,
+        })
+        .unwrap(),
+        amount: Uint128::new(100u128),
+    });
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("liquidity0000", &[]),
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::MinAssetsToReceiveAssertion {});
 
     // Withdraw liquidity
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: String::from("addr0000"),
-        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {}).unwrap(),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            min_assets_to_receive: None,
+            deadline: None,
+            callback: None,
+        })
+        .unwrap(),
         amount: Uint128::new(100u128),
     });
 
@@ -600,30 +1072,25 @@ fn withdraw_liquidity() {
 }
 
 #[test]
-fn try_native_to_token() {
-    let total_share = Uint128::new(30000000000u128);
-    let asset_pool_amount = Uint128::new(20000000000u128);
-    let collateral_pool_amount = Uint128::new(30000000000u128);
-    let offer_amount = Uint128::new(1500000000u128);
+fn withdraw_liquidity_one_asset() {
+    let pool_amount = Uint128::new(1000000u128);
 
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
-        amount: collateral_pool_amount + offer_amount, /* user deposit must be pre-applied */
+        amount: pool_amount,
     }]);
-
     deps.querier.with_tax(
         Decimal::zero(),
         &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
     );
-
     deps.querier.with_token_balances(&[
         (
             &String::from("liquidity0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+            &[(&String::from("addr0000"), &pool_amount)],
         ),
         (
             &String::from("asset0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+            &[(&String::from(MOCK_CONTRACT_ADDR), &pool_amount)],
         ),
     ]);
 
@@ -638,31 +1105,204 @@ fn try_native_to_token() {
         ],
         token_code_id: 10u64,
         factory_addr: String::from("factory"),
-        init_params: Some(to_binary(&StablePoolParams { amp: 100 }).unwrap()),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_on_transfer: false,
+            })
+            .unwrap(),
+        ),
     };
 
     let env = mock_env_with_block_time(100);
     let info = mock_info("addr0000", &[]);
-    // We can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+    instantiate(deps.as_mut(), env, info, msg).unwrap();
 
     // Store the liquidity token
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [pool_amount, pool_amount]);
 
-    // Normal swap
-    let msg = ExecuteMsg::Swap {
-        offer_asset: Asset {
-            info: AssetInfo::NativeToken {
-                denom: "uusd".to_string(),
-            },
-            amount: offer_amount,
-        },
-        belief_price: None,
-        max_spread: Some(Decimal::percent(50)),
-        to: None,
+    let ask_asset_info = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
     };
-    let env = mock_env_with_block_time(1000);
-    let info = mock_info(
+    let res: WithdrawOneAssetSimulationResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env_with_block_time(1000),
+            QueryMsg::SimulateWithdrawOneAsset {
+                lp_amount: Uint128::new(100000u128),
+                ask_asset_info: ask_asset_info.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    // A balanced pool returns almost twice the proportional share of the ask asset.
+    // The fee is charged only on the amount above the proportional share
+    let proportional_amount = Uint128::new(100000u128);
+    assert!(res.return_amount > Uint128::new(199000u128));
+    assert!(res.return_amount < Uint128::new(200000u128));
+    assert_eq!(
+        res.commission_amount,
+        (res.return_amount + res.commission_amount - proportional_amount)
+            * Decimal::from_ratio(3u128, 1000u128)
+    );
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidityOneAsset {
+            ask_asset_info: ask_asset_info.clone(),
+            min_amount: Some(res.return_amount + Uint128::new(1u128)),
+        })
+        .unwrap(),
+        amount: Uint128::new(100000u128),
+    });
+    let err = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        mock_info("liquidity0000", &[]),
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::MinAmountAssertion {});
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidityOneAsset {
+            ask_asset_info,
+            min_amount: Some(res.return_amount),
+        })
+        .unwrap(),
+        amount: Uint128::new(100000u128),
+    });
+    let res_withdraw = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        mock_info("liquidity0000", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res_withdraw.messages[0],
+        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("addr0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: res.return_amount,
+            }],
+        }))
+    );
+    assert_eq!(
+        res_withdraw.attributes[5],
+        attr("commission_amount", res.commission_amount.to_string())
+    );
+
+    // The commission is added to the statistics without counting as a swap
+    let maker_fee_amount: u128 = res_withdraw.attributes[6].value.parse().unwrap();
+    assert!(maker_fee_amount > 0);
+    assert_eq!(
+        query_commissions(deps.as_ref()),
+        (
+            0,
+            vec![(
+                "uusd".to_string(),
+                res.commission_amount.u128() - maker_fee_amount,
+                maker_fee_amount
+            )]
+        )
+    );
+}
+
+#[test]
+fn calc_withdraw_one_amount_errors() {
+    // A valid withdrawal of 10% of the pool
+    let amount = calc_withdraw_one_amount(1_000_000, 1_000_000, 100, 1_000, 100).unwrap();
+    assert!(amount > 100_000 && amount < 200_000);
+
+    // Invalid inputs return an error instead of panicking
+    let err = calc_withdraw_one_amount(1_000_000, 1_000_000, 100, 1_000, u64::MAX).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("Failed to calculate the withdrawal amount")
+    );
+    calc_withdraw_one_amount(1_000_000, 1_000_000, 100, 0, 100).unwrap_err();
+    calc_withdraw_one_amount(1_000_000, 1_000_000, 1_001, 1_000, 100).unwrap_err();
+}
+
+#[test]
+fn try_native_to_token() {
+    let total_share = Uint128::new(30000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+    let offer_amount = Uint128::new(1500000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount, /* user deposit must be pre-applied */
+    }]);
+
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_on_transfer: false,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env_with_block_time(100);
+    let info = mock_info("addr0000", &[]);
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+
+    // Normal swap
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        belief_price: None,
+        max_spread: Some(Decimal::percent(50)),
+        to: None,
+        deadline: None,
+        callback: None,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info(
         "addr0000",
         &[Coin {
             denom: "uusd".to_string(),
@@ -699,6 +1339,7 @@ fn try_native_to_token() {
             amount: collateral_pool_amount, /* user deposit must be pre-applied */
         }],
     )]);
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
 
     let simulation_res: SimulationResponse = query_simulation(
         deps.as_ref(),
@@ -792,7 +1433,13 @@ fn try_token_to_native() {
         ],
         token_code_id: 10u64,
         factory_addr: String::from("factory"),
-        init_params: Some(to_binary(&StablePoolParams { amp: 100 }).unwrap()),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_on_transfer: false,
+            })
+            .unwrap(),
+        ),
     };
 
     let env = mock_env_with_block_time(100);
@@ -802,6 +1449,7 @@ fn try_token_to_native() {
 
     // Store the liquidity token
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
 
     // Unauthorized access; can not execute swap directy for token swap
     let msg = ExecuteMsg::Swap {
@@ -814,6 +1462,8 @@ fn try_token_to_native() {
         belief_price: None,
         max_spread: None,
         to: None,
+        deadline: None,
+        callback: None,
     };
     let env = mock_env_with_block_time(1000);
     let info = mock_info("addr0000", &[]);
@@ -828,6 +1478,8 @@ fn try_token_to_native() {
             belief_price: None,
             max_spread: None,
             to: None,
+            deadline: None,
+            callback: None,
         })
         .unwrap(),
     });
@@ -873,6 +1525,7 @@ fn try_token_to_native() {
             &[(&String::from(MOCK_CONTRACT_ADDR), &(asset_pool_amount))],
         ),
     ]);
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
 
     let simulation_res: SimulationResponse = query_simulation(
         deps.as_ref(),
@@ -933,6 +1586,8 @@ fn try_token_to_native() {
             belief_price: None,
             max_spread: None,
             to: None,
+            deadline: None,
+            callback: None,
         })
         .unwrap(),
     });
@@ -943,91 +1598,31 @@ fn try_token_to_native() {
 }
 
 #[test]
-fn test_max_spread() {
-    assert_max_spread(
-        Some(Decimal::from_ratio(1200u128, 1u128)),
-        Some(Decimal::percent(1)),
-        Uint128::from(1200000000u128),
-        Uint128::from(989999u128),
-        Uint128::zero(),
-    )
-    .unwrap_err();
-
-    assert_max_spread(
-        Some(Decimal::from_ratio(1200u128, 1u128)),
-        Some(Decimal::percent(1)),
-        Uint128::from(1200000000u128),
-        Uint128::from(990000u128),
-        Uint128::zero(),
-    )
-    .unwrap();
-
-    assert_max_spread(
-        None,
-        Some(Decimal::percent(1)),
-        Uint128::zero(),
-        Uint128::from(989999u128),
-        Uint128::from(10001u128),
-    )
-    .unwrap_err();
-
-    assert_max_spread(
-        None,
-        Some(Decimal::percent(1)),
-        Uint128::zero(),
-        Uint128::from(990000u128),
-        Uint128::from(10000u128),
-    )
-    .unwrap();
-}
+fn swap_exact_out_native_to_token() {
+    let total_share = Uint128::new(30000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+    let ask_amount = Uint128::new(1000000000u128);
+    let max_offer_amount = Uint128::new(2000000000u128);
 
-#[test]
-fn test_deduct() {
-    let mut deps = mock_dependencies(&[]);
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
 
-    let tax_rate = Decimal::percent(2);
-    let tax_cap = Uint128::from(1_000_000u128);
     deps.querier.with_tax(
-        Decimal::percent(2),
+        Decimal::zero(),
         &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
     );
 
-    let amount = Uint128::new(1000_000_000u128);
-    let expected_after_amount = std::cmp::max(
-        amount.checked_sub(amount * tax_rate).unwrap(),
-        amount.checked_sub(tax_cap).unwrap(),
-    );
-
-    let after_amount = (Asset {
-        info: AssetInfo::NativeToken {
-            denom: "uusd".to_string(),
-        },
-        amount,
-    })
-    .deduct_tax(&deps.as_ref().querier)
-    .unwrap();
-
-    assert_eq!(expected_after_amount, after_amount.amount);
-}
-
-#[test]
-fn test_query_pool() {
-    let total_share_amount = Uint128::from(111u128);
-    let asset_0_amount = Uint128::from(222u128);
-    let asset_1_amount = Uint128::from(333u128);
-    let mut deps = mock_dependencies(&[Coin {
-        denom: "uusd".to_string(),
-        amount: asset_0_amount,
-    }]);
-
     deps.querier.with_token_balances(&[
         (
-            &String::from("asset0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_1_amount)],
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
         ),
         (
-            &String::from("liquidity0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share_amount)],
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
         ),
     ]);
 
@@ -1042,52 +1637,1133 @@ fn test_query_pool() {
         ],
         token_code_id: 10u64,
         factory_addr: String::from("factory"),
-        init_params: Some(to_binary(&StablePoolParams { amp: 100 }).unwrap()),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_on_transfer: false,
+            })
+            .unwrap(),
+        ),
     };
 
-    let env = mock_env();
+    let env = mock_env_with_block_time(100);
     let info = mock_info("addr0000", &[]);
     // We can just call .unwrap() to assert this was a success
     let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
 
     // Store the liquidity token
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
 
-    let res: PoolResponse = query_pool(deps.as_ref()).unwrap();
+    let ask_asset = Asset {
+        info: AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+        amount: ask_amount,
+    };
 
-    assert_eq!(
-        res.assets,
-        [
-            Asset {
-                info: AssetInfo::NativeToken {
-                    denom: "uusd".to_string(),
-                },
-                amount: asset_0_amount
-            },
-            Asset {
-                info: AssetInfo::Token {
-                    contract_addr: Addr::unchecked("asset0000"),
-                },
-                amount: asset_1_amount
-            }
-        ]
-    );
-    assert_eq!(res.total_share, total_share_amount);
-}
+    // The exact output swap spends the same amount as the reverse simulation returns
+    let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
+        deps.as_ref(),
+        mock_env_with_block_time(1000),
+        ask_asset.clone(),
+    )
+    .unwrap();
+    let expected_offer_amount = reverse_simulation_res.offer_amount;
+    let expected_refund_amount = max_offer_amount - expected_offer_amount;
 
-#[test]
-fn test_query_share() {
-    let total_share_amount = Uint128::from(500u128);
-    let asset_0_amount = Uint128::from(250u128);
-    let asset_1_amount = Uint128::from(1000u128);
-    let mut deps = mock_dependencies(&[Coin {
-        denom: "uusd".to_string(),
-        amount: asset_0_amount,
-    }]);
+    // The max offer amount is too small
+    let too_small_offer_amount = expected_offer_amount - Uint128::new(1u128);
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount + too_small_offer_amount, /* user deposit must be pre-applied */
+        }],
+    )]);
+    let msg = ExecuteMsg::SwapExactOut {
+        ask_asset: ask_asset.clone(),
+        max_offer_amount: too_small_offer_amount,
+        to: None,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: too_small_offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::MaxOfferAmountAssertion {});
 
-    deps.querier.with_token_balances(&[
-        (
-            &String::from("asset0000"),
+    // The ask amount can't drain the ask pool
+    let msg = ExecuteMsg::SwapExactOut {
+        ask_asset: Asset {
+            amount: asset_pool_amount,
+            ..ask_asset.clone()
+        },
+        max_offer_amount: too_small_offer_amount,
+        to: None,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: too_small_offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::AskAmountExceedsPool {});
+
+    // Normal exact output swap
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount + max_offer_amount, /* user deposit must be pre-applied */
+        }],
+    )]);
+    let msg = ExecuteMsg::SwapExactOut {
+        ask_asset: ask_asset.clone(),
+        max_offer_amount,
+        to: None,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: max_offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+    assert_eq!(res.attributes[0], attr("action", "swap_exact_out"));
+    assert_eq!(
+        res.attributes[5],
+        attr("offer_amount", expected_offer_amount.to_string())
+    );
+    assert_eq!(
+        res.attributes[6],
+        attr("refund_amount", expected_refund_amount.to_string())
+    );
+    assert_eq!(
+        res.attributes[7],
+        attr("return_amount", ask_amount.to_string())
+    );
+
+    assert_eq!(
+        res.messages[0],
+        SubMsg {
+            msg: WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0000"),
+                    amount: ask_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+            .into(),
+            id: 0,
+            gas_limit: None,
+            reply_on: ReplyOn::Never,
+        },
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg {
+            msg: CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("addr0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: expected_refund_amount,
+                }],
+            }),
+            id: 0,
+            gas_limit: None,
+            reply_on: ReplyOn::Never,
+        },
+    );
+
+    // Asking for an asset that does not belong to the pair
+    let msg = ExecuteMsg::SwapExactOut {
+        ask_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "ukrw".to_string(),
+            },
+            amount: ask_amount,
+        },
+        max_offer_amount,
+        to: None,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: max_offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::AssetMismatch {});
+}
+
+#[test]
+fn swap_exact_out_token_to_native() {
+    let total_share = Uint128::new(20000000000u128);
+    let asset_pool_amount = Uint128::new(30000000000u128);
+    let collateral_pool_amount = Uint128::new(20000000000u128);
+    let ask_amount = Uint128::new(1000000000u128);
+    let max_offer_amount = Uint128::new(2000000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_on_transfer: false,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env_with_block_time(100);
+    let info = mock_info("addr0000", &[]);
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+
+    let ask_asset = Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: ask_amount,
+    };
+
+    let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
+        deps.as_ref(),
+        mock_env_with_block_time(1000),
+        ask_asset.clone(),
+    )
+    .unwrap();
+    let expected_offer_amount = reverse_simulation_res.offer_amount;
+    let expected_refund_amount = max_offer_amount - expected_offer_amount;
+
+    // Unauthorized access; can not offer tokens directly
+    let msg = ExecuteMsg::SwapExactOut {
+        ask_asset: ask_asset.clone(),
+        max_offer_amount,
+        to: None,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    // Normal exact output swap
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(asset_pool_amount + max_offer_amount),
+            )],
+        ),
+    ]);
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: max_offer_amount,
+        msg: to_binary(&Cw20HookMsg::SwapExactOut {
+            ask_asset: ask_asset.clone(),
+            to: Some(String::from("addr0001")),
+        })
+        .unwrap(),
+    });
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("asset0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+    assert_eq!(
+        res.attributes[5],
+        attr("offer_amount", expected_offer_amount.to_string())
+    );
+    assert_eq!(
+        res.attributes[6],
+        attr("refund_amount", expected_refund_amount.to_string())
+    );
+    assert_eq!(
+        res.messages[0],
+        SubMsg {
+            msg: CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("addr0001"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: ask_amount,
+                }],
+            }),
+            id: 0,
+            gas_limit: None,
+            reply_on: ReplyOn::Never,
+        },
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg {
+            msg: WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0000"),
+                    amount: expected_refund_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+            .into(),
+            id: 0,
+            gas_limit: None,
+            reply_on: ReplyOn::Never,
+        },
+    );
+
+    // Only pair tokens can be offered
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: max_offer_amount,
+        msg: to_binary(&Cw20HookMsg::SwapExactOut {
+            ask_asset,
+            to: None,
+        })
+        .unwrap(),
+    });
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("liquidity0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+}
+
+#[test]
+fn flash_loan() {
+    let total_share = Uint128::new(20000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+    let loan_amount = Uint128::new(1000000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_on_transfer: false,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env_with_block_time(100);
+    let info = mock_info("addr0000", &[]);
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+
+    let loan_asset = Asset {
+        info: AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+        amount: loan_amount,
+    };
+    let flash_loan_msg = ExecuteMsg::FlashLoan {
+        asset: loan_asset.clone(),
+        callback_contract: String::from("borrower"),
+        msg: Binary::from(b"arbitrage".to_vec()),
+    };
+    let repay_reply = Reply {
+        id: 2,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+
+    // The fee is 9 bps of the repaid amount
+    let expected_fee_amount = Uint128::new(900811u128);
+
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env, info, flash_loan_msg.clone()).unwrap();
+    assert_eq!(res.attributes[0], attr("action", "flash_loan"));
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("borrower"),
+                    amount: loan_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::reply_on_success(
+                WasmMsg::Execute {
+                    contract_addr: String::from("borrower"),
+                    msg: to_binary(&FlashLoanCallbackMsg::FlashLoanCallback {
+                        sender: String::from("addr0000"),
+                        asset: loan_asset,
+                        fee_amount: expected_fee_amount,
+                        msg: Binary::from(b"arbitrage".to_vec()),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                },
+                2,
+            ),
+        ]
+    );
+
+    // The pair can't be used while the loan is in progress
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("borrower"),
+        amount: Uint128::new(1000u128),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            min_assets_to_receive: None,
+            deadline: None,
+            callback: None,
+        })
+        .unwrap(),
+    });
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("liquidity0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::FlashLoanInProgress {});
+
+    // The borrower didn't pay the whole fee
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(asset_pool_amount + expected_fee_amount - Uint128::new(1u128)),
+            )],
+        ),
+    ]);
+    let res = reply(deps.as_mut(), mock_env(), repay_reply.clone()).unwrap_err();
+    assert_eq!(res, ContractError::FlashLoanNotRepaid {});
+
+    // The borrower repaid the loan together with the fee
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), env, info, flash_loan_msg.clone()).unwrap();
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(asset_pool_amount + expected_fee_amount),
+            )],
+        ),
+    ]);
+    let res = reply(deps.as_mut(), mock_env(), repay_reply.clone()).unwrap();
+    assert_eq!(res.attributes[0], attr("action", "repay_flash_loan"));
+    assert_eq!(res.attributes[2], attr("fees", "900811asset0000"));
+    assert_eq!(res.attributes[3], attr("maker_fees", "149534asset0000"));
+    // The fee stays in the pool reserves without the Maker fee
+    assert_eq!(
+        RESERVES.load(&deps.storage).unwrap(),
+        [
+            collateral_pool_amount,
+            asset_pool_amount + Uint128::new(900811u128 - 149534u128)
+        ]
+    );
+
+    // The loan is repaid in the other pool asset (a flash swap), but the invariant decreased
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), env, info, flash_loan_msg.clone()).unwrap();
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(asset_pool_amount - loan_amount),
+            )],
+        ),
+    ]);
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount + Uint128::new(900000000u128),
+        }],
+    )]);
+    let res = reply(deps.as_mut(), mock_env(), repay_reply.clone()).unwrap_err();
+    assert_eq!(res, ContractError::FlashLoanNotRepaid {});
+
+    // The flash swap is repaid with enough assets
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount,
+        }],
+    )]);
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), env, info, flash_loan_msg).unwrap();
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(asset_pool_amount - loan_amount),
+            )],
+        ),
+    ]);
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount + Uint128::new(1100000000u128),
+        }],
+    )]);
+    let res = reply(deps.as_mut(), mock_env(), repay_reply).unwrap();
+    assert_eq!(res.attributes[2], attr("fees", "990000uusd"));
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("fee_address"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(164340u128),
+            }],
+        }))]
+    );
+
+    // The flash loan fees are added to the statistics without counting as swaps
+    assert_eq!(
+        query_commissions(deps.as_ref()),
+        (
+            0,
+            vec![
+                ("asset0000".to_string(), 751277, 149534),
+                ("uusd".to_string(), 825660, 164340)
+            ]
+        )
+    );
+}
+
+#[test]
+fn skim_and_sync() {
+    let total_share = Uint128::new(20000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(asset_pool_amount + Uint128::new(700u128)),
+            )],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_on_transfer: false,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env_with_block_time(100);
+    let info = mock_info("addr0000", &[]);
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+
+    // Skim sends the donation to the recipient
+    let msg = ExecuteMsg::Skim {
+        to: String::from("addr0001"),
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(res.attributes[2], attr("assets", "700asset0000"));
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: String::from("asset0000"),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("addr0001"),
+                amount: Uint128::new(700u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+
+    // Sync adds the donation to the pool
+    let msg = ExecuteMsg::Sync {};
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), env, info, msg).unwrap();
+
+    let res: PoolResponse = query_pool(deps.as_ref()).unwrap();
+    assert_eq!(res.assets[0].amount, collateral_pool_amount);
+    assert_eq!(
+        res.assets[1].amount,
+        asset_pool_amount + Uint128::new(700u128)
+    );
+}
+
+#[test]
+fn fee_on_transfer_tokens() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(1100_000000u128),
+    }]);
+
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &Uint128::new(2000_000000u128))],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(1000_000000u128),
+            )],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_on_transfer: true,
+            })
+            .unwrap(),
+        ),
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(
+        deps.as_mut(),
+        [Uint128::new(1000_000000u128), Uint128::new(1000_000000u128)],
+    );
+
+    let res: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert!(
+        from_binary::<StablePoolConfig>(&res.params.unwrap())
+            .unwrap()
+            .fee_on_transfer
+    );
+
+    // The provision waits for the token transfer to settle
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::new(100_000000u128),
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                amount: Uint128::new(100_000000u128),
+            },
+        ],
+        slippage_tolerance: Some(Decimal::percent(2)),
+        auto_stake: None,
+        receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
+    };
+    let info = mock_info("addr0000", &[Coin::new(100_000000, "uusd")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: String::from("addr0000"),
+                    recipient: String::from(MOCK_CONTRACT_ADDR),
+                    amount: Uint128::new(100_000000u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            },
+            4,
+        )]
+    );
+
+    // The token charges a 1% transfer fee, so the pair receives only 99 tokens
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &Uint128::new(2000_000000u128))],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(1099_000000u128),
+            )],
+        ),
+    ]);
+    let settle_reply = Reply {
+        id: 4,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+    let res = reply(deps.as_mut(), mock_env(), settle_reply.clone()).unwrap();
+    assert_eq!(
+        res.attributes[3],
+        attr("assets", "100000000uusd, 99000000asset0000")
+    );
+    assert_eq!(res.attributes[4], attr("share", "198998498"));
+    // The imbalance fee share of the Maker leaves the pool
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [Uint128::new(1099_999876u128), Uint128::new(1098_999876u128)]
+    );
+
+    // The pending provision is settled only once
+    reply(deps.as_mut(), mock_env(), settle_reply).unwrap_err();
+
+    // A swap uses the amount that the pair received instead of the sent amount
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &Uint128::new(2000_000000u128))],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(1193_999876u128),
+            )],
+        ),
+    ]);
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: Uint128::new(100_000000u128),
+        msg: to_binary(&Cw20HookMsg::Swap {
+            belief_price: None,
+            max_spread: Some(Decimal::percent(50)),
+            to: None,
+            deadline: None,
+            callback: None,
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), mock_env(), mock_info("asset0000", &[]), msg).unwrap();
+    assert_eq!(res.attributes[5], attr("offer_amount", "95000000"));
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap()[1],
+        Uint128::new(1193_999876u128)
+    );
+}
+
+#[test]
+fn migrate_from_previous_versions() {
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+    )]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_on_transfer: false,
+            })
+            .unwrap(),
+        ),
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // A 1.0.0 pair did not track its reserves, so they are seeded from the pair balances
+    RESERVES.remove(deps.as_mut().storage);
+    cw2::set_contract_version(deps.as_mut().storage, "astroport-pair-stable", "1.0.0").unwrap();
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        res.attributes[1],
+        attr("previous_contract_version", "1.0.0")
+    );
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [collateral_pool_amount, asset_pool_amount]
+    );
+    assert_eq!(
+        cw2::get_contract_version(deps.as_ref().storage)
+            .unwrap()
+            .version,
+        env!("CARGO_PKG_VERSION")
+    );
+
+    // A 1.1.0 pair keeps its reserves, so donations can still be skimmed
+    store_reserves(
+        deps.as_mut(),
+        [
+            collateral_pool_amount - Uint128::new(500u128),
+            asset_pool_amount - Uint128::new(700u128),
+        ],
+    );
+    cw2::set_contract_version(deps.as_mut().storage, "astroport-pair-stable", "1.1.0").unwrap();
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [
+            collateral_pool_amount - Uint128::new(500u128),
+            asset_pool_amount - Uint128::new(700u128),
+        ]
+    );
+
+    // Unknown versions can not be migrated
+    cw2::set_contract_version(deps.as_mut().storage, "astroport-pair-stable", "0.9.0").unwrap();
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(res, ContractError::MigrationError {});
+}
+
+#[test]
+fn test_max_spread() {
+    assert_max_spread(
+        Some(Decimal::from_ratio(1200u128, 1u128)),
+        Some(Decimal::percent(1)),
+        Uint128::from(1200000000u128),
+        Uint128::from(989999u128),
+        Uint128::zero(),
+    )
+    .unwrap_err();
+
+    assert_max_spread(
+        Some(Decimal::from_ratio(1200u128, 1u128)),
+        Some(Decimal::percent(1)),
+        Uint128::from(1200000000u128),
+        Uint128::from(990000u128),
+        Uint128::zero(),
+    )
+    .unwrap();
+
+    assert_max_spread(
+        None,
+        Some(Decimal::percent(1)),
+        Uint128::zero(),
+        Uint128::from(989999u128),
+        Uint128::from(10001u128),
+    )
+    .unwrap_err();
+
+    assert_max_spread(
+        None,
+        Some(Decimal::percent(1)),
+        Uint128::zero(),
+        Uint128::from(990000u128),
+        Uint128::from(10000u128),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_deduct() {
+    let mut deps = mock_dependencies(&[]);
+
+    let tax_rate = Decimal::percent(2);
+    let tax_cap = Uint128::from(1_000_000u128);
+    deps.querier.with_tax(
+        Decimal::percent(2),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let amount = Uint128::new(1000_000_000u128);
+    let expected_after_amount = std::cmp::max(
+        amount.checked_sub(amount * tax_rate).unwrap(),
+        amount.checked_sub(tax_cap).unwrap(),
+    );
+
+    let after_amount = (Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount,
+    })
+    .deduct_tax(&deps.as_ref().querier)
+    .unwrap();
+
+    assert_eq!(expected_after_amount, after_amount.amount);
+}
+
+#[test]
+fn test_query_pool() {
+    let total_share_amount = Uint128::from(111u128);
+    let asset_0_amount = Uint128::from(222u128);
+    let asset_1_amount = Uint128::from(333u128);
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: asset_0_amount,
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_1_amount)],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_on_transfer: false,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [asset_0_amount, asset_1_amount]);
+
+    let res: PoolResponse = query_pool(deps.as_ref()).unwrap();
+
+    assert_eq!(
+        res.assets,
+        [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: asset_0_amount
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                amount: asset_1_amount
+            }
+        ]
+    );
+    assert_eq!(res.total_share, total_share_amount);
+}
+
+#[test]
+fn test_query_share() {
+    let total_share_amount = Uint128::from(500u128);
+    let asset_0_amount = Uint128::from(250u128);
+    let asset_1_amount = Uint128::from(1000u128);
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: asset_0_amount,
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
             &[(&String::from(MOCK_CONTRACT_ADDR), &asset_1_amount)],
         ),
         (
@@ -1107,7 +2783,13 @@ fn test_query_share() {
         ],
         token_code_id: 10u64,
         factory_addr: String::from("factory"),
-        init_params: Some(to_binary(&StablePoolParams { amp: 100 }).unwrap()),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_on_transfer: false,
+            })
+            .unwrap(),
+        ),
     };
 
     let env = mock_env();
@@ -1117,6 +2799,7 @@ fn test_query_share() {
 
     // Store the liquidity token
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [asset_0_amount, asset_1_amount]);
 
     let res = query_share(deps.as_ref(), Uint128::new(250)).unwrap();
 
@@ -1124,6 +2807,222 @@ fn test_query_share() {
     assert_eq!(res[1].amount, Uint128::new(500));
 }
 
+#[test]
+fn test_query_virtual_price() {
+    let total_share_amount = Uint128::from(1000000u128);
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_on_transfer: false,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    // The invariant of a balanced pool is the sum of its balances
+    store_reserves(
+        deps.as_mut(),
+        [Uint128::new(1000000u128), Uint128::new(1000000u128)],
+    );
+    let res: PoolInvariantResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::PoolInvariant {}).unwrap())
+            .unwrap();
+    assert_eq!(res.d, Uint128::new(2000000u128));
+    assert_eq!(res.amp, Decimal::from_ratio(100u128, 1u128));
+
+    let res: VirtualPriceResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::VirtualPrice {}).unwrap())
+            .unwrap();
+    assert_eq!(res.virtual_price, Decimal::from_ratio(2u128, 1u128));
+
+    // Moving the balances along the curve barely changes the virtual price
+    store_reserves(
+        deps.as_mut(),
+        [Uint128::new(1500000u128), Uint128::new(500000u128)],
+    );
+    let res: VirtualPriceResponse =
+        from_binary(&query(deps.as_ref(), env, QueryMsg::VirtualPrice {}).unwrap()).unwrap();
+    assert!(res.virtual_price < Decimal::from_ratio(2u128, 1u128));
+    assert!(res.virtual_price > Decimal::from_ratio(199u128, 100u128));
+}
+
+#[test]
+fn test_query_spot_price_and_price_impact() {
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+    )]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_on_transfer: false,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let asset0000 = AssetInfo::Token {
+        contract_addr: Addr::unchecked("asset0000"),
+    };
+    let query_spot_price = |deps: Deps, offer_asset_info: &AssetInfo| -> Decimal {
+        let res: SpotPriceResponse = from_binary(
+            &query(
+                deps,
+                mock_env(),
+                QueryMsg::SpotPrice {
+                    offer_asset_info: offer_asset_info.clone(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.price
+    };
+
+    // A balanced pool trades at par
+    store_reserves(
+        deps.as_mut(),
+        [
+            Uint128::new(1000000000000u128),
+            Uint128::new(1000000000000u128),
+        ],
+    );
+    assert_eq!(query_spot_price(deps.as_ref(), &uusd), Decimal::one());
+
+    // The asset with the greater balance is cheaper
+    store_reserves(
+        deps.as_mut(),
+        [
+            Uint128::new(1500000000000u128),
+            Uint128::new(500000000000u128),
+        ],
+    );
+    let uusd_price = query_spot_price(deps.as_ref(), &uusd);
+    let asset_price = query_spot_price(deps.as_ref(), &asset0000);
+    assert!(uusd_price < Decimal::one());
+    assert!(asset_price > Decimal::one());
+    // The prices in both directions are reciprocal
+    let product = Uint128::new(1000000000000u128) * uusd_price * asset_price;
+    assert!(product <= Uint128::new(1000000000000u128));
+    assert!(product >= Uint128::new(999999999999u128));
+
+    // The spot price matches the price of a tiny swap up to rounding
+    let offer_asset = Asset {
+        info: uusd.clone(),
+        amount: Uint128::new(1000000u128),
+    };
+    let simulation: SimulationResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::Simulation {
+                offer_asset: offer_asset.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let spot_amount = offer_asset.amount * uusd_price;
+    let swap_amount = simulation.return_amount + simulation.commission_amount;
+    assert!(swap_amount <= spot_amount + Uint128::new(1u128));
+    assert!(swap_amount + Uint128::new(1u128) >= spot_amount);
+
+    // A large swap moves the price against the trader
+    let offer_asset = Asset {
+        info: uusd,
+        amount: Uint128::new(500000000000u128),
+    };
+    let res: PriceImpactResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::PriceImpact {
+                offer_asset: offer_asset.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let simulation: SimulationResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env,
+            QueryMsg::Simulation {
+                offer_asset: offer_asset.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let spot_amount = offer_asset.amount * uusd_price;
+    assert_eq!(res.spot_price, uusd_price);
+    assert_eq!(
+        res.execution_price,
+        Decimal::from_ratio(simulation.return_amount, offer_asset.amount)
+    );
+    assert_eq!(
+        res.price_impact,
+        Decimal::from_ratio(
+            spot_amount - simulation.return_amount - simulation.commission_amount,
+            spot_amount
+        )
+    );
+    assert!(res.price_impact > Decimal::percent(1));
+}
+
 #[test]
 fn test_accumulate_prices() {
     struct Case {
@@ -1224,6 +3123,7 @@ fn test_accumulate_prices() {
                 init_amp_time: env.block.time.seconds(),
                 next_amp: 100 * AMP_PRECISION,
                 next_amp_time: env.block.time.seconds(),
+                fee_on_transfer: false,
             },
             Uint128::new(case.x_amount),
             6,
@@ -1248,6 +3148,153 @@ fn test_accumulate_prices() {
     }
 }
 
+#[test]
+fn circuit_breaker() {
+    let total_share = Uint128::new(20000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_on_transfer: false,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env_with_block_time(100);
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+
+    let swap = |deps: DepsMut, amount: u128| {
+        let msg = ExecuteMsg::Swap {
+            offer_asset: Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::new(amount),
+            },
+            belief_price: None,
+            max_spread: Some(Decimal::percent(50)),
+            to: None,
+            deadline: None,
+            callback: None,
+        };
+        let info = mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(amount),
+            }],
+        );
+        execute(deps, mock_env_with_block_time(1000), info, msg)
+    };
+    let status = |deps: Deps| -> StatusResponse {
+        from_binary(&query(deps, mock_env(), QueryMsg::Status {}).unwrap()).unwrap()
+    };
+
+    // Only the factory owner or the guardian can pause the pair
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Pause {},
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Pause {},
+    )
+    .unwrap();
+    assert!(status(deps.as_ref()).paused);
+
+    assert_eq!(
+        swap(deps.as_mut(), 1000000u128).unwrap_err(),
+        ContractError::Paused {}
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Sync {},
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Paused {});
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Unpause {},
+    )
+    .unwrap();
+
+    // Reject operations that move the price by more than 1% within a block
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateCircuitBreaker {
+            guardian: None,
+            max_price_change_bps: Some(100),
+        },
+    )
+    .unwrap();
+
+    swap(deps.as_mut(), 1000000u128).unwrap();
+    let reserves = RESERVES.load(&deps.storage).unwrap();
+
+    assert_eq!(
+        swap(deps.as_mut(), 10000000000u128).unwrap_err(),
+        ContractError::MaxPriceChangeAssertion {}
+    );
+    assert!(!status(deps.as_ref()).paused);
+
+    // The failed swap is reverted on chain, but the mock storage keeps its writes
+    store_reserves(deps.as_mut(), reserves);
+
+    // Swaps within the limit still go through in the same block
+    swap(deps.as_mut(), 1000000u128).unwrap();
+}
+
 fn mock_env_with_block_time(time: u64) -> Env {
     let mut env = mock_env();
     env.block = BlockInfo {
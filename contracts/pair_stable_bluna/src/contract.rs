@@ -1,45 +1,50 @@
 use crate::error::ContractError;
-use crate::math::{
-    calc_ask_amount, calc_offer_amount, compute_d, AMP_PRECISION, MAX_AMP, MAX_AMP_CHANGE,
-    MIN_AMP_CHANGING_TIME, N_COINS,
-};
-use crate::state::{
-    Config, BLUNA_REWARD_GLOBAL_INDEX, BLUNA_REWARD_HOLDER, BLUNA_REWARD_USER_INDEXES, CONFIG,
+use crate::state::{Config, FlashLoanState, BLUNA_REWARD_INDEXES, CONFIG, FLASH_LOAN, RESERVES};
+use astroport::stable_math::{
+    calc_ask_amount, calc_offer_amount, calc_withdraw_one_amount, compute_d, AMP_PRECISION,
+    MAX_AMP, MAX_AMP_CHANGE, MIN_AMP_CHANGING_TIME, N_COINS,
 };
 
 use cosmwasm_bignumber::Decimal256;
 use cosmwasm_std::{
-    attr, entry_point, from_binary, to_binary, Addr, Binary, Coin, CosmosMsg, Decimal, Deps,
-    DepsMut, Env, MessageInfo, Reply, ReplyOn, Response, StdError, StdResult, SubMsg, Uint128,
-    Uint256, WasmMsg,
+    attr, entry_point, from_binary, to_binary, Addr, Api, Binary, Coin, CosmosMsg, Decimal, Deps,
+    DepsMut, Env, Fraction, MessageInfo, QuerierWrapper, Reply, ReplyOn, Response, StdError,
+    StdResult, Storage, SubMsg, Uint128, WasmMsg,
 };
 
 use crate::response::MsgInstantiateContractResponse;
-use astroport::asset::{addr_validate_to_lower, format_lp_token_name, Asset, AssetInfo, PairInfo};
+use astroport::asset::{
+    addr_validate_to_lower, format_lp_token_name, Asset, AssetInfo, Callback, PairInfo,
+};
+use astroport::circuit_breaker::{
+    self, guardian, is_paused, query_status, set_paused, track_price_change,
+};
 use astroport::factory::PairType;
 
-use astroport::generator::{
-    Cw20HookMsg as GeneratorHookMsg, PoolInfoResponse, QueryMsg as GeneratorQueryMsg,
-};
+use astroport::generator::Cw20HookMsg as GeneratorHookMsg;
 use astroport::pair::{
-    ConfigResponse, CumulativePricesResponse, Cw20HookMsg, InstantiateMsg, PoolResponse,
-    ReverseSimulationResponse, SimulationResponse, DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE,
-    TWAP_PRECISION,
+    ConfigResponse, CumulativePricesResponse, Cw20HookMsg, FlashLoanCallbackMsg, InstantiateMsg,
+    PoolInvariantResponse, PoolResponse, ReverseSimulationResponse, SimulateProvideResponse,
+    SimulateWithdrawResponse, SimulationResponse, VirtualPriceResponse,
+    WithdrawOneAssetSimulationResponse, DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE, TWAP_PRECISION,
 };
 use astroport::pair_stable_bluna::{
     ExecuteMsg, MigrateMsg, QueryMsg, StablePoolConfig, StablePoolParams, StablePoolUpdateParams,
 };
-use astroport::whitelist::InstantiateMsg as WhitelistInstantiateMsg;
+use astroport::reward_bearing::{self, reward_holder_instantiate_msg, RewardBearingConfig};
 
+use astroport::observation::{
+    increase_observations_size, observe, store_observation, ObserveResponse,
+};
 use astroport::querier::{
-    query_factory_config, query_fee_info, query_supply, query_token_precision,
+    query_factory_config, query_pair_fee_info, query_supply, query_token_precision,
 };
+use astroport::stats::{query_daily_stats, query_stats, record_fee, record_swap};
 use astroport::{token::InstantiateMsg as TokenInstantiateMsg, U256};
 use cw2::{get_contract_version, set_contract_version};
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
 use protobuf::Message;
 use std::cmp::Ordering;
-use std::convert::TryInto;
 use std::str::FromStr;
 use std::vec;
 
@@ -50,6 +55,7 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 /// `reply` call code IDs used for sub-messages.
 const INSTANTIATE_TOKEN_REPLY_ID: u64 = 1;
 const INSTANTIATE_BLUNA_REWARD_HOLDER_REPLY_ID: u64 = 2;
+const FLASH_LOAN_REPLY_ID: u64 = 3;
 
 /// ## Description
 /// Creates a new contract with the specified parameters in the [`InstantiateMsg`].
@@ -86,10 +92,12 @@ pub fn instantiate(
         return Err(ContractError::IncorrectAmp {});
     }
 
-    let mut messages: Vec<SubMsg> = vec![get_bluna_reward_holder_instantiating_message(
-        deps.as_ref(),
+    let mut messages: Vec<SubMsg> = vec![reward_holder_instantiate_msg(
+        &deps.querier,
         &env,
         &addr_validate_to_lower(deps.api, &msg.factory_addr)?,
+        INSTANTIATE_BLUNA_REWARD_HOLDER_REPLY_ID,
+        "Bluna rewarder",
     )?];
 
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
@@ -114,8 +122,9 @@ pub fn instantiate(
     };
 
     CONFIG.save(deps.storage, &config)?;
+    RESERVES.save(deps.storage, &[Uint128::zero(), Uint128::zero()])?;
 
-    let token_name = format_lp_token_name(msg.asset_infos, &deps.querier)?;
+    let token_name = format_lp_token_name(&msg.asset_infos, &deps.querier)?;
 
     // Create LP token
     messages.push(SubMsg {
@@ -153,7 +162,11 @@ pub fn instantiate(
 ///
 /// * **msg** is an object of type [`Reply`].
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    if msg.id == FLASH_LOAN_REPLY_ID {
+        return repay_flash_loan(deps, env);
+    }
+
     let data = msg.result.unwrap().data.unwrap();
     let res: MsgInstantiateContractResponse =
         Message::parse_from_bytes(data.as_slice()).map_err(|_| {
@@ -181,7 +194,7 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
         }
         INSTANTIATE_BLUNA_REWARD_HOLDER_REPLY_ID => {
             let addr = addr_validate_to_lower(deps.api, res.get_contract_address())?;
-            BLUNA_REWARD_HOLDER.save(deps.storage, &addr)?;
+            BLUNA_REWARD_INDEXES.holder.save(deps.storage, &addr)?;
             response.attributes.push(attr("bluna_reward_holder", addr))
         }
         _ => return Err(ContractError::Unauthorized {}),
@@ -204,6 +217,9 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 /// ## Queries
 /// * **ExecuteMsg::UpdateConfig { params: Binary }** Not supported.
 ///
+/// * **ExecuteMsg::IncreaseObservationsSize { size }** Increases the amount of price observations
+/// stored by the pair.
+///
 /// * **ExecuteMsg::Receive(msg)** Receives a message of type [`Cw20ReceiveMsg`] and processes
 /// it depending on the received template.
 ///
@@ -212,6 +228,9 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 ///             slippage_tolerance,
 ///             auto_stake,
 ///             receiver,
+///             min_lp_to_receive,
+///             deadline,
+///             callback,
 ///         }** Provides liquidity in the pair with the specified input parameters.
 ///
 /// * **ExecuteMsg::Swap {
@@ -219,8 +238,26 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 ///             belief_price,
 ///             max_spread,
 ///             to,
+///             deadline,
+///             callback,
 ///         }** Performs a swap operation with the specified parameters.
 ///
+/// * **ExecuteMsg::SwapExactOut {
+///             ask_asset,
+///             max_offer_amount,
+///             to,
+///         }** Performs a swap that returns an exact amount of ask assets and refunds the unused offer assets.
+///
+/// * **ExecuteMsg::FlashLoan {
+///             asset,
+///             callback_contract,
+///             msg,
+///         }** Lends a pool asset to a callback contract that has to repay it within the same transaction.
+///
+/// * **ExecuteMsg::Skim { to }** Sends the pair balances that exceed the pool reserves to the recipient.
+///
+/// * **ExecuteMsg::Sync {}** Sets the pool reserves to the current pair balances.
+///
 /// * **ExecuteMsg::ClaimReward {
 ///             receiver,
 ///             user_share,
@@ -239,6 +276,15 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 ///             total_share,
 ///             user,
 ///         }** Handles and distributes bLUNA rewards.
+///
+/// * **ExecuteMsg::Pause {}** Stops swaps and liquidity provision in the pair.
+///
+/// * **ExecuteMsg::Unpause {}** Resumes swaps and liquidity provision in the pair.
+///
+/// * **ExecuteMsg::UpdateCircuitBreaker {
+///             guardian,
+///             max_price_change_bps,
+///         }** Updates the guardian and the maximum price change of the pair.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -246,29 +292,51 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // The pool balances must not change while a flash loan is in progress
+    if FLASH_LOAN.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::FlashLoanInProgress {});
+    }
+
     match msg {
         ExecuteMsg::UpdateConfig { params } => update_config(deps, env, info, params),
+        ExecuteMsg::IncreaseObservationsSize { size } => {
+            increase_observations_size(deps.storage, size)?;
+            Ok(Response::new()
+                .add_attribute("action", "increase_observations_size")
+                .add_attribute("size", size.to_string()))
+        }
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
         ExecuteMsg::ProvideLiquidity {
             assets,
             slippage_tolerance,
             auto_stake,
             receiver,
-        } => provide_liquidity(
-            deps,
-            env,
-            info,
-            assets,
-            slippage_tolerance,
-            auto_stake,
-            receiver,
-        ),
+            min_lp_to_receive,
+            deadline,
+            callback,
+        } => {
+            assert_deadline(&env, deadline)?;
+            provide_liquidity(
+                deps,
+                env,
+                info,
+                assets,
+                slippage_tolerance,
+                auto_stake,
+                receiver,
+                min_lp_to_receive,
+                callback,
+            )
+        }
         ExecuteMsg::Swap {
             offer_asset,
             belief_price,
             max_spread,
             to,
+            deadline,
+            callback,
         } => {
+            assert_deadline(&env, deadline)?;
             offer_asset.info.check(deps.api)?;
             if !offer_asset.is_native_token() {
                 return Err(ContractError::Unauthorized {});
@@ -289,8 +357,48 @@ pub fn execute(
                 belief_price,
                 max_spread,
                 to_addr,
+                callback,
+            )
+        }
+        ExecuteMsg::SwapExactOut {
+            ask_asset,
+            max_offer_amount,
+            to,
+        } => {
+            ask_asset.info.check(deps.api)?;
+
+            let config: Config = CONFIG.load(deps.storage)?;
+            let offer_asset = Asset {
+                info: get_offer_asset_info(&config, &ask_asset.info)?,
+                amount: max_offer_amount,
+            };
+            if !offer_asset.is_native_token() {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let to_addr = if let Some(to_addr) = to {
+                Some(addr_validate_to_lower(deps.api, &to_addr)?)
+            } else {
+                None
+            };
+
+            swap_exact_out(
+                deps,
+                env,
+                info.clone(),
+                info.sender,
+                offer_asset,
+                ask_asset,
+                to_addr,
             )
         }
+        ExecuteMsg::FlashLoan {
+            asset,
+            callback_contract,
+            msg,
+        } => flash_loan(deps, env, info, asset, callback_contract, msg),
+        ExecuteMsg::Skim { to } => skim(deps, env, to),
+        ExecuteMsg::Sync {} => sync(deps, env),
         ExecuteMsg::ClaimReward { receiver } => claim_reward(deps, env, info, receiver),
         ExecuteMsg::ClaimRewardByGenerator {
             user,
@@ -313,6 +421,12 @@ pub fn execute(
             total_share,
             receiver,
         ),
+        ExecuteMsg::Pause {} => pause(deps, info),
+        ExecuteMsg::Unpause {} => unpause(deps, info),
+        ExecuteMsg::UpdateCircuitBreaker {
+            guardian,
+            max_price_change_bps,
+        } => update_circuit_breaker(deps, info, guardian, max_price_change_bps),
     }
 }
 
@@ -340,7 +454,10 @@ pub fn receive_cw20(
             belief_price,
             max_spread,
             to,
+            deadline,
+            callback,
         }) => {
+            assert_deadline(&env, deadline)?;
             // Only an asset contract can execute this message
             let mut authorized: bool = false;
             let config: Config = CONFIG.load(deps.storage)?;
@@ -375,14 +492,68 @@ pub fn receive_cw20(
                 belief_price,
                 max_spread,
                 to_addr,
+                callback,
+            )
+        }
+        Ok(Cw20HookMsg::SwapExactOut { ask_asset, to }) => {
+            // Only an asset (token) contract can execute this message
+            let config: Config = CONFIG.load(deps.storage)?;
+            let offer_asset_info = AssetInfo::Token { contract_addr };
+            if !config
+                .pair_info
+                .asset_infos
+                .iter()
+                .any(|a| a.equal(&offer_asset_info))
+            {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let to_addr = if let Some(to_addr) = to {
+                Some(addr_validate_to_lower(deps.api, to_addr.as_str())?)
+            } else {
+                None
+            };
+
+            swap_exact_out(
+                deps,
+                env,
+                info,
+                Addr::unchecked(cw20_msg.sender),
+                Asset {
+                    info: offer_asset_info,
+                    amount: cw20_msg.amount,
+                },
+                ask_asset,
+                to_addr,
+            )
+        }
+        Ok(Cw20HookMsg::WithdrawLiquidity {
+            min_assets_to_receive,
+            deadline,
+            callback,
+        }) => {
+            assert_deadline(&env, deadline)?;
+            withdraw_liquidity(
+                deps,
+                env,
+                info,
+                Addr::unchecked(cw20_msg.sender),
+                cw20_msg.amount,
+                min_assets_to_receive,
+                callback,
             )
         }
-        Ok(Cw20HookMsg::WithdrawLiquidity {}) => withdraw_liquidity(
+        Ok(Cw20HookMsg::WithdrawLiquidityOneAsset {
+            ask_asset_info,
+            min_amount,
+        }) => withdraw_liquidity_one_asset(
             deps,
             env,
             info,
             Addr::unchecked(cw20_msg.sender),
             cw20_msg.amount,
+            ask_asset_info,
+            min_amount,
         ),
         Err(err) => Err(ContractError::Std(err)),
     }
@@ -407,7 +578,14 @@ pub fn receive_cw20(
 ///
 /// * **receiver** is an object of type [`Option<String>`]. This is the address that receives LP tokens.
 /// If this address isn't specified, the function will default to the caller.
+///
+/// * **min_lp_to_receive** is an object of type [`Option<Uint128>`]. This is the minimum amount of LP tokens to receive.
+/// The provision fails if fewer LP tokens are minted.
 /// NOTE - the address that wants to swap should approve the pair contract to pull the offer token.
+///
+/// * **callback** is an [`Option`] field of type [`Callback`]. This is the contract that receives
+/// the LP tokens along with a message.
+#[allow(clippy::too_many_arguments)]
 pub fn provide_liquidity(
     deps: DepsMut,
     env: Env,
@@ -416,38 +594,28 @@ pub fn provide_liquidity(
     slippage_tolerance: Option<Decimal>,
     auto_stake: Option<bool>,
     receiver: Option<String>,
+    min_lp_to_receive: Option<Uint128>,
+    callback: Option<Callback>,
 ) -> Result<Response, ContractError> {
+    if is_paused(deps.storage)? {
+        return Err(ContractError::Paused {});
+    }
+
     assets[0].info.check(deps.api)?;
     assets[1].info.check(deps.api)?;
 
     let auto_stake = auto_stake.unwrap_or(false);
+    assert_callback(deps.api, &callback, auto_stake || receiver.is_some())?;
     for asset in assets.iter() {
         asset.assert_sent_native_token_balance(&info)?;
     }
 
     let mut config: Config = CONFIG.load(deps.storage)?;
-    let mut pools: [Asset; 2] = config
-        .pair_info
-        .query_pools(&deps.querier, env.contract.address.clone())?;
-    let deposits: [Uint128; 2] = [
-        assets
-            .iter()
-            .find(|a| a.info.equal(&pools[0].info))
-            .map(|a| a.amount)
-            .expect("Wrong asset info is given"),
-        assets
-            .iter()
-            .find(|a| a.info.equal(&pools[1].info))
-            .map(|a| a.amount)
-            .expect("Wrong asset info is given"),
-    ];
-
-    if deposits[0].is_zero() || deposits[1].is_zero() {
-        return Err(ContractError::InvalidZeroAmount {});
-    }
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
+    let deposits = get_deposits(&assets, &pools)?;
 
     let mut messages: Vec<CosmosMsg> = vec![];
-    for (i, pool) in pools.iter_mut().enumerate() {
+    for (i, pool) in pools.iter().enumerate() {
         // If the pool is a token contract, then we need to execute a TransferFrom msg to receive funds
         if let AssetInfo::Token { contract_addr, .. } = &pool.info {
             messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -459,10 +627,6 @@ pub fn provide_liquidity(
                 })?,
                 funds: vec![],
             }))
-        } else {
-            // If the asset is a native token, the pool balance already increased
-            // To calculate the pool balance properly, we should subtract the user deposit from the recorded pool token amount
-            pool.amount = pool.amount.checked_sub(deposits[i])?;
         }
     }
 
@@ -472,96 +636,164 @@ pub fn provide_liquidity(
     let token_precision_0 = query_token_precision(&deps.querier, pools[0].info.clone())?;
     let token_precision_1 = query_token_precision(&deps.querier, pools[1].info.clone())?;
 
-    let greater_precision = token_precision_0.max(token_precision_1);
-
-    let deposit_amount_0 = adjust_precision(deposits[0], token_precision_0, greater_precision)?;
-    let deposit_amount_1 = adjust_precision(deposits[1], token_precision_1, greater_precision)?;
-
     let total_share = query_supply(&deps.querier, config.pair_info.liquidity_token.clone())?;
-    let share = if total_share.is_zero() {
-        let liquidity_token_precision = query_token_precision(
-            &deps.querier,
-            AssetInfo::Token {
-                contract_addr: config.pair_info.liquidity_token.clone(),
-            },
-        )?;
-
-        // Initial share = collateral amount
-        adjust_precision(
-            Uint128::new(
-                (U256::from(deposit_amount_0.u128()) * U256::from(deposit_amount_1.u128()))
-                    .integer_sqrt()
-                    .as_u128(),
-            ),
-            greater_precision,
-            liquidity_token_precision,
-        )?
-    } else {
-        let leverage = compute_current_amp(&config, &env)?
-            .checked_mul(u64::from(N_COINS))
-            .unwrap();
-
-        let mut pool_amount_0 =
-            adjust_precision(pools[0].amount, token_precision_0, greater_precision)?;
-        let mut pool_amount_1 =
-            adjust_precision(pools[1].amount, token_precision_1, greater_precision)?;
 
-        let d_before_addition_liquidity =
-            compute_d(leverage, pool_amount_0.u128(), pool_amount_1.u128()).unwrap();
-
-        pool_amount_0 = pool_amount_0.checked_add(deposit_amount_0)?;
-        pool_amount_1 = pool_amount_1.checked_add(deposit_amount_1)?;
+    // Get fee info from the factory
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
 
-        let d_after_addition_liquidity =
-            compute_d(leverage, pool_amount_0.u128(), pool_amount_1.u128()).unwrap();
+    let (share, imbalance_fees) = compute_share(
+        &deps.querier,
+        &env,
+        &config,
+        &pools,
+        &deposits,
+        [token_precision_0, token_precision_1],
+        total_share,
+        fee_info.total_fee_rate,
+    )?;
 
-        // d after adding liquidity may be less than or equal to d before adding liquidity because of rounding
-        if d_before_addition_liquidity >= d_after_addition_liquidity {
-            return Err(ContractError::LiquidityAmountTooSmall {});
+    // Maker fee
+    let mut maker_fees = [Uint128::zero(); 2];
+    let mut maker_fee_messages: Vec<CosmosMsg> = vec![];
+    if let Some(fee_address) = fee_info.fee_address {
+        for (i, pool) in pools.iter().enumerate() {
+            if let Some(f) = calculate_maker_fee(
+                pool.info.clone(),
+                imbalance_fees[i],
+                fee_info.maker_fee_rate,
+            ) {
+                maker_fee_messages.push(f.clone().into_msg(&deps.querier, fee_address.clone())?);
+                maker_fees[i] = f.amount;
+            }
         }
+    }
 
-        total_share.multiply_ratio(
-            d_after_addition_liquidity - d_before_addition_liquidity,
-            d_before_addition_liquidity,
-        )
-    };
+    // The imbalance fee is charged like a swap commission
+    for (i, pool) in pools.iter().enumerate() {
+        record_fee(
+            deps.storage,
+            env.block.time.seconds(),
+            &pool.info,
+            imbalance_fees[i],
+            maker_fees[i],
+        )?;
+    }
 
-    if share.is_zero() {
-        return Err(ContractError::LiquidityAmountTooSmall {});
+    // The minimum is checked before minting, so it also applies to the LP tokens staked in the Generator
+    if let Some(min_lp_to_receive) = min_lp_to_receive {
+        if share < min_lp_to_receive {
+            return Err(ContractError::MinLpToReceiveAssertion {});
+        }
     }
 
-    // Mint LP token for the caller (or for the receiver if it was set)
-    let receiver = receiver.unwrap_or_else(|| info.sender.to_string());
-    messages.extend(mint_liquidity_token_message(
-        deps.as_ref(),
-        &config,
-        env.clone(),
-        addr_validate_to_lower(deps.api, receiver.as_str())?,
-        share,
-        auto_stake,
-    )?);
+    let receiver = match &callback {
+        Some(callback) => callback.contract.clone(),
+        None => receiver.unwrap_or_else(|| info.sender.to_string()),
+    };
+    let callback_messages = match callback {
+        // Mint LP tokens for the pair and send them to the callback contract after all the other messages
+        Some(callback) => {
+            messages.extend(mint_liquidity_token_message(
+                deps.as_ref(),
+                &config,
+                env.clone(),
+                env.contract.address.clone(),
+                share,
+                false,
+            )?);
+            callback.into_msgs(
+                &deps.querier,
+                &[Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: config.pair_info.liquidity_token.clone(),
+                    },
+                    amount: share,
+                }],
+            )?
+        }
+        // Mint LP token for the caller (or for the receiver if it was set)
+        None => {
+            messages.extend(mint_liquidity_token_message(
+                deps.as_ref(),
+                &config,
+                env.clone(),
+                addr_validate_to_lower(deps.api, receiver.as_str())?,
+                share,
+                auto_stake,
+            )?);
+            vec![]
+        }
+    };
+    messages.extend(maker_fee_messages);
 
     // Accumulate prices assets in the pool
     if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
-        env,
+        env.clone(),
         &config,
         pools[0].amount,
         token_precision_0,
         pools[1].amount,
         token_precision_1,
     )? {
+        store_observation(
+            deps.storage,
+            config.block_time_last,
+            block_time,
+            price0_cumulative_new.wrapping_sub(config.price0_cumulative_last),
+            price1_cumulative_new.wrapping_sub(config.price1_cumulative_last),
+        )?;
         config.price0_cumulative_last = price0_cumulative_new;
         config.price1_cumulative_last = price1_cumulative_new;
         config.block_time_last = block_time;
         CONFIG.save(deps.storage, &config)?;
     }
 
+    let reserves_after = [
+        pools[0]
+            .amount
+            .checked_add(deposits[0])?
+            .checked_sub(maker_fees[0])?,
+        pools[1]
+            .amount
+            .checked_add(deposits[1])?
+            .checked_sub(maker_fees[1])?,
+    ];
+    RESERVES.save(deps.storage, &reserves_after)?;
+    record_price_change(
+        deps.storage,
+        &deps.querier,
+        &env,
+        &config,
+        &pools,
+        reserves_after,
+    )?;
+
+    messages.extend(callback_messages);
+
     Ok(Response::new().add_messages(messages).add_attributes(vec![
         attr("action", "provide_liquidity"),
         attr("sender", info.sender.as_str()),
         attr("receiver", receiver.as_str()),
         attr("assets", format!("{}, {}", assets[0], assets[1])),
         attr("share", share.to_string()),
+        attr(
+            "imbalance_fees",
+            format!(
+                "{}{}, {}{}",
+                imbalance_fees[0], pools[0].info, imbalance_fees[1], pools[1].info
+            ),
+        ),
+        attr(
+            "maker_fees",
+            format!(
+                "{}{}, {}{}",
+                maker_fees[0], pools[0].info, maker_fees[1], pools[1].info
+            ),
+        ),
     ]))
 }
 
@@ -643,21 +875,30 @@ fn mint_liquidity_token_message(
 /// * **sender** is an object of type [`Addr`]. This is the address that will receive the withdrawn liquidity.
 ///
 /// * **amount** is an object of type [`Uint128`]. This is the amount of LP tokens to burn and withdraw liquidity with.
+///
+/// * **min_assets_to_receive** is an object of type [`Option<Vec<Asset>>`]. These are the minimum amounts of pool assets to receive.
+///
+/// * **callback** is an [`Option`] field of type [`Callback`]. This is the contract that receives
+/// the withdrawn assets along with a message.
 pub fn withdraw_liquidity(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     sender: Addr,
     amount: Uint128,
+    min_assets_to_receive: Option<Vec<Asset>>,
+    callback: Option<Callback>,
 ) -> Result<Response, ContractError> {
     let mut config: Config = CONFIG.load(deps.storage).unwrap();
 
     if info.sender != config.pair_info.liquidity_token {
         return Err(ContractError::Unauthorized {});
     }
+    assert_callback(deps.api, &callback, false)?;
 
     let (pools, total_share) = pool_info(deps.as_ref(), config.clone())?;
     let refund_assets = get_share_in_assets(&pools, amount, total_share);
+    assert_min_assets_to_receive(&refund_assets, min_assets_to_receive)?;
 
     // Accumulate prices for the assets in the pool
     if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
@@ -668,25 +909,47 @@ pub fn withdraw_liquidity(
         pools[1].amount,
         query_token_precision(&deps.querier, pools[1].info.clone())?,
     )? {
+        store_observation(
+            deps.storage,
+            config.block_time_last,
+            block_time,
+            price0_cumulative_new.wrapping_sub(config.price0_cumulative_last),
+            price1_cumulative_new.wrapping_sub(config.price1_cumulative_last),
+        )?;
         config.price0_cumulative_last = price0_cumulative_new;
         config.price1_cumulative_last = price1_cumulative_new;
         config.block_time_last = block_time;
         CONFIG.save(deps.storage, &config)?;
     }
 
-    let messages: Vec<CosmosMsg> = vec![
-        refund_assets[0]
-            .clone()
-            .into_msg(&deps.querier, sender.clone())?,
-        refund_assets[1]
-            .clone()
-            .into_msg(&deps.querier, sender.clone())?,
-        CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: config.pair_info.liquidity_token.to_string(),
-            msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
-            funds: vec![],
-        }),
-    ];
+    RESERVES.save(
+        deps.storage,
+        &[
+            pools[0].amount.checked_sub(refund_assets[0].amount)?,
+            pools[1].amount.checked_sub(refund_assets[1].amount)?,
+        ],
+    )?;
+
+    let mut messages: Vec<CosmosMsg> = match &callback {
+        Some(_) => vec![],
+        None => vec![
+            refund_assets[0]
+                .clone()
+                .into_msg(&deps.querier, sender.clone())?,
+            refund_assets[1]
+                .clone()
+                .into_msg(&deps.querier, sender.clone())?,
+        ],
+    };
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.pair_info.liquidity_token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
+        funds: vec![],
+    }));
+    if let Some(callback) = callback {
+        // The callback contract gets the assets after the LP tokens are burned
+        messages.extend(callback.into_msgs(&deps.querier, &refund_assets)?);
+    }
 
     let attributes = vec![
         attr("action", "withdraw_liquidity"),
@@ -704,40 +967,9 @@ pub fn withdraw_liquidity(
 }
 
 /// ## Description
-/// Return the amount of tokens that a specific amount of LP tokens would withdraw.
-/// ## Params
-/// * **pools** is an array of [`Asset`] type items. These are the assets available in the pool.
-///
-/// * **amount** is an object of type [`Uint128`]. This is the amount of LP tokens to calculate underlying amounts for.
-///
-/// * **total_share** is an object of type [`Uint128`]. This is the total amount of LP tokens currently issued by the pool.
-pub fn get_share_in_assets(
-    pools: &[Asset; 2],
-    amount: Uint128,
-    total_share: Uint128,
-) -> [Asset; 2] {
-    let mut share_ratio = Decimal::zero();
-    if !total_share.is_zero() {
-        share_ratio = Decimal::from_ratio(amount, total_share);
-    }
-
-    [
-        Asset {
-            info: pools[0].info.clone(),
-            amount: pools[0].amount * share_ratio,
-        },
-        Asset {
-            info: pools[1].info.clone(),
-            amount: pools[1].amount * share_ratio,
-        },
-    ]
-}
-
-/// ## Description
-/// Performs a swap with the specified parameters.
-/// CONTRACT - the user must allow the pool to pull tokens from their account in order to swap them.
-/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the
-/// specified attributes if the operation was successful.
+/// Withdraws liquidity from the pool in a single asset using the StableSwap invariant.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified attributes
+/// if the operation was successful.
 /// ## Params
 /// * **deps** is an object of type [`DepsMut`].
 ///
@@ -745,47 +977,428 @@ pub fn get_share_in_assets(
 ///
 /// * **info** is an object of type [`MessageInfo`].
 ///
-/// * **sender** is an object of type [`Addr`]. This is the default recipient of the swap operation.
-///
-/// * **offer_asset** is an object of type [`Asset`]. This is the asset to swap.
+/// * **sender** is an object of type [`Addr`]. This is the address that will receive the ask assets.
 ///
-/// * **belief_price** is an object of type [`Option<Decimal>`]. This is used to calculate the maximum spread.
+/// * **amount** is an object of type [`Uint128`]. This is the amount of LP tokens to burn.
 ///
-/// * **max_spread** is an object of type [`Option<Decimal>`]. This is the maximum spread allowed for the swap.
+/// * **ask_asset_info** is an object of type [`AssetInfo`]. This is the asset to receive.
 ///
-/// * **to** is an object of type [`Option<Addr>`]. This is the address that receives ask tokens.
-#[allow(clippy::too_many_arguments)]
-pub fn swap(
+/// * **min_amount** is an [`Option`] field of type [`Uint128`]. This is the minimum amount of ask assets to receive.
+pub fn withdraw_liquidity_one_asset(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     sender: Addr,
-    offer_asset: Asset,
-    belief_price: Option<Decimal>,
-    max_spread: Option<Decimal>,
-    to: Option<Addr>,
+    amount: Uint128,
+    ask_asset_info: AssetInfo,
+    min_amount: Option<Uint128>,
 ) -> Result<Response, ContractError> {
-    offer_asset.assert_sent_native_token_balance(&info)?;
+    // The withdrawal swaps one of the pool assets, so it is stopped together with swaps
+    if is_paused(deps.storage)? {
+        return Err(ContractError::Paused {});
+    }
 
     let mut config: Config = CONFIG.load(deps.storage)?;
 
-    // If the asset balance already increased
-    // We should subtract the user deposit from the pool offer asset amount
-    let pools: Vec<Asset> = config
-        .pair_info
-        .query_pools(&deps.querier, env.clone().contract.address)?
+    if info.sender != config.pair_info.liquidity_token {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let (pools, total_share) = pool_info(deps.as_ref(), config.clone())?;
+    let ask_index = pools
         .iter()
-        .map(|p| {
-            let mut p = p.clone();
-            if p.info.equal(&offer_asset.info) {
-                p.amount = p.amount.checked_sub(offer_asset.amount).unwrap();
-            }
+        .position(|pool| pool.info.equal(&ask_asset_info))
+        .ok_or(ContractError::AssetMismatch {})?;
 
-            p
-        })
-        .collect();
+    // Get fee info from the factory
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
 
-    let offer_pool: Asset;
+    let (return_amount, commission_amount) = compute_withdraw_one_asset(
+        &deps.querier,
+        &env,
+        &config,
+        &pools,
+        amount,
+        total_share,
+        ask_index,
+        fee_info.total_fee_rate,
+    )?;
+
+    if return_amount < min_amount.unwrap_or_default() {
+        return Err(ContractError::MinAmountAssertion {});
+    }
+
+    let return_asset = Asset {
+        info: ask_asset_info.clone(),
+        amount: return_amount,
+    };
+    let mut messages: Vec<CosmosMsg> = vec![return_asset
+        .clone()
+        .into_msg(&deps.querier, sender.clone())?];
+
+    // Maker fee
+    let mut maker_fee_amount = Uint128::new(0);
+    if let Some(fee_address) = fee_info.fee_address {
+        if let Some(f) =
+            calculate_maker_fee(ask_asset_info, commission_amount, fee_info.maker_fee_rate)
+        {
+            messages.push(f.clone().into_msg(&deps.querier, fee_address)?);
+            maker_fee_amount = f.amount;
+        }
+    }
+
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.pair_info.liquidity_token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
+        funds: vec![],
+    }));
+
+    // Accumulate prices for the assets in the pool
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
+        env.clone(),
+        &config,
+        pools[0].amount,
+        query_token_precision(&deps.querier, pools[0].info.clone())?,
+        pools[1].amount,
+        query_token_precision(&deps.querier, pools[1].info.clone())?,
+    )? {
+        store_observation(
+            deps.storage,
+            config.block_time_last,
+            block_time,
+            price0_cumulative_new.wrapping_sub(config.price0_cumulative_last),
+            price1_cumulative_new.wrapping_sub(config.price1_cumulative_last),
+        )?;
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    // The other asset stays in the pool
+    let mut reserves_after = [pools[0].amount, pools[1].amount];
+    reserves_after[ask_index] =
+        reserves_after[ask_index].checked_sub(return_amount + maker_fee_amount)?;
+    RESERVES.save(deps.storage, &reserves_after)?;
+    record_price_change(
+        deps.storage,
+        &deps.querier,
+        &env,
+        &config,
+        &pools,
+        reserves_after,
+    )?;
+
+    record_fee(
+        deps.storage,
+        env.block.time.seconds(),
+        &return_asset.info,
+        commission_amount,
+        maker_fee_amount,
+    )?;
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "withdraw_liquidity_one_asset"),
+        attr("sender", sender.as_str()),
+        attr("withdrawn_share", amount.to_string()),
+        attr("ask_asset", return_asset.info.to_string()),
+        attr("return_amount", return_amount.to_string()),
+        attr("commission_amount", commission_amount.to_string()),
+        attr("maker_fee_amount", maker_fee_amount.to_string()),
+    ]))
+}
+
+/// ## Description
+/// Returns the amount of ask assets and the commission for burning LP tokens and withdrawing a single asset.
+/// Only the part of the withdrawal above the proportional share of the ask asset acts as a swap,
+/// so the fee is charged on that part only.
+/// ## Params
+/// * **querier** is an object of type [`QuerierWrapper`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **pools** is an array with two objects of type [`Asset`]. These are the current pool amounts.
+///
+/// * **amount** is an object of type [`Uint128`]. This is the amount of LP tokens to burn.
+///
+/// * **total_share** is an object of type [`Uint128`]. This is the total amount of LP tokens currently issued by the pool.
+///
+/// * **ask_index** is an object of type [`usize`]. This is the position of the ask asset in the pool.
+///
+/// * **commission_rate** is an object of type [`Decimal`]. This is the total fee rate charged by the pair.
+#[allow(clippy::too_many_arguments)]
+fn compute_withdraw_one_asset(
+    querier: &QuerierWrapper,
+    env: &Env,
+    config: &Config,
+    pools: &[Asset; 2],
+    amount: Uint128,
+    total_share: Uint128,
+    ask_index: usize,
+    commission_rate: Decimal,
+) -> StdResult<(Uint128, Uint128)> {
+    let other_index = 1 - ask_index;
+    let ask_precision = query_token_precision(querier, pools[ask_index].info.clone())?;
+    let other_precision = query_token_precision(querier, pools[other_index].info.clone())?;
+    let greater_precision = ask_precision.max(other_precision);
+
+    let ask_pool = adjust_precision(pools[ask_index].amount, ask_precision, greater_precision)?;
+    let other_pool = adjust_precision(
+        pools[other_index].amount,
+        other_precision,
+        greater_precision,
+    )?;
+
+    let withdraw_amount = calc_withdraw_one_amount(
+        ask_pool.u128(),
+        other_pool.u128(),
+        amount.u128(),
+        total_share.u128(),
+        compute_current_amp(config, env)?,
+    )?;
+    let withdraw_amount = adjust_precision(
+        Uint128::new(withdraw_amount),
+        greater_precision,
+        ask_precision,
+    )?;
+
+    let proportional_amount = pools[ask_index].amount.multiply_ratio(amount, total_share);
+    let commission_amount = withdraw_amount.saturating_sub(proportional_amount) * commission_rate;
+
+    Ok((
+        withdraw_amount.checked_sub(commission_amount)?,
+        commission_amount,
+    ))
+}
+
+/// ## Description
+/// Returns the deposit amounts of the given assets in the order of the pool assets.
+/// Returns a [`ContractError`] if an asset does not belong to the pool or if a deposit amount is zero.
+/// ## Params
+/// * **assets** is an array with two objects of type [`Asset`]. These are the assets to deposit.
+///
+/// * **pools** is an array with two objects of type [`Asset`]. These are the assets in the pool.
+fn get_deposits(assets: &[Asset; 2], pools: &[Asset; 2]) -> Result<[Uint128; 2], ContractError> {
+    let mut deposits = [Uint128::zero(); 2];
+    for (deposit, pool) in deposits.iter_mut().zip(pools.iter()) {
+        *deposit = assets
+            .iter()
+            .find(|a| a.info.equal(&pool.info))
+            .map(|a| a.amount)
+            .ok_or(ContractError::AssetMismatch {})?;
+    }
+
+    if deposits[0].is_zero() || deposits[1].is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    Ok(deposits)
+}
+
+/// ## Description
+/// Returns the amount of LP tokens that a deposit mints together with the imbalance fees charged on it
+/// in the precision of the pool assets. The first deposit mints the square root of the product of the
+/// deposit amounts and charges no fees.
+/// ## Params
+/// * **querier** is an object of type [`QuerierWrapper`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **pools** is an array with two objects of type [`Asset`]. These are the assets in the pool.
+///
+/// * **deposits** is an array with two objects of type [`Uint128`]. These are the deposit amounts in the order of the pool assets.
+///
+/// * **token_precisions** is an array with two items of type [`u8`]. These are the precisions of the pool assets.
+///
+/// * **total_share** is an object of type [`Uint128`]. This is the total amount of LP tokens currently issued by the pool.
+///
+/// * **total_fee_rate** is an object of type [`Decimal`]. This is the total fee rate charged by the pair.
+#[allow(clippy::too_many_arguments)]
+fn compute_share(
+    querier: &QuerierWrapper,
+    env: &Env,
+    config: &Config,
+    pools: &[Asset; 2],
+    deposits: &[Uint128; 2],
+    token_precisions: [u8; 2],
+    total_share: Uint128,
+    total_fee_rate: Decimal,
+) -> Result<(Uint128, [Uint128; 2]), ContractError> {
+    let greater_precision = token_precisions[0].max(token_precisions[1]);
+
+    let deposit_amount_0 = adjust_precision(deposits[0], token_precisions[0], greater_precision)?;
+    let deposit_amount_1 = adjust_precision(deposits[1], token_precisions[1], greater_precision)?;
+
+    let mut imbalance_fees = [Uint128::zero(); 2];
+    let share = if total_share.is_zero() {
+        let liquidity_token_precision = query_token_precision(
+            querier,
+            AssetInfo::Token {
+                contract_addr: config.pair_info.liquidity_token.clone(),
+            },
+        )?;
+
+        // Initial share = collateral amount
+        adjust_precision(
+            Uint128::new(
+                (U256::from(deposit_amount_0.u128()) * U256::from(deposit_amount_1.u128()))
+                    .integer_sqrt()
+                    .as_u128(),
+            ),
+            greater_precision,
+            liquidity_token_precision,
+        )?
+    } else {
+        let leverage = compute_current_amp(config, env)?
+            .checked_mul(u64::from(N_COINS))
+            .ok_or(ContractError::InvariantComputationError {})?;
+
+        let old_balances = [
+            adjust_precision(pools[0].amount, token_precisions[0], greater_precision)?,
+            adjust_precision(pools[1].amount, token_precisions[1], greater_precision)?,
+        ];
+        let new_balances = [
+            old_balances[0].checked_add(deposit_amount_0)?,
+            old_balances[1].checked_add(deposit_amount_1)?,
+        ];
+
+        let d_before_addition_liquidity =
+            compute_d(leverage, old_balances[0].u128(), old_balances[1].u128())
+                .ok_or(ContractError::InvariantComputationError {})?;
+        let d_after_addition_liquidity =
+            compute_d(leverage, new_balances[0].u128(), new_balances[1].u128())
+                .ok_or(ContractError::InvariantComputationError {})?;
+
+        // d after adding liquidity may be less than or equal to d before adding liquidity because of rounding
+        if d_before_addition_liquidity >= d_after_addition_liquidity {
+            return Err(ContractError::LiquidityAmountTooSmall {});
+        }
+
+        // Charge the fee on the deviation of every deposit from a balanced deposit, which is about
+        // what swapping the excess into the other asset would cost. The fee rate is fee * N / (4 * (N - 1))
+        let mut balances_after_fee = new_balances;
+        for i in 0..N_COINS as usize {
+            let ideal_balance = old_balances[i]
+                .multiply_ratio(d_after_addition_liquidity, d_before_addition_liquidity);
+            let difference = if ideal_balance > new_balances[i] {
+                ideal_balance - new_balances[i]
+            } else {
+                new_balances[i] - ideal_balance
+            };
+            let fee = (difference * total_fee_rate).multiply_ratio(N_COINS, 4 * (N_COINS - 1));
+            balances_after_fee[i] = new_balances[i].checked_sub(fee)?;
+            imbalance_fees[i] = adjust_precision(fee, greater_precision, token_precisions[i])?;
+        }
+
+        let d_after_fee = compute_d(
+            leverage,
+            balances_after_fee[0].u128(),
+            balances_after_fee[1].u128(),
+        )
+        .ok_or(ContractError::InvariantComputationError {})?;
+        if d_before_addition_liquidity >= d_after_fee {
+            return Err(ContractError::LiquidityAmountTooSmall {});
+        }
+
+        // The rest of the fee stays in the pool and goes to the current liquidity providers
+        total_share.multiply_ratio(
+            d_after_fee - d_before_addition_liquidity,
+            d_before_addition_liquidity,
+        )
+    };
+
+    if share.is_zero() {
+        return Err(ContractError::LiquidityAmountTooSmall {});
+    }
+
+    Ok((share, imbalance_fees))
+}
+
+/// ## Description
+/// Return the amount of tokens that a specific amount of LP tokens would withdraw.
+/// ## Params
+/// * **pools** is an array of [`Asset`] type items. These are the assets available in the pool.
+///
+/// * **amount** is an object of type [`Uint128`]. This is the amount of LP tokens to calculate underlying amounts for.
+///
+/// * **total_share** is an object of type [`Uint128`]. This is the total amount of LP tokens currently issued by the pool.
+pub fn get_share_in_assets(
+    pools: &[Asset; 2],
+    amount: Uint128,
+    total_share: Uint128,
+) -> [Asset; 2] {
+    let mut share_ratio = Decimal::zero();
+    if !total_share.is_zero() {
+        share_ratio = Decimal::from_ratio(amount, total_share);
+    }
+
+    [
+        Asset {
+            info: pools[0].info.clone(),
+            amount: pools[0].amount * share_ratio,
+        },
+        Asset {
+            info: pools[1].info.clone(),
+            amount: pools[1].amount * share_ratio,
+        },
+    ]
+}
+
+/// ## Description
+/// Performs a swap with the specified parameters.
+/// CONTRACT - the user must allow the pool to pull tokens from their account in order to swap them.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the
+/// specified attributes if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **sender** is an object of type [`Addr`]. This is the default recipient of the swap operation.
+///
+/// * **offer_asset** is an object of type [`Asset`]. This is the asset to swap.
+///
+/// * **belief_price** is an object of type [`Option<Decimal>`]. This is used to calculate the maximum spread.
+///
+/// * **max_spread** is an object of type [`Option<Decimal>`]. This is the maximum spread allowed for the swap.
+///
+/// * **to** is an object of type [`Option<Addr>`]. This is the address that receives ask tokens.
+///
+/// * **callback** is an [`Option`] field of type [`Callback`]. This is the contract that receives
+/// the ask assets along with a message.
+#[allow(clippy::too_many_arguments)]
+pub fn swap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    offer_asset: Asset,
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    to: Option<Addr>,
+    callback: Option<Callback>,
+) -> Result<Response, ContractError> {
+    if is_paused(deps.storage)? {
+        return Err(ContractError::Paused {});
+    }
+
+    offer_asset.assert_sent_native_token_balance(&info)?;
+
+    let mut config: Config = CONFIG.load(deps.storage)?;
+
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
+
+    let offer_pool: Asset;
     let ask_pool: Asset;
 
     if offer_asset.info.equal(&pools[0].info) {
@@ -799,10 +1412,10 @@ pub fn swap(
     }
 
     // Get fee info from the factory
-    let fee_info = query_fee_info(
+    let fee_info = query_pair_fee_info(
         &deps.querier,
         config.factory_addr.clone(),
-        config.pair_info.pair_type.clone(),
+        config.pair_info.contract_addr.clone(),
     )?;
 
     let offer_amount = offer_asset.amount;
@@ -833,10 +1446,20 @@ pub fn swap(
 
     let tax_amount = return_asset.compute_tax(&deps.querier)?;
 
-    let receiver = to.unwrap_or_else(|| sender.clone());
+    assert_callback(deps.api, &callback, to.is_some())?;
+    let receiver = match &callback {
+        Some(callback) => Addr::unchecked(&callback.contract),
+        None => to.unwrap_or_else(|| sender.clone()),
+    };
 
-    let mut messages: Vec<CosmosMsg> =
-        vec![return_asset.into_msg(&deps.querier, receiver.clone())?];
+    let (mut messages, callback_messages): (Vec<CosmosMsg>, Vec<CosmosMsg>) = match callback {
+        // The callback contract gets the return assets after all the other messages
+        Some(callback) => (vec![], callback.into_msgs(&deps.querier, &[return_asset])?),
+        None => (
+            vec![return_asset.into_msg(&deps.querier, receiver.clone())?],
+            vec![],
+        ),
+    };
 
     // Maker fee
     let mut maker_fee_amount = Uint128::new(0);
@@ -853,36 +1476,685 @@ pub fn swap(
 
     // Accumulate prices for the assets in the pool
     if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
-        env,
+        env.clone(),
         &config,
         pools[0].amount,
         query_token_precision(&deps.querier, pools[0].info.clone())?,
         pools[1].amount,
         query_token_precision(&deps.querier, pools[1].info.clone())?,
     )? {
+        store_observation(
+            deps.storage,
+            config.block_time_last,
+            block_time,
+            price0_cumulative_new.wrapping_sub(config.price0_cumulative_last),
+            price1_cumulative_new.wrapping_sub(config.price1_cumulative_last),
+        )?;
         config.price0_cumulative_last = price0_cumulative_new;
         config.price1_cumulative_last = price1_cumulative_new;
         config.block_time_last = block_time;
         CONFIG.save(deps.storage, &config)?;
     }
 
+    // The Maker fee leaves the pool along with the return amount
+    let reserves_after = reserves_after_swap(
+        &pools,
+        &offer_asset.info,
+        offer_amount,
+        return_amount + maker_fee_amount,
+    )?;
+    RESERVES.save(deps.storage, &reserves_after)?;
+    record_price_change(
+        deps.storage,
+        &deps.querier,
+        &env,
+        &config,
+        &pools,
+        reserves_after,
+    )?;
+
+    record_swap(
+        deps.storage,
+        env.block.time.seconds(),
+        &offer_asset,
+        &Asset {
+            info: ask_pool.info.clone(),
+            amount: return_amount,
+        },
+        commission_amount,
+        maker_fee_amount,
+    )?;
+
+    messages.extend(callback_messages);
+
+    Ok(Response::new()
+        .add_messages(
+            // 1. send collateral token from the contract to a user
+            // 2. send inactive commission to collector
+            messages,
+        )
+        .add_attribute("action", "swap")
+        .add_attribute("sender", sender.as_str())
+        .add_attribute("receiver", receiver.as_str())
+        .add_attribute("offer_asset", offer_asset.info.to_string())
+        .add_attribute("ask_asset", ask_pool.info.to_string())
+        .add_attribute("offer_amount", offer_amount.to_string())
+        .add_attribute("return_amount", return_amount.to_string())
+        .add_attribute("tax_amount", tax_amount.to_string())
+        .add_attribute("spread_amount", spread_amount.to_string())
+        .add_attribute("commission_amount", commission_amount.to_string())
+        .add_attribute("maker_fee_amount", maker_fee_amount.to_string()))
+}
+
+/// ## Description
+/// Performs a swap that returns an exact amount of ask assets. The required offer amount is computed
+/// the same way as in a reverse simulation and the unused part of the offered assets is refunded to the sender.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the
+/// specified attributes if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **sender** is an object of type [`Addr`]. This is the default recipient of the swap operation.
+///
+/// * **offer_asset** is an object of type [`Asset`]. This is the asset to swap as well as the maximum amount of it to spend.
+///
+/// * **ask_asset** is an object of type [`Asset`]. This is the asset to receive as well as the exact amount to receive.
+///
+/// * **to** is an object of type [`Option<Addr>`]. This is the address that receives ask tokens.
+pub fn swap_exact_out(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    offer_asset: Asset,
+    ask_asset: Asset,
+    to: Option<Addr>,
+) -> Result<Response, ContractError> {
+    if is_paused(deps.storage)? {
+        return Err(ContractError::Paused {});
+    }
+
+    offer_asset.assert_sent_native_token_balance(&info)?;
+
+    if ask_asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let mut config: Config = CONFIG.load(deps.storage)?;
+
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
+
+    let offer_pool: Asset;
+    let ask_pool: Asset;
+
+    if offer_asset.info.equal(&pools[0].info) {
+        offer_pool = pools[0].clone();
+        ask_pool = pools[1].clone();
+    } else if offer_asset.info.equal(&pools[1].info) {
+        offer_pool = pools[1].clone();
+        ask_pool = pools[0].clone();
+    } else {
+        return Err(ContractError::AssetMismatch {});
+    }
+
+    if !ask_asset.info.equal(&ask_pool.info) {
+        return Err(ContractError::AssetMismatch {});
+    }
+
+    // The pool can't give away its whole ask balance
+    if ask_asset.amount >= ask_pool.amount {
+        return Err(ContractError::AskAmountExceedsPool {});
+    }
+
+    // Get fee info from the factory
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
+
+    let (offer_amount, spread_amount, commission_amount) = compute_offer_amount(
+        offer_pool.amount,
+        query_token_precision(&deps.querier, offer_pool.info.clone())?,
+        ask_pool.amount,
+        query_token_precision(&deps.querier, ask_pool.info.clone())?,
+        ask_asset.amount,
+        fee_info.total_fee_rate,
+        compute_current_amp(&config, &env)?,
+    )?;
+
+    if offer_amount > offer_asset.amount {
+        return Err(ContractError::MaxOfferAmountAssertion {});
+    }
+
+    let return_asset = Asset {
+        info: ask_pool.info.clone(),
+        amount: ask_asset.amount,
+    };
+
+    let tax_amount = return_asset.compute_tax(&deps.querier)?;
+
+    let receiver = to.unwrap_or_else(|| sender.clone());
+
+    let mut messages: Vec<CosmosMsg> =
+        vec![return_asset.into_msg(&deps.querier, receiver.clone())?];
+
+    // Refund the unused offer assets
+    let refund_amount = offer_asset.amount.checked_sub(offer_amount)?;
+    if !refund_amount.is_zero() {
+        messages.push(
+            Asset {
+                info: offer_pool.info.clone(),
+                amount: refund_amount,
+            }
+            .into_msg(&deps.querier, sender.clone())?,
+        );
+    }
+
+    // Maker fee
+    let mut maker_fee_amount = Uint128::new(0);
+    if let Some(fee_address) = fee_info.fee_address {
+        if let Some(f) = calculate_maker_fee(
+            ask_pool.info.clone(),
+            commission_amount,
+            fee_info.maker_fee_rate,
+        ) {
+            messages.push(f.clone().into_msg(&deps.querier, fee_address)?);
+            maker_fee_amount = f.amount;
+        }
+    }
+
+    // Accumulate prices for the assets in the pool
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
+        env.clone(),
+        &config,
+        pools[0].amount,
+        query_token_precision(&deps.querier, pools[0].info.clone())?,
+        pools[1].amount,
+        query_token_precision(&deps.querier, pools[1].info.clone())?,
+    )? {
+        store_observation(
+            deps.storage,
+            config.block_time_last,
+            block_time,
+            price0_cumulative_new.wrapping_sub(config.price0_cumulative_last),
+            price1_cumulative_new.wrapping_sub(config.price1_cumulative_last),
+        )?;
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    let reserves_after = reserves_after_swap(
+        &pools,
+        &offer_asset.info,
+        offer_amount,
+        ask_asset.amount + maker_fee_amount,
+    )?;
+    RESERVES.save(deps.storage, &reserves_after)?;
+    record_price_change(
+        deps.storage,
+        &deps.querier,
+        &env,
+        &config,
+        &pools,
+        reserves_after,
+    )?;
+
+    record_swap(
+        deps.storage,
+        env.block.time.seconds(),
+        &Asset {
+            info: offer_pool.info.clone(),
+            amount: offer_amount,
+        },
+        &ask_asset,
+        commission_amount,
+        maker_fee_amount,
+    )?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "swap_exact_out")
+        .add_attribute("sender", sender.as_str())
+        .add_attribute("receiver", receiver.as_str())
+        .add_attribute("offer_asset", offer_asset.info.to_string())
+        .add_attribute("ask_asset", ask_pool.info.to_string())
+        .add_attribute("offer_amount", offer_amount.to_string())
+        .add_attribute("refund_amount", refund_amount.to_string())
+        .add_attribute("return_amount", ask_asset.amount.to_string())
+        .add_attribute("tax_amount", tax_amount.to_string())
+        .add_attribute("spread_amount", spread_amount.to_string())
+        .add_attribute("commission_amount", commission_amount.to_string())
+        .add_attribute("maker_fee_amount", maker_fee_amount.to_string()))
+}
+
+/// ## Description
+/// Lends a pool asset to a callback contract and executes the callback contract with a [`FlashLoanCallbackMsg`].
+/// The loan is checked in [`repay_flash_loan`] once the callback contract returns.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified attributes
+/// if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **asset** is an object of type [`Asset`]. This is the asset and the amount to borrow.
+///
+/// * **callback_contract** is an object of type [`String`]. This is the contract that receives the borrowed assets.
+///
+/// * **msg** is an object of type [`Binary`]. This is the message forwarded to the callback contract.
+pub fn flash_loan(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    asset: Asset,
+    callback_contract: String,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    if is_paused(deps.storage)? {
+        return Err(ContractError::Paused {});
+    }
+
+    asset.info.check(deps.api)?;
+    if asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let callback_contract = addr_validate_to_lower(deps.api, &callback_contract)?;
+
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
+    let balances: [Asset; 2] = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address.clone())?;
+
+    if !pools.iter().any(|pool| pool.info.equal(&asset.info)) {
+        return Err(ContractError::AssetMismatch {});
+    }
+
+    // Get fee info from the factory
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
+
+    let fee_amount = compute_flash_loan_fee(asset.amount, fee_info.flash_loan_fee_rate);
+
+    // Accumulate prices for the assets in the pool
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
+        env,
+        &config,
+        pools[0].amount,
+        query_token_precision(&deps.querier, pools[0].info.clone())?,
+        pools[1].amount,
+        query_token_precision(&deps.querier, pools[1].info.clone())?,
+    )? {
+        store_observation(
+            deps.storage,
+            config.block_time_last,
+            block_time,
+            price0_cumulative_new.wrapping_sub(config.price0_cumulative_last),
+            price1_cumulative_new.wrapping_sub(config.price1_cumulative_last),
+        )?;
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    FLASH_LOAN.save(
+        deps.storage,
+        &FlashLoanState {
+            pools,
+            balances: [balances[0].amount, balances[1].amount],
+            asset: asset.clone(),
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_message(
+            asset
+                .clone()
+                .into_msg(&deps.querier, callback_contract.clone())?,
+        )
+        .add_submessage(SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: callback_contract.to_string(),
+                msg: to_binary(&FlashLoanCallbackMsg::FlashLoanCallback {
+                    sender: info.sender.to_string(),
+                    asset: asset.clone(),
+                    fee_amount,
+                    msg,
+                })?,
+                funds: vec![],
+            },
+            FLASH_LOAN_REPLY_ID,
+        ))
+        .add_attribute("action", "flash_loan")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("callback_contract", callback_contract.as_str())
+        .add_attribute("asset", asset.to_string())
+        .add_attribute("fee_amount", fee_amount.to_string()))
+}
+
+/// ## Description
+/// Sends the pair balances that exceed the pool reserves (e.g. tokens that were transferred to the pair directly)
+/// to the specified recipient. Returns a [`ContractError`] on failure, otherwise returns a [`Response`]
+/// with the specified attributes if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **to** is an object of type [`String`]. This is the recipient of the excess balances.
+pub fn skim(deps: DepsMut, env: Env, to: String) -> Result<Response, ContractError> {
+    let to = addr_validate_to_lower(deps.api, &to)?;
+
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
+    let balances: [Asset; 2] = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut skimmed: Vec<Asset> = vec![];
+    for (pool, balance) in pools.iter().zip(balances.iter()) {
+        let amount = balance.amount.checked_sub(pool.amount).unwrap_or_default();
+        if !amount.is_zero() {
+            let asset = Asset {
+                info: pool.info.clone(),
+                amount,
+            };
+            messages.push(asset.clone().into_msg(&deps.querier, to.clone())?);
+            skimmed.push(asset);
+        }
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "skim")
+        .add_attribute("to", to.as_str())
+        .add_attribute("assets", format_assets(&skimmed)))
+}
+
+/// ## Description
+/// Sets the pool reserves to the current pair balances. The cumulative prices are accumulated
+/// with the previous reserves first. Returns a [`ContractError`] on failure, otherwise returns a [`Response`]
+/// with the specified attributes if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+pub fn sync(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    if is_paused(deps.storage)? {
+        return Err(ContractError::Paused {});
+    }
+
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
+    let balances: [Asset; 2] = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address.clone())?;
+
+    // Accumulate prices for the assets in the pool
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
+        env.clone(),
+        &config,
+        pools[0].amount,
+        query_token_precision(&deps.querier, pools[0].info.clone())?,
+        pools[1].amount,
+        query_token_precision(&deps.querier, pools[1].info.clone())?,
+    )? {
+        store_observation(
+            deps.storage,
+            config.block_time_last,
+            block_time,
+            price0_cumulative_new.wrapping_sub(config.price0_cumulative_last),
+            price1_cumulative_new.wrapping_sub(config.price1_cumulative_last),
+        )?;
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    let reserves_after = [balances[0].amount, balances[1].amount];
+    RESERVES.save(deps.storage, &reserves_after)?;
+    record_price_change(
+        deps.storage,
+        &deps.querier,
+        &env,
+        &config,
+        &pools,
+        reserves_after,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "sync")
+        .add_attribute("reserves", format_assets(&balances)))
+}
+
+/// ## Description
+/// Checks that a flash loan was repaid after the callback contract was executed and sends the Maker fee.
+/// The fee is charged on every asset that came back to the pool, so the loan may also be repaid
+/// in the other pool asset (a flash swap) as long as the stableswap invariant without fees doesn't decrease.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified attributes
+/// if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+pub fn repay_flash_loan(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let flash_loan = FLASH_LOAN.load(deps.storage)?;
+    FLASH_LOAN.remove(deps.storage);
+
+    let pools: [Asset; 2] = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address.clone())?;
+
+    // Get fee info from the factory
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
+
+    let mut fees: Vec<Asset> = vec![];
+    let mut reserves = [Uint128::zero(); 2];
+    let mut balances = [Uint128::zero(); 2];
+    for (i, pool) in pools.iter().enumerate() {
+        let mut reserve = flash_loan.pools[i].amount;
+        let mut balance_after_loan = flash_loan.balances[i];
+        if pool.info.equal(&flash_loan.asset.info) {
+            reserve = reserve.checked_sub(flash_loan.asset.amount)?;
+            balance_after_loan = balance_after_loan.checked_sub(flash_loan.asset.amount)?;
+        }
+
+        // The flash loan fee is charged on everything that was sent back to the pool
+        let amount_in = pool
+            .amount
+            .checked_sub(balance_after_loan)
+            .unwrap_or_default();
+        let fee_amount = mul_ceil(amount_in, fee_info.flash_loan_fee_rate);
+
+        reserves[i] = reserve.checked_add(amount_in)?;
+        balances[i] = reserves[i].checked_sub(fee_amount)?;
+        if !fee_amount.is_zero() {
+            fees.push(Asset {
+                info: pool.info.clone(),
+                amount: fee_amount,
+            });
+        }
+    }
+
+    // If some pool balance is below its value before the loan, the pool invariant must not decrease
+    if balances
+        .iter()
+        .zip(flash_loan.pools.iter())
+        .any(|(balance, pool)| *balance < pool.amount)
+    {
+        let token_precision_0 = query_token_precision(&deps.querier, pools[0].info.clone())?;
+        let token_precision_1 = query_token_precision(&deps.querier, pools[1].info.clone())?;
+        let greater_precision = token_precision_0.max(token_precision_1);
+        let leverage = compute_current_amp(&config, &env)?
+            .checked_mul(u64::from(N_COINS))
+            .unwrap();
+
+        let d_before = compute_d(
+            leverage,
+            adjust_precision(
+                flash_loan.pools[0].amount,
+                token_precision_0,
+                greater_precision,
+            )?
+            .u128(),
+            adjust_precision(
+                flash_loan.pools[1].amount,
+                token_precision_1,
+                greater_precision,
+            )?
+            .u128(),
+        )
+        .unwrap();
+        let d_after = compute_d(
+            leverage,
+            adjust_precision(balances[0], token_precision_0, greater_precision)?.u128(),
+            adjust_precision(balances[1], token_precision_1, greater_precision)?.u128(),
+        )
+        .unwrap();
+
+        if d_after < d_before {
+            return Err(ContractError::FlashLoanNotRepaid {});
+        }
+    }
+
+    // Maker fee
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut maker_fees: Vec<Asset> = vec![];
+    if let Some(fee_address) = fee_info.fee_address {
+        for fee in fees.iter() {
+            if let Some(f) =
+                calculate_maker_fee(fee.info.clone(), fee.amount, fee_info.maker_fee_rate)
+            {
+                messages.push(f.clone().into_msg(&deps.querier, fee_address.clone())?);
+                maker_fees.push(f);
+            }
+        }
+    }
+
+    // The Maker fee leaves the pool while the rest of the flash loan fee stays with the LPs
+    for (reserve, pool) in reserves.iter_mut().zip(pools.iter()) {
+        for maker_fee in maker_fees.iter() {
+            if maker_fee.info.equal(&pool.info) {
+                *reserve = reserve.checked_sub(maker_fee.amount)?;
+            }
+        }
+    }
+    RESERVES.save(deps.storage, &reserves)?;
+    record_price_change(
+        deps.storage,
+        &deps.querier,
+        &env,
+        &config,
+        &flash_loan.pools,
+        reserves,
+    )?;
+
+    for fee in fees.iter() {
+        let maker_fee_amount = maker_fees
+            .iter()
+            .find(|maker_fee| maker_fee.info.equal(&fee.info))
+            .map(|maker_fee| maker_fee.amount)
+            .unwrap_or_default();
+        record_fee(
+            deps.storage,
+            env.block.time.seconds(),
+            &fee.info,
+            fee.amount,
+            maker_fee_amount,
+        )?;
+    }
+
     Ok(Response::new()
-        .add_messages(
-            // 1. send collateral token from the contract to a user
-            // 2. send inactive commission to collector
-            messages,
-        )
-        .add_attribute("action", "swap")
-        .add_attribute("sender", sender.as_str())
-        .add_attribute("receiver", receiver.as_str())
-        .add_attribute("offer_asset", offer_asset.info.to_string())
-        .add_attribute("ask_asset", ask_pool.info.to_string())
-        .add_attribute("offer_amount", offer_amount.to_string())
-        .add_attribute("return_amount", return_amount.to_string())
-        .add_attribute("tax_amount", tax_amount.to_string())
-        .add_attribute("spread_amount", spread_amount.to_string())
-        .add_attribute("commission_amount", commission_amount.to_string())
-        .add_attribute("maker_fee_amount", maker_fee_amount.to_string()))
+        .add_messages(messages)
+        .add_attribute("action", "repay_flash_loan")
+        .add_attribute("asset", flash_loan.asset.to_string())
+        .add_attribute("fees", format_assets(&fees))
+        .add_attribute("maker_fees", format_assets(&maker_fees)))
+}
+
+/// ## Description
+/// Returns the fee that has to be paid on top of a flash loan of `amount` tokens if it is repaid in the borrowed asset.
+/// The fee is charged on the whole repaid amount, so the result is `amount / (1 - flash_loan_fee_rate) - amount` rounded up.
+/// ## Params
+/// * **amount** is an object of type [`Uint128`]. This is the amount of tokens to borrow.
+///
+/// * **flash_loan_fee_rate** is an object of type [`Decimal`]. This is the flash loan fee rate.
+fn compute_flash_loan_fee(amount: Uint128, flash_loan_fee_rate: Decimal) -> Uint128 {
+    let denominator = U256::from(flash_loan_fee_rate.denominator());
+    let remainder = denominator - U256::from(flash_loan_fee_rate.numerator());
+    let repay_amount =
+        (U256::from(amount.u128()) * denominator + remainder - U256::one()) / remainder;
+
+    Uint128::new(repay_amount.as_u128()) - amount
+}
+
+/// ## Description
+/// Returns `amount * rate` rounded up.
+/// ## Params
+/// * **amount** is an object of type [`Uint128`].
+///
+/// * **rate** is an object of type [`Decimal`].
+fn mul_ceil(amount: Uint128, rate: Decimal) -> Uint128 {
+    let denominator = U256::from(rate.denominator());
+    let result = (U256::from(amount.u128()) * U256::from(rate.numerator()) + denominator
+        - U256::one())
+        / denominator;
+
+    Uint128::new(result.as_u128())
+}
+
+/// ## Description
+/// Returns a comma separated list of assets to be used in attributes.
+/// ## Params
+/// * **assets** is a slice of [`Asset`] objects.
+fn format_assets(assets: &[Asset]) -> String {
+    assets
+        .iter()
+        .map(|asset| asset.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// ## Description
+/// Returns the information about the pool asset that has to be offered in order to receive `ask_asset_info`.
+/// Returns a [`ContractError`] if `ask_asset_info` does not belong to the pair.
+/// ## Params
+/// * **config** is an object of type [`Config`].
+///
+/// * **ask_asset_info** is an object of type [`AssetInfo`]. This is the asset to swap to.
+fn get_offer_asset_info(
+    config: &Config,
+    ask_asset_info: &AssetInfo,
+) -> Result<AssetInfo, ContractError> {
+    let asset_infos = &config.pair_info.asset_infos;
+    if ask_asset_info.equal(&asset_infos[0]) {
+        Ok(asset_infos[1].clone())
+    } else if ask_asset_info.equal(&asset_infos[1]) {
+        Ok(asset_infos[0].clone())
+    } else {
+        Err(ContractError::AssetMismatch {})
+    }
 }
 
 /// ## Description
@@ -1008,9 +2280,35 @@ pub fn calculate_maker_fee(
 /// * **QueryMsg::CumulativePrices {}** Returns information about cumulative prices for the assets in the
 /// pool using a [`CumulativePricesResponse`] object.
 ///
+/// * **QueryMsg::Observe { seconds_ago }** Returns the cumulative prices of the pool at the specified
+/// points in time using an [`ObserveResponse`] object.
+///
 /// * **QueryMsg::Config {}** Returns the configuration for the pair contract using a [`ConfigResponse`] object.
 ///
 /// * **QueryMsg::PendingReward {}** Returns the amount of bLUNA pending rewards for a specific address using an [`Asset`] object.
+///
+/// * **QueryMsg::Status {}** Returns the circuit breaker status of the pair using a [`StatusResponse`] object.
+///
+/// * **QueryMsg::SimulateWithdrawOneAsset { lp_amount, ask_asset_info }** Returns the result of a single asset
+/// withdrawal simulation using a [`WithdrawOneAssetSimulationResponse`] object.
+///
+/// * **QueryMsg::SimulateProvide { assets }** Returns the amount of LP tokens minted for a deposit and
+/// the imbalance fees charged on it using a [`SimulateProvideResponse`] object.
+///
+/// * **QueryMsg::SimulateWithdraw { lp_amount, ask_asset_info }** Returns the assets received for burning
+/// LP tokens using a [`SimulateWithdrawResponse`] object.
+///
+/// * **QueryMsg::VirtualPrice {}** Returns the pool invariant divided by the LP token supply using a
+/// [`VirtualPriceResponse`] object.
+///
+/// * **QueryMsg::PoolInvariant {}** Returns the current pool invariant and amplification using a
+/// [`PoolInvariantResponse`] object.
+///
+/// * **QueryMsg::Stats {}** Returns the cumulative swap volume and commissions of the pair using
+/// a [`astroport::stats::StatsResponse`] object.
+///
+/// * **QueryMsg::DailyStats { start_after, limit }** Returns the swap volume and commissions of the pair
+/// by day using a [`astroport::stats::DailyStatsResponse`] object.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -1023,9 +2321,38 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::ReverseSimulation { ask_asset } => {
             to_binary(&query_reverse_simulation(deps, env, ask_asset)?)
         }
-        QueryMsg::CumulativePrices {} => to_binary(&query_cumulative_prices(deps, env)?),
+        QueryMsg::CumulativePrices {} => to_binary(&query_cumulative_prices(deps, env.clone())?),
+        QueryMsg::Observe { seconds_ago } => to_binary(&query_observe(deps, env, seconds_ago)?),
         QueryMsg::Config {} => to_binary(&query_config(deps, env)?),
         QueryMsg::PendingReward { user } => to_binary(&query_pending_reward(deps, env, user)?),
+        QueryMsg::Status {} => to_binary(&query_status(deps.storage)?),
+        QueryMsg::SimulateWithdrawOneAsset {
+            lp_amount,
+            ask_asset_info,
+        } => to_binary(&query_simulate_withdraw_one_asset(
+            deps,
+            env,
+            lp_amount,
+            ask_asset_info,
+        )?),
+        QueryMsg::SimulateProvide { assets } => {
+            to_binary(&query_simulate_provide(deps, env, assets)?)
+        }
+        QueryMsg::SimulateWithdraw {
+            lp_amount,
+            ask_asset_info,
+        } => to_binary(&query_simulate_withdraw(
+            deps,
+            env,
+            lp_amount,
+            ask_asset_info,
+        )?),
+        QueryMsg::VirtualPrice {} => to_binary(&query_virtual_price(deps, env)?),
+        QueryMsg::PoolInvariant {} => to_binary(&query_pool_invariant(deps, env)?),
+        QueryMsg::Stats {} => to_binary(&query_stats(deps.storage)?),
+        QueryMsg::DailyStats { start_after, limit } => {
+            to_binary(&query_daily_stats(deps.storage, start_after, limit)?)
+        }
     }
 }
 
@@ -1078,9 +2405,7 @@ pub fn query_share(deps: Deps, amount: Uint128) -> StdResult<[Asset; 2]> {
 /// * **offer_asset** is an object of type [`Asset`]. This is the asset to swap as well as an amount of the said asset.
 pub fn query_simulation(deps: Deps, env: Env, offer_asset: Asset) -> StdResult<SimulationResponse> {
     let config: Config = CONFIG.load(deps.storage)?;
-    let contract_addr = config.pair_info.contract_addr.clone();
-
-    let pools: [Asset; 2] = config.pair_info.query_pools(&deps.querier, contract_addr)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
 
     let offer_pool: Asset;
     let ask_pool: Asset;
@@ -1097,10 +2422,10 @@ pub fn query_simulation(deps: Deps, env: Env, offer_asset: Asset) -> StdResult<S
     }
 
     // Get fee info from factory
-    let fee_info = query_fee_info(
+    let fee_info = query_pair_fee_info(
         &deps.querier,
         config.factory_addr.clone(),
-        config.pair_info.pair_type.clone(),
+        config.pair_info.contract_addr.clone(),
     )?;
 
     let (return_amount, spread_amount, commission_amount) = compute_swap(
@@ -1117,6 +2442,7 @@ pub fn query_simulation(deps: Deps, env: Env, offer_asset: Asset) -> StdResult<S
         return_amount,
         spread_amount,
         commission_amount,
+        total_fee_rate: fee_info.total_fee_rate,
     })
 }
 
@@ -1133,9 +2459,7 @@ pub fn query_reverse_simulation(
     ask_asset: Asset,
 ) -> StdResult<ReverseSimulationResponse> {
     let config: Config = CONFIG.load(deps.storage)?;
-    let contract_addr = config.pair_info.contract_addr.clone();
-
-    let pools: [Asset; 2] = config.pair_info.query_pools(&deps.querier, contract_addr)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
 
     let offer_pool: Asset;
     let ask_pool: Asset;
@@ -1152,10 +2476,10 @@ pub fn query_reverse_simulation(
     }
 
     // Get fee info from the factory
-    let fee_info = query_fee_info(
+    let fee_info = query_pair_fee_info(
         &deps.querier,
         config.factory_addr.clone(),
-        config.pair_info.pair_type.clone(),
+        config.pair_info.contract_addr.clone(),
     )?;
 
     let (offer_amount, spread_amount, commission_amount) = compute_offer_amount(
@@ -1175,6 +2499,248 @@ pub fn query_reverse_simulation(
     })
 }
 
+/// ## Description
+/// Returns the result of a single asset withdrawal simulation in a [`WithdrawOneAssetSimulationResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **lp_amount** is an object of type [`Uint128`]. This is the amount of LP tokens to burn.
+///
+/// * **ask_asset_info** is an object of type [`AssetInfo`]. This is the asset to receive.
+pub fn query_simulate_withdraw_one_asset(
+    deps: Deps,
+    env: Env,
+    lp_amount: Uint128,
+    ask_asset_info: AssetInfo,
+) -> StdResult<WithdrawOneAssetSimulationResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps, config.clone())?;
+    let ask_index = pools
+        .iter()
+        .position(|pool| pool.info.equal(&ask_asset_info))
+        .ok_or_else(|| StdError::generic_err("Given ask asset doesn't belong to pairs"))?;
+
+    // Get fee info from the factory
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
+
+    let (return_amount, commission_amount) = compute_withdraw_one_asset(
+        &deps.querier,
+        &env,
+        &config,
+        &pools,
+        lp_amount,
+        total_share,
+        ask_index,
+        fee_info.total_fee_rate,
+    )?;
+
+    Ok(WithdrawOneAssetSimulationResponse {
+        return_amount,
+        commission_amount,
+    })
+}
+
+/// ## Description
+/// Returns the amount of LP tokens that [`provide_liquidity`] mints for a deposit together with the imbalance fees
+/// charged on it in a [`SimulateProvideResponse`] object. The slippage tolerance is not checked.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **assets** is an array with two objects of type [`Asset`]. These are the assets to deposit.
+pub fn query_simulate_provide(
+    deps: Deps,
+    env: Env,
+    assets: [Asset; 2],
+) -> StdResult<SimulateProvideResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps, config.clone())?;
+    let deposits =
+        get_deposits(&assets, &pools).map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    let token_precisions = [
+        query_token_precision(&deps.querier, pools[0].info.clone())?,
+        query_token_precision(&deps.querier, pools[1].info.clone())?,
+    ];
+
+    // Get fee info from the factory
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
+
+    let (share, imbalance_fees) = compute_share(
+        &deps.querier,
+        &env,
+        &config,
+        &pools,
+        &deposits,
+        token_precisions,
+        total_share,
+        fee_info.total_fee_rate,
+    )
+    .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    Ok(SimulateProvideResponse {
+        share,
+        imbalance_fees: pools
+            .iter()
+            .zip(imbalance_fees.iter())
+            .map(|(pool, fee)| Asset {
+                info: pool.info.clone(),
+                amount: *fee,
+            })
+            .collect(),
+    })
+}
+
+/// ## Description
+/// Returns the assets that [`withdraw_liquidity`] or [`withdraw_liquidity_one_asset`] return for burning
+/// LP tokens in a [`SimulateWithdrawResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **lp_amount** is an object of type [`Uint128`]. This is the amount of LP tokens to burn.
+///
+/// * **ask_asset_info** is an [`Option`] field of type [`AssetInfo`]. The withdrawal is simulated in this asset if it is set.
+pub fn query_simulate_withdraw(
+    deps: Deps,
+    env: Env,
+    lp_amount: Uint128,
+    ask_asset_info: Option<AssetInfo>,
+) -> StdResult<SimulateWithdrawResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps, config.clone())?;
+
+    match ask_asset_info {
+        None => Ok(SimulateWithdrawResponse {
+            assets: get_share_in_assets(&pools, lp_amount, total_share).to_vec(),
+            commission_amount: Uint128::zero(),
+        }),
+        Some(ask_asset_info) => {
+            let ask_index = pools
+                .iter()
+                .position(|pool| pool.info.equal(&ask_asset_info))
+                .ok_or_else(|| StdError::generic_err("Given ask asset doesn't belong to pairs"))?;
+
+            // Get fee info from the factory
+            let fee_info = query_pair_fee_info(
+                &deps.querier,
+                config.factory_addr.clone(),
+                config.pair_info.contract_addr.clone(),
+            )?;
+
+            let (return_amount, commission_amount) = compute_withdraw_one_asset(
+                &deps.querier,
+                &env,
+                &config,
+                &pools,
+                lp_amount,
+                total_share,
+                ask_index,
+                fee_info.total_fee_rate,
+            )?;
+
+            Ok(SimulateWithdrawResponse {
+                assets: vec![Asset {
+                    info: ask_asset_info,
+                    amount: return_amount,
+                }],
+                commission_amount,
+            })
+        }
+    }
+}
+
+/// ## Description
+/// Returns the pool invariant divided by the LP token supply in a [`VirtualPriceResponse`] object.
+/// The invariant only grows with fees, so unlike the pool balances it can't be moved by a large swap.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+pub fn query_virtual_price(deps: Deps, env: Env) -> StdResult<VirtualPriceResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps, config.clone())?;
+    if total_share.is_zero() {
+        return Err(StdError::generic_err("The pool has no liquidity"));
+    }
+
+    let (d, _, greater_precision) = compute_invariant(deps, &env, &config, &pools)?;
+    let liquidity_token_precision = query_token_precision(
+        &deps.querier,
+        AssetInfo::Token {
+            contract_addr: config.pair_info.liquidity_token,
+        },
+    )?;
+
+    Ok(VirtualPriceResponse {
+        virtual_price: Decimal::from_ratio(
+            adjust_precision(d, greater_precision, liquidity_token_precision)?,
+            total_share,
+        ),
+    })
+}
+
+/// ## Description
+/// Returns the current pool invariant and amplification in a [`PoolInvariantResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+pub fn query_pool_invariant(deps: Deps, env: Env) -> StdResult<PoolInvariantResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, _) = pool_info(deps, config.clone())?;
+    let (d, amp, _) = compute_invariant(deps, &env, &config, &pools)?;
+
+    Ok(PoolInvariantResponse {
+        d,
+        amp: Decimal::from_ratio(amp, AMP_PRECISION),
+    })
+}
+
+/// ## Description
+/// Returns the pool invariant D expressed with the greater precision of the two pool assets,
+/// the current amplification and that precision.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **pools** is an array with two objects of type [`Asset`]. These are the assets available in the pool.
+fn compute_invariant(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    pools: &[Asset; 2],
+) -> StdResult<(Uint128, u64, u8)> {
+    let token_precision_0 = query_token_precision(&deps.querier, pools[0].info.clone())?;
+    let token_precision_1 = query_token_precision(&deps.querier, pools[1].info.clone())?;
+    let greater_precision = token_precision_0.max(token_precision_1);
+
+    let amp = compute_current_amp(config, env)?;
+    let d = compute_d(
+        amp.checked_mul(u64::from(N_COINS)).unwrap(),
+        adjust_precision(pools[0].amount, token_precision_0, greater_precision)?.u128(),
+        adjust_precision(pools[1].amount, token_precision_1, greater_precision)?.u128(),
+    )
+    .ok_or_else(|| StdError::generic_err("Failed to compute the pool invariant"))?;
+
+    Ok((Uint128::new(d), amp, greater_precision))
+}
+
 /// ## Description
 /// Returns information about cumulative prices for the assets in the pool using a [`CumulativePricesResponse`] object.
 /// ## Params
@@ -1210,6 +2776,44 @@ pub fn query_cumulative_prices(deps: Deps, env: Env) -> StdResult<CumulativePric
     Ok(resp)
 }
 
+/// ## Description
+/// Returns the cumulative prices of the pair `seconds_ago` seconds before the current block time
+/// using an [`ObserveResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **seconds_ago** is a vector of [`u64`] time offsets from the current block time.
+pub fn query_observe(deps: Deps, env: Env, seconds_ago: Vec<u64>) -> StdResult<ObserveResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (assets, _) = pool_info(deps, config.clone())?;
+
+    let mut price0_increment = Uint128::zero();
+    let mut price1_increment = Uint128::zero();
+
+    if let Some((price0_cumulative_new, price1_cumulative_new, _)) = accumulate_prices(
+        env.clone(),
+        &config,
+        assets[0].amount,
+        query_token_precision(&deps.querier, assets[0].info.clone())?,
+        assets[1].amount,
+        query_token_precision(&deps.querier, assets[1].info.clone())?,
+    )? {
+        price0_increment = price0_cumulative_new.wrapping_sub(config.price0_cumulative_last);
+        price1_increment = price1_cumulative_new.wrapping_sub(config.price1_cumulative_last);
+    }
+
+    observe(
+        deps.storage,
+        config.block_time_last,
+        env.block.time.seconds(),
+        price0_increment,
+        price1_increment,
+        &seconds_ago,
+    )
+}
+
 /// ## Description
 /// Returns the pair contract configuration in a [`ConfigResponse`] object.
 /// ## Params
@@ -1235,40 +2839,15 @@ pub fn query_config(deps: Deps, env: Env) -> StdResult<ConfigResponse> {
 ///
 /// * **user** is an object of type [`String`]. This is the address for which we query the amount of pending bLUNA rewards to claim.
 pub fn query_pending_reward(deps: Deps, _env: Env, user: String) -> StdResult<Asset> {
-    use cosmwasm_std::Decimal256;
-
-    let user = addr_validate_to_lower(deps.api, &user)?;
-
     let config = CONFIG.load(deps.storage)?;
 
-    let user_share: Uint128 = deps.querier.query_wasm_smart(
-        &config.generator,
-        &GeneratorQueryMsg::Deposit {
-            lp_token: config.pair_info.liquidity_token.to_string(),
-            user: user.to_string(),
-        },
-    )?;
-
-    let global_index = BLUNA_REWARD_GLOBAL_INDEX
-        .may_load(deps.storage)?
-        .unwrap_or_default();
-
-    let user_index_opt = BLUNA_REWARD_USER_INDEXES.may_load(deps.storage, &user)?;
-
-    let user_index = if let Some(user_index) = user_index_opt {
-        user_index
-    } else if user_share.is_zero() {
-        global_index
-    } else {
-        Decimal256::zero()
-    };
-
-    Ok(Asset {
-        info: AssetInfo::NativeToken {
-            denom: "uusd".to_string(),
-        },
-        amount: ((global_index - user_index) * Uint256::from(user_share)).try_into()?,
-    })
+    reward_bearing::query_pending_reward(
+        deps,
+        &BLUNA_REWARD_INDEXES,
+        &reward_bearing_config(&config),
+        &config.pair_info.liquidity_token,
+        user,
+    )
 }
 
 /// ## Description
@@ -1466,31 +3045,98 @@ pub fn assert_max_spread(
 }
 
 /// ## Description
-/// This is an internal function that enforces slippage tolerance for swaps.
+/// This is an internal function that enforces slippage tolerance for swaps.
+/// Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+/// ## Params
+/// * **slippage_tolerance** is an object of type [`Option<Decimal>`]. This is the slippage tolerance to enforce.
+///
+/// * **deposits** are an array of [`Uint128`] type items. These are offer and ask amounts for a swap.
+///
+/// * **pools** are an array of [`Asset`] type items. These are total amounts of assets in the pool.
+fn assert_slippage_tolerance(
+    _slippage_tolerance: &Option<Decimal>,
+    _deposits: &[Uint128; 2],
+    _pools: &[Asset; 2],
+) -> Result<(), ContractError> {
+    //There is no slippage in the stable pool
+    Ok(())
+}
+
+/// ## Description
+/// This is an internal function that checks the withdrawn assets against the minimum amounts
+/// that the LP token holder wants to receive. Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+/// ## Params
+/// * **refund_assets** are an array of [`Asset`] type items. These are the assets that will be withdrawn.
+///
+/// * **min_assets_to_receive** is an [`Option`] field of type [`Vec<Asset>`]. These are the minimum
+/// amounts of assets to receive.
+fn assert_min_assets_to_receive(
+    refund_assets: &[Asset],
+    min_assets_to_receive: Option<Vec<Asset>>,
+) -> Result<(), ContractError> {
+    for min_asset in min_assets_to_receive.unwrap_or_default() {
+        let refund_asset = refund_assets
+            .iter()
+            .find(|asset| asset.info.equal(&min_asset.info))
+            .ok_or(ContractError::AssetMismatch {})?;
+        if refund_asset.amount < min_asset.amount {
+            return Err(ContractError::MinAssetsToReceiveAssertion {});
+        }
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Checks that the deadline of a message has not passed yet.
 /// Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
 /// ## Params
-/// * **slippage_tolerance** is an object of type [`Option<Decimal>`]. This is the slippage tolerance to enforce.
+/// * **env** is an object of type [`Env`].
 ///
-/// * **deposits** are an array of [`Uint128`] type items. These are offer and ask amounts for a swap.
+/// * **deadline** is an [`Option`] field of type [`u64`]. This is the timestamp (in seconds) after which the message fails.
+fn assert_deadline(env: &Env, deadline: Option<u64>) -> Result<(), ContractError> {
+    if let Some(deadline) = deadline {
+        if env.block.time.seconds() > deadline {
+            return Err(ContractError::DeadlineExpired {});
+        }
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Checks that the callback contract address is valid and that the output of an action is not
+/// also sent to a receiver. Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+/// ## Params
+/// * **api** is an object of type [`Api`].
 ///
-/// * **pools** are an array of [`Asset`] type items. These are total amounts of assets in the pool.
-fn assert_slippage_tolerance(
-    _slippage_tolerance: &Option<Decimal>,
-    _deposits: &[Uint128; 2],
-    _pools: &[Asset; 2],
+/// * **callback** is an [`Option`] field of type [`Callback`]. This is the contract that receives the output.
+///
+/// * **has_receiver** is the field of type [`bool`]. Determines whether a receiver was specified as well.
+fn assert_callback(
+    api: &dyn Api,
+    callback: &Option<Callback>,
+    has_receiver: bool,
 ) -> Result<(), ContractError> {
-    //There is no slippage in the stable pool
+    if let Some(callback) = callback {
+        if has_receiver {
+            return Err(ContractError::CallbackWithReceiver {});
+        }
+        addr_validate_to_lower(api, &callback.contract)?;
+    }
+
     Ok(())
 }
 
 /// ## Description
-/// Used for the contract migration. Returns a default object of type [`Response`].
+/// Used for the contract migration. Stable pairs migrating from v1.0.0 get the bLUNA reward holder,
+/// and all pairs start tracking their reserves from their current asset balances.
 /// ## Params
-/// * **_deps** is an object of type [`DepsMut`].
+/// * **deps** is an object of type [`DepsMut`].
 ///
-/// * **_env** is an object of type [`Env`].
+/// * **env** is an object of type [`Env`].
 ///
-/// * **_msg** is an object of type [`MigrateMsg`].
+/// * **msg** is an object of type [`MigrateMsg`].
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
     let contract_version = get_contract_version(deps.storage)?;
@@ -1506,13 +3152,28 @@ pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, Con
                 config.bluna_rewarder = addr_validate_to_lower(deps.api, &msg.bluna_rewarder)?;
                 config.generator = addr_validate_to_lower(deps.api, &msg.generator)?;
                 CONFIG.save(deps.storage, &config)?;
-                response
-                    .messages
-                    .push(get_bluna_reward_holder_instantiating_message(
-                        deps.as_ref(),
-                        &env,
-                        &config.factory_addr,
-                    )?);
+                response.messages.push(reward_holder_instantiate_msg(
+                    &deps.querier,
+                    &env,
+                    &config.factory_addr,
+                    INSTANTIATE_BLUNA_REWARD_HOLDER_REPLY_ID,
+                    "Bluna rewarder",
+                )?);
+
+                let pools: [Asset; 2] = config
+                    .pair_info
+                    .query_pools(&deps.querier, env.contract.address)?;
+                RESERVES.save(deps.storage, &[pools[0].amount, pools[1].amount])?;
+            }
+            _ => return Err(ContractError::MigrationError {}),
+        },
+        "astroport-pair-stable-bluna" => match contract_version.version.as_ref() {
+            "1.0.1" => {
+                let config: Config = CONFIG.load(deps.storage)?;
+                let pools: [Asset; 2] = config
+                    .pair_info
+                    .query_pools(&deps.querier, env.contract.address)?;
+                RESERVES.save(deps.storage, &[pools[0].amount, pools[1].amount])?;
             }
             _ => return Err(ContractError::MigrationError {}),
         },
@@ -1526,6 +3187,28 @@ pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, Con
         .add_attribute("new_contract_version", CONTRACT_VERSION))
 }
 
+/// ## Description
+/// Returns the pool assets with the amounts that are tracked as the pool reserves.
+/// Tokens that were sent to the pair directly are not part of the reserves until [`sync`] is called.
+/// ## Params
+/// * **storage** is an object of type [`Storage`].
+///
+/// * **config** is an object of type [`Config`].
+pub fn load_pools(storage: &dyn Storage, config: &Config) -> StdResult<[Asset; 2]> {
+    let reserves = RESERVES.load(storage)?;
+
+    Ok([
+        Asset {
+            info: config.pair_info.asset_infos[0].clone(),
+            amount: reserves[0],
+        },
+        Asset {
+            info: config.pair_info.asset_infos[1].clone(),
+            amount: reserves[1],
+        },
+    ])
+}
+
 /// ## Description
 /// Returns information about the pool.
 /// ## Params
@@ -1533,8 +3216,7 @@ pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, Con
 ///
 /// * **config** is an object of type [`Config`].
 pub fn pool_info(deps: Deps, config: Config) -> StdResult<([Asset; 2], Uint128)> {
-    let contract_addr = config.pair_info.contract_addr.clone();
-    let pools: [Asset; 2] = config.pair_info.query_pools(&deps.querier, contract_addr)?;
+    let pools: [Asset; 2] = load_pools(deps.storage, &config)?;
     let total_share: Uint128 = query_supply(&deps.querier, config.pair_info.liquidity_token)?;
 
     Ok((pools, total_share))
@@ -1583,6 +3265,193 @@ pub fn update_config(
     Ok(Response::default())
 }
 
+/// ## Description
+/// Stops swaps and liquidity provision in the pair. Withdrawals stay open.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified attributes.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// ## Executor
+/// Only the factory owner or the guardian can execute this.
+pub fn pause(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, config.factory_addr)?;
+
+    if info.sender != factory_config.owner && Some(info.sender) != guardian(deps.storage)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    set_paused(deps.storage, true)?;
+
+    Ok(Response::new().add_attribute("action", "pause"))
+}
+
+/// ## Description
+/// Resumes swaps and liquidity provision in the pair after a pause.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified attributes.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// ## Executor
+/// Only the factory owner can execute this.
+pub fn unpause(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, config.factory_addr)?;
+
+    if info.sender != factory_config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    set_paused(deps.storage, false)?;
+
+    Ok(Response::new().add_attribute("action", "unpause"))
+}
+
+/// ## Description
+/// Updates the guardian and the maximum price change of the pair.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the specified attributes.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **guardian** is an [`Option`] field of type [`String`]. This is the address that can pause the pair.
+///
+/// * **max_price_change_bps** is an [`Option`] field of type [`u16`]. This is the maximum spot price change
+/// within a single block. Operations that exceed it fail.
+///
+/// ## Executor
+/// Only the factory owner can execute this.
+pub fn update_circuit_breaker(
+    deps: DepsMut,
+    info: MessageInfo,
+    guardian: Option<String>,
+    max_price_change_bps: Option<u16>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, config.factory_addr)?;
+
+    if info.sender != factory_config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let guardian = guardian
+        .map(|guardian| addr_validate_to_lower(deps.api, &guardian))
+        .transpose()?;
+    circuit_breaker::update_circuit_breaker(deps.storage, guardian, max_price_change_bps)?;
+
+    Ok(Response::new().add_attribute("action", "update_circuit_breaker"))
+}
+
+/// ## Description
+/// Passes the spot price change caused by an operation to the circuit breaker of the pair.
+/// Returns a [`ContractError`] if the price moved by more than the pair allows within a block.
+/// ## Params
+/// * **storage** is an object of type [`Storage`].
+///
+/// * **querier** is an object of type [`QuerierWrapper`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **pools** is an array with two objects of type [`Asset`]. These are the pool amounts before the operation.
+///
+/// * **reserves_after** is an array with two objects of type [`Uint128`]. These are the pool amounts after the operation.
+fn record_price_change(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    env: &Env,
+    config: &Config,
+    pools: &[Asset],
+    reserves_after: [Uint128; 2],
+) -> Result<(), ContractError> {
+    let precisions = [
+        query_token_precision(querier, pools[0].info.clone())?,
+        query_token_precision(querier, pools[1].info.clone())?,
+    ];
+    let reserves_before = [pools[0].amount, pools[1].amount];
+
+    if !track_price_change(
+        storage,
+        env.block.height,
+        spot_price(config, env, reserves_before, precisions)?,
+        spot_price(config, env, reserves_after, precisions)?,
+    )? {
+        return Err(ContractError::MaxPriceChangeAssertion {});
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Returns the amount of asset 1 received for one unit of asset 0 (ignoring fees) with
+/// [`TWAP_PRECISION`] decimals or zero for an empty pool.
+/// ## Params
+/// * **config** is an object of type [`Config`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **reserves** is an array with two objects of type [`Uint128`]. These are the pool reserves.
+///
+/// * **precisions** is an array with two objects of type [`u8`]. These are the precisions of the pool assets.
+fn spot_price(
+    config: &Config,
+    env: &Env,
+    reserves: [Uint128; 2],
+    precisions: [u8; 2],
+) -> StdResult<Uint128> {
+    let greater_precision = precisions[0].max(precisions[1]).max(TWAP_PRECISION);
+    let x = adjust_precision(reserves[0], precisions[0], greater_precision)?;
+    let y = adjust_precision(reserves[1], precisions[1], greater_precision)?;
+    if x.is_zero() || y.is_zero() {
+        return Ok(Uint128::zero());
+    }
+
+    let price = calc_ask_amount(
+        x.u128(),
+        y.u128(),
+        adjust_precision(Uint128::new(1), 0, greater_precision)?.u128(),
+        compute_current_amp(config, env)?,
+    )
+    .ok_or_else(|| StdError::generic_err("Failed to calculate the spot price"))?;
+
+    adjust_precision(Uint128::new(price), greater_precision, TWAP_PRECISION)
+}
+
+/// ## Description
+/// Returns the pool amounts after a swap.
+/// ## Params
+/// * **pools** is an array with two objects of type [`Asset`]. These are the pool amounts before the swap.
+///
+/// * **offer_asset_info** is an object of type [`AssetInfo`]. This is the asset that was offered.
+///
+/// * **offer_amount** is an object of type [`Uint128`]. This is the amount of offer assets that were added to the pool.
+///
+/// * **ask_amount** is an object of type [`Uint128`]. This is the amount of ask assets that left the pool.
+fn reserves_after_swap(
+    pools: &[Asset],
+    offer_asset_info: &AssetInfo,
+    offer_amount: Uint128,
+    ask_amount: Uint128,
+) -> StdResult<[Uint128; 2]> {
+    if offer_asset_info.equal(&pools[0].info) {
+        Ok([
+            pools[0].amount.checked_add(offer_amount)?,
+            pools[1].amount.checked_sub(ask_amount)?,
+        ])
+    } else {
+        Ok([
+            pools[0].amount.checked_sub(ask_amount)?,
+            pools[1].amount.checked_add(offer_amount)?,
+        ])
+    }
+}
+
 /// ## Description
 /// Start changing the AMP value. Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
 /// ## Params
@@ -1689,100 +3558,25 @@ fn compute_current_amp(config: &Config, env: &Env) -> StdResult<u64> {
 }
 
 /// ## Description
-/// Return a message object that can help claim bLUNA rewards for an account.
-/// Returns an [`ContractError`] on failure, otherwise returns the object
-/// of type [`SubMsg`].
-/// ## Params
-/// * **deps** is an object of type [`Deps`].
-///
-/// * **env** is an object of type [`Env`].
-///
-/// * **factory_addr** is an object of type [`Addr`]. This is the Astroport factory address.
-fn get_bluna_reward_holder_instantiating_message(
-    deps: Deps,
-    env: &Env,
-    factory_addr: &Addr,
-) -> Result<SubMsg, ContractError> {
-    Ok(SubMsg {
-        msg: CosmosMsg::Wasm(WasmMsg::Instantiate {
-            admin: None,
-            code_id: query_factory_config(&deps.querier, factory_addr.clone())?.whitelist_code_id,
-            funds: vec![],
-            label: "Bluna rewarder".to_string(),
-            msg: to_binary(&WhitelistInstantiateMsg {
-                admins: vec![env.contract.address.to_string()],
-                mutable: false,
-            })?,
-        }),
-        id: INSTANTIATE_BLUNA_REWARD_HOLDER_REPLY_ID,
-        gas_limit: None,
-        reply_on: ReplyOn::Success,
-    })
-}
-
-/// ## Description
-/// Returns a vector of messages that are meant to claim bLUNA rewards for a specific user.
-/// Returns a [`ContractError`] on failure, otherwise returns the vector that contains objects
-/// of type [`CosmosMsg`].
+/// Returns the bLUNA rewards parameters in the format used by the shared reward-bearing module.
+/// bLUNA rewards are paid in UST.
 /// ## Params
-/// * **deps** is an object of type [`Deps`].
-///
-/// * **env** is an object of type [`Env`].
-///
-/// * **bluna_rewarder** is an object of type [`str`]. This is the contract that distributes bLUNA rewards.
-///
-/// * **user** is an object of type [`Addr`]. This is the address for which we return messages that are meant to claim rewards.
-///
-/// * **user_share** is an object of type [`Uint128`]. This is the share of the bLUNA rewards that the `user` is entitled to.
-///
-/// * **total_share** is an object of type [`Uint128`]. This is the total amount of bLUNA rewards entitled to bLUNA LPs.
-///
-/// * **receiver** is an object of type [`Option<Addr>`]. This is a custom address that can receive the bLUNA rewards.
-/// If it's not present, the function defaults to the `user`.
-fn get_bluna_reward_handling_messages(
-    deps: Deps,
-    env: &Env,
-    bluna_rewarder: &str,
-    user: Addr,
-    user_share: Uint128,
-    total_share: Uint128,
-    receiver: Option<Addr>,
-) -> Result<Vec<CosmosMsg>, ContractError> {
-    let bluna_reward_holder = BLUNA_REWARD_HOLDER.load(deps.storage)?;
-
-    let reward_balance = astroport::querier::query_balance(
-        &deps.querier,
-        bluna_reward_holder.clone(),
-        "uusd".to_string(),
-    )?;
-
-    Ok(vec![
-        CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: bluna_rewarder.to_string(),
-            msg: to_binary(&anchor_basset::reward::ExecuteMsg::ClaimRewards {
-                recipient: Some(bluna_reward_holder.to_string()),
-            })?,
-            funds: vec![],
-        }),
-        CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: env.contract.address.to_string(),
-            funds: vec![],
-            msg: to_binary(&ExecuteMsg::HandleReward {
-                previous_reward_balance: reward_balance,
-                user,
-                user_share,
-                total_share,
-                receiver,
-            })?,
-        }),
-    ])
+/// * **config** is an object of type [`Config`].
+fn reward_bearing_config(config: &Config) -> RewardBearingConfig {
+    RewardBearingConfig {
+        rewarder: config.bluna_rewarder.clone(),
+        reward_asset: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        generator: config.generator.clone(),
+    }
 }
 
 /// ## Description
 /// Claims bLUNA rewards on behalf of the function caller and sends them to the specified receiver.
 /// Returns A [`ContractError`] on failure, otherwise returns A [`Response`] with the specified attributes if the operation was successful.
 /// ## Params
-/// * **deps** is an object of type [`Deps`].
+/// * **deps** is an object of type [`DepsMut`].
 ///
 /// * **env** is an object of type [`Env`].
 ///
@@ -1795,42 +3589,17 @@ fn claim_reward(
     info: MessageInfo,
     receiver: Option<String>,
 ) -> Result<Response, ContractError> {
-    let receiver = receiver
-        .map(|receiver| addr_validate_to_lower(deps.api, &receiver))
-        .transpose()?;
-
     let config: Config = CONFIG.load(deps.storage)?;
 
-    let user_share: Uint128 = deps.querier.query_wasm_smart(
-        &config.generator,
-        &GeneratorQueryMsg::Deposit {
-            lp_token: config.pair_info.liquidity_token.to_string(),
-            user: info.sender.to_string(),
-        },
-    )?;
-
-    if user_share.is_zero() {
-        return Err(StdError::generic_err("No lp tokens staked to the generator!").into());
-    }
-
-    let pool_info: PoolInfoResponse = deps.querier.query_wasm_smart(
-        &config.generator,
-        &GeneratorQueryMsg::PoolInfo {
-            lp_token: config.pair_info.liquidity_token.to_string(),
-        },
-    )?;
-
-    Ok(
-        Response::new().add_messages(get_bluna_reward_handling_messages(
-            deps.as_ref(),
-            &env,
-            config.bluna_rewarder.as_str(),
-            info.sender,
-            user_share,
-            pool_info.lp_supply,
-            receiver,
-        )?),
-    )
+    Ok(reward_bearing::claim_reward(
+        deps.as_ref(),
+        &env,
+        info,
+        &BLUNA_REWARD_INDEXES,
+        &reward_bearing_config(&config),
+        &config.pair_info.liquidity_token,
+        receiver,
+    )?)
 }
 
 /// ## Description
@@ -1838,7 +3607,7 @@ fn claim_reward(
 /// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the
 /// specified attributes if the operation was successful.
 /// ## Params
-/// * **deps** is an object of type [`Deps`].
+/// * **deps** is an object of type [`DepsMut`].
 ///
 /// * **env** is an object of type [`Env`].
 ///
@@ -1860,23 +3629,16 @@ fn claim_reward_by_generator(
 ) -> Result<Response, ContractError> {
     let config: Config = CONFIG.load(deps.storage)?;
 
-    let user = addr_validate_to_lower(deps.api, &user)?;
-
-    if info.sender != config.generator {
-        return Err(StdError::generic_err("Only the generator can use this method!").into());
-    }
-
-    Ok(
-        Response::new().add_messages(get_bluna_reward_handling_messages(
-            deps.as_ref(),
-            &env,
-            config.bluna_rewarder.as_str(),
-            user,
-            user_share,
-            total_share,
-            None,
-        )?),
-    )
+    Ok(reward_bearing::claim_reward_by_generator(
+        deps.as_ref(),
+        &env,
+        info,
+        &BLUNA_REWARD_INDEXES,
+        &reward_bearing_config(&config),
+        user,
+        user_share,
+        total_share,
+    )?)
 }
 
 /// ## Description
@@ -1911,110 +3673,20 @@ pub fn handle_reward(
     total_share: Uint128,
     receiver: Option<Addr>,
 ) -> Result<Response, ContractError> {
-    use astroport::whitelist::ExecuteMsg;
-
     if info.sender != env.contract.address {
         return Err(ContractError::Unauthorized {});
     }
 
-    let receiver = receiver.unwrap_or_else(|| user.clone());
-
-    let bluna_reward_holder = BLUNA_REWARD_HOLDER.load(deps.storage)?;
-
-    let reward_balance = astroport::querier::query_balance(
-        &deps.querier,
-        bluna_reward_holder.clone(),
-        "uusd".to_string(),
-    )?;
-
-    let bluna_reward_global_index = BLUNA_REWARD_GLOBAL_INDEX
-        .may_load(deps.storage)?
-        .unwrap_or_default();
-    let bluna_reward_user_index = BLUNA_REWARD_USER_INDEXES.may_load(deps.storage, &user)?;
+    let config: Config = CONFIG.load(deps.storage)?;
 
-    let (bluna_reward_global_index, latest_reward_amount, user_reward) = calc_user_reward(
-        reward_balance,
+    Ok(reward_bearing::handle_reward(
+        deps,
+        &BLUNA_REWARD_INDEXES,
+        &reward_bearing_config(&config),
         previous_reward_balance,
+        user,
         user_share,
         total_share,
-        bluna_reward_global_index,
-        bluna_reward_user_index,
-    )?;
-
-    BLUNA_REWARD_GLOBAL_INDEX.save(deps.storage, &bluna_reward_global_index)?;
-    BLUNA_REWARD_USER_INDEXES.save(deps.storage, &user, &bluna_reward_global_index)?;
-
-    let mut response =
-        Response::new().add_attribute("bluna_claimed_reward_to_pool", latest_reward_amount);
-
-    if !user_reward.is_zero() {
-        response.messages.push(SubMsg::new(WasmMsg::Execute {
-            contract_addr: bluna_reward_holder.to_string(),
-            funds: vec![],
-            msg: to_binary(&ExecuteMsg::Execute {
-                msgs: vec![Asset {
-                    info: AssetInfo::NativeToken {
-                        denom: "uusd".to_string(),
-                    },
-                    amount: user_reward,
-                }
-                .into_msg(&deps.querier, receiver.clone())?],
-            })?,
-        }));
-    }
-
-    Ok(response
-        .add_attribute("user", user)
-        .add_attribute("receiver", receiver)
-        .add_attribute("sent_bluna_reward", user_reward))
-}
-
-/// ## Description
-/// Calculate the amount of bLUNA rewards that a user can claim.
-/// Returns a [`ContractError`] on failure, otherwise returns the tuple values
-/// [bluna_reward_global_index, latest_reward_amount and user_reward].
-/// ## Params
-/// * **reward_balance** is an object of type [`Uint128`].
-/// This is the total amount of bLUNA rewards that the bLUNA reward distributor currently holds.
-///
-/// * **previous_reward_balance** is an object of type [`Uint128`].
-/// This is the total amount of bLUNA rewards that the bLUNA reward distributor previously held.
-///
-/// * **user_share** is an object of type [`Uint128`]. This is the portion of bLUNA rewards that the user can claim.
-///
-/// * **total_share** is an object of type [`Uint128`]. This is the total share of bLUNA rewards that go to bLUNA Astroport LPs.
-///
-/// * **bluna_reward_global_index** is an object of type [`Decimal256`].
-/// This is an index tracking how many rewards have been distributed to bLUNA stakers.
-///
-/// * **bluna_reward_user_index** is an object of type [`Option<Decimal256>`].
-/// This is an index tracking how many rewards have been claimed by the `user`.
-pub fn calc_user_reward(
-    reward_balance: Uint128,
-    previous_reward_balance: Uint128,
-    user_share: Uint128,
-    total_share: Uint128,
-    bluna_reward_global_index: cosmwasm_std::Decimal256,
-    bluna_reward_user_index: Option<cosmwasm_std::Decimal256>,
-) -> Result<(cosmwasm_std::Decimal256, Uint128, Uint128), ContractError> {
-    use cosmwasm_std::Decimal256;
-
-    let latest_reward_amount = reward_balance.saturating_sub(previous_reward_balance);
-
-    let bluna_reward_global_index =
-        bluna_reward_global_index + Decimal256::from_ratio(latest_reward_amount, total_share);
-
-    let user_reward: Uint128 = if let Some(bluna_reward_user_index) = bluna_reward_user_index {
-        ((bluna_reward_global_index - bluna_reward_user_index) * Uint256::from(user_share))
-            .try_into()
-            .map_err(|e| ContractError::Std(StdError::from(e)))?
-    } else if !user_share.is_zero() {
-        (bluna_reward_global_index * Uint256::from(user_share))
-            .try_into()
-            .map_err(|e| ContractError::Std(StdError::from(e)))?
-    } else {
-        Uint128::zero()
-    };
-
-    Ok((bluna_reward_global_index, latest_reward_amount, user_reward))
+        receiver,
+    )?)
 }
@@ -1,4 +1,4 @@
-use crate::math::{MAX_AMP, MAX_AMP_CHANGE, MIN_AMP_CHANGING_TIME};
+use astroport::stable_math::{MAX_AMP, MAX_AMP_CHANGE, MIN_AMP_CHANGING_TIME};
 use cosmwasm_std::{OverflowError, StdError};
 use thiserror::Error;
 
@@ -12,6 +12,15 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("The pair is paused")]
+    Paused {},
+
+    #[error("Flash loan was not repaid")]
+    FlashLoanNotRepaid {},
+
+    #[error("A flash loan is in progress")]
+    FlashLoanInProgress {},
+
     #[error("Doubling assets in asset infos")]
     DoublingAssets {},
 
@@ -21,15 +30,30 @@ pub enum ContractError {
     #[error("Insufficient amount of Liquidity")]
     LiquidityAmountTooSmall {},
 
+    #[error("Failed to compute the pool invariant")]
+    InvariantComputationError {},
+
     #[error("Provided spread amount exceeds allowed limit")]
     AllowedSpreadAssertion {},
 
     #[error("Operation exceeds max spread limit")]
     MaxSpreadAssertion {},
 
+    #[error("Operation moves the price by more than the circuit breaker allows within a block")]
+    MaxPriceChangeAssertion {},
+
     #[error("Operation exceeds max splippage tolerance")]
     MaxSlippageAssertion {},
 
+    #[error("Operation exceeds max offer amount")]
+    MaxOfferAmountAssertion {},
+
+    #[error("Ask amount must be less than the ask pool balance")]
+    AskAmountExceedsPool {},
+
+    #[error("Return amount is less than the minimum amount")]
+    MinAmountAssertion {},
+
     #[error("Native token balance mismatch between the argument and the transferred")]
     AssetMismatch {},
 
@@ -62,6 +86,18 @@ pub enum ContractError {
 
     #[error("Contract can't be migrated!")]
     MigrationError {},
+
+    #[error("The amount of LP tokens to receive is less than the minimum amount")]
+    MinLpToReceiveAssertion {},
+
+    #[error("The amount of withdrawn assets is less than the minimum amount")]
+    MinAssetsToReceiveAssertion {},
+
+    #[error("The deadline of the message has passed")]
+    DeadlineExpired {},
+
+    #[error("A callback contract can't be combined with a receiver or auto staking")]
+    CallbackWithReceiver {},
 }
 
 impl From<OverflowError> for ContractError {
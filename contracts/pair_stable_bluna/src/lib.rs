@@ -1,5 +1,4 @@
 pub mod contract;
-pub mod math;
 pub mod state;
 
 pub mod error;
@@ -5,7 +5,7 @@ use cosmwasm_std::{
 };
 use std::collections::HashMap;
 
-use astroport::factory::QueryMsg::{Config, FeeInfo};
+use astroport::factory::QueryMsg::{Config, PairFeeInfo};
 use astroport::factory::{ConfigResponse, FeeInfoResponse};
 use cw20::{BalanceResponse, Cw20QueryMsg, TokenInfoResponse};
 use terra_cosmwasm::{TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute};
@@ -131,11 +131,12 @@ impl WasmMockQuerier {
             QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
                 if contract_addr == "factory" {
                     match from_binary(&msg).unwrap() {
-                        FeeInfo { .. } => SystemResult::Ok(
+                        PairFeeInfo { .. } => SystemResult::Ok(
                             to_binary(&FeeInfoResponse {
                                 fee_address: Some(Addr::unchecked("fee_address")),
                                 total_fee_bps: 30,
                                 maker_fee_bps: 1660,
+                                flash_loan_fee_bps: 9,
                             })
                             .into(),
                         ),
@@ -1,6 +1,7 @@
-use astroport::asset::PairInfo;
-use cosmwasm_std::{Addr, Decimal256, Uint128};
-use cw_storage_plus::{Item, Map};
+use astroport::asset::{Asset, PairInfo};
+use astroport::reward_bearing::RewardIndexes;
+use cosmwasm_std::{Addr, Uint128};
+use cw_storage_plus::Item;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -34,6 +35,29 @@ pub struct Config {
 
 pub const CONFIG: Item<Config> = Item::new("config");
 
-pub const BLUNA_REWARD_HOLDER: Item<Addr> = Item::new("bluna_reward_holder");
-pub const BLUNA_REWARD_GLOBAL_INDEX: Item<Decimal256> = Item::new("bluna_reward_global_index");
-pub const BLUNA_REWARD_USER_INDEXES: Map<&Addr, Decimal256> = Map::new("bluna_reward_user_indexes");
+/// Stores the bLUNA reward accounting. The keys are kept from the time the pair had its own reward logic
+pub const BLUNA_REWARD_INDEXES: RewardIndexes = RewardIndexes::new(
+    "bluna_reward_holder",
+    "bluna_reward_global_index",
+    "bluna_reward_user_indexes",
+);
+
+/// ## Description
+/// This structure stores the state of a flash loan while the callback contract is being executed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FlashLoanState {
+    /// The pool reserves before the loan
+    pub pools: [Asset; 2],
+    /// The pair balances before the loan
+    pub balances: [Uint128; 2],
+    /// The borrowed asset and amount
+    pub asset: Asset,
+}
+
+/// ## Description
+/// Stores the state of the flash loan that is currently in progress
+pub const FLASH_LOAN: Item<FlashLoanState> = Item::new("flash_loan");
+
+/// ## Description
+/// Stores the pool reserves. The pair balances may exceed them when tokens are sent to the pair directly
+pub const RESERVES: Item<[Uint128; 2]> = Item::new("reserves");
@@ -1,25 +1,29 @@
 use crate::contract::{
-    accumulate_prices, assert_max_spread, calc_user_reward, execute, instantiate, query_pair_info,
+    accumulate_prices, assert_max_spread, execute, instantiate, migrate, query, query_pair_info,
     query_pool, query_reverse_simulation, query_share, query_simulation, reply,
 };
 use crate::error::ContractError;
-use crate::math::{calc_ask_amount, calc_offer_amount, AMP_PRECISION};
 use crate::mock_querier::mock_dependencies;
 use crate::response::MsgInstantiateContractResponse;
-use crate::state::Config;
+use crate::state::{Config, RESERVES};
 use astroport::asset::{Asset, AssetInfo, PairInfo};
+use astroport::circuit_breaker::StatusResponse;
 use astroport::pair::{
-    Cw20HookMsg, InstantiateMsg, PoolResponse, ReverseSimulationResponse, SimulationResponse,
-    TWAP_PRECISION,
+    Cw20HookMsg, FlashLoanCallbackMsg, InstantiateMsg, PoolInvariantResponse, PoolResponse,
+    ReverseSimulationResponse, SimulateProvideResponse, SimulationResponse, VirtualPriceResponse,
+    WithdrawOneAssetSimulationResponse, TWAP_PRECISION,
 };
-use astroport::pair_stable_bluna::{ExecuteMsg, StablePoolParams};
+use astroport::pair_stable_bluna::{ExecuteMsg, MigrateMsg, QueryMsg, StablePoolParams};
+use astroport::reward_bearing::calc_user_reward;
+use astroport::stable_math::{calc_ask_amount, calc_offer_amount, AMP_PRECISION};
+use astroport::stats::StatsResponse;
 use astroport::token::InstantiateMsg as TokenInstantiateMsg;
 use astroport::whitelist::InstantiateMsg as WhitelistInstantiateMsg;
 use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    attr, to_binary, Addr, BankMsg, BlockInfo, Coin, ContractResult, CosmosMsg, Decimal,
-    Decimal256, DepsMut, Env, Reply, ReplyOn, StdError, SubMsg, SubMsgExecutionResponse, Timestamp,
-    Uint128, WasmMsg,
+    attr, from_binary, to_binary, Addr, BankMsg, Binary, BlockInfo, Coin, ContractResult,
+    CosmosMsg, Decimal, Decimal256, Deps, DepsMut, Env, Reply, ReplyOn, StdError, SubMsg,
+    SubMsgExecutionResponse, Timestamp, Uint128, WasmMsg,
 };
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
 use protobuf::Message;
@@ -46,6 +50,29 @@ fn store_liquidity_token(deps: DepsMut, msg_id: u64, contract_addr: String) {
     reply(deps, mock_env(), reply_msg.clone()).unwrap();
 }
 
+fn store_reserves(deps: DepsMut, reserves: [Uint128; 2]) {
+    RESERVES.save(deps.storage, &reserves).unwrap();
+}
+
+/// Returns the swap count and the LP and Maker commissions of every asset in the pair statistics
+fn query_commissions(deps: Deps) -> (u64, Vec<(String, u128, u128)>) {
+    let stats: StatsResponse =
+        from_binary(&query(deps, mock_env(), QueryMsg::Stats {}).unwrap()).unwrap();
+    let commissions = stats
+        .assets
+        .iter()
+        .map(|asset| {
+            (
+                asset.info.to_string(),
+                asset.lp_commission.u128(),
+                asset.maker_commission.u128(),
+            )
+        })
+        .collect();
+
+    (stats.swap_count, commissions)
+}
+
 #[test]
 fn proper_initialization() {
     let mut deps = mock_dependencies(&[]);
@@ -216,6 +243,9 @@ fn provide_liquidity() {
         slippage_tolerance: None,
         auto_stake: None,
         receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
     };
 
     let env = mock_env();
@@ -286,6 +316,9 @@ fn provide_liquidity() {
         slippage_tolerance: None,
         auto_stake: None,
         receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
     };
 
     let env = mock_env();
@@ -306,6 +339,228 @@ fn provide_liquidity() {
     }
 }
 
+#[test]
+fn provide_liquidity_imbalance_fee() {
+    let pool_amount = Uint128::new(1000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: pool_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &pool_amount)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                bluna_rewarder: "bluna_rewarder".to_string(),
+                generator: "generator".to_string(),
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env_with_block_time(100);
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [pool_amount, pool_amount]);
+
+    let provide = |deps: DepsMut, uusd_amount: u128, token_amount: u128| {
+        let msg = ExecuteMsg::ProvideLiquidity {
+            assets: [
+                Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    amount: Uint128::new(uusd_amount),
+                },
+                Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: Addr::unchecked("asset0000"),
+                    },
+                    amount: Uint128::new(token_amount),
+                },
+            ],
+            slippage_tolerance: None,
+            auto_stake: None,
+            receiver: None,
+            min_lp_to_receive: None,
+            deadline: None,
+            callback: None,
+        };
+        let info = mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(uusd_amount),
+            }],
+        );
+        execute(deps, mock_env_with_block_time(1000), info, msg).unwrap()
+    };
+
+    // A balanced deposit pays no fee
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: pool_amount + Uint128::new(100000u128),
+        }],
+    )]);
+    let res = provide(deps.as_mut(), 100000, 100000);
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(res.attributes[4], attr("share", "100000"));
+    assert_eq!(
+        res.attributes[5],
+        attr("imbalance_fees", "0uusd, 0asset0000")
+    );
+
+    // An imbalanced deposit of the same value pays the fee on its deviation from a balanced deposit
+    // and sends the maker share to the fee address. The simulation runs before the deposit is sent
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: pool_amount,
+        }],
+    )]);
+    store_reserves(deps.as_mut(), [pool_amount, pool_amount]);
+    let simulation: SimulateProvideResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env_with_block_time(1000),
+            QueryMsg::SimulateProvide {
+                assets: [
+                    Asset {
+                        info: AssetInfo::NativeToken {
+                            denom: "uusd".to_string(),
+                        },
+                        amount: Uint128::new(190000u128),
+                    },
+                    Asset {
+                        info: AssetInfo::Token {
+                            contract_addr: Addr::unchecked("asset0000"),
+                        },
+                        amount: Uint128::new(10000u128),
+                    },
+                ],
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(simulation.share, Uint128::new(99828u128));
+    assert_eq!(
+        format!(
+            "{}, {}",
+            simulation.imbalance_fees[0], simulation.imbalance_fees[1]
+        ),
+        "135uusd, 134asset0000"
+    );
+
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: pool_amount + Uint128::new(190000u128),
+        }],
+    )]);
+    let res = provide(deps.as_mut(), 190000, 10000);
+    assert_eq!(res.messages.len(), 4);
+    assert_eq!(res.attributes[4], attr("share", "99828"));
+    assert_eq!(
+        res.attributes[5],
+        attr("imbalance_fees", "135uusd, 134asset0000")
+    );
+    assert_eq!(res.attributes[6], attr("maker_fees", "22uusd, 22asset0000"));
+
+    // The imbalance fees are added to the statistics without counting as a swap
+    assert_eq!(
+        query_commissions(deps.as_ref()),
+        (
+            0,
+            vec![
+                ("uusd".to_string(), 113, 22),
+                ("asset0000".to_string(), 112, 22)
+            ]
+        )
+    );
+    assert_eq!(
+        res.messages[2].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("fee_address"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(22u128),
+            }],
+        })
+    );
+    assert_eq!(
+        res.messages[3].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("asset0000"),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("fee_address"),
+                amount: Uint128::new(22u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    // An invariant that overflows returns an error instead of aborting the contract
+    store_reserves(deps.as_mut(), [Uint128::new(u128::MAX / 4); 2]);
+    let err = query(
+        deps.as_ref(),
+        mock_env_with_block_time(1000),
+        QueryMsg::SimulateProvide {
+            assets: [
+                Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    amount: Uint128::new(190000u128),
+                },
+                Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: Addr::unchecked("asset0000"),
+                    },
+                    amount: Uint128::new(10000u128),
+                },
+            ],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(ContractError::InvariantComputationError {}.to_string())
+    );
+}
+
 #[test]
 fn withdraw_liquidity() {
     let mut deps = mock_dependencies(&[Coin {
@@ -356,11 +611,20 @@ fn withdraw_liquidity() {
 
     // Store the liquidity token
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(
+        deps.as_mut(),
+        [Uint128::new(100u128), Uint128::new(100u128)],
+    );
 
     // Withdraw liquidity
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: String::from("addr0000"),
-        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {}).unwrap(),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            min_assets_to_receive: None,
+            deadline: None,
+            callback: None,
+        })
+        .unwrap(),
         amount: Uint128::new(100u128),
     });
 
@@ -433,6 +697,150 @@ fn withdraw_liquidity() {
     );
 }
 
+#[test]
+fn withdraw_liquidity_one_asset() {
+    let pool_amount = Uint128::new(1000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: pool_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &pool_amount)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                bluna_rewarder: "bluna_rewarder".to_string(),
+                generator: "generator".to_string(),
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env_with_block_time(100);
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [pool_amount, pool_amount]);
+
+    let ask_asset_info = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let res: WithdrawOneAssetSimulationResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env_with_block_time(1000),
+            QueryMsg::SimulateWithdrawOneAsset {
+                lp_amount: Uint128::new(100000u128),
+                ask_asset_info: ask_asset_info.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    // A balanced pool returns almost twice the proportional share of the ask asset.
+    // The fee is charged only on the amount above the proportional share
+    let proportional_amount = Uint128::new(100000u128);
+    assert!(res.return_amount > Uint128::new(199000u128));
+    assert!(res.return_amount < Uint128::new(200000u128));
+    assert_eq!(
+        res.commission_amount,
+        (res.return_amount + res.commission_amount - proportional_amount)
+            * Decimal::from_ratio(3u128, 1000u128)
+    );
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidityOneAsset {
+            ask_asset_info: ask_asset_info.clone(),
+            min_amount: Some(res.return_amount + Uint128::new(1u128)),
+        })
+        .unwrap(),
+        amount: Uint128::new(100000u128),
+    });
+    let err = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        mock_info("liquidity0000", &[]),
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::MinAmountAssertion {});
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidityOneAsset {
+            ask_asset_info,
+            min_amount: Some(res.return_amount),
+        })
+        .unwrap(),
+        amount: Uint128::new(100000u128),
+    });
+    let res_withdraw = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        mock_info("liquidity0000", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res_withdraw.messages[0],
+        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("addr0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: res.return_amount,
+            }],
+        }))
+    );
+    assert_eq!(
+        res_withdraw.attributes[5],
+        attr("commission_amount", res.commission_amount.to_string())
+    );
+
+    // The commission is added to the statistics without counting as a swap
+    let maker_fee_amount: u128 = res_withdraw.attributes[6].value.parse().unwrap();
+    assert!(maker_fee_amount > 0);
+    assert_eq!(
+        query_commissions(deps.as_ref()),
+        (
+            0,
+            vec![(
+                "uusd".to_string(),
+                res.commission_amount.u128() - maker_fee_amount,
+                maker_fee_amount
+            )]
+        )
+    );
+}
+
 #[test]
 fn try_native_to_token() {
     let total_share = Uint128::new(30000000000u128);
@@ -489,6 +897,7 @@ fn try_native_to_token() {
 
     // Store the liquidity token
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
 
     // Normal swap
     let msg = ExecuteMsg::Swap {
@@ -501,6 +910,8 @@ fn try_native_to_token() {
         belief_price: None,
         max_spread: Some(Decimal::percent(50)),
         to: None,
+        deadline: None,
+        callback: None,
     };
     let env = mock_env_with_block_time(1000);
     let info = mock_info(
@@ -540,6 +951,7 @@ fn try_native_to_token() {
             amount: collateral_pool_amount, /* user deposit must be pre-applied */
         }],
     )]);
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
 
     let simulation_res: SimulationResponse = query_simulation(
         deps.as_ref(),
@@ -650,6 +1062,7 @@ fn try_token_to_native() {
 
     // Store the liquidity token
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
 
     // Unauthorized access; can not execute swap directy for token swap
     let msg = ExecuteMsg::Swap {
@@ -662,6 +1075,8 @@ fn try_token_to_native() {
         belief_price: None,
         max_spread: None,
         to: None,
+        deadline: None,
+        callback: None,
     };
     let env = mock_env_with_block_time(1000);
     let info = mock_info("addr0000", &[]);
@@ -676,6 +1091,8 @@ fn try_token_to_native() {
             belief_price: None,
             max_spread: None,
             to: None,
+            deadline: None,
+            callback: None,
         })
         .unwrap(),
     });
@@ -721,6 +1138,7 @@ fn try_token_to_native() {
             &[(&String::from(MOCK_CONTRACT_ADDR), &(asset_pool_amount))],
         ),
     ]);
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
 
     let simulation_res: SimulationResponse = query_simulation(
         deps.as_ref(),
@@ -813,6 +1231,8 @@ fn try_token_to_native() {
             belief_price: None,
             max_spread: None,
             to: None,
+            deadline: None,
+            callback: None,
         })
         .unwrap(),
     });
@@ -823,59 +1243,827 @@ fn try_token_to_native() {
 }
 
 #[test]
-fn test_max_spread() {
-    assert_max_spread(
-        Some(Decimal::from_ratio(1200u128, 1u128)),
-        Some(Decimal::percent(1)),
-        Uint128::from(1200000000u128),
-        Uint128::from(989999u128),
-        Uint128::zero(),
-    )
-    .unwrap_err();
-
-    assert_max_spread(
-        Some(Decimal::from_ratio(1200u128, 1u128)),
-        Some(Decimal::percent(1)),
-        Uint128::from(1200000000u128),
-        Uint128::from(990000u128),
-        Uint128::zero(),
-    )
-    .unwrap();
-
-    assert_max_spread(
-        None,
-        Some(Decimal::percent(1)),
-        Uint128::zero(),
-        Uint128::from(989999u128),
-        Uint128::from(10001u128),
-    )
-    .unwrap_err();
-
-    assert_max_spread(
-        None,
-        Some(Decimal::percent(1)),
-        Uint128::zero(),
-        Uint128::from(990000u128),
-        Uint128::from(10000u128),
-    )
-    .unwrap();
-}
+fn swap_exact_out_native_to_token() {
+    let total_share = Uint128::new(30000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+    let ask_amount = Uint128::new(1000000000u128);
+    let max_offer_amount = Uint128::new(2000000000u128);
 
-#[test]
-fn test_deduct() {
-    let mut deps = mock_dependencies(&[]);
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
 
-    let tax_rate = Decimal::percent(2);
-    let tax_cap = Uint128::from(1_000_000u128);
     deps.querier.with_tax(
-        Decimal::percent(2),
+        Decimal::zero(),
         &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
     );
 
-    let amount = Uint128::new(1000_000_000u128);
-    let expected_after_amount = std::cmp::max(
-        amount.checked_sub(amount * tax_rate).unwrap(),
-        amount.checked_sub(tax_cap).unwrap(),
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                bluna_rewarder: "bluna_rewarder".to_string(),
+                generator: "generator".to_string(),
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env_with_block_time(100);
+    let info = mock_info("addr0000", &[]);
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+
+    let ask_asset = Asset {
+        info: AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+        amount: ask_amount,
+    };
+
+    // The exact output swap spends the same amount as the reverse simulation returns
+    let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
+        deps.as_ref(),
+        mock_env_with_block_time(1000),
+        ask_asset.clone(),
+    )
+    .unwrap();
+    let expected_offer_amount = reverse_simulation_res.offer_amount;
+    let expected_refund_amount = max_offer_amount - expected_offer_amount;
+
+    // The max offer amount is too small
+    let too_small_offer_amount = expected_offer_amount - Uint128::new(1u128);
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount + too_small_offer_amount, /* user deposit must be pre-applied */
+        }],
+    )]);
+    let msg = ExecuteMsg::SwapExactOut {
+        ask_asset: ask_asset.clone(),
+        max_offer_amount: too_small_offer_amount,
+        to: None,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: too_small_offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::MaxOfferAmountAssertion {});
+
+    // The ask amount can't drain the ask pool
+    let msg = ExecuteMsg::SwapExactOut {
+        ask_asset: Asset {
+            amount: asset_pool_amount,
+            ..ask_asset.clone()
+        },
+        max_offer_amount: too_small_offer_amount,
+        to: None,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: too_small_offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::AskAmountExceedsPool {});
+
+    // Normal exact output swap
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount + max_offer_amount, /* user deposit must be pre-applied */
+        }],
+    )]);
+    let msg = ExecuteMsg::SwapExactOut {
+        ask_asset: ask_asset.clone(),
+        max_offer_amount,
+        to: None,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: max_offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+    assert_eq!(res.attributes[0], attr("action", "swap_exact_out"));
+    assert_eq!(
+        res.attributes[5],
+        attr("offer_amount", expected_offer_amount.to_string())
+    );
+    assert_eq!(
+        res.attributes[6],
+        attr("refund_amount", expected_refund_amount.to_string())
+    );
+    assert_eq!(
+        res.attributes[7],
+        attr("return_amount", ask_amount.to_string())
+    );
+
+    assert_eq!(
+        res.messages[0],
+        SubMsg {
+            msg: WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0000"),
+                    amount: ask_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+            .into(),
+            id: 0,
+            gas_limit: None,
+            reply_on: ReplyOn::Never,
+        },
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg {
+            msg: CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("addr0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: expected_refund_amount,
+                }],
+            }),
+            id: 0,
+            gas_limit: None,
+            reply_on: ReplyOn::Never,
+        },
+    );
+
+    // Asking for an asset that does not belong to the pair
+    let msg = ExecuteMsg::SwapExactOut {
+        ask_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "ukrw".to_string(),
+            },
+            amount: ask_amount,
+        },
+        max_offer_amount,
+        to: None,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: max_offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::AssetMismatch {});
+}
+
+#[test]
+fn swap_exact_out_token_to_native() {
+    let total_share = Uint128::new(20000000000u128);
+    let asset_pool_amount = Uint128::new(30000000000u128);
+    let collateral_pool_amount = Uint128::new(20000000000u128);
+    let ask_amount = Uint128::new(1000000000u128);
+    let max_offer_amount = Uint128::new(2000000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                bluna_rewarder: "bluna_rewarder".to_string(),
+                generator: "generator".to_string(),
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env_with_block_time(100);
+    let info = mock_info("addr0000", &[]);
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+
+    let ask_asset = Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: ask_amount,
+    };
+
+    let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
+        deps.as_ref(),
+        mock_env_with_block_time(1000),
+        ask_asset.clone(),
+    )
+    .unwrap();
+    let expected_offer_amount = reverse_simulation_res.offer_amount;
+    let expected_refund_amount = max_offer_amount - expected_offer_amount;
+
+    // Unauthorized access; can not offer tokens directly
+    let msg = ExecuteMsg::SwapExactOut {
+        ask_asset: ask_asset.clone(),
+        max_offer_amount,
+        to: None,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    // Normal exact output swap
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(asset_pool_amount + max_offer_amount),
+            )],
+        ),
+    ]);
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: max_offer_amount,
+        msg: to_binary(&Cw20HookMsg::SwapExactOut {
+            ask_asset: ask_asset.clone(),
+            to: Some(String::from("addr0001")),
+        })
+        .unwrap(),
+    });
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("asset0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+    assert_eq!(
+        res.attributes[5],
+        attr("offer_amount", expected_offer_amount.to_string())
+    );
+    assert_eq!(
+        res.attributes[6],
+        attr("refund_amount", expected_refund_amount.to_string())
+    );
+    assert_eq!(
+        res.messages[0],
+        SubMsg {
+            msg: CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("addr0001"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: ask_amount,
+                }],
+            }),
+            id: 0,
+            gas_limit: None,
+            reply_on: ReplyOn::Never,
+        },
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg {
+            msg: WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0000"),
+                    amount: expected_refund_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+            .into(),
+            id: 0,
+            gas_limit: None,
+            reply_on: ReplyOn::Never,
+        },
+    );
+
+    // Only pair tokens can be offered
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: max_offer_amount,
+        msg: to_binary(&Cw20HookMsg::SwapExactOut {
+            ask_asset,
+            to: None,
+        })
+        .unwrap(),
+    });
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("liquidity0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+}
+
+#[test]
+fn flash_loan() {
+    let total_share = Uint128::new(20000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+    let loan_amount = Uint128::new(1000000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                bluna_rewarder: "bluna_rewarder".to_string(),
+                generator: "generator".to_string(),
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env_with_block_time(100);
+    let info = mock_info("addr0000", &[]);
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+
+    let loan_asset = Asset {
+        info: AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+        amount: loan_amount,
+    };
+    let flash_loan_msg = ExecuteMsg::FlashLoan {
+        asset: loan_asset.clone(),
+        callback_contract: String::from("borrower"),
+        msg: Binary::from(b"arbitrage".to_vec()),
+    };
+    let repay_reply = Reply {
+        id: 3,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+
+    // The fee is 9 bps of the repaid amount
+    let expected_fee_amount = Uint128::new(900811u128);
+
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env, info, flash_loan_msg.clone()).unwrap();
+    assert_eq!(res.attributes[0], attr("action", "flash_loan"));
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("borrower"),
+                    amount: loan_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::reply_on_success(
+                WasmMsg::Execute {
+                    contract_addr: String::from("borrower"),
+                    msg: to_binary(&FlashLoanCallbackMsg::FlashLoanCallback {
+                        sender: String::from("addr0000"),
+                        asset: loan_asset,
+                        fee_amount: expected_fee_amount,
+                        msg: Binary::from(b"arbitrage".to_vec()),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                },
+                3,
+            ),
+        ]
+    );
+
+    // The pair can't be used while the loan is in progress
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("borrower"),
+        amount: Uint128::new(1000u128),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            min_assets_to_receive: None,
+            deadline: None,
+            callback: None,
+        })
+        .unwrap(),
+    });
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("liquidity0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::FlashLoanInProgress {});
+
+    // The borrower didn't pay the whole fee
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(asset_pool_amount + expected_fee_amount - Uint128::new(1u128)),
+            )],
+        ),
+    ]);
+    let res = reply(deps.as_mut(), mock_env(), repay_reply.clone()).unwrap_err();
+    assert_eq!(res, ContractError::FlashLoanNotRepaid {});
+
+    // The borrower repaid the loan together with the fee
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), env, info, flash_loan_msg.clone()).unwrap();
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(asset_pool_amount + expected_fee_amount),
+            )],
+        ),
+    ]);
+    let res = reply(deps.as_mut(), mock_env(), repay_reply.clone()).unwrap();
+    assert_eq!(res.attributes[0], attr("action", "repay_flash_loan"));
+    assert_eq!(res.attributes[2], attr("fees", "900811asset0000"));
+    assert_eq!(res.attributes[3], attr("maker_fees", "149534asset0000"));
+    // The fee stays in the pool reserves without the Maker fee
+    assert_eq!(
+        RESERVES.load(&deps.storage).unwrap(),
+        [
+            collateral_pool_amount,
+            asset_pool_amount + Uint128::new(900811u128 - 149534u128)
+        ]
+    );
+
+    // The loan is repaid in the other pool asset (a flash swap), but the invariant decreased
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), env, info, flash_loan_msg.clone()).unwrap();
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(asset_pool_amount - loan_amount),
+            )],
+        ),
+    ]);
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount + Uint128::new(900000000u128),
+        }],
+    )]);
+    let res = reply(deps.as_mut(), mock_env(), repay_reply.clone()).unwrap_err();
+    assert_eq!(res, ContractError::FlashLoanNotRepaid {});
+
+    // The flash swap is repaid with enough assets
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount,
+        }],
+    )]);
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), env, info, flash_loan_msg).unwrap();
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(asset_pool_amount - loan_amount),
+            )],
+        ),
+    ]);
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount + Uint128::new(1100000000u128),
+        }],
+    )]);
+    let res = reply(deps.as_mut(), mock_env(), repay_reply).unwrap();
+    assert_eq!(res.attributes[2], attr("fees", "990000uusd"));
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("fee_address"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(164340u128),
+            }],
+        }))]
+    );
+
+    // The flash loan fees are added to the statistics without counting as swaps
+    assert_eq!(
+        query_commissions(deps.as_ref()),
+        (
+            0,
+            vec![
+                ("asset0000".to_string(), 751277, 149534),
+                ("uusd".to_string(), 825660, 164340)
+            ]
+        )
+    );
+}
+
+#[test]
+fn skim_and_sync() {
+    let total_share = Uint128::new(20000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(asset_pool_amount + Uint128::new(700u128)),
+            )],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                bluna_rewarder: "bluna_rewarder".to_string(),
+                generator: "generator".to_string(),
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env_with_block_time(100);
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+
+    // Skim sends the donation to the recipient
+    let msg = ExecuteMsg::Skim {
+        to: String::from("addr0001"),
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(res.attributes[2], attr("assets", "700asset0000"));
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: String::from("asset0000"),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("addr0001"),
+                amount: Uint128::new(700u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+
+    // Sync adds the donation to the pool
+    let msg = ExecuteMsg::Sync {};
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), env, info, msg).unwrap();
+
+    let res: PoolResponse = query_pool(deps.as_ref()).unwrap();
+    assert_eq!(res.assets[0].amount, collateral_pool_amount);
+    assert_eq!(
+        res.assets[1].amount,
+        asset_pool_amount + Uint128::new(700u128)
+    );
+}
+
+#[test]
+fn test_max_spread() {
+    assert_max_spread(
+        Some(Decimal::from_ratio(1200u128, 1u128)),
+        Some(Decimal::percent(1)),
+        Uint128::from(1200000000u128),
+        Uint128::from(989999u128),
+        Uint128::zero(),
+    )
+    .unwrap_err();
+
+    assert_max_spread(
+        Some(Decimal::from_ratio(1200u128, 1u128)),
+        Some(Decimal::percent(1)),
+        Uint128::from(1200000000u128),
+        Uint128::from(990000u128),
+        Uint128::zero(),
+    )
+    .unwrap();
+
+    assert_max_spread(
+        None,
+        Some(Decimal::percent(1)),
+        Uint128::zero(),
+        Uint128::from(989999u128),
+        Uint128::from(10001u128),
+    )
+    .unwrap_err();
+
+    assert_max_spread(
+        None,
+        Some(Decimal::percent(1)),
+        Uint128::zero(),
+        Uint128::from(990000u128),
+        Uint128::from(10000u128),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_deduct() {
+    let mut deps = mock_dependencies(&[]);
+
+    let tax_rate = Decimal::percent(2);
+    let tax_cap = Uint128::from(1_000_000u128);
+    deps.querier.with_tax(
+        Decimal::percent(2),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let amount = Uint128::new(1000_000_000u128);
+    let expected_after_amount = std::cmp::max(
+        amount.checked_sub(amount * tax_rate).unwrap(),
+        amount.checked_sub(tax_cap).unwrap(),
     );
 
     let after_amount = (Asset {
@@ -939,6 +2127,7 @@ fn test_query_pool() {
 
     // Store liquidity token
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [asset_0_amount, asset_1_amount]);
 
     let res: PoolResponse = query_pool(deps.as_ref()).unwrap();
 
@@ -1011,6 +2200,7 @@ fn test_query_share() {
 
     // Store the liquidity token
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [asset_0_amount, asset_1_amount]);
 
     let res = query_share(deps.as_ref(), Uint128::new(250)).unwrap();
 
@@ -1018,6 +2208,114 @@ fn test_query_share() {
     assert_eq!(res[1].amount, Uint128::new(500));
 }
 
+#[test]
+fn test_query_virtual_price() {
+    let total_share_amount = Uint128::from(1000000u128);
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                bluna_rewarder: "bluna_rewarder".to_string(),
+                generator: "generator".to_string(),
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    // The invariant of a balanced pool is the sum of its balances
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(1000000u128),
+        }],
+    )]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(1000000u128),
+            )],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share_amount)],
+        ),
+    ]);
+    store_reserves(
+        deps.as_mut(),
+        [Uint128::new(1000000u128), Uint128::new(1000000u128)],
+    );
+    let res: PoolInvariantResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::PoolInvariant {}).unwrap())
+            .unwrap();
+    assert_eq!(res.d, Uint128::new(2000000u128));
+    assert_eq!(res.amp, Decimal::from_ratio(100u128, 1u128));
+
+    let res: VirtualPriceResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::VirtualPrice {}).unwrap())
+            .unwrap();
+    assert_eq!(res.virtual_price, Decimal::from_ratio(2u128, 1u128));
+
+    // Moving the balances along the curve barely changes the virtual price
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(1500000u128),
+        }],
+    )]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(500000u128))],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share_amount)],
+        ),
+    ]);
+    store_reserves(
+        deps.as_mut(),
+        [Uint128::new(1500000u128), Uint128::new(500000u128)],
+    );
+    let res: VirtualPriceResponse =
+        from_binary(&query(deps.as_ref(), env, QueryMsg::VirtualPrice {}).unwrap()).unwrap();
+    assert!(res.virtual_price < Decimal::from_ratio(2u128, 1u128));
+    assert!(res.virtual_price > Decimal::from_ratio(199u128, 100u128));
+}
+
 #[test]
 fn test_accumulate_prices() {
     struct Case {
@@ -1144,6 +2442,244 @@ fn test_accumulate_prices() {
     }
 }
 
+#[test]
+fn circuit_breaker() {
+    let total_share = Uint128::new(30000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+    let offer_amount = Uint128::new(15000000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount, /* user deposit must be pre-applied */
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: "factory".to_string(),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                bluna_rewarder: "bluna_rewarder".to_string(),
+                generator: "generator".to_string(),
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env_with_block_time(100);
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        belief_price: None,
+        max_spread: Some(Decimal::percent(50)),
+        to: None,
+        deadline: None,
+        callback: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+
+    // The guardian can pause the pair, but only the factory owner can unpause it
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateCircuitBreaker {
+            guardian: Some("guardian".to_string()),
+            max_price_change_bps: Some(100),
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian", &[]),
+        ExecuteMsg::Pause {},
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        info.clone(),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Paused {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian", &[]),
+        ExecuteMsg::Unpause {},
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Unpause {},
+    )
+    .unwrap();
+
+    // The swap moves the price by more than 1% and fails without pausing the pair
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        info.clone(),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::MaxPriceChangeAssertion {});
+    let res: StatusResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Status {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        StatusResponse {
+            paused: false,
+            guardian: Some(Addr::unchecked("guardian")),
+            max_price_change_bps: Some(100),
+        }
+    );
+
+    // The failed swap is reverted on chain, but the mock storage keeps its writes
+    store_reserves(deps.as_mut(), [collateral_pool_amount, asset_pool_amount]);
+
+    // A swap within the limit still goes through in the same block
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::new(1000000u128),
+        },
+        belief_price: None,
+        max_spread: Some(Decimal::percent(50)),
+        to: None,
+        deadline: None,
+        callback: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(1000000u128),
+        }],
+    );
+    execute(deps.as_mut(), mock_env_with_block_time(1000), info, msg).unwrap();
+}
+
+#[test]
+fn migrate_seeds_reserves() {
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+    )]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                bluna_rewarder: "bluna_rewarder".to_string(),
+                generator: "generator".to_string(),
+            })
+            .unwrap(),
+        ),
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // A 1.0.1 pair did not track its reserves
+    RESERVES.remove(deps.as_mut().storage);
+    cw2::set_contract_version(
+        deps.as_mut().storage,
+        "astroport-pair-stable-bluna",
+        "1.0.1",
+    )
+    .unwrap();
+
+    let msg = MigrateMsg {
+        bluna_rewarder: "bluna_rewarder".to_string(),
+        generator: "generator".to_string(),
+    };
+    migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [collateral_pool_amount, asset_pool_amount]
+    );
+    assert_eq!(
+        cw2::get_contract_version(deps.as_ref().storage)
+            .unwrap()
+            .version,
+        env!("CARGO_PKG_VERSION")
+    );
+
+    // An unknown version can not be migrated
+    cw2::set_contract_version(
+        deps.as_mut().storage,
+        "astroport-pair-stable-bluna",
+        "0.9.0",
+    )
+    .unwrap();
+    let res = migrate(deps.as_mut(), mock_env(), msg).unwrap_err();
+    assert_eq!(res, ContractError::MigrationError {});
+}
+
 fn mock_env_with_block_time(time: u64) -> Env {
     let mut env = mock_env();
     env.block = BlockInfo {
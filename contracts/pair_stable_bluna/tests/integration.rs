@@ -6,7 +6,7 @@ use astroport::pair_stable_bluna::{
     ExecuteMsg, QueryMsg, StablePoolConfig, StablePoolParams, StablePoolUpdateParams,
 };
 
-use astroport_pair_stable_bluna::math::{MAX_AMP, MAX_AMP_CHANGE, MIN_AMP_CHANGING_TIME};
+use astroport::stable_math::{MAX_AMP, MAX_AMP_CHANGE, MIN_AMP_CHANGING_TIME};
 use cosmwasm_std::testing::{mock_env, MockApi, MockStorage};
 use cosmwasm_std::{from_binary, to_binary, Addr, Decimal};
 
@@ -89,6 +89,7 @@ fn create_pair_with_same_assets() {
         pair_configs: vec![PairConfig {
             code_id: pair_contract_code_id,
             maker_fee_bps: 0,
+            flash_loan_fee_bps: 0,
             total_fee_bps: 0,
             pair_type: PairType::Stable {},
             is_disabled: false,
@@ -0,0 +1,111 @@
+use astroport::pair::MINIMUM_LIQUIDITY_AMOUNT;
+use astroport::stable_math::{MAX_AMP, MAX_AMP_CHANGE, MIN_AMP_CHANGING_TIME};
+use cosmwasm_std::{OverflowError, StdError};
+use thiserror::Error;
+
+/// ## Description
+/// This enum describes stableswap pair contract errors!
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("The pair is paused")]
+    Paused {},
+
+    #[error("Flash loan was not repaid")]
+    FlashLoanNotRepaid {},
+
+    #[error("A flash loan is in progress")]
+    FlashLoanInProgress {},
+
+    #[error("Doubling assets in asset infos")]
+    DoublingAssets {},
+
+    #[error("Event of zero transfer")]
+    InvalidZeroAmount {},
+
+    #[error("Insufficient amount of liquidity")]
+    LiquidityAmountTooSmall {},
+
+    #[error("Failed to compute the pool invariant")]
+    InvariantComputationError {},
+
+    #[error("Provided spread amount exceeds allowed limit")]
+    AllowedSpreadAssertion {},
+
+    #[error("Operation exceeds max spread limit")]
+    MaxSpreadAssertion {},
+
+    #[error("Operation moves the price by more than the circuit breaker allows within a block")]
+    MaxPriceChangeAssertion {},
+
+    #[error("Operation exceeds max splippage tolerance")]
+    MaxSlippageAssertion {},
+
+    #[error("Operation exceeds max offer amount")]
+    MaxOfferAmountAssertion {},
+
+    #[error("Ask amount must be less than the ask pool balance")]
+    AskAmountExceedsPool {},
+
+    #[error("Return amount is less than the minimum amount")]
+    MinAmountAssertion {},
+
+    #[error("Native token balance mismatch between the argument and the transferred")]
+    AssetMismatch {},
+
+    #[error("Pair type mismatch. Check factory pair configs")]
+    PairTypeMismatch {},
+
+    #[error(
+        "Amp coefficient must be greater than 0 and less than or equal to {}",
+        MAX_AMP
+    )]
+    IncorrectAmp {},
+
+    #[error(
+        "The difference between the old and new amp value must not exceed {} times",
+        MAX_AMP_CHANGE
+    )]
+    MaxAmpChangeAssertion {},
+
+    #[error(
+        "Amp coefficient cannot be changed more often than once per {} seconds",
+        MIN_AMP_CHANGING_TIME
+    )]
+    MinAmpChangingTimeAssertion {},
+
+    #[error("You need to provide init params")]
+    InitParamsNotFound {},
+
+    #[error("Generator address is not set in factory. Cannot autostake")]
+    AutoStakeError {},
+
+    #[error("Initial liquidity must be more than {}", MINIMUM_LIQUIDITY_AMOUNT)]
+    MinimumLiquidityAmountError {},
+
+    #[error("Contract can't be migrated!")]
+    MigrationError {},
+
+    #[error("The amount of LP tokens to receive is less than the minimum amount")]
+    MinLpToReceiveAssertion {},
+
+    #[error("The amount of withdrawn assets is less than the minimum amount")]
+    MinAssetsToReceiveAssertion {},
+
+    #[error("The deadline of the message has passed")]
+    DeadlineExpired {},
+
+    #[error("A callback contract can't be combined with a receiver or auto staking")]
+    CallbackWithReceiver {},
+}
+
+impl From<OverflowError> for ContractError {
+    fn from(o: OverflowError) -> Self {
+        StdError::from(o).into()
+    }
+}
@@ -0,0 +1,732 @@
+use crate::contract::{execute, instantiate, query, reply};
+use crate::error::ContractError;
+use crate::mock_querier::{mock_dependencies, WasmMockQuerier};
+
+use crate::response::MsgInstantiateContractResponse;
+use crate::state::RESERVES;
+use astroport::asset::{Asset, AssetInfo};
+use astroport::pair::{
+    ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, PoolInvariantResponse, QueryMsg,
+    SimulateProvideResponse, SimulationResponse, SpotPriceResponse, VirtualPriceResponse,
+};
+use astroport::pair_stable_lsd::{
+    AssetRate, RateProvider, StablePoolConfig, StablePoolParams, StablePoolUpdateParams,
+};
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    from_binary, to_binary, Addr, Coin, ContractResult, CosmosMsg, Decimal, DepsMut, OwnedDeps,
+    Reply, StdError, SubMsg, SubMsgExecutionResponse, Uint128, WasmMsg,
+};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use protobuf::Message;
+
+fn store_liquidity_token(deps: DepsMut, msg_id: u64, contract_addr: String) {
+    let data = MsgInstantiateContractResponse {
+        contract_address: contract_addr,
+        data: vec![],
+        unknown_fields: Default::default(),
+        cached_size: Default::default(),
+    }
+    .write_to_bytes()
+    .unwrap();
+
+    let reply_msg = Reply {
+        id: msg_id,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(data.into()),
+        }),
+    };
+
+    let _res = reply(deps, mock_env(), reply_msg).unwrap();
+}
+
+fn store_reserves(deps: DepsMut, reserves: [Uint128; 2]) {
+    RESERVES.save(deps.storage, &reserves).unwrap();
+}
+
+fn asset_infos() -> [AssetInfo; 2] {
+    [
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+    ]
+}
+
+fn instantiate_pair(
+    deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+    rate_providers: Vec<RateProvider>,
+) -> Result<(), ContractError> {
+    let msg = InstantiateMsg {
+        factory_addr: String::from("factory"),
+        asset_infos: asset_infos(),
+        token_code_id: 10u64,
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                rate_providers,
+                fee_on_transfer: false,
+            })
+            .unwrap(),
+        ),
+    };
+
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg)?;
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    Ok(())
+}
+
+fn query_simulation(
+    deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+    offer_amount: Uint128,
+) -> SimulationResponse {
+    from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Simulation {
+                offer_asset: Asset {
+                    info: asset_infos()[0].clone(),
+                    amount: offer_amount,
+                },
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn proper_initialization() {
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+    )]);
+    deps.querier
+        .with_rates(&[(&String::from("rate_provider"), &Decimal::percent(105))]);
+
+    // A rate provider for an asset that is not in the pool is rejected
+    let err = instantiate_pair(
+        &mut deps,
+        vec![RateProvider {
+            asset_info: AssetInfo::NativeToken {
+                denom: "uluna".to_string(),
+            },
+            rate_provider: "rate_provider".to_string(),
+        }],
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::AssetMismatch {});
+
+    instantiate_pair(
+        &mut deps,
+        vec![RateProvider {
+            asset_info: asset_infos()[1].clone(),
+            rate_provider: "rate_provider".to_string(),
+        }],
+    )
+    .unwrap();
+
+    let res: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    let params: StablePoolConfig = from_binary(&res.params.unwrap()).unwrap();
+    assert_eq!(
+        params,
+        StablePoolConfig {
+            amp: Decimal::from_ratio(100u128, 1u128),
+            fee_on_transfer: false,
+            rates: vec![
+                AssetRate {
+                    asset_info: asset_infos()[0].clone(),
+                    rate_provider: None,
+                    rate: Decimal::one(),
+                },
+                AssetRate {
+                    asset_info: asset_infos()[1].clone(),
+                    rate_provider: Some(Addr::unchecked("rate_provider")),
+                    rate: Decimal::percent(105),
+                },
+            ],
+        }
+    );
+}
+
+#[test]
+fn swap_applies_rates() {
+    let offer_amount = Uint128::new(1000000u128);
+
+    // A pool without rate providers holding the same value in both assets
+    let mut plain_deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(2000_000000u128),
+    }]);
+    plain_deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &Uint128::new(2000_000000u128),
+        )],
+    )]);
+    instantiate_pair(&mut plain_deps, vec![]).unwrap();
+    store_reserves(
+        plain_deps.as_mut(),
+        [Uint128::new(2000_000000u128), Uint128::new(2000_000000u128)],
+    );
+
+    // A pool where one asset0000 token is worth two uusd
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(2000_000000u128) + offer_amount,
+    }]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &Uint128::new(1000_000000u128),
+        )],
+    )]);
+    deps.querier
+        .with_rates(&[(&String::from("rate_provider"), &Decimal::percent(200))]);
+    instantiate_pair(
+        &mut deps,
+        vec![RateProvider {
+            asset_info: asset_infos()[1].clone(),
+            rate_provider: "rate_provider".to_string(),
+        }],
+    )
+    .unwrap();
+    store_reserves(
+        deps.as_mut(),
+        [Uint128::new(2000_000000u128), Uint128::new(1000_000000u128)],
+    );
+
+    // The scaled pool is the same as the plain one, so it returns half as many tokens
+    let plain_res = query_simulation(&plain_deps, offer_amount);
+    let res = query_simulation(&deps, offer_amount);
+    let diff =
+        (res.return_amount.u128() as i128 * 2 - plain_res.return_amount.u128() as i128).abs();
+    assert!(diff <= 2, "diff: {}", diff);
+    let diff = (res.commission_amount.u128() as i128 * 2
+        - plain_res.commission_amount.u128() as i128)
+        .abs();
+    assert!(diff <= 2, "diff: {}", diff);
+    assert!(res.return_amount < Uint128::new(500000u128));
+    assert!(res.return_amount > Uint128::new(498000u128));
+
+    // The scaled pool is balanced, so the spot price is the ratio of the rates
+    for (offer_asset_info, price) in [
+        (asset_infos()[0].clone(), Decimal::percent(50)),
+        (asset_infos()[1].clone(), Decimal::percent(200)),
+    ] {
+        let res: SpotPriceResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::SpotPrice { offer_asset_info },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.price, price);
+    }
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: asset_infos()[0].clone(),
+            amount: offer_amount,
+        },
+        belief_price: None,
+        max_spread: Some(Decimal::percent(1)),
+        to: None,
+        deadline: None,
+        callback: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let res_swap = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res_swap.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("asset0000"),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("addr0000"),
+                amount: res.return_amount,
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+}
+
+#[test]
+fn provide_liquidity_applies_rates() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(2002_000000u128),
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(1000_000000u128),
+            )],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(4000_000000u128),
+            )],
+        ),
+    ]);
+    deps.querier
+        .with_rates(&[(&String::from("rate_provider"), &Decimal::percent(200))]);
+    instantiate_pair(
+        &mut deps,
+        vec![RateProvider {
+            asset_info: asset_infos()[1].clone(),
+            rate_provider: "rate_provider".to_string(),
+        }],
+    )
+    .unwrap();
+    store_reserves(
+        deps.as_mut(),
+        [Uint128::new(2000_000000u128), Uint128::new(1000_000000u128)],
+    );
+
+    // The deposit keeps the scaled pool ratio, so it pays no imbalance fee
+    let assets = [
+        Asset {
+            info: asset_infos()[0].clone(),
+            amount: Uint128::new(2_000000u128),
+        },
+        Asset {
+            info: asset_infos()[1].clone(),
+            amount: Uint128::new(1_000000u128),
+        },
+    ];
+    let simulation: SimulateProvideResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateProvide {
+                assets: assets.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(simulation.share, Uint128::new(4000000u128));
+    assert!(simulation
+        .imbalance_fees
+        .iter()
+        .all(|fee| fee.amount.is_zero()));
+
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets,
+        slippage_tolerance: None,
+        auto_stake: None,
+        receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(2_000000u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes
+            .iter()
+            .find(|attr| attr.key == "imbalance_fees")
+            .unwrap()
+            .value,
+        "0uusd, 0asset0000"
+    );
+    assert_eq!(
+        res.attributes
+            .iter()
+            .find(|attr| attr.key == "share")
+            .unwrap()
+            .value,
+        "4000000"
+    );
+}
+
+#[test]
+fn fee_on_transfer_tokens() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(2002_000000u128),
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(1000_000000u128),
+            )],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(4000_000000u128),
+            )],
+        ),
+    ]);
+    deps.querier
+        .with_rates(&[(&String::from("rate_provider"), &Decimal::percent(200))]);
+
+    let msg = InstantiateMsg {
+        factory_addr: String::from("factory"),
+        asset_infos: asset_infos(),
+        token_code_id: 10u64,
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                rate_providers: vec![RateProvider {
+                    asset_info: asset_infos()[1].clone(),
+                    rate_provider: "rate_provider".to_string(),
+                }],
+                fee_on_transfer: true,
+            })
+            .unwrap(),
+        ),
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(
+        deps.as_mut(),
+        [Uint128::new(2000_000000u128), Uint128::new(1000_000000u128)],
+    );
+
+    // The provision waits for the token transfer to settle
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: [
+            Asset {
+                info: asset_infos()[0].clone(),
+                amount: Uint128::new(2_000000u128),
+            },
+            Asset {
+                info: asset_infos()[1].clone(),
+                amount: Uint128::new(1_000000u128),
+            },
+        ],
+        slippage_tolerance: None,
+        auto_stake: None,
+        receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(2_000000u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: String::from("addr0000"),
+                    recipient: String::from(MOCK_CONTRACT_ADDR),
+                    amount: Uint128::new(1_000000u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            },
+            4,
+        )]
+    );
+
+    // The token charges a 1% transfer fee, so the pair receives only 0.99 tokens
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(1000_990000u128),
+            )],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(4000_000000u128),
+            )],
+        ),
+    ]);
+    let settle_reply = Reply {
+        id: 4,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+    let res = reply(deps.as_mut(), mock_env(), settle_reply.clone()).unwrap();
+    assert_eq!(
+        res.attributes
+            .iter()
+            .find(|attr| attr.key == "assets")
+            .unwrap()
+            .value,
+        "2000000uusd, 990000asset0000"
+    );
+    // The transfer fee unbalances the deposit slightly, so the Maker takes a tiny imbalance fee
+    let reserves = RESERVES.load(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        reserves,
+        [Uint128::new(2001_999998u128), Uint128::new(1000_989999u128)]
+    );
+
+    // The pending provision is settled only once
+    reply(deps.as_mut(), mock_env(), settle_reply).unwrap_err();
+
+    // A swap uses the amount that the pair received instead of the sent amount
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(reserves[1] + Uint128::new(990000u128)),
+            )],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(4000_000000u128),
+            )],
+        ),
+    ]);
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: Uint128::new(1_000000u128),
+        msg: to_binary(&Cw20HookMsg::Swap {
+            belief_price: None,
+            max_spread: Some(Decimal::percent(50)),
+            to: None,
+            deadline: None,
+            callback: None,
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), mock_env(), mock_info("asset0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.attributes
+            .iter()
+            .find(|attr| attr.key == "offer_amount")
+            .unwrap()
+            .value,
+        "990000"
+    );
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap()[1],
+        reserves[1] + Uint128::new(990000u128)
+    );
+}
+
+#[test]
+fn pool_invariant_is_scaled() {
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(2000000u128),
+            )],
+        ),
+    ]);
+    deps.querier
+        .with_rates(&[(&String::from("rate_provider"), &Decimal::percent(200))]);
+    instantiate_pair(
+        &mut deps,
+        vec![RateProvider {
+            asset_info: asset_infos()[1].clone(),
+            rate_provider: "rate_provider".to_string(),
+        }],
+    )
+    .unwrap();
+
+    // The pool is balanced once asset0000 is scaled by its rate
+    store_reserves(
+        deps.as_mut(),
+        [Uint128::new(2000000u128), Uint128::new(1000000u128)],
+    );
+    let res: PoolInvariantResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PoolInvariant {}).unwrap())
+            .unwrap();
+    assert_eq!(res.d, Uint128::new(4000000u128));
+
+    let res: VirtualPriceResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::VirtualPrice {}).unwrap()).unwrap();
+    assert_eq!(res.virtual_price, Decimal::from_ratio(2u128, 1u128));
+
+    // The invariant grows with the rate
+    deps.querier
+        .with_rates(&[(&String::from("rate_provider"), &Decimal::percent(300))]);
+    let res: PoolInvariantResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PoolInvariant {}).unwrap())
+            .unwrap();
+    assert!(res.d > Uint128::new(4900000u128));
+    assert!(res.d < Uint128::new(5000000u128));
+}
+
+#[test]
+fn update_rate_provider() {
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+    )]);
+    deps.querier
+        .with_rates(&[(&String::from("rate_provider"), &Decimal::percent(110))]);
+    instantiate_pair(&mut deps, vec![]).unwrap();
+
+    let update_msg =
+        |asset_info: AssetInfo, rate_provider: Option<String>| ExecuteMsg::UpdateConfig {
+            params: to_binary(&StablePoolUpdateParams::UpdateRateProvider {
+                asset_info,
+                rate_provider,
+            })
+            .unwrap(),
+        };
+    let query_params = |deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier>| {
+        let res: ConfigResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        from_binary::<StablePoolConfig>(&res.params.unwrap()).unwrap()
+    };
+
+    // Only the factory owner can update the rate providers
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        update_msg(asset_infos()[1].clone(), Some("rate_provider".to_string())),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_msg(
+            AssetInfo::NativeToken {
+                denom: "uluna".to_string(),
+            },
+            Some("rate_provider".to_string()),
+        ),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::AssetMismatch {});
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_msg(asset_infos()[1].clone(), Some("rate_provider".to_string())),
+    )
+    .unwrap();
+    let params = query_params(&deps);
+    assert_eq!(
+        params.rates[1],
+        AssetRate {
+            asset_info: asset_infos()[1].clone(),
+            rate_provider: Some(Addr::unchecked("rate_provider")),
+            rate: Decimal::percent(110),
+        }
+    );
+
+    // Removing the rate provider falls back to a rate of 1
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_msg(asset_infos()[1].clone(), None),
+    )
+    .unwrap();
+    let params = query_params(&deps);
+    assert_eq!(
+        params.rates[1],
+        AssetRate {
+            asset_info: asset_infos()[1].clone(),
+            rate_provider: None,
+            rate: Decimal::one(),
+        }
+    );
+}
+
+#[test]
+fn zero_rate_is_rejected() {
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &Uint128::new(1000000u128),
+        )],
+    )]);
+    deps.querier
+        .with_rates(&[(&String::from("rate_provider"), &Decimal::zero())]);
+    instantiate_pair(
+        &mut deps,
+        vec![RateProvider {
+            asset_info: asset_infos()[1].clone(),
+            rate_provider: "rate_provider".to_string(),
+        }],
+    )
+    .unwrap();
+    store_reserves(
+        deps.as_mut(),
+        [Uint128::new(1000000u128), Uint128::new(1000000u128)],
+    );
+
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Simulation {
+            offer_asset: Asset {
+                info: asset_infos()[0].clone(),
+                amount: Uint128::new(1000u128),
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("The rate provider returned a zero rate for asset0000")
+    );
+}
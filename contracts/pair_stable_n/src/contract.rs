@@ -0,0 +1,1951 @@
+use crate::error::ContractError;
+use crate::math::{
+    calc_ask_amount, calc_offer_amount, compute_d, AMP_PRECISION, MAX_AMP, MAX_AMP_CHANGE,
+    MIN_AMP_CHANGING_TIME,
+};
+use crate::state::{Config, CONFIG};
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{
+    attr, entry_point, from_binary, to_binary, Addr, Api, Binary, CosmosMsg, Decimal, Deps,
+    DepsMut, Env, MessageInfo, QuerierWrapper, Reply, ReplyOn, Response, StdError, StdResult,
+    SubMsg, Uint128, WasmMsg,
+};
+
+use crate::response::MsgInstantiateContractResponse;
+use astroport::asset::{
+    addr_validate_to_lower, format_lp_token_name, Asset, AssetInfo, Callback, MultiAssetPairInfo,
+};
+use astroport::factory::PairType;
+
+use astroport::generator::Cw20HookMsg as GeneratorHookMsg;
+use astroport::pair::{
+    ConfigResponse, MultiAssetInstantiateMsg, PoolInvariantResponse, ReverseSimulationResponse,
+    SimulateProvideResponse, SimulateWithdrawResponse, SimulationResponse, StablePoolConfig,
+    StablePoolParams, StablePoolUpdateParams, VirtualPriceResponse, DEFAULT_SLIPPAGE,
+    MAX_ALLOWED_SLIPPAGE, MAX_MULTI_ASSETS, MINIMUM_LIQUIDITY_AMOUNT,
+};
+use astroport::pair_stable_n::{Cw20HookMsg, ExecuteMsg, PoolResponse, QueryMsg};
+use astroport::querier::{
+    query_factory_config, query_pair_fee_info, query_supply, query_token_precision,
+};
+use astroport::stats::{query_daily_stats, query_stats, record_fee, record_swap};
+use astroport::token::InstantiateMsg as TokenInstantiateMsg;
+use cw2::set_contract_version;
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
+use protobuf::Message;
+use std::cmp::Ordering;
+use std::str::FromStr;
+use std::vec;
+
+/// Contract name that is used for migration.
+const CONTRACT_NAME: &str = "astroport-pair-stable-n";
+/// Contract version that is used for migration.
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// A `reply` call code ID of sub-message.
+const INSTANTIATE_TOKEN_REPLY_ID: u64 = 1;
+/// The name of the custom pair type under which the pair is registered in the factory.
+pub const PAIR_TYPE: &str = "stable_n";
+
+/// ## Description
+/// Creates a new contract with the specified parameters in [`MultiAssetInstantiateMsg`].
+/// Returns a [`Response`] with the specified attributes if the operation was successful,
+/// or a [`ContractError`] if the contract was not created.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **_info** is an object of type [`MessageInfo`].
+///
+/// * **msg** is a message of type [`MultiAssetInstantiateMsg`] which contains the parameters for creating the contract.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    msg: MultiAssetInstantiateMsg,
+) -> Result<Response, ContractError> {
+    if msg.asset_infos.len() <= 2 || msg.asset_infos.len() > MAX_MULTI_ASSETS {
+        return Err(ContractError::InvalidNumberOfAssets {});
+    }
+
+    for (i, asset_info) in msg.asset_infos.iter().enumerate() {
+        asset_info.check(deps.api)?;
+        if msg.asset_infos[..i].iter().any(|a| a.equal(asset_info)) {
+            return Err(ContractError::DoublingAssets {});
+        }
+    }
+
+    if msg.init_params.is_none() {
+        return Err(ContractError::InitParamsNotFound {});
+    }
+
+    let params: StablePoolParams = from_binary(&msg.init_params.unwrap())?;
+
+    if params.amp == 0 || params.amp > MAX_AMP {
+        return Err(ContractError::IncorrectAmp {});
+    }
+
+    // The pool prices from its balances, so it can't measure the amounts it received for a deposit
+    if params.fee_on_transfer {
+        return Err(ContractError::FeeOnTransferNotSupported {});
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let config = Config {
+        pair_info: MultiAssetPairInfo {
+            contract_addr: env.contract.address.clone(),
+            liquidity_token: Addr::unchecked(""),
+            asset_infos: msg.asset_infos.clone(),
+            pair_type: PairType::Custom(PAIR_TYPE.to_string()),
+        },
+        factory_addr: addr_validate_to_lower(deps.api, msg.factory_addr.as_str())?,
+        init_amp: params.amp * AMP_PRECISION,
+        init_amp_time: env.block.time.seconds(),
+        next_amp: params.amp * AMP_PRECISION,
+        next_amp_time: env.block.time.seconds(),
+    };
+
+    CONFIG.save(deps.storage, &config)?;
+
+    let token_name = format_lp_token_name(&msg.asset_infos, &deps.querier)?;
+
+    // Create LP token
+    let sub_msg: Vec<SubMsg> = vec![SubMsg {
+        msg: WasmMsg::Instantiate {
+            code_id: msg.token_code_id,
+            msg: to_binary(&TokenInstantiateMsg {
+                name: token_name,
+                symbol: "uLP".to_string(),
+                decimals: 6,
+                initial_balances: vec![],
+                mint: Some(MinterResponse {
+                    minter: env.contract.address.to_string(),
+                    cap: None,
+                }),
+            })?,
+            funds: vec![],
+            admin: None,
+            label: String::from("Astroport LP token"),
+        }
+        .into(),
+        id: INSTANTIATE_TOKEN_REPLY_ID,
+        gas_limit: None,
+        reply_on: ReplyOn::Success,
+    }];
+
+    Ok(Response::new().add_submessages(sub_msg))
+}
+
+/// ## Description
+/// The entry point to the contract for processing replies from submessages.
+/// # Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **_env** is an object of type [`Env`].
+///
+/// * **msg** is an object of type [`Reply`]. This is the reply from the submessage.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let mut config: Config = CONFIG.load(deps.storage)?;
+
+    if config.pair_info.liquidity_token != Addr::unchecked("") {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let data = msg.result.unwrap().data.unwrap();
+    let res: MsgInstantiateContractResponse =
+        Message::parse_from_bytes(data.as_slice()).map_err(|_| {
+            StdError::parse_err("MsgInstantiateContractResponse", "failed to parse data")
+        })?;
+
+    config.pair_info.liquidity_token =
+        addr_validate_to_lower(deps.api, res.get_contract_address())?;
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("liquidity_token_addr", config.pair_info.liquidity_token))
+}
+
+/// ## Description
+/// Exposes all the execute functions available in the contract.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **msg** is an object of type [`ExecuteMsg`].
+///
+/// ## Queries
+/// * **ExecuteMsg::UpdateConfig { params: Binary }** Updates the contract configuration with the specified
+/// input parameters.
+///
+/// * **ExecuteMsg::Receive(msg)** Receives a message of type [`Cw20ReceiveMsg`] and processes
+/// it depending on the received template.
+///
+/// * **ExecuteMsg::ProvideLiquidity {
+///             assets,
+///             auto_stake,
+///             receiver,
+///             min_lp_to_receive,
+///             deadline,
+///             callback,
+///         }** Provides liquidity in the pair using the specified input parameters.
+///
+/// * **ExecuteMsg::Swap {
+///             offer_asset,
+///             ask_asset_info,
+///             belief_price,
+///             max_spread,
+///             to,
+///             deadline,
+///             callback,
+///         }** Performs an swap using the specified parameters.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::UpdateConfig { params } => update_config(deps, env, info, params),
+        ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
+        ExecuteMsg::ProvideLiquidity {
+            assets,
+            auto_stake,
+            receiver,
+            min_lp_to_receive,
+            deadline,
+            callback,
+        } => {
+            assert_deadline(&env, deadline)?;
+            provide_liquidity(
+                deps,
+                env,
+                info,
+                assets,
+                auto_stake,
+                receiver,
+                min_lp_to_receive,
+                callback,
+            )
+        }
+        ExecuteMsg::Swap {
+            offer_asset,
+            ask_asset_info,
+            belief_price,
+            max_spread,
+            to,
+            deadline,
+            callback,
+        } => {
+            assert_deadline(&env, deadline)?;
+            offer_asset.info.check(deps.api)?;
+            if !offer_asset.is_native_token() {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let to_addr = if let Some(to_addr) = to {
+                Some(addr_validate_to_lower(deps.api, &to_addr)?)
+            } else {
+                None
+            };
+
+            swap(
+                deps,
+                env,
+                info.clone(),
+                info.sender,
+                offer_asset,
+                ask_asset_info,
+                belief_price,
+                max_spread,
+                to_addr,
+                callback,
+            )
+        }
+    }
+}
+
+/// ## Description
+/// Receives a message of type [`Cw20ReceiveMsg`] and processes it depending on the received template.
+/// If no template is not found in the received message, then an [`ContractError`] is returned,
+/// otherwise it returns a [`Response`] with the specified attributes if the operation was successful
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **cw20_msg** is an object of type [`Cw20ReceiveMsg`]. This is the CW20 receive message to process.
+pub fn receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let contract_addr = info.sender.clone();
+    match from_binary(&cw20_msg.msg) {
+        Ok(Cw20HookMsg::Swap {
+            ask_asset_info,
+            belief_price,
+            max_spread,
+            to,
+            deadline,
+            callback,
+        }) => {
+            assert_deadline(&env, deadline)?;
+            // Only an asset (token) contract can execute this message
+            let config: Config = CONFIG.load(deps.storage)?;
+            let offer_asset_info = AssetInfo::Token { contract_addr };
+            if !config
+                .pair_info
+                .asset_infos
+                .iter()
+                .any(|a| a.equal(&offer_asset_info))
+            {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let to_addr = if let Some(to_addr) = to {
+                Some(addr_validate_to_lower(deps.api, to_addr.as_str())?)
+            } else {
+                None
+            };
+
+            swap(
+                deps,
+                env,
+                info,
+                Addr::unchecked(cw20_msg.sender),
+                Asset {
+                    info: offer_asset_info,
+                    amount: cw20_msg.amount,
+                },
+                ask_asset_info,
+                belief_price,
+                max_spread,
+                to_addr,
+                callback,
+            )
+        }
+        Ok(Cw20HookMsg::WithdrawLiquidity {
+            min_assets_to_receive,
+            deadline,
+            callback,
+        }) => {
+            assert_deadline(&env, deadline)?;
+            withdraw_liquidity(
+                deps,
+                env,
+                info,
+                Addr::unchecked(cw20_msg.sender),
+                cw20_msg.amount,
+                min_assets_to_receive,
+                callback,
+            )
+        }
+        Err(err) => Err(ContractError::Std(err)),
+    }
+}
+
+/// ## Description
+/// Provides liquidity with the specified input parameters. Any subset of the pool assets can be
+/// deposited, except for the first deposit which must contain all of them.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the
+/// specified attributes if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **assets** is a vector with objects of type [`Asset`]. These are the assets to deposit.
+///
+/// * **auto_stake** is object of type [`Option<bool>`]. Determines whether the resulting LP tokens are automatically staked in
+/// the Generator contract to receive token incentives.
+///
+/// * **receiver** is object of type [`Option<String>`]. This is the address that receives LP tokens.
+/// If this address isn't specified, the function will default to the caller.
+///
+/// * **min_lp_to_receive** is an object of type [`Option<Uint128>`]. This is the minimum amount of LP tokens to receive.
+/// The provision fails if fewer LP tokens are minted.
+/// NOTE - the address that wants to provide liquidity should approve the pair contract to pull its relevant tokens.
+///
+/// * **callback** is an [`Option`] field of type [`Callback`]. This is the contract that receives
+/// the LP tokens along with a message.
+#[allow(clippy::too_many_arguments)]
+pub fn provide_liquidity(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    assets: Vec<Asset>,
+    auto_stake: Option<bool>,
+    receiver: Option<String>,
+    min_lp_to_receive: Option<Uint128>,
+    callback: Option<Callback>,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    for asset in assets.iter() {
+        asset.info.check(deps.api)?;
+        asset.assert_sent_native_token_balance(&info)?;
+    }
+
+    let auto_stake = auto_stake.unwrap_or(false);
+    assert_callback(deps.api, &callback, auto_stake || receiver.is_some())?;
+
+    let mut pools: Vec<Asset> = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address.clone())?;
+    let deposits = get_deposits(&assets, &pools)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for (pool, deposit) in pools.iter_mut().zip(deposits.iter()) {
+        if pool.is_native_token() {
+            // Native tokens were already sent along with the message
+            pool.amount = pool.amount.checked_sub(*deposit)?;
+        } else if let AssetInfo::Token { contract_addr, .. } = &pool.info {
+            // If the pool is a token contract, then we need to execute a TransferFrom msg to receive funds
+            if !deposit.is_zero() {
+                messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: contract_addr.to_string(),
+                    msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                        owner: info.sender.to_string(),
+                        recipient: env.contract.address.to_string(),
+                        amount: *deposit,
+                    })?,
+                    funds: vec![],
+                }))
+            }
+        }
+    }
+
+    let total_share = query_supply(&deps.querier, config.pair_info.liquidity_token.clone())?;
+
+    // Get fee info from the factory
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
+
+    let (share, imbalance_fees) = compute_share(
+        &deps.querier,
+        &env,
+        &config,
+        &pools,
+        &deposits,
+        total_share,
+        fee_info.total_fee_rate,
+    )?;
+
+    if total_share.is_zero() {
+        // Lock the minimum liquidity amount in the pair forever, so the first provider
+        // can't inflate the LP token price and round the share of the next providers down to zero
+        messages.extend(mint_liquidity_token_message(
+            deps.as_ref(),
+            &config,
+            env.clone(),
+            env.contract.address.clone(),
+            MINIMUM_LIQUIDITY_AMOUNT,
+            false,
+        )?);
+    }
+
+    // Maker fee
+    let mut maker_fees = vec![Uint128::zero(); pools.len()];
+    let mut maker_fee_messages: Vec<CosmosMsg> = vec![];
+    if let Some(fee_address) = fee_info.fee_address {
+        for (i, pool) in pools.iter().enumerate() {
+            if let Some(f) = calculate_maker_fee(
+                pool.info.clone(),
+                imbalance_fees[i],
+                fee_info.maker_fee_rate,
+            ) {
+                maker_fee_messages.push(f.clone().into_msg(&deps.querier, fee_address.clone())?);
+                maker_fees[i] = f.amount;
+            }
+        }
+    }
+
+    // The imbalance fee is charged like a swap commission
+    for (i, pool) in pools.iter().enumerate() {
+        record_fee(
+            deps.storage,
+            env.block.time.seconds(),
+            &pool.info,
+            imbalance_fees[i],
+            maker_fees[i],
+        )?;
+    }
+
+    // The minimum is checked before minting, so it also applies to the LP tokens staked in the Generator
+    if let Some(min_lp_to_receive) = min_lp_to_receive {
+        if share < min_lp_to_receive {
+            return Err(ContractError::MinLpToReceiveAssertion {});
+        }
+    }
+
+    let receiver = match &callback {
+        Some(callback) => callback.contract.clone(),
+        None => receiver.unwrap_or_else(|| info.sender.to_string()),
+    };
+    let callback_messages = match callback {
+        // Mint LP tokens for the pair and send them to the callback contract after all the other messages
+        Some(callback) => {
+            messages.extend(mint_liquidity_token_message(
+                deps.as_ref(),
+                &config,
+                env.clone(),
+                env.contract.address.clone(),
+                share,
+                false,
+            )?);
+            callback.into_msgs(
+                &deps.querier,
+                &[Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: config.pair_info.liquidity_token.clone(),
+                    },
+                    amount: share,
+                }],
+            )?
+        }
+        // Mint LP token for the caller (or for the receiver if it was set)
+        None => {
+            messages.extend(mint_liquidity_token_message(
+                deps.as_ref(),
+                &config,
+                env,
+                addr_validate_to_lower(deps.api, receiver.as_str())?,
+                share,
+                auto_stake,
+            )?);
+            vec![]
+        }
+    };
+    messages.extend(maker_fee_messages);
+
+    messages.extend(callback_messages);
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "provide_liquidity"),
+        attr("sender", info.sender.as_str()),
+        attr("receiver", receiver.as_str()),
+        attr("assets", format_assets(&assets)),
+        attr("share", share.to_string()),
+        attr("imbalance_fees", format_amounts(&imbalance_fees, &pools)),
+        attr("maker_fees", format_amounts(&maker_fees, &pools)),
+    ]))
+}
+
+/// ## Description
+/// Mint LP tokens for a beneficiary and auto deposit them into the Generator contract (if requested).
+/// # Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **recipient** is an object of type [`Addr`]. This is the LP token recipient.
+///
+/// * **amount** is an object of type [`Uint128`]. This is the amount of LP tokens to mint.
+///
+/// * **auto_stake** is a field of type [`bool`]. Determines whether or not LP tokens will be automatically staked in the Generator contract.
+fn mint_liquidity_token_message(
+    deps: Deps,
+    config: &Config,
+    env: Env,
+    recipient: Addr,
+    amount: Uint128,
+    auto_stake: bool,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let lp_token = config.pair_info.liquidity_token.clone();
+
+    // If no auto-stake - just mint LP tokens for the recipient and return
+    if !auto_stake {
+        return Ok(vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: lp_token.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        })]);
+    }
+
+    // Mint for the contract and stake into the Generator
+    let generator =
+        query_factory_config(&deps.querier, config.clone().factory_addr)?.generator_address;
+
+    if generator.is_none() {
+        return Err(ContractError::AutoStakeError {});
+    }
+
+    Ok(vec![
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: lp_token.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: env.contract.address.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: lp_token.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: generator.unwrap().to_string(),
+                amount,
+                msg: to_binary(&GeneratorHookMsg::DepositFor(recipient))?,
+            })?,
+            funds: vec![],
+        }),
+    ])
+}
+
+/// ## Description
+/// Withdraw liquidity from the pool in proportion to the pool balances. Returns a [`ContractError`] on failure,
+/// otherwise returns a [`Response`] with the specified attributes if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **sender** is an object of type [`Addr`]. This is the address that will receive the withdrawn liquidity.
+///
+/// * **amount** is an object of type [`Uint128`]. This is the amount of LP tokens to burn and withdraw liquidity with.
+///
+/// * **min_assets_to_receive** is an object of type [`Option<Vec<Asset>>`]. These are the minimum amounts of pool assets to receive.
+///
+/// * **callback** is an [`Option`] field of type [`Callback`]. This is the contract that receives
+/// the withdrawn assets along with a message.
+pub fn withdraw_liquidity(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    amount: Uint128,
+    min_assets_to_receive: Option<Vec<Asset>>,
+    callback: Option<Callback>,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.pair_info.liquidity_token {
+        return Err(ContractError::Unauthorized {});
+    }
+    assert_callback(deps.api, &callback, false)?;
+
+    let (pools, total_share) = pool_info(deps.as_ref(), &env, &config)?;
+    let refund_assets = get_share_in_assets(&pools, amount, total_share);
+    assert_min_assets_to_receive(&refund_assets, min_assets_to_receive)?;
+
+    // Assets that round down to zero are not sent, as CW20 tokens reject zero transfers
+    let mut messages: Vec<CosmosMsg> = match &callback {
+        Some(_) => vec![],
+        None => refund_assets
+            .iter()
+            .filter(|asset| !asset.amount.is_zero())
+            .map(|asset| asset.clone().into_msg(&deps.querier, sender.clone()))
+            .collect::<StdResult<Vec<CosmosMsg>>>()?,
+    };
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.pair_info.liquidity_token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
+        funds: vec![],
+    }));
+    if let Some(callback) = callback {
+        // The callback contract gets the assets after the LP tokens are burned
+        messages.extend(callback.into_msgs(&deps.querier, &refund_assets)?);
+    }
+
+    let attributes = vec![
+        attr("action", "withdraw_liquidity"),
+        attr("sender", sender.as_str()),
+        attr("withdrawn_share", amount.to_string()),
+        attr("refund_assets", format_assets(&refund_assets)),
+    ];
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(attributes))
+}
+
+/// ## Description
+/// Returns the deposit amounts of the given assets in the order of the pool assets. Pool assets that are
+/// not given are deposited with a zero amount.
+/// Returns a [`ContractError`] if an asset does not belong to the pool, if an asset is given twice
+/// or if all of the deposit amounts are zero.
+/// ## Params
+/// * **assets** is a slice with objects of type [`Asset`]. These are the assets to deposit.
+///
+/// * **pools** is a slice with objects of type [`Asset`]. These are the assets in the pool.
+fn get_deposits(assets: &[Asset], pools: &[Asset]) -> Result<Vec<Uint128>, ContractError> {
+    for (i, asset) in assets.iter().enumerate() {
+        if !pools.iter().any(|pool| pool.info.equal(&asset.info)) {
+            return Err(ContractError::AssetMismatch {});
+        }
+        if assets[..i].iter().any(|a| a.info.equal(&asset.info)) {
+            return Err(ContractError::DoublingAssets {});
+        }
+    }
+
+    let deposits: Vec<Uint128> = pools
+        .iter()
+        .map(|pool| {
+            assets
+                .iter()
+                .find(|a| a.info.equal(&pool.info))
+                .map(|a| a.amount)
+                .unwrap_or_default()
+        })
+        .collect();
+
+    if deposits.iter().all(|deposit| deposit.is_zero()) {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    Ok(deposits)
+}
+
+/// ## Description
+/// Returns the amount of LP tokens that a deposit mints together with the imbalance fees charged on it
+/// in the precision of the pool assets. The first deposit mints the pool invariant minus the
+/// [`MINIMUM_LIQUIDITY_AMOUNT`] that is locked in the pair and charges no fees.
+/// ## Params
+/// * **querier** is an object of type [`QuerierWrapper`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **pools** is a slice with objects of type [`Asset`]. These are the assets in the pool.
+///
+/// * **deposits** is a slice with objects of type [`Uint128`]. These are the deposit amounts in the order of the pool assets.
+///
+/// * **total_share** is an object of type [`Uint128`]. This is the total amount of LP tokens currently issued by the pool.
+///
+/// * **total_fee_rate** is an object of type [`Decimal`]. This is the total fee rate charged by the pair.
+fn compute_share(
+    querier: &QuerierWrapper,
+    env: &Env,
+    config: &Config,
+    pools: &[Asset],
+    deposits: &[Uint128],
+    total_share: Uint128,
+    total_fee_rate: Decimal,
+) -> Result<(Uint128, Vec<Uint128>), ContractError> {
+    let token_precisions = query_token_precisions(querier, pools)?;
+    let greater_precision = token_precisions.iter().copied().max().unwrap();
+
+    let old_balances = adjust_balances(
+        pools.iter().map(|pool| pool.amount),
+        &token_precisions,
+        greater_precision,
+    )?;
+    let new_balances = adjust_balances(
+        pools
+            .iter()
+            .zip(deposits.iter())
+            .map(|(pool, deposit)| pool.amount + *deposit),
+        &token_precisions,
+        greater_precision,
+    )?;
+
+    let n_coins = pools.len() as u64;
+    let leverage = compute_current_amp(config, env)?
+        .checked_mul(n_coins)
+        .ok_or(ContractError::InvariantComputationError {})?;
+
+    let mut imbalance_fees = vec![Uint128::zero(); pools.len()];
+    let share = if total_share.is_zero() {
+        // The invariant can't be computed if any of the pool balances is zero
+        if deposits.iter().any(|deposit| deposit.is_zero()) {
+            return Err(ContractError::InvalidZeroAmount {});
+        }
+
+        let liquidity_token_precision = query_token_precision(
+            querier,
+            AssetInfo::Token {
+                contract_addr: config.pair_info.liquidity_token.clone(),
+            },
+        )?;
+
+        // Initial share = the pool invariant
+        let d = compute_d(leverage, &to_u128(&new_balances))
+            .ok_or(ContractError::LiquidityAmountTooSmall {})?;
+        let share = adjust_precision(
+            Uint128::new(d),
+            greater_precision,
+            liquidity_token_precision,
+        )?;
+        if share <= MINIMUM_LIQUIDITY_AMOUNT {
+            return Err(ContractError::MinimumLiquidityAmountError {});
+        }
+
+        share - MINIMUM_LIQUIDITY_AMOUNT
+    } else {
+        let d_before_addition_liquidity = compute_d(leverage, &to_u128(&old_balances))
+            .ok_or(ContractError::LiquidityAmountTooSmall {})?;
+        let d_after_addition_liquidity = compute_d(leverage, &to_u128(&new_balances))
+            .ok_or(ContractError::LiquidityAmountTooSmall {})?;
+
+        // d after adding liquidity may be less than or equal to d before adding liquidity because of rounding
+        if d_before_addition_liquidity >= d_after_addition_liquidity {
+            return Err(ContractError::LiquidityAmountTooSmall {});
+        }
+
+        // Charge the fee on the deviation of every deposit from a balanced deposit, which is about
+        // what swapping the excess into the other assets would cost. The fee rate is fee * N / (4 * (N - 1))
+        let mut balances_after_fee = new_balances.clone();
+        for i in 0..pools.len() {
+            let ideal_balance = old_balances[i]
+                .multiply_ratio(d_after_addition_liquidity, d_before_addition_liquidity);
+            let difference = if ideal_balance > new_balances[i] {
+                ideal_balance - new_balances[i]
+            } else {
+                new_balances[i] - ideal_balance
+            };
+            let fee = (difference * total_fee_rate).multiply_ratio(n_coins, 4 * (n_coins - 1));
+            balances_after_fee[i] = new_balances[i].checked_sub(fee)?;
+            imbalance_fees[i] = adjust_precision(fee, greater_precision, token_precisions[i])?;
+        }
+
+        let d_after_fee = compute_d(leverage, &to_u128(&balances_after_fee))
+            .ok_or(ContractError::LiquidityAmountTooSmall {})?;
+        if d_before_addition_liquidity >= d_after_fee {
+            return Err(ContractError::LiquidityAmountTooSmall {});
+        }
+
+        // The rest of the fee stays in the pool and goes to the current liquidity providers
+        total_share.multiply_ratio(
+            d_after_fee - d_before_addition_liquidity,
+            d_before_addition_liquidity,
+        )
+    };
+
+    if share.is_zero() {
+        return Err(ContractError::LiquidityAmountTooSmall {});
+    }
+
+    Ok((share, imbalance_fees))
+}
+
+/// ## Description
+/// Returns the amount of pool assets that correspond to an amount of LP tokens.
+/// ## Params
+/// * **pools** is a slice with objects of type [`Asset`]. These are the assets in the pool.
+///
+/// * **amount** is an object of type [`Uint128`]. This is the amount of LP tokens to calculate underlying amounts for.
+///
+/// * **total_share** is an object of type [`Uint128`]. This is the total amount of LP tokens currently issued by the pool.
+pub fn get_share_in_assets(pools: &[Asset], amount: Uint128, total_share: Uint128) -> Vec<Asset> {
+    let mut share_ratio = Decimal::zero();
+    if !total_share.is_zero() {
+        share_ratio = Decimal::from_ratio(amount, total_share);
+    }
+
+    pools
+        .iter()
+        .map(|pool| Asset {
+            info: pool.info.clone(),
+            amount: pool.amount * share_ratio,
+        })
+        .collect()
+}
+
+/// ## Description
+/// This is an internal function that checks the withdrawn assets against the minimum amounts
+/// that the LP token holder wants to receive. Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+/// ## Params
+/// * **refund_assets** are an array of [`Asset`] type items. These are the assets that will be withdrawn.
+///
+/// * **min_assets_to_receive** is an [`Option`] field of type [`Vec<Asset>`]. These are the minimum
+/// amounts of assets to receive.
+fn assert_min_assets_to_receive(
+    refund_assets: &[Asset],
+    min_assets_to_receive: Option<Vec<Asset>>,
+) -> Result<(), ContractError> {
+    for min_asset in min_assets_to_receive.unwrap_or_default() {
+        let refund_asset = refund_assets
+            .iter()
+            .find(|asset| asset.info.equal(&min_asset.info))
+            .ok_or(ContractError::AssetMismatch {})?;
+        if refund_asset.amount < min_asset.amount {
+            return Err(ContractError::MinAssetsToReceiveAssertion {});
+        }
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Checks that the deadline of a message has not passed yet.
+/// Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+/// ## Params
+/// * **env** is an object of type [`Env`].
+///
+/// * **deadline** is an [`Option`] field of type [`u64`]. This is the timestamp (in seconds) after which the message fails.
+fn assert_deadline(env: &Env, deadline: Option<u64>) -> Result<(), ContractError> {
+    if let Some(deadline) = deadline {
+        if env.block.time.seconds() > deadline {
+            return Err(ContractError::DeadlineExpired {});
+        }
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Checks that the callback contract address is valid and that the output of an action is not
+/// also sent to a receiver. Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+/// ## Params
+/// * **api** is an object of type [`Api`].
+///
+/// * **callback** is an [`Option`] field of type [`Callback`]. This is the contract that receives the output.
+///
+/// * **has_receiver** is the field of type [`bool`]. Determines whether a receiver was specified as well.
+fn assert_callback(
+    api: &dyn Api,
+    callback: &Option<Callback>,
+    has_receiver: bool,
+) -> Result<(), ContractError> {
+    if let Some(callback) = callback {
+        if has_receiver {
+            return Err(ContractError::CallbackWithReceiver {});
+        }
+        addr_validate_to_lower(api, &callback.contract)?;
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Performs a swap with the specified parameters.
+/// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] with the
+/// specified attributes if the operation was successful.
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **sender** is an object of type [`Addr`]. This is the default recipient of the swap operation.
+///
+/// * **offer_asset** is an object of type [`Asset`]. This is the asset to swap.
+///
+/// * **ask_asset_info** is an object of type [`AssetInfo`]. This is the asset to receive.
+///
+/// * **belief_price** is an object of type [`Option<Decimal>`]. This is used to calculate the maximum spread.
+///
+/// * **max_spread** is an object of type [`Option<Decimal>`]. This is the maximum spread allowed for the swap.
+///
+/// * **to** is an object of type [`Option<Addr>`]. This is the address that receives ask tokens.
+/// NOTE - the address that wants to swap should approve the pair contract to pull the offer token.
+///
+/// * **callback** is an [`Option`] field of type [`Callback`]. This is the contract that receives
+/// the ask assets along with a message.
+#[allow(clippy::too_many_arguments)]
+pub fn swap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    offer_asset: Asset,
+    ask_asset_info: AssetInfo,
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    to: Option<Addr>,
+    callback: Option<Callback>,
+) -> Result<Response, ContractError> {
+    offer_asset.assert_sent_native_token_balance(&info)?;
+
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    // The offer assets were already sent to the pair and must not be counted in the pool
+    let mut pools: Vec<Asset> = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address.clone())?;
+    let (offer_index, ask_index) = find_swap_indexes(&pools, &offer_asset.info, &ask_asset_info)
+        .ok_or(ContractError::AssetMismatch {})?;
+    pools[offer_index].amount = pools[offer_index].amount.checked_sub(offer_asset.amount)?;
+
+    // Get fee info from the factory
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
+
+    let offer_amount = offer_asset.amount;
+    let (return_amount, spread_amount, commission_amount) = compute_swap(
+        &pools,
+        &query_token_precisions(&deps.querier, &pools)?,
+        offer_index,
+        ask_index,
+        offer_amount,
+        fee_info.total_fee_rate,
+        compute_current_amp(&config, &env)?,
+    )?;
+
+    // Check the max spread limit (if it was specified)
+    assert_max_spread(
+        belief_price,
+        max_spread,
+        offer_amount,
+        return_amount + commission_amount,
+        spread_amount,
+    )?;
+
+    // Compute the tax for the ask asset
+    let return_asset = Asset {
+        info: ask_asset_info.clone(),
+        amount: return_amount,
+    };
+
+    let tax_amount = return_asset.compute_tax(&deps.querier)?;
+
+    assert_callback(deps.api, &callback, to.is_some())?;
+    let receiver = match &callback {
+        Some(callback) => Addr::unchecked(&callback.contract),
+        None => to.unwrap_or_else(|| sender.clone()),
+    };
+
+    let (mut messages, callback_messages): (Vec<CosmosMsg>, Vec<CosmosMsg>) = match callback {
+        // The callback contract gets the return assets after all the other messages
+        Some(callback) => (vec![], callback.into_msgs(&deps.querier, &[return_asset])?),
+        None => (
+            vec![return_asset.into_msg(&deps.querier, receiver.clone())?],
+            vec![],
+        ),
+    };
+
+    // Maker fee
+    let mut maker_fee_amount = Uint128::new(0);
+    if let Some(fee_address) = fee_info.fee_address {
+        if let Some(f) = calculate_maker_fee(
+            ask_asset_info.clone(),
+            commission_amount,
+            fee_info.maker_fee_rate,
+        ) {
+            messages.push(f.clone().into_msg(&deps.querier, fee_address)?);
+            maker_fee_amount = f.amount;
+        }
+    }
+
+    record_swap(
+        deps.storage,
+        env.block.time.seconds(),
+        &offer_asset,
+        &Asset {
+            info: ask_asset_info.clone(),
+            amount: return_amount,
+        },
+        commission_amount,
+        maker_fee_amount,
+    )?;
+
+    messages.extend(callback_messages);
+
+    Ok(Response::new()
+        .add_messages(
+            // 1. send collateral token from the contract to a user
+            // 2. send inactive commission to collector
+            messages,
+        )
+        .add_attribute("action", "swap")
+        .add_attribute("sender", sender.as_str())
+        .add_attribute("receiver", receiver.as_str())
+        .add_attribute("offer_asset", offer_asset.info.to_string())
+        .add_attribute("ask_asset", ask_asset_info.to_string())
+        .add_attribute("offer_amount", offer_amount.to_string())
+        .add_attribute("return_amount", return_amount.to_string())
+        .add_attribute("tax_amount", tax_amount.to_string())
+        .add_attribute("spread_amount", spread_amount.to_string())
+        .add_attribute("commission_amount", commission_amount.to_string())
+        .add_attribute("maker_fee_amount", maker_fee_amount.to_string()))
+}
+
+/// ## Description
+/// Calculates the amount of fees the Maker contract gets according to specified pair parameters.
+/// Returns a [`None`] if the Maker fee is zero, otherwise returns a [`Asset`] struct with the specified attributes.
+/// ## Params
+/// * **pool_info** is an object of type [`AssetInfo`]. Contains information about the pool asset for which the commission will be calculated.
+///
+/// * **commission_amount** is an object of type [`Uint128`]. This is the total amount of fees charged for a swap.
+///
+/// * **maker_commission_rate** is an object of type [`Decimal`]. This is the percentage of fees that go to the Maker contract.
+pub fn calculate_maker_fee(
+    pool_info: AssetInfo,
+    commission_amount: Uint128,
+    maker_commission_rate: Decimal,
+) -> Option<Asset> {
+    let maker_fee: Uint128 = commission_amount * maker_commission_rate;
+    if maker_fee.is_zero() {
+        return None;
+    }
+
+    Some(Asset {
+        info: pool_info,
+        amount: maker_fee,
+    })
+}
+
+/// ## Description
+/// Exposes all the queries available in the contract.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **msg** is an object of type [`QueryMsg`].
+///
+/// ## Queries
+/// * **QueryMsg::Pair {}** Returns information about the pair in an object of type [`MultiAssetPairInfo`].
+///
+/// * **QueryMsg::Pool {}** Returns information about the amount of assets in the pair contract as
+/// well as the amount of LP tokens issued using an object of type [`PoolResponse`].
+///
+/// * **QueryMsg::Config {}** Returns the configuration for the pair contract using a [`ConfigResponse`] object.
+///
+/// * **QueryMsg::Share { amount }** Returns the amount of assets that could be withdrawn from the pool
+/// using a specific amount of LP tokens. The result is returned in a vector that contains objects of type [`Asset`].
+///
+/// * **QueryMsg::Simulation { offer_asset, ask_asset_info }** Returns the result of a swap simulation using
+/// a [`SimulationResponse`] object.
+///
+/// * **QueryMsg::ReverseSimulation { offer_asset_info, ask_asset }** Returns the result of a reverse swap
+/// simulation using a [`ReverseSimulationResponse`] object.
+///
+/// * **QueryMsg::VirtualPrice {}** Returns the pool invariant divided by the LP token supply using a
+/// [`VirtualPriceResponse`] object.
+///
+/// * **QueryMsg::PoolInvariant {}** Returns the current pool invariant and amplification using a
+/// [`PoolInvariantResponse`] object.
+///
+/// * **QueryMsg::SimulateProvide { assets }** Returns the amount of LP tokens minted for a deposit and
+/// the imbalance fees charged on it using a [`SimulateProvideResponse`] object.
+///
+/// * **QueryMsg::SimulateWithdraw { lp_amount, ask_asset_info }** Returns the assets received for burning
+/// LP tokens using a [`SimulateWithdrawResponse`] object.
+///
+/// * **QueryMsg::Stats {}** Returns the cumulative swap volume and commissions of the pair using
+/// a [`astroport::stats::StatsResponse`] object.
+///
+/// * **QueryMsg::DailyStats { start_after, limit }** Returns the swap volume and commissions of the pair
+/// by day using a [`astroport::stats::DailyStatsResponse`] object.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Pair {} => to_binary(&CONFIG.load(deps.storage)?.pair_info),
+        QueryMsg::Pool {} => to_binary(&query_pool(deps, env)?),
+        QueryMsg::Config {} => to_binary(&query_config(deps, env)?),
+        QueryMsg::Share { amount } => to_binary(&query_share(deps, env, amount)?),
+        QueryMsg::Simulation {
+            offer_asset,
+            ask_asset_info,
+        } => to_binary(&query_simulation(deps, env, offer_asset, ask_asset_info)?),
+        QueryMsg::ReverseSimulation {
+            offer_asset_info,
+            ask_asset,
+        } => to_binary(&query_reverse_simulation(
+            deps,
+            env,
+            offer_asset_info,
+            ask_asset,
+        )?),
+        QueryMsg::VirtualPrice {} => to_binary(&query_virtual_price(deps, env)?),
+        QueryMsg::PoolInvariant {} => to_binary(&query_pool_invariant(deps, env)?),
+        QueryMsg::SimulateProvide { assets } => {
+            to_binary(&query_simulate_provide(deps, env, assets)?)
+        }
+        QueryMsg::SimulateWithdraw {
+            lp_amount,
+            ask_asset_info,
+        } => to_binary(&query_simulate_withdraw(
+            deps,
+            env,
+            lp_amount,
+            ask_asset_info,
+        )?),
+        QueryMsg::Stats {} => to_binary(&query_stats(deps.storage)?),
+        QueryMsg::DailyStats { start_after, limit } => {
+            to_binary(&query_daily_stats(deps.storage, start_after, limit)?)
+        }
+    }
+}
+
+/// ## Description
+/// Returns the amounts of assets in the pair contract as well as the amount of LP
+/// tokens currently minted in an object of type [`PoolResponse`].
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+pub fn query_pool(deps: Deps, env: Env) -> StdResult<PoolResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (assets, total_share) = pool_info(deps, &env, &config)?;
+
+    Ok(PoolResponse {
+        assets,
+        total_share,
+    })
+}
+
+/// ## Description
+/// Returns the amount of assets that could be withdrawn from the pool using a specific amount of LP tokens.
+/// The result is returned in a vector that contains objects of type [`Asset`].
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **amount** is an object of type [`Uint128`]. This is the amount of LP tokens for which we calculate associated amounts of assets.
+pub fn query_share(deps: Deps, env: Env, amount: Uint128) -> StdResult<Vec<Asset>> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps, &env, &config)?;
+
+    Ok(get_share_in_assets(&pools, amount, total_share))
+}
+
+/// ## Description
+/// Returns the amount of LP tokens that [`provide_liquidity`] mints for a deposit together with the imbalance fees
+/// charged on it in a [`SimulateProvideResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **assets** is a vector with objects of type [`Asset`]. These are the assets to deposit.
+pub fn query_simulate_provide(
+    deps: Deps,
+    env: Env,
+    assets: Vec<Asset>,
+) -> StdResult<SimulateProvideResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps, &env, &config)?;
+    let deposits =
+        get_deposits(&assets, &pools).map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    // Get fee info from the factory
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
+
+    let (share, imbalance_fees) = compute_share(
+        &deps.querier,
+        &env,
+        &config,
+        &pools,
+        &deposits,
+        total_share,
+        fee_info.total_fee_rate,
+    )
+    .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    Ok(SimulateProvideResponse {
+        share,
+        imbalance_fees: pools
+            .iter()
+            .zip(imbalance_fees.iter())
+            .map(|(pool, fee)| Asset {
+                info: pool.info.clone(),
+                amount: *fee,
+            })
+            .collect(),
+    })
+}
+
+/// ## Description
+/// Returns the assets that [`withdraw_liquidity`] returns for burning LP tokens in a [`SimulateWithdrawResponse`] object.
+/// The pair only supports balanced withdrawals, so the query fails if `ask_asset_info` is set.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **lp_amount** is an object of type [`Uint128`]. This is the amount of LP tokens to burn.
+///
+/// * **ask_asset_info** is an [`Option`] field of type [`AssetInfo`]. It must be empty.
+pub fn query_simulate_withdraw(
+    deps: Deps,
+    env: Env,
+    lp_amount: Uint128,
+    ask_asset_info: Option<AssetInfo>,
+) -> StdResult<SimulateWithdrawResponse> {
+    if ask_asset_info.is_some() {
+        return Err(StdError::generic_err(
+            "Single asset withdrawals are not supported by this pair",
+        ));
+    }
+
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps, &env, &config)?;
+
+    Ok(SimulateWithdrawResponse {
+        assets: get_share_in_assets(&pools, lp_amount, total_share),
+        commission_amount: Uint128::zero(),
+    })
+}
+
+/// ## Description
+/// Returns information about a swap simulation in a [`SimulationResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **offer_asset** is an object of type [`Asset`]. This is the asset to swap as well as an amount of the said asset.
+///
+/// * **ask_asset_info** is an object of type [`AssetInfo`]. This is the asset to receive.
+pub fn query_simulation(
+    deps: Deps,
+    env: Env,
+    offer_asset: Asset,
+    ask_asset_info: AssetInfo,
+) -> StdResult<SimulationResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, _) = pool_info(deps, &env, &config)?;
+    let (offer_index, ask_index) = find_swap_indexes(&pools, &offer_asset.info, &ask_asset_info)
+        .ok_or_else(|| StdError::generic_err("Given assets don't belong to the pool"))?;
+
+    // Get fee info from the factory
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
+
+    let (return_amount, spread_amount, commission_amount) = compute_swap(
+        &pools,
+        &query_token_precisions(&deps.querier, &pools)?,
+        offer_index,
+        ask_index,
+        offer_asset.amount,
+        fee_info.total_fee_rate,
+        compute_current_amp(&config, &env)?,
+    )?;
+
+    Ok(SimulationResponse {
+        return_amount,
+        spread_amount,
+        commission_amount,
+        total_fee_rate: fee_info.total_fee_rate,
+    })
+}
+
+/// ## Description
+/// Returns information about a reverse swap simulation in a [`ReverseSimulationResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **offer_asset_info** is an object of type [`AssetInfo`]. This is the asset to swap.
+///
+/// * **ask_asset** is an object of type [`Asset`]. This is the asset to swap to as well as the desired
+/// amount of ask assets to receive from the swap.
+pub fn query_reverse_simulation(
+    deps: Deps,
+    env: Env,
+    offer_asset_info: AssetInfo,
+    ask_asset: Asset,
+) -> StdResult<ReverseSimulationResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, _) = pool_info(deps, &env, &config)?;
+    let (offer_index, ask_index) = find_swap_indexes(&pools, &offer_asset_info, &ask_asset.info)
+        .ok_or_else(|| StdError::generic_err("Given assets don't belong to the pool"))?;
+
+    // Get fee info from the factory
+    let fee_info = query_pair_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.contract_addr.clone(),
+    )?;
+
+    let (offer_amount, spread_amount, commission_amount) = compute_offer_amount(
+        &pools,
+        &query_token_precisions(&deps.querier, &pools)?,
+        offer_index,
+        ask_index,
+        ask_asset.amount,
+        fee_info.total_fee_rate,
+        compute_current_amp(&config, &env)?,
+    )?;
+
+    Ok(ReverseSimulationResponse {
+        offer_amount,
+        spread_amount,
+        commission_amount,
+    })
+}
+
+/// ## Description
+/// Returns the pool invariant divided by the LP token supply in a [`VirtualPriceResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+pub fn query_virtual_price(deps: Deps, env: Env) -> StdResult<VirtualPriceResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps, &env, &config)?;
+    if total_share.is_zero() {
+        return Err(StdError::generic_err("The pool has no liquidity"));
+    }
+
+    let (d, _, greater_precision) = compute_invariant(deps, &env, &config, &pools)?;
+    let liquidity_token_precision = query_token_precision(
+        &deps.querier,
+        AssetInfo::Token {
+            contract_addr: config.pair_info.liquidity_token,
+        },
+    )?;
+
+    Ok(VirtualPriceResponse {
+        virtual_price: Decimal::from_ratio(
+            adjust_precision(d, greater_precision, liquidity_token_precision)?,
+            total_share,
+        ),
+    })
+}
+
+/// ## Description
+/// Returns the current pool invariant and amplification in a [`PoolInvariantResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+pub fn query_pool_invariant(deps: Deps, env: Env) -> StdResult<PoolInvariantResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, _) = pool_info(deps, &env, &config)?;
+    let (d, amp, _) = compute_invariant(deps, &env, &config, &pools)?;
+
+    Ok(PoolInvariantResponse {
+        d,
+        amp: Decimal::from_ratio(amp, AMP_PRECISION),
+    })
+}
+
+/// ## Description
+/// Returns the pool invariant D expressed with the greatest precision of the pool assets,
+/// the current amplification and that precision.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **config** is an object of type [`Config`].
+///
+/// * **pools** is a slice with objects of type [`Asset`]. These are the assets available in the pool.
+fn compute_invariant(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    pools: &[Asset],
+) -> StdResult<(Uint128, u64, u8)> {
+    let token_precisions = query_token_precisions(&deps.querier, pools)?;
+    let greater_precision = token_precisions.iter().copied().max().unwrap();
+    let balances = adjust_balances(
+        pools.iter().map(|pool| pool.amount),
+        &token_precisions,
+        greater_precision,
+    )?;
+
+    let amp = compute_current_amp(config, env)?;
+    let d = compute_d(
+        amp.checked_mul(pools.len() as u64).unwrap(),
+        &to_u128(&balances),
+    )
+    .ok_or_else(|| StdError::generic_err("Failed to compute the pool invariant"))?;
+
+    Ok((Uint128::new(d), amp, greater_precision))
+}
+
+/// ## Description
+/// Returns the pair contract configuration in a [`ConfigResponse`] object.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+pub fn query_config(deps: Deps, env: Env) -> StdResult<ConfigResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse {
+        // The pair doesn't accumulate prices
+        block_time_last: 0,
+        params: Some(to_binary(&StablePoolConfig {
+            amp: Decimal::from_ratio(compute_current_amp(&config, &env)?, AMP_PRECISION),
+            fee_on_transfer: false,
+        })?),
+    })
+}
+
+/// ## Description
+/// Returns the result of a swap.
+/// ## Params
+/// * **pools** is a slice with objects of type [`Asset`]. These are the assets in the pool.
+///
+/// * **token_precisions** is a slice with [`u8`] items. These are the precisions of the pool assets.
+///
+/// * **offer_index** is an object of type [`usize`]. This is the position of the offer asset in the pool.
+///
+/// * **ask_index** is an object of type [`usize`]. This is the position of the ask asset in the pool.
+///
+/// * **offer_amount** is an object of type [`Uint128`]. This is the amount of offer assets to swap.
+///
+/// * **commission_rate** is an object of type [`Decimal`]. This is the total amount of fees charged for the swap.
+///
+/// * **amp** is an object of type [`u64`]. This is the pool amplification used to calculate the swap result.
+fn compute_swap(
+    pools: &[Asset],
+    token_precisions: &[u8],
+    offer_index: usize,
+    ask_index: usize,
+    offer_amount: Uint128,
+    commission_rate: Decimal,
+    amp: u64,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    // offer => ask
+
+    let greater_precision = token_precisions.iter().copied().max().unwrap();
+    let ask_precision = token_precisions[ask_index];
+    let balances = adjust_balances(
+        pools.iter().map(|pool| pool.amount),
+        token_precisions,
+        greater_precision,
+    )?;
+    let offer_amount = adjust_precision(
+        offer_amount,
+        token_precisions[offer_index],
+        greater_precision,
+    )?;
+
+    let return_amount = Uint128::new(
+        calc_ask_amount(
+            &to_u128(&balances),
+            offer_index,
+            ask_index,
+            offer_amount.u128(),
+            amp,
+        )
+        .ok_or_else(|| StdError::generic_err("Failed to compute the swap amount"))?,
+    );
+
+    // We assume the assets should stay in a 1:1 ratio, so the true exchange rate is 1. So any exchange rate <1 could be considered the spread
+    let spread_amount = offer_amount.saturating_sub(return_amount);
+
+    let commission_amount: Uint128 = return_amount * commission_rate;
+
+    // The commission will be absorbed by the pool
+    let return_amount: Uint128 = return_amount.checked_sub(commission_amount).unwrap();
+
+    let return_amount = adjust_precision(return_amount, greater_precision, ask_precision)?;
+    let spread_amount = adjust_precision(spread_amount, greater_precision, ask_precision)?;
+    let commission_amount = adjust_precision(commission_amount, greater_precision, ask_precision)?;
+
+    Ok((return_amount, spread_amount, commission_amount))
+}
+
+/// ## Description
+/// Returns an amount of offer assets for a specified amount of ask assets.
+/// ## Params
+/// * **pools** is a slice with objects of type [`Asset`]. These are the assets in the pool.
+///
+/// * **token_precisions** is a slice with [`u8`] items. These are the precisions of the pool assets.
+///
+/// * **offer_index** is an object of type [`usize`]. This is the position of the offer asset in the pool.
+///
+/// * **ask_index** is an object of type [`usize`]. This is the position of the ask asset in the pool.
+///
+/// * **ask_amount** is an object of type [`Uint128`]. This is the amount of ask assets to swap to.
+///
+/// * **commission_rate** is an object of type [`Decimal`]. This is the total amount of fees charged for the swap.
+///
+/// * **amp** is an object of type [`u64`]. This is the pool amplification used to calculate the swap result.
+fn compute_offer_amount(
+    pools: &[Asset],
+    token_precisions: &[u8],
+    offer_index: usize,
+    ask_index: usize,
+    ask_amount: Uint128,
+    commission_rate: Decimal,
+    amp: u64,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    // ask => offer
+
+    let greater_precision = token_precisions.iter().copied().max().unwrap();
+    let ask_precision = token_precisions[ask_index];
+    let balances = adjust_balances(
+        pools.iter().map(|pool| pool.amount),
+        token_precisions,
+        greater_precision,
+    )?;
+    let ask_amount = adjust_precision(ask_amount, ask_precision, greater_precision)?;
+
+    let one_minus_commission = Decimal256::one() - Decimal256::from(commission_rate);
+    let inv_one_minus_commission: Decimal = (Decimal256::one() / one_minus_commission).into();
+    let before_commission_deduction = ask_amount * inv_one_minus_commission;
+
+    let offer_amount = Uint128::new(
+        calc_offer_amount(
+            &to_u128(&balances),
+            offer_index,
+            ask_index,
+            before_commission_deduction.u128(),
+            amp,
+        )
+        .ok_or_else(|| StdError::generic_err("Failed to compute the offer amount"))?,
+    );
+
+    // We assume the assets should stay in a 1:1 ratio, so the true exchange rate is 1. Any exchange rate < 1 could be considered the spread
+    let spread_amount = offer_amount.saturating_sub(before_commission_deduction);
+
+    let commission_amount = before_commission_deduction * commission_rate;
+
+    let offer_amount = adjust_precision(
+        offer_amount,
+        greater_precision,
+        token_precisions[offer_index],
+    )?;
+    let spread_amount = adjust_precision(spread_amount, greater_precision, ask_precision)?;
+    let commission_amount = adjust_precision(commission_amount, greater_precision, ask_precision)?;
+
+    Ok((offer_amount, spread_amount, commission_amount))
+}
+
+/// ## Description
+/// Returns the positions of the offer and the ask assets in the pool,
+/// or [`None`] if either of them doesn't belong to the pool or if they are the same asset.
+/// ## Params
+/// * **pools** is a slice with objects of type [`Asset`]. These are the assets in the pool.
+///
+/// * **offer_asset_info** is an object of type [`AssetInfo`]. This is the asset to swap.
+///
+/// * **ask_asset_info** is an object of type [`AssetInfo`]. This is the asset to receive.
+fn find_swap_indexes(
+    pools: &[Asset],
+    offer_asset_info: &AssetInfo,
+    ask_asset_info: &AssetInfo,
+) -> Option<(usize, usize)> {
+    let offer_index = pools
+        .iter()
+        .position(|pool| pool.info.equal(offer_asset_info))?;
+    let ask_index = pools
+        .iter()
+        .position(|pool| pool.info.equal(ask_asset_info))?;
+
+    if offer_index == ask_index {
+        return None;
+    }
+
+    Some((offer_index, ask_index))
+}
+
+/// ## Description
+/// Returns the precisions of all the pool assets.
+/// ## Params
+/// * **querier** is an object of type [`QuerierWrapper`].
+///
+/// * **pools** is a slice with objects of type [`Asset`]. These are the assets in the pool.
+fn query_token_precisions(querier: &QuerierWrapper, pools: &[Asset]) -> StdResult<Vec<u8>> {
+    pools
+        .iter()
+        .map(|pool| query_token_precision(querier, pool.info.clone()))
+        .collect()
+}
+
+/// ## Description
+/// Returns the specified amounts expressed with the same precision.
+/// ## Params
+/// * **amounts** is an iterator over [`Uint128`] items. These are the amounts in the precisions of their assets.
+///
+/// * **token_precisions** is a slice with [`u8`] items. These are the precisions of the amounts.
+///
+/// * **new_precision** is an object of type [`u8`]. This is the precision to use when returning the amounts.
+fn adjust_balances(
+    amounts: impl Iterator<Item = Uint128>,
+    token_precisions: &[u8],
+    new_precision: u8,
+) -> StdResult<Vec<Uint128>> {
+    amounts
+        .zip(token_precisions.iter())
+        .map(|(amount, precision)| adjust_precision(amount, *precision, new_precision))
+        .collect()
+}
+
+/// ## Description
+/// Converts a slice of [`Uint128`] values to [`u128`] values.
+fn to_u128(values: &[Uint128]) -> Vec<u128> {
+    values.iter().map(|value| value.u128()).collect()
+}
+
+/// ## Description
+/// Return a value using a newly specified precision.
+/// ## Params
+/// * **value** is an object of type [`Uint128`]. This is the value that will have its precision adjusted.
+///
+/// * **current_precision** is an object of type [`u8`]. This is the `value`'s current precision
+///
+/// * **new_precision** is an object of type [`u8`]. This is the new precision to use when returning the `value`.
+fn adjust_precision(
+    value: Uint128,
+    current_precision: u8,
+    new_precision: u8,
+) -> StdResult<Uint128> {
+    Ok(match current_precision.cmp(&new_precision) {
+        Ordering::Equal => value,
+        Ordering::Less => value.checked_mul(Uint128::new(
+            10_u128.pow((new_precision - current_precision) as u32),
+        ))?,
+        Ordering::Greater => value.checked_div(Uint128::new(
+            10_u128.pow((current_precision - new_precision) as u32),
+        ))?,
+    })
+}
+
+/// ## Description
+/// Returns a [`ContractError`] on failure.
+/// If `belief_price` and `max_spread` are both specified, we compute a new spread,
+/// otherwise we just use the swap spread to check `max_spread`.
+/// ## Params
+/// * **belief_price** is an object of type [`Option<Decimal>`]. This is the belief price used in the swap.
+///
+/// * **max_spread** is an object of type [`Option<Decimal>`]. This is the
+/// max spread allowed so that the swap can be executed successfuly.
+///
+/// * **offer_amount** is an object of type [`Uint128`]. This is the amount of assets to swap.
+///
+/// * **return_amount** is an object of type [`Uint128`]. This is the amount of assets to receive from the swap.
+///
+/// * **spread_amount** is an object of type [`Uint128`]. This is the spread used in the swap.
+pub fn assert_max_spread(
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    offer_amount: Uint128,
+    return_amount: Uint128,
+    spread_amount: Uint128,
+) -> Result<(), ContractError> {
+    let default_spread = Decimal::from_str(DEFAULT_SLIPPAGE)?;
+    let max_allowed_spread = Decimal::from_str(MAX_ALLOWED_SLIPPAGE)?;
+
+    let max_spread = max_spread.unwrap_or(default_spread);
+    if max_spread.gt(&max_allowed_spread) {
+        return Err(ContractError::AllowedSpreadAssertion {});
+    }
+
+    if let Some(belief_price) = belief_price {
+        let expected_return =
+            offer_amount * Decimal::from(Decimal256::one() / Decimal256::from(belief_price));
+        let spread_amount = expected_return
+            .checked_sub(return_amount)
+            .unwrap_or_else(|_| Uint128::zero());
+
+        if return_amount < expected_return
+            && Decimal::from_ratio(spread_amount, expected_return) > max_spread
+        {
+            return Err(ContractError::MaxSpreadAssertion {});
+        }
+    } else if Decimal::from_ratio(spread_amount, return_amount + spread_amount) > max_spread {
+        return Err(ContractError::MaxSpreadAssertion {});
+    }
+
+    Ok(())
+}
+
+/// ## Description
+/// Returns information about the pool.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **config** is an object of type [`Config`].
+pub fn pool_info(deps: Deps, env: &Env, config: &Config) -> StdResult<(Vec<Asset>, Uint128)> {
+    let pools: Vec<Asset> = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address.clone())?;
+    let total_share: Uint128 =
+        query_supply(&deps.querier, config.pair_info.liquidity_token.clone())?;
+
+    Ok((pools, total_share))
+}
+
+/// ## Description
+/// Returns the amounts of the specified assets formatted for an attribute.
+/// ## Params
+/// * **assets** is a slice with objects of type [`Asset`].
+fn format_assets(assets: &[Asset]) -> String {
+    assets
+        .iter()
+        .map(|asset| asset.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// ## Description
+/// Returns the specified amounts of the pool assets formatted for an attribute.
+/// ## Params
+/// * **amounts** is a slice with [`Uint128`] items.
+///
+/// * **pools** is a slice with objects of type [`Asset`]. These are the assets in the pool.
+fn format_amounts(amounts: &[Uint128], pools: &[Asset]) -> String {
+    amounts
+        .iter()
+        .zip(pools.iter())
+        .map(|(amount, pool)| format!("{}{}", amount, pool.info))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// ## Description
+/// Updates the pool configuration with the specified parameters in the `params` variable.
+/// Returns a [`ContractError`] as a failure, otherwise returns a [`Response`] with the specified
+/// attributes if the operation was successful
+/// ## Params
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **info** is an object of type [`MessageInfo`].
+///
+/// * **params** is an object of type [`Binary`]. These are the the new parameter values.
+pub fn update_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    params: Binary,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, config.factory_addr.clone())?;
+
+    if info.sender != factory_config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    match from_binary::<StablePoolUpdateParams>(&params)? {
+        StablePoolUpdateParams::StartChangingAmp {
+            next_amp,
+            next_amp_time,
+        } => start_changing_amp(config, deps, env, next_amp, next_amp_time)?,
+        StablePoolUpdateParams::StopChangingAmp {} => stop_changing_amp(config, deps, env)?,
+    }
+
+    Ok(Response::default())
+}
+
+/// ## Description
+/// Start changing the AMP value. Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+/// ## Params
+/// * **mut config** is an object of type [`Config`]. This is a mutable reference to the pool configuration.
+///
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **next_amp** is an object of type [`u64`]. This is the new value for AMP.
+///
+/// * **next_amp_time** is an object of type [`u64`]. This is the end time when the pool amplification will be equal to `next_amp`.
+fn start_changing_amp(
+    mut config: Config,
+    deps: DepsMut,
+    env: Env,
+    next_amp: u64,
+    next_amp_time: u64,
+) -> Result<(), ContractError> {
+    if next_amp == 0 || next_amp > MAX_AMP {
+        return Err(ContractError::IncorrectAmp {});
+    }
+
+    let current_amp = compute_current_amp(&config, &env)?;
+
+    let next_amp_with_precision = next_amp * AMP_PRECISION;
+
+    if next_amp_with_precision * MAX_AMP_CHANGE < current_amp
+        || next_amp_with_precision > current_amp * MAX_AMP_CHANGE
+    {
+        return Err(ContractError::MaxAmpChangeAssertion {});
+    }
+
+    let block_time = env.block.time.seconds();
+
+    if block_time < config.init_amp_time + MIN_AMP_CHANGING_TIME
+        || next_amp_time < block_time + MIN_AMP_CHANGING_TIME
+    {
+        return Err(ContractError::MinAmpChangingTimeAssertion {});
+    }
+
+    config.init_amp = current_amp;
+    config.next_amp = next_amp_with_precision;
+    config.init_amp_time = block_time;
+    config.next_amp_time = next_amp_time;
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(())
+}
+
+/// ## Description
+/// Stop changing the AMP value. Returns [`Ok`].
+/// ## Params
+/// * **mut config** is an object of type [`Config`]. This is a mutable reference to the pool configuration.
+///
+/// * **deps** is an object of type [`DepsMut`].
+///
+/// * **env** is an object of type [`Env`].
+fn stop_changing_amp(mut config: Config, deps: DepsMut, env: Env) -> StdResult<()> {
+    let current_amp = compute_current_amp(&config, &env)?;
+    let block_time = env.block.time.seconds();
+
+    config.init_amp = current_amp;
+    config.next_amp = current_amp;
+    config.init_amp_time = block_time;
+    config.next_amp_time = block_time;
+
+    // now (block_time < next_amp_time) is always False, so we return the saved AMP
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(())
+}
+
+/// ## Description
+/// Compute the current pool amplification coefficient (AMP).
+/// ## Params
+/// * **config** is an object of type [`Config`].
+///
+/// * **env** is an object of type [`Env`].
+fn compute_current_amp(config: &Config, env: &Env) -> StdResult<u64> {
+    let block_time = env.block.time.seconds();
+
+    if block_time < config.next_amp_time {
+        let elapsed_time =
+            Uint128::from(block_time).checked_sub(Uint128::from(config.init_amp_time))?;
+        let time_range =
+            Uint128::from(config.next_amp_time).checked_sub(Uint128::from(config.init_amp_time))?;
+        let init_amp = Uint128::from(config.init_amp);
+        let next_amp = Uint128::from(config.next_amp);
+
+        if config.next_amp > config.init_amp {
+            let amp_range = next_amp - init_amp;
+            let res = init_amp + (amp_range * elapsed_time).checked_div(time_range)?;
+            Ok(res.u128() as u64)
+        } else {
+            let amp_range = init_amp - next_amp;
+            let res = init_amp - (amp_range * elapsed_time).checked_div(time_range)?;
+            Ok(res.u128() as u64)
+        }
+    } else {
+        Ok(config.next_amp)
+    }
+}
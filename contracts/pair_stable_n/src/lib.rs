@@ -0,0 +1,10 @@
+pub mod contract;
+pub mod math;
+pub mod state;
+
+pub mod error;
+
+mod response;
+
+#[cfg(test)]
+mod testing;
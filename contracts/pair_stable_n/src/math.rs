@@ -0,0 +1,215 @@
+use std::convert::TryFrom;
+
+use astroport::U256;
+
+const ITERATIONS: u8 = 32;
+
+pub const MAX_AMP: u64 = 1_000_000;
+pub const MAX_AMP_CHANGE: u64 = 10;
+pub const MIN_AMP_CHANGING_TIME: u64 = 86400;
+pub const AMP_PRECISION: u64 = 100;
+
+/// ## Description
+/// Calculates the ask amount (the amount of tokens swapped to).
+/// ## Params
+/// * **balances** is a slice of [`u128`] items. These are the amounts of all tokens currently in a stableswap pool.
+///
+/// * **offer_index** is an object of type [`usize`]. This is the position of the offer token in `balances`.
+///
+/// * **ask_index** is an object of type [`usize`]. This is the position of the ask token in `balances`.
+///
+/// * **offer_amount** is an object of type [`u128`]. This is the amount of offer tokens to swap.
+///
+/// * **amp** is an object of type [`u64`]. This is the pool's amplification parameter.
+pub fn calc_ask_amount(
+    balances: &[u128],
+    offer_index: usize,
+    ask_index: usize,
+    offer_amount: u128,
+    amp: u64,
+) -> Option<u128> {
+    let leverage = amp.checked_mul(u64::try_from(balances.len()).ok()?)?;
+    let d = compute_d(leverage, balances)?;
+
+    let mut new_balances = balances.to_vec();
+    new_balances[offer_index] = new_balances[offer_index].checked_add(offer_amount)?;
+
+    let new_ask_pool = compute_new_balance(leverage, &new_balances, ask_index, d)?;
+
+    balances[ask_index].checked_sub(new_ask_pool)
+}
+
+/// ## Description
+/// Calculates the amount to be swapped (the offer amount).
+/// ## Params
+/// * **balances** is a slice of [`u128`] items. These are the amounts of all tokens currently in a stableswap pool.
+///
+/// * **offer_index** is an object of type [`usize`]. This is the position of the offer token in `balances`.
+///
+/// * **ask_index** is an object of type [`usize`]. This is the position of the ask token in `balances`.
+///
+/// * **ask_amount** is an object of type [`u128`]. This is the amount of ask tokens to receive.
+///
+/// * **amp** is an object of type [`u64`]. This is the pool's amplification parameter.
+pub fn calc_offer_amount(
+    balances: &[u128],
+    offer_index: usize,
+    ask_index: usize,
+    ask_amount: u128,
+    amp: u64,
+) -> Option<u128> {
+    let leverage = amp.checked_mul(u64::try_from(balances.len()).ok()?)?;
+    let d = compute_d(leverage, balances)?;
+
+    let mut new_balances = balances.to_vec();
+    new_balances[ask_index] = new_balances[ask_index].checked_sub(ask_amount)?;
+
+    let new_offer_pool = compute_new_balance(leverage, &new_balances, offer_index, d)?;
+
+    new_offer_pool.checked_sub(balances[offer_index])
+}
+
+/// ## Description
+/// Computes the stableswap invariant (D) for any amount of tokens.
+///
+/// * **Equation**
+///
+/// A * sum(x_i) * n**n + D = A * D * n**n + D**(n+1) / (n**n * prod(x_i))
+///
+/// ## Params
+/// * **leverage** is an object of type [`u64`]. This is the amplification multiplied by the amount of tokens.
+///
+/// * **amounts** is a slice of [`u128`] items. These are the amounts of all tokens in the pool.
+pub fn compute_d(leverage: u64, amounts: &[u128]) -> Option<u128> {
+    let n_coins = u8::try_from(amounts.len()).ok()?;
+    // sum(x_i), a.k.a S
+    let sum_x = amounts
+        .iter()
+        .try_fold(0u128, |sum, amount| sum.checked_add(*amount))?;
+    if sum_x == 0 {
+        return Some(0);
+    }
+
+    let amounts_times_coins = amounts
+        .iter()
+        .map(|amount| checked_u8_mul(&U256::from(*amount), n_coins)?.checked_add(U256::one()))
+        .collect::<Option<Vec<U256>>>()?;
+
+    let mut d_previous: U256;
+    let mut d: U256 = sum_x.into();
+
+    // Newton's method to approximate D
+    for _ in 0..ITERATIONS {
+        let mut d_product = d;
+        for amount_times_coins in &amounts_times_coins {
+            d_product = d_product.checked_mul(d)?.checked_div(*amount_times_coins)?;
+        }
+        d_previous = d;
+        // d = (leverage * sum_x + d_p * n_coins) * d / ((leverage - 1) * d + (n_coins + 1) * d_p);
+        d = calculate_step(&d, leverage, sum_x, &d_product, n_coins)?;
+        // Equality with the precision of 1
+        if d == d_previous {
+            break;
+        }
+    }
+    u128::try_from(d).ok()
+}
+
+/// ## Description
+/// Helper function used to calculate the D invariant as a last step in the `compute_d` public function.
+///
+/// * **Equation**:
+///
+/// d = (leverage * sum_x + d_product * n_coins) * initial_d / ((leverage - 1) * initial_d + (n_coins + 1) * d_product)
+fn calculate_step(
+    initial_d: &U256,
+    leverage: u64,
+    sum_x: u128,
+    d_product: &U256,
+    n_coins: u8,
+) -> Option<U256> {
+    let leverage_mul = U256::from(leverage).checked_mul(sum_x.into())? / AMP_PRECISION;
+    let d_p_mul = checked_u8_mul(d_product, n_coins)?;
+
+    let l_val = leverage_mul.checked_add(d_p_mul)?.checked_mul(*initial_d)?;
+
+    let leverage_sub =
+        initial_d.checked_mul((leverage.checked_sub(AMP_PRECISION)?).into())? / AMP_PRECISION;
+    let n_coins_sum = checked_u8_mul(d_product, n_coins.checked_add(1)?)?;
+
+    let r_val = leverage_sub.checked_add(n_coins_sum)?;
+
+    l_val.checked_div(r_val)
+}
+
+/// ## Description
+/// Computes the balance of the token at `index` that keeps the invariant equal to `d_val`
+/// when all the other tokens have the balances specified in `balances`.
+///
+/// * **Solve for y**
+///
+/// y**2 + y * (sum' - (A*n**n - 1) * D / (A * n**n)) = D ** (n + 1) / (n ** (2 * n) * prod' * A)
+///
+/// y**2 + b*y = c
+///
+/// ## Params
+/// * **leverage** is an object of type [`u64`]. This is the amplification multiplied by the amount of tokens.
+///
+/// * **balances** is a slice of [`u128`] items. The balance at `index` is ignored.
+///
+/// * **index** is an object of type [`usize`]. This is the position of the token to compute the balance for.
+///
+/// * **d_val** is an object of type [`u128`]. This is the invariant to keep.
+pub fn compute_new_balance(
+    leverage: u64,
+    balances: &[u128],
+    index: usize,
+    d_val: u128,
+) -> Option<u128> {
+    let n_coins = u8::try_from(balances.len()).ok()?;
+
+    // Upscale to U256
+    let leverage: U256 = leverage.into();
+    let d_val: U256 = d_val.into();
+
+    // sum' and c = D ** (n + 1) / (n ** (2 * n) * prod' * A) accumulated over all other tokens
+    let mut sum = U256::zero();
+    let mut c = d_val;
+    for (_, balance) in balances.iter().enumerate().filter(|(i, _)| *i != index) {
+        let balance = U256::from(*balance);
+        sum = sum.checked_add(balance)?;
+        c = c
+            .checked_mul(d_val)?
+            .checked_div(checked_u8_mul(&balance, n_coins)?)?;
+    }
+    c = c
+        .checked_mul(d_val)?
+        .checked_mul(U256::from(AMP_PRECISION))?
+        .checked_div(checked_u8_mul(&leverage, n_coins)?)?;
+
+    // b = sum' - (A*n**n - 1) * D / (A * n**n)
+    let b = sum.checked_add(
+        d_val
+            .checked_mul(U256::from(AMP_PRECISION))?
+            .checked_div(leverage)?,
+    )?;
+
+    // Solve for y by approximating: y**2 + b*y = c
+    let mut y_prev: U256;
+    let mut y = d_val;
+    for _ in 0..ITERATIONS {
+        y_prev = y;
+        y = (y.checked_mul(y)?.checked_add(c)?)
+            .checked_div(checked_u8_mul(&y, 2)?.checked_add(b)?.checked_sub(d_val)?)?;
+        if y == y_prev {
+            break;
+        }
+    }
+    u128::try_from(y).ok()
+}
+
+/// ## Description
+/// Returns self multiplied by b.
+fn checked_u8_mul(a: &U256, b: u8) -> Option<U256> {
+    a.checked_mul(U256::from(b))
+}
@@ -0,0 +1,25 @@
+use astroport::asset::MultiAssetPairInfo;
+use cosmwasm_std::Addr;
+use cw_storage_plus::Item;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// ## Description
+/// This structure stores the main parameters of a stableswap pair that holds more than two assets.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    /// The pair information stored in a [`MultiAssetPairInfo`] struct
+    pub pair_info: MultiAssetPairInfo,
+    /// The factory contract address
+    pub factory_addr: Addr,
+    // This is the current amplification used in the pool
+    pub init_amp: u64,
+    // This is the start time when amplification starts to scale up or down
+    pub init_amp_time: u64,
+    // This is the target amplification to reach at `next_amp_time`
+    pub next_amp: u64,
+    // This is the timestamp when the current pool amplification should be `next_amp`
+    pub next_amp_time: u64,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
@@ -0,0 +1,902 @@
+use crate::contract::{execute, instantiate, query, reply, PAIR_TYPE};
+use crate::error::ContractError;
+use crate::math::{calc_ask_amount, calc_offer_amount, compute_d, AMP_PRECISION};
+use astroport_mocks::mock_querier::mock_dependencies;
+
+use crate::response::MsgInstantiateContractResponse;
+use astroport::asset::{Asset, AssetInfo, MultiAssetPairInfo};
+use astroport::factory::PairType;
+use astroport::pair::{
+    MultiAssetInstantiateMsg, PoolInvariantResponse, ReverseSimulationResponse,
+    SimulateProvideResponse, SimulateWithdrawResponse, SimulationResponse, StablePoolParams,
+    VirtualPriceResponse, MINIMUM_LIQUIDITY_AMOUNT,
+};
+use astroport::pair_stable_n::{Cw20HookMsg, ExecuteMsg, PoolResponse, QueryMsg};
+use astroport::stats::StatsResponse;
+use astroport::token::InstantiateMsg as TokenInstantiateMsg;
+use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    attr, from_binary, to_binary, Addr, BankMsg, Coin, ContractResult, CosmosMsg, Decimal, DepsMut,
+    Reply, ReplyOn, StdError, SubMsg, SubMsgExecutionResponse, Uint128, WasmMsg,
+};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
+use proptest::prelude::*;
+use protobuf::Message;
+use sim::StableSwapModel;
+
+fn store_liquidity_token(deps: DepsMut, msg_id: u64, contract_addr: String) {
+    let data = MsgInstantiateContractResponse {
+        contract_address: contract_addr,
+        data: vec![],
+        unknown_fields: Default::default(),
+        cached_size: Default::default(),
+    }
+    .write_to_bytes()
+    .unwrap();
+
+    let reply_msg = Reply {
+        id: msg_id,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(data.into()),
+        }),
+    };
+
+    let _res = reply(deps, mock_env(), reply_msg.clone()).unwrap();
+}
+
+fn asset_infos() -> Vec<AssetInfo> {
+    vec![
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0001"),
+        },
+    ]
+}
+
+fn instantiate_msg(asset_infos: Vec<AssetInfo>) -> MultiAssetInstantiateMsg {
+    MultiAssetInstantiateMsg {
+        factory_addr: String::from("factory"),
+        asset_infos,
+        token_code_id: 10u64,
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_on_transfer: false,
+            })
+            .unwrap(),
+        ),
+    }
+}
+
+/// Sets the pool balances, instantiates the pair and stores its liquidity token
+fn setup_pair(
+    uusd_balance: u128,
+    asset0000_balance: u128,
+    asset0001_balance: u128,
+    total_share: u128,
+) -> cosmwasm_std::OwnedDeps<
+    cosmwasm_std::testing::MockStorage,
+    cosmwasm_std::testing::MockApi,
+    astroport_mocks::mock_querier::WasmMockQuerier,
+> {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(uusd_balance),
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &Uint128::new(total_share))],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(asset0000_balance),
+            )],
+        ),
+        (
+            &String::from("asset0001"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(asset0001_balance),
+            )],
+        ),
+    ]);
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env, info, instantiate_msg(asset_infos())).unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    deps
+}
+
+#[test]
+fn proper_initialization() {
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(123u128))],
+        ),
+        (
+            &String::from("asset0001"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(123u128))],
+        ),
+    ]);
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+
+    // Two assets are served by the regular stableswap pair
+    let err = instantiate(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        instantiate_msg(asset_infos()[..2].to_vec()),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidNumberOfAssets {});
+
+    let mut doubled_asset_infos = asset_infos();
+    doubled_asset_infos[2] = doubled_asset_infos[1].clone();
+    let err = instantiate(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        instantiate_msg(doubled_asset_infos),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::DoublingAssets {});
+
+    // The pool can't measure the amounts of tokens that charge a fee on transfers
+    let mut msg = instantiate_msg(asset_infos());
+    msg.init_params = Some(
+        to_binary(&StablePoolParams {
+            amp: 100,
+            fee_on_transfer: true,
+        })
+        .unwrap(),
+    );
+    let err = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::FeeOnTransferNotSupported {});
+
+    let res = instantiate(deps.as_mut(), env, info, instantiate_msg(asset_infos())).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg {
+            msg: WasmMsg::Instantiate {
+                code_id: 10u64,
+                msg: to_binary(&TokenInstantiateMsg {
+                    name: "UUSD-MAPP-MAPP-LP".to_string(),
+                    symbol: "uLP".to_string(),
+                    decimals: 6,
+                    initial_balances: vec![],
+                    mint: Some(MinterResponse {
+                        minter: String::from(MOCK_CONTRACT_ADDR),
+                        cap: None,
+                    }),
+                })
+                .unwrap(),
+                funds: vec![],
+                admin: None,
+                label: String::from("Astroport LP token"),
+            }
+            .into(),
+            id: 1,
+            gas_limit: None,
+            reply_on: ReplyOn::Success
+        },]
+    );
+
+    // Store liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Pair {}).unwrap();
+    let pair_info: MultiAssetPairInfo = from_binary(&res).unwrap();
+    assert_eq!(
+        pair_info,
+        MultiAssetPairInfo {
+            asset_infos: asset_infos(),
+            contract_addr: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            liquidity_token: Addr::unchecked("liquidity0000"),
+            pair_type: PairType::Custom(PAIR_TYPE.to_string()),
+        }
+    );
+}
+
+#[test]
+fn provide_liquidity() {
+    // The native deposit is already in the pair balance
+    let mut deps = setup_pair(100_000000, 0, 0, 0);
+
+    // The first deposit must contain all the pool assets
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![
+            Asset {
+                info: asset_infos()[0].clone(),
+                amount: Uint128::new(100_000000),
+            },
+            Asset {
+                info: asset_infos()[1].clone(),
+                amount: Uint128::new(100_000000),
+            },
+        ],
+        auto_stake: None,
+        receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(100_000000),
+        }],
+    );
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidZeroAmount {});
+
+    // Assets that don't belong to the pool are rejected
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0002"),
+            },
+            amount: Uint128::new(100_000000),
+        }],
+        auto_stake: None,
+        receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::AssetMismatch {});
+
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: asset_infos()
+            .into_iter()
+            .map(|info| Asset {
+                info,
+                amount: Uint128::new(100_000000),
+            })
+            .collect(),
+        auto_stake: None,
+        receiver: None,
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // A balanced pool mints the sum of its balances
+    let share = Uint128::new(300_000000) - MINIMUM_LIQUIDITY_AMOUNT;
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: String::from("addr0000"),
+                    recipient: String::from(MOCK_CONTRACT_ADDR),
+                    amount: Uint128::new(100_000000),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0001"),
+                msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: String::from("addr0000"),
+                    recipient: String::from(MOCK_CONTRACT_ADDR),
+                    amount: Uint128::new(100_000000),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("liquidity0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: String::from(MOCK_CONTRACT_ADDR),
+                    amount: MINIMUM_LIQUIDITY_AMOUNT,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("liquidity0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: String::from("addr0000"),
+                    amount: share,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+
+    // A single-asset deposit into an existing pool pays the imbalance fee
+    let mut deps = setup_pair(100_000000, 100_000000, 100_000000, 300_000000);
+    let assets = vec![Asset {
+        info: asset_infos()[1].clone(),
+        amount: Uint128::new(30_000000),
+    }];
+    let simulation: SimulateProvideResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateProvide {
+                assets: assets.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(simulation.share, Uint128::new(29929063));
+    assert_eq!(
+        simulation
+            .imbalance_fees
+            .iter()
+            .map(|fee| fee.amount)
+            .collect::<Vec<_>>(),
+        vec![
+            Uint128::new(11240),
+            Uint128::new(22509),
+            Uint128::new(11240)
+        ]
+    );
+
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets,
+        auto_stake: None,
+        receiver: Some("addr0001".to_string()),
+        min_lp_to_receive: None,
+        deadline: None,
+        callback: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "provide_liquidity"),
+            attr("sender", "addr0000"),
+            attr("receiver", "addr0001"),
+            attr("assets", "30000000asset0000"),
+            attr("share", "29929063"),
+            attr(
+                "imbalance_fees",
+                "11240uusd, 22509asset0000, 11240asset0001"
+            ),
+            attr("maker_fees", "1865uusd, 3736asset0000, 1865asset0001"),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: String::from("addr0000"),
+                    recipient: String::from(MOCK_CONTRACT_ADDR),
+                    amount: Uint128::new(30_000000),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("liquidity0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: String::from("addr0001"),
+                    amount: Uint128::new(29929063),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("fee_address"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::new(1865),
+                }],
+            })),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("fee_address"),
+                    amount: Uint128::new(3736),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0001"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("fee_address"),
+                    amount: Uint128::new(1865),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+
+    // The imbalance fees are added to the statistics without counting as a swap
+    let stats: StatsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Stats {}).unwrap()).unwrap();
+    assert_eq!(stats.swap_count, 0);
+    assert_eq!(
+        stats
+            .assets
+            .iter()
+            .map(|asset| (asset.lp_commission.u128(), asset.maker_commission.u128()))
+            .collect::<Vec<_>>(),
+        vec![(9375, 1865), (18773, 3736), (9375, 1865)]
+    );
+}
+
+#[test]
+fn withdraw_liquidity() {
+    let mut deps = setup_pair(100_000000, 200_000000, 300_000000, 600_000000);
+
+    // Only the liquidity token can withdraw
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            min_assets_to_receive: None,
+            deadline: None,
+            callback: None,
+        })
+        .unwrap(),
+        amount: Uint128::new(60_000000),
+    });
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("asset0000", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // The pair has no single asset withdrawals
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::SimulateWithdraw {
+            lp_amount: Uint128::new(60_000000),
+            ask_asset_info: Some(asset_infos()[0].clone()),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("Single asset withdrawals are not supported by this pair")
+    );
+
+    let simulation: SimulateWithdrawResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateWithdraw {
+                lp_amount: Uint128::new(60_000000),
+                ask_asset_info: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        simulation
+            .assets
+            .iter()
+            .map(|asset| asset.amount)
+            .collect::<Vec<_>>(),
+        vec![
+            Uint128::new(10_000000),
+            Uint128::new(20_000000),
+            Uint128::new(30_000000)
+        ]
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("liquidity0000", &[]),
+        msg,
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "withdraw_liquidity"),
+            attr("sender", "addr0000"),
+            attr("withdrawn_share", "60000000"),
+            attr(
+                "refund_assets",
+                "10000000uusd, 20000000asset0000, 30000000asset0001"
+            ),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("addr0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::new(10_000000),
+                }],
+            })),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0000"),
+                    amount: Uint128::new(20_000000),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0001"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0000"),
+                    amount: Uint128::new(30_000000),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("liquidity0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::new(60_000000),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+}
+
+#[test]
+fn swap() {
+    let offer_amount = Uint128::new(1_000000);
+
+    // The pair balance already holds the native offer amount
+    let mut deps = setup_pair(
+        1000_000000 + offer_amount.u128(),
+        1000_000000,
+        1000_000000,
+        3000_000000,
+    );
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Simulation {
+            offer_asset: Asset {
+                info: asset_infos()[0].clone(),
+                amount: offer_amount,
+            },
+            ask_asset_info: asset_infos()[2].clone(),
+        },
+    );
+    // The simulation sees the offer amount as part of the pool
+    assert!(res.is_ok());
+
+    // The offer and the ask assets must differ
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::Swap {
+            offer_asset: Asset {
+                info: asset_infos()[0].clone(),
+                amount: offer_amount,
+            },
+            ask_asset_info: asset_infos()[0].clone(),
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            deadline: None,
+            callback: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::AssetMismatch {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::Swap {
+            offer_asset: Asset {
+                info: asset_infos()[0].clone(),
+                amount: offer_amount,
+            },
+            ask_asset_info: asset_infos()[2].clone(),
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            deadline: None,
+            callback: None,
+        },
+    )
+    .unwrap();
+
+    // 0.3% fee on top of the small curve slippage of a balanced pool
+    let expected_return_amount = Uint128::new(996992);
+    let expected_commission_amount = Uint128::new(2999);
+    let expected_maker_fee_amount = Uint128::new(497);
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "swap"),
+            attr("sender", "addr0000"),
+            attr("receiver", "addr0000"),
+            attr("offer_asset", "uusd"),
+            attr("ask_asset", "asset0001"),
+            attr("offer_amount", offer_amount.to_string()),
+            attr("return_amount", expected_return_amount.to_string()),
+            attr("tax_amount", "0"),
+            attr("spread_amount", "9"),
+            attr("commission_amount", expected_commission_amount.to_string()),
+            attr("maker_fee_amount", expected_maker_fee_amount.to_string()),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0001"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0000"),
+                    amount: expected_return_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0001"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("fee_address"),
+                    amount: expected_maker_fee_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+
+    // A token offer goes through the CW20 hook; the balance of asset0000 includes the offer
+    let mut deps = setup_pair(
+        1000_000000,
+        1000_000000 + offer_amount.u128(),
+        1000_000000,
+        3000_000000,
+    );
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: offer_amount,
+        msg: to_binary(&Cw20HookMsg::Swap {
+            ask_asset_info: asset_infos()[0].clone(),
+            belief_price: None,
+            max_spread: None,
+            to: Some("addr0001".to_string()),
+            deadline: None,
+            callback: None,
+        })
+        .unwrap(),
+    });
+
+    // Only the pool tokens can be offered
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("asset0002", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info("asset0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("addr0001"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: expected_return_amount,
+            }],
+        }))
+    );
+
+    // The asset0000 balance already includes the last offer, so the same return costs a bit more
+    let res: ReverseSimulationResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ReverseSimulation {
+                offer_asset_info: asset_infos()[1].clone(),
+                ask_asset: Asset {
+                    info: asset_infos()[0].clone(),
+                    amount: expected_return_amount,
+                },
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        ReverseSimulationResponse {
+            offer_amount: Uint128::new(1000010),
+            spread_amount: Uint128::new(19),
+            commission_amount: Uint128::new(2999),
+        }
+    );
+
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Simulation {
+            offer_asset: Asset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0002"),
+                },
+                amount: offer_amount,
+            },
+            ask_asset_info: asset_infos()[0].clone(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("Given assets don't belong to the pool")
+    );
+}
+
+#[test]
+fn test_query_pool_and_virtual_price() {
+    let deps = setup_pair(1_000000, 1_000000, 1_000000, 3_000000);
+
+    let res: PoolResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Pool {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        PoolResponse {
+            assets: asset_infos()
+                .into_iter()
+                .map(|info| Asset {
+                    info,
+                    amount: Uint128::new(1_000000),
+                })
+                .collect(),
+            total_share: Uint128::new(3_000000),
+        }
+    );
+
+    let res: SimulationResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Simulation {
+                offer_asset: Asset {
+                    info: asset_infos()[1].clone(),
+                    amount: Uint128::new(1000),
+                },
+                ask_asset_info: asset_infos()[2].clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.total_fee_rate, Decimal::permille(3));
+
+    let res: PoolInvariantResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PoolInvariant {}).unwrap())
+            .unwrap();
+    assert_eq!(
+        res,
+        PoolInvariantResponse {
+            d: Uint128::new(3_000000),
+            amp: Decimal::from_ratio(100u64, 1u64),
+        }
+    );
+
+    let res: VirtualPriceResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::VirtualPrice {}).unwrap()).unwrap();
+    assert_eq!(res.virtual_price, Decimal::one());
+
+    // An imbalanced pool has a lower invariant
+    let deps = setup_pair(2_000000, 500000, 500000, 3_000000);
+    let res: VirtualPriceResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::VirtualPrice {}).unwrap()).unwrap();
+    assert!(res.virtual_price < Decimal::one());
+    assert!(res.virtual_price > Decimal::percent(99));
+}
+
+#[test]
+fn compute_d_matches_two_asset_invariant() {
+    let leverage = 100 * AMP_PRECISION * 3;
+    assert_eq!(compute_d(leverage, &[0, 0, 0]), Some(0));
+    assert_eq!(
+        compute_d(leverage, &[1_000000, 1_000000, 1_000000]),
+        Some(3_000000)
+    );
+
+    let model = StableSwapModel::new(100, vec![1_000000, 2_000000, 3_000000], 3);
+    let d = compute_d(leverage, &[1_000000, 2_000000, 3_000000]).unwrap();
+    assert!((d as i128 - model.sim_d() as i128).abs() <= 1);
+}
+
+proptest! {
+    #[test]
+    fn stable_swap_three_assets_no_fee(
+        balance_in in 100..1_000_000_000_000_000_000u128,
+        balance_out in 100..1_000_000_000_000_000_000u128,
+        balance_other in 100..1_000_000_000_000_000_000u128,
+        amount_in in 100..100_000_000_000u128,
+        amp in 1..150u64
+    ) {
+        prop_assume!(amount_in < balance_in);
+
+        let balances = vec![balance_in, balance_out, balance_other];
+        let model = StableSwapModel::new(amp.into(), balances.clone(), 3);
+
+        let result = calc_ask_amount(&balances, 0, 1, amount_in, amp * AMP_PRECISION).unwrap();
+
+        let sim_result = model.sim_exchange(0, 1, amount_in);
+
+        // The reference model divides in a different order, so heavily imbalanced pools may round
+        // a few units apart
+        let diff = (sim_result as i128 - result as i128).abs();
+
+        assert!(
+            diff <= 1 || diff as f64 / sim_result as f64 <= 1e-9,
+            "result={}, sim_result={}, amp={}, amount_in={}, balances={:?}, diff={}",
+            result,
+            sim_result,
+            amp,
+            amount_in,
+            balances,
+            diff
+        );
+
+        let reverse_result =
+            calc_offer_amount(&balances, 0, 1, result, amp * AMP_PRECISION).unwrap();
+
+        let amount_in_f = amount_in as f64;
+        let reverse_diff = (reverse_result as f64 - amount_in_f) / amount_in_f * 100.;
+
+        assert!(
+            reverse_diff <= 0.0001,
+            "result={}, amount_in={}, amp={}, amount_out={}, balances={:?}, diff(%)={}",
+            reverse_result,
+            amount_in,
+            amp,
+            result,
+            balances,
+            reverse_diff
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use astroport::U256;
+use cosmwasm_std::{Decimal, Fraction};
+
+/// The fixed point precision used in the weighted math (18 decimals).
+const ONE: u128 = 1_000_000_000_000_000_000;
+/// Series terms smaller than this are not added to the result of [`pow_approx`].
+const POW_PRECISION: u128 = 100_000_000;
+
+/// The maximum share of the offer pool (in bps) that can be swapped in at once.
+pub const MAX_IN_RATIO_BPS: u128 = 5000;
+/// The maximum share of the ask pool (in bps) that can be swapped out at once.
+pub const MAX_OUT_RATIO_BPS: u128 = 3000;
+
+/// ## Description
+/// Calculates the ask amount (the amount of tokens swapped to) before fees.
+/// `ask_amount = ask_pool * (1 - (offer_pool / (offer_pool + offer_amount)) ^ (offer_weight / ask_weight))`
+/// ## Params
+/// * **offer_pool** is an object of type [`u128`]. This is the amount of offer tokens currently in the pool.
+///
+/// * **ask_pool** is an object of type [`u128`]. This is the amount of ask tokens currently in the pool.
+///
+/// * **offer_amount** is an object of type [`u128`]. This is the amount of offer tokens to swap.
+///
+/// * **offer_weight** is an object of type [`Decimal`]. This is the weight of the offer asset.
+///
+/// * **ask_weight** is an object of type [`Decimal`]. This is the weight of the ask asset.
+pub fn calc_ask_amount(
+    offer_pool: u128,
+    ask_pool: u128,
+    offer_amount: u128,
+    offer_weight: Decimal,
+    ask_weight: Decimal,
+) -> Option<u128> {
+    let offer_pool = U256::from(offer_pool);
+    let new_offer_pool = offer_pool.checked_add(U256::from(offer_amount))?;
+
+    // Round the base up so that the ask amount is rounded down
+    let base = div_up(offer_pool, new_offer_pool)?;
+    let power = pow(base, weight_ratio(offer_weight, ask_weight)?)?;
+
+    let ask_amount =
+        U256::from(ask_pool).checked_mul(one().checked_sub(power.min(one()))?)? / one();
+
+    Some(ask_amount.as_u128())
+}
+
+/// ## Description
+/// Calculates the offer amount (the amount of tokens to swap) that is needed to receive an amount
+/// of ask tokens before fees.
+/// `offer_amount = offer_pool * ((ask_pool / (ask_pool - ask_amount)) ^ (ask_weight / offer_weight) - 1)`
+/// ## Params
+/// * **offer_pool** is an object of type [`u128`]. This is the amount of offer tokens currently in the pool.
+///
+/// * **ask_pool** is an object of type [`u128`]. This is the amount of ask tokens currently in the pool.
+///
+/// * **ask_amount** is an object of type [`u128`]. This is the amount of ask tokens to receive.
+///
+/// * **offer_weight** is an object of type [`Decimal`]. This is the weight of the offer asset.
+///
+/// * **ask_weight** is an object of type [`Decimal`]. This is the weight of the ask asset.
+pub fn calc_offer_amount(
+    offer_pool: u128,
+    ask_pool: u128,
+    ask_amount: u128,
+    offer_weight: Decimal,
+    ask_weight: Decimal,
+) -> Option<u128> {
+    let ask_pool = U256::from(ask_pool);
+    let new_ask_pool = ask_pool.checked_sub(U256::from(ask_amount))?;
+    if new_ask_pool.is_zero() {
+        return None;
+    }
+
+    // Round the base up so that the offer amount is rounded up
+    let base = div_up(ask_pool, new_ask_pool)?;
+    let power = pow(base, weight_ratio(ask_weight, offer_weight)?)?;
+
+    let offer_amount = U256::from(offer_pool)
+        .checked_mul(power.checked_sub(one())?)?
+        .checked_add(one() - U256::one())?
+        / one();
+
+    if offer_amount > U256::from(u128::MAX) {
+        return None;
+    }
+
+    Some(offer_amount.as_u128())
+}
+
+/// ## Description
+/// Returns `base ^ exp` for a fixed point `base` in the (0, 2) range and a fixed point `exp`.
+/// The integer part of the exponent is applied by repeated squaring and the fractional part
+/// is approximated with [`pow_approx`].
+/// ## Params
+/// * **base** is an object of type [`U256`].
+///
+/// * **exp** is an object of type [`U256`].
+pub fn pow(base: U256, exp: U256) -> Option<U256> {
+    if base.is_zero() || base >= U256::from(2 * ONE) {
+        return None;
+    }
+
+    let whole = exp / one();
+    let remain = exp - whole * one();
+
+    let mut result = one();
+    let mut square = base;
+    let mut n = whole;
+    while !n.is_zero() {
+        if n.low_u32() & 1 == 1 {
+            result = mul(result, square)?;
+        }
+        n >>= 1;
+        if !n.is_zero() {
+            square = mul(square, square)?;
+        }
+    }
+
+    if remain.is_zero() {
+        return Some(result);
+    }
+
+    mul(result, pow_approx(base, remain)?)
+}
+
+/// ## Description
+/// Approximates `base ^ exp` for a fractional `exp` with the binomial series of `(1 + x) ^ exp`
+/// where `x = base - 1`. The series converges because `|x| < 1`.
+/// ## Params
+/// * **base** is an object of type [`U256`].
+///
+/// * **exp** is an object of type [`U256`]. It has to be smaller than one.
+fn pow_approx(base: U256, exp: U256) -> Option<U256> {
+    let (x, x_negative) = sub_sign(base, one());
+
+    let mut term = one();
+    let mut sum = one();
+    let mut negative = false;
+    let mut i = U256::one();
+
+    while term >= U256::from(POW_PRECISION) {
+        let big_k = i * one();
+        let (c, c_negative) = sub_sign(exp, big_k - one());
+
+        term = mul(term, mul(c, x)?)?.checked_mul(one())? / big_k;
+        if term.is_zero() {
+            break;
+        }
+
+        if x_negative {
+            negative = !negative;
+        }
+        if c_negative {
+            negative = !negative;
+        }
+
+        sum = if negative {
+            sum.checked_sub(term)?
+        } else {
+            sum.checked_add(term)?
+        };
+        i += U256::one();
+    }
+
+    Some(sum)
+}
+
+/// ## Description
+/// Returns the ratio of two weights as a fixed point number.
+fn weight_ratio(numerator: Decimal, denominator: Decimal) -> Option<U256> {
+    U256::from(numerator.numerator())
+        .checked_mul(one())?
+        .checked_div(U256::from(denominator.numerator()))
+}
+
+/// ## Description
+/// Returns the absolute difference of two numbers and whether it is negative.
+fn sub_sign(a: U256, b: U256) -> (U256, bool) {
+    if a >= b {
+        (a - b, false)
+    } else {
+        (b - a, true)
+    }
+}
+
+/// ## Description
+/// Multiplies two fixed point numbers rounding down.
+fn mul(a: U256, b: U256) -> Option<U256> {
+    Some(a.checked_mul(b)? / one())
+}
+
+/// ## Description
+/// Divides two numbers into a fixed point number rounding up.
+fn div_up(a: U256, b: U256) -> Option<U256> {
+    if b.is_zero() {
+        return None;
+    }
+
+    Some((a.checked_mul(one())? + b - U256::one()) / b)
+}
+
+fn one() -> U256 {
+    U256::from(ONE)
+}
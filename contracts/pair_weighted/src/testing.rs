@@ -0,0 +1,718 @@
+use crate::contract::{
+    compute_swap, execute, instantiate, query, query_pool, query_reverse_simulation,
+    query_simulate_provide, query_simulate_withdraw, query_simulation, reply, PAIR_TYPE,
+};
+use crate::error::ContractError;
+use crate::math::pow;
+use crate::response::MsgInstantiateContractResponse;
+use crate::state::{RESERVES, WEIGHTS};
+use astroport::asset::{Asset, AssetInfo};
+use astroport::factory::PairType;
+use astroport::pair::{ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg};
+use astroport::pair_weighted::{WeightedPoolConfig, WeightedPoolParams};
+use astroport::stats::{AssetStats, StatsResponse};
+use astroport::U256;
+use astroport_mocks::mock_querier::{mock_dependencies, WasmMockQuerier};
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    attr, from_binary, to_binary, Addr, Coin, ContractResult, Decimal, DepsMut, OwnedDeps, Reply,
+    StdError, SubMsg, SubMsgExecutionResponse, Uint128, WasmMsg,
+};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use protobuf::Message;
+use std::str::FromStr;
+
+fn store_liquidity_token(deps: DepsMut, msg_id: u64, contract_addr: String) {
+    let data = MsgInstantiateContractResponse {
+        contract_address: contract_addr,
+        data: vec![],
+        unknown_fields: Default::default(),
+        cached_size: Default::default(),
+    }
+    .write_to_bytes()
+    .unwrap();
+
+    let reply_msg = Reply {
+        id: msg_id,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(data.into()),
+        }),
+    };
+
+    let _res = reply(deps, mock_env(), reply_msg.clone()).unwrap();
+}
+
+fn instantiate_msg(weights: [u64; 2]) -> InstantiateMsg {
+    InstantiateMsg {
+        factory_addr: String::from("factory"),
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        init_params: Some(
+            to_binary(&WeightedPoolParams {
+                weights,
+                fee_on_transfer: false,
+            })
+            .unwrap(),
+        ),
+    }
+}
+
+fn mock_pair(
+    weights: [u64; 2],
+    lp_supply: Uint128,
+) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &lp_supply)],
+        ),
+    ]);
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(weights),
+    )
+    .unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    deps
+}
+
+/// Returns `ask_pool * (1 - (offer_pool / (offer_pool + offer_amount)) ^ (offer_weight / ask_weight))`
+/// computed with floats.
+fn float_ask_amount(
+    offer_pool: f64,
+    ask_pool: f64,
+    offer_amount: f64,
+    offer_weight: f64,
+    ask_weight: f64,
+) -> f64 {
+    ask_pool * (1.0 - (offer_pool / (offer_pool + offer_amount)).powf(offer_weight / ask_weight))
+}
+
+#[test]
+fn proper_initialization() {
+    let deps = mock_pair([80, 20], Uint128::zero());
+
+    let pair_info: astroport::asset::PairInfo =
+        from_binary(&crate::contract::query(deps.as_ref(), mock_env(), QueryMsg::Pair {}).unwrap())
+            .unwrap();
+    assert_eq!(pair_info.pair_type, PairType::Custom(PAIR_TYPE.to_string()));
+    assert_eq!(pair_info.liquidity_token, Addr::unchecked("liquidity0000"));
+
+    let config: ConfigResponse = from_binary(
+        &crate::contract::query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap(),
+    )
+    .unwrap();
+    let params: WeightedPoolConfig = from_binary(&config.params.unwrap()).unwrap();
+    assert_eq!(
+        params.weights,
+        [
+            Decimal::from_str("0.8").unwrap(),
+            Decimal::from_str("0.2").unwrap()
+        ]
+    );
+
+    // The normalized weights always sum up to 1
+    let deps = mock_pair([1, 2], Uint128::zero());
+    let config: ConfigResponse = from_binary(
+        &crate::contract::query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap(),
+    )
+    .unwrap();
+    let params: WeightedPoolConfig = from_binary(&config.params.unwrap()).unwrap();
+    assert_eq!(params.weights[0] + params.weights[1], Decimal::one());
+}
+
+#[test]
+fn incorrect_init_params() {
+    let mut deps = mock_pair([80, 20], Uint128::zero());
+
+    let mut msg = instantiate_msg([80, 20]);
+    msg.init_params = None;
+    let err = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::InitParamsNotFound {});
+
+    let err = instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg([0, 100]),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::IncorrectWeights {});
+
+    let err = instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg([0, 0]),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::IncorrectWeights {});
+
+    let err = instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg([1, 100]),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::IncorrectWeights {});
+
+    // 1% is the smallest allowed weight
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg([1, 99]),
+    )
+    .unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            params: to_binary(&WeightedPoolParams {
+                weights: [50, 50],
+                fee_on_transfer: false,
+            })
+            .unwrap(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NonSupported {});
+}
+
+#[test]
+fn swap() {
+    let mut deps = mock_pair([80, 20], Uint128::new(400_000_000_000));
+    let weights = WEIGHTS.load(&deps.storage).unwrap();
+
+    // The spot price of an 80/20 pool with these reserves is 1
+    let reserves = [Uint128::new(800_000_000_000), Uint128::new(200_000_000_000)];
+    RESERVES.save(deps.as_mut().storage, &reserves).unwrap();
+
+    let offer_amount = Uint128::new(1_000_000_000);
+    let offer_asset = Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: offer_amount,
+    };
+
+    let simulation = query_simulation(deps.as_ref(), offer_asset.clone(), &weights).unwrap();
+
+    let expected = float_ask_amount(
+        800_000_000_000.0,
+        200_000_000_000.0,
+        1_000_000_000.0,
+        0.8,
+        0.2,
+    );
+    let before_fee = simulation.return_amount + simulation.commission_amount;
+    assert!((before_fee.u128() as f64 - expected).abs() < 10.0);
+    assert_eq!(
+        simulation.commission_amount,
+        before_fee * Decimal::from_ratio(3u128, 1000u128)
+    );
+    assert_eq!(
+        simulation.spread_amount,
+        offer_amount.checked_sub(before_fee).unwrap()
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: offer_amount,
+            }],
+        ),
+        ExecuteMsg::Swap {
+            offer_asset,
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            deadline: None,
+            callback: None,
+        },
+    )
+    .unwrap();
+    assert!(res
+        .attributes
+        .contains(&attr("return_amount", simulation.return_amount.to_string())));
+
+    // The Maker fee leaves the pool along with the return amount
+    let maker_fee_amount = simulation.commission_amount * Decimal::from_ratio(1660u128, 10000u128);
+    let pool = query_pool(deps.as_ref()).unwrap();
+    assert_eq!(
+        pool.assets[0].amount,
+        reserves[0].checked_add(offer_amount).unwrap()
+    );
+    assert_eq!(
+        pool.assets[1].amount,
+        reserves[1]
+            .checked_sub(simulation.return_amount + maker_fee_amount)
+            .unwrap()
+    );
+
+    // The reverse swap goes through the other weight ratio
+    let offer_asset = Asset {
+        info: AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+        amount: Uint128::new(1_000_000_000),
+    };
+    let simulation = query_simulation(deps.as_ref(), offer_asset, &weights).unwrap();
+    let expected = float_ask_amount(
+        pool.assets[1].amount.u128() as f64,
+        pool.assets[0].amount.u128() as f64,
+        1_000_000_000.0,
+        0.2,
+        0.8,
+    );
+    let before_fee = simulation.return_amount + simulation.commission_amount;
+    assert!((before_fee.u128() as f64 - expected).abs() < 10.0);
+}
+
+#[test]
+fn reverse_simulation() {
+    let mut deps = mock_pair([80, 20], Uint128::new(400_000_000_000));
+    let weights = WEIGHTS.load(&deps.storage).unwrap();
+    RESERVES
+        .save(
+            deps.as_mut().storage,
+            &[
+                Uint128::new(1_600_000_000_000),
+                Uint128::new(300_000_000_000),
+            ],
+        )
+        .unwrap();
+
+    for ask_asset_info in [
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+    ] {
+        let ask_asset = Asset {
+            info: ask_asset_info,
+            amount: Uint128::new(5_000_000_000),
+        };
+        let reverse_simulation =
+            query_reverse_simulation(deps.as_ref(), ask_asset.clone(), &weights).unwrap();
+
+        // Offering the computed amount returns at least the asked amount
+        let offer_asset_info = if ask_asset.info.is_native_token() {
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            }
+        } else {
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            }
+        };
+        let simulation = query_simulation(
+            deps.as_ref(),
+            Asset {
+                info: offer_asset_info,
+                amount: reverse_simulation.offer_amount,
+            },
+            &weights,
+        )
+        .unwrap();
+        assert!(simulation.return_amount >= ask_asset.amount);
+        assert!(simulation.return_amount - ask_asset.amount < Uint128::new(10));
+    }
+
+    // Too large swaps are rejected
+    let err = query_reverse_simulation(
+        deps.as_ref(),
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            amount: Uint128::new(100_000_000_000),
+        },
+        &weights,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("The ask amount exceeds the maximum out ratio of the pool")
+    );
+}
+
+#[test]
+fn max_in_ratio() {
+    let err = compute_swap(
+        Uint128::new(1_000_000),
+        Uint128::new(1_000_000),
+        Uint128::new(500_001),
+        Decimal::from_str("0.5").unwrap(),
+        Decimal::from_str("0.5").unwrap(),
+        Decimal::zero(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("The offer amount exceeds the maximum in ratio of the pool")
+    );
+
+    // An equally weighted pool behaves like a constant product pool
+    let (return_amount, _, _) = compute_swap(
+        Uint128::new(1_000_000),
+        Uint128::new(1_000_000),
+        Uint128::new(500_000),
+        Decimal::from_str("0.5").unwrap(),
+        Decimal::from_str("0.5").unwrap(),
+        Decimal::zero(),
+    )
+    .unwrap();
+    assert_eq!(return_amount, Uint128::new(333_333));
+}
+
+#[test]
+fn pow_approximation() {
+    let one = U256::from(1_000_000_000_000_000_000u128);
+    let to_float = |value: U256| value.as_u128() as f64 / 1e18;
+
+    for (base, exp) in [
+        (0.5, 0.5),
+        (0.9, 4.0),
+        (1.5, 2.5),
+        (0.75, 0.25),
+        (1.25, 99.0),
+        (0.999, 0.01),
+    ] {
+        let result = pow(
+            U256::from((base * 1e18) as u128),
+            U256::from((exp * 1e18) as u128),
+        )
+        .unwrap();
+        let expected: f64 = f64::powf(base, exp);
+        assert!(
+            ((to_float(result) - expected) / expected).abs() < 1e-8,
+            "{}^{} = {}, expected {}",
+            base,
+            exp,
+            to_float(result),
+            expected
+        );
+    }
+
+    // The base has to be in the (0, 2) range
+    assert_eq!(pow(U256::zero(), one), None);
+    assert_eq!(pow(one * 2, one), None);
+}
+
+#[test]
+fn provide_liquidity_with_reserves() {
+    let mut deps = mock_pair([80, 20], Uint128::new(400_000_000));
+    let weights = WEIGHTS.load(&deps.storage).unwrap();
+    RESERVES
+        .save(
+            deps.as_mut().storage,
+            &[Uint128::new(800_000_000), Uint128::new(200_000_000)],
+        )
+        .unwrap();
+
+    let assets = [
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::new(100_000_000),
+        },
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            amount: Uint128::new(25_000_000),
+        },
+    ];
+    let simulation = query_simulate_provide(deps.as_ref(), assets.clone()).unwrap();
+    assert_eq!(simulation.share, Uint128::new(50_000_000));
+
+    // Deposits that follow the reserve ratio keep the price of the pool
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0001",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000),
+            }],
+        ),
+        ExecuteMsg::ProvideLiquidity {
+            assets,
+            slippage_tolerance: None,
+            auto_stake: None,
+            receiver: None,
+            min_lp_to_receive: None,
+            deadline: None,
+            callback: None,
+        },
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&attr("share", "50000000")));
+
+    let pool = query_pool(deps.as_ref()).unwrap();
+    assert_eq!(pool.assets[0].amount, Uint128::new(900_000_000));
+    assert_eq!(pool.assets[1].amount, Uint128::new(225_000_000));
+
+    // The LP supply of the mock stays at 400000000, so 10% of it returns 10% of the reserves
+    let simulation =
+        query_simulate_withdraw(deps.as_ref(), Uint128::new(40_000_000), None, &weights).unwrap();
+    assert_eq!(simulation.assets[0].amount, Uint128::new(90_000_000));
+    assert_eq!(simulation.assets[1].amount, Uint128::new(22_500_000));
+    assert_eq!(simulation.commission_amount, Uint128::zero());
+}
+
+#[test]
+fn withdraw_liquidity_one_asset() {
+    let mut deps = mock_pair([80, 20], Uint128::new(400_000_000));
+    let weights = WEIGHTS.load(&deps.storage).unwrap();
+    RESERVES
+        .save(
+            deps.as_mut().storage,
+            &[Uint128::new(800_000_000), Uint128::new(200_000_000)],
+        )
+        .unwrap();
+
+    let ask_asset_info = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let simulation = query_simulate_withdraw(
+        deps.as_ref(),
+        Uint128::new(40_000_000),
+        Some(ask_asset_info.clone()),
+        &weights,
+    )
+    .unwrap();
+    assert!(!simulation.commission_amount.is_zero());
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("liquidity0000", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("addr0000"),
+            msg: to_binary(&Cw20HookMsg::WithdrawLiquidityOneAsset {
+                ask_asset_info: ask_asset_info.clone(),
+                min_amount: Some(simulation.assets[0].amount),
+            })
+            .unwrap(),
+            amount: Uint128::new(40_000_000),
+        }),
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&attr(
+        "commission_amount",
+        simulation.commission_amount.to_string()
+    )));
+
+    // The commission is added to the statistics without counting as a swap
+    let maker_fee_amount = simulation.commission_amount * Decimal::from_ratio(1660u128, 10000u128);
+    let stats: StatsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Stats {}).unwrap()).unwrap();
+    assert_eq!(
+        stats,
+        StatsResponse {
+            swap_count: 0,
+            assets: vec![AssetStats {
+                info: ask_asset_info,
+                volume_in: Uint128::zero(),
+                volume_out: Uint128::zero(),
+                lp_commission: simulation.commission_amount - maker_fee_amount,
+                maker_commission: maker_fee_amount,
+            }],
+        }
+    );
+}
+
+#[test]
+fn fee_on_transfer_tokens() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(200_000_000),
+            )],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &Uint128::new(400_000_000))],
+        ),
+    ]);
+
+    let mut msg = instantiate_msg([80, 20]);
+    msg.init_params = Some(
+        to_binary(&WeightedPoolParams {
+            weights: [80, 20],
+            fee_on_transfer: true,
+        })
+        .unwrap(),
+    );
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    RESERVES
+        .save(
+            deps.as_mut().storage,
+            &[Uint128::new(800_000_000), Uint128::new(200_000_000)],
+        )
+        .unwrap();
+
+    let config: ConfigResponse = from_binary(
+        &crate::contract::query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap(),
+    )
+    .unwrap();
+    let params: WeightedPoolConfig = from_binary(&config.params.unwrap()).unwrap();
+    assert!(params.fee_on_transfer);
+
+    // The provision waits for the token transfer to settle
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0001",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000),
+            }],
+        ),
+        ExecuteMsg::ProvideLiquidity {
+            assets: [
+                Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    amount: Uint128::new(100_000_000),
+                },
+                Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: Addr::unchecked("asset0000"),
+                    },
+                    amount: Uint128::new(25_000_000),
+                },
+            ],
+            slippage_tolerance: Some(Decimal::percent(2)),
+            auto_stake: None,
+            receiver: None,
+            min_lp_to_receive: None,
+            deadline: None,
+            callback: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: String::from("addr0001"),
+                    recipient: String::from(MOCK_CONTRACT_ADDR),
+                    amount: Uint128::new(25_000_000),
+                })
+                .unwrap(),
+                funds: vec![],
+            },
+            4,
+        )]
+    );
+
+    // The token charges a 1% transfer fee, so the pair receives only 24.75 tokens
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(224_750_000),
+            )],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &Uint128::new(400_000_000))],
+        ),
+    ]);
+    let settle_reply = Reply {
+        id: 4,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+    let res = reply(deps.as_mut(), mock_env(), settle_reply.clone()).unwrap();
+    assert!(res
+        .attributes
+        .contains(&attr("assets", "100000000uusd, 24750000asset0000")));
+    assert!(res.attributes.contains(&attr("share", "49500000")));
+
+    let pool = query_pool(deps.as_ref()).unwrap();
+    assert_eq!(pool.assets[0].amount, Uint128::new(900_000_000));
+    assert_eq!(pool.assets[1].amount, Uint128::new(224_750_000));
+
+    // The pending provision is settled only once
+    reply(deps.as_mut(), mock_env(), settle_reply).unwrap_err();
+
+    // A swap uses the amount that the pair received instead of the sent amount
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(225_740_000),
+            )],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &Uint128::new(400_000_000))],
+        ),
+    ]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("asset0000", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("addr0001"),
+            amount: Uint128::new(1_000_000),
+            msg: to_binary(&Cw20HookMsg::Swap {
+                belief_price: None,
+                max_spread: Some(Decimal::percent(50)),
+                to: None,
+                deadline: None,
+                callback: None,
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&attr("offer_amount", "990000")));
+
+    let pool = query_pool(deps.as_ref()).unwrap();
+    assert_eq!(pool.assets[1].amount, Uint128::new(225_740_000));
+}
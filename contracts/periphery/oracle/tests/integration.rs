@@ -100,6 +100,7 @@ fn instantiate_contracts(router: &mut TerraApp, owner: Addr) -> (Addr, Addr, u64
                 pair_type: PairType::Xyk {},
                 total_fee_bps: 0,
                 maker_fee_bps: 0,
+                flash_loan_fee_bps: 0,
                 is_disabled: false,
                 is_generator_disabled: false,
             },
@@ -108,6 +109,7 @@ fn instantiate_contracts(router: &mut TerraApp, owner: Addr) -> (Addr, Addr, u64
                 pair_type: PairType::Stable {},
                 total_fee_bps: 0,
                 maker_fee_bps: 0,
+                flash_loan_fee_bps: 0,
                 is_disabled: false,
                 is_generator_disabled: false,
             },
@@ -334,6 +336,9 @@ fn create_pair(
                 slippage_tolerance: None,
                 auto_stake: None,
                 receiver: None,
+                min_lp_to_receive: None,
+                deadline: None,
+                callback: None,
             },
             &funds,
         )
@@ -375,7 +380,13 @@ fn create_pair_stable(
             &astroport::factory::ExecuteMsg::CreatePair {
                 pair_type: PairType::Stable {},
                 asset_infos: asset_infos.clone(),
-                init_params: Some(to_binary(&StablePoolParams { amp: 100 }).unwrap()),
+                init_params: Some(
+                    to_binary(&StablePoolParams {
+                        amp: 100,
+                        fee_on_transfer: false,
+                    })
+                    .unwrap(),
+                ),
             },
             &[],
         )
@@ -439,6 +450,9 @@ fn create_pair_stable(
                 slippage_tolerance: None,
                 auto_stake: None,
                 receiver: None,
+                min_lp_to_receive: None,
+                deadline: None,
+                callback: None,
             },
             &funds,
         )
@@ -509,6 +523,9 @@ fn change_provide_liquidity(
                 slippage_tolerance: Some(Decimal::percent(50)),
                 auto_stake: None,
                 receiver: None,
+                min_lp_to_receive: None,
+                deadline: None,
+                callback: None,
             },
             &vec![],
         )
@@ -73,7 +73,9 @@ pub fn instantiate(
 /// * **ExecuteMsg::ExecuteSwapOperations {
 ///             operations,
 ///             minimum_receive,
-///             to
+///             to,
+///             max_spread,
+///             deadline
 ///         }** Performs swap operations with the specified parameters.
 ///
 /// * **ExecuteMsg::ExecuteSwapOperation { operation, to }** Execute a single swap operation.
@@ -98,6 +100,7 @@ pub fn execute(
             minimum_receive,
             to,
             max_spread,
+            deadline,
         } => execute_swap_operations(
             deps,
             env,
@@ -107,6 +110,7 @@ pub fn execute(
             minimum_receive,
             to,
             max_spread,
+            deadline,
         ),
         ExecuteMsg::ExecuteSwapOperation {
             operation,
@@ -153,6 +157,7 @@ pub fn receive_cw20(
             minimum_receive,
             to,
             max_spread,
+            deadline,
         } => {
             let to_addr = if let Some(to_addr) = to {
                 Some(addr_validate_to_lower(deps.api, to_addr.as_str())?)
@@ -169,6 +174,7 @@ pub fn receive_cw20(
                 minimum_receive,
                 to_addr,
                 max_spread,
+                deadline,
             )
         }
     }
@@ -192,6 +198,10 @@ pub fn receive_cw20(
 /// * **minimum_receive** is an object of type [`Option<Uint128>`]. Used to guarantee that the ask amount is above a minimum amount.
 ///
 /// * **to** is an object of type [`Option<Addr>`]. This is the recipient of the ask tokens.
+///
+/// * **max_spread** is an object of type [`Option<Decimal>`]. This is the max spread of every swap operation.
+///
+/// * **deadline** is an object of type [`Option<u64>`]. This is the timestamp (in seconds) after which the swaps fail.
 #[allow(clippy::too_many_arguments)]
 pub fn execute_swap_operations(
     deps: DepsMut,
@@ -202,7 +212,14 @@ pub fn execute_swap_operations(
     minimum_receive: Option<Uint128>,
     to: Option<Addr>,
     max_spread: Option<Decimal>,
+    deadline: Option<u64>,
 ) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    if let Some(deadline) = deadline {
+        if env.block.time.seconds() > deadline {
+            return Err(ContractError::DeadlineExpired {});
+        }
+    }
+
     let operations_len = operations.len();
     if operations_len == 0 {
         return Err(ContractError::MustProvideOperations {});
@@ -19,6 +19,9 @@ pub enum ContractError {
 
     #[error("The swap operation limit was exceeded!")]
     SwapLimitExceeded {},
+
+    #[error("The deadline of the swap operations has passed")]
+    DeadlineExpired {},
 }
 
 impl From<OverflowError> for ContractError {
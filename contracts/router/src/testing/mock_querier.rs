@@ -223,6 +223,7 @@ impl WasmMockQuerier {
                     return_amount: offer_asset.amount,
                     commission_amount: Uint128::zero(),
                     spread_amount: Uint128::zero(),
+                    total_fee_rate: Decimal::zero(),
                 })))
             }
         }
@@ -55,6 +55,7 @@ fn execute_swap_operations() {
         minimum_receive: None,
         to: None,
         max_spread: None,
+        deadline: None,
     };
 
     let env = mock_env();
@@ -62,6 +63,18 @@ fn execute_swap_operations() {
     let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
     assert_eq!(res, ContractError::MustProvideOperations {});
 
+    // The operations fail once the deadline has passed
+    let env = mock_env();
+    let msg = ExecuteMsg::ExecuteSwapOperations {
+        operations: vec![],
+        minimum_receive: None,
+        to: None,
+        max_spread: None,
+        deadline: Some(env.block.time.seconds() - 1),
+    };
+    let res = execute(deps.as_mut(), env, mock_info("addr0000", &[]), msg).unwrap_err();
+    assert_eq!(res, ContractError::DeadlineExpired {});
+
     let msg = ExecuteMsg::ExecuteSwapOperations {
         operations: vec![
             SwapOperation::NativeSwap {
@@ -96,6 +109,7 @@ fn execute_swap_operations() {
         minimum_receive: Some(Uint128::from(1000000u128)),
         to: None,
         max_spread: None,
+        deadline: Some(mock_env().block.time.seconds()),
     };
 
     let env = mock_env();
@@ -251,6 +265,7 @@ fn execute_swap_operations() {
             minimum_receive: None,
             to: Some(String::from("addr0002")),
             max_spread: None,
+            deadline: None,
         })
         .unwrap(),
     });
@@ -484,6 +499,8 @@ fn execute_swap_operation() {
                         belief_price: None,
                         max_spread: None,
                         to: Some(String::from("addr0000")),
+                        deadline: None,
+                        callback: None,
                     })
                     .unwrap()
                 })
@@ -691,6 +708,7 @@ fn assert_maximum_receive_swap_operations() {
         minimum_receive: None,
         to: None,
         max_spread: None,
+        deadline: None,
     };
 
     let env = mock_env();
@@ -1174,7 +1174,7 @@ pub fn build_claim_pools_asset_reward_messages(
             contract_addr: minter_response.minter,
             funds: vec![],
             msg: to_binary(
-                &astroport::pair_stable_bluna::ExecuteMsg::ClaimRewardByGenerator {
+                &astroport::reward_bearing::ExecuteMsg::ClaimRewardByGenerator {
                     user: account.to_string(),
                     user_share: user_amount,
                     total_share,
@@ -421,6 +421,7 @@ fn disabling_pool() {
             pair_type: PairType::Xyk {},
             total_fee_bps: 100,
             maker_fee_bps: 10,
+            flash_loan_fee_bps: 0,
             is_disabled: false,
             is_generator_disabled: true,
         },
@@ -450,6 +451,7 @@ fn disabling_pool() {
             pair_type: PairType::Xyk {},
             total_fee_bps: 100,
             maker_fee_bps: 10,
+            flash_loan_fee_bps: 0,
             is_disabled: false,
             is_generator_disabled: false,
         },
@@ -2451,6 +2453,7 @@ fn instantiate_factory(
             pair_type: PairType::Xyk {},
             total_fee_bps: 100,
             maker_fee_bps: 10,
+            flash_loan_fee_bps: 0,
             is_disabled: false,
             is_generator_disabled: false,
         }],
@@ -4,8 +4,8 @@ use std::cmp::min;
 
 use crate::migration;
 use crate::utils::{
-    build_distribute_msg, build_swap_msg, get_pool, try_build_swap_msg, validate_bridge,
-    BRIDGES_EXECUTION_MAX_DEPTH, BRIDGES_INITIAL_DEPTH,
+    build_distribute_msg, build_swap_msg, get_lp_token_pair, get_pool, try_build_swap_msg,
+    validate_bridge, BRIDGES_EXECUTION_MAX_DEPTH, BRIDGES_INITIAL_DEPTH,
 };
 use astroport::asset::{
     addr_validate_to_lower, native_asset_info, token_asset, token_asset_info, Asset, AssetInfo,
@@ -17,7 +17,7 @@ use astroport::maker::{
     AssetWithLimit, BalancesResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg,
     QueryMsg,
 };
-use astroport::pair::QueryMsg as PairQueryMsg;
+use astroport::pair::{Cw20HookMsg as PairCw20HookMsg, QueryMsg as PairQueryMsg};
 use cosmwasm_std::{
     attr, entry_point, to_binary, Addr, Attribute, Binary, CosmosMsg, Decimal, Deps, DepsMut, Env,
     MessageInfo, Order, QueryRequest, Response, StdError, StdResult, SubMsg, Uint128, Uint64,
@@ -110,8 +110,8 @@ pub fn instantiate(
 /// * **msg** is an object of type [`ExecuteMsg`].
 ///
 /// ## Queries
-/// * **ExecuteMsg::Collect { assets }** Swaps collected fee tokens to ASTRO
-/// and distributes the ASTRO between xASTRO and vxASTRO stakers.
+/// * **ExecuteMsg::Collect { assets }** Withdraws the liquidity behind collected LP tokens, swaps collected
+/// fee tokens to ASTRO and distributes the ASTRO between xASTRO and vxASTRO stakers.
 ///
 /// * **ExecuteMsg::UpdateConfig {
 ///             factory_contract,
@@ -224,6 +224,7 @@ pub fn execute(
 
 /// ## Description
 /// Swaps fee tokens to ASTRO and distribute the resulting ASTRO to xASTRO and vxASTRO stakers.
+/// LP tokens minted to the Maker by pairs are burned for the pool assets, which are collected in a follow-up call.
 /// Returns a [`ContractError`] on failure, otherwise returns a [`Response`] object if the
 /// operation was successful.
 /// # Params
@@ -251,6 +252,20 @@ fn collect(
         return Err(ContractError::DuplicatedAsset {});
     }
 
+    // Withdraw the liquidity behind LP tokens first. The withdrawn assets are collected
+    // together with the rest of the assets in a follow-up call
+    let (withdraw_msgs, assets) = withdraw_lp_tokens(deps.as_ref(), &env, &cfg, assets)?;
+    if !withdraw_msgs.is_empty() {
+        return Ok(Response::new()
+            .add_messages(withdraw_msgs)
+            .add_message(WasmMsg::Execute {
+                contract_addr: env.contract.address.to_string(),
+                msg: to_binary(&ExecuteMsg::Collect { assets })?,
+                funds: vec![],
+            })
+            .add_attribute("action", "collect"));
+    }
+
     // Swap all non ASTRO tokens
     let (mut response, bridge_assets) = swap_assets(
         deps.as_ref(),
@@ -278,6 +293,74 @@ fn collect(
     Ok(response.add_attribute("action", "collect"))
 }
 
+/// ## Description
+/// Builds the messages that burn the LP tokens among the collected assets and withdraw the liquidity
+/// behind them. Returns the messages along with the assets to collect afterwards: the assets that are not
+/// LP tokens and the pool assets of the withdrawn LP tokens. Returns no messages if none of the
+/// collected assets is an LP token with a non-zero balance.
+/// # Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **env** is an object of type [`Env`].
+///
+/// * **cfg** is an object of type [`Config`]. This is the Maker contract configuration.
+///
+/// * **assets** is a vector that contains objects of type [`AssetWithLimit`]. These are the collected assets.
+fn withdraw_lp_tokens(
+    deps: Deps,
+    env: &Env,
+    cfg: &Config,
+    assets: Vec<AssetWithLimit>,
+) -> Result<(Vec<CosmosMsg>, Vec<AssetWithLimit>), ContractError> {
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut remaining_assets: Vec<AssetWithLimit> = vec![];
+    let mut pool_assets: Vec<AssetInfo> = vec![];
+
+    for a in assets {
+        let pair_info = match get_lp_token_pair(deps, cfg, &a.info) {
+            Some(pair_info) => pair_info,
+            None => {
+                remaining_assets.push(a);
+                continue;
+            }
+        };
+
+        let mut balance = a
+            .info
+            .query_pool(&deps.querier, env.contract.address.clone())?;
+        if let Some(limit) = a.limit {
+            if limit < balance && limit > Uint128::zero() {
+                balance = limit;
+            }
+        }
+
+        if !balance.is_zero() {
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: a.info.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Send {
+                    contract: pair_info.contract_addr.to_string(),
+                    amount: balance,
+                    msg: to_binary(&PairCw20HookMsg::WithdrawLiquidity {
+                        min_assets_to_receive: None,
+                        deadline: None,
+                        callback: None,
+                    })?,
+                })?,
+                funds: vec![],
+            }));
+            pool_assets.extend(pair_info.asset_infos);
+        }
+    }
+
+    for info in pool_assets {
+        if !remaining_assets.iter().any(|a| a.info.equal(&info)) {
+            remaining_assets.push(AssetWithLimit { info, limit: None });
+        }
+    }
+
+    Ok((messages, remaining_assets))
+}
+
 /// ## Description
 /// This enum describes available token types that can be used as a SwapTarget.
 enum SwapTarget {
@@ -2,9 +2,10 @@ use crate::error::ContractError;
 use crate::state::{Config, BRIDGES};
 use astroport::asset::{Asset, AssetInfo, PairInfo};
 use astroport::maker::ExecuteMsg;
-use astroport::pair::Cw20HookMsg;
+use astroport::pair::{Cw20HookMsg, QueryMsg as PairQueryMsg};
 use astroport::querier::query_pair_info;
-use cosmwasm_std::{to_binary, Coin, Deps, Env, StdResult, SubMsg, Uint128, WasmMsg};
+use cosmwasm_std::{to_binary, Addr, Coin, Deps, Env, StdResult, SubMsg, Uint128, WasmMsg};
+use cw20::{Cw20QueryMsg, MinterResponse};
 
 /// The default bridge depth for a fee token
 pub const BRIDGES_INITIAL_DEPTH: u64 = 0;
@@ -50,6 +51,8 @@ pub fn build_swap_msg(
                 belief_price: None,
                 max_spread: Some(cfg.max_spread),
                 to: None,
+                deadline: None,
+                callback: None,
             })?,
             funds: vec![Coin {
                 denom: from.to_string(),
@@ -66,6 +69,8 @@ pub fn build_swap_msg(
                     belief_price: None,
                     max_spread: Some(cfg.max_spread),
                     to: None,
+                    deadline: None,
+                    callback: None,
                 })?,
             })?,
             funds: vec![],
@@ -150,3 +155,45 @@ pub fn get_pool(
     )
     .map_err(|_| ContractError::InvalidBridgeNoPool(from.clone(), to.clone()))
 }
+
+/// ## Description
+/// Returns the pair that minted the specified LP token. Returns [`None`] if the asset is not
+/// the LP token of a pair created by the factory.
+/// ## Params
+/// * **deps** is an object of type [`Deps`].
+///
+/// * **cfg** is an object of type [`Config`]. This is the Maker contract configuration.
+///
+/// * **asset_info** is an object of type [`AssetInfo`]. This is the asset to check.
+pub fn get_lp_token_pair(deps: Deps, cfg: &Config, asset_info: &AssetInfo) -> Option<PairInfo> {
+    let lp_token: &Addr = match asset_info {
+        AssetInfo::Token { contract_addr } => contract_addr,
+        AssetInfo::NativeToken { .. } => return None,
+    };
+
+    // LP tokens are minted by their pair
+    let minter: Option<MinterResponse> = deps
+        .querier
+        .query_wasm_smart(lp_token, &Cw20QueryMsg::Minter {})
+        .ok()?;
+    let pair_info: PairInfo = deps
+        .querier
+        .query_wasm_smart(minter?.minter, &PairQueryMsg::Pair {})
+        .ok()?;
+    if pair_info.liquidity_token != *lp_token {
+        return None;
+    }
+
+    // The pair must be registered in the factory
+    let factory_pair_info = query_pair_info(
+        &deps.querier,
+        cfg.factory_contract.clone(),
+        &pair_info.asset_infos,
+    )
+    .ok()?;
+    if factory_pair_info.contract_addr != pair_info.contract_addr {
+        return None;
+    }
+
+    Some(pair_info)
+}
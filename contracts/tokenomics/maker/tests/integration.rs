@@ -97,6 +97,7 @@ fn instantiate_contracts(
             pair_type: PairType::Xyk {},
             total_fee_bps: 0,
             maker_fee_bps: 0,
+            flash_loan_fee_bps: 0,
             is_disabled: false,
             is_generator_disabled: false,
         }],
@@ -386,6 +387,9 @@ fn create_pair(
                 slippage_tolerance: None,
                 auto_stake: None,
                 receiver: None,
+                min_lp_to_receive: None,
+                deadline: None,
+                callback: None,
             },
             &funds,
         )
@@ -905,6 +909,134 @@ fn collect_default_bridges() {
     );
 }
 
+#[test]
+fn collect_lp_tokens() {
+    let mut router = mock_app();
+    let owner = Addr::unchecked("owner");
+    let staking = Addr::unchecked("staking");
+    let user = Addr::unchecked("user0000");
+    let governance_percent = Uint64::new(10);
+
+    let (astro_token_instance, factory_instance, maker_instance, governance_instance) =
+        instantiate_contracts(
+            &mut router,
+            owner.clone(),
+            staking.clone(),
+            governance_percent,
+            None,
+        );
+
+    let test_token_instance = instantiate_token(
+        &mut router,
+        owner.clone(),
+        "Test token".to_string(),
+        "TEST".to_string(),
+    );
+
+    let uusd_asset = String::from(UUSD_DENOM);
+    let uluna_asset = String::from(ULUNA_DENOM);
+
+    // The Maker always checks the uusd - ASTRO and uluna - uusd pools
+    let pairs = vec![
+        [
+            native_asset(uusd_asset.clone(), Uint128::from(100_000_u128)),
+            token_asset(astro_token_instance.clone(), Uint128::from(100_000_u128)),
+        ],
+        [
+            native_asset(uluna_asset.clone(), Uint128::from(100_000_u128)),
+            native_asset(uusd_asset.clone(), Uint128::from(100_000_u128)),
+        ],
+    ];
+    for assets in pairs {
+        create_pair(
+            &mut router,
+            owner.clone(),
+            user.clone(),
+            &factory_instance,
+            assets,
+        );
+    }
+
+    let pair_info = create_pair(
+        &mut router,
+        owner.clone(),
+        user.clone(),
+        &factory_instance,
+        [
+            token_asset(test_token_instance.clone(), Uint128::from(100_000_u128)),
+            token_asset(astro_token_instance.clone(), Uint128::from(100_000_u128)),
+        ],
+    );
+
+    router
+        .execute_contract(
+            owner.clone(),
+            maker_instance.clone(),
+            &ExecuteMsg::EnableRewards { blocks: 1 },
+            &[],
+        )
+        .unwrap();
+
+    // Pairs that mint the Maker fee as LP tokens send them to the Maker
+    router
+        .execute_contract(
+            user.clone(),
+            pair_info.liquidity_token.clone(),
+            &cw20::Cw20ExecuteMsg::Transfer {
+                recipient: maker_instance.to_string(),
+                amount: Uint128::new(1_000),
+            },
+            &[],
+        )
+        .unwrap();
+
+    router
+        .execute_contract(
+            Addr::unchecked("anyone"),
+            maker_instance.clone(),
+            &ExecuteMsg::Collect {
+                assets: vec![AssetWithLimit {
+                    info: token_asset_info(pair_info.liquidity_token.clone()),
+                    limit: None,
+                }],
+            },
+            &[],
+        )
+        .unwrap();
+
+    // 1,000 LP tokens are burned for 1,000 TEST and 1,000 ASTRO and the TEST is swapped for 990 ASTRO
+    check_balance(
+        &mut router,
+        maker_instance.clone(),
+        pair_info.liquidity_token,
+        Uint128::zero(),
+    );
+    check_balance(
+        &mut router,
+        maker_instance.clone(),
+        test_token_instance,
+        Uint128::zero(),
+    );
+    check_balance(
+        &mut router,
+        maker_instance,
+        astro_token_instance.clone(),
+        Uint128::zero(),
+    );
+    check_balance(
+        &mut router,
+        governance_instance,
+        astro_token_instance.clone(),
+        Uint128::new(199),
+    );
+    check_balance(
+        &mut router,
+        staking,
+        astro_token_instance,
+        Uint128::new(1_791),
+    );
+}
+
 #[test]
 fn collect_maxdepth_test() {
     let mut router = mock_app();
@@ -6,8 +6,8 @@ use crate::factory::PairType;
 use crate::pair::QueryMsg as PairQueryMsg;
 use crate::querier::{query_balance, query_token_balance, query_token_symbol};
 use cosmwasm_std::{
-    to_binary, Addr, Api, BankMsg, Coin, CosmosMsg, Decimal, Deps, MessageInfo, QuerierWrapper,
-    StdError, StdResult, Uint128, WasmMsg,
+    to_binary, Addr, Api, BankMsg, Binary, Coin, CosmosMsg, Decimal, Deps, MessageInfo,
+    QuerierWrapper, StdError, StdResult, Uint128, WasmMsg,
 };
 use cw20::{Cw20ExecuteMsg, Cw20QueryMsg, MinterResponse};
 use terra_cosmwasm::TerraQuerier;
@@ -144,6 +144,68 @@ impl Asset {
     }
 }
 
+/// ## Description
+/// This structure describes a contract that receives the output of a pair action along with a message,
+/// so the contract can act on the received assets within the same transaction.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Callback {
+    /// The contract that receives the assets and the message
+    pub contract: String,
+    /// The binary message executed on the contract
+    pub msg: Binary,
+}
+
+impl Callback {
+    /// Returns the messages that send the assets to the callback contract.
+    ///
+    /// Every token of type [`AssetInfo::Token`] is sent with a [`Cw20ExecuteMsg::Send`] that carries the callback message.
+    /// All native tokens are attached (after the tax is deducted) as funds to a single [`WasmMsg::Execute`]
+    /// of the callback message. Zero amounts are skipped.
+    /// ## Params
+    /// * **self** is the type of the caller object.
+    ///
+    /// * **querier** is an object of type [`QuerierWrapper`]
+    ///
+    /// * **assets** are the assets to send to the callback contract.
+    pub fn into_msgs(
+        self,
+        querier: &QuerierWrapper,
+        assets: &[Asset],
+    ) -> StdResult<Vec<CosmosMsg>> {
+        let mut messages: Vec<CosmosMsg> = vec![];
+        let mut funds: Vec<Coin> = vec![];
+
+        for asset in assets.iter().filter(|asset| !asset.amount.is_zero()) {
+            match &asset.info {
+                AssetInfo::Token { contract_addr } => {
+                    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                        contract_addr: contract_addr.to_string(),
+                        msg: to_binary(&Cw20ExecuteMsg::Send {
+                            contract: self.contract.clone(),
+                            amount: asset.amount,
+                            msg: self.msg.clone(),
+                        })?,
+                        funds: vec![],
+                    }))
+                }
+                AssetInfo::NativeToken { .. } => funds.push(asset.deduct_tax(querier)?),
+            }
+        }
+
+        if !funds.is_empty() {
+            // The chain only accepts funds sorted by denomination
+            funds.sort_by(|a, b| a.denom.cmp(&b.denom));
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: self.contract,
+                msg: self.msg,
+                funds,
+            }));
+        }
+
+        Ok(messages)
+    }
+}
+
 /// This enum describes available Token types.
 /// ## Examples
 /// ```
@@ -296,6 +358,45 @@ impl PairInfo {
     }
 }
 
+/// This structure stores the main parameters for an Astroport pair that holds more than two assets.
+/// Its JSON representation matches [`PairInfo`] apart from the amount of assets.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MultiAssetPairInfo {
+    /// Asset information for the assets in the pool
+    pub asset_infos: Vec<AssetInfo>,
+    /// Pair contract address
+    pub contract_addr: Addr,
+    /// Pair LP token address
+    pub liquidity_token: Addr,
+    /// The pool type available in [`PairType`]
+    pub pair_type: PairType,
+}
+
+impl MultiAssetPairInfo {
+    /// Returns the balance for each asset in the pool.
+    /// ## Params
+    /// * **self** is the type of the caller object
+    ///
+    /// * **querier** is an object of type [`QuerierWrapper`]
+    ///
+    /// * **contract_addr** is pair's pool address.
+    pub fn query_pools(
+        &self,
+        querier: &QuerierWrapper,
+        contract_addr: Addr,
+    ) -> StdResult<Vec<Asset>> {
+        self.asset_infos
+            .iter()
+            .map(|asset_info| {
+                Ok(Asset {
+                    amount: asset_info.query_pool(querier, contract_addr.clone())?,
+                    info: asset_info.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
 /// Returns a lowercased, validated address upon success. Otherwise returns [`Err`]
 /// ## Params
 /// * **api** is an object of type [`Api`]
@@ -315,15 +416,15 @@ const TOKEN_SYMBOL_MAX_LENGTH: usize = 4;
 
 /// Returns a formatted LP token name
 /// ## Params
-/// * **asset_infos** is an array with two items the type of [`AssetInfo`].
+/// * **asset_infos** is a slice with items of type [`AssetInfo`]. These are the assets of the pool.
 ///
 /// * **querier** is an object of type [`QuerierWrapper`].
 pub fn format_lp_token_name(
-    asset_infos: [AssetInfo; 2],
+    asset_infos: &[AssetInfo],
     querier: &QuerierWrapper,
 ) -> StdResult<String> {
     let mut short_symbols: Vec<String> = vec![];
-    for asset_info in asset_infos {
+    for asset_info in asset_infos.iter().cloned() {
         let short_symbol: String;
         match asset_info {
             AssetInfo::NativeToken { denom } => {
@@ -336,7 +437,7 @@ pub fn format_lp_token_name(
         }
         short_symbols.push(short_symbol);
     }
-    Ok(format!("{}-{}-LP", short_symbols[0], short_symbols[1]).to_uppercase())
+    Ok(format!("{}-LP", short_symbols.join("-")).to_uppercase())
 }
 
 /// Returns an [`Asset`] object representing a native token and an amount of tokens.
@@ -0,0 +1,153 @@
+use cosmwasm_std::{Addr, StdError, StdResult, Storage, Uint128};
+use cw_storage_plus::Item;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// This structure describes the circuit breaker of a pair. Swaps and liquidity provision stop
+/// while the pair is paused and withdrawals stay open. Operations that move the spot price too far
+/// within a single block are rejected.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct CircuitBreaker {
+    /// The address that can pause the pair besides the factory owner
+    pub guardian: Option<Addr>,
+    /// The maximum spot price change (in bps) within a single block. Operations that exceed it fail
+    pub max_price_change_bps: Option<u16>,
+    /// Whether the pair was paused
+    pub paused: bool,
+    /// The height of the block in which the spot price last changed
+    pub block_height: u64,
+    /// The spot price at the start of that block
+    pub block_price: Uint128,
+}
+
+/// This structure holds the parameters that are returned from a status query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StatusResponse {
+    /// Whether the pair was paused
+    pub paused: bool,
+    /// The address that can pause the pair besides the factory owner
+    pub guardian: Option<Addr>,
+    /// The maximum spot price change (in bps) within a single block
+    pub max_price_change_bps: Option<u16>,
+}
+
+/// Stores the circuit breaker of a pair
+pub const CIRCUIT_BREAKER: Item<CircuitBreaker> = Item::new("circuit_breaker");
+
+/// Returns `true` if swaps and liquidity provision are stopped in the pair.
+/// ## Params
+/// `storage` is an object of type [`Storage`].
+pub fn is_paused(storage: &dyn Storage) -> StdResult<bool> {
+    Ok(CIRCUIT_BREAKER
+        .may_load(storage)?
+        .unwrap_or_default()
+        .paused)
+}
+
+/// Returns the guardian of a pair.
+/// ## Params
+/// `storage` is an object of type [`Storage`].
+pub fn guardian(storage: &dyn Storage) -> StdResult<Option<Addr>> {
+    Ok(CIRCUIT_BREAKER
+        .may_load(storage)?
+        .unwrap_or_default()
+        .guardian)
+}
+
+/// Pauses or resumes swaps and liquidity provision in a pair.
+/// ## Params
+/// `storage` is an object of type [`Storage`].
+///
+/// `paused` is whether to pause the pair.
+pub fn set_paused(storage: &mut dyn Storage, paused: bool) -> StdResult<()> {
+    let mut circuit_breaker = CIRCUIT_BREAKER.may_load(storage)?.unwrap_or_default();
+    circuit_breaker.paused = paused;
+    CIRCUIT_BREAKER.save(storage, &circuit_breaker)
+}
+
+/// Sets the guardian and the maximum price change of a pair.
+/// ## Params
+/// `storage` is an object of type [`Storage`].
+///
+/// `guardian` is the address that can pause the pair besides the factory owner.
+///
+/// `max_price_change_bps` is the maximum spot price change (in bps) within a single block.
+/// If [`None`], the price may change by any amount.
+pub fn update_circuit_breaker(
+    storage: &mut dyn Storage,
+    guardian: Option<Addr>,
+    max_price_change_bps: Option<u16>,
+) -> StdResult<()> {
+    if max_price_change_bps == Some(0) {
+        return Err(StdError::generic_err(
+            "The maximum price change must be greater than zero",
+        ));
+    }
+
+    let mut circuit_breaker = CIRCUIT_BREAKER.may_load(storage)?.unwrap_or_default();
+    circuit_breaker.guardian = guardian;
+    circuit_breaker.max_price_change_bps = max_price_change_bps;
+    CIRCUIT_BREAKER.save(storage, &circuit_breaker)
+}
+
+/// Records a spot price change of a pair. Returns `false` without recording the change if the price moved
+/// by more than the allowed amount since the start of the block. The pair must reject the operation then,
+/// so that the pool state stays as it was.
+/// ## Params
+/// `storage` is an object of type [`Storage`].
+///
+/// `block_height` is the current block height.
+///
+/// `price_before` is the spot price before the operation.
+///
+/// `price_after` is the spot price after the operation.
+pub fn track_price_change(
+    storage: &mut dyn Storage,
+    block_height: u64,
+    price_before: Uint128,
+    price_after: Uint128,
+) -> StdResult<bool> {
+    let mut circuit_breaker = CIRCUIT_BREAKER.may_load(storage)?.unwrap_or_default();
+    let max_price_change_bps = match circuit_breaker.max_price_change_bps {
+        Some(max_price_change_bps) => max_price_change_bps,
+        None => return Ok(true),
+    };
+
+    // The first price change in a block starts from the block's opening price
+    if circuit_breaker.block_height != block_height {
+        circuit_breaker.block_height = block_height;
+        circuit_breaker.block_price = price_before;
+    }
+    // An empty pool gets its opening price from the first liquidity provision
+    if circuit_breaker.block_price.is_zero() {
+        circuit_breaker.block_price = price_after;
+    }
+
+    let block_price = circuit_breaker.block_price;
+    let price_change = if price_after > block_price {
+        price_after - block_price
+    } else {
+        block_price - price_after
+    };
+
+    if price_change > block_price.multiply_ratio(max_price_change_bps, 10000u16) {
+        return Ok(false);
+    }
+
+    CIRCUIT_BREAKER.save(storage, &circuit_breaker)?;
+
+    Ok(true)
+}
+
+/// Returns the circuit breaker status of a pair.
+/// ## Params
+/// `storage` is an object of type [`Storage`].
+pub fn query_status(storage: &dyn Storage) -> StdResult<StatusResponse> {
+    let circuit_breaker = CIRCUIT_BREAKER.may_load(storage)?.unwrap_or_default();
+
+    Ok(StatusResponse {
+        paused: circuit_breaker.paused,
+        guardian: circuit_breaker.guardian,
+        max_price_change_bps: circuit_breaker.max_price_change_bps,
+    })
+}
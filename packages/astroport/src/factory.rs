@@ -1,4 +1,4 @@
-use crate::asset::{AssetInfo, PairInfo};
+use crate::asset::{AssetInfo, MultiAssetPairInfo, PairInfo};
 use cosmwasm_std::{Addr, Binary};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -45,6 +45,10 @@ pub struct PairConfig {
     pub total_fee_bps: u16,
     /// The amount of fees (in bps) collected by the Maker contract from this pair type
     pub maker_fee_bps: u16,
+    /// The fee (in bps) charged on flash loans taken from pairs of this type. Pair configs stored
+    /// before flash loans were added don't have it, so it defaults to zero
+    #[serde(default)]
+    pub flash_loan_fee_bps: u16,
     /// Whether a pair type is disabled or not. If it is disabled, new pairs cannot be
     /// created, but existing ones can still read the pair configuration
     pub is_disabled: bool,
@@ -58,7 +62,9 @@ impl PairConfig {
     /// ## Params
     /// `&self` is the type of the caller object.
     pub fn valid_fee_bps(&self) -> bool {
-        self.total_fee_bps <= 10_000 && self.maker_fee_bps <= 10_000
+        self.total_fee_bps <= 10_000
+            && self.maker_fee_bps <= 10_000
+            && self.flash_loan_fee_bps < 10_000
     }
 }
 
@@ -108,11 +114,34 @@ pub enum ExecuteMsg {
         /// Optional binary serialised parameters for custom pool types
         init_params: Option<Binary>,
     },
+    /// CreateMultiAssetPair instantiates a new pair contract that holds more than two assets.
+    CreateMultiAssetPair {
+        /// The pair type (exposed in [`PairType`])
+        pair_type: PairType,
+        /// The assets to create the pool for
+        asset_infos: Vec<AssetInfo>,
+        /// Optional binary serialised parameters for custom pool types
+        init_params: Option<Binary>,
+    },
+    /// UpdatePairFees sets fees for a specific pair that override the fees of its pair type.
+    UpdatePairFees {
+        /// The assets of the pair for which we update fees. The pair can hold two or more assets
+        asset_infos: Vec<AssetInfo>,
+        /// The total fees (in bps) charged by the pair. If [`None`], the pair type fee is used
+        total_fee_bps: Option<u16>,
+        /// The amount of fees (in bps) collected by the Maker contract from the pair. If [`None`], the pair type fee is used
+        maker_fee_bps: Option<u16>,
+    },
     /// Deregister removes a previously created pair.
     Deregister {
         /// The assets for which we deregister a pool
         asset_infos: [AssetInfo; 2],
     },
+    /// DeregisterMultiAssetPair removes a previously created pair that holds more than two assets.
+    DeregisterMultiAssetPair {
+        /// The assets for which we deregister a pool
+        asset_infos: Vec<AssetInfo>,
+    },
     /// ProposeNewOwner creates a proposal to change contract ownership.
     /// The validity period for the proposal is set in the `expires_in` variable.
     ProposeNewOwner {
@@ -145,11 +174,31 @@ pub enum QueryMsg {
         /// The number of pairs to read and return. It is an [`Option`] type.
         limit: Option<u32>,
     },
+    /// MultiAssetPair returns information about a specific pair that holds more than two assets.
+    /// The response is returned using a [`MultiAssetPairInfo`] structure
+    MultiAssetPair {
+        /// The assets for which we return a pair
+        asset_infos: Vec<AssetInfo>,
+    },
+    /// MultiAssetPairs returns an array of pairs that hold more than two assets according to the
+    /// specified parameters in `start_after` and `limit` variables.
+    MultiAssetPairs {
+        /// The pair item to start reading from
+        start_after: Option<Vec<AssetInfo>>,
+        /// The number of pairs to read and return. It is an [`Option`] type.
+        limit: Option<u32>,
+    },
     /// FeeInfo returns fee parameters for a specific pair. The response is returned using a [`FeeInfoResponse`] structure
     FeeInfo {
         /// The pair type for which we return fee information. Pair type is a [`PairType`] struct
         pair_type: PairType,
     },
+    /// PairFeeInfo returns the fee parameters used by a specific pair, including its fee overrides.
+    /// The response is returned using a [`FeeInfoResponse`] structure
+    PairFeeInfo {
+        /// The address of the pair for which we return fee information
+        pair_addr: String,
+    },
 }
 
 /// A custom struct for each query response that returns general contract settings/configs.
@@ -182,6 +231,13 @@ pub struct PairsResponse {
     pub pairs: Vec<PairInfo>,
 }
 
+/// A custom struct for each query response that returns an array of objects of type [`MultiAssetPairInfo`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MultiAssetPairsResponse {
+    /// Arrays of structs containing information about multiple pairs
+    pub pairs: Vec<MultiAssetPairInfo>,
+}
+
 /// A custom struct for each query response that returns an object of type [`FeeInfoResponse`].
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct FeeInfoResponse {
@@ -191,6 +247,9 @@ pub struct FeeInfoResponse {
     pub total_fee_bps: u16,
     /// Amount of fees (in bps) sent to the Maker contract
     pub maker_fee_bps: u16,
+    /// Amount of fees (in bps) charged on a flash loan. Factories without flash loans don't return it
+    #[serde(default)]
+    pub flash_loan_fee_bps: u16,
 }
 
 /// This is an enum used for setting and removing a contract address.
@@ -1,15 +1,25 @@
 pub mod asset;
+pub mod circuit_breaker;
 pub mod common;
 pub mod factory;
 pub mod generator;
 pub mod generator_proxy;
 pub mod maker;
+pub mod observation;
 pub mod oracle;
 pub mod pair;
+pub mod pair_concentrated;
+pub mod pair_lbp;
 pub mod pair_stable_bluna;
+pub mod pair_stable_lsd;
+pub mod pair_stable_n;
+pub mod pair_weighted;
 pub mod querier;
+pub mod reward_bearing;
 pub mod router;
+pub mod stable_math;
 pub mod staking;
+pub mod stats;
 pub mod token;
 pub mod vesting;
 pub mod whitelist;
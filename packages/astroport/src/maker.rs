@@ -27,7 +27,7 @@ pub struct InstantiateMsg {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    /// Collects and swaps fee tokens to ASTRO
+    /// Collects and swaps fee tokens to ASTRO. LP tokens are burned for their pool assets first
     Collect {
         /// The assets to swap to ASTRO
         assets: Vec<AssetWithLimit>,
@@ -0,0 +1,284 @@
+use cosmwasm_std::{StdError, StdResult, Storage, Uint128, Uint256};
+use cw_storage_plus::{Item, Map, U32Key};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// The default amount of observations stored by a pair
+pub const DEFAULT_OBSERVATIONS_SIZE: u32 = 100;
+/// The maximum amount of observations that can be stored by a pair
+pub const MAX_OBSERVATIONS_SIZE: u32 = 10_000;
+
+/// This structure describes the cumulative prices of a pair at a specific point in time.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Observation {
+    /// The timestamp of the observation
+    pub timestamp: u64,
+    /// The cumulative price for asset 0
+    pub price0_cumulative: Uint256,
+    /// The cumulative price for asset 1
+    pub price1_cumulative: Uint256,
+}
+
+/// This structure describes the ring buffer that stores the observations of a pair.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ObservationBuffer {
+    /// The amount of observations that can currently be stored
+    pub capacity: u32,
+    /// The capacity the buffer grows to once the last slot of the buffer is written
+    pub capacity_next: u32,
+    /// The slot of the latest observation
+    pub index: u32,
+    /// The amount of stored observations
+    pub length: u32,
+}
+
+impl Default for ObservationBuffer {
+    fn default() -> Self {
+        ObservationBuffer {
+            capacity: DEFAULT_OBSERVATIONS_SIZE,
+            capacity_next: DEFAULT_OBSERVATIONS_SIZE,
+            index: 0,
+            length: 0,
+        }
+    }
+}
+
+/// This structure holds the parameters that are returned from an observe query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ObserveResponse {
+    /// The cumulative prices for every requested point in time, in the order of the request
+    pub observations: Vec<Observation>,
+}
+
+/// Stores the state of the observation ring buffer
+pub const OBSERVATION_BUFFER: Item<ObservationBuffer> = Item::new("observation_buffer");
+/// Stores the observations by their slot in the ring buffer
+pub const OBSERVATIONS: Map<U32Key, Observation> = Map::new("observations");
+
+/// Stores a new observation after a pair has accumulated prices.
+/// ## Params
+/// `storage` is an object of type [`Storage`].
+///
+/// `block_time_last` is the timestamp when the pair accumulated prices before this update.
+///
+/// `block_time` is the timestamp of the new observation.
+///
+/// `price0_increment` is the amount that was added to the cumulative price for asset 0.
+///
+/// `price1_increment` is the amount that was added to the cumulative price for asset 1.
+pub fn store_observation(
+    storage: &mut dyn Storage,
+    block_time_last: u64,
+    block_time: u64,
+    price0_increment: Uint128,
+    price1_increment: Uint128,
+) -> StdResult<()> {
+    let mut buffer = OBSERVATION_BUFFER.may_load(storage)?.unwrap_or_default();
+
+    let latest = match latest_observation(storage, &buffer)? {
+        Some(observation) => observation,
+        None => {
+            let observation = Observation {
+                timestamp: block_time_last,
+                price0_cumulative: Uint256::zero(),
+                price1_cumulative: Uint256::zero(),
+            };
+            // A zero timestamp means that the pair never accumulated prices before
+            if block_time_last > 0 {
+                push_observation(storage, &mut buffer, &observation)?;
+            }
+            observation
+        }
+    };
+
+    push_observation(
+        storage,
+        &mut buffer,
+        &Observation {
+            timestamp: block_time,
+            price0_cumulative: latest.price0_cumulative + Uint256::from(price0_increment),
+            price1_cumulative: latest.price1_cumulative + Uint256::from(price1_increment),
+        },
+    )
+}
+
+/// Increases the amount of observations a pair stores. The buffer grows once its last slot is written,
+/// so that the stored observations stay in chronological order.
+/// ## Params
+/// `storage` is an object of type [`Storage`].
+///
+/// `size` is the new amount of observations to store.
+pub fn increase_observations_size(storage: &mut dyn Storage, size: u32) -> StdResult<()> {
+    let mut buffer = OBSERVATION_BUFFER.may_load(storage)?.unwrap_or_default();
+
+    if size <= buffer.capacity_next {
+        return Err(StdError::generic_err(
+            "The observations size can only be increased",
+        ));
+    }
+    if size > MAX_OBSERVATIONS_SIZE {
+        return Err(StdError::generic_err(format!(
+            "The observations size cannot be greater than {}",
+            MAX_OBSERVATIONS_SIZE
+        )));
+    }
+
+    buffer.capacity_next = size;
+    OBSERVATION_BUFFER.save(storage, &buffer)
+}
+
+/// Returns the cumulative prices of a pair `seconds_ago` seconds before `block_time`.
+/// Values between two observations are interpolated linearly.
+/// ## Params
+/// `storage` is an object of type [`Storage`].
+///
+/// `block_time_last` is the timestamp when the pair last accumulated prices.
+///
+/// `block_time` is the current block timestamp.
+///
+/// `price0_increment` is the amount the cumulative price for asset 0 grew by since `block_time_last`.
+///
+/// `price1_increment` is the amount the cumulative price for asset 1 grew by since `block_time_last`.
+///
+/// `seconds_ago` is a list of time offsets from `block_time` for which to return cumulative prices.
+pub fn observe(
+    storage: &dyn Storage,
+    block_time_last: u64,
+    block_time: u64,
+    price0_increment: Uint128,
+    price1_increment: Uint128,
+    seconds_ago: &[u64],
+) -> StdResult<ObserveResponse> {
+    let buffer = OBSERVATION_BUFFER.may_load(storage)?.unwrap_or_default();
+
+    let latest = latest_observation(storage, &buffer)?.unwrap_or(Observation {
+        timestamp: block_time_last,
+        price0_cumulative: Uint256::zero(),
+        price1_cumulative: Uint256::zero(),
+    });
+    let current = Observation {
+        timestamp: block_time,
+        price0_cumulative: latest.price0_cumulative + Uint256::from(price0_increment),
+        price1_cumulative: latest.price1_cumulative + Uint256::from(price1_increment),
+    };
+
+    let observations = seconds_ago
+        .iter()
+        .map(|seconds_ago| {
+            let target = block_time.checked_sub(*seconds_ago).ok_or_else(|| {
+                StdError::generic_err("The requested observation is before the genesis time")
+            })?;
+
+            if target >= latest.timestamp {
+                return Ok(interpolate(&latest, &current, target));
+            }
+
+            if buffer.length == 0 {
+                return Err(StdError::generic_err(
+                    "The requested observation is older than the oldest stored observation",
+                ));
+            }
+
+            // Observations are addressed by their position starting from the oldest one
+            let oldest_slot = oldest_slot(&buffer);
+            let load = |position: u32| {
+                OBSERVATIONS.load(storage, ((oldest_slot + position) % buffer.capacity).into())
+            };
+
+            let oldest = load(0)?;
+            if target < oldest.timestamp {
+                return Err(StdError::generic_err(
+                    "The requested observation is older than the oldest stored observation",
+                ));
+            }
+
+            // Find the two neighbouring observations around the target.
+            // The latest stored observation is always newer than the target here
+            let (mut low, mut high) = (0, buffer.length - 1);
+            while high - low > 1 {
+                let middle = (low + high) / 2;
+                if load(middle)?.timestamp <= target {
+                    low = middle;
+                } else {
+                    high = middle;
+                }
+            }
+
+            Ok(interpolate(&load(low)?, &load(high)?, target))
+        })
+        .collect::<StdResult<Vec<Observation>>>()?;
+
+    Ok(ObserveResponse { observations })
+}
+
+/// Returns the oldest stored observation or [`None`] if no observations are stored.
+/// ## Params
+/// `storage` is an object of type [`Storage`].
+pub fn oldest_observation(storage: &dyn Storage) -> StdResult<Option<Observation>> {
+    let buffer = OBSERVATION_BUFFER.may_load(storage)?.unwrap_or_default();
+    if buffer.length == 0 {
+        return Ok(None);
+    }
+
+    OBSERVATIONS.may_load(storage, oldest_slot(&buffer).into())
+}
+
+/// Returns the latest stored observation or [`None`] if no observations are stored.
+fn latest_observation(
+    storage: &dyn Storage,
+    buffer: &ObservationBuffer,
+) -> StdResult<Option<Observation>> {
+    if buffer.length == 0 {
+        return Ok(None);
+    }
+
+    OBSERVATIONS.may_load(storage, buffer.index.into())
+}
+
+/// Returns the slot of the oldest stored observation.
+fn oldest_slot(buffer: &ObservationBuffer) -> u32 {
+    if buffer.length < buffer.capacity {
+        0
+    } else {
+        (buffer.index + 1) % buffer.capacity
+    }
+}
+
+/// Writes an observation into the next slot of the ring buffer.
+fn push_observation(
+    storage: &mut dyn Storage,
+    buffer: &mut ObservationBuffer,
+    observation: &Observation,
+) -> StdResult<()> {
+    if buffer.length > 0 {
+        // Grow the buffer only at its end so the observations stay in chronological order
+        if buffer.index + 1 == buffer.capacity {
+            buffer.capacity = buffer.capacity_next;
+        }
+        buffer.index = (buffer.index + 1) % buffer.capacity;
+    }
+    buffer.length = (buffer.length + 1).min(buffer.capacity);
+
+    OBSERVATIONS.save(storage, buffer.index.into(), observation)?;
+    OBSERVATION_BUFFER.save(storage, buffer)
+}
+
+/// Returns the cumulative prices at `timestamp` interpolated between the observations `a` and `b`.
+fn interpolate(a: &Observation, b: &Observation, timestamp: u64) -> Observation {
+    if b.timestamp <= a.timestamp {
+        return Observation {
+            timestamp,
+            ..a.clone()
+        };
+    }
+
+    let elapsed = timestamp - a.timestamp;
+    let range = b.timestamp - a.timestamp;
+    Observation {
+        timestamp,
+        price0_cumulative: a.price0_cumulative
+            + (b.price0_cumulative - a.price0_cumulative).multiply_ratio(elapsed, range),
+        price1_cumulative: a.price1_cumulative
+            + (b.price1_cumulative - a.price1_cumulative).multiply_ratio(elapsed, range),
+    }
+}
@@ -1,9 +1,9 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::asset::{Asset, AssetInfo};
+use crate::asset::{Asset, AssetInfo, Callback};
 
-use cosmwasm_std::{Binary, Decimal, Uint128};
+use cosmwasm_std::{Addr, Binary, Decimal, Uint128};
 use cw20::Cw20ReceiveMsg;
 
 /// The default swap slippage
@@ -14,6 +14,9 @@ pub const MAX_ALLOWED_SLIPPAGE: &str = "0.5";
 // Decimal precision for TWAP results
 pub const TWAP_PRECISION: u8 = 6;
 
+/// The amount of LP tokens that is minted to the pair itself and locked forever on the first liquidity provision
+pub const MINIMUM_LIQUIDITY_AMOUNT: Uint128 = Uint128::new(1_000);
+
 /// This structure describes the parameters used for creating a contract.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
@@ -27,6 +30,22 @@ pub struct InstantiateMsg {
     pub init_params: Option<Binary>,
 }
 
+/// The maximum amount of assets in a pair that holds more than two assets
+pub const MAX_MULTI_ASSETS: usize = 5;
+
+/// This structure describes the parameters used for creating a pair that holds more than two assets.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MultiAssetInstantiateMsg {
+    /// Information about the assets in the pool
+    pub asset_infos: Vec<AssetInfo>,
+    /// The token contract code ID used for the tokens in the pool
+    pub token_code_id: u64,
+    /// The factory contract address
+    pub factory_addr: String,
+    /// Optional binary serialised parameters for custom pool types
+    pub init_params: Option<Binary>,
+}
+
 /// This structure describes the execute messages available in the contract.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -43,6 +62,12 @@ pub enum ExecuteMsg {
         auto_stake: Option<bool>,
         /// The receiver of LP tokens
         receiver: Option<String>,
+        /// The minimum amount of LP tokens to receive. The provision fails if fewer LP tokens are minted
+        min_lp_to_receive: Option<Uint128>,
+        /// The timestamp (in seconds) after which the message fails
+        deadline: Option<u64>,
+        /// The contract that receives the LP tokens along with a message instead of the receiver
+        callback: Option<Callback>,
     },
     /// Swap performs a swap in the pool
     Swap {
@@ -50,9 +75,79 @@ pub enum ExecuteMsg {
         belief_price: Option<Decimal>,
         max_spread: Option<Decimal>,
         to: Option<String>,
+        /// The timestamp (in seconds) after which the message fails
+        deadline: Option<u64>,
+        /// The contract that receives the output assets along with a message instead of the receiver
+        callback: Option<Callback>,
+    },
+    /// SwapExactOut performs a swap that returns an exact amount of ask assets.
+    /// The offer asset is the other pool asset and must be sent along with the message
+    SwapExactOut {
+        /// The asset and the exact amount to receive from the swap
+        ask_asset: Asset,
+        /// The maximum amount of offer assets to spend. Unused offer assets are refunded
+        max_offer_amount: Uint128,
+        /// The receiver of the ask assets
+        to: Option<String>,
+    },
+    /// FlashLoan lends a pool asset to a callback contract. Within the same transaction, the pool must get back
+    /// the borrowed amount plus the flash loan fee, or an equivalent amount of the other pool asset (a flash swap)
+    FlashLoan {
+        /// The asset and the amount to borrow
+        asset: Asset,
+        /// The contract that receives the borrowed assets and a [`FlashLoanCallbackMsg`]
+        callback_contract: String,
+        /// The binary message forwarded to the callback contract
+        msg: Binary,
+    },
+    /// Skim sends the pair balances that exceed the pool reserves to a recipient
+    Skim {
+        /// The recipient of the excess balances
+        to: String,
+    },
+    /// Sync sets the pool reserves to the current pair balances
+    Sync {},
+    /// IncreaseObservationsSize increases the amount of price observations stored by the pair
+    IncreaseObservationsSize {
+        /// The new amount of observations to store
+        size: u32,
     },
     /// Update the pair configuration
     UpdateConfig { params: Binary },
+    /// Pause stops swaps and liquidity provision in the pair. Withdrawals stay open
+    Pause {},
+    /// Unpause resumes swaps and liquidity provision in the pair after a pause
+    Unpause {},
+    /// UpdateCircuitBreaker sets who can pause the pair and how far the price can move within a block
+    UpdateCircuitBreaker {
+        /// The address that can pause the pair besides the factory owner
+        guardian: Option<String>,
+        /// The maximum spot price change (in bps) within a single block. Operations that exceed it fail
+        max_price_change_bps: Option<u16>,
+    },
+    /// Claims the rewards of the reward-bearing pool asset and sends them to the specified receiver.
+    /// Only supported by pairs created with a reward-bearing asset
+    ClaimReward {
+        /// An address which will receive the rewards
+        receiver: Option<String>,
+    },
+    /// Claims the rewards for a user that deposited their LP tokens in the Generator contract
+    ClaimRewardByGenerator {
+        /// The user whose LP tokens are/were staked in the Generator
+        user: String,
+        /// The user's LP token amount before the LP token transfer between their wallet and the Generator
+        user_share: Uint128,
+        /// The total LP token amount already deposited by all users in the Generator
+        total_share: Uint128,
+    },
+    /// Callback for distributing rewards
+    HandleReward {
+        previous_reward_balance: Uint128,
+        user: Addr,
+        user_share: Uint128,
+        total_share: Uint128,
+        receiver: Option<Addr>,
+    },
 }
 
 /// This structure describes a CW20 hook message.
@@ -64,9 +159,52 @@ pub enum Cw20HookMsg {
         belief_price: Option<Decimal>,
         max_spread: Option<Decimal>,
         to: Option<String>,
+        /// The timestamp (in seconds) after which the message fails
+        deadline: Option<u64>,
+        /// The contract that receives the output assets along with a message instead of the receiver
+        callback: Option<Callback>,
+    },
+    /// Swap the sent tokens for an exact amount of ask assets.
+    /// The amount of tokens sent is used as the maximum offer amount and the unused part is refunded
+    SwapExactOut {
+        /// The asset and the exact amount to receive from the swap
+        ask_asset: Asset,
+        /// The receiver of the ask assets
+        to: Option<String>,
     },
     /// Withdraw liquidity from the pool
-    WithdrawLiquidity {},
+    WithdrawLiquidity {
+        /// The minimum amounts of pool assets to receive. The withdrawal fails if it returns less of any listed asset
+        min_assets_to_receive: Option<Vec<Asset>>,
+        /// The timestamp (in seconds) after which the message fails
+        deadline: Option<u64>,
+        /// The contract that receives the output assets along with a message instead of the receiver
+        callback: Option<Callback>,
+    },
+    /// Withdraw liquidity from the pool in a single asset
+    WithdrawLiquidityOneAsset {
+        /// The asset to receive
+        ask_asset_info: AssetInfo,
+        /// The minimum amount of ask assets to receive
+        min_amount: Option<Uint128>,
+    },
+}
+
+/// This structure describes the message a pair sends to the callback contract of a flash loan.
+/// The callback contract must pay the pair back with a bank send or a CW20 transfer.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FlashLoanCallbackMsg {
+    FlashLoanCallback {
+        /// The address that requested the flash loan
+        sender: String,
+        /// The borrowed asset and amount
+        asset: Asset,
+        /// The fee to pay on top of the borrowed amount when repaying in the borrowed asset
+        fee_amount: Uint128,
+        /// The binary message specified in the flash loan request
+        msg: Binary,
+    },
 }
 
 /// This structure describes the query messages available in the contract.
@@ -87,6 +225,50 @@ pub enum QueryMsg {
     ReverseSimulation { ask_asset: Asset },
     /// Returns information about the cumulative prices in a [`CumulativePricesResponse`] object
     CumulativePrices {},
+    /// Returns the cumulative prices `seconds_ago` seconds before the current block time in a
+    /// [`crate::observation::ObserveResponse`] object.
+    Observe { seconds_ago: Vec<u64> },
+    /// Returns the circuit breaker status of the pair in a [`crate::circuit_breaker::StatusResponse`] object.
+    Status {},
+    /// Returns information about a single asset withdrawal simulation in a
+    /// [`WithdrawOneAssetSimulationResponse`] object.
+    SimulateWithdrawOneAsset {
+        lp_amount: Uint128,
+        ask_asset_info: AssetInfo,
+    },
+    /// Returns the amount of LP tokens minted for a deposit and the imbalance fees charged on it
+    /// in a [`SimulateProvideResponse`] object.
+    SimulateProvide { assets: [Asset; 2] },
+    /// Returns the assets received for burning LP tokens in a [`SimulateWithdrawResponse`] object.
+    /// The withdrawal is simulated in a single asset if `ask_asset_info` is specified
+    SimulateWithdraw {
+        lp_amount: Uint128,
+        ask_asset_info: Option<AssetInfo>,
+    },
+    /// Returns the marginal price of the offer asset in the other pool asset in a [`SpotPriceResponse`] object.
+    /// Only supported by the constant product and stableswap pairs.
+    SpotPrice { offer_asset_info: AssetInfo },
+    /// Returns the execution price, the spot price and the price impact of a swap in a
+    /// [`PriceImpactResponse`] object. Only supported by the constant product and stableswap pairs.
+    PriceImpact { offer_asset: Asset },
+    /// Returns the invariant of a stableswap pool divided by the LP token supply in a
+    /// [`VirtualPriceResponse`] object. Not supported by the constant product pair.
+    VirtualPrice {},
+    /// Returns the current invariant and amplification of a stableswap pool in a
+    /// [`PoolInvariantResponse`] object. Not supported by the constant product pair.
+    PoolInvariant {},
+    /// Returns the rewards of the reward-bearing pool asset that can be claimed by a specific user
+    /// using an [`Asset`] object. Only supported by pairs created with a reward-bearing asset.
+    PendingReward { user: String },
+    /// Returns the cumulative swap volume and commissions of the pair in a
+    /// [`crate::stats::StatsResponse`] object.
+    Stats {},
+    /// Returns the swap volume and commissions of the pair by day in a
+    /// [`crate::stats::DailyStatsResponse`] object.
+    DailyStats {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
 }
 
 /// This struct is used to return a query result with the total amount of LP tokens and the two assets in a specific pool.
@@ -116,6 +298,71 @@ pub struct SimulationResponse {
     pub spread_amount: Uint128,
     /// The amount of fees charged by the transaction
     pub commission_amount: Uint128,
+    /// The total fee rate currently charged by the pair
+    #[serde(default)]
+    pub total_fee_rate: Decimal,
+}
+
+/// This structure holds the parameters that are returned from a single asset withdrawal simulation response.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WithdrawOneAssetSimulationResponse {
+    /// The amount of ask assets returned by the withdrawal
+    pub return_amount: Uint128,
+    /// The amount of fees charged on the part of the withdrawal that acts as a swap
+    pub commission_amount: Uint128,
+}
+
+/// This structure holds the parameters that are returned from a liquidity provision simulation.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateProvideResponse {
+    /// The amount of LP tokens minted for the deposit
+    pub share: Uint128,
+    /// The imbalance fees charged on the deposit. Pairs that charge no imbalance fees return zero amounts
+    pub imbalance_fees: Vec<Asset>,
+}
+
+/// This structure holds the parameters that are returned from a withdrawal simulation.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateWithdrawResponse {
+    /// The assets returned by the withdrawal
+    pub assets: Vec<Asset>,
+    /// The amount of fees charged on the part of a single asset withdrawal that acts as a swap
+    pub commission_amount: Uint128,
+}
+
+/// This structure holds the parameters that are returned from a spot price query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SpotPriceResponse {
+    /// The amount of the other pool asset that one unit of the offer asset buys at the margin, not counting fees
+    pub price: Decimal,
+}
+
+/// This structure holds the parameters that are returned from a price impact query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceImpactResponse {
+    /// The amount of ask assets received per offer asset, after the commission is deducted
+    pub execution_price: Decimal,
+    /// The spot price of the offer asset before the swap
+    pub spot_price: Decimal,
+    /// The relative difference between the spot price and the price of the swap before the commission
+    /// is deducted, where 0.01 is 1%
+    pub price_impact: Decimal,
+}
+
+/// This structure holds the parameters that are returned from a virtual price query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VirtualPriceResponse {
+    /// The pool invariant (expressed with the LP token precision) divided by the LP token supply
+    pub virtual_price: Decimal,
+}
+
+/// This structure holds the parameters that are returned from a pool invariant query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PoolInvariantResponse {
+    /// The pool invariant D, expressed with the greater precision of the two pool assets
+    pub d: Uint128,
+    /// The current pool amplification
+    pub amp: Decimal,
 }
 
 /// This structure holds the parameters that are returned from a reverse swap simulation response.
@@ -153,6 +400,10 @@ pub struct MigrateMsg {}
 pub struct StablePoolParams {
     /// The current stableswap pool amplification
     pub amp: u64,
+    /// Whether the pool measures the amounts of CW20 tokens that it actually receives instead of
+    /// trusting the transferred amounts. Enables tokens that charge a fee on transfers or rebase
+    #[serde(default)]
+    pub fee_on_transfer: bool,
 }
 
 /// This structure stores a stableswap pool's configuration.
@@ -161,6 +412,9 @@ pub struct StablePoolParams {
 pub struct StablePoolConfig {
     /// The stableswap pool amplification
     pub amp: Decimal,
+    /// Whether the pool measures the amounts of CW20 tokens that it actually receives
+    #[serde(default)]
+    pub fee_on_transfer: bool,
 }
 
 /// This enum stores the options available to start and stop changing a stableswap pool's amplification.
@@ -170,3 +424,54 @@ pub enum StablePoolUpdateParams {
     StartChangingAmp { next_amp: u64, next_amp_time: u64 },
     StopChangingAmp {},
 }
+
+/// This structure describes the parameters of the dynamic fee mode of an xyk pool.
+/// The total fee scales linearly from `min_fee_bps` to `max_fee_bps` as the current price moves
+/// away from the time-weighted average price over `window`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct DynamicFeeParams {
+    /// The total fee (in bps) charged when the price does not move
+    pub min_fee_bps: u16,
+    /// The total fee (in bps) charged when the price movement reaches `max_volatility_bps`
+    pub max_fee_bps: u16,
+    /// The period (in seconds) over which the average price is measured
+    pub window: u64,
+    /// The price movement (in bps) at which the total fee reaches `max_fee_bps`
+    pub max_volatility_bps: u16,
+}
+
+/// This structure holds the `init_params` of an xyk pool.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct XykPoolParams {
+    /// Whether the pool measures the amounts of CW20 tokens that it actually receives instead of
+    /// trusting the transferred amounts. Enables tokens that charge a fee on transfers or rebase
+    #[serde(default)]
+    pub fee_on_transfer: bool,
+}
+
+/// This structure stores an xyk pool's configuration.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct XykPoolConfig {
+    /// The dynamic fee parameters. If [`None`], the pool charges the fee set in the factory
+    pub dynamic_fee: Option<DynamicFeeParams>,
+    /// Whether the Maker fee is minted as LP tokens on liquidity events instead of being sent on every swap
+    #[serde(default)]
+    pub mint_maker_fee: bool,
+    /// Whether the pool measures the amounts of CW20 tokens that it actually receives
+    #[serde(default)]
+    pub fee_on_transfer: bool,
+}
+
+/// This enum stores the options available to enable and disable the dynamic fee mode and the
+/// Maker fee minting mode of an xyk pool.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum XykPoolUpdateParams {
+    EnableDynamicFee { params: DynamicFeeParams },
+    DisableDynamicFee {},
+    EnableMakerFeeMinting {},
+    DisableMakerFeeMinting {},
+}
@@ -0,0 +1,223 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::asset::{Asset, Callback};
+
+use cosmwasm_std::{Addr, Decimal, Uint128};
+use cw20::Cw20ReceiveMsg;
+
+/// The lowest tick that can be used by a position. The price at this tick is about 1e-12
+pub const MIN_TICK: i32 = -276_324;
+/// The highest tick that can be used by a position. The price at this tick is about 1e12
+pub const MAX_TICK: i32 = 276_324;
+/// The default amount of positions returned by a positions query
+pub const DEFAULT_POSITIONS_LIMIT: u32 = 10;
+/// The maximum amount of positions returned by a positions query
+pub const MAX_POSITIONS_LIMIT: u32 = 30;
+
+/// This struct is used to store the parameters of a concentrated liquidity pool.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ConcentratedPoolParams {
+    /// The position ticks must be multiples of the tick spacing
+    pub tick_spacing: u32,
+    /// The initial price of the first pool asset expressed in the second pool asset
+    pub initial_price: Decimal,
+}
+
+/// This struct is used to return the concentrated liquidity pool configuration.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ConcentratedPoolConfig {
+    /// The position ticks must be multiples of the tick spacing
+    pub tick_spacing: u32,
+}
+
+/// This structure describes the execute messages available in the contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// Receives a message of type [`Cw20ReceiveMsg`]
+    Receive(Cw20ReceiveMsg),
+    /// Swap performs a swap in the pool
+    Swap {
+        offer_asset: Asset,
+        belief_price: Option<Decimal>,
+        max_spread: Option<Decimal>,
+        to: Option<String>,
+        /// The timestamp (in seconds) after which the message fails
+        deadline: Option<u64>,
+        /// The contract that receives the output assets along with a message instead of the receiver
+        callback: Option<Callback>,
+    },
+    /// CreatePosition opens a new position that provides liquidity between two ticks
+    CreatePosition {
+        /// The lower tick of the position
+        lower_tick: i32,
+        /// The upper tick of the position
+        upper_tick: i32,
+        /// The maximum amounts of assets to deposit. The unused native assets are refunded
+        assets: [Asset; 2],
+        /// The owner of the position
+        receiver: Option<String>,
+        /// The minimum amount of liquidity the position must get. The call fails if the deposit buys less liquidity
+        min_liquidity_to_receive: Option<Uint128>,
+        /// The timestamp (in seconds) after which the message fails
+        deadline: Option<u64>,
+    },
+    /// IncreaseLiquidity adds liquidity to an existing position
+    IncreaseLiquidity {
+        /// The position to add liquidity to
+        position_id: u64,
+        /// The maximum amounts of assets to deposit. The unused native assets are refunded
+        assets: [Asset; 2],
+        /// The minimum amount of liquidity the position must get. The call fails if the deposit buys less liquidity
+        min_liquidity_to_receive: Option<Uint128>,
+        /// The timestamp (in seconds) after which the message fails
+        deadline: Option<u64>,
+    },
+    /// DecreaseLiquidity withdraws liquidity from a position together with the fees it earned
+    DecreaseLiquidity {
+        /// The position to withdraw liquidity from
+        position_id: u64,
+        /// The amount of liquidity to withdraw. All of the position liquidity is withdrawn if not specified
+        liquidity: Option<Uint128>,
+        /// The receiver of the withdrawn assets
+        receiver: Option<String>,
+        /// The minimum amounts of pool assets to withdraw, not counting the collected fees.
+        /// The withdrawal fails if it returns less of any listed asset
+        min_assets_to_receive: Option<Vec<Asset>>,
+        /// The timestamp (in seconds) after which the message fails
+        deadline: Option<u64>,
+        /// The contract that receives the output assets along with a message instead of the receiver
+        callback: Option<Callback>,
+    },
+    /// CollectFees sends the fees earned by a position to its owner
+    CollectFees {
+        /// The position to collect fees from
+        position_id: u64,
+        /// The receiver of the fees
+        receiver: Option<String>,
+    },
+    /// TransferPosition changes the owner of a position
+    TransferPosition {
+        /// The position to transfer
+        position_id: u64,
+        /// The new owner of the position
+        recipient: String,
+    },
+}
+
+/// This structure describes a CW20 hook message.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    /// Swap a given amount of asset
+    Swap {
+        belief_price: Option<Decimal>,
+        max_spread: Option<Decimal>,
+        to: Option<String>,
+        /// The timestamp (in seconds) after which the message fails
+        deadline: Option<u64>,
+        /// The contract that receives the output assets along with a message instead of the receiver
+        callback: Option<Callback>,
+    },
+}
+
+/// This structure describes the query messages available in the contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    /// Returns information about a pair in an object of type [`super::asset::PairInfo`].
+    Pair {},
+    /// Returns the pair balances and the active liquidity in an object of type [`super::pair::PoolResponse`].
+    Pool {},
+    /// Returns contract configuration settings in a custom [`super::pair::ConfigResponse`] structure.
+    Config {},
+    /// Returns information about a swap simulation in a [`super::pair::SimulationResponse`] object.
+    Simulation { offer_asset: Asset },
+    /// Returns information about a reverse simulation in a [`super::pair::ReverseSimulationResponse`] object.
+    ReverseSimulation { ask_asset: Asset },
+    /// Returns information about the cumulative prices in a [`super::pair::CumulativePricesResponse`] object
+    CumulativePrices {},
+    /// Returns the current price, tick and active liquidity in a [`StateResponse`] object.
+    State {},
+    /// Returns information about a position in a [`PositionResponse`] object.
+    Position { position_id: u64 },
+    /// Returns the positions of an owner in a [`PositionsResponse`] object.
+    Positions {
+        owner: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the liquidity that a new position between the specified ticks would get for the
+    /// specified maximum deposits and the amounts it would take in a [`SimulatePositionResponse`] object.
+    SimulateProvide {
+        lower_tick: i32,
+        upper_tick: i32,
+        assets: [Asset; 2],
+    },
+    /// Returns the amounts of assets that withdrawing liquidity from a position would return,
+    /// not counting the fees, in a [`SimulatePositionResponse`] object.
+    SimulateWithdraw {
+        position_id: u64,
+        liquidity: Option<Uint128>,
+    },
+    /// Returns the cumulative swap volume and commissions of the pair in a
+    /// [`crate::stats::StatsResponse`] object.
+    Stats {},
+    /// Returns the swap volume and commissions of the pair by day in a
+    /// [`crate::stats::DailyStatsResponse`] object.
+    DailyStats {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+/// This structure holds the parameters that are returned from a state query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StateResponse {
+    /// The current price of the first pool asset expressed in the second pool asset
+    pub price: Decimal,
+    /// The square root of the current price
+    pub sqrt_price: Decimal,
+    /// The current tick
+    pub tick: i32,
+    /// The liquidity of the positions whose range contains the current price
+    pub liquidity: Uint128,
+}
+
+/// This structure holds the parameters that are returned from a position query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PositionResponse {
+    /// The position identifier
+    pub position_id: u64,
+    /// The owner of the position
+    pub owner: Addr,
+    /// The lower tick of the position
+    pub lower_tick: i32,
+    /// The upper tick of the position
+    pub upper_tick: i32,
+    /// The liquidity of the position
+    pub liquidity: Uint128,
+    /// The assets that would be withdrawn for the position liquidity at the current price
+    pub assets: [Asset; 2],
+    /// The fees earned by the position that were not collected yet
+    pub fees: [Asset; 2],
+}
+
+/// This structure holds the parameters that are returned from a positions query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PositionsResponse {
+    pub positions: Vec<PositionResponse>,
+}
+
+/// This structure holds the parameters that are returned from the simulate provide and
+/// simulate withdraw queries.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulatePositionResponse {
+    /// The liquidity that is added to or removed from the position
+    pub liquidity: Uint128,
+    /// The assets that enter or leave the pool
+    pub assets: [Asset; 2],
+}
@@ -0,0 +1,65 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Decimal};
+
+/// This struct is used to store the parameters of a liquidity bootstrapping pool.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct LbpPoolParams {
+    /// The pool owner. Only the owner can provide liquidity before the sale starts
+    pub owner: String,
+    /// The relative weights of the pool assets at the start of the sale, e.g. `[90, 10]`
+    pub start_weights: [u64; 2],
+    /// The relative weights of the pool assets at the end of the sale, e.g. `[30, 70]`
+    pub end_weights: [u64; 2],
+    /// The timestamp (in seconds) when the sale starts and the weights start changing
+    pub start_time: u64,
+    /// The timestamp (in seconds) when the sale ends and the weights stop changing
+    pub end_time: u64,
+    /// Whether addresses other than the owner can provide liquidity after the sale starts
+    pub allow_third_party_liquidity: bool,
+    /// Whether the pool measures the amounts of CW20 tokens that it actually receives instead of
+    /// trusting the transferred amounts. Enables tokens that charge a fee on transfers or rebase
+    #[serde(default)]
+    pub fee_on_transfer: bool,
+}
+
+/// This enum stores the options available to update a liquidity bootstrapping pool.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LbpPoolUpdateParams {
+    /// Starts changing the weights from the current weights to `end_weights` by `end_time`.
+    /// The weights start changing at the sale start if the sale has not started yet
+    StartChangingWeights {
+        end_weights: [u64; 2],
+        end_time: u64,
+    },
+    /// Stops changing the weights and keeps the current weights
+    StopChangingWeights {},
+    /// Allows or disallows liquidity provision by addresses other than the owner
+    SetThirdPartyLiquidity { allowed: bool },
+}
+
+/// This struct is used to return the liquidity bootstrapping pool configuration.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct LbpPoolConfig {
+    /// The pool owner
+    pub owner: Addr,
+    /// The current normalized weights of the pool assets. They sum up to 1
+    pub weights: [Decimal; 2],
+    /// The normalized weights of the pool assets at `start_time`
+    pub start_weights: [Decimal; 2],
+    /// The normalized weights of the pool assets at `end_time`
+    pub end_weights: [Decimal; 2],
+    /// The timestamp (in seconds) when the weights start changing
+    pub start_time: u64,
+    /// The timestamp (in seconds) when the weights stop changing
+    pub end_time: u64,
+    /// Whether addresses other than the owner can provide liquidity after the sale starts
+    pub allow_third_party_liquidity: bool,
+    /// Whether the pool measures the amounts of CW20 tokens that it actually receives
+    #[serde(default)]
+    pub fee_on_transfer: bool,
+}
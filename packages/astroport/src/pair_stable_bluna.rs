@@ -1,7 +1,7 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::asset::Asset;
+use crate::asset::{Asset, AssetInfo, Callback};
 
 use cosmwasm_std::{Addr, Binary, Decimal, Uint128};
 use cw20::Cw20ReceiveMsg;
@@ -23,6 +23,12 @@ pub enum ExecuteMsg {
         auto_stake: Option<bool>,
         /// The receiver of LP tokens
         receiver: Option<String>,
+        /// The minimum amount of LP tokens to receive. The provision fails if fewer LP tokens are minted
+        min_lp_to_receive: Option<Uint128>,
+        /// The timestamp (in seconds) after which the message fails
+        deadline: Option<u64>,
+        /// The contract that receives the LP tokens along with a message instead of the receiver
+        callback: Option<Callback>,
     },
     /// Swap performs a swap in the pool
     Swap {
@@ -30,6 +36,42 @@ pub enum ExecuteMsg {
         belief_price: Option<Decimal>,
         max_spread: Option<Decimal>,
         to: Option<String>,
+        /// The timestamp (in seconds) after which the message fails
+        deadline: Option<u64>,
+        /// The contract that receives the output assets along with a message instead of the receiver
+        callback: Option<Callback>,
+    },
+    /// SwapExactOut performs a swap that returns an exact amount of ask assets.
+    /// The offer asset is the other pool asset and must be sent along with the message
+    SwapExactOut {
+        /// The asset and the exact amount to receive from the swap
+        ask_asset: Asset,
+        /// The maximum amount of offer assets to spend. Unused offer assets are refunded
+        max_offer_amount: Uint128,
+        /// The receiver of the ask assets
+        to: Option<String>,
+    },
+    /// FlashLoan lends a pool asset to a callback contract. Within the same transaction, the pool must get back
+    /// the borrowed amount plus the flash loan fee, or an equivalent amount of the other pool asset (a flash swap)
+    FlashLoan {
+        /// The asset and the amount to borrow
+        asset: Asset,
+        /// The contract that receives the borrowed assets and a [`crate::pair::FlashLoanCallbackMsg`]
+        callback_contract: String,
+        /// The binary message forwarded to the callback contract
+        msg: Binary,
+    },
+    /// Skim sends the pair balances that exceed the pool reserves to a recipient
+    Skim {
+        /// The recipient of the excess balances
+        to: String,
+    },
+    /// Sync sets the pool reserves to the current pair balances
+    Sync {},
+    /// IncreaseObservationsSize increases the amount of price observations stored by the pair
+    IncreaseObservationsSize {
+        /// The new amount of observations to store
+        size: u32,
     },
     /// Update the pair configuration
     UpdateConfig { params: Binary },
@@ -55,6 +97,17 @@ pub enum ExecuteMsg {
         total_share: Uint128,
         receiver: Option<Addr>,
     },
+    /// Pause stops swaps and liquidity provision in the pair. Withdrawals stay open
+    Pause {},
+    /// Unpause resumes swaps and liquidity provision in the pair after a pause
+    Unpause {},
+    /// UpdateCircuitBreaker sets who can pause the pair and how far the price can move within a block
+    UpdateCircuitBreaker {
+        /// The address that can pause the pair besides the factory owner
+        guardian: Option<String>,
+        /// The maximum spot price change (in bps) within a single block. Operations that exceed it fail
+        max_price_change_bps: Option<u16>,
+    },
 }
 
 /// This structure describes the query messages available in the contract.
@@ -75,8 +128,43 @@ pub enum QueryMsg {
     ReverseSimulation { ask_asset: Asset },
     /// Returns information about cumulative prices (used for TWAPs) in a [`super::pair::CumulativePricesResponse`] object.
     CumulativePrices {},
+    /// Returns the cumulative prices `seconds_ago` seconds before the current block time in a
+    /// [`crate::observation::ObserveResponse`] object.
+    Observe { seconds_ago: Vec<u64> },
     /// Returns pending token rewards that can be claimed by a specific user using a [`Asset`] object.
     PendingReward { user: String },
+    /// Returns the circuit breaker status of the pair in a [`crate::circuit_breaker::StatusResponse`] object.
+    Status {},
+    /// Returns information about a single asset withdrawal simulation in a
+    /// [`super::pair::WithdrawOneAssetSimulationResponse`] object.
+    SimulateWithdrawOneAsset {
+        lp_amount: Uint128,
+        ask_asset_info: AssetInfo,
+    },
+    /// Returns the amount of LP tokens minted for a deposit and the imbalance fees charged on it
+    /// in a [`super::pair::SimulateProvideResponse`] object.
+    SimulateProvide { assets: [Asset; 2] },
+    /// Returns the assets received for burning LP tokens in a [`super::pair::SimulateWithdrawResponse`] object.
+    /// The withdrawal is simulated in a single asset if `ask_asset_info` is specified
+    SimulateWithdraw {
+        lp_amount: Uint128,
+        ask_asset_info: Option<AssetInfo>,
+    },
+    /// Returns the pool invariant divided by the LP token supply in a
+    /// [`super::pair::VirtualPriceResponse`] object.
+    VirtualPrice {},
+    /// Returns the current pool invariant and amplification in a
+    /// [`super::pair::PoolInvariantResponse`] object.
+    PoolInvariant {},
+    /// Returns the cumulative swap volume and commissions of the pair in a
+    /// [`crate::stats::StatsResponse`] object.
+    Stats {},
+    /// Returns the swap volume and commissions of the pair by day in a
+    /// [`crate::stats::DailyStatsResponse`] object.
+    DailyStats {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
 }
 
 /// This struct is used to store bLUNA stableswap specific parameters.
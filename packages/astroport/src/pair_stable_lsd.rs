@@ -0,0 +1,89 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::asset::AssetInfo;
+
+use cosmwasm_std::{Addr, Decimal};
+
+/// This struct is used to store the parameters of a stableswap pool with rate-scaled assets.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StablePoolParams {
+    /// The current pool amplification
+    pub amp: u64,
+    /// The contracts that return the redemption rates of the pool assets.
+    /// Assets without a rate provider use a rate of 1
+    #[serde(default)]
+    pub rate_providers: Vec<RateProvider>,
+    /// Whether the pool measures the amounts of CW20 tokens that it actually receives instead of
+    /// trusting the transferred amounts. Enables tokens that charge a fee on transfers or rebase
+    #[serde(default)]
+    pub fee_on_transfer: bool,
+}
+
+/// This struct links a pool asset to the contract that returns its redemption rate.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RateProvider {
+    /// The pool asset
+    pub asset_info: AssetInfo,
+    /// The contract that answers a [`RateProviderQueryMsg::Rate`] query for the asset
+    pub rate_provider: String,
+}
+
+/// This struct is used to return the stableswap pool configuration.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StablePoolConfig {
+    /// The current pool amplification
+    pub amp: Decimal,
+    /// Whether the pool measures the amounts of CW20 tokens that it actually receives
+    #[serde(default)]
+    pub fee_on_transfer: bool,
+    /// The current rates of the pool assets
+    pub rates: Vec<AssetRate>,
+}
+
+/// This struct describes the rate that is applied to a pool asset.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AssetRate {
+    /// The pool asset
+    pub asset_info: AssetInfo,
+    /// The contract that returns the rate of the asset
+    pub rate_provider: Option<Addr>,
+    /// The current rate of the asset
+    pub rate: Decimal,
+}
+
+/// This enum stores the options available to update the parameters of a rate-scaled stableswap pool.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StablePoolUpdateParams {
+    StartChangingAmp {
+        next_amp: u64,
+        next_amp_time: u64,
+    },
+    StopChangingAmp {},
+    /// Sets the rate provider of a pool asset. Removing it sets the rate of the asset back to 1
+    UpdateRateProvider {
+        asset_info: AssetInfo,
+        rate_provider: Option<String>,
+    },
+}
+
+/// This enum describes the query that a rate provider contract must answer.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RateProviderQueryMsg {
+    /// Returns the redemption rate of an asset in a [`RateResponse`] object
+    Rate { asset_info: AssetInfo },
+}
+
+/// This struct is used to return the redemption rate of an asset.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RateResponse {
+    /// The amount of the underlying asset that one unit of the asset is worth
+    pub rate: Decimal,
+}
@@ -0,0 +1,131 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::asset::{Asset, AssetInfo, Callback};
+
+use cosmwasm_std::{Binary, Decimal, Uint128};
+use cw20::Cw20ReceiveMsg;
+
+/// This structure describes the execute messages available in the contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// ## Description
+    /// Receives a message of type [`Cw20ReceiveMsg`]
+    Receive(Cw20ReceiveMsg),
+    /// ProvideLiquidity allows someone to provide liquidity in the pool
+    ProvideLiquidity {
+        /// The assets to deposit. Assets that are not listed are not deposited
+        assets: Vec<Asset>,
+        /// Determines whether the LP tokens minted for the user is auto_staked in the Generator contract
+        auto_stake: Option<bool>,
+        /// The receiver of LP tokens
+        receiver: Option<String>,
+        /// The minimum amount of LP tokens to receive. The provision fails if fewer LP tokens are minted
+        min_lp_to_receive: Option<Uint128>,
+        /// The timestamp (in seconds) after which the message fails
+        deadline: Option<u64>,
+        /// The contract that receives the LP tokens along with a message instead of the receiver
+        callback: Option<Callback>,
+    },
+    /// Swap performs a swap in the pool
+    Swap {
+        /// The asset to swap
+        offer_asset: Asset,
+        /// The asset to receive
+        ask_asset_info: AssetInfo,
+        belief_price: Option<Decimal>,
+        max_spread: Option<Decimal>,
+        to: Option<String>,
+        /// The timestamp (in seconds) after which the message fails
+        deadline: Option<u64>,
+        /// The contract that receives the output assets along with a message instead of the receiver
+        callback: Option<Callback>,
+    },
+    /// Update the pair configuration
+    UpdateConfig { params: Binary },
+}
+
+/// This structure describes a CW20 hook message.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    /// Swap a given amount of asset
+    Swap {
+        /// The asset to receive
+        ask_asset_info: AssetInfo,
+        belief_price: Option<Decimal>,
+        max_spread: Option<Decimal>,
+        to: Option<String>,
+        /// The timestamp (in seconds) after which the message fails
+        deadline: Option<u64>,
+        /// The contract that receives the output assets along with a message instead of the receiver
+        callback: Option<Callback>,
+    },
+    /// Withdraw liquidity from the pool
+    WithdrawLiquidity {
+        /// The minimum amounts of pool assets to receive. The withdrawal fails if it returns less of any listed asset
+        min_assets_to_receive: Option<Vec<Asset>>,
+        /// The timestamp (in seconds) after which the message fails
+        deadline: Option<u64>,
+        /// The contract that receives the output assets along with a message instead of the receiver
+        callback: Option<Callback>,
+    },
+}
+
+/// This structure describes the query messages available in the contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    /// Returns information about a pair in an object of type [`super::asset::MultiAssetPairInfo`].
+    Pair {},
+    /// Returns information about a pool in an object of type [`PoolResponse`].
+    Pool {},
+    /// Returns contract configuration settings in a custom [`super::pair::ConfigResponse`] structure.
+    Config {},
+    /// Returns information about the share of the pool in a vector that contains objects of type [`Asset`].
+    Share { amount: Uint128 },
+    /// Returns information about a swap simulation in a [`super::pair::SimulationResponse`] object.
+    Simulation {
+        offer_asset: Asset,
+        ask_asset_info: AssetInfo,
+    },
+    /// Returns information about a reverse simulation in a [`super::pair::ReverseSimulationResponse`] object.
+    ReverseSimulation {
+        offer_asset_info: AssetInfo,
+        ask_asset: Asset,
+    },
+    /// Returns the pool invariant divided by the LP token supply in a
+    /// [`super::pair::VirtualPriceResponse`] object.
+    VirtualPrice {},
+    /// Returns the current pool invariant and amplification in a
+    /// [`super::pair::PoolInvariantResponse`] object.
+    PoolInvariant {},
+    /// Returns the amount of LP tokens minted for a deposit and the imbalance fees charged on it
+    /// in a [`super::pair::SimulateProvideResponse`] object.
+    SimulateProvide { assets: Vec<Asset> },
+    /// Returns the assets received for burning LP tokens in a [`super::pair::SimulateWithdrawResponse`] object.
+    /// The pair only supports balanced withdrawals, so `ask_asset_info` must be empty
+    SimulateWithdraw {
+        lp_amount: Uint128,
+        ask_asset_info: Option<AssetInfo>,
+    },
+    /// Returns the cumulative swap volume and commissions of the pair in a
+    /// [`crate::stats::StatsResponse`] object.
+    Stats {},
+    /// Returns the swap volume and commissions of the pair by day in a
+    /// [`crate::stats::DailyStatsResponse`] object.
+    DailyStats {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+/// This struct is used to return a query result with the total amount of LP tokens and the assets in a specific pool.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PoolResponse {
+    /// The assets in the pool together with asset amounts
+    pub assets: Vec<Asset>,
+    /// The total amount of LP tokens currently issued
+    pub total_share: Uint128,
+}
@@ -0,0 +1,30 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::Decimal;
+
+/// The minimum normalized weight of a pool asset (1%).
+pub const MIN_WEIGHT_BPS: u64 = 100;
+
+/// This struct is used to store the parameters of a weighted pool.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct WeightedPoolParams {
+    /// The relative weights of the pool assets in the same order as the asset infos, e.g. `[80, 20]`
+    pub weights: [u64; 2],
+    /// Whether the pool measures the amounts of CW20 tokens that it actually receives instead of
+    /// trusting the transferred amounts. Enables tokens that charge a fee on transfers or rebase
+    #[serde(default)]
+    pub fee_on_transfer: bool,
+}
+
+/// This struct is used to return the weighted pool configuration.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct WeightedPoolConfig {
+    /// The normalized weights of the pool assets. They sum up to 1
+    pub weights: [Decimal; 2],
+    /// Whether the pool measures the amounts of CW20 tokens that it actually receives
+    #[serde(default)]
+    pub fee_on_transfer: bool,
+}
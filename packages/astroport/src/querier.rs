@@ -142,6 +142,28 @@ pub struct FeeInfo {
     pub total_fee_rate: Decimal,
     /// The amount of fees sent to the Maker contract
     pub maker_fee_rate: Decimal,
+    /// The amount of fees charged per flash loan
+    pub flash_loan_fee_rate: Decimal,
+}
+
+impl From<FeeInfoResponse> for FeeInfo {
+    fn from(res: FeeInfoResponse) -> Self {
+        FeeInfo {
+            fee_address: res.fee_address,
+            total_fee_rate: Decimal::from_ratio(
+                Uint128::from(res.total_fee_bps),
+                Uint128::new(10000),
+            ),
+            maker_fee_rate: Decimal::from_ratio(
+                Uint128::from(res.maker_fee_bps),
+                Uint128::new(10000),
+            ),
+            flash_loan_fee_rate: Decimal::from_ratio(
+                Uint128::from(res.flash_loan_fee_bps),
+                Uint128::new(10000),
+            ),
+        }
+    }
 }
 
 /// Returns the fee information for a specific pair type.
@@ -161,11 +183,29 @@ pub fn query_fee_info(
         msg: to_binary(&FactoryQueryMsg::FeeInfo { pair_type })?,
     }))?;
 
-    Ok(FeeInfo {
-        fee_address: res.fee_address,
-        total_fee_rate: Decimal::from_ratio(Uint128::from(res.total_fee_bps), Uint128::new(10000)),
-        maker_fee_rate: Decimal::from_ratio(Uint128::from(res.maker_fee_bps), Uint128::new(10000)),
-    })
+    Ok(res.into())
+}
+
+/// Returns the fee information used by a specific pair, taking the pair's fee overrides into account.
+/// ## Params
+/// * **querier** is an object of type [`QuerierWrapper`].
+///
+/// * **factory_contract** is an object of type [`Addr`].
+///
+/// * **pair_contract** is an object of type [`Addr`]. This is the pair we return information for.
+pub fn query_pair_fee_info(
+    querier: &QuerierWrapper,
+    factory_contract: Addr,
+    pair_contract: Addr,
+) -> StdResult<FeeInfo> {
+    let res: FeeInfoResponse = querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: factory_contract.to_string(),
+        msg: to_binary(&FactoryQueryMsg::PairFeeInfo {
+            pair_addr: pair_contract.to_string(),
+        })?,
+    }))?;
+
+    Ok(res.into())
 }
 
 /// Accepts two tokens as input and returns a pair's information.
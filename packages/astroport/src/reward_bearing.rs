@@ -0,0 +1,550 @@
+use crate::asset::{addr_validate_to_lower, Asset, AssetInfo};
+use crate::generator::{PoolInfoResponse, QueryMsg as GeneratorQueryMsg};
+use crate::querier::query_factory_config;
+use crate::whitelist::{
+    ExecuteMsg as WhitelistExecuteMsg, InstantiateMsg as WhitelistInstantiateMsg,
+};
+use cosmwasm_std::{
+    from_binary, to_binary, Addr, Binary, CosmosMsg, Decimal256, Deps, DepsMut, Env, MessageInfo,
+    QuerierWrapper, ReplyOn, Response, StdError, StdResult, Storage, SubMsg, Uint128, Uint256,
+    WasmMsg,
+};
+use cw_storage_plus::{Item, Map};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
+
+/// This structure describes the reward-bearing asset of a pair. It is passed in the `reward_bearing`
+/// field of the pair `init_params`, next to the parameters of the pair type.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RewardBearingParams {
+    /// The contract that pays the rewards accrued by the reward-bearing asset held in the pool.
+    /// It must accept a [`RewarderExecuteMsg::ClaimRewards`] message
+    pub rewarder: String,
+    /// The asset in which the rewards are paid
+    pub reward_asset: AssetInfo,
+    /// The Astroport Generator contract. Rewards go to the LP tokens staked in it
+    pub generator: String,
+}
+
+/// This structure describes the `init_params` fields read by the reward-bearing extension.
+/// Other fields belong to the pair type and are ignored here.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RewardBearingInitParams {
+    /// The reward-bearing asset of the pair, if any
+    #[serde(default)]
+    pub reward_bearing: Option<RewardBearingParams>,
+}
+
+/// This structure stores the validated reward-bearing asset parameters of a pair.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardBearingConfig {
+    /// The contract that pays the rewards accrued by the reward-bearing asset
+    pub rewarder: Addr,
+    /// The asset in which the rewards are paid
+    pub reward_asset: AssetInfo,
+    /// The Astroport Generator contract
+    pub generator: Addr,
+}
+
+/// This enum describes the message that a rewarder contract must accept. It matches the reward
+/// contract of Anchor bAssets.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RewarderExecuteMsg {
+    /// Sends the accrued rewards to the recipient
+    ClaimRewards { recipient: Option<String> },
+}
+
+/// This enum describes the execute messages that pairs with a reward-bearing asset accept.
+/// The pair contracts expose the same variants in their own `ExecuteMsg`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// Claims rewards and sends them to the specified receiver
+    ClaimReward {
+        /// An address which will receive the rewards
+        receiver: Option<String>,
+    },
+    /// Claims the rewards for a user that deposited their LP tokens in the Generator contract
+    ClaimRewardByGenerator {
+        /// The user whose LP tokens are/were staked in the Generator
+        user: String,
+        /// The user's LP token amount before the LP token transfer between their wallet and the Generator
+        user_share: Uint128,
+        /// The total LP token amount already deposited by all users in the Generator
+        total_share: Uint128,
+    },
+    /// Callback for distributing rewards
+    HandleReward {
+        previous_reward_balance: Uint128,
+        user: Addr,
+        user_share: Uint128,
+        total_share: Uint128,
+        receiver: Option<Addr>,
+    },
+}
+
+/// This structure groups the storage used for the reward accounting of a pair.
+pub struct RewardIndexes<'a> {
+    /// The contract that holds the claimed rewards until users withdraw them
+    pub holder: Item<'a, Addr>,
+    /// The amount of rewards distributed per staked LP token
+    pub global_index: Item<'a, Decimal256>,
+    /// The global index at the last reward claim of every user
+    pub user_indexes: Map<'a, &'a Addr, Decimal256>,
+}
+
+impl<'a> RewardIndexes<'a> {
+    pub const fn new(
+        holder_key: &'a str,
+        global_index_key: &'a str,
+        user_indexes_key: &'a str,
+    ) -> Self {
+        RewardIndexes {
+            holder: Item::new(holder_key),
+            global_index: Item::new(global_index_key),
+            user_indexes: Map::new(user_indexes_key),
+        }
+    }
+}
+
+/// Stores the reward-bearing asset parameters of a pair
+pub const REWARD_BEARING_CONFIG: Item<RewardBearingConfig> = Item::new("reward_bearing_config");
+
+/// Stores the reward accounting of a pair
+pub const REWARD_INDEXES: RewardIndexes = RewardIndexes::new(
+    "reward_holder",
+    "reward_global_index",
+    "reward_user_indexes",
+);
+
+/// Reads the reward-bearing asset from the pair `init_params`, stores it and returns the sub-message
+/// that instantiates the reward holder. Returns [`None`] if the pair has no reward-bearing asset.
+/// ## Params
+/// `deps` is an object of type [`DepsMut`].
+///
+/// `env` is an object of type [`Env`].
+///
+/// `init_params` is an [`Option`] of type [`Binary`]. These are the pair `init_params`.
+///
+/// `factory_addr` is an object of type [`Addr`]. This is the Astroport factory address.
+///
+/// `reply_id` is the ID of the `reply` call that stores the reward holder address.
+pub fn init_reward_bearing(
+    deps: DepsMut,
+    env: &Env,
+    init_params: &Option<Binary>,
+    factory_addr: &Addr,
+    reply_id: u64,
+) -> StdResult<Option<SubMsg>> {
+    let params = match init_params {
+        Some(init_params) => from_binary::<RewardBearingInitParams>(init_params)?.reward_bearing,
+        None => None,
+    };
+
+    if let Some(params) = params {
+        params.reward_asset.check(deps.api)?;
+        REWARD_BEARING_CONFIG.save(
+            deps.storage,
+            &RewardBearingConfig {
+                rewarder: addr_validate_to_lower(deps.api, &params.rewarder)?,
+                reward_asset: params.reward_asset,
+                generator: addr_validate_to_lower(deps.api, &params.generator)?,
+            },
+        )?;
+
+        Ok(Some(reward_holder_instantiate_msg(
+            &deps.querier,
+            env,
+            factory_addr,
+            reply_id,
+            "Reward holder",
+        )?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Returns the reward-bearing asset parameters of a pair, or an error if the pair has none.
+/// ## Params
+/// `storage` is an object of type [`Storage`].
+pub fn load_reward_bearing_config(storage: &dyn Storage) -> StdResult<RewardBearingConfig> {
+    REWARD_BEARING_CONFIG
+        .may_load(storage)?
+        .ok_or_else(|| StdError::generic_err("The pair has no reward-bearing asset"))
+}
+
+/// Returns the sub-message that instantiates the whitelist contract which holds claimed rewards.
+/// ## Params
+/// `querier` is an object of type [`QuerierWrapper`].
+///
+/// `env` is an object of type [`Env`].
+///
+/// `factory_addr` is an object of type [`Addr`]. This is the Astroport factory address.
+///
+/// `reply_id` is the ID of the `reply` call that stores the reward holder address.
+///
+/// `label` is the label of the reward holder contract.
+pub fn reward_holder_instantiate_msg(
+    querier: &QuerierWrapper,
+    env: &Env,
+    factory_addr: &Addr,
+    reply_id: u64,
+    label: &str,
+) -> StdResult<SubMsg> {
+    Ok(SubMsg {
+        msg: CosmosMsg::Wasm(WasmMsg::Instantiate {
+            admin: None,
+            code_id: query_factory_config(querier, factory_addr.clone())?.whitelist_code_id,
+            funds: vec![],
+            label: label.to_string(),
+            msg: to_binary(&WhitelistInstantiateMsg {
+                admins: vec![env.contract.address.to_string()],
+                mutable: false,
+            })?,
+        }),
+        id: reply_id,
+        gas_limit: None,
+        reply_on: ReplyOn::Success,
+    })
+}
+
+/// Returns the messages that claim rewards from the rewarder into the reward holder and then
+/// distribute them to a user with a [`ExecuteMsg::HandleReward`] callback.
+/// ## Params
+/// `deps` is an object of type [`Deps`].
+///
+/// `env` is an object of type [`Env`].
+///
+/// `indexes` is an object of type [`RewardIndexes`]. This is the reward accounting storage of the pair.
+///
+/// `config` is an object of type [`RewardBearingConfig`].
+///
+/// `user` is an object of type [`Addr`]. This is the address for which rewards are claimed.
+///
+/// `user_share` is an object of type [`Uint128`]. This is the amount of LP tokens the user has staked.
+///
+/// `total_share` is an object of type [`Uint128`]. This is the total amount of staked LP tokens.
+///
+/// `receiver` is an [`Option`] of type [`Addr`]. This is the address that receives the rewards.
+/// If it's not present, the rewards go to the `user`.
+#[allow(clippy::too_many_arguments)]
+pub fn reward_handling_messages(
+    deps: Deps,
+    env: &Env,
+    indexes: &RewardIndexes,
+    config: &RewardBearingConfig,
+    user: Addr,
+    user_share: Uint128,
+    total_share: Uint128,
+    receiver: Option<Addr>,
+) -> StdResult<Vec<CosmosMsg>> {
+    let reward_holder = indexes.holder.load(deps.storage)?;
+
+    let reward_balance = config
+        .reward_asset
+        .query_pool(&deps.querier, reward_holder.clone())?;
+
+    Ok(vec![
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.rewarder.to_string(),
+            msg: to_binary(&RewarderExecuteMsg::ClaimRewards {
+                recipient: Some(reward_holder.to_string()),
+            })?,
+            funds: vec![],
+        }),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            funds: vec![],
+            msg: to_binary(&ExecuteMsg::HandleReward {
+                previous_reward_balance: reward_balance,
+                user,
+                user_share,
+                total_share,
+                receiver,
+            })?,
+        }),
+    ])
+}
+
+/// Claims rewards on behalf of the function caller and sends them to the specified receiver.
+/// Only LP tokens staked in the Generator earn rewards.
+/// ## Params
+/// `deps` is an object of type [`Deps`].
+///
+/// `env` is an object of type [`Env`].
+///
+/// `info` is an object of type [`MessageInfo`].
+///
+/// `indexes` is an object of type [`RewardIndexes`]. This is the reward accounting storage of the pair.
+///
+/// `config` is an object of type [`RewardBearingConfig`].
+///
+/// `liquidity_token` is an object of type [`Addr`]. This is the LP token of the pair.
+///
+/// `receiver` is an [`Option`] of type [`String`]. This is the address that receives the rewards.
+pub fn claim_reward(
+    deps: Deps,
+    env: &Env,
+    info: MessageInfo,
+    indexes: &RewardIndexes,
+    config: &RewardBearingConfig,
+    liquidity_token: &Addr,
+    receiver: Option<String>,
+) -> StdResult<Response> {
+    let receiver = receiver
+        .map(|receiver| addr_validate_to_lower(deps.api, &receiver))
+        .transpose()?;
+
+    let user_share: Uint128 = deps.querier.query_wasm_smart(
+        &config.generator,
+        &GeneratorQueryMsg::Deposit {
+            lp_token: liquidity_token.to_string(),
+            user: info.sender.to_string(),
+        },
+    )?;
+
+    if user_share.is_zero() {
+        return Err(StdError::generic_err(
+            "No lp tokens staked to the generator!",
+        ));
+    }
+
+    let pool_info: PoolInfoResponse = deps.querier.query_wasm_smart(
+        &config.generator,
+        &GeneratorQueryMsg::PoolInfo {
+            lp_token: liquidity_token.to_string(),
+        },
+    )?;
+
+    Ok(Response::new().add_messages(reward_handling_messages(
+        deps,
+        env,
+        indexes,
+        config,
+        info.sender,
+        user_share,
+        pool_info.lp_supply,
+        receiver,
+    )?))
+}
+
+/// Claims rewards on behalf of a user whose LP tokens are staked in the Generator. Only the Generator
+/// can call this, before it changes the amount of LP tokens staked by the user.
+/// ## Params
+/// `deps` is an object of type [`Deps`].
+///
+/// `env` is an object of type [`Env`].
+///
+/// `info` is an object of type [`MessageInfo`].
+///
+/// `indexes` is an object of type [`RewardIndexes`]. This is the reward accounting storage of the pair.
+///
+/// `config` is an object of type [`RewardBearingConfig`].
+///
+/// `user` is an object of type [`String`]. This is the user for which to claim rewards.
+///
+/// `user_share` is an object of type [`Uint128`]. This is the amount of LP tokens the user has staked.
+///
+/// `total_share` is an object of type [`Uint128`]. This is the total amount of staked LP tokens.
+#[allow(clippy::too_many_arguments)]
+pub fn claim_reward_by_generator(
+    deps: Deps,
+    env: &Env,
+    info: MessageInfo,
+    indexes: &RewardIndexes,
+    config: &RewardBearingConfig,
+    user: String,
+    user_share: Uint128,
+    total_share: Uint128,
+) -> StdResult<Response> {
+    let user = addr_validate_to_lower(deps.api, &user)?;
+
+    if info.sender != config.generator {
+        return Err(StdError::generic_err(
+            "Only the generator can use this method!",
+        ));
+    }
+
+    Ok(Response::new().add_messages(reward_handling_messages(
+        deps,
+        env,
+        indexes,
+        config,
+        user,
+        user_share,
+        total_share,
+        None,
+    )?))
+}
+
+/// Updates the reward indexes with the rewards claimed since `previous_reward_balance` and sends
+/// the pending rewards of a user from the reward holder. The caller must make sure that this is
+/// only executed as a callback of the pair itself.
+/// ## Params
+/// `deps` is an object of type [`DepsMut`].
+///
+/// `indexes` is an object of type [`RewardIndexes`]. This is the reward accounting storage of the pair.
+///
+/// `config` is an object of type [`RewardBearingConfig`].
+///
+/// `previous_reward_balance` is an object of type [`Uint128`]. This is the reward holder balance
+/// before the rewards were claimed.
+///
+/// `user` is an object of type [`Addr`]. This is the address for which rewards are distributed.
+///
+/// `user_share` is an object of type [`Uint128`]. This is the amount of LP tokens the user has staked.
+///
+/// `total_share` is an object of type [`Uint128`]. This is the total amount of staked LP tokens.
+///
+/// `receiver` is an [`Option`] of type [`Addr`]. This is the address that receives the rewards.
+#[allow(clippy::too_many_arguments)]
+pub fn handle_reward(
+    deps: DepsMut,
+    indexes: &RewardIndexes,
+    config: &RewardBearingConfig,
+    previous_reward_balance: Uint128,
+    user: Addr,
+    user_share: Uint128,
+    total_share: Uint128,
+    receiver: Option<Addr>,
+) -> StdResult<Response> {
+    let receiver = receiver.unwrap_or_else(|| user.clone());
+
+    let reward_holder = indexes.holder.load(deps.storage)?;
+
+    let reward_balance = config
+        .reward_asset
+        .query_pool(&deps.querier, reward_holder.clone())?;
+
+    let global_index = indexes
+        .global_index
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    let user_index = indexes.user_indexes.may_load(deps.storage, &user)?;
+
+    let (global_index, latest_reward_amount, user_reward) = calc_user_reward(
+        reward_balance,
+        previous_reward_balance,
+        user_share,
+        total_share,
+        global_index,
+        user_index,
+    )?;
+
+    indexes.global_index.save(deps.storage, &global_index)?;
+    indexes
+        .user_indexes
+        .save(deps.storage, &user, &global_index)?;
+
+    let mut response =
+        Response::new().add_attribute("claimed_reward_to_pool", latest_reward_amount);
+
+    if !user_reward.is_zero() {
+        response.messages.push(SubMsg::new(WasmMsg::Execute {
+            contract_addr: reward_holder.to_string(),
+            funds: vec![],
+            msg: to_binary(&WhitelistExecuteMsg::Execute {
+                msgs: vec![Asset {
+                    info: config.reward_asset.clone(),
+                    amount: user_reward,
+                }
+                .into_msg(&deps.querier, receiver.clone())?],
+            })?,
+        }));
+    }
+
+    Ok(response
+        .add_attribute("user", user)
+        .add_attribute("receiver", receiver)
+        .add_attribute("sent_reward", user_reward))
+}
+
+/// Calculates the amount of rewards that a user can claim.
+/// Returns the new global index, the amount of rewards claimed to the pool and the user reward.
+/// ## Params
+/// `reward_balance` is an object of type [`Uint128`]. This is the current reward holder balance.
+///
+/// `previous_reward_balance` is an object of type [`Uint128`]. This is the reward holder balance
+/// before the rewards were claimed.
+///
+/// `user_share` is an object of type [`Uint128`]. This is the amount of LP tokens the user has staked.
+///
+/// `total_share` is an object of type [`Uint128`]. This is the total amount of staked LP tokens.
+///
+/// `global_index` is an object of type [`Decimal256`]. This is the amount of rewards distributed
+/// per staked LP token so far.
+///
+/// `user_index` is an [`Option`] of type [`Decimal256`]. This is the global index at the last claim of the user.
+pub fn calc_user_reward(
+    reward_balance: Uint128,
+    previous_reward_balance: Uint128,
+    user_share: Uint128,
+    total_share: Uint128,
+    global_index: Decimal256,
+    user_index: Option<Decimal256>,
+) -> StdResult<(Decimal256, Uint128, Uint128)> {
+    let latest_reward_amount = reward_balance.saturating_sub(previous_reward_balance);
+
+    let global_index = global_index + Decimal256::from_ratio(latest_reward_amount, total_share);
+
+    let user_reward: Uint128 = if let Some(user_index) = user_index {
+        ((global_index - user_index) * Uint256::from(user_share)).try_into()?
+    } else if !user_share.is_zero() {
+        (global_index * Uint256::from(user_share)).try_into()?
+    } else {
+        Uint128::zero()
+    };
+
+    Ok((global_index, latest_reward_amount, user_reward))
+}
+
+/// Returns the amount of rewards that a user can claim.
+/// ## Params
+/// `deps` is an object of type [`Deps`].
+///
+/// `indexes` is an object of type [`RewardIndexes`]. This is the reward accounting storage of the pair.
+///
+/// `config` is an object of type [`RewardBearingConfig`].
+///
+/// `liquidity_token` is an object of type [`Addr`]. This is the LP token of the pair.
+///
+/// `user` is an object of type [`String`]. This is the address for which to return pending rewards.
+pub fn query_pending_reward(
+    deps: Deps,
+    indexes: &RewardIndexes,
+    config: &RewardBearingConfig,
+    liquidity_token: &Addr,
+    user: String,
+) -> StdResult<Asset> {
+    let user = addr_validate_to_lower(deps.api, &user)?;
+
+    let user_share: Uint128 = deps.querier.query_wasm_smart(
+        &config.generator,
+        &GeneratorQueryMsg::Deposit {
+            lp_token: liquidity_token.to_string(),
+            user: user.to_string(),
+        },
+    )?;
+
+    let global_index = indexes
+        .global_index
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+
+    let user_index = if let Some(user_index) = indexes.user_indexes.may_load(deps.storage, &user)? {
+        user_index
+    } else if user_share.is_zero() {
+        global_index
+    } else {
+        Decimal256::zero()
+    };
+
+    Ok(Asset {
+        info: config.reward_asset.clone(),
+        amount: ((global_index - user_index) * Uint256::from(user_share)).try_into()?,
+    })
+}
@@ -58,6 +58,8 @@ pub enum ExecuteMsg {
         minimum_receive: Option<Uint128>,
         to: Option<Addr>,
         max_spread: Option<Decimal>,
+        /// The timestamp (in seconds) after which the message fails
+        deadline: Option<u64>,
     },
 
     /// Internal use
@@ -89,6 +91,8 @@ pub enum Cw20HookMsg {
         to: Option<String>,
         /// Max spread
         max_spread: Option<Decimal>,
+        /// The timestamp (in seconds) after which the message fails
+        deadline: Option<u64>,
     },
 }
 
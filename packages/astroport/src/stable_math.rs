@@ -1,6 +1,7 @@
 use std::convert::TryFrom;
 
-use astroport::U256;
+use crate::U256;
+use cosmwasm_std::{StdError, StdResult};
 
 const N_COINS_SQUARED: u8 = 4;
 const ITERATIONS: u8 = 32;
@@ -10,6 +11,7 @@ pub const MAX_AMP: u64 = 1_000_000;
 pub const MAX_AMP_CHANGE: u64 = 10;
 pub const MIN_AMP_CHANGING_TIME: u64 = 86400;
 pub const AMP_PRECISION: u64 = 100;
+pub const PRICE_PRECISION: u128 = 1_000_000_000_000_000_000;
 
 /// ## Description
 /// Calculates the ask amount (the amount of tokens swapped to).
@@ -65,6 +67,93 @@ pub fn calc_offer_amount(
     Some(amount_swapped)
 }
 
+/// ## Description
+/// Calculates the amount of a single asset withdrawn from a stableswap pool when LP tokens are burned.
+/// The invariant (D) decreases in proportion to the burned share and the balance of the ask asset
+/// is solved for the new invariant. No fee is applied.
+/// ## Params
+/// * **ask_pool** is an object of type [`u128`]. This is the amount of ask tokens currently in a stableswap pool.
+///
+/// * **other_pool** is an object of type [`u128`]. This is the amount of the other tokens currently in a stableswap pool.
+///
+/// * **share** is an object of type [`u128`]. This is the amount of LP tokens to burn.
+///
+/// * **total_share** is an object of type [`u128`]. This is the total amount of LP tokens currently issued by the pool.
+///
+/// * **amp** is an object of type [`u64`]. This is the pool's amplification parameter.
+pub fn calc_withdraw_one_amount(
+    ask_pool: u128,
+    other_pool: u128,
+    share: u128,
+    total_share: u128,
+    amp: u64,
+) -> StdResult<u128> {
+    let calc_err = || StdError::generic_err("Failed to calculate the withdrawal amount");
+    let leverage = amp.checked_mul(u64::from(N_COINS)).ok_or_else(calc_err)?;
+
+    let d = U256::from(compute_d(leverage, ask_pool, other_pool).ok_or_else(calc_err)?);
+    let d_decrease = d
+        .checked_mul(U256::from(share))
+        .and_then(|res| res.checked_div(U256::from(total_share)))
+        .ok_or_else(calc_err)?;
+    let new_d = d
+        .checked_sub(d_decrease)
+        .and_then(|res| u128::try_from(res).ok())
+        .ok_or_else(calc_err)?;
+
+    let new_ask_pool = compute_new_balance(leverage, other_pool, new_d).ok_or_else(calc_err)?;
+
+    Ok(ask_pool.saturating_sub(new_ask_pool))
+}
+
+/// ## Description
+/// Calculates the spot price of the offer asset: the marginal amount of ask tokens received for one offer
+/// token (ignoring fees) multiplied by [`PRICE_PRECISION`]. The price is the ratio of the partial
+/// derivatives of the invariant with respect to the pool balances.
+///
+/// * **Equation**
+///
+/// price = (A * n**n + D**(n+1) / (n**n * prod(x_i) * x)) / (A * n**n + D**(n+1) / (n**n * prod(x_i) * y))
+///
+/// ## Params
+/// * **offer_pool** is an object of type [`u128`]. This is the amount of offer tokens (x) currently in a stableswap pool.
+///
+/// * **ask_pool** is an object of type [`u128`]. This is the amount of ask tokens (y) currently in a stableswap pool.
+///
+/// * **amp** is an object of type [`u64`]. This is the pool's amplification parameter.
+pub fn calc_spot_price(offer_pool: u128, ask_pool: u128, amp: u64) -> Option<u128> {
+    let leverage = amp.checked_mul(u64::from(N_COINS))?;
+    let d = U256::from(compute_d(leverage, offer_pool, ask_pool)?);
+    let precision = U256::from(PRICE_PRECISION);
+    let offer_pool = U256::from(offer_pool);
+    let ask_pool = U256::from(ask_pool);
+
+    // Both derivatives are multiplied by n**n * prod(x_i) * AMP_PRECISION / D**n to keep the numbers small.
+    // A * n**n is stored as leverage / AMP_PRECISION, so the first term becomes leverage * n**n * prod(x_i) / D**n
+    let amp_term = checked_u8_mul(
+        &offer_pool
+            .checked_mul(precision)?
+            .checked_div(d)?
+            .checked_mul(ask_pool)?
+            .checked_div(d)?
+            .checked_mul(U256::from(leverage))?,
+        N_COINS_SQUARED,
+    )?;
+    // The second term becomes D * AMP_PRECISION / x for the offer asset and D * AMP_PRECISION / y for the ask asset
+    let d_term = d
+        .checked_mul(U256::from(AMP_PRECISION))?
+        .checked_mul(precision)?;
+    let offer_derivative = amp_term.checked_add(d_term.checked_div(offer_pool)?)?;
+    let ask_derivative = amp_term.checked_add(d_term.checked_div(ask_pool)?)?;
+
+    u128::try_from(
+        offer_derivative
+            .checked_mul(precision)?
+            .checked_div(ask_derivative)?,
+    )
+    .ok()
+}
+
 /// ## Description
 /// Computes the stableswap invariant (D).
 ///
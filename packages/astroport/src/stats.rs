@@ -0,0 +1,225 @@
+use crate::asset::{Asset, AssetInfo};
+use cosmwasm_std::{Order, StdResult, Storage, Uint128};
+use cw_storage_plus::{Bound, Item, Map, U64Key};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// The length of a daily statistics bucket in seconds
+pub const SECONDS_PER_DAY: u64 = 86400;
+/// The default amount of days returned by a daily stats query
+pub const DEFAULT_DAILY_STATS_LIMIT: u32 = 10;
+/// The maximum amount of days returned by a daily stats query
+pub const MAX_DAILY_STATS_LIMIT: u32 = 30;
+
+/// This structure describes the swap volume and the commissions of a single pool asset.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AssetStats {
+    /// The pool asset
+    pub info: AssetInfo,
+    /// The amount of the asset offered to the pair in swaps
+    pub volume_in: Uint128,
+    /// The amount of the asset returned by the pair in swaps, after the commission is deducted
+    pub volume_out: Uint128,
+    /// The part of the commissions and fees charged in the asset that stays in the pool for the liquidity providers
+    pub lp_commission: Uint128,
+    /// The part of the commissions and fees charged in the asset that is sent to the Maker
+    pub maker_commission: Uint128,
+}
+
+/// This structure describes the swap statistics of a pair over a period of time.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct Stats {
+    /// The amount of swaps
+    pub swap_count: u64,
+    /// The statistics of every asset that was swapped or charged a fee, in the order of first appearance
+    pub assets: Vec<AssetStats>,
+}
+
+/// This structure holds the parameters that are returned from a stats query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StatsResponse {
+    /// The amount of swaps since the statistics started
+    pub swap_count: u64,
+    /// The cumulative statistics of every asset that was swapped
+    pub assets: Vec<AssetStats>,
+}
+
+/// This structure describes the swap statistics of a pair within a single day.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DailyStats {
+    /// The day index, which is the block time in seconds divided by [`SECONDS_PER_DAY`]
+    pub day: u64,
+    /// The amount of swaps within the day
+    pub swap_count: u64,
+    /// The statistics of every asset that was swapped within the day
+    pub assets: Vec<AssetStats>,
+}
+
+/// This structure holds the parameters that are returned from a daily stats query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DailyStatsResponse {
+    /// The days with at least one swap or fee, in ascending order
+    pub days: Vec<DailyStats>,
+}
+
+/// Stores the cumulative swap statistics of a pair
+pub const STATS: Item<Stats> = Item::new("stats");
+/// Stores the swap statistics of a pair by day index
+pub const DAILY_STATS: Map<U64Key, Stats> = Map::new("daily_stats");
+
+/// Adds a swap to the cumulative statistics and to the statistics of the current day.
+/// ## Params
+/// `storage` is an object of type [`Storage`].
+///
+/// `block_time` is the current block timestamp in seconds.
+///
+/// `offer_asset` is the asset offered to the pair.
+///
+/// `return_asset` is the asset returned by the pair, after the commission is deducted.
+///
+/// `commission_amount` is the total commission charged in the returned asset.
+///
+/// `maker_fee_amount` is the part of the commission that is sent to the Maker.
+pub fn record_swap(
+    storage: &mut dyn Storage,
+    block_time: u64,
+    offer_asset: &Asset,
+    return_asset: &Asset,
+    commission_amount: Uint128,
+    maker_fee_amount: Uint128,
+) -> StdResult<()> {
+    let lp_commission = commission_amount.checked_sub(maker_fee_amount)?;
+    update_stats(storage, block_time, |stats| {
+        stats.swap_count += 1;
+
+        let offer_stats = asset_stats(stats, &offer_asset.info);
+        offer_stats.volume_in = offer_stats.volume_in.checked_add(offer_asset.amount)?;
+
+        let return_stats = asset_stats(stats, &return_asset.info);
+        return_stats.volume_out = return_stats.volume_out.checked_add(return_asset.amount)?;
+        return_stats.lp_commission = return_stats.lp_commission.checked_add(lp_commission)?;
+        return_stats.maker_commission = return_stats
+            .maker_commission
+            .checked_add(maker_fee_amount)?;
+
+        Ok(())
+    })
+}
+
+/// Adds a fee charged outside of a swap (a single asset withdrawal, an imbalanced provide or a
+/// flash loan) to the cumulative statistics and to the statistics of the current day.
+/// The swap count and the volumes are left unchanged.
+/// ## Params
+/// `storage` is an object of type [`Storage`].
+///
+/// `block_time` is the current block timestamp in seconds.
+///
+/// `fee_asset` is the asset in which the fee is charged.
+///
+/// `fee_amount` is the total fee charged.
+///
+/// `maker_fee_amount` is the part of the fee that is sent to the Maker.
+pub fn record_fee(
+    storage: &mut dyn Storage,
+    block_time: u64,
+    fee_asset: &AssetInfo,
+    fee_amount: Uint128,
+    maker_fee_amount: Uint128,
+) -> StdResult<()> {
+    if fee_amount.is_zero() {
+        return Ok(());
+    }
+
+    let lp_commission = fee_amount.checked_sub(maker_fee_amount)?;
+    update_stats(storage, block_time, |stats| {
+        let fee_stats = asset_stats(stats, fee_asset);
+        fee_stats.lp_commission = fee_stats.lp_commission.checked_add(lp_commission)?;
+        fee_stats.maker_commission = fee_stats.maker_commission.checked_add(maker_fee_amount)?;
+
+        Ok(())
+    })
+}
+
+/// Applies an update to the cumulative statistics and to the statistics of the current day.
+fn update_stats(
+    storage: &mut dyn Storage,
+    block_time: u64,
+    update: impl Fn(&mut Stats) -> StdResult<()>,
+) -> StdResult<()> {
+    let mut stats = STATS.may_load(storage)?.unwrap_or_default();
+    update(&mut stats)?;
+    STATS.save(storage, &stats)?;
+
+    let day = U64Key::new(block_time / SECONDS_PER_DAY);
+    let mut daily_stats = DAILY_STATS
+        .may_load(storage, day.clone())?
+        .unwrap_or_default();
+    update(&mut daily_stats)?;
+    DAILY_STATS.save(storage, day, &daily_stats)
+}
+
+/// Returns the cumulative swap statistics of a pair.
+/// ## Params
+/// `storage` is an object of type [`Storage`].
+pub fn query_stats(storage: &dyn Storage) -> StdResult<StatsResponse> {
+    let stats = STATS.may_load(storage)?.unwrap_or_default();
+
+    Ok(StatsResponse {
+        swap_count: stats.swap_count,
+        assets: stats.assets,
+    })
+}
+
+/// Returns the swap statistics of a pair by day in ascending order. Days without swaps or fees are skipped.
+/// ## Params
+/// `storage` is an object of type [`Storage`].
+///
+/// `start_after` is an optional day index to start after.
+///
+/// `limit` is an optional maximum amount of days to return.
+pub fn query_daily_stats(
+    storage: &dyn Storage,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<DailyStatsResponse> {
+    let limit = limit
+        .unwrap_or(DEFAULT_DAILY_STATS_LIMIT)
+        .min(MAX_DAILY_STATS_LIMIT) as usize;
+    let start = start_after.map(|day| Bound::exclusive(U64Key::new(day)));
+
+    let days = DAILY_STATS
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (key, stats) = item?;
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&key);
+            Ok(DailyStats {
+                day: u64::from_be_bytes(bytes),
+                swap_count: stats.swap_count,
+                assets: stats.assets,
+            })
+        })
+        .collect::<StdResult<Vec<DailyStats>>>()?;
+
+    Ok(DailyStatsResponse { days })
+}
+
+/// Returns the statistics of an asset, adding empty statistics if the asset was not swapped before.
+fn asset_stats<'a>(stats: &'a mut Stats, info: &AssetInfo) -> &'a mut AssetStats {
+    let index = match stats.assets.iter().position(|a| a.info.equal(info)) {
+        Some(index) => index,
+        None => {
+            stats.assets.push(AssetStats {
+                info: info.clone(),
+                volume_in: Uint128::zero(),
+                volume_out: Uint128::zero(),
+                lp_commission: Uint128::zero(),
+                maker_commission: Uint128::zero(),
+            });
+            stats.assets.len() - 1
+        }
+    };
+
+    &mut stats.assets[index]
+}
@@ -1,13 +1,23 @@
-use crate::asset::{format_lp_token_name, Asset, AssetInfo, PairInfo};
+use crate::asset::{format_lp_token_name, Asset, AssetInfo, Callback, PairInfo};
 use crate::mock_querier::mock_dependencies;
 use crate::querier::{
     query_all_balances, query_balance, query_pair_info, query_supply, query_token_balance,
 };
 
 use crate::factory::PairType;
+use crate::observation::{
+    increase_observations_size, observe, store_observation, ObservationBuffer,
+    DEFAULT_OBSERVATIONS_SIZE, MAX_OBSERVATIONS_SIZE, OBSERVATION_BUFFER,
+};
+use crate::stats::{
+    query_daily_stats, query_stats, record_fee, record_swap, AssetStats, DailyStats, StatsResponse,
+    SECONDS_PER_DAY,
+};
 use crate::DecimalCheckedOps;
-use cosmwasm_std::testing::MOCK_CONTRACT_ADDR;
-use cosmwasm_std::{to_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, Uint128, WasmMsg};
+use cosmwasm_std::testing::{MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    to_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, StdError, Uint128, Uint256, WasmMsg,
+};
 use cw20::Cw20ExecuteMsg;
 
 #[test]
@@ -323,7 +333,7 @@ fn test_format_lp_token_name() {
 
     deps.querier.with_cw20_query_handler();
 
-    let lp_name = format_lp_token_name(pair_info.asset_infos, &deps.as_ref().querier).unwrap();
+    let lp_name = format_lp_token_name(&pair_info.asset_infos, &deps.as_ref().querier).unwrap();
     assert_eq!(lp_name, "MAPP-UUSD-LP")
 }
 
@@ -352,3 +362,302 @@ fn test_decimal_checked_ops() {
             .is_err()
     );
 }
+
+#[test]
+fn test_observations() {
+    let mut storage = MockStorage::new();
+
+    store_observation(&mut storage, 0, 100, Uint128::zero(), Uint128::zero()).unwrap();
+    store_observation(
+        &mut storage,
+        100,
+        200,
+        Uint128::new(1000),
+        Uint128::new(2000),
+    )
+    .unwrap();
+    store_observation(&mut storage, 200, 400, Uint128::new(500), Uint128::new(500)).unwrap();
+
+    let res = observe(
+        &storage,
+        400,
+        500,
+        Uint128::new(100),
+        Uint128::new(100),
+        &[0, 100, 150, 300, 400],
+    )
+    .unwrap();
+    let cumulative_prices: Vec<(u64, Uint256, Uint256)> = res
+        .observations
+        .into_iter()
+        .map(|o| (o.timestamp, o.price0_cumulative, o.price1_cumulative))
+        .collect();
+    assert_eq!(
+        cumulative_prices,
+        vec![
+            (500, Uint256::from(1600u128), Uint256::from(2600u128)),
+            (400, Uint256::from(1500u128), Uint256::from(2500u128)),
+            (350, Uint256::from(1375u128), Uint256::from(2375u128)),
+            (200, Uint256::from(1000u128), Uint256::from(2000u128)),
+            (100, Uint256::zero(), Uint256::zero()),
+        ]
+    );
+
+    let err = observe(&storage, 400, 500, Uint128::zero(), Uint128::zero(), &[401]).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(
+            "The requested observation is older than the oldest stored observation"
+        )
+    );
+    let err = observe(&storage, 400, 500, Uint128::zero(), Uint128::zero(), &[501]).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("The requested observation is before the genesis time")
+    );
+}
+
+#[test]
+fn test_observations_ring_buffer() {
+    let mut storage = MockStorage::new();
+
+    let err = increase_observations_size(&mut storage, DEFAULT_OBSERVATIONS_SIZE).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("The observations size can only be increased")
+    );
+    let err = increase_observations_size(&mut storage, MAX_OBSERVATIONS_SIZE + 1).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(format!(
+            "The observations size cannot be greater than {}",
+            MAX_OBSERVATIONS_SIZE
+        ))
+    );
+
+    let store = |storage: &mut MockStorage, i: u64| {
+        store_observation(
+            storage,
+            (i - 1) * 10,
+            i * 10,
+            Uint128::new(10),
+            Uint128::new(20),
+        )
+        .unwrap()
+    };
+    let observe_at = |storage: &MockStorage, now: u64, seconds_ago: u64| {
+        observe(
+            storage,
+            now,
+            now,
+            Uint128::zero(),
+            Uint128::zero(),
+            &[seconds_ago],
+        )
+        .map(|res| res.observations[0].price0_cumulative)
+    };
+
+    // The oldest observations are overwritten once the buffer is full
+    for i in 1..=150 {
+        store(&mut storage, i);
+    }
+    assert_eq!(observe_at(&storage, 1500, 990), Ok(Uint256::from(510u128)));
+    assert_eq!(observe_at(&storage, 1500, 985), Ok(Uint256::from(515u128)));
+    assert!(observe_at(&storage, 1500, 991).is_err());
+
+    // The buffer grows once its last slot is written
+    increase_observations_size(&mut storage, 120).unwrap();
+    for i in 151..=210 {
+        store(&mut storage, i);
+    }
+    assert_eq!(
+        OBSERVATION_BUFFER.load(&storage).unwrap(),
+        ObservationBuffer {
+            capacity: 120,
+            capacity_next: 120,
+            index: 109,
+            length: 110,
+        }
+    );
+    assert_eq!(
+        observe_at(&storage, 2100, 1090),
+        Ok(Uint256::from(1010u128))
+    );
+    assert!(observe_at(&storage, 2100, 1091).is_err());
+
+    for i in 211..=221 {
+        store(&mut storage, i);
+    }
+    assert_eq!(
+        observe_at(&storage, 2210, 1190),
+        Ok(Uint256::from(1020u128))
+    );
+    assert_eq!(observe_at(&storage, 2210, 5), Ok(Uint256::from(2205u128)));
+    assert!(observe_at(&storage, 2210, 1191).is_err());
+}
+
+#[test]
+fn test_stats() {
+    let mut storage = MockStorage::new();
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let asset0000 = AssetInfo::Token {
+        contract_addr: Addr::unchecked("asset0000"),
+    };
+    let swap = |storage: &mut MockStorage, block_time: u64, offer: &AssetInfo, ask: &AssetInfo| {
+        record_swap(
+            storage,
+            block_time,
+            &Asset {
+                info: offer.clone(),
+                amount: Uint128::new(1000),
+            },
+            &Asset {
+                info: ask.clone(),
+                amount: Uint128::new(997),
+            },
+            Uint128::new(3),
+            Uint128::new(1),
+        )
+        .unwrap()
+    };
+
+    assert_eq!(
+        query_stats(&storage).unwrap(),
+        StatsResponse {
+            swap_count: 0,
+            assets: vec![],
+        }
+    );
+
+    swap(&mut storage, 100, &uusd, &asset0000);
+    swap(&mut storage, SECONDS_PER_DAY + 100, &uusd, &asset0000);
+    swap(&mut storage, 3 * SECONDS_PER_DAY, &asset0000, &uusd);
+
+    let asset_stats =
+        |info: &AssetInfo, volume_in: u128, volume_out: u128, swaps_out: u128| AssetStats {
+            info: info.clone(),
+            volume_in: Uint128::new(volume_in),
+            volume_out: Uint128::new(volume_out),
+            lp_commission: Uint128::new(2 * swaps_out),
+            maker_commission: Uint128::new(swaps_out),
+        };
+    assert_eq!(
+        query_stats(&storage).unwrap(),
+        StatsResponse {
+            swap_count: 3,
+            assets: vec![
+                asset_stats(&uusd, 2000, 997, 1),
+                asset_stats(&asset0000, 1000, 1994, 2),
+            ],
+        }
+    );
+
+    let days: Vec<(u64, u64)> = query_daily_stats(&storage, None, None)
+        .unwrap()
+        .days
+        .into_iter()
+        .map(|day| (day.day, day.swap_count))
+        .collect();
+    assert_eq!(days, vec![(0, 1), (1, 1), (3, 1)]);
+
+    let res = query_daily_stats(&storage, Some(1), Some(1)).unwrap();
+    assert_eq!(
+        res.days,
+        vec![DailyStats {
+            day: 3,
+            swap_count: 1,
+            assets: vec![
+                asset_stats(&asset0000, 1000, 0, 0),
+                asset_stats(&uusd, 0, 997, 1),
+            ],
+        }]
+    );
+
+    // Fees charged outside of swaps count neither as swaps nor as volume
+    record_fee(
+        &mut storage,
+        3 * SECONDS_PER_DAY,
+        &uusd,
+        Uint128::new(10),
+        Uint128::new(4),
+    )
+    .unwrap();
+    let fee_stats = AssetStats {
+        info: uusd.clone(),
+        volume_in: Uint128::zero(),
+        volume_out: Uint128::new(997),
+        lp_commission: Uint128::new(8),
+        maker_commission: Uint128::new(5),
+    };
+    let res = query_daily_stats(&storage, Some(1), Some(1)).unwrap();
+    assert_eq!(res.days[0].swap_count, 1);
+    assert_eq!(res.days[0].assets[1], fee_stats);
+    assert_eq!(query_stats(&storage).unwrap().swap_count, 3);
+}
+
+#[test]
+fn test_callback() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[
+            (&"uusd".to_string(), &Uint128::new(1000000u128)),
+            (&"uluna".to_string(), &Uint128::zero()),
+        ],
+    );
+
+    let callback = Callback {
+        contract: String::from("vault0000"),
+        msg: to_binary(&"deposit").unwrap(),
+    };
+    let assets = [
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::new(123123u128),
+        },
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            amount: Uint128::new(100u128),
+        },
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0001"),
+            },
+            amount: Uint128::zero(),
+        },
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uluna".to_string(),
+            },
+            amount: Uint128::new(500u128),
+        },
+    ];
+
+    // Tokens are sent with Cw20 Send, native coins are sorted and attached to a single execute
+    assert_eq!(
+        callback.into_msgs(&deps.as_ref().querier, &assets).unwrap(),
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Send {
+                    contract: String::from("vault0000"),
+                    amount: Uint128::new(100u128),
+                    msg: to_binary(&"deposit").unwrap(),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("vault0000"),
+                msg: to_binary(&"deposit").unwrap(),
+                funds: vec![Coin::new(500, "uluna"), Coin::new(121903, "uusd")],
+            }),
+        ]
+    );
+}